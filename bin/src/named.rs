@@ -44,6 +44,7 @@ use std::{
     net::{IpAddr, Ipv4Addr, SocketAddr, ToSocketAddrs},
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 use clap::{Arg, ArgMatches};
@@ -188,7 +189,12 @@ async fn load_zone(
 
             // load any keys for the Zone, if it is a dynamic update zone, then keys are required
             load_keys(&mut authority, zone_name_for_signer, zone_config).await?;
-            Box::new(Arc::new(authority)) as Box<dyn AuthorityObject>
+
+            let authority = Arc::new(authority);
+            if config.watch {
+                FileAuthority::watch(&authority, ZONE_WATCH_INTERVAL);
+            }
+            Box::new(authority) as Box<dyn AuthorityObject>
         }
         #[cfg(feature = "resolver")]
         Some(StoreConfig::Forward(ref config)) => {
@@ -213,6 +219,8 @@ async fn load_zone(
                 zone_file_path,
                 journal_file_path,
                 allow_update: zone_config.is_update_allowed(),
+                allow_update_keys: Vec::new(),
+                nsec_white_lies: false,
             };
 
             let mut authority = SqliteAuthority::try_from_config(
@@ -232,6 +240,8 @@ async fn load_zone(
         None => {
             let config = FileConfig {
                 zone_file_path: zone_path.ok_or("file is a necessary parameter of zone_config")?,
+                watch: false,
+                nsec_white_lies: false,
             };
 
             let mut authority = FileAuthority::try_from_config(
@@ -258,6 +268,9 @@ async fn load_zone(
     Ok(authority)
 }
 
+/// How often zone files with `watch` enabled are checked for changes
+const ZONE_WATCH_INTERVAL: Duration = Duration::from_secs(5);
+
 // argument name constants for the CLI options
 const QUIET_ARG: &str = "quiet";
 const DEBUG_ARG: &str = "debug";
@@ -408,11 +421,20 @@ fn main() {
         .build()
         .expect("failed to initialize Tokio Runtime");
     let mut catalog: Catalog = Catalog::new();
+    catalog.set_minimal_any(config.is_minimal_any());
+    if let Some(axfr_message_size) = config.get_axfr_message_size() {
+        catalog.set_axfr_message_size(axfr_message_size);
+    }
+    catalog.set_chaos(config.get_chaos().clone());
     // configure our server based on the config_path
     for zone in config.get_zones() {
         let zone_name = zone
             .get_zone()
             .unwrap_or_else(|_| panic!("bad zone name in {:?}", config_path));
+        let acl = zone
+            .get_acl()
+            .unwrap_or_else(|_| panic!("bad transfer key name in {:?}", config_path));
+        catalog.set_acl(zone_name.clone().into(), acl);
 
         match runtime.block_on(load_zone(&zone_dir, zone)) {
             Ok(authority) => catalog.upsert(zone_name.into(), authority),
@@ -443,6 +465,9 @@ fn main() {
     // now, run the server, based on the config
     #[cfg_attr(not(feature = "dns-over-tls"), allow(unused_mut))]
     let mut server = ServerFuture::new(catalog);
+    server.set_tcp_keepalive(config.get_tcp_keepalive());
+    server.set_dso_keepalive(config.get_dso_keepalive());
+    server.set_rrl(config.get_rrl());
 
     // load all the listeners
     for udp_socket in &sockaddrs {
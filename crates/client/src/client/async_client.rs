@@ -5,6 +5,7 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
+use std::cmp::Ordering;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
@@ -19,15 +20,16 @@ use trust_dns_proto::op::Edns;
 
 use crate::client::Signer;
 use crate::error::*;
-use crate::op::{update_message, Message, MessageType, OpCode, Query};
+use crate::op::{update_message, Message, MessageType, OpCode, Query, ResponseCode};
 use crate::proto::error::{ProtoError, ProtoErrorKind};
 use crate::proto::xfer::{
     BufDnsStreamHandle, DnsClientStream, DnsExchange, DnsExchangeBackground, DnsExchangeSend,
     DnsHandle, DnsMultiplexer, DnsRequest, DnsRequestOptions, DnsRequestSender, DnsResponse,
+    FirstAnswer,
 };
 use crate::proto::TokioTime;
 use crate::rr::rdata::SOA;
-use crate::rr::{DNSClass, Name, RData, Record, RecordSet, RecordType};
+use crate::rr::{DNSClass, Name, RData, Record, RecordSet, RecordType, SerialNumber};
 
 // TODO: this should be configurable
 // > An EDNS buffer size of 1232 bytes will avoid fragmentation on nearly all current networks.
@@ -139,6 +141,30 @@ impl AsyncClient {
     pub fn disable_edns(&mut self) {
         self.use_edns = false;
     }
+
+    /// Sends an already serialized message and returns the first response as it was received
+    ///
+    /// The bytes are sent as is, only with a transaction id of the transport, which matches the
+    ///  response to it as for any other request. The message is neither signed nor are any EDNS
+    ///  options added. The id of `wire` is restored in the returned bytes, which are otherwise
+    ///  untouched.
+    ///
+    /// # Arguments
+    ///
+    /// * `wire` - the serialized message, e.g. as received by a proxy or captured from the network
+    pub async fn send_bytes(&mut self, wire: &[u8]) -> Result<Vec<u8>, ProtoError> {
+        let request = DnsRequest::from_bytes(wire.to_vec(), DnsRequestOptions::default())?;
+        let id = request.id();
+
+        let mut response = self.send(request).first_answer().await?;
+        let mut bytes = response.take_buffer().ok_or_else(|| {
+            ProtoError::from("the transport did not keep the response as received")
+        })?;
+
+        // the message was decoded, the header of at least 12 bytes is present
+        bytes[..2].copy_from_slice(&id.to_be_bytes());
+        Ok(bytes)
+    }
 }
 
 impl DnsHandle for AsyncClient {
@@ -181,6 +207,20 @@ pub trait ClientHandle: 'static + Clone + DnsHandle<Error = ProtoError> + Send {
         ClientResponse(self.lookup(query, options))
     }
 
+    /// Sends the message as is and returns the first response to it
+    ///
+    /// Unlike `query`, this makes no assumption about the questions of the message, which may be
+    ///  empty or contain several `Query`s for servers which accept that. The response is returned
+    ///  as received, with all of its queries and records, for the caller to correlate the answers.
+    ///  The message is sent with its own header and EDNS, `is_using_edns` is not consulted.
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - the message to send, the id is replaced with the one of the exchange
+    fn exchange(&mut self, message: Message) -> ClientResponse<<Self as DnsHandle>::Response> {
+        ClientResponse(self.send(message))
+    }
+
     /// Sends a NOTIFY message to the remote system
     ///
     /// [RFC 1996](https://tools.ietf.org/html/rfc1996), DNS NOTIFY, August 1996
@@ -447,23 +487,11 @@ pub trait ClientHandle: 'static + Clone + DnsHandle<Error = ProtoError> + Send {
         ClientResponse(self.send(message))
     }
 
-    /// Deletes a record (by rdata) from an rrset, optionally require the rrset to exist.
+    /// Deletes a record (by rdata) from an rrset.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
     ///
     /// ```text
-    /// 2.4.1 - RRset Exists (Value Independent)
-    ///
-    ///   At least one RR with a specified NAME and TYPE (in the zone and class
-    ///   specified in the Zone Section) must exist.
-    ///
-    ///   For this prerequisite, a requestor adds to the section a single RR
-    ///   whose NAME and TYPE are equal to that of the zone RRset whose
-    ///   existence is required.  RDLENGTH is zero and RDATA is therefore
-    ///   empty.  CLASS must be specified as ANY to differentiate this
-    ///   condition from that of an actual RR whose RDLENGTH is naturally zero
-    ///   (0) (e.g., NULL).  TTL is specified as zero (0).
-    ///
     /// 2.5.4 - Delete An RR From An RRset
     ///
     ///   RRs to be deleted are added to the Update Section.  The NAME, TYPE,
@@ -479,10 +507,9 @@ pub trait ClientHandle: 'static + Clone + DnsHandle<Error = ProtoError> + Send {
     /// * `rrset` - the record(s) to delete from a RRSet, the name, type and rdata must match the
     ///              record to delete
     /// * `zone_origin` - the zone name to update, i.e. SOA name
-    /// * `signer` - the signer, with private key, to use to sign the request
     ///
     /// The update must go to a zone authority (i.e. the server used in the ClientConnection). If
-    /// the rrset does not exist and must_exist is false, then the RRSet will be deleted.
+    /// no such record exists, the update is silently ignored by the server.
     fn delete_by_rdata<R>(
         &mut self,
         rrset: R,
@@ -497,23 +524,11 @@ pub trait ClientHandle: 'static + Clone + DnsHandle<Error = ProtoError> + Send {
         ClientResponse(self.send(message))
     }
 
-    /// Deletes an entire rrset, optionally require the rrset to exist.
+    /// Deletes an entire rrset.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
     ///
     /// ```text
-    /// 2.4.1 - RRset Exists (Value Independent)
-    ///
-    ///   At least one RR with a specified NAME and TYPE (in the zone and class
-    ///   specified in the Zone Section) must exist.
-    ///
-    ///   For this prerequisite, a requestor adds to the section a single RR
-    ///   whose NAME and TYPE are equal to that of the zone RRset whose
-    ///   existence is required.  RDLENGTH is zero and RDATA is therefore
-    ///   empty.  CLASS must be specified as ANY to differentiate this
-    ///   condition from that of an actual RR whose RDLENGTH is naturally zero
-    ///   (0) (e.g., NULL).  TTL is specified as zero (0).
-    ///
     /// 2.5.2 - Delete An RRset
     ///
     ///   One RR is added to the Update Section whose NAME and TYPE are those
@@ -530,7 +545,7 @@ pub trait ClientHandle: 'static + Clone + DnsHandle<Error = ProtoError> + Send {
     /// * `zone_origin` - the zone name to update, i.e. SOA name
     ///
     /// The update must go to a zone authority (i.e. the server used in the ClientConnection). If
-    /// the rrset does not exist and must_exist is false, then the RRSet will be deleted.
+    /// no such rrset exists, the update is silently ignored by the server.
     fn delete_rrset(
         &mut self,
         record: Record,
@@ -566,6 +581,8 @@ pub trait ClientHandle: 'static + Clone + DnsHandle<Error = ProtoError> + Send {
     /// The update must go to a zone authority (i.e. the server used in the ClientConnection). This
     /// operation attempts to delete all resource record sets the specified name regardless of
     /// the record type.
+    ///
+    /// At the zone origin, the SOA and NS records of the zone are kept, see RFC 2136 section 3.4.2.3.
     fn delete_all(
         &mut self,
         name_of_records: Name,
@@ -590,16 +607,50 @@ pub trait ClientHandle: 'static + Clone + DnsHandle<Error = ProtoError> + Send {
     /// # Arguments
     /// * `zone_origin` - the zone name to update, i.e. SOA name
     /// * `last_soa` - the last SOA known, if any. If provided, name must match `zone_origin`
+    ///
+    /// The stream ends after the closing SOA, if the server responds with an error, e.g. `NOTAUTH` or `REFUSED`,
+    ///  a `ClientErrorKind::ZoneTransfer` is returned and the stream ends.
 
     fn zone_transfer(
         &mut self,
         zone_origin: Name,
         last_soa: Option<SOA>,
     ) -> ClientStreamXfr<<Self as DnsHandle>::Response> {
-        let ixfr = last_soa.is_some();
+        let last_serial = last_soa.as_ref().map(SOA::serial);
         let message = update_message::zone_transfer(zone_origin, last_soa);
 
-        ClientStreamXfr::new(self.send(message), ixfr)
+        ClientStreamXfr::new(self.send(message), last_serial)
+    }
+
+    /// Download the changes to a zone since `current_serial`, with an IXFR Query (incremental zone transfer)
+    ///
+    /// The server may respond with the entire zone instead, see [`ZoneDiff`]. If the zone has not changed since
+    ///  `current_serial`, this returns an empty `ZoneDiff::Incremental`.
+    ///
+    /// # Arguments
+    /// * `zone_origin` - the zone name to transfer, i.e. SOA name
+    /// * `current_serial` - the serial of the SOA of the zone as currently known
+    fn incremental_transfer(
+        &mut self,
+        zone_origin: Name,
+        current_serial: u32,
+    ) -> ClientZoneDiff<<Self as DnsHandle>::Response> {
+        // only the serial of the SOA is used by the server
+        let soa = SOA::new(
+            zone_origin.clone(),
+            Name::root(),
+            current_serial,
+            0,
+            0,
+            0,
+            0,
+        );
+
+        ClientZoneDiff {
+            stream: self.zone_transfer(zone_origin, Some(soa)),
+            current_serial,
+            answers: Vec::new(),
+        }
     }
 }
 
@@ -658,9 +709,9 @@ impl<R> ClientStreamXfr<R>
 where
     R: Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin + 'static,
 {
-    fn new(inner: R, maybe_incr: bool) -> Self {
+    fn new(inner: R, last_serial: Option<u32>) -> Self {
         Self {
-            state: ClientStreamXfrState::Start { inner, maybe_incr },
+            state: ClientStreamXfrState::Start { inner, last_serial },
         }
     }
 }
@@ -670,7 +721,7 @@ where
 enum ClientStreamXfrState<R> {
     Start {
         inner: R,
-        maybe_incr: bool,
+        last_serial: Option<u32>,
     },
     Second {
         inner: R,
@@ -705,24 +756,50 @@ impl<R> ClientStreamXfrState<R> {
 
     /// Helper to ingest answer Records
     // TODO: this is complex enough it should get its own tests
-    fn process(&mut self, answers: &[Record]) -> Result<(), ClientError> {
+    fn process(&mut self, response: &DnsResponse) -> Result<(), ClientError> {
+        // the server will not send any more messages after refusing the transfer, e.g. NOTAUTH or REFUSED
+        if response.response_code() != ResponseCode::NoError {
+            *self = Self::Ended;
+            return Err(ClientErrorKind::ZoneTransfer(response.response_code()).into());
+        }
+
+        self.process_answers(response.answers())
+    }
+
+    fn process_answers(&mut self, answers: &[Record]) -> Result<(), ClientError> {
         use ClientStreamXfrState::*;
         fn get_serial(r: &Record) -> Option<u32> {
             r.data().and_then(RData::as_soa).map(SOA::serial)
         }
 
         if answers.is_empty() {
+            // a transfer starts with the SOA, a server which does not perform it answers without any records
+            if let Start { .. } = self {
+                *self = Ended;
+                return Err(ClientErrorKind::Message(
+                    "invalid zone transfer, the response contains no records",
+                )
+                .into());
+            }
             return Ok(());
         }
         match std::mem::replace(self, Invalid) {
-            Start { inner, maybe_incr } => {
+            Start { inner, last_serial } => {
                 if let Some(expected_serial) = get_serial(&answers[0]) {
+                    // RFC 1995: if the server's version is not newer, the response only contains its current SOA
+                    if let Some(last_serial) = last_serial {
+                        if SerialNumber::from(expected_serial) <= SerialNumber::from(last_serial) {
+                            *self = Ended;
+                            return Ok(());
+                        }
+                    }
+
                     *self = Second {
                         inner,
-                        maybe_incr,
+                        maybe_incr: last_serial.is_some(),
                         expected_serial,
                     };
-                    self.process(&answers[1..])
+                    self.process_answers(&answers[1..])
                 } else {
                     *self = Ended;
                     Ok(())
@@ -753,7 +830,7 @@ impl<R> ClientStreamXfrState<R> {
                             expected_serial,
                             even: true,
                         };
-                        self.process(&answers[1..])
+                        self.process_answers(&answers[1..])
                     } else {
                         *self = Ended;
                         Err(ClientErrorKind::Message(
@@ -767,7 +844,7 @@ impl<R> ClientStreamXfrState<R> {
                         inner,
                         expected_serial,
                     };
-                    self.process(&answers[1..])
+                    self.process_answers(&answers[1..])
                 }
             }
             Axfr {
@@ -854,7 +931,7 @@ where
         let message = if let Some(response) = ready!(self.state.inner().poll_next_unpin(cx)) {
             Some(match response {
                 Ok(ok) => {
-                    if let Err(e) = self.state.process(ok.answers()) {
+                    if let Err(e) = self.state.process(&ok) {
                         Err(e)
                     } else {
                         Ok(ok)
@@ -869,6 +946,173 @@ where
     }
 }
 
+/// The changes to a zone, as returned by an incremental zone transfer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ZoneDiff {
+    /// The sequences of the deleted and then added records, in order, from the oldest version of the zone to the newest.
+    ///
+    /// The deleted records start with the SOA of the old version, the added records with the SOA of the new version.
+    ///  This is empty if the zone did not change.
+    Incremental(Vec<(Vec<Record>, Vec<Record>)>),
+    /// The server responded with the entire zone, as with AXFR, the leading SOA is included once
+    Full(Vec<Record>),
+}
+
+impl ZoneDiff {
+    /// Parses the answers of all messages of an IXFR response
+    ///
+    /// ```text
+    /// 4. Response Format
+    ///
+    ///    If incremental zone transfer is available, one or more difference
+    ///    sequences is returned.  The list of difference sequences is preceded
+    ///    and followed by a copy of the server's current version of the SOA.
+    ///
+    ///    Each difference sequence represents one update to the zone (one SOA
+    ///    serial change) consisting of deleted RRs and added RRs.  The first RR
+    ///    of the deleted RRs is the older SOA RR and the first RR of the added
+    ///    RRs is the newer SOA RR.
+    ///
+    ///    If incremental zone transfer is not available, the entire zone is
+    ///    returned.  The first and the last RR of the response is the SOA RR of
+    ///    the zone.  I.e. the behavior is the same as an AXFR response except
+    ///    the query type is IXFR.
+    /// ```
+    fn from_answers(mut answers: Vec<Record>, current_serial: u32) -> Result<Self, ClientError> {
+        fn get_serial(r: &Record) -> Option<SerialNumber> {
+            r.data()
+                .and_then(RData::as_soa)
+                .map(|soa| SerialNumber::from(soa.serial()))
+        }
+
+        let new_serial = match answers.first().and_then(get_serial) {
+            Some(serial) => serial,
+            None => {
+                return Err(ClientErrorKind::Message(
+                    "invalid zone transfer, expected SOA as first record",
+                )
+                .into())
+            }
+        };
+
+        // the zone is up to date
+        if answers.len() == 1 {
+            return Ok(Self::Incremental(Vec::new()));
+        }
+
+        // a single trailing SOA always terminates the transfer
+        if answers.last().and_then(get_serial) != Some(new_serial) {
+            return Err(ClientErrorKind::Message(
+                "invalid zone transfer, expected SOA as last record",
+            )
+            .into());
+        }
+        answers.pop();
+
+        match answers.get(1).map(get_serial) {
+            // AXFR style response, the second record is not an SOA
+            Some(None) => return Ok(Self::Full(answers)),
+            Some(Some(_)) => (),
+            // nothing between the two SOAs, the zone is up to date or the response is truncated
+            None if new_serial == SerialNumber::from(current_serial) => {
+                return Ok(Self::Incremental(Vec::new()))
+            }
+            None => {
+                return Err(ClientErrorKind::Message(
+                    "invalid zone transfer, expected records between the SOAs",
+                )
+                .into())
+            }
+        }
+
+        let mut diffs = Vec::<(Vec<Record>, Vec<Record>)>::new();
+        let mut expected_serial = SerialNumber::from(current_serial);
+        let mut adding = true;
+        for record in answers.into_iter().skip(1) {
+            if let Some(serial) = get_serial(&record) {
+                if adding {
+                    // the start of the next sequence, this must continue from the previous one
+                    if serial != expected_serial {
+                        return Err(ClientErrorKind::Msg(format!(
+                            "invalid incremental zone transfer, expected serial {} got {}",
+                            expected_serial, serial
+                        ))
+                        .into());
+                    }
+
+                    diffs.push((vec![record], Vec::new()));
+                } else {
+                    if serial.partial_cmp(&expected_serial) != Some(Ordering::Greater) {
+                        return Err(ClientErrorKind::Msg(format!(
+                            "invalid incremental zone transfer, serial {} is not newer than {}",
+                            serial, expected_serial
+                        ))
+                        .into());
+                    }
+
+                    expected_serial = serial;
+                    diffs
+                        .last_mut()
+                        .expect("diffs is not empty when adding")
+                        .1
+                        .push(record);
+                }
+
+                adding = !adding;
+            } else if let Some((deleted, added)) = diffs.last_mut() {
+                if adding {
+                    added.push(record);
+                } else {
+                    deleted.push(record);
+                }
+            }
+        }
+
+        if !adding || expected_serial != new_serial {
+            return Err(ClientErrorKind::Message(
+                "invalid incremental zone transfer, incomplete difference sequence",
+            )
+            .into());
+        }
+
+        Ok(Self::Incremental(diffs))
+    }
+}
+
+/// A future result of an incremental zone transfer, collecting all messages of the transfer
+#[must_use = "futures do nothing unless polled"]
+pub struct ClientZoneDiff<R>
+where
+    R: Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin + 'static,
+{
+    stream: ClientStreamXfr<R>,
+    current_serial: u32,
+    answers: Vec<Record>,
+}
+
+impl<R> Future for ClientZoneDiff<R>
+where
+    R: Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin + 'static,
+{
+    type Output = Result<ZoneDiff, ClientError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            match ready!(self.stream.poll_next_unpin(cx)) {
+                Some(Ok(mut response)) => {
+                    let answers = response.take_answers();
+                    self.answers.extend(answers);
+                }
+                Some(Err(e)) => return Poll::Ready(Err(e)),
+                None => {
+                    let answers = std::mem::take(&mut self.answers);
+                    return Poll::Ready(ZoneDiff::from_answers(answers, self.current_serial));
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -895,6 +1139,14 @@ mod tests {
         Record::from_rdata(Name::from_ascii("www.example.com.").unwrap(), 600, a)
     }
 
+    fn get_error_testcase(
+        response_code: ResponseCode,
+    ) -> impl Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin + 'static {
+        let mut message = Message::new();
+        message.set_response_code(response_code);
+        iter(vec![Ok(message.into())])
+    }
+
     fn get_stream_testcase(
         records: Vec<Vec<Record>>,
     ) -> impl Stream<Item = Result<DnsResponse, ProtoError>> + Send + Unpin + 'static {
@@ -917,7 +1169,7 @@ mod tests {
             a_record(2),
             soa_record(3),
         ]]);
-        let mut stream = ClientStreamXfr::new(stream, false);
+        let mut stream = ClientStreamXfr::new(stream, None);
         assert!(matches!(stream.state, Start { .. }));
 
         let response = stream.next().await.unwrap().unwrap();
@@ -935,7 +1187,7 @@ mod tests {
             vec![soa_record(3)],
             vec![a_record(2)], // will be ignored as connection is dropped before reading this message
         ]);
-        let mut stream = ClientStreamXfr::new(stream, false);
+        let mut stream = ClientStreamXfr::new(stream, None);
         assert!(matches!(stream.state, Start { .. }));
 
         let response = stream.next().await.unwrap().unwrap();
@@ -953,10 +1205,20 @@ mod tests {
         assert!(stream.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_stream_xfr_no_records() {
+        let stream = get_stream_testcase(vec![vec![], vec![soa_record(3)]]);
+        let mut stream = ClientStreamXfr::new(stream, Some(2));
+
+        assert!(stream.next().await.unwrap().is_err());
+        assert!(matches!(stream.state, Ended));
+        assert!(stream.next().await.is_none());
+    }
+
     #[tokio::test]
     async fn test_stream_xfr_empty_axfr() {
         let stream = get_stream_testcase(vec![vec![soa_record(3)], vec![soa_record(3)]]);
-        let mut stream = ClientStreamXfr::new(stream, false);
+        let mut stream = ClientStreamXfr::new(stream, None);
         assert!(matches!(stream.state, Start { .. }));
 
         let response = stream.next().await.unwrap().unwrap();
@@ -980,7 +1242,7 @@ mod tests {
             a_record(2),
             soa_record(3),
         ]]);
-        let mut stream = ClientStreamXfr::new(stream, false);
+        let mut stream = ClientStreamXfr::new(stream, None);
         assert!(matches!(stream.state, Start { .. }));
 
         stream.next().await.unwrap().unwrap_err();
@@ -995,7 +1257,7 @@ mod tests {
             vec![a_record(1)], // assume this is an error response, not a zone transfer
             vec![a_record(2)],
         ]);
-        let mut stream = ClientStreamXfr::new(stream, false);
+        let mut stream = ClientStreamXfr::new(stream, None);
         assert!(matches!(stream.state, Start { .. }));
 
         let response = stream.next().await.unwrap().unwrap();
@@ -1005,6 +1267,22 @@ mod tests {
         assert!(stream.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn test_stream_xfr_refused() {
+        for response_code in &[ResponseCode::NotAuth, ResponseCode::Refused] {
+            let mut stream = ClientStreamXfr::new(get_error_testcase(*response_code), None);
+            assert!(matches!(stream.state, Start { .. }));
+
+            let error = stream.next().await.unwrap().unwrap_err();
+            assert!(matches!(stream.state, Ended));
+            assert!(
+                matches!(error.kind(), ClientErrorKind::ZoneTransfer(code) if code == response_code)
+            );
+
+            assert!(stream.next().await.is_none());
+        }
+    }
+
     #[tokio::test]
     async fn test_stream_xfr_invalid_axfr_multipart() {
         let stream = get_stream_testcase(vec![
@@ -1013,7 +1291,7 @@ mod tests {
             vec![soa_record(3), a_record(2)],
             vec![soa_record(3)],
         ]);
-        let mut stream = ClientStreamXfr::new(stream, false);
+        let mut stream = ClientStreamXfr::new(stream, None);
         assert!(matches!(stream.state, Start { .. }));
 
         let response = stream.next().await.unwrap().unwrap();
@@ -1040,7 +1318,7 @@ mod tests {
             a_record(2),
             soa_record(3),
         ]]);
-        let mut stream = ClientStreamXfr::new(stream, true);
+        let mut stream = ClientStreamXfr::new(stream, Some(2));
         assert!(matches!(stream.state, Start { .. }));
 
         let response = stream.next().await.unwrap().unwrap();
@@ -1061,7 +1339,7 @@ mod tests {
             vec![soa_record(3)],
             vec![a_record(3)], //
         ]);
-        let mut stream = ClientStreamXfr::new(stream, true);
+        let mut stream = ClientStreamXfr::new(stream, Some(2));
         assert!(matches!(stream.state, Start { .. }));
 
         let response = stream.next().await.unwrap().unwrap();
@@ -1090,4 +1368,108 @@ mod tests {
 
         assert!(stream.next().await.is_none());
     }
+
+    #[tokio::test]
+    async fn test_zone_diff_incremental() {
+        let stream = get_stream_testcase(vec![
+            vec![soa_record(3), soa_record(1), a_record(1), soa_record(2)],
+            vec![a_record(2), soa_record(2), soa_record(3), a_record(3)],
+            vec![soa_record(3)],
+        ]);
+        let zone_diff = ClientZoneDiff {
+            stream: ClientStreamXfr::new(stream, Some(1)),
+            current_serial: 1,
+            answers: Vec::new(),
+        };
+
+        assert_eq!(
+            zone_diff.await.unwrap(),
+            ZoneDiff::Incremental(vec![
+                (
+                    vec![soa_record(1), a_record(1)],
+                    vec![soa_record(2), a_record(2)]
+                ),
+                (vec![soa_record(2)], vec![soa_record(3), a_record(3)]),
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zone_diff_full() {
+        let stream = get_stream_testcase(vec![
+            vec![soa_record(3), a_record(1)],
+            vec![a_record(2), soa_record(3)],
+        ]);
+        let zone_diff = ClientZoneDiff {
+            stream: ClientStreamXfr::new(stream, Some(1)),
+            current_serial: 1,
+            answers: Vec::new(),
+        };
+
+        assert_eq!(
+            zone_diff.await.unwrap(),
+            ZoneDiff::Full(vec![soa_record(3), a_record(1), a_record(2)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_zone_diff_up_to_date() {
+        // the server only responds with its current SOA, the connection stays open
+        let stream =
+            get_stream_testcase(vec![vec![soa_record(3)]]).chain(futures_util::stream::pending());
+        let zone_diff = ClientZoneDiff {
+            stream: ClientStreamXfr::new(stream, Some(3)),
+            current_serial: 3,
+            answers: Vec::new(),
+        };
+
+        assert_eq!(zone_diff.await.unwrap(), ZoneDiff::Incremental(vec![]));
+    }
+
+    #[test]
+    fn test_zone_diff_serial_wrapping() {
+        let answers = vec![
+            soa_record(1),
+            soa_record(u32::max_value()),
+            soa_record(1),
+            a_record(1),
+            soa_record(1),
+        ];
+
+        assert_eq!(
+            ZoneDiff::from_answers(answers, u32::max_value()).unwrap(),
+            ZoneDiff::Incremental(vec![(
+                vec![soa_record(u32::max_value())],
+                vec![soa_record(1), a_record(1)]
+            )])
+        );
+    }
+
+    #[test]
+    fn test_zone_diff_invalid() {
+        // does not continue from the current serial
+        let answers = vec![soa_record(3), soa_record(2), soa_record(3), soa_record(3)];
+        assert!(ZoneDiff::from_answers(answers, 1).is_err());
+
+        // serial moves backwards
+        let answers = vec![soa_record(3), soa_record(2), soa_record(1), soa_record(3)];
+        assert!(ZoneDiff::from_answers(answers, 2).is_err());
+
+        // incomplete sequence
+        let answers = vec![soa_record(3), soa_record(2), a_record(1), soa_record(3)];
+        assert!(ZoneDiff::from_answers(answers, 2).is_err());
+
+        // nothing between the SOAs of a changed zone
+        let answers = vec![soa_record(3), soa_record(3)];
+        assert!(ZoneDiff::from_answers(answers, 2).is_err());
+    }
+
+    #[test]
+    fn test_zone_diff_two_soas_up_to_date() {
+        let answers = vec![soa_record(3), soa_record(3)];
+        assert_eq!(
+            ZoneDiff::from_answers(answers, 3).unwrap(),
+            ZoneDiff::Incremental(vec![])
+        );
+    }
 }
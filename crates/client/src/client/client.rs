@@ -21,7 +21,7 @@ use tokio::runtime::{self, Runtime};
 use trust_dns_proto::xfer::DnsRequest;
 
 use crate::client::async_client::ClientStreamXfr;
-use crate::client::{AsyncClient, ClientConnection, ClientHandle, Signer};
+use crate::client::{AsyncClient, ClientConnection, ClientHandle, Signer, ZoneDiff};
 use crate::error::*;
 use crate::proto::{
     error::ProtoError,
@@ -431,6 +431,19 @@ pub trait Client {
             runtime,
         })
     }
+
+    /// Download the changes to a zone since `current_serial`, with an IXFR Query (incremental zone transfer)
+    ///
+    /// The server may respond with the entire zone instead, see [`ZoneDiff`].
+    ///
+    /// # Arguments
+    /// * `zone_origin` - the zone name to transfer, i.e. SOA name
+    /// * `current_serial` - the serial of the SOA of the zone as currently known
+    fn incremental_transfer(&self, name: &Name, current_serial: u32) -> ClientResult<ZoneDiff> {
+        let (mut client, runtime) = self.spawn_client()?;
+
+        runtime.block_on(client.incremental_transfer(name.clone(), current_serial))
+    }
 }
 
 /// The Client is abstracted over either trust_dns_client::tcp::TcpClientConnection or
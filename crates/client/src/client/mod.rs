@@ -25,9 +25,12 @@ mod client;
 pub mod client_connection;
 mod memoize_client_handle;
 mod rc_stream;
+mod tcp_fallback_client_handle;
 
 #[allow(deprecated)]
-pub use self::async_client::{AsyncClient, ClientFuture, ClientHandle, ClientStreamingResponse};
+pub use self::async_client::{
+    AsyncClient, ClientFuture, ClientHandle, ClientStreamingResponse, ClientZoneDiff, ZoneDiff,
+};
 #[cfg(feature = "dnssec")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
 pub use self::async_secure_client::{AsyncDnssecClient, AsyncSecureClientBuilder};
@@ -39,3 +42,4 @@ pub use self::client::{BlockingStream, Client, SyncClient};
 pub use self::client_connection::ClientConnection;
 pub use self::client_connection::Signer;
 pub use self::memoize_client_handle::MemoizeClientHandle;
+pub use self::tcp_fallback_client_handle::TcpFallbackClientHandle;
@@ -0,0 +1,224 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::pin::Pin;
+
+use futures_util::stream::{self, Stream};
+use log::debug;
+use trust_dns_proto::{
+    error::ProtoError,
+    xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer},
+};
+
+use crate::client::ClientHandle;
+
+/// A ClientHandle which retries truncated UDP responses over TCP.
+///
+/// This wraps a pair of ClientHandles to the same server, one over UDP and one over TCP. Requests
+///  are sent over UDP, and when the response comes back with the TC bit set, the same request is
+///  sent again over TCP and that response is returned instead. The request is re-sent unchanged,
+///  the TCP connection assigns it a new query id just as it does for any other request, so the
+///  response is matched to the retried request and not the original UDP one.
+///
+/// This is opt-in, an `AsyncClient` over UDP by itself returns the truncated response.
+///
+/// ```rust,no_run
+/// use std::net::SocketAddr;
+/// use std::str::FromStr;
+///
+/// use tokio::net::{TcpStream as TokioTcpStream, UdpSocket};
+/// use trust_dns_client::client::{AsyncClient, ClientHandle, TcpFallbackClientHandle};
+/// use trust_dns_client::proto::iocompat::AsyncIoTokioAsStd;
+/// use trust_dns_client::rr::{DNSClass, Name, RecordType};
+/// use trust_dns_client::tcp::TcpClientStream;
+/// use trust_dns_client::udp::UdpClientStream;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let address: SocketAddr = "8.8.8.8:53".parse().unwrap();
+///
+/// let stream = UdpClientStream::<UdpSocket>::new(address);
+/// let (udp, bg) = AsyncClient::connect(stream).await.unwrap();
+/// tokio::spawn(bg);
+///
+/// let (stream, sender) = TcpClientStream::<AsyncIoTokioAsStd<TokioTcpStream>>::new(address);
+/// let (tcp, bg) = AsyncClient::new(stream, sender, None).await.unwrap();
+/// tokio::spawn(bg);
+///
+/// let mut client = TcpFallbackClientHandle::new(udp, tcp);
+/// let response = client
+///     .query(Name::from_str("www.example.com.").unwrap(), DNSClass::IN, RecordType::TXT)
+///     .await
+///     .unwrap();
+/// assert!(!response.truncated());
+/// # }
+/// ```
+#[derive(Clone)]
+#[must_use = "queries can only be sent through a ClientHandle"]
+pub struct TcpFallbackClientHandle<U: ClientHandle, T: ClientHandle> {
+    udp: U,
+    tcp: T,
+}
+
+impl<U, T> TcpFallbackClientHandle<U, T>
+where
+    U: ClientHandle,
+    T: ClientHandle,
+{
+    /// Returns a new handle sending requests over `udp`, and retrying truncated responses over `tcp`
+    pub fn new(udp: U, tcp: T) -> Self {
+        Self { udp, tcp }
+    }
+
+    async fn inner_send(
+        request: DnsRequest,
+        mut udp: U,
+        mut tcp: T,
+    ) -> Result<DnsResponse, ProtoError> {
+        let response = udp.send(request.clone()).first_answer().await?;
+        if !response.truncated() {
+            return Ok(response);
+        }
+
+        debug!("truncated response received over UDP, retrying over TCP");
+        let response = tcp.send(request).first_answer().await?;
+
+        // TCP responses are not limited in size, this should never happen
+        if response.truncated() {
+            return Err(ProtoError::from("truncated response received over TCP"));
+        }
+
+        Ok(response)
+    }
+}
+
+impl<U, T> DnsHandle for TcpFallbackClientHandle<U, T>
+where
+    U: ClientHandle,
+    T: ClientHandle,
+{
+    type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send>>;
+    type Error = ProtoError;
+
+    fn is_using_edns(&self) -> bool {
+        self.udp.is_using_edns()
+    }
+
+    fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+        let request = request.into();
+
+        Box::pin(stream::once(Self::inner_send(
+            request,
+            self.udp.clone(),
+            self.tcp.clone(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::{Arc, Mutex};
+
+    use futures::executor::block_on;
+    use futures::*;
+    use trust_dns_proto::{
+        error::ProtoError,
+        xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer},
+    };
+
+    use super::*;
+    use crate::op::*;
+    use crate::rr::*;
+
+    /// Responds to every request with the query and a fixed id, recording the requests
+    #[derive(Clone)]
+    struct TestClient {
+        id: u16,
+        truncated: bool,
+        requests: Arc<Mutex<Vec<Message>>>,
+    }
+
+    impl TestClient {
+        fn new(id: u16, truncated: bool) -> Self {
+            Self {
+                id,
+                truncated,
+                requests: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+
+        fn requests(&self) -> Vec<Message> {
+            self.requests.lock().unwrap().clone()
+        }
+    }
+
+    impl DnsHandle for TestClient {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send>>;
+        type Error = ProtoError;
+
+        fn send<R: Into<DnsRequest> + Send + 'static>(&mut self, request: R) -> Self::Response {
+            let (request, _) = request.into().into_parts();
+            self.requests.lock().unwrap().push(request.clone());
+
+            let mut response = Message::new();
+            response
+                .set_id(self.id)
+                .set_message_type(MessageType::Response)
+                .set_truncated(self.truncated)
+                .add_queries(request.queries().to_vec());
+
+            Box::pin(stream::once(future::ok(response.into())))
+        }
+    }
+
+    fn request() -> Message {
+        let mut request = Message::new();
+        request.set_id(7).add_query(Query::query(
+            Name::from_ascii("www.example.com.").unwrap(),
+            RecordType::TXT,
+        ));
+        request
+    }
+
+    #[test]
+    fn test_not_truncated() {
+        let udp = TestClient::new(1, false);
+        let tcp = TestClient::new(2, false);
+        let mut client = TcpFallbackClientHandle::new(udp.clone(), tcp.clone());
+
+        let response = block_on(client.send(request()).first_answer()).unwrap();
+        assert_eq!(response.id(), 1);
+        assert_eq!(udp.requests().len(), 1);
+        assert!(tcp.requests().is_empty());
+    }
+
+    #[test]
+    fn test_truncated_retried_over_tcp() {
+        let udp = TestClient::new(1, true);
+        let tcp = TestClient::new(2, false);
+        let mut client = TcpFallbackClientHandle::new(udp.clone(), tcp.clone());
+
+        let response = block_on(client.send(request()).first_answer()).unwrap();
+        assert_eq!(response.id(), 2);
+        assert!(!response.truncated());
+
+        // the same request is sent over both
+        assert_eq!(udp.requests(), vec![request()]);
+        assert_eq!(tcp.requests(), vec![request()]);
+    }
+
+    #[test]
+    fn test_truncated_over_tcp() {
+        let udp = TestClient::new(1, true);
+        let tcp = TestClient::new(2, true);
+        let mut client = TcpFallbackClientHandle::new(udp, tcp.clone());
+
+        assert!(block_on(client.send(request()).first_answer()).is_err());
+        assert_eq!(tcp.requests().len(), 1);
+    }
+}
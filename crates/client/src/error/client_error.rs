@@ -12,6 +12,7 @@ use std::{fmt, io};
 use futures_channel::mpsc;
 use thiserror::Error;
 use trust_dns_proto::error::{ProtoError, ProtoErrorKind};
+use trust_dns_proto::op::ResponseCode;
 
 use crate::error::{DnsSecError, DnsSecErrorKind};
 #[cfg(feature = "backtrace")]
@@ -52,6 +53,10 @@ pub enum ErrorKind {
     /// A request timed out
     #[error("request timed out")]
     Timeout,
+
+    /// The server answered a zone transfer with an error, e.g. `NOTAUTH` or `REFUSED`
+    #[error("zone transfer failed: {0}")]
+    ZoneTransfer(ResponseCode),
 }
 
 impl Clone for ErrorKind {
@@ -66,6 +71,7 @@ impl Clone for ErrorKind {
             Proto(proto) => Proto(proto.clone()),
             SendError(e) => SendError(e.clone()),
             Timeout => Timeout,
+            ZoneTransfer(code) => ZoneTransfer(*code),
         }
     }
 }
@@ -362,23 +362,11 @@ pub fn compare_and_swap(
     message
 }
 
-/// Deletes a record (by rdata) from an rrset, optionally require the rrset to exist.
+/// Deletes a record (by rdata) from an rrset.
 ///
 /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
 ///
 /// ```text
-/// 2.4.1 - RRset Exists (Value Independent)
-///
-///   At least one RR with a specified NAME and TYPE (in the zone and class
-///   specified in the Zone Section) must exist.
-///
-///   For this prerequisite, a requestor adds to the section a single RR
-///   whose NAME and TYPE are equal to that of the zone RRset whose
-///   existence is required.  RDLENGTH is zero and RDATA is therefore
-///   empty.  CLASS must be specified as ANY to differentiate this
-///   condition from that of an actual RR whose RDLENGTH is naturally zero
-///   (0) (e.g., NULL).  TTL is specified as zero (0).
-///
 /// 2.5.4 - Delete An RR From An RRset
 ///
 ///   RRs to be deleted are added to the Update Section.  The NAME, TYPE,
@@ -394,10 +382,9 @@ pub fn compare_and_swap(
 /// * `rrset` - the record(s) to delete from a RRSet, the name, type and rdata must match the
 ///              record to delete
 /// * `zone_origin` - the zone name to update, i.e. SOA name
-/// * `signer` - the signer, with private key, to use to sign the request
 ///
 /// The update must go to a zone authority (i.e. the server used in the ClientConnection). If
-/// the rrset does not exist and must_exist is false, then the RRSet will be deleted.
+/// no such record exists, the update is silently ignored by the server.
 pub fn delete_by_rdata(mut rrset: RecordSet, zone_origin: Name, use_edns: bool) -> Message {
     assert!(zone_origin.zone_of(rrset.name()));
 
@@ -434,23 +421,11 @@ pub fn delete_by_rdata(mut rrset: RecordSet, zone_origin: Name, use_edns: bool)
     message
 }
 
-/// Deletes an entire rrset, optionally require the rrset to exist.
+/// Deletes an entire rrset.
 ///
 /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
 ///
 /// ```text
-/// 2.4.1 - RRset Exists (Value Independent)
-///
-///   At least one RR with a specified NAME and TYPE (in the zone and class
-///   specified in the Zone Section) must exist.
-///
-///   For this prerequisite, a requestor adds to the section a single RR
-///   whose NAME and TYPE are equal to that of the zone RRset whose
-///   existence is required.  RDLENGTH is zero and RDATA is therefore
-///   empty.  CLASS must be specified as ANY to differentiate this
-///   condition from that of an actual RR whose RDLENGTH is naturally zero
-///   (0) (e.g., NULL).  TTL is specified as zero (0).
-///
 /// 2.5.2 - Delete An RRset
 ///
 ///   One RR is added to the Update Section whose NAME and TYPE are those
@@ -467,7 +442,7 @@ pub fn delete_by_rdata(mut rrset: RecordSet, zone_origin: Name, use_edns: bool)
 /// * `zone_origin` - the zone name to update, i.e. SOA name
 ///
 /// The update must go to a zone authority (i.e. the server used in the ClientConnection). If
-/// the rrset does not exist and must_exist is false, then the RRSet will be deleted.
+/// no such rrset exists, the update is silently ignored by the server.
 pub fn delete_rrset(mut record: Record, zone_origin: Name, use_edns: bool) -> Message {
     assert!(zone_origin.zone_of(record.name()));
 
@@ -530,6 +505,8 @@ pub fn delete_rrset(mut record: Record, zone_origin: Name, use_edns: bool) -> Me
 /// The update must go to a zone authority (i.e. the server used in the ClientConnection). This
 /// operation attempts to delete all resource record sets the specified name regardless of
 /// the record type.
+///
+/// At the zone origin, the SOA and NS records of the zone are kept, see RFC 2136 section 3.4.2.3.
 pub fn delete_all(
     name_of_records: Name,
     zone_origin: Name,
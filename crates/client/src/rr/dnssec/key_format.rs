@@ -1,6 +1,8 @@
 #[cfg(feature = "openssl")]
 use openssl::ec::EcKey;
 #[cfg(feature = "openssl")]
+use openssl::pkey::{Id, PKey};
+#[cfg(feature = "openssl")]
 use openssl::rsa::Rsa;
 #[cfg(feature = "openssl")]
 use openssl::symm::Cipher;
@@ -113,6 +115,30 @@ impl KeyFormat {
                 )
                 .into()),
             },
+            #[cfg(feature = "openssl")]
+            Algorithm::ED448 => {
+                let key = match self {
+                    KeyFormat::Der => PKey::private_key_from_der(bytes)
+                        .map_err(|e| format!("error reading ED448 as DER: {}", e))?,
+                    KeyFormat::Pem => PKey::private_key_from_pem_passphrase(bytes, password)
+                        .map_err(|e| {
+                            format!("could not decode ED448 from PEM, bad password?: {}", e)
+                        })?,
+                    e => {
+                        return Err(format!(
+                            "unsupported key format with ED448 (DER or PEM only): {:?}",
+                            e
+                        )
+                        .into())
+                    }
+                };
+
+                if key.id() != Id::ED448 {
+                    return Err(format!("expected an ED448 key: {:?}", key.id()).into());
+                }
+
+                Ok(KeyPair::from_ed448_pkey(key))
+            }
             e => Err(format!(
                 "unsupported Algorithm, enable openssl or ring feature: {:?}",
                 e
@@ -154,6 +180,8 @@ impl KeyFormat {
             },
             #[cfg(feature = "ring")]
             Algorithm::ED25519 => return KeyPair::generate_pkcs8(algorithm),
+            #[cfg(feature = "openssl")]
+            Algorithm::ED448 => KeyPair::generate(algorithm)?,
             e => {
                 return Err(format!(
                     "unsupported Algorithm, enable openssl or ring feature: {:?}",
@@ -167,7 +195,7 @@ impl KeyFormat {
         #[allow(unreachable_code)]
         match key_pair {
             #[cfg(feature = "openssl")]
-            KeyPair::EC(ref pkey) | KeyPair::RSA(ref pkey) => {
+            KeyPair::EC(ref pkey) | KeyPair::RSA(ref pkey) | KeyPair::ED448(ref pkey) => {
                 match self {
                     KeyFormat::Der => {
                         // to avoid accidentally storing a key where there was an expectation that it was password protected
@@ -190,7 +218,7 @@ impl KeyFormat {
                         key.map_err(|e| format!("error writing key as PEM: {}", e).into())
                     }
                     e => Err(format!(
-                        "unsupported key format with RSA or EC (DER or PEM \
+                        "unsupported key format with RSA, EC or ED448 (DER or PEM \
                          only): {:?}",
                         e
                     )
@@ -226,7 +254,7 @@ impl KeyFormat {
 
         match *key_pair {
             #[cfg(feature = "openssl")]
-            KeyPair::EC(ref pkey) | KeyPair::RSA(ref pkey) => {
+            KeyPair::EC(ref pkey) | KeyPair::RSA(ref pkey) | KeyPair::ED448(ref pkey) => {
                 match self {
                     KeyFormat::Der => {
                         // to avoid accidentally storing a key where there was an expectation that it was password protected
@@ -249,7 +277,7 @@ impl KeyFormat {
                         key.map_err(|e| format!("error writing key as PEM: {}", e).into())
                     }
                     e => Err(format!(
-                        "unsupported key format with RSA or EC (DER or PEM \
+                        "unsupported key format with RSA, EC or ED448 (DER or PEM \
                          only): {:?}",
                         e
                     )
@@ -299,6 +327,20 @@ mod tests {
         encode_decode_with_format(KeyFormat::Pem, algorithm, true, true);
     }
 
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn test_ed448_encode_decode_der() {
+        let algorithm = Algorithm::ED448;
+        encode_decode_with_format(KeyFormat::Der, algorithm, false, true);
+    }
+
+    #[test]
+    #[cfg(feature = "openssl")]
+    fn test_ed448_encode_decode_pem() {
+        let algorithm = Algorithm::ED448;
+        encode_decode_with_format(KeyFormat::Pem, algorithm, true, true);
+    }
+
     #[test]
     #[cfg(feature = "ring")]
     fn test_ec_encode_decode_pkcs8() {
@@ -47,8 +47,8 @@ use crate::rr::Name;
 /// A public and private key pair, the private portion is not required.
 ///
 /// This supports all the various public/private keys which Trust-DNS is capable of using. Given
-///  differing features, some key types may not be available. The `openssl` feature will enable RSA and EC
-///  (P256 and P384). The `ring` feature enables ED25519, in the future, Ring will also be used for other keys.
+///  differing features, some key types may not be available. The `openssl` feature will enable RSA, EC
+///  (P256 and P384) and ED448. The `ring` feature enables ED25519, in the future, Ring will also be used for other keys.
 #[allow(clippy::large_enum_variant)]
 pub enum KeyPair<K> {
     /// RSA keypair, supported by OpenSSL
@@ -59,6 +59,10 @@ pub enum KeyPair<K> {
     #[cfg(feature = "openssl")]
     #[cfg_attr(docsrs, doc(cfg(feature = "openssl")))]
     EC(PKey<K>),
+    /// ED448 keypair, supported by OpenSSL
+    #[cfg(feature = "openssl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "openssl")))]
+    ED448(PKey<K>),
     #[cfg(not(feature = "openssl"))]
     #[doc(hidden)]
     Phantom(PhantomData<K>),
@@ -101,6 +105,13 @@ impl<K> KeyPair<K> {
         Self::EC(pkey)
     }
 
+    /// Given a known pkey of an ED448 key, return the wrapped keypair
+    #[cfg(feature = "openssl")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "openssl")))]
+    pub fn from_ed448_pkey(pkey: PKey<K>) -> Self {
+        Self::ED448(pkey)
+    }
+
     /// Creates an ECDSA keypair with ring.
     #[cfg(feature = "ring")]
     #[cfg_attr(docsrs, doc(cfg(feature = "ring")))]
@@ -168,6 +179,9 @@ impl<K: HasPublic> KeyPair<K> {
                 bytes.remove(0);
                 Ok(bytes)
             }
+            // RFC 8080, the public key is the raw 57 octets
+            #[cfg(feature = "openssl")]
+            KeyPair::ED448(ref pkey) => pkey.raw_public_key().map_err(Into::into),
             #[cfg(feature = "ring")]
             KeyPair::ECDSA(ref ec_key) => {
                 let mut bytes: Vec<u8> = ec_key.public_key().as_ref().to_vec();
@@ -432,6 +446,11 @@ impl<K: HasPrivate> KeyPair<K> {
                     Ok(ret)
                 })
             }
+            // EdDSA hashes the message itself, there is no separate digest
+            #[cfg(feature = "openssl")]
+            KeyPair::ED448(ref pkey) => Signer::new_without_digest(pkey)
+                .and_then(|mut signer| signer.sign_oneshot_to_vec(tbs.as_ref()))
+                .map_err(Into::into),
             #[cfg(feature = "ring")]
             KeyPair::ECDSA(ref ec_key) => {
                 let rng = rand::SystemRandom::new();
@@ -475,6 +494,10 @@ impl KeyPair<Private> {
                 .and_then(|group| EcKey::generate(&group))
                 .map_err(Into::into)
                 .and_then(Self::from_ec_key),
+            #[cfg(feature = "openssl")]
+            Algorithm::ED448 => PKey::generate_ed448()
+                .map(Self::from_ed448_pkey)
+                .map_err(Into::into),
             #[cfg(feature = "ring")]
             Algorithm::ED25519 => Err(DnsSecErrorKind::Message(
                 "use generate_pkcs8 for generating private key and encoding",
@@ -572,6 +595,13 @@ mod tests {
         hash_test(Algorithm::ED25519, KeyFormat::Pkcs8);
     }
 
+    #[cfg(feature = "openssl")]
+    #[test]
+    fn test_ed448() {
+        public_key_test(Algorithm::ED448, KeyFormat::Der);
+        hash_test(Algorithm::ED448, KeyFormat::Der);
+    }
+
     fn public_key_test(algorithm: Algorithm, key_format: KeyFormat) {
         let key = key_format
             .decode_key(
@@ -591,6 +591,7 @@ mod tests {
     use crate::rr::rdata::key::KeyUsage;
     use crate::rr::rdata::{DNSSECRData, SIG};
     use crate::rr::{DNSClass, Name, Record, RecordType};
+    use crate::serialize::binary::BinEncodable;
 
     use super::*;
 
@@ -655,6 +656,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sign_and_verify_signed_message_sig0() {
+        let origin: Name = Name::parse("example.com.", None).unwrap();
+        let mut update: Message = Message::new();
+        update
+            .set_id(10)
+            .set_op_code(crate::op::OpCode::Update)
+            .add_query(Query::query(origin.clone(), RecordType::SOA))
+            .add_name_server(
+                Record::new()
+                    .set_name(Name::parse("www.example.com.", None).unwrap())
+                    .set_rr_type(RecordType::A)
+                    .set_dns_class(DNSClass::IN)
+                    .set_data(Some(RData::A([93, 184, 216, 34].into())))
+                    .clone(),
+            );
+
+        let rsa = Rsa::generate(2048).unwrap();
+        let key = KeyPair::from_rsa(rsa).unwrap();
+        let sig0key = key.to_sig0key(Algorithm::RSASHA256).unwrap();
+        let signer = SigSigner::sig0(sig0key.clone(), key, origin);
+
+        update.finalize(&signer, 1000).expect("should have signed");
+        let mut bytes = update.to_bytes().unwrap();
+
+        // the message as received is what was signed, excluding the sig0
+        let (tbs, sig) = tbs::signed_message_tbs(&bytes).unwrap();
+        assert_eq!(sig.sig_inception(), 1000);
+        assert_eq!(sig.sig_expiration(), 1300);
+        assert_eq!(
+            tbs.as_ref(),
+            tbs::message_tbs(&update, &sig).unwrap().as_ref()
+        );
+        assert!(sig0key.verify(tbs.as_ref(), sig.sig()).is_ok());
+
+        // any change to the message invalidates the signature
+        bytes[1] ^= 1;
+        let (tbs, sig) = tbs::signed_message_tbs(&bytes).unwrap();
+        assert!(sig0key.verify(tbs.as_ref(), sig.sig()).is_err());
+    }
+
+    #[test]
+    fn test_verify_sig0_with_parsed_key() {
+        use crate::serialize::txt::RDataParser;
+
+        let origin: Name = Name::parse("example.com.", None).unwrap();
+        let mut update: Message = Message::new();
+        update
+            .set_id(10)
+            .set_op_code(crate::op::OpCode::Update)
+            .add_query(Query::query(origin.clone(), RecordType::SOA));
+
+        let rsa = Rsa::generate(2048).unwrap();
+        let key = KeyPair::from_rsa(rsa).unwrap();
+        let sig0key = key.to_sig0key(Algorithm::RSASHA256).unwrap();
+        let signer = SigSigner::sig0(sig0key.clone(), key, origin);
+
+        update.finalize(&signer, 1000).expect("should have signed");
+        let bytes = update.to_bytes().unwrap();
+
+        // the KEY as it would be found in a zone file, with the public key split over lines
+        let text = sig0key.to_string();
+        let (fields, public_key) = text.split_at(text.rfind(' ').unwrap());
+        let (first, second) = public_key.split_at(public_key.len() / 2);
+        let parsed = RData::try_from_str(
+            RecordType::KEY,
+            &format!("( {}\n  {}\n  {} )", fields, first, second),
+        )
+        .unwrap();
+        let parsed = parsed.as_dnssec().and_then(DNSSECRData::as_key).unwrap();
+        assert_eq!(*parsed, sig0key);
+
+        let (tbs, sig) = tbs::signed_message_tbs(&bytes).unwrap();
+        assert!(parsed.verify(tbs.as_ref(), sig.sig()).is_ok());
+    }
+
     #[test]
     #[allow(deprecated)]
     fn test_sign_and_verify_rrset() {
@@ -789,7 +866,7 @@ MC0CAQACBQC+L6pNAgMBAAECBQCYj0ZNAgMA9CsCAwDHZwICeEUCAnE/AgMA3u0=
 
         use crate::rr::dnssec::tbs::*;
         use crate::rr::dnssec::*;
-        use crate::rr::rdata::{DNSSECRData, SIG};
+        use crate::rr::rdata::{DNSSECRData, MX, SIG};
         use crate::rr::*;
 
         #[test]
@@ -893,5 +970,61 @@ MC0CAQACBQC+L6pNAgMBAAECBQCYj0ZNAgMA9CsCAwDHZwICeEUCAnE/AgMA3u0=
             assert!(!filtered_tbs.as_ref().is_empty());
             assert_eq!(tbs.as_ref(), filtered_tbs.as_ref());
         }
+
+        #[test]
+        fn test_rrset_tbs_canonical_order() {
+            let rsa = Rsa::generate(2048).unwrap();
+            let key = KeyPair::from_rsa(rsa).unwrap();
+            let sig0key = key.to_sig0key(Algorithm::RSASHA256).unwrap();
+            let signer = SigSigner::sig0(sig0key, key, Name::root());
+
+            let origin: Name = Name::parse("example.com.", None).unwrap();
+            let rrsig = Record::from_rdata(
+                origin.clone(),
+                86400,
+                RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+                    RecordType::MX,
+                    Algorithm::RSASHA256,
+                    origin.num_labels(),
+                    86400,
+                    5,
+                    0,
+                    signer.calculate_key_tag().unwrap(),
+                    origin.clone(),
+                    vec![],
+                ))),
+            );
+            let mx = |preference, exchange| {
+                Record::from_rdata(
+                    origin.clone(),
+                    86400,
+                    RData::MX(MX::new(preference, Name::from_ascii(exchange).unwrap())),
+                )
+            };
+
+            let canonical = vec![
+                mx(10, "a.example.com."),
+                mx(10, "b.example.com."),
+                mx(20, "a.example.com."),
+            ];
+            // in reverse, with a duplicate and an uppercase name, which would sort first if not lowercased
+            let reversed = vec![
+                mx(20, "a.example.com."),
+                mx(10, "B.example.com."),
+                mx(10, "a.example.com."),
+                mx(10, "A.example.com."),
+            ];
+
+            let tbs = rrset_tbs_with_rrsig(&rrsig, &canonical).unwrap();
+            let reversed_tbs = rrset_tbs_with_rrsig(&rrsig, &reversed).unwrap();
+            assert_eq!(tbs.as_ref(), reversed_tbs.as_ref());
+
+            let sig = signer.sign(&reversed_tbs).unwrap();
+            let pub_key = signer.key().to_public_bytes().unwrap();
+            let pub_key = PublicKeyEnum::from_public_bytes(&pub_key, Algorithm::RSASHA256).unwrap();
+            assert!(pub_key
+                .verify(Algorithm::RSASHA256, tbs.as_ref(), &sig)
+                .is_ok());
+        }
     }
 }
@@ -14,7 +14,7 @@ use crate::proto::rr::dnssec::rdata::DNSSECRData;
 use std::ops::Range;
 use std::sync::Arc;
 
-use crate::op::{DnsResponse, Message, MessageFinalizer, MessageVerifier};
+use crate::op::{DnsResponse, Message, MessageFinalizer, MessageVerifier, ResponseCode};
 use crate::rr::{Name, RData, Record};
 
 /// Struct to pass to a client for it to authenticate requests using TSIG.
@@ -129,6 +129,15 @@ impl TSigner {
             return Err(ProtoError::from("tsig validation error: wrong key"));
         }
 
+        // an error response to a request with a bad key or MAC is unsigned, report the error of the remote
+        if tsig.error() != 0 {
+            let error: ResponseCode = tsig.error().into();
+            return Err(ProtoError::from(format!(
+                "tsig validation error: remote returned {}",
+                error
+            )));
+        }
+
         // 2.  Check MAC
         //  note: that this verification does not allow for truncation of the HMAC, which technically the RFC suggests.
         //    this is to be pedantic about constant time HMAC validation (prevent timing attacks) as well as any security
@@ -159,8 +168,8 @@ impl TSigner {
         Ok((
             tsig.mac().to_vec(),
             Range {
-                start: tsig.time() - tsig.fudge() as u64,
-                end: tsig.time() + tsig.fudge() as u64,
+                start: tsig.time().saturating_sub(tsig.fudge() as u64),
+                end: tsig.time().saturating_add(tsig.fudge() as u64),
             },
             tsig.time(),
         ))
@@ -260,6 +269,75 @@ mod tests {
         assert!(!validity_range.contains(&(time_begin - fudge * 2))); // too soon to be accepted
     }
 
+    #[test]
+    fn test_verify_chained_responses() {
+        use crate::proto::rr::dnssec::rdata::tsig::chained_message_tbs;
+        use crate::rr::RecordType;
+
+        let time_begin = 1609459200u64;
+        let key_name: Name = Name::from_ascii("key_name").unwrap();
+        let mut question: Message = Message::new();
+        question.add_query(Query::query(
+            Name::parse("example.com.", None).unwrap(),
+            RecordType::AXFR,
+        ));
+
+        let signer = TSigner::new(
+            b"some_key".to_vec(),
+            TsigAlgorithm::HmacSha256,
+            key_name,
+            300,
+        )
+        .unwrap();
+        let mut verifier = question
+            .finalize(&signer, time_begin as u32)
+            .expect("should have signed")
+            .expect("should have a verifier");
+        let request_mac = question.signature()[0]
+            .data()
+            .and_then(RData::as_dnssec)
+            .and_then(DNSSECRData::as_tsig)
+            .unwrap()
+            .mac()
+            .to_vec();
+
+        let pre_tsig = TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            time_begin,
+            300,
+            Vec::new(),
+            question.id(),
+            0,
+            Vec::new(),
+        );
+
+        // the first response is chained to the request
+        let mut first = Message::new();
+        first.set_id(question.id());
+        let tbs = message_tbs(Some(&request_mac), &first, &pre_tsig, signer.signer_name()).unwrap();
+        let first_mac = signer.sign(&tbs).unwrap();
+        first.add_tsig(make_tsig_record(
+            signer.signer_name().clone(),
+            pre_tsig.clone().set_mac(first_mac.clone()),
+        ));
+        verifier(&first.to_bytes().unwrap()).expect("first response should verify");
+
+        // the following ones to the previous response, only with the timers
+        let mut second = Message::new();
+        second.set_id(question.id());
+        let tbs = chained_message_tbs(&first_mac, &second, &pre_tsig).unwrap();
+        let second_mac = signer.sign(&tbs).unwrap();
+        second.add_tsig(make_tsig_record(
+            signer.signer_name().clone(),
+            pre_tsig.clone().set_mac(second_mac),
+        ));
+        let second_bytes = second.to_bytes().unwrap();
+        verifier(&second_bytes).expect("second response should verify");
+
+        // replaying a response breaks the chain
+        assert!(verifier(&second_bytes).is_err());
+    }
+
     // make rejection tests shorter by centralizing common setup code
     fn get_message_and_signer() -> (Message, TSigner) {
         let time_begin = 1609459200u64;
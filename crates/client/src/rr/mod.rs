@@ -29,6 +29,7 @@ pub use crate::proto::rr::domain;
 pub use crate::proto::rr::record_data;
 pub use crate::proto::rr::record_type;
 pub use crate::proto::rr::resource;
+pub use crate::proto::rr::serial_number;
 
 pub use self::dns_class::DNSClass;
 pub use self::lower_name::LowerName;
@@ -40,6 +41,7 @@ pub use self::rr::domain::{IntoName, Label, Name};
 pub use self::rr::IntoRecordSet;
 pub use self::rr::RecordSet;
 pub use self::rr_key::RrKey;
+pub use self::serial_number::SerialNumber;
 
 /// All record data structures and related serialization methods
 pub mod rdata {
@@ -19,6 +19,7 @@
 use crate::error::*;
 #[cfg(feature = "dnssec")]
 use crate::proto::rr::dnssec::rdata::DNSSECRData;
+use crate::proto::serialize::binary::{BinDecoder, Restrict};
 use crate::rr::{Name, RData, RecordType};
 use crate::serialize::txt::rdata_parsers::*;
 
@@ -66,18 +67,32 @@ impl RDataParser for RData {
         tokens: I,
         origin: Option<&Name>,
     ) -> ParseResult<Self> {
+        let mut tokens = tokens.peekable();
+
+        // the generic representation of RFC 3597 may be used for any record type
+        if tokens.peek() == Some(&"\\#") {
+            return parse_generic(record_type, tokens);
+        }
+
         let rdata = match record_type {
             RecordType::A => Self::A(a::parse(tokens)?),
             RecordType::AAAA => Self::AAAA(aaaa::parse(tokens)?),
             RecordType::ANAME => Self::ANAME(name::parse(tokens, origin)?),
             RecordType::ANY => return Err(ParseError::from("parsing ANY doesn't make sense")),
+            RecordType::APL => Self::APL(apl::parse(tokens)?),
+            RecordType::AVC => Self::AVC(txt::parse(tokens)?),
             RecordType::AXFR => return Err(ParseError::from("parsing AXFR doesn't make sense")),
             RecordType::CAA => caa::parse(tokens).map(Self::CAA)?,
+            RecordType::CERT => Self::CERT(cert::parse(tokens)?),
             RecordType::CNAME => Self::CNAME(name::parse(tokens, origin)?),
             RecordType::CSYNC => csync::parse(tokens).map(Self::CSYNC)?,
+            RecordType::DHCID => Self::DHCID(dhcid::parse(tokens)?),
+            RecordType::DNAME => Self::DNAME(name::parse(tokens, origin)?),
             RecordType::HINFO => Self::HINFO(hinfo::parse(tokens)?),
+            RecordType::HIP => Self::HIP(hip::parse(tokens, origin)?),
             RecordType::HTTPS => svcb::parse(tokens).map(Self::SVCB)?,
             RecordType::IXFR => return Err(ParseError::from("parsing IXFR doesn't make sense")),
+            RecordType::LOC => Self::LOC(loc::parse(tokens)?),
             RecordType::MX => Self::MX(mx::parse(tokens, origin)?),
             RecordType::NAPTR => Self::NAPTR(naptr::parse(tokens, origin)?),
             RecordType::NULL => Self::NULL(null::parse(tokens)?),
@@ -89,8 +104,11 @@ impl RDataParser for RData {
             RecordType::SRV => Self::SRV(srv::parse(tokens, origin)?),
             RecordType::SSHFP => Self::SSHFP(sshfp::parse(tokens)?),
             RecordType::SVCB => svcb::parse(tokens).map(Self::SVCB)?,
+            RecordType::SMIMEA => Self::SMIMEA(tlsa::parse(tokens)?),
             RecordType::TLSA => Self::TLSA(tlsa::parse(tokens)?),
             RecordType::TXT => Self::TXT(txt::parse(tokens)?),
+            RecordType::URI => Self::URI(uri::parse(tokens)?),
+            RecordType::ZONEMD => Self::ZONEMD(zonemd::parse(tokens)?),
             RecordType::SIG => return Err(ParseError::from("parsing SIG doesn't make sense")),
             RecordType::DNSKEY => {
                 return Err(ParseError::from("DNSKEY should be dynamically generated"))
@@ -98,6 +116,9 @@ impl RDataParser for RData {
             RecordType::CDNSKEY => {
                 return Err(ParseError::from("CDNSKEY should be dynamically generated"))
             }
+            #[cfg(feature = "dnssec")]
+            RecordType::KEY => Self::DNSSEC(DNSSECRData::KEY(key::parse(tokens)?)),
+            #[cfg(not(feature = "dnssec"))]
             RecordType::KEY => return Err(ParseError::from("KEY should be dynamically generated")),
             #[cfg(feature = "dnssec")]
             RecordType::DS => Self::DNSSEC(DNSSECRData::DS(ds::parse(tokens)?)),
@@ -131,6 +152,36 @@ impl RDataParser for RData {
     }
 }
 
+/// Parses the generic representation of [RFC 3597](https://tools.ietf.org/html/rfc3597#section-5)
+///
+/// The data of known record types is decoded as from the wire, while unknown record types are carried as is.
+fn parse_generic<'i, I: Iterator<Item = &'i str>>(
+    record_type: RecordType,
+    tokens: I,
+) -> ParseResult<RData> {
+    let rdata = null::parse(tokens)?;
+
+    match record_type {
+        RecordType::NULL => Ok(RData::NULL(rdata)),
+        RecordType::Unknown(code) => Ok(RData::Unknown { code, rdata }),
+        RecordType::ANY
+        | RecordType::AXFR
+        | RecordType::IXFR
+        | RecordType::OPT
+        | RecordType::ZERO => Err(ParseError::from(format!(
+            "generic rdata not supported for {}",
+            record_type
+        ))),
+        _ => {
+            let bytes = rdata.anything();
+            let mut decoder = BinDecoder::new(bytes);
+            let rdata = RData::read(&mut decoder, record_type, Restrict::new(bytes.len() as u16))?;
+
+            Ok(rdata)
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -150,6 +201,31 @@ mod tests {
         assert_eq!(record, RData::A("192.168.0.1".parse().unwrap()));
     }
 
+    #[test]
+    fn test_generic() {
+        let record = RData::try_from_str(RecordType::Unknown(65534), "\\# 4 DEADBEEF").unwrap();
+        assert_eq!(
+            record,
+            RData::Unknown {
+                code: 65534,
+                rdata: NULL::with(vec![0xDE, 0xAD, 0xBE, 0xEF]),
+            }
+        );
+        assert_eq!(
+            RData::try_from_str(RecordType::Unknown(65534), &record.to_string()).unwrap(),
+            record
+        );
+
+        let record = RData::try_from_str(RecordType::A, "\\# 4 0A000001").unwrap();
+        assert_eq!(record, RData::A("10.0.0.1".parse().unwrap()));
+
+        let record = RData::try_from_str(RecordType::NULL, "\\# 0").unwrap();
+        assert_eq!(record, RData::NULL(NULL::new()));
+
+        assert!(RData::try_from_str(RecordType::A, "\\# 3 0A0000").is_err());
+        assert!(RData::try_from_str(RecordType::Unknown(65534), "DEADBEEF").is_err());
+    }
+
     #[test]
     fn test_a_parse() {
         let data = "192.168.0.1";
@@ -180,6 +256,39 @@ mod tests {
         assert_eq!(record, RData::AAAA("::1".parse().unwrap()));
     }
 
+    #[test]
+    fn test_apl_parse() {
+        let data = "1:192.0.2.0/24 !1:192.0.2.128/25 2:2001:db8::/32";
+        let record = RData::try_from_str(RecordType::APL, data).unwrap();
+
+        assert_eq!(record.to_string(), data);
+    }
+
+    #[test]
+    fn test_openpgpkey_parse() {
+        let data = "mQINBF ZWwKMB EAC2eOu XtiDmyw==";
+        let record = RData::try_from_str(RecordType::OPENPGPKEY, data).unwrap();
+
+        assert_eq!(record.to_string(), "mQINBFZWwKMBEAC2eOuXtiDmyw==");
+    }
+
+    #[test]
+    fn test_smimea_parse() {
+        let data = "3 1 1 d2abde240d7cd3ee6b4b28c54df034b97983a1d16e8a410e4561cb106618e971";
+        let record = RData::try_from_str(RecordType::SMIMEA, data).unwrap();
+
+        assert_eq!(record.to_record_type(), RecordType::SMIMEA);
+        assert_eq!(record.to_string(), data);
+    }
+
+    #[test]
+    fn test_zonemd_parse() {
+        let data = "2018031900 1 1 c68090d90a7aed716bc459f9340e3d7c1370d4d24b7e2fc3a1ddc0b9a87153b9a9713b3c9ae5cc27777f98b8e730044c";
+        let record = RData::try_from_str(RecordType::ZONEMD, data).unwrap();
+
+        assert_eq!(record.to_string(), data);
+    }
+
     #[test]
     fn test_ns_parse() {
         let data = "ns.example.com";
@@ -191,6 +300,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dname_parse() {
+        let data = "new.example.com";
+        let record = RData::try_from_str(RecordType::DNAME, data).unwrap();
+
+        assert_eq!(
+            record,
+            RData::DNAME(Name::from_str("new.example.com.").unwrap())
+        );
+    }
+
     #[test]
     fn test_csync() {
         let tokens = vec!["123", "1", "A", "NS"];
@@ -227,6 +347,59 @@ mod tests {
                 vec![RecordType::A, RecordType::NS]
             ))
         );
+        assert_eq!(record.to_string(), data);
+    }
+
+    #[test]
+    fn test_avc_parse() {
+        let data = "app-name:WebEx";
+        let record = RData::try_from_str(RecordType::AVC, data).unwrap();
+
+        assert_eq!(record.to_record_type(), RecordType::AVC);
+        assert_eq!(
+            record,
+            RData::AVC(TXT::new(vec!["app-name:WebEx".to_string()]))
+        );
+        assert_eq!(record.to_string(), data);
+    }
+
+    #[test]
+    fn test_hip_parse() {
+        let data = "2 200100107B1A74DF365639CC39F1D578 Zm9vYmFy rvs.example.com.";
+        let record = RData::try_from_str(RecordType::HIP, data).unwrap();
+
+        assert_eq!(record.to_string(), data);
+        assert_eq!(
+            RData::try_from_str(RecordType::HIP, &record.to_string()).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    fn test_cert_parse() {
+        let data = "PGP 0 0 ( Zm9v YmFy )";
+        let record = RData::try_from_str(RecordType::CERT, data).unwrap();
+
+        assert_eq!(record.to_string(), "PGP 0 0 Zm9vYmFy");
+        assert_eq!(
+            RData::try_from_str(RecordType::CERT, &record.to_string()).unwrap(),
+            record
+        );
+    }
+
+    #[test]
+    fn test_loc_parse() {
+        let data = "42 21 54 N 71 06 18 W -24m 30m";
+        let record = RData::try_from_str(RecordType::LOC, data).unwrap();
+
+        assert_eq!(
+            record.to_string(),
+            "42 21 54.000 N 71 6 18.000 W -24.00m 30m 10000m 10m"
+        );
+        assert_eq!(
+            RData::try_from_str(RecordType::LOC, &record.to_string()).unwrap(),
+            record
+        );
     }
 
     #[cfg(feature = "dnssec")]
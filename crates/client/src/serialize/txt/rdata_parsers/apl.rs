@@ -0,0 +1,120 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! APL records for lists of address prefixes
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::error::*;
+use crate::rr::rdata::apl::{AplItem, ADDRESS_FAMILY_IPV4, ADDRESS_FAMILY_IPV6};
+use crate::rr::rdata::APL;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 3123](https://tools.ietf.org/html/rfc3123#section-5)
+///
+/// ```text
+/// 5. APL RR Master File Format
+///
+///    The textual presentation of an APL RR consists of zero or more
+///    instances of an address prefix, separated by whitespace.
+///
+///       [!]afi:address/prefix
+///
+///    The meaning of the fields is as follows:
+///
+///       o  "!" is an optional negation indicator.
+///       o  afi is the decimal numeric value of that
+///          prefix's address family.
+///       o  address is the prefix's address in the corresponding
+///          address family's format.
+///       o  prefix is the decimal length of the prefix.
+/// ```
+///
+/// Only the IPv4 and IPv6 address families have a defined text form.
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<APL> {
+    tokens
+        .map(parse_item)
+        .collect::<ParseResult<_>>()
+        .map(APL::new)
+}
+
+fn parse_item(token: &str) -> ParseResult<AplItem> {
+    let (negation, item) = match token.strip_prefix('!') {
+        Some(item) => (true, item),
+        None => (false, token),
+    };
+
+    let (address_family, address, prefix) = match (item.find(':'), item.rfind('/')) {
+        (Some(colon), Some(slash)) if colon < slash => {
+            (&item[..colon], &item[colon + 1..slash], &item[slash + 1..])
+        }
+        _ => {
+            return Err(ParseError::from(format!(
+                "APL item is not of the form [!]afi:address/prefix: {}",
+                token
+            )))
+        }
+    };
+    let prefix = prefix.parse::<u8>()?;
+
+    let address = match address_family.parse::<u16>()? {
+        ADDRESS_FAMILY_IPV4 => IpAddr::V4(address.parse::<Ipv4Addr>()?),
+        ADDRESS_FAMILY_IPV6 => IpAddr::V6(address.parse::<Ipv6Addr>()?),
+        address_family => {
+            return Err(ParseError::from(format!(
+                "unsupported APL address family: {}",
+                address_family
+            )))
+        }
+    };
+
+    Ok(AplItem::from_ip(address, prefix, negation)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        let apl = parse(
+            "1:192.0.2.0/24 !1:192.0.2.128/25 2:2001:db8::/32 !2:::/0"
+                .split(' ')
+                .filter(|s| !s.is_empty()),
+        )
+        .unwrap();
+
+        assert_eq!(
+            apl,
+            APL::new(vec![
+                AplItem::from_ip(Ipv4Addr::new(192, 0, 2, 0).into(), 24, false).unwrap(),
+                AplItem::from_ip(Ipv4Addr::new(192, 0, 2, 128).into(), 25, true).unwrap(),
+                AplItem::from_ip("2001:db8::".parse().unwrap(), 32, false).unwrap(),
+                AplItem::from_ip(Ipv6Addr::UNSPECIFIED.into(), 0, true).unwrap(),
+            ])
+        );
+        assert_eq!(
+            parse(apl.to_string().split(' ')).unwrap(),
+            apl,
+            "text form round trips"
+        );
+
+        assert!(parse(std::iter::empty()).unwrap().items().is_empty());
+    }
+
+    #[test]
+    fn test_parsing_errors() {
+        assert!(parse(vec!["1:192.0.2.0/33"].into_iter()).is_err());
+        assert!(parse(vec!["2:2001:db8::/129"].into_iter()).is_err());
+        assert!(parse(vec!["1:2001:db8::/32"].into_iter()).is_err());
+        assert!(parse(vec!["3:192.0.2.0/24"].into_iter()).is_err());
+        assert!(parse(vec!["192.0.2.0/24"].into_iter()).is_err());
+        assert!(parse(vec!["1:192.0.2.0"].into_iter()).is_err());
+        assert!(parse(vec!["!!1:192.0.2.0/24"].into_iter()).is_err());
+    }
+}
@@ -68,8 +68,7 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResu
 
     // parse the tag
     let tag = {
-        // unnecessary clone
-        let tag = Property::from(tag_str.to_string());
+        let tag = caa::parse_tag(tag_str)?;
         if tag.is_unknown() {
             warn!("unknown tag found for caa: {:?}", tag);
         }
@@ -110,5 +109,10 @@ mod tests {
         assert!(parse(vec!["0", "issue", ";"].into_iter()).is_ok());
         // certs         CAA 0 issuewild \"example.net\"
         assert!(parse(vec!["0", "issue", "example.net"].into_iter()).is_ok());
+
+        // tags are letters and digits only
+        assert!(parse(vec!["0", "tbs2", "value"].into_iter()).is_ok());
+        assert!(parse(vec!["0", "issue-", "example.net"].into_iter()).is_err());
+        assert!(parse(vec!["0", "is_sue", "example.net"].into_iter()).is_err());
     }
 }
@@ -0,0 +1,112 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! CERT records for storing certificates and certificate revocation lists
+
+use crate::error::*;
+use crate::rr::rdata::cert::{CertType, CERT};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4398](https://tools.ietf.org/html/rfc4398#section-2.2)
+///
+/// ```text
+/// 2.2.  Text Representation of CERT RRs
+///
+///    The RDATA portion of a CERT RR has the type field as an unsigned
+///    decimal integer or as a mnemonic symbol as listed in Section 2.1,
+///    above.
+///
+///    The key tag field is represented as an unsigned decimal integer.
+///
+///    The algorithm field is represented as an unsigned decimal integer or
+///    a mnemonic symbol as listed in [12].
+///
+///    The certificate/CRL portion is represented in base 64 [16] and may be
+///    divided into any number of white-space-separated substrings, any of
+///    which may be empty, with the total being a valid base-64 encoding.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<CERT> {
+    fn missing_field(field: &str) -> ParseError {
+        ParseErrorKind::MissingToken(format!("CERT {}", field)).into()
+    }
+
+    let cert_type: CertType = tokens
+        .next()
+        .ok_or_else(|| missing_field("type"))?
+        .parse()?;
+    let key_tag: u16 = tokens
+        .next()
+        .ok_or_else(|| missing_field("key tag"))?
+        .parse()?;
+    let algorithm = tokens
+        .next()
+        .ok_or_else(|| missing_field("algorithm"))
+        .and_then(parse_algorithm)?;
+
+    let certificate = tokens.collect::<String>();
+    if certificate.is_empty() {
+        return Err(missing_field("certificate"));
+    }
+    let certificate = data_encoding::BASE64.decode(certificate.as_bytes())?;
+
+    Ok(CERT::new(cert_type, key_tag, algorithm, certificate))
+}
+
+/// The algorithm is either a number or one of the DNSSEC algorithm mnemonics of
+///  [RFC 4034](https://tools.ietf.org/html/rfc4034#appendix-A.1) and its successors
+fn parse_algorithm(algorithm: &str) -> ParseResult<u8> {
+    if let Ok(algorithm) = algorithm.parse::<u8>() {
+        return Ok(algorithm);
+    }
+
+    let algorithm = match algorithm.to_ascii_uppercase().as_str() {
+        "RSAMD5" => 1,
+        "DH" => 2,
+        "DSA" => 3,
+        "RSASHA1" => 5,
+        "DSA-NSEC3-SHA1" => 6,
+        "RSASHA1-NSEC3-SHA1" => 7,
+        "RSASHA256" => 8,
+        "RSASHA512" => 10,
+        "ECC-GOST" => 12,
+        "ECDSAP256SHA256" => 13,
+        "ECDSAP384SHA384" => 14,
+        "ED25519" => 15,
+        "ED448" => 16,
+        "INDIRECT" => 252,
+        "PRIVATEDNS" => 253,
+        "PRIVATEOID" => 254,
+        _ => return Err(format!("unknown CERT algorithm: {}", algorithm).into()),
+    };
+
+    Ok(algorithm)
+}
+
+#[test]
+fn test_parsing() {
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["PGP", "0"].into_iter()).is_err());
+    assert!(parse(vec!["PGP", "0", "0"].into_iter()).is_err());
+    assert!(parse(vec!["X509", "0", "0", "Zm9v"].into_iter()).is_err());
+    assert!(parse(vec!["PGP", "0", "RSAMD4", "Zm9v"].into_iter()).is_err());
+    assert!(parse(vec!["PGP", "65536", "0", "Zm9v"].into_iter()).is_err());
+    assert!(parse(vec!["PGP", "0", "0", "äöüäö"].into_iter()).is_err());
+
+    assert_eq!(
+        parse(vec!["PGP", "0", "0", "Zm9v", "YmFy"].into_iter()).unwrap(),
+        CERT::new(CertType::PGP, 0, 0, b"foobar".to_vec())
+    );
+    assert_eq!(
+        parse(vec!["1", "12345", "RSASHA256", "Zm9vYmFy"].into_iter()).unwrap(),
+        CERT::new(CertType::PKIX, 12345, 8, b"foobar".to_vec())
+    );
+    assert_eq!(
+        parse(vec!["65280", "1", "13", "Zm9vYmFy"].into_iter()).unwrap(),
+        CERT::new(CertType::Unassigned(65280), 1, 13, b"foobar".to_vec())
+    );
+}
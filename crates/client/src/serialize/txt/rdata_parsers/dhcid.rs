@@ -0,0 +1,55 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DHCID records for associating DHCP clients with their names
+
+use crate::error::*;
+use crate::rr::rdata::DHCID;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 4701](https://tools.ietf.org/html/rfc4701#section-3.2)
+///
+/// ```text
+/// 3.2.  DHCID Presentation Format
+///
+///    In DNS master files, the RDATA is represented as a single block in
+///    base-64 encoding identical to that used for representing binary data
+///    in [3], Section 3.  The data may be divided up into any number of
+///    white-space-separated substrings, each of which is a valid base-64
+///    encoding, which are concatenated to obtain the full signature.
+///    These substrings can span lines using the standard parenthesis.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(tokens: I) -> ParseResult<DHCID> {
+    let encoded_digest = tokens.collect::<String>();
+    if encoded_digest.is_empty() {
+        return Err(ParseErrorKind::Message("DHCID digest field is missing").into());
+    }
+
+    let digest = data_encoding::BASE64.decode(encoded_digest.as_bytes())?;
+    Ok(DHCID::new(digest))
+}
+
+#[test]
+fn test_parsing() {
+    assert!(parse(::std::iter::empty()).is_err());
+    assert!(parse(vec!["äöüäööüä"].into_iter()).is_err());
+
+    // RFC 4701, section 3.6
+    assert_eq!(
+        parse(vec!["AAIBY2/AuCccgoJbsaxcQc9TUapptP69lOjxfNuVAA2kjEA="].into_iter()).unwrap(),
+        DHCID::new(
+            data_encoding::BASE64
+                .decode(b"AAIBY2/AuCccgoJbsaxcQc9TUapptP69lOjxfNuVAA2kjEA=")
+                .unwrap()
+        )
+    );
+    assert_eq!(
+        parse(vec!["AAIBY2/AuCccgoJbsaxcQc9TUapptP69", "lOjxfNuVAA2kjEA="].into_iter()).unwrap(),
+        parse(vec!["AAIBY2/AuCccgoJbsaxcQc9TUapptP69lOjxfNuVAA2kjEA="].into_iter()).unwrap(),
+    );
+}
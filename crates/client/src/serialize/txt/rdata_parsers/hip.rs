@@ -0,0 +1,102 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HIP records for Host Identity Protocol host identities and rendezvous servers
+
+use crate::error::*;
+use crate::rr::domain::Name;
+use crate::rr::rdata::HIP;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 8005](https://tools.ietf.org/html/rfc8005#section-6)
+///
+/// ```text
+/// 6.  HIP RR Presentation Format
+///
+///    The representation of HIP RRs in DNS master files is as follows:
+///
+///    IN  HIP ( pk-algorithm
+///              base16-encoded-hit
+///              base64-encoded-public-key
+///              rendezvous-server[1]
+///                      ...
+///              rendezvous-server[n] )
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(
+    mut tokens: I,
+    origin: Option<&Name>,
+) -> ParseResult<HIP> {
+    fn missing_field(field: &str) -> ParseError {
+        ParseErrorKind::MissingToken(format!("HIP {}", field)).into()
+    }
+
+    let algorithm: u8 = tokens
+        .next()
+        .ok_or_else(|| missing_field("pk algorithm"))?
+        .parse()?;
+    let hit = tokens.next().ok_or_else(|| missing_field("hit"))?;
+    let hit = data_encoding::HEXUPPER_PERMISSIVE.decode(hit.as_bytes())?;
+    let public_key = tokens.next().ok_or_else(|| missing_field("public key"))?;
+    let public_key = data_encoding::BASE64.decode(public_key.as_bytes())?;
+    let rendezvous_servers = tokens
+        .map(|s| Name::parse(s, origin).map_err(ParseError::from))
+        .collect::<ParseResult<Vec<_>>>()?;
+
+    Ok(HIP::new(algorithm, hit, public_key, rendezvous_servers))
+}
+
+#[test]
+fn test_parsing() {
+    use std::str::FromStr;
+
+    assert!(parse(::std::iter::empty(), None).is_err());
+    assert!(parse(
+        vec!["2", "200100107B1A74DF365639CC39F1D578"].into_iter(),
+        None
+    )
+    .is_err());
+    assert!(parse(vec!["2", "XYZ", "Zm9vYmFy"].into_iter(), None).is_err());
+    assert!(parse(vec!["256", "2001", "Zm9vYmFy"].into_iter(), None).is_err());
+
+    let hit = data_encoding::HEXUPPER
+        .decode(b"200100107B1A74DF365639CC39F1D578")
+        .unwrap();
+    assert_eq!(
+        parse(
+            vec!["2", "200100107b1a74df365639cc39f1d578", "Zm9vYmFy"].into_iter(),
+            None
+        )
+        .unwrap(),
+        HIP::new(2, hit.clone(), b"foobar".to_vec(), vec![])
+    );
+
+    let origin = Name::from_str("example.com.").unwrap();
+    assert_eq!(
+        parse(
+            vec![
+                "2",
+                "200100107B1A74DF365639CC39F1D578",
+                "Zm9vYmFy",
+                "rvs1.example.com.",
+                "rvs2"
+            ]
+            .into_iter(),
+            Some(&origin)
+        )
+        .unwrap(),
+        HIP::new(
+            2,
+            hit,
+            b"foobar".to_vec(),
+            vec![
+                Name::from_str("rvs1.example.com.").unwrap(),
+                Name::from_str("rvs2.example.com.").unwrap()
+            ]
+        )
+    );
+}
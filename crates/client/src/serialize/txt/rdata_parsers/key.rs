@@ -0,0 +1,254 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Parser for KEY text form, as used for SIG(0)
+
+use crate::error::*;
+#[allow(deprecated)]
+use crate::proto::rr::dnssec::rdata::key::{KeyTrust, KeyUsage, Protocol, UpdateScope, KEY};
+use crate::proto::rr::dnssec::Algorithm;
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 2535](https://tools.ietf.org/html/rfc2535#section-7.1), Domain Name System Security Extensions, March 1999
+///
+/// ```text
+/// 7.1 Presentation of KEY RRs
+///
+///    The flag field is represented as an unsigned integer or a sequence of
+///    mnemonics as follows separated by instances of the verticle bar ("|")
+///    character:
+///
+///      BIT  Mnemonic  Explanation
+///     0-1           key type
+///         NOCONF    =1 confidentiality use prohibited
+///         NOAUTH    =2 authentication use prohibited
+///         NOKEY     =3 no key present
+///     2   FLAG2     - reserved
+///     3   EXTEND    flags extension
+///     4   FLAG4     - reserved
+///     5   FLAG5     - reserved
+///     6-7           name type
+///         USER      =0 (default, may be omitted)
+///         ZONE      =1
+///         HOST      =2 (host or other end entity)
+///         NTYP3     - reserved
+///     8   FLAG8     - reserved
+///     9   FLAG9     - reserved
+///    10   FLAG10    - reserved
+///    11   FLAG11    - reserved
+///    12-15          signatory field, values 0 to 15
+///             can be represented by SIG0, SIG1, ... SIG15
+///
+///    The protocol octet can be represented as either an unsigned integer
+///    or symbolicly.  The following initial symbols are defined:
+///
+///         000    NONE
+///         001    TLS
+///         002    EMAIL
+///         003    DNSSEC
+///         004    IPSEC
+///         255    ALL
+///
+///    Note that if the type flags field has the NOKEY value, nothing
+///    appears after the algorithm octet.
+/// ```
+///
+/// The reserved flags and the flags extension are not supported, just as in the binary form.
+#[allow(deprecated)]
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<KEY> {
+    let flags = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("flags not present")))
+        .and_then(parse_flags)?;
+    let protocol = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("protocol not present")))
+        .and_then(parse_protocol)?;
+    let algorithm = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::Message("algorithm not present")))
+        .and_then(parse_algorithm)?;
+
+    let key_trust = KeyTrust::from(flags);
+    let public_key: String = tokens.collect();
+    let public_key = match (public_key.is_empty(), key_trust) {
+        (true, KeyTrust::DoNotTrust) => Vec::new(),
+        (true, _) => {
+            return Err(ParseError::from(ParseErrorKind::Message(
+                "public key not present",
+            )))
+        }
+        (false, _) => data_encoding::BASE64.decode(public_key.as_bytes())?,
+    };
+
+    Ok(KEY::new(
+        key_trust,
+        KeyUsage::from(flags),
+        UpdateScope::from(flags),
+        protocol,
+        algorithm,
+        public_key,
+    ))
+}
+
+/// The flags as an unsigned integer, or mnemonics separated by `|`
+fn parse_flags(flags: &str) -> ParseResult<u16> {
+    let flags = match flags.parse::<u16>() {
+        Ok(flags) => flags,
+        Err(_) => flags.split('|').try_fold(0_u16, |flags, mnemonic| {
+            let flag = match mnemonic.to_ascii_uppercase().as_str() {
+                "NOCONF" => 0b0100_0000_0000_0000,
+                "NOAUTH" => 0b1000_0000_0000_0000,
+                "NOKEY" => 0b1100_0000_0000_0000,
+                "EXTEND" => 0b0001_0000_0000_0000,
+                "USER" => 0b0000_0000_0000_0000,
+                "ZONE" => 0b0000_0001_0000_0000,
+                "HOST" => 0b0000_0010_0000_0000,
+                "NTYP3" => 0b0000_0011_0000_0000,
+                signatory => match signatory.strip_prefix("SIG").map(str::parse::<u16>) {
+                    Some(Ok(signatory)) if signatory <= 15 => signatory,
+                    _ => {
+                        return Err(ParseError::from(ParseErrorKind::Msg(format!(
+                            "unsupported KEY flag: {}",
+                            mnemonic
+                        ))))
+                    }
+                },
+            };
+            Ok(flags | flag)
+        })?,
+    };
+
+    if flags & 0b0010_1100_1111_0000 != 0 {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "flag 2, 4-5, and 8-11 are reserved, must be zero",
+        )));
+    }
+    if flags & 0b0001_0000_0000_0000 != 0 {
+        return Err(ParseError::from(ParseErrorKind::Message(
+            "extended flags currently not supported",
+        )));
+    }
+
+    Ok(flags)
+}
+
+/// The protocol as an unsigned integer or symbolically
+#[allow(deprecated)]
+fn parse_protocol(protocol: &str) -> ParseResult<Protocol> {
+    let protocol = match protocol.to_ascii_uppercase().as_str() {
+        "NONE" => Protocol::Reserved,
+        "TLS" => Protocol::TLS,
+        "EMAIL" => Protocol::Email,
+        "DNSSEC" => Protocol::DNSSec,
+        "IPSEC" => Protocol::IPSec,
+        "ALL" => Protocol::All,
+        _ => Protocol::from(protocol.parse::<u8>()?),
+    };
+
+    Ok(protocol)
+}
+
+/// The algorithm as an unsigned integer or the mnemonic of the algorithm
+#[allow(deprecated)]
+fn parse_algorithm(algorithm: &str) -> ParseResult<Algorithm> {
+    let algorithm = match algorithm.to_ascii_uppercase().as_str() {
+        "RSAMD5" => Algorithm::RSAMD5,
+        "DSA" => Algorithm::DSA,
+        "RSASHA1" => Algorithm::RSASHA1,
+        "RSASHA1-NSEC3-SHA1" => Algorithm::RSASHA1NSEC3SHA1,
+        "RSASHA256" => Algorithm::RSASHA256,
+        "RSASHA512" => Algorithm::RSASHA512,
+        "ECDSAP256SHA256" => Algorithm::ECDSAP256SHA256,
+        "ECDSAP384SHA384" => Algorithm::ECDSAP384SHA384,
+        "ED25519" => Algorithm::ED25519,
+        "ED448" => Algorithm::ED448,
+        _ => Algorithm::from_u8(algorithm.parse()?),
+    };
+
+    Ok(algorithm)
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(deprecated)]
+
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        assert_eq!(
+            parse("513 3 15 AAECAwQFBgc=".split(' ')).unwrap(),
+            KEY::new(
+                KeyTrust::AuthOrPrivate,
+                KeyUsage::Entity,
+                UpdateScope {
+                    zone: false,
+                    strong: false,
+                    unique: false,
+                    general: true,
+                },
+                Protocol::DNSSec,
+                Algorithm::ED25519,
+                vec![0, 1, 2, 3, 4, 5, 6, 7],
+            )
+        );
+
+        // mnemonics, split key
+        assert_eq!(
+            parse("NOCONF|HOST|SIG1 DNSSEC ED25519 AAECAw QFBgc=".split(' ')).unwrap(),
+            parse("16897 3 15 AAECAwQFBgc=".split(' ')).unwrap(),
+        );
+        assert_eq!(
+            parse("zone|sig15 email 8 AAECAwQFBgc=".split(' '))
+                .unwrap()
+                .flags(),
+            0x010F
+        );
+
+        // no key present
+        let key = parse("NOKEY 3 15".split(' ')).unwrap();
+        assert!(key.public_key().is_empty());
+        assert!(key.revoke());
+    }
+
+    #[test]
+    fn test_parsing_errors() {
+        assert!(parse(std::iter::empty()).is_err());
+        assert!(parse("512 3 15".split(' ')).is_err());
+        assert!(parse("512 3 15 !!!".split(' ')).is_err());
+        assert!(parse("SIG16 3 15 AAECAwQFBgc=".split(' ')).is_err());
+        assert!(parse("FLAG2 3 15 AAECAwQFBgc=".split(' ')).is_err());
+        // reserved bit 2 and the flags extension
+        assert!(parse("8192 3 15 AAECAwQFBgc=".split(' ')).is_err());
+        assert!(parse("EXTEND 3 15 AAECAwQFBgc=".split(' ')).is_err());
+        assert!(parse("512 DNS 15 AAECAwQFBgc=".split(' ')).is_err());
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for &protocol in &[0, 1, 2, 3, 4, 5, 255] {
+            for &flags in &[0x0000, 0x4201, 0x810F, 0xC000, 0x0306, 0x0108] {
+                let key = KEY::new(
+                    KeyTrust::from(flags),
+                    KeyUsage::from(flags),
+                    UpdateScope::from(flags),
+                    Protocol::from(protocol),
+                    Algorithm::Unknown(254),
+                    vec![0, 1, 2, 3, 4, 5, 6, 7],
+                );
+                let text = key.to_string();
+                let parsed = parse(text.split(' ')).unwrap();
+
+                assert_eq!(parsed, key, "{}", text);
+                assert_eq!(parsed.flags(), flags);
+                assert_eq!(u8::from(parsed.protocol()), protocol);
+            }
+        }
+    }
+}
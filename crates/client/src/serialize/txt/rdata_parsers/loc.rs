@@ -0,0 +1,229 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! LOC records for the geographic location of hosts, networks and subnets
+
+use std::convert::TryFrom;
+
+use crate::error::*;
+use crate::proto::error::ProtoError;
+use crate::rr::rdata::loc::{self, ALTITUDE_BASE_CM, EQUATOR, LOC};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-3)
+///
+/// ```text
+/// 3. Master File Format
+///
+///    The LOC record is expressed in a master file in the following format:
+///
+///    <owner> <TTL> <class> LOC ( d1 [m1 [s1]] {"N"|"S"} d2 [m2 [s2]]
+///                                {"E"|"W"} alt["m"] [siz["m"] [hp["m"]
+///                                [vp["m"]]]] )
+///
+///    (The parentheses are used for multi-line data as specified in [RFC
+///    1035] section 5.1.)
+///
+///    where:
+///
+///        d1:     [0 .. 90]            (degrees latitude)
+///        d2:     [0 .. 180]           (degrees longitude)
+///        m1, m2: [0 .. 59]            (minutes latitude/longitude)
+///        s1, s2: [0 .. 59.999]        (seconds latitude/longitude)
+///        alt:    [-100000.00 .. 42849672.95] BY .01 (altitude in meters)
+///        siz, hp, vp: [0 .. 90000000.00] (size/precision in meters)
+///
+///    If omitted, minutes and seconds default to zero, size defaults to 1m,
+///    horizontal precision defaults to 10000m, and vertical precision
+///    defaults to 10m.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<LOC> {
+    let latitude = parse_coordinate(&mut tokens, "latitude", "N", "S")?;
+    let longitude = parse_coordinate(&mut tokens, "longitude", "E", "W")?;
+
+    let altitude = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("altitude".to_string())))
+        .and_then(parse_meters)?;
+    let altitude = u32::try_from(altitude + ALTITUDE_BASE_CM)
+        .map_err(|_| ParseError::from(format!("LOC altitude out of range: {}cm", altitude)))?;
+
+    let mut parse_precision = |default: u8| -> ParseResult<u8> {
+        match tokens.next() {
+            Some(token) => {
+                let cm = u64::try_from(parse_meters(token)?).map_err(|_| {
+                    ParseError::from(format!("LOC precision must not be negative: {}", token))
+                })?;
+                Ok(loc::cm_to_precision(cm)?)
+            }
+            None => Ok(default),
+        }
+    };
+    let size = parse_precision(0x12)?;
+    let horiz_pre = parse_precision(0x16)?;
+    let vert_pre = parse_precision(0x13)?;
+
+    Ok(LOC::new(
+        latitude, longitude, altitude, size, horiz_pre, vert_pre,
+    )?)
+}
+
+/// Parses degrees, optional minutes and seconds, and the hemisphere into thousandths of a second of arc
+fn parse_coordinate<'i, I: Iterator<Item = &'i str>>(
+    tokens: &mut I,
+    field: &str,
+    positive: &str,
+    negative: &str,
+) -> ParseResult<u32> {
+    let mut thousandths = 0_i64;
+    let mut parsed = 0;
+
+    let hemisphere = loop {
+        let token = tokens
+            .next()
+            .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken(field.to_string())))?;
+
+        if token.eq_ignore_ascii_case(positive) {
+            break 1;
+        } else if token.eq_ignore_ascii_case(negative) {
+            break -1;
+        }
+
+        thousandths += match parsed {
+            0 => i64::from(token.parse::<u16>()?) * 3_600_000,
+            1 => match token.parse::<u8>()? {
+                minutes if minutes < 60 => i64::from(minutes) * 60_000,
+                _ => return Err(format!("LOC {} minutes out of range: {}", field, token).into()),
+            },
+            2 => match parse_decimal(token, 3)? {
+                seconds if (0..60_000).contains(&seconds) => seconds,
+                _ => return Err(format!("LOC {} seconds out of range: {}", field, token).into()),
+            },
+            _ => return Err(format!("LOC {} missing hemisphere: {}", field, token).into()),
+        };
+        parsed += 1;
+    };
+
+    if parsed == 0 {
+        return Err(ParseErrorKind::MissingToken(format!("{} degrees", field)).into());
+    }
+
+    u32::try_from(i64::from(EQUATOR) + hemisphere * thousandths)
+        .map_err(|_| ProtoError::from(format!("LOC {} out of range", field)).into())
+}
+
+/// Parses meters, with an optional `m` suffix, into centimeters
+fn parse_meters(token: &str) -> ParseResult<i64> {
+    parse_decimal(token.strip_suffix('m').unwrap_or(token), 2)
+}
+
+/// Parses a decimal number with at most `scale` fractional digits, into an integer of that scale
+fn parse_decimal(token: &str, scale: usize) -> ParseResult<i64> {
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, token),
+    };
+    let (integer, fraction) = match digits.find('.') {
+        Some(dot) => (&digits[..dot], &digits[dot + 1..]),
+        None => (digits, ""),
+    };
+
+    if integer.is_empty()
+        || fraction.len() > scale
+        || !integer
+            .bytes()
+            .chain(fraction.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return Err(format!("invalid decimal in LOC: {}", token).into());
+    }
+
+    let value = format!("{}{:0<scale$}", integer, fraction, scale = scale)
+        .parse::<i64>()
+        .map_err(|_| ParseError::from(format!("decimal out of range in LOC: {}", token)))?;
+
+    Ok(if negative { -value } else { value })
+}
+
+#[test]
+fn test_parsing() {
+    let example = LOC::new(
+        EQUATOR + (42 * 3600 + 21 * 60 + 54) * 1000,
+        EQUATOR - (71 * 3600 + 6 * 60 + 18) * 1000,
+        (ALTITUDE_BASE_CM - 2400) as u32,
+        0x33,
+        0x16,
+        0x13,
+    )
+    .unwrap();
+
+    assert_eq!(
+        parse("42 21 54 N 71 06 18 W -24m 30m".split(' ')).expect("failed to parse LOC"),
+        example
+    );
+    assert_eq!(
+        parse("42 21 54.000 N 71 6 18.000 W -24.00m 30m 10000m 10m".split(' '))
+            .expect("failed to parse LOC"),
+        example
+    );
+    assert_eq!(
+        parse("42 21 54 n 71 6 18 w -24 30".split(' ')).expect("failed to parse LOC"),
+        example
+    );
+
+    // minutes and seconds may be omitted, as may the precisions
+    assert_eq!(
+        parse("52 N 4 E 0m".split(' ')).expect("failed to parse LOC"),
+        LOC::new(
+            EQUATOR + 52 * 3_600_000,
+            EQUATOR + 4 * 3_600_000,
+            ALTITUDE_BASE_CM as u32,
+            0x12,
+            0x16,
+            0x13
+        )
+        .unwrap()
+    );
+    assert_eq!(
+        parse("0 S 0 0 0.001 W 42849672.95m 90000000m 0.5m 1.23m".split(' '))
+            .expect("failed to parse LOC")
+            .to_string(),
+        "0 0 0.000 N 0 0 0.001 W 42849672.95m 90000000m 0.50m 1m"
+    );
+}
+
+#[test]
+fn test_parsing_fails() {
+    assert!(parse(vec![].into_iter()).is_err());
+    assert!(parse("42 21 54 N".split(' ')).is_err());
+    assert!(parse("42 21 54 N 71 06 18 W".split(' ')).is_err());
+    assert!(parse("42 21 54 X 71 06 18 W -24m".split(' ')).is_err());
+    assert!(parse("N 71 06 18 W -24m".split(' ')).is_err());
+    assert!(parse("42 60 0 N 71 06 18 W -24m".split(' ')).is_err());
+    assert!(parse("42 21 60 N 71 06 18 W -24m".split(' ')).is_err());
+    assert!(parse("42 21 54.0001 N 71 06 18 W -24m".split(' ')).is_err());
+    assert!(parse("42 21 54 N 71 06 18 W -100000.01m".split(' ')).is_err());
+    assert!(parse("42 21 54 N 71 06 18 W -24m -1m".split(' ')).is_err());
+    assert!(parse("42 21 54 N 71 06 18 W -24m 90000000.01m".split(' ')).is_err());
+}
+
+#[test]
+fn test_out_of_range() {
+    fn is_proto_error(input: &str) -> bool {
+        matches!(
+            parse(input.split(' ')).map_err(|e| e.kind().clone()),
+            Err(ParseErrorKind::Proto(_))
+        )
+    }
+
+    assert!(parse("90 N 180 E 0m".split(' ')).is_ok());
+    assert!(is_proto_error("90 0 0.001 N 71 06 18 W -24m"));
+    assert!(is_proto_error("91 S 71 06 18 W -24m"));
+    assert!(is_proto_error("42 21 54 N 181 E -24m"));
+    assert!(is_proto_error("42 21 54 N 65535 W -24m"));
+}
@@ -21,11 +21,18 @@
 // each of these module's has the parser for that rdata embedded, to keep the file sizes down...
 pub(crate) mod a;
 pub(crate) mod aaaa;
+pub(crate) mod apl;
 pub(crate) mod caa;
+pub(crate) mod cert;
 pub(crate) mod csync;
+pub(crate) mod dhcid;
 #[cfg(feature = "dnssec")]
 pub(crate) mod ds;
 pub(crate) mod hinfo;
+pub(crate) mod hip;
+#[cfg(feature = "dnssec")]
+pub(crate) mod key;
+pub(crate) mod loc;
 pub(crate) mod mx;
 pub(crate) mod name;
 pub(crate) mod naptr;
@@ -37,3 +44,5 @@ pub(crate) mod sshfp;
 pub(crate) mod svcb;
 pub(crate) mod tlsa;
 pub(crate) mod txt;
+pub(crate) mod uri;
+pub(crate) mod zonemd;
@@ -17,12 +17,84 @@
 //! null record type, generally not used except as an internal tool for representing null data
 
 use crate::error::*;
-use crate::rr::rdata::NULL;
+use crate::rr::rdata::{sshfp, NULL};
 
 /// Parse the RData from a set of Tokens
-#[allow(unused)]
+///
+/// NULL records can only be expressed in the generic format, which is also used for unknown record types.
+///
+/// [RFC 3597, Handling of Unknown DNS Resource Record (RR) Types, September 2003](https://tools.ietf.org/html/rfc3597#section-5)
+///
+/// ```text
+/// 5.  Text Representation
+///
+///    The RDATA section of an RR of unknown type is represented as a
+///    sequence of white space separated words as follows:
+///
+///       The special token \# (a backslash immediately followed by a hash
+///       sign), which identifies the RDATA as having the generic encoding
+///       defined herein rather than a traditional type-specific encoding.
+///
+///       An unsigned decimal integer specifying the RDATA length in octets.
+///
+///       Zero or more words of hexadecimal data encoding the actual RDATA
+///       field, each containing an even number of hexadecimal digits.
+///
+///    If the RDATA is of zero length, the text representation contains
+///    only the \# token and the single zero representing the length.
+/// ```
 pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<NULL> {
-    Err(ParseError::from(ParseErrorKind::Msg(
-        "Parse is not implemented for NULL record".to_string(),
-    )))
+    match tokens.next() {
+        Some("\\#") => (),
+        _ => {
+            return Err(ParseErrorKind::Message(
+                "NULL records must use the generic \\# representation",
+            )
+            .into())
+        }
+    }
+
+    let length: u16 = tokens
+        .next()
+        .ok_or(ParseErrorKind::Message("generic rdata length missing"))?
+        .parse()?;
+
+    let anything = tokens.try_fold(Vec::new(), |mut anything, word| {
+        anything.extend(sshfp::HEX.decode(word.as_bytes())?);
+        Ok::<_, ParseError>(anything)
+    })?;
+
+    if anything.len() != usize::from(length) {
+        return Err(ParseErrorKind::Msg(format!(
+            "generic rdata length {} does not match data length {}",
+            length,
+            anything.len()
+        ))
+        .into());
+    }
+
+    if anything.is_empty() {
+        Ok(NULL::new())
+    } else {
+        Ok(NULL::with(anything))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing() {
+        assert_eq!(
+            parse(vec!["\\#", "4", "0A00", "0001"].into_iter()).unwrap(),
+            NULL::with(vec![0x0A, 0x00, 0x00, 0x01])
+        );
+        assert_eq!(parse(vec!["\\#", "0"].into_iter()).unwrap(), NULL::new());
+
+        assert!(parse(vec!["0A000001"].into_iter()).is_err());
+        assert!(parse(vec!["\\#"].into_iter()).is_err());
+        assert!(parse(vec!["\\#", "3", "0A000001"].into_iter()).is_err());
+        assert!(parse(vec!["\\#", "4", "0A00000"].into_iter()).is_err());
+    }
 }
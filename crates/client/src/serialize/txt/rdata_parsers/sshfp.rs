@@ -25,6 +25,8 @@ use crate::rr::rdata::{sshfp, SSHFP};
 ///
 ///    The use of mnemonics instead of numbers is not allowed.
 /// ```
+///
+/// Whitespace within the hex fingerprint is ignored.
 pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<SSHFP> {
     fn missing_field<E: From<ParseErrorKind>>(field: &str) -> E {
         ParseErrorKind::Msg(format!("SSHFP {} field missing", field)).into()
@@ -41,16 +43,17 @@ pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResu
             parse_u8("fingerprint type")?.into(),
         )
     };
-    let fingerprint = sshfp::HEX.decode(
-        tokens
-            .next()
-            .filter(|fp| !fp.is_empty())
-            .ok_or_else(|| missing_field::<ParseError>("fingerprint"))?
-            .as_bytes(),
-    )?;
-    Some(SSHFP::new(algorithm, fingerprint_type, fingerprint))
-        .filter(|_| tokens.next().is_none())
-        .ok_or_else(|| ParseErrorKind::Message("too many fields for SSHFP").into())
+    // like TLSA, the fingerprint may be split with whitespace, e.g. across lines in parentheses
+    let fingerprint = tokens.fold(String::new(), |mut fingerprint, data| {
+        fingerprint.push_str(data);
+        fingerprint
+    });
+    if fingerprint.is_empty() {
+        return Err(missing_field("fingerprint"));
+    }
+    let fingerprint = sshfp::HEX.decode(fingerprint.as_bytes())?;
+
+    Ok(SSHFP::new(algorithm, fingerprint_type, fingerprint))
 }
 
 #[test]
@@ -79,6 +82,15 @@ fn test_parsing() {
             172,
         ],
     );
+    test_parsing(
+        vec!["1", "1", "dd465c09cfa51fb4", "5020cc83316fff21", "b9ec74ac"],
+        RSA,
+        SHA1,
+        &[
+            221, 70, 92, 9, 207, 165, 31, 180, 80, 32, 204, 131, 49, 111, 255, 33, 185, 236, 116,
+            172,
+        ],
+    );
     test_parsing(
         vec![
             "1",
@@ -0,0 +1,56 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! URI records for publishing mappings from hostnames to URIs
+
+use crate::error::*;
+use crate::rr::rdata::URI;
+
+/// Parse the RData from a set of Tokens
+///
+/// ```text
+/// _ftp._tcp.example.com. IN URI 10 1 "ftp://ftp.example.com/public"
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<URI> {
+    let priority: u16 = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("priority".to_string())))
+        .and_then(|s| s.parse().map_err(Into::into))?;
+
+    let weight: u16 = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("weight".to_string())))
+        .and_then(|s| s.parse().map_err(Into::into))?;
+
+    let target = tokens
+        .next()
+        .ok_or_else(|| ParseError::from(ParseErrorKind::MissingToken("target".to_string())))?;
+
+    if tokens.next().is_some() {
+        return Err(ParseError::from(
+            "URI target must be a single quoted string",
+        ));
+    }
+
+    Ok(URI::new(priority, weight, target.to_string())?)
+}
+
+#[test]
+fn test_parsing() {
+    assert_eq!(
+        parse(vec!["10", "1", "ftp://ftp.example.com/public"].into_iter()).unwrap(),
+        URI::new(10, 1, "ftp://ftp.example.com/public".to_string()).unwrap(),
+    );
+}
+
+#[test]
+fn test_parsing_fails() {
+    assert!(parse(vec!["10", "1"].into_iter()).is_err());
+    assert!(parse(vec!["10", "1", "ftp.example.com"].into_iter()).is_err());
+    assert!(parse(vec!["10", "1", "ftp://ftp.example.com/my", "files"].into_iter()).is_err());
+    assert!(parse(vec!["65536", "1", "ftp://ftp.example.com/public"].into_iter()).is_err());
+}
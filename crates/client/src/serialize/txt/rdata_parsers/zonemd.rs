@@ -0,0 +1,145 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! ZONEMD records for message digests of whole zones
+
+use crate::error::*;
+use crate::rr::rdata::{sshfp, ZONEMD};
+
+/// Parse the RData from a set of Tokens
+///
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-2.3)
+///
+/// ```text
+/// 2.3.  ZONEMD Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Serial field MUST be represented as an unsigned decimal integer.
+///
+///    The Scheme field MUST be represented as an unsigned decimal integer.
+///
+///    The Hash Algorithm field MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Digest MUST be represented as a sequence of case-insensitive
+///    hexadecimal digits.  Whitespace is allowed within the hexadecimal
+///    text.
+/// ```
+pub(crate) fn parse<'i, I: Iterator<Item = &'i str>>(mut tokens: I) -> ParseResult<ZONEMD> {
+    fn missing_field<E: From<ParseErrorKind>>(field: &str) -> E {
+        ParseErrorKind::Msg(format!("ZONEMD {} field missing", field)).into()
+    }
+
+    let serial = tokens
+        .next()
+        .ok_or_else(|| missing_field("serial"))
+        .and_then(|t| t.parse::<u32>().map_err(ParseError::from))?;
+    let (scheme, hash_algorithm) = {
+        let mut parse_u8 = |field: &str| {
+            tokens
+                .next()
+                .ok_or_else(|| missing_field(field))
+                .and_then(|t| t.parse::<u8>().map_err(ParseError::from))
+        };
+        (
+            parse_u8("scheme")?.into(),
+            parse_u8("hash algorithm")?.into(),
+        )
+    };
+    // the digest is usually split across lines in parentheses
+    let digest = tokens.fold(String::new(), |mut digest, data| {
+        digest.push_str(data);
+        digest
+    });
+    if digest.is_empty() {
+        return Err(missing_field("digest"));
+    }
+    let digest = sshfp::HEX.decode(digest.as_bytes())?;
+
+    Ok(ZONEMD::new(serial, scheme, hash_algorithm, digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rr::rdata::zonemd::{HashAlgorithm, Scheme};
+
+    #[test]
+    fn test_parsing() {
+        assert_eq!(
+            parse("2018031900 1 1 C68090D9 0a7aed71".split(' ')).unwrap(),
+            ZONEMD::new(
+                2018031900,
+                Scheme::Simple,
+                HashAlgorithm::SHA384,
+                vec![0xc6, 0x80, 0x90, 0xd9, 0x0a, 0x7a, 0xed, 0x71],
+            )
+        );
+
+        assert!(parse(std::iter::empty()).is_err());
+        assert!(parse("2018031900 1 1".split(' ')).is_err());
+        assert!(parse("2018031900 1 1 xyz".split(' ')).is_err());
+        assert!(parse("2018031900 256 1 c68090d9".split(' ')).is_err());
+        assert!(parse("-1 1 1 c68090d9".split(' ')).is_err());
+    }
+
+    #[cfg(any(feature = "dnssec-openssl", feature = "dnssec-ring"))]
+    #[test]
+    fn test_verify_zone() {
+        use crate::proto::rr::dnssec::{verify_zonemd, ZonemdError};
+        use crate::rr::{Name, RecordType};
+        use crate::serialize::txt::{Lexer, Parser};
+
+        // RFC 8976, Appendix A.1. Simple EXAMPLE Zone
+        let zone = r###"
+example.      86400  IN  SOA     ns1 admin 2018031900 (
+                                 1800 900 604800 86400 )
+              86400  IN  NS      ns1
+              86400  IN  NS      ns2
+              86400  IN  ZONEMD  2018031900 1 1 (
+                                 c68090d90a7aed71
+                                 6bc459f9340e3d7c
+                                 1370d4d24b7e2fc3
+                                 a1ddc0b9a87153b9
+                                 a9713b3c9ae5cc27
+                                 777f98b8e730044c )
+ns1           3600   IN  A       203.0.113.63
+ns2           3600   IN  AAAA    2001:db8::63
+"###;
+        let verify = |zone: &str| {
+            let (_, records) = Parser::new()
+                .parse(
+                    Lexer::new(zone),
+                    Some(Name::from_ascii("example.").unwrap()),
+                    None,
+                )
+                .expect("failed to parse zone");
+            let mut records = records
+                .into_iter()
+                .flat_map(|(_, rrset)| rrset.records_without_rrsigs().cloned().collect::<Vec<_>>())
+                .collect::<Vec<_>>();
+            // the parser uses the SOA expire as the TTL of the SOA, not the TTL in the zone
+            for record in &mut records {
+                if record.rr_type() == RecordType::SOA {
+                    record.set_ttl(86400);
+                }
+            }
+            verify_zonemd(&records)
+        };
+
+        verify(zone).expect("zone should verify");
+        assert!(matches!(
+            verify(&zone.replace("203.0.113.63", "203.0.113.64")),
+            Err(ZonemdError::Digest)
+        ));
+        assert!(matches!(
+            verify(&zone.replace("c68090d90a7aed71", "c68090d90a7aed72")),
+            Err(ZonemdError::Digest)
+        ));
+    }
+}
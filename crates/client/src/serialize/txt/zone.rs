@@ -14,13 +14,24 @@
  * limitations under the License.
  */
 use std::collections::BTreeMap;
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
+use log::debug;
+
 use crate::error::*;
 use crate::rr::{DNSClass, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey};
 use crate::serialize::txt::parse_rdata::RDataParser;
 use crate::serialize::txt::zone_lex::{Lexer, Token};
 
+/// The most records one `$GENERATE` may produce, so that a short line can't exhaust the memory of the server
+const MAX_GENERATE: u32 = 65_536;
+
+/// The widest field of a `$GENERATE` substitution, a label can't be longer anyway
+const MAX_GENERATE_WIDTH: usize = 255;
+
 /// ```text
 /// 5. ZONE FILES
 ///
@@ -132,6 +143,9 @@ impl Parser {
 
     /// Parse a file from the Lexer
     ///
+    /// `$INCLUDE` directives are rejected, as the content has no path to resolve them against, see
+    ///  `parse_file` to parse a zone file with includes.
+    ///
     /// # Return
     ///
     /// A pair of the Zone origin name and a map of all Keys to RecordSets
@@ -141,14 +155,127 @@ impl Parser {
         origin: Option<Name>,
         class: Option<DNSClass>,
     ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
+        let mut zone = Zone::new(class);
+        let origin = Self::parse_lexer(lexer, origin, &mut zone)?;
+        zone.into_records(origin)
+    }
+
+    /// Parse a zone file, along with all files it includes with `$INCLUDE`
+    ///
+    /// Relative paths of included files are resolved against the directory of the including file.
+    ///
+    /// # Return
+    ///
+    /// A pair of the Zone origin name and a map of all Keys to RecordSets
+    pub fn parse_file(
+        &mut self,
+        path: &Path,
+        origin: Option<Name>,
+        class: Option<DNSClass>,
+    ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
+        self.parse_file_with_includes(path, origin, class)
+            .map(|(origin, records, _)| (origin, records))
+    }
+
+    /// Parse a zone file, along with all files it includes with `$INCLUDE`, see `parse_file`
+    ///
+    /// # Return
+    ///
+    /// The Zone origin name, a map of all Keys to RecordSets, and the canonical paths of all files which
+    ///  were read, the zone file first
+    pub fn parse_file_with_includes(
+        &mut self,
+        path: &Path,
+        origin: Option<Name>,
+        class: Option<DNSClass>,
+    ) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>, Vec<PathBuf>)> {
+        let mut zone = Zone::new(class);
+        let origin = Self::parse_include(path, origin, &mut zone)?;
+        let read = mem::take(&mut zone.read);
+        zone.into_records(origin)
+            .map(|(origin, records)| (origin, records, read))
+    }
+
+    /// Parses an included file, returning the origin at the end of the file
+    fn parse_include(
+        path: &Path,
+        origin: Option<Name>,
+        zone: &mut Zone,
+    ) -> ParseResult<Option<Name>> {
+        let path = fs::canonicalize(path)
+            .map_err(|e| ParseError::from(format!("failed to read {}: {}", path.display(), e)))?;
+
+        if zone.files.contains(&path) {
+            return Err(ParseError::from(format!(
+                "$INCLUDE cycle, {} is already being parsed",
+                path.display()
+            )));
+        }
+
+        let content = fs::read_to_string(&path)
+            .map_err(|e| ParseError::from(format!("failed to read {}: {}", path.display(), e)))?;
+
+        zone.read.push(path.clone());
+        zone.files.push(path);
+        let origin = Self::parse_lexer(Lexer::new(&content), origin, zone);
+        zone.files.pop();
+
+        origin
+    }
+
+    /// Handles `$INCLUDE <file-name> [<domain-name>]`
+    ///
+    /// The origin of the included file defaults to the current origin, changes to it in the included file
+    ///  never change the origin of the including file.
+    fn include(parts: Vec<String>, origin: &Option<Name>, zone: &mut Zone) -> ParseResult<()> {
+        // only files read by `parse_file` may include others, content from elsewhere must not read local files
+        let including = match zone.files.last() {
+            Some(including) => including,
+            None => {
+                return Err(ParseErrorKind::Message(
+                    "$INCLUDE is only supported when parsing a zone file",
+                )
+                .into())
+            }
+        };
+
+        let mut parts = parts.into_iter();
+        let (file, include_origin) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(file), None, None) => (file, origin.clone()),
+            (Some(file), Some(domain), None) => {
+                (file, Some(Name::parse(&domain, origin.as_ref())?))
+            }
+            _ => {
+                return Err(ParseErrorKind::Message(
+                    "$INCLUDE requires a file name and an optional domain name",
+                )
+                .into())
+            }
+        };
+
+        // RFC1035 does not specify how the file name is resolved into a path, a relative path is
+        //  resolved against the directory of the including file.
+        let file = Path::new(&file);
+        let path = match including.parent() {
+            Some(dir) if file.is_relative() => dir.join(file),
+            _ => file.to_path_buf(),
+        };
+
+        debug!("including file {}", path.display());
+        Self::parse_include(&path, include_origin, zone).map(|_| ())
+    }
+
+    /// Parses the records of one file into the zone, returning the origin at the end of the file
+    fn parse_lexer(
+        lexer: Lexer<'_>,
+        origin: Option<Name>,
+        zone: &mut Zone,
+    ) -> ParseResult<Option<Name>> {
         let mut lexer = lexer;
-        let mut records: BTreeMap<RrKey, RecordSet> = BTreeMap::new();
 
         let mut origin: Option<Name> = origin;
-        let mut class: Option<DNSClass> = class;
         let mut current_name: Option<Name> = None;
         let mut rtype: Option<RecordType> = None;
-        let mut ttl: Option<u32> = None;
         let mut state = State::StartLine;
 
         while let Some(t) = lexer.next_token()? {
@@ -159,9 +286,8 @@ impl Parser {
 
                     match t {
                         // if Dollar, then $INCLUDE or $ORIGIN
-                        Token::Include => {
-                            return Err(ParseError::from(ParseErrorKind::Message("The parser does not support $INCLUDE. Consider inlining file before parsing")))
-                        },
+                        Token::Include => State::Include(vec![]),
+                        Token::Generate => State::Generate(vec![]),
                         Token::Origin => State::Origin,
                         Token::Ttl => State::Ttl,
 
@@ -185,7 +311,7 @@ impl Parser {
                 }
                 State::Ttl => match t {
                     Token::CharData(data) => {
-                        ttl = Some(Self::parse_time(&data)?);
+                        zone.ttl = Some(Self::parse_time(&data)?);
                         State::StartLine
                     }
                     _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
@@ -200,9 +326,35 @@ impl Parser {
                         _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
                     }
                 }
-                State::Include => return Err(ParseError::from(ParseErrorKind::Message(
-                    "The parser does not support $INCLUDE. Consider inlining file before parsing",
-                ))),
+                State::Include(parts) => match t {
+                    Token::CharData(part) => {
+                        let mut parts = parts;
+                        parts.push(part);
+                        State::Include(parts)
+                    }
+                    Token::EOL => {
+                        Self::include(parts, &origin, zone)?;
+                        State::StartLine
+                    }
+                    _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+                },
+                State::Generate(parts) => match t {
+                    Token::CharData(part) => {
+                        let mut parts = parts;
+                        parts.push(part);
+                        State::Generate(parts)
+                    }
+                    Token::List(list) => {
+                        let mut parts = parts;
+                        parts.extend(list);
+                        State::Generate(parts)
+                    }
+                    Token::EOL => {
+                        Self::generate(parts, &origin, zone)?;
+                        State::StartLine
+                    }
+                    _ => return Err(ParseErrorKind::UnexpectedToken(t).into()),
+                },
                 State::TtlClassType => {
                     match t {
                         // if number, TTL
@@ -212,14 +364,14 @@ impl Parser {
                             // if it's a number it's a ttl
                             let result: ParseResult<u32> = Self::parse_time(&data);
                             if result.is_ok() {
-                                ttl = result.ok();
+                                zone.ttl = result.ok();
                                 State::TtlClassType // hm, should this go to just ClassType?
                             } else {
                                 // if can parse DNSClass, then class
                                 data.make_ascii_uppercase();
                                 let result = DNSClass::from_str(&data);
                                 if result.is_ok() {
-                                    class = result.ok();
+                                    zone.class = result.ok();
                                     State::TtlClassType
                                 } else {
                                     // if can parse RecordType, then RecordType
@@ -245,9 +397,9 @@ impl Parser {
                                 &origin,
                                 &current_name,
                                 rtype,
-                                &mut ttl,
-                                class,
-                                &mut records,
+                                &mut zone.ttl,
+                                zone.class,
+                                &mut zone.records,
                             )?;
                             State::StartLine
                         }
@@ -269,24 +421,186 @@ impl Parser {
         }
 
         //Extra flush at the end for the case of missing endline
-        if let State::Record(record_parts) = state {
-            Self::flush_record(
+        match state {
+            State::Record(record_parts) => Self::flush_record(
                 record_parts,
                 &origin,
                 &current_name,
                 rtype,
+                &mut zone.ttl,
+                zone.class,
+                &mut zone.records,
+            )?,
+            State::Include(parts) => Self::include(parts, &origin, zone)?,
+            State::Generate(parts) => Self::generate(parts, &origin, zone)?,
+            _ => (),
+        }
+
+        Ok(origin)
+    }
+
+    /// Handles `$GENERATE <range> <lhs> [<ttl>] [<class>] <type> <rhs>`, as implemented by BIND
+    ///
+    /// A record is added for each value of the range, `start-stop[/step]`, as if it were written out. In
+    ///  the owner name and the rdata, `$` is replaced by the value, `${offset[,width[,radix]]}` by the
+    ///  value plus the offset, zero padded to the width, in the radix of `d`, `o`, `x` or `X`. `\$` is a
+    ///  literal `$`.
+    ///
+    /// ```text
+    /// $GENERATE 1-254 $ PTR host-${0,3,d}.example.com.
+    /// ```
+    fn generate(parts: Vec<String>, origin: &Option<Name>, zone: &mut Zone) -> ParseResult<()> {
+        let mut parts = parts.into_iter();
+        let (range, lhs) = match (parts.next(), parts.next()) {
+            (Some(range), Some(lhs)) => (range, lhs),
+            _ => {
+                return Err(ParseErrorKind::Message(
+                    "$GENERATE requires a range, owner name, type and rdata",
+                )
+                .into())
+            }
+        };
+        let (start, stop, step) = Self::parse_range(&range)?;
+
+        let mut ttl = zone.ttl;
+        let mut class = zone.class;
+        let rtype = loop {
+            let mut data = parts.next().ok_or_else(|| {
+                ParseError::from(ParseErrorKind::Message(
+                    "$GENERATE record type not specified",
+                ))
+            })?;
+
+            if let Ok(generate_ttl) = Self::parse_time(&data) {
+                ttl = Some(generate_ttl);
+                continue;
+            }
+
+            data.make_ascii_uppercase();
+            if let Ok(generate_class) = DNSClass::from_str(&data) {
+                class = Some(generate_class);
+                continue;
+            }
+
+            break RecordType::from_str(&data)?;
+        };
+        let rhs = parts.collect::<Vec<_>>();
+
+        for value in (start..=stop).step_by(step) {
+            let name = Name::parse(&Self::substitute(&lhs, value)?, origin.as_ref())?;
+            let record_parts = rhs
+                .iter()
+                .map(|part| Self::substitute(part, value))
+                .collect::<ParseResult<Vec<_>>>()?;
+
+            Self::flush_record(
+                record_parts,
+                origin,
+                &Some(name),
+                Some(rtype),
                 &mut ttl,
                 class,
-                &mut records,
+                &mut zone.records,
             )?;
         }
 
-        //
-        // build the Authority and return.
-        let origin = origin.ok_or_else(|| {
-            ParseError::from(ParseErrorKind::Message("$ORIGIN was not specified"))
-        })?;
-        Ok((origin, records))
+        Ok(())
+    }
+
+    /// Parses the `start-stop[/step]` range of `$GENERATE`
+    fn parse_range(range: &str) -> ParseResult<(u32, u32, usize)> {
+        let (range, step) = match range.split_once('/') {
+            Some((range, step)) => (range, step.parse::<usize>()?),
+            None => (range, 1),
+        };
+        let (start, stop) = range
+            .split_once('-')
+            .ok_or_else(|| ParseError::from(format!("invalid $GENERATE range: {}", range)))?;
+        let (start, stop) = (start.parse::<u32>()?, stop.parse::<u32>()?);
+
+        if start > stop {
+            return Err(format!("descending $GENERATE range: {}", range).into());
+        }
+        if step == 0 {
+            return Err(ParseErrorKind::Message("$GENERATE step must not be zero").into());
+        }
+        // like BIND, the values must be positive 32 bit integers
+        if stop > i32::MAX as u32 {
+            return Err(format!("$GENERATE range exceeds {}: {}", i32::MAX, range).into());
+        }
+        if (stop - start) as usize / step >= MAX_GENERATE as usize {
+            return Err(format!(
+                "$GENERATE range produces more than {} records: {}",
+                MAX_GENERATE, range
+            )
+            .into());
+        }
+
+        Ok((start, stop, step))
+    }
+
+    /// Replaces the `$` and `${offset[,width[,radix]]}` of a `$GENERATE` template with the value
+    fn substitute(template: &str, value: u32) -> ParseResult<String> {
+        let mut result = String::with_capacity(template.len());
+        let mut chars = template.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\\' if chars.peek() == Some(&'$') => {
+                    chars.next();
+                    result.push('$');
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    let modifiers = chars
+                        .by_ref()
+                        .take_while(|ch| *ch != '}')
+                        .collect::<String>();
+                    let mut modifiers = modifiers.split(',');
+
+                    let offset = match modifiers.next() {
+                        Some(offset) if !offset.is_empty() => offset.parse::<i64>()?,
+                        _ => 0,
+                    };
+                    let width = match modifiers.next() {
+                        Some(width) => width.parse::<usize>()?,
+                        None => 0,
+                    };
+                    if width > MAX_GENERATE_WIDTH {
+                        return Err(format!(
+                            "$GENERATE width exceeds {} in: {}",
+                            MAX_GENERATE_WIDTH, template
+                        )
+                        .into());
+                    }
+                    let value = match i64::from(value).checked_add(offset) {
+                        Some(value) if value >= 0 => value,
+                        Some(_) => {
+                            return Err(format!("negative $GENERATE value in: {}", template).into())
+                        }
+                        None => {
+                            return Err(
+                                format!("$GENERATE offset overflows in: {}", template).into()
+                            )
+                        }
+                    };
+
+                    match modifiers.next().unwrap_or("d") {
+                        "d" => result.push_str(&format!("{:0width$}", value, width = width)),
+                        "o" => result.push_str(&format!("{:0width$o}", value, width = width)),
+                        "x" => result.push_str(&format!("{:0width$x}", value, width = width)),
+                        "X" => result.push_str(&format!("{:0width$X}", value, width = width)),
+                        radix => {
+                            return Err(format!("unsupported $GENERATE radix: {}", radix).into())
+                        }
+                    }
+                }
+                '$' => result.push_str(&value.to_string()),
+                ch => result.push(ch),
+            }
+        }
+
+        Ok(result)
     }
 
     fn flush_record(
@@ -451,12 +765,44 @@ impl Parser {
     }
 }
 
-#[allow(unused)]
+/// The state of the zone which is shared with included files
+struct Zone {
+    records: BTreeMap<RrKey, RecordSet>,
+    class: Option<DNSClass>,
+    ttl: Option<u32>,
+    /// The files being parsed, from the zone file down to the current include, to detect cycles
+    files: Vec<PathBuf>,
+    /// All files which were read, in the order they were included
+    read: Vec<PathBuf>,
+}
+
+impl Zone {
+    fn new(class: Option<DNSClass>) -> Self {
+        Self {
+            records: BTreeMap::new(),
+            class,
+            ttl: None,
+            files: Vec::new(),
+            read: Vec::new(),
+        }
+    }
+
+    fn into_records(self, origin: Option<Name>) -> ParseResult<(Name, BTreeMap<RrKey, RecordSet>)> {
+        //
+        // build the Authority and return.
+        let origin = origin.ok_or_else(|| {
+            ParseError::from(ParseErrorKind::Message("$ORIGIN was not specified"))
+        })?;
+        Ok((origin, self.records))
+    }
+}
+
 enum State {
     StartLine,    // start of line, @, $<WORD>, Name, Blank
     TtlClassType, // [<TTL>] [<class>] <type>,
     Ttl,          // $TTL <time>
     Record(Vec<String>),
-    Include, // $INCLUDE <filename>
+    Include(Vec<String>),  // $INCLUDE <filename> [<domain-name>]
+    Generate(Vec<String>), // $GENERATE <range> <lhs> [<ttl>] [<class>] <type> <rhs>
     Origin,
 }
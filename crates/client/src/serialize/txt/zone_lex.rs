@@ -146,6 +146,11 @@ impl<'a> Lexer<'a> {
                             self.txt.next();
                             Self::push_to_str(&mut char_data, ch.unwrap())?;
                         }
+                        // a $ which is not a directive, e.g. the iterator of $GENERATE
+                        Some(_) | None if char_data.as_deref() == Some("") => {
+                            char_data = Some("$".to_string());
+                            self.state = State::CharData { is_list: false };
+                        }
                         // finishes the Dollar...
                         Some(_) | None => {
                             self.state = State::RestOfLine;
@@ -162,6 +167,8 @@ impl<'a> Lexer<'a> {
                                 return Ok(Some(Token::Origin));
                             } else if "TTL" == dollar {
                                 return Ok(Some(Token::Ttl));
+                            } else if "GENERATE" == dollar {
+                                return Ok(Some(Token::Generate));
                             } else {
                                 return Err(LexerErrorKind::UnrecognizedDollar(
                                     char_data.take().unwrap_or_else(|| "".into()),
@@ -372,6 +379,8 @@ pub enum Token {
     Origin,
     /// $TTL
     Ttl,
+    /// $GENERATE
+    Generate,
     /// \n or \r\n
     EOL,
 }
@@ -558,10 +567,48 @@ mod lex_test {
             Token::Origin
         );
         assert_eq!(next_token(&mut Lexer::new("$TTL")).unwrap(), Token::Ttl);
+        assert_eq!(
+            next_token(&mut Lexer::new("$GENERATE")).unwrap(),
+            Token::Generate
+        );
         assert_eq!(next_token(&mut Lexer::new("\n")), Some(Token::EOL));
         assert_eq!(next_token(&mut Lexer::new("\r\n")), Some(Token::EOL));
     }
 
+    #[test]
+    fn generate() {
+        let mut lexer = Lexer::new("$GENERATE 1-254 $ PTR host-${0,3,d}.example.com.");
+        assert_eq!(next_token(&mut lexer).unwrap(), Token::Generate);
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("1-254".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("$".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("PTR".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("host-${0,3,d}.example.com.".to_string())
+        );
+        assert!(next_token(&mut lexer).is_none());
+
+        let mut lexer = Lexer::new("$GENERATE 1-2 ${0,3,d} A 10.0.0.$");
+        assert_eq!(next_token(&mut lexer).unwrap(), Token::Generate);
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("1-2".to_string())
+        );
+        assert_eq!(
+            next_token(&mut lexer).unwrap(),
+            Token::CharData("${0,3,d}".to_string())
+        );
+    }
+
     #[test]
     fn list() {
         let mut lexer = Lexer::new("(");
@@ -0,0 +1,96 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use test::Bencher;
+
+use trust_dns_proto::op::{Message, Query};
+use trust_dns_proto::rr::{Name, RData, Record, RecordType};
+use trust_dns_proto::serialize::binary::{BinDecodable, BinDecoder, BinEncodable};
+
+/// Counts the allocations made, to compare the owned and borrowed decoding of names
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const MESSAGES: usize = 10_000;
+
+/// A response with names long enough not to fit inline in a `Name`
+fn message_bytes() -> Vec<u8> {
+    let name = Name::from_str("a-rather-long-host-name.with-a-subdomain.example.com.").unwrap();
+    let target = Name::from_str("another-rather-long-host-name.example.net.").unwrap();
+
+    let mut message = Message::new();
+    message.add_query(Query::query(name.clone(), RecordType::CNAME));
+    message.add_answer(Record::from_rdata(name, 300, RData::CNAME(target)));
+    message.to_bytes().unwrap()
+}
+
+/// Reads the names of the question and the answer, skipping over everything else
+fn read_names<'a, T>(
+    bytes: &'a [u8],
+    mut read_name: impl FnMut(&mut BinDecoder<'a>) -> T,
+) -> [T; 3] {
+    let mut decoder = BinDecoder::new(bytes);
+    decoder.read_slice(12).unwrap();
+
+    let qname = read_name(&mut decoder);
+    decoder.read_slice(4).unwrap();
+
+    let owner = read_name(&mut decoder);
+    decoder.read_slice(10).unwrap();
+    let target = read_name(&mut decoder);
+
+    [qname, owner, target]
+}
+
+fn report_allocations(b: &mut Bencher, parse: impl Fn()) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    parse();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    println!("{} allocations for {} messages", allocations, MESSAGES);
+
+    b.iter(parse);
+}
+
+#[bench]
+fn name_read_owned(b: &mut Bencher) {
+    let bytes = message_bytes();
+
+    report_allocations(b, || {
+        for _ in 0..MESSAGES {
+            let names = read_names(&bytes, |d| Name::read(d).unwrap());
+            assert_eq!(names[2].num_labels(), 3);
+        }
+    });
+}
+
+#[bench]
+fn name_read_labels(b: &mut Bencher) {
+    let bytes = message_bytes();
+
+    report_allocations(b, || {
+        for _ in 0..MESSAGES {
+            let names = read_names(&bytes, |d| d.read_name_labels().unwrap());
+            assert_eq!(names[2].clone().count(), 3);
+        }
+    });
+}
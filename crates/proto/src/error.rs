@@ -30,6 +30,8 @@ use thiserror::Error;
 use crate::op::Header;
 use crate::rr::{Name, RecordType};
 use crate::serialize::binary::DecodeError;
+#[cfg(feature = "dnssec")]
+use crate::xfer::DnsResponse;
 
 #[cfg(feature = "backtrace")]
 #[cfg_attr(docsrs, doc(cfg(feature = "backtrace")))]
@@ -113,6 +115,10 @@ pub enum ProtoErrorKind {
     #[error("edns resource record label must be the root label (.): {0}")]
     EdnsNameNotRoot(crate::rr::Name),
 
+    /// The EDNS OPT record of a message is malformed, e.g. the length of an option exceeds the record
+    #[error("malformed edns: {0}")]
+    MalformedEdns(&'static str),
+
     /// Format error in Message Parsing
     #[error("message format error: {error}")]
     FormError {
@@ -122,6 +128,16 @@ pub enum ProtoErrorKind {
         error: Box<ProtoError>,
     },
 
+    /// A response failed DNSSEC validation
+    #[cfg(feature = "dnssec")]
+    #[error("dnssec validation failed: {error}")]
+    Bogus {
+        /// The error which caused validation to fail
+        error: String,
+        /// The response which failed validation, with all of its records
+        response: Box<DnsResponse>,
+    },
+
     /// An HMAC failed to verify
     #[error("hmac validation failure")]
     HmacInvalid(),
@@ -148,6 +164,10 @@ pub enum ProtoErrorKind {
         ptr: u16,
     },
 
+    /// Too many compression pointers were followed while reading a name
+    #[error("compression pointer chain exceeds {0} jumps")]
+    PointerChainTooLong(usize),
+
     /// The maximum buffer size was exceeded
     #[error("maximum buffer size exceeded: {0}")]
     MaxBufferSizeExceeded(usize),
@@ -355,6 +375,7 @@ impl From<DecodeError> for ProtoError {
             DecodeError::PointerNotPriorToLabel { idx, ptr } => {
                 ProtoErrorKind::PointerNotPriorToLabel { idx, ptr }
             }
+            DecodeError::PointerChainTooLong(max) => ProtoErrorKind::PointerChainTooLong(max),
             DecodeError::LabelBytesTooLong(len) => ProtoErrorKind::LabelBytesTooLong(len),
             DecodeError::UnrecognizedLabelCode(code) => ProtoErrorKind::UnrecognizedLabelCode(code),
             DecodeError::DomainNameTooLong(len) => ProtoErrorKind::DomainNameTooLong(len),
@@ -475,14 +496,24 @@ impl Clone for ProtoErrorKind {
             DnsKeyProtocolNot3(protocol) => DnsKeyProtocolNot3(protocol),
             DomainNameTooLong(len) => DomainNameTooLong(len),
             EdnsNameNotRoot(ref found) => EdnsNameNotRoot(found.clone()),
+            MalformedEdns(msg) => MalformedEdns(msg),
             FormError { header, ref error } => FormError {
                 header,
                 error: error.clone(),
             },
+            #[cfg(feature = "dnssec")]
+            Bogus {
+                ref error,
+                ref response,
+            } => Bogus {
+                error: error.clone(),
+                response: response.clone(),
+            },
             HmacInvalid() => HmacInvalid(),
             IncorrectRDataLengthRead { read, len } => IncorrectRDataLengthRead { read, len },
             LabelBytesTooLong(len) => LabelBytesTooLong(len),
             PointerNotPriorToLabel { idx, ptr } => PointerNotPriorToLabel { idx, ptr },
+            PointerChainTooLong(max) => PointerChainTooLong(max),
             MaxBufferSizeExceeded(max) => MaxBufferSizeExceeded(max),
             Message(msg) => Message(msg),
             Msg(ref msg) => Msg(msg.clone()),
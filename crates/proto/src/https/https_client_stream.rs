@@ -21,6 +21,7 @@ use futures_util::future::{FutureExt, TryFutureExt};
 use futures_util::ready;
 use futures_util::stream::Stream;
 use h2::client::{Connection, SendRequest};
+use h2::RecvStream;
 use http::header::{self, CONTENT_LENGTH};
 use log::{debug, warn};
 use rustls::ClientConfig;
@@ -30,19 +31,27 @@ use tokio_rustls::{
 
 use crate::error::ProtoError;
 use crate::iocompat::AsyncIoStdAsTokio;
+use crate::rustls::spki_pin::verify_spki_pins;
 use crate::tcp::Connect;
 use crate::xfer::{DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream, SerialMessage};
 
 const ALPN_H2: &[u8] = b"h2";
 
+/// Longest path and query sent in a GET request, longer queries are sent with POST
+///
+/// RFC 7230 recommends supporting request lines of at least 8000 octets, this is comfortably within
+///  the limits of most servers and caches.
+const MAX_GET_PATH_AND_QUERY_LEN: usize = 2048;
+
 /// A DNS client connection for DNS-over-HTTPS
 #[derive(Clone)]
 #[must_use = "futures do nothing unless polled"]
 pub struct HttpsClientStream {
     // Corresponds to the dns-name of the HTTPS server
-    name_server_name: Arc<str>,
-    name_server: SocketAddr,
-    h2: SendRequest<Bytes>,
+    pub(super) name_server_name: Arc<str>,
+    pub(super) name_server: SocketAddr,
+    pub(super) h2: SendRequest<Bytes>,
+    use_get: bool,
     is_shutdown: bool,
 }
 
@@ -62,6 +71,8 @@ impl HttpsClientStream {
         message: Bytes,
         name_server_name: Arc<str>,
         name_server: SocketAddr,
+        use_get: bool,
+        keep_buffer: bool,
     ) -> Result<DnsResponse, ProtoError> {
         let mut h2 = match h2.ready().await {
             Ok(h2) => h2,
@@ -71,22 +82,31 @@ impl HttpsClientStream {
             }
         };
 
-        // build up the http request
-        let request = crate::https::request::new(&name_server_name, message.remaining());
+        // build up the http request, GET carries the message in the URI if it fits
+        let use_get = use_get
+            && crate::https::request::get_path_and_query_len(message.remaining())
+                <= MAX_GET_PATH_AND_QUERY_LEN;
+        let request = if use_get {
+            crate::https::request::new_get(&name_server_name, &message)
+        } else {
+            crate::https::request::new(&name_server_name, message.remaining())
+        };
 
         let request =
             request.map_err(|err| ProtoError::from(format!("bad http request: {}", err)))?;
 
         debug!("request: {:#?}", request);
 
-        // Send the request
+        // Send the request, GET requests have no body
         let (response_future, mut send_stream) = h2
-            .send_request(request, false)
+            .send_request(request, use_get)
             .map_err(|err| ProtoError::from(format!("h2 send_request error: {}", err)))?;
 
-        send_stream
-            .send_data(message, true)
-            .map_err(|e| ProtoError::from(format!("h2 send_data error: {}", e)))?;
+        if !use_get {
+            send_stream
+                .send_data(message, true)
+                .map_err(|e| ProtoError::from(format!("h2 send_data error: {}", e)))?;
+        }
 
         let mut response_stream = response_future
             .await
@@ -94,49 +114,7 @@ impl HttpsClientStream {
 
         debug!("got response: {:#?}", response_stream);
 
-        // get the length of packet
-        let content_length = response_stream
-            .headers()
-            .get(CONTENT_LENGTH)
-            .map(|v| v.to_str())
-            .transpose()
-            .map_err(|e| ProtoError::from(format!("bad headers received: {}", e)))?
-            .map(usize::from_str)
-            .transpose()
-            .map_err(|e| ProtoError::from(format!("bad headers received: {}", e)))?;
-
-        // TODO: what is a good max here?
-        // clamp(512, 4096) says make sure it is at least 512 bytes, and min 4096 says it is at most 4k
-        // just a little protection from malicious actors.
-        let mut response_bytes =
-            BytesMut::with_capacity(content_length.unwrap_or(512).clamp(512, 4096));
-
-        while let Some(partial_bytes) = response_stream.body_mut().data().await {
-            let partial_bytes =
-                partial_bytes.map_err(|e| ProtoError::from(format!("bad http request: {}", e)))?;
-
-            debug!("got bytes: {}", partial_bytes.len());
-            response_bytes.extend(partial_bytes);
-
-            // assert the length
-            if let Some(content_length) = content_length {
-                if response_bytes.len() >= content_length {
-                    break;
-                }
-            }
-        }
-
-        // assert the length
-        if let Some(content_length) = content_length {
-            if response_bytes.len() != content_length {
-                // TODO: make explicit error type
-                return Err(ProtoError::from(format!(
-                    "expected byte length: {}, got: {}",
-                    content_length,
-                    response_bytes.len()
-                )));
-            }
-        }
+        let response_bytes = read_body(&mut response_stream).await?;
 
         // Was it a successful request?
         if !response_stream.status().is_success() {
@@ -174,11 +152,67 @@ impl HttpsClientStream {
         };
 
         // and finally convert the bytes into a DNS message
-        let message = SerialMessage::new(response_bytes.to_vec(), name_server).to_message()?;
-        Ok(message.into())
+        let message = SerialMessage::new(response_bytes.to_vec(), name_server);
+        let mut response = DnsResponse::from(message.to_message()?);
+        if keep_buffer {
+            response = response.with_buffer(message.into_parts().0);
+        }
+        response.set_server_addr(Some(name_server));
+        Ok(response)
     }
 }
 
+/// Reads the body of an HTTP response, verifying the length if the content-length is known
+pub(crate) async fn read_body(
+    response_stream: &mut http::Response<RecvStream>,
+) -> Result<BytesMut, ProtoError> {
+    // get the length of packet
+    let content_length = response_stream
+        .headers()
+        .get(CONTENT_LENGTH)
+        .map(|v| v.to_str())
+        .transpose()
+        .map_err(|e| ProtoError::from(format!("bad headers received: {}", e)))?
+        .map(usize::from_str)
+        .transpose()
+        .map_err(|e| ProtoError::from(format!("bad headers received: {}", e)))?;
+
+    // TODO: what is a good max here?
+    // clamp(512, 4096) says make sure it is at least 512 bytes, and min 4096 says it is at most 4k
+    // just a little protection from malicious actors.
+    let mut response_bytes =
+        BytesMut::with_capacity(content_length.unwrap_or(512).clamp(512, 4096));
+
+    while let Some(partial_bytes) = response_stream.body_mut().data().await {
+        let partial_bytes =
+            partial_bytes.map_err(|e| ProtoError::from(format!("bad http request: {}", e)))?;
+
+        debug!("got bytes: {}", partial_bytes.len());
+        response_bytes.extend(partial_bytes);
+
+        // assert the length
+        if let Some(content_length) = content_length {
+            if response_bytes.len() >= content_length {
+                break;
+            }
+        }
+    }
+
+    // assert the length
+    if let Some(content_length) = content_length {
+        if response_bytes.len() != content_length {
+            // TODO: make explicit error type
+            return Err(ProtoError::from(format!(
+                "expected byte length: {}, got: {}",
+                content_length,
+                response_bytes.len()
+            )));
+        }
+    }
+
+    Ok(response_bytes)
+}
+
 impl DnsRequestSender for HttpsClientStream {
     /// This indicates that the HTTP message was successfully sent, and we now have the response.RecvStream
     ///
@@ -235,7 +269,7 @@ impl DnsRequestSender for HttpsClientStream {
         // per the RFC, a zero id allows for the HTTP packet to be cached better
         message.set_id(0);
 
-        let bytes = match message.to_vec() {
+        let bytes = match message.to_wire() {
             Ok(bytes) => bytes,
             Err(err) => return err.into(),
         };
@@ -245,6 +279,8 @@ impl DnsRequestSender for HttpsClientStream {
             Bytes::from(bytes),
             Arc::clone(&self.name_server_name),
             self.name_server,
+            self.use_get,
+            message.is_raw(),
         ))
         .into()
     }
@@ -283,6 +319,8 @@ impl Stream for HttpsClientStream {
 pub struct HttpsClientStreamBuilder {
     client_config: Arc<ClientConfig>,
     bind_addr: Option<SocketAddr>,
+    use_get: bool,
+    spki_pins: Vec<[u8; 32]>,
 }
 
 impl HttpsClientStreamBuilder {
@@ -291,6 +329,8 @@ impl HttpsClientStreamBuilder {
         Self {
             client_config,
             bind_addr: None,
+            use_get: false,
+            spki_pins: Vec::new(),
         }
     }
 
@@ -299,6 +339,26 @@ impl HttpsClientStreamBuilder {
         self.bind_addr = Some(bind_addr);
     }
 
+    /// Send queries with GET, and the message base64url encoded in the `dns` variable of the URI
+    ///
+    /// This is friendlier to HTTP caches than the default of POST. Queries too large to fit in the
+    ///  URI are still sent with POST. Set the id of the message to 0 for the best caching, which
+    ///  `HttpsClientStream` does for all messages.
+    pub fn use_get(&mut self, use_get: bool) {
+        self.use_get = use_get;
+    }
+
+    /// Only accept a certificate with one of the public keys, given as SHA-256 digests of the SubjectPublicKeyInfo
+    ///
+    /// The pins are checked after the handshake, in addition to the validation of the certificate by the
+    ///  `ClientConfig`, see [`spki_sha256`] for computing them and [`tls_connect_with_spki_pins`] for details.
+    ///
+    /// [`spki_sha256`]: crate::rustls::spki_sha256
+    /// [`tls_connect_with_spki_pins`]: crate::rustls::tls_connect_with_spki_pins
+    pub fn pin_spki(&mut self, sha256_digests: Vec<[u8; 32]>) {
+        self.spki_pins = sha256_digests;
+    }
+
     /// Creates a new HttpsStream to the specified name_server
     ///
     /// # Arguments
@@ -321,6 +381,8 @@ impl HttpsClientStreamBuilder {
         let tls = TlsConfig {
             client_config: self.client_config,
             dns_name: Arc::from(dns_name),
+            use_get: self.use_get,
+            spki_pins: self.spki_pins,
         };
 
         HttpsClientConnect::<S>(HttpsClientConnectState::ConnectTcp {
@@ -350,6 +412,8 @@ where
 struct TlsConfig {
     client_config: Arc<ClientConfig>,
     dns_name: Arc<str>,
+    use_get: bool,
+    spki_pins: Vec<[u8; 32]>,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -373,6 +437,8 @@ where
         tls: TokioTlsConnect<AsyncIoStdAsTokio<S>>,
         name_server_name: Arc<str>,
         name_server: SocketAddr,
+        use_get: bool,
+        spki_pins: Vec<[u8; 32]>,
     },
     H2Handshake {
         handshake: Pin<
@@ -390,6 +456,7 @@ where
         >,
         name_server_name: Arc<str>,
         name_server: SocketAddr,
+        use_get: bool,
     },
     Connected(Option<HttpsClientStream>),
     Errored(Option<ProtoError>),
@@ -429,6 +496,8 @@ where
                         .take()
                         .expect("programming error, tls should not be None here");
                     let name_server_name = Arc::clone(&tls.dns_name);
+                    let use_get = tls.use_get;
+                    let spki_pins = tls.spki_pins;
 
                     match tls.dns_name.as_ref().try_into() {
                         Ok(dns_name) => {
@@ -438,6 +507,8 @@ where
                                 name_server_name,
                                 name_server,
                                 tls,
+                                use_get,
+                                spki_pins,
                             }
                         }
                         Err(_) => Self::Errored(Some(ProtoError::from(format!(
@@ -450,8 +521,11 @@ where
                     ref name_server_name,
                     name_server,
                     ref mut tls,
+                    use_get,
+                    ref spki_pins,
                 } => {
                     let tls = ready!(tls.poll_unpin(cx))?;
+                    verify_spki_pins(spki_pins, tls.get_ref().1.peer_certificates())?;
                     debug!("tls connection established to: {}", name_server);
                     let mut handshake = h2::client::Builder::new();
                     handshake.enable_push(false);
@@ -461,12 +535,14 @@ where
                         name_server_name: Arc::clone(name_server_name),
                         name_server,
                         handshake: Box::pin(handshake),
+                        use_get,
                     }
                 }
                 HttpsClientConnectState::H2Handshake {
                     ref name_server_name,
                     name_server,
                     ref mut handshake,
+                    use_get,
                 } => {
                     let (send_request, connection) = ready!(handshake
                         .poll_unpin(cx)
@@ -484,6 +560,7 @@ where
                         name_server_name: Arc::clone(name_server_name),
                         name_server,
                         h2: send_request,
+                        use_get,
                         is_shutdown: false,
                     }))
                 }
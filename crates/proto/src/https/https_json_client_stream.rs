@@ -0,0 +1,579 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! A client for the JSON DNS-over-HTTPS APIs of Google and Cloudflare
+//!
+//! This is not [RFC 8484](https://tools.ietf.org/html/rfc8484), but the `application/dns-json` format
+//!  documented by [Google](https://developers.google.com/speed/public-dns/docs/doh/json) and
+//!  [Cloudflare](https://developers.cloudflare.com/1.1.1.1/encryption/dns-over-https/make-api-requests/dns-json/).
+//!  Prefer `HttpsClientStream` where the wire format endpoints are reachable.
+
+use std::fmt::{self, Display};
+use std::future::Future;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use futures_util::ready;
+use futures_util::stream::Stream;
+use h2::client::SendRequest;
+use http::header::{self, ACCEPT};
+use http::{uri, Request, Version};
+use log::debug;
+use serde::Deserialize;
+
+use crate::error::ProtoError;
+use crate::https::{HttpsClientConnect, HttpsClientStream, HttpsClientStreamBuilder};
+use crate::op::{Message, MessageType, OpCode, Query};
+use crate::rr::rdata::{MX, SOA, SRV, TXT, URI};
+use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+use crate::tcp::Connect;
+use crate::xfer::{DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream};
+
+const MIME_APPLICATION_DNS_JSON: &str = "application/dns-json";
+
+/// A DNS client connection for the JSON DNS-over-HTTPS APIs
+///
+/// Only queries are supported, with exactly one question. The records in the response are decoded from their
+///  text format, an error is returned for any record type that can not be decoded.
+#[derive(Clone)]
+#[must_use = "futures do nothing unless polled"]
+pub struct HttpsJsonClientStream {
+    // Corresponds to the dns-name of the HTTPS server
+    name_server_name: Arc<str>,
+    name_server: SocketAddr,
+    path: Arc<str>,
+    h2: SendRequest<Bytes>,
+    is_shutdown: bool,
+}
+
+impl Display for HttpsJsonClientStream {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            formatter,
+            "HTTPS-JSON({},{})",
+            self.name_server, self.name_server_name
+        )
+    }
+}
+
+impl HttpsJsonClientStream {
+    async fn inner_send(
+        h2: SendRequest<Bytes>,
+        message: Message,
+        name_server_name: Arc<str>,
+        path: Arc<str>,
+    ) -> Result<DnsResponse, ProtoError> {
+        let mut h2 = match h2.ready().await {
+            Ok(h2) => h2,
+            Err(err) => {
+                // TODO: make specific error
+                return Err(ProtoError::from(format!("h2 send_request error: {}", err)));
+            }
+        };
+
+        let request = json_request(&name_server_name, &path, &message)?;
+        debug!("request: {:#?}", request);
+
+        let (response_future, _) = h2
+            .send_request(request, true)
+            .map_err(|err| ProtoError::from(format!("h2 send_request error: {}", err)))?;
+
+        let mut response_stream = response_future
+            .await
+            .map_err(|err| ProtoError::from(format!("received a stream error: {}", err)))?;
+
+        debug!("got response: {:#?}", response_stream);
+        let response_bytes =
+            crate::https::https_client_stream::read_body(&mut response_stream).await?;
+
+        if !response_stream.status().is_success() {
+            let error_string = String::from_utf8_lossy(response_bytes.as_ref());
+
+            // TODO: make explicit error type
+            return Err(ProtoError::from(format!(
+                "http unsuccessful code: {}, message: {}",
+                response_stream.status(),
+                error_string
+            )));
+        }
+
+        // Google responds with application/json, Cloudflare with application/dns-json
+        if let Some(content_type) = response_stream.headers().get(header::CONTENT_TYPE) {
+            let content_type = content_type.to_str().map_err(|err| {
+                ProtoError::from(format!("ContentType header not a string: {}", err))
+            })?;
+
+            if !content_type.contains("json") {
+                return Err(ProtoError::from(format!(
+                    "ContentType unsupported (must be '{}'): '{}'",
+                    MIME_APPLICATION_DNS_JSON, content_type
+                )));
+            }
+        }
+
+        let mut response = message_from_json(&response_bytes)?;
+        response.set_id(message.id());
+        Ok(response.into())
+    }
+}
+
+impl DnsRequestSender for HttpsJsonClientStream {
+    fn send_message(&mut self, message: DnsRequest) -> DnsResponseStream {
+        if self.is_shutdown {
+            panic!("can not send messages after stream is shutdown")
+        }
+
+        let (message, _) = message.into_parts();
+
+        Box::pin(Self::inner_send(
+            self.h2.clone(),
+            message,
+            Arc::clone(&self.name_server_name),
+            Arc::clone(&self.path),
+        ))
+        .into()
+    }
+
+    fn shutdown(&mut self) {
+        self.is_shutdown = true;
+    }
+
+    fn is_shutdown(&self) -> bool {
+        self.is_shutdown
+    }
+}
+
+impl Stream for HttpsJsonClientStream {
+    type Item = Result<(), ProtoError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        if self.is_shutdown {
+            return Poll::Ready(None);
+        }
+
+        // just checking if the connection is ok
+        match self.h2.poll_ready(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(Some(Ok(()))),
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(ProtoError::from(format!(
+                "h2 stream errored: {}",
+                e
+            ))))),
+        }
+    }
+}
+
+impl HttpsClientStreamBuilder {
+    /// Creates a new HttpsJsonClientStream to the specified name_server
+    ///
+    /// # Arguments
+    ///
+    /// * `name_server` - IP and Port for the remote DNS resolver
+    /// * `dns_name` - The DNS name, Subject Public Key Info (SPKI) name, as associated to a certificate
+    /// * `path` - The path of the JSON API, `/resolve` for Google and `/dns-query` for Cloudflare
+    pub fn build_json<S: Connect>(
+        self,
+        name_server: SocketAddr,
+        dns_name: String,
+        path: &str,
+    ) -> HttpsJsonClientConnect<S> {
+        HttpsJsonClientConnect {
+            connect: self.build(name_server, dns_name),
+            path: Arc::from(path),
+        }
+    }
+}
+
+/// A future that resolves to an HttpsJsonClientStream
+pub struct HttpsJsonClientConnect<S>
+where
+    S: Connect,
+{
+    connect: HttpsClientConnect<S>,
+    path: Arc<str>,
+}
+
+impl<S> Future for HttpsJsonClientConnect<S>
+where
+    S: Connect,
+{
+    type Output = Result<HttpsJsonClientStream, ProtoError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let HttpsClientStream {
+            name_server_name,
+            name_server,
+            h2,
+            ..
+        } = ready!(Pin::new(&mut self.connect).poll(cx))?;
+
+        Poll::Ready(Ok(HttpsJsonClientStream {
+            name_server_name,
+            name_server,
+            path: Arc::clone(&self.path),
+            h2,
+            is_shutdown: false,
+        }))
+    }
+}
+
+/// Builds the GET request for the single query of `message`
+fn json_request(
+    name_server_name: &str,
+    path: &str,
+    message: &Message,
+) -> Result<Request<()>, ProtoError> {
+    if message.op_code() != OpCode::Query || message.queries().len() != 1 {
+        return Err("only queries with a single question are supported over JSON".into());
+    }
+    let query = &message.queries()[0];
+
+    let mut params = url::form_urlencoded::Serializer::new(String::new());
+    params
+        .append_pair("name", &query.name().to_ascii())
+        .append_pair("type", &u16::from(query.query_type()).to_string());
+    if message.checking_disabled() {
+        params.append_pair("cd", "1");
+    }
+    if matches!(message.extensions(), Some(edns) if edns.dnssec_ok()) {
+        params.append_pair("do", "1");
+    }
+
+    let path_and_query = uri::PathAndQuery::from_str(&format!("{}?{}", path, params.finish()))
+        .map_err(|e| ProtoError::from(format!("invalid query: {}", e)))?;
+    let url = crate::https::request::uri(name_server_name, path_and_query)
+        .map_err(|e| ProtoError::from(e.to_string()))?;
+
+    Request::builder()
+        .method("GET")
+        .uri(url)
+        .version(Version::HTTP_2)
+        .header(ACCEPT, MIME_APPLICATION_DNS_JSON)
+        .body(())
+        .map_err(|e| ProtoError::from(format!("h2 stream errored: {}", e)))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JsonResponse {
+    status: u16,
+    #[serde(rename = "TC", default)]
+    tc: bool,
+    #[serde(rename = "RD", default)]
+    rd: bool,
+    #[serde(rename = "RA", default)]
+    ra: bool,
+    #[serde(rename = "AD", default)]
+    ad: bool,
+    #[serde(rename = "CD", default)]
+    cd: bool,
+    #[serde(default)]
+    question: Vec<JsonQuestion>,
+    #[serde(default)]
+    answer: Vec<JsonRecord>,
+    #[serde(default)]
+    authority: Vec<JsonRecord>,
+    #[serde(default)]
+    additional: Vec<JsonRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonQuestion {
+    name: String,
+    #[serde(rename = "type")]
+    query_type: u16,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRecord {
+    name: String,
+    #[serde(rename = "type")]
+    rr_type: u16,
+    #[serde(rename = "TTL", default)]
+    ttl: u32,
+    data: String,
+}
+
+/// Converts the JSON response into a Message
+fn message_from_json(bytes: &[u8]) -> Result<Message, ProtoError> {
+    let response: JsonResponse = serde_json::from_slice(bytes)
+        .map_err(|e| ProtoError::from(format!("bad JSON response: {}", e)))?;
+
+    let mut message = Message::new();
+    message
+        .set_message_type(MessageType::Response)
+        .set_op_code(OpCode::Query)
+        .set_response_code(response.status.into())
+        .set_truncated(response.tc)
+        .set_recursion_desired(response.rd)
+        .set_recursion_available(response.ra)
+        .set_authentic_data(response.ad)
+        .set_checking_disabled(response.cd);
+
+    for question in response.question {
+        message.add_query(Query::query(
+            Name::from_str(&question.name)?,
+            RecordType::from(question.query_type),
+        ));
+    }
+
+    for record in response.answer {
+        message.add_answer(record.into_record()?);
+    }
+    for record in response.authority {
+        message.add_name_server(record.into_record()?);
+    }
+    for record in response.additional {
+        message.add_additional(record.into_record()?);
+    }
+
+    Ok(message)
+}
+
+impl JsonRecord {
+    fn into_record(self) -> Result<Record, ProtoError> {
+        let name = Name::from_str(&self.name)?;
+        let rr_type = RecordType::from(self.rr_type);
+        let rdata = rdata_from_text(rr_type, &self.data)?;
+
+        let mut record = Record::from_rdata(name, self.ttl, rdata);
+        record.set_dns_class(DNSClass::IN);
+        Ok(record)
+    }
+}
+
+/// Decodes the text form of the data of a record, as found in the `data` field
+fn rdata_from_text(rr_type: RecordType, data: &str) -> Result<RData, ProtoError> {
+    let mut tokens = data.split_whitespace();
+    let mut next = |field: &str| {
+        tokens.next().ok_or_else(|| {
+            ProtoError::from(format!("missing {} in {} data: {}", field, rr_type, data))
+        })
+    };
+
+    let name = |s: &str| Name::from_str(s);
+    let number = |s: &str| {
+        s.parse::<u32>()
+            .map_err(|e| ProtoError::from(format!("bad number in {} data: {}", rr_type, e)))
+    };
+    let short = |s: &str| {
+        s.parse::<u16>()
+            .map_err(|e| ProtoError::from(format!("bad number in {} data: {}", rr_type, e)))
+    };
+
+    let rdata = match rr_type {
+        RecordType::A => RData::A(
+            Ipv4Addr::from_str(data.trim())
+                .map_err(|e| ProtoError::from(format!("bad A data: {}", e)))?,
+        ),
+        RecordType::AAAA => RData::AAAA(
+            Ipv6Addr::from_str(data.trim())
+                .map_err(|e| ProtoError::from(format!("bad AAAA data: {}", e)))?,
+        ),
+        RecordType::ANAME => RData::ANAME(name(next("name")?)?),
+        RecordType::CNAME => RData::CNAME(name(next("name")?)?),
+        RecordType::NS => RData::NS(name(next("name")?)?),
+        RecordType::PTR => RData::PTR(name(next("name")?)?),
+        RecordType::MX => {
+            let preference = short(next("preference")?)?;
+            RData::MX(MX::new(preference, name(next("exchange")?)?))
+        }
+        RecordType::SRV => {
+            let priority = short(next("priority")?)?;
+            let weight = short(next("weight")?)?;
+            let port = short(next("port")?)?;
+            RData::SRV(SRV::new(priority, weight, port, name(next("target")?)?))
+        }
+        RecordType::SOA => {
+            let mname = name(next("mname")?)?;
+            let rname = name(next("rname")?)?;
+            let serial = number(next("serial")?)?;
+            let refresh = number(next("refresh")?)? as i32;
+            let retry = number(next("retry")?)? as i32;
+            let expire = number(next("expire")?)? as i32;
+            let minimum = number(next("minimum")?)?;
+            RData::SOA(SOA::new(
+                mname, rname, serial, refresh, retry, expire, minimum,
+            ))
+        }
+        RecordType::TXT => RData::TXT(TXT::new(txt_strings(data)?)),
+        RecordType::URI => {
+            let priority = short(next("priority")?)?;
+            let weight = short(next("weight")?)?;
+            let target = next("target")?.trim_matches('"').to_string();
+            RData::URI(URI::new(priority, weight, target)?)
+        }
+        _ => {
+            return Err(ProtoError::from(format!(
+                "unsupported record type in JSON response: {}",
+                rr_type
+            )))
+        }
+    };
+
+    Ok(rdata)
+}
+
+/// Google returns TXT data unquoted, Cloudflare as quoted character-strings
+fn txt_strings(data: &str) -> Result<Vec<String>, ProtoError> {
+    let data = data.trim();
+    if !data.starts_with('"') {
+        return Ok(vec![data.to_string()]);
+    }
+
+    let mut strings = Vec::new();
+    let mut chars = data.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let mut string = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped) => string.push(escaped),
+                            None => return Err(format!("bad escape in TXT data: {}", data).into()),
+                        },
+                        Some(c) => string.push(c),
+                        None => return Err(format!("unterminated TXT data: {}", data).into()),
+                    }
+                }
+                strings.push(string);
+            }
+            c if c.is_whitespace() => continue,
+            _ => return Err(format!("bad TXT data: {}", data).into()),
+        }
+    }
+
+    Ok(strings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::op::{Edns, ResponseCode};
+
+    #[test]
+    fn test_json_request() {
+        let mut message = Message::new();
+        message
+            .add_query(Query::query(
+                Name::from_ascii("www.example.com.").unwrap(),
+                RecordType::AAAA,
+            ))
+            .set_edns({
+                let mut edns = Edns::new();
+                edns.set_dnssec_ok(true);
+                edns
+            });
+
+        let request = json_request("dns.google", "/resolve", &message).unwrap();
+        assert_eq!(request.method(), http::Method::GET);
+        assert_eq!(
+            request.uri().to_string(),
+            "https://dns.google/resolve?name=www.example.com.&type=28&do=1"
+        );
+        assert_eq!(
+            request.headers().get(ACCEPT).unwrap(),
+            MIME_APPLICATION_DNS_JSON
+        );
+
+        message.add_query(Query::new());
+        assert!(json_request("dns.google", "/resolve", &message).is_err());
+    }
+
+    #[test]
+    fn test_message_from_json() {
+        let json = r#"{
+            "Status": 0, "TC": false, "RD": true, "RA": true, "AD": false, "CD": false,
+            "Question": [{ "name": "www.example.com.", "type": 1 }],
+            "Answer": [
+                { "name": "www.example.com.", "type": 5, "TTL": 300, "data": "example.com." },
+                { "name": "example.com.", "type": 1, "TTL": 3600, "data": "93.184.216.34" }
+            ],
+            "Comment": "Response from 199.43.135.53."
+        }"#;
+
+        let message = message_from_json(json.as_bytes()).unwrap();
+        assert_eq!(message.response_code(), ResponseCode::NoError);
+        assert!(message.recursion_available());
+        assert_eq!(message.queries()[0].query_type(), RecordType::A);
+        assert_eq!(message.answers().len(), 2);
+        assert_eq!(
+            message.answers()[0].data(),
+            Some(&RData::CNAME(Name::from_ascii("example.com.").unwrap()))
+        );
+        assert_eq!(message.answers()[1].ttl(), 3600);
+        assert_eq!(
+            message.answers()[1].data(),
+            Some(&RData::A(Ipv4Addr::new(93, 184, 216, 34)))
+        );
+    }
+
+    #[test]
+    fn test_message_from_json_nxdomain() {
+        let json = r#"{
+            "Status": 3, "TC": false, "RD": true, "RA": true, "AD": true, "CD": false,
+            "Question": [{ "name": "nx.example.com.", "type": 1 }],
+            "Authority": [{
+                "name": "example.com.", "type": 6, "TTL": 3600,
+                "data": "ns.icann.org. noc.dns.icann.org. 2021120738 7200 3600 1209600 3600"
+            }]
+        }"#;
+
+        let message = message_from_json(json.as_bytes()).unwrap();
+        assert_eq!(message.response_code(), ResponseCode::NXDomain);
+        assert!(message.authentic_data());
+
+        let soa = message.name_servers()[0].data().unwrap().as_soa().unwrap();
+        assert_eq!(soa.serial(), 2021120738);
+        assert_eq!(soa.minimum(), 3600);
+    }
+
+    #[test]
+    fn test_rdata_from_text() {
+        assert_eq!(
+            rdata_from_text(RecordType::MX, "10 mail.example.com.").unwrap(),
+            RData::MX(MX::new(10, Name::from_ascii("mail.example.com.").unwrap()))
+        );
+        assert_eq!(
+            rdata_from_text(RecordType::SRV, "1 2 443 www.example.com.").unwrap(),
+            RData::SRV(SRV::new(
+                1,
+                2,
+                443,
+                Name::from_ascii("www.example.com.").unwrap()
+            ))
+        );
+
+        // both styles of TXT data
+        assert_eq!(
+            rdata_from_text(RecordType::TXT, "v=spf1 -all").unwrap(),
+            RData::TXT(TXT::new(vec!["v=spf1 -all".to_string()]))
+        );
+        assert_eq!(
+            rdata_from_text(RecordType::TXT, r#""v=spf1 -all" "two \"quoted\"""#).unwrap(),
+            RData::TXT(TXT::new(vec![
+                "v=spf1 -all".to_string(),
+                "two \"quoted\"".to_string()
+            ]))
+        );
+
+        assert_eq!(
+            rdata_from_text(RecordType::URI, r#"10 1 "ftp://ftp.example.com/public""#).unwrap(),
+            RData::URI(URI::new(10, 1, "ftp://ftp.example.com/public".to_string()).unwrap())
+        );
+
+        assert!(rdata_from_text(RecordType::MX, "10").is_err());
+        assert!(rdata_from_text(RecordType::A, "not an address").is_err());
+        assert!(rdata_from_text(RecordType::SSHFP, "1 1 ABCDEF").is_err());
+    }
+}
@@ -49,7 +49,8 @@ where
     }
 
     match *request.method() {
-        Method::GET => Err(format!("GET unimplemented: {}", request.method()).into()),
+        Method::GET => crate::https::request::query_message(request.uri())
+            .map(|message| BytesMut::from(&message as &[u8])),
         Method::POST => message_from_post(request.into_body(), content_length).await,
         _ => Err(format!("bad method: {}", request.method()).into()),
     }
@@ -136,4 +137,21 @@ mod tests {
         let msg_from_post = Message::from_vec(bytes.as_ref()).expect("bytes failed");
         assert_eq!(message, msg_from_post);
     }
+
+    #[test]
+    fn test_from_get() {
+        let message = Message::new();
+        let msg_bytes = message.to_vec().unwrap();
+        let request = request::new_get("ns.example.com", &msg_bytes).unwrap();
+        let request = request.map(|()| TestBytesStream(vec![]));
+
+        let from_get = message_from(Arc::from("ns.example.com"), request);
+        let bytes = match block_on(from_get) {
+            Ok(bytes) => bytes,
+            e => panic!("{:#?}", e),
+        };
+
+        let msg_from_get = Message::from_vec(bytes.as_ref()).expect("bytes failed");
+        assert_eq!(message, msg_from_get);
+    }
 }
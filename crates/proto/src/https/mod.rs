@@ -12,6 +12,9 @@ const DNS_QUERY_PATH: &str = "/dns-query";
 
 mod error;
 mod https_client_stream;
+#[cfg(feature = "dns-over-https-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dns-over-https-json")))]
+mod https_json_client_stream;
 pub mod https_server;
 pub mod request;
 pub mod response;
@@ -21,3 +24,6 @@ pub use self::error::{Error as HttpsError, Result as HttpsResult};
 pub use self::https_client_stream::{
     HttpsClientConnect, HttpsClientResponse, HttpsClientStream, HttpsClientStreamBuilder,
 };
+#[cfg(feature = "dns-over-https-json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dns-over-https-json")))]
+pub use self::https_json_client_stream::{HttpsJsonClientConnect, HttpsJsonClientStream};
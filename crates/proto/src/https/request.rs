@@ -9,8 +9,9 @@
 
 use std::str::FromStr;
 
+use data_encoding::BASE64URL_NOPAD;
 use http::header::{ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
-use http::{header, uri, Request, Uri, Version};
+use http::{header, uri, Method, Request, Uri, Version};
 use log::debug;
 
 use crate::error::ProtoError;
@@ -26,30 +27,11 @@ use crate::https::HttpsResult;
 /// request (as described in Section 7), encoded with base64url
 /// [RFC4648].
 /// ```
-#[allow(clippy::field_reassign_with_default)] // https://github.com/rust-lang/rust-clippy/issues/6527
 pub fn new(name_server_name: &str, message_len: usize) -> HttpsResult<Request<()>> {
-    // TODO: this is basically the GET version, but it is more expensive than POST
-    //   perhaps add an option if people want better HTTP caching options.
-
-    // let query = BASE64URL_NOPAD.encode(&message);
-    // let url = format!("/dns-query?dns={}", query);
-    // let request = Request::get(&url)
-    //     .header(header::CONTENT_TYPE, ::MIME_DNS_BINARY)
-    //     .header(header::HOST, &self.name_server_name as &str)
-    //     .header("authority", &self.name_server_name as &str)
-    //     .header(header::USER_AGENT, USER_AGENT)
-    //     .body(());
-
-    let mut parts = uri::Parts::default();
-    parts.path_and_query = Some(uri::PathAndQuery::from_static(crate::https::DNS_QUERY_PATH));
-    parts.scheme = Some(uri::Scheme::HTTPS);
-    parts.authority = Some(
-        uri::Authority::from_str(name_server_name)
-            .map_err(|e| ProtoError::from(format!("invalid authority: {}", e)))?,
-    );
-
-    let url =
-        Uri::from_parts(parts).map_err(|e| ProtoError::from(format!("uri parse error: {}", e)))?;
+    let url = uri(
+        name_server_name,
+        uri::PathAndQuery::from_static(crate::https::DNS_QUERY_PATH),
+    )?;
 
     // TODO: add user agent to TypedHeaders
     let request = Request::builder()
@@ -65,6 +47,74 @@ pub fn new(name_server_name: &str, message_len: usize) -> HttpsResult<Request<()
     Ok(request)
 }
 
+/// Create a new GET Request for an http/2 dns-message request, the message is carried in the URI
+///
+/// ```text
+/// https://tools.ietf.org/html/rfc8484#section-4.1
+/// When the HTTP method is GET, the single variable "dns" is defined as
+/// the content of the DNS request (as described in Section 6), encoded
+/// with base64url [RFC4648].
+///
+/// In order to maximize HTTP cache friendliness, DoH clients using media
+/// formats that include the ID field from the DNS message header, such
+/// as "application/dns-message", SHOULD use a DNS ID of 0 in every DNS
+/// request.
+/// ```
+///
+/// Padding characters for base64url MUST NOT be included, per section 6 of the RFC.
+pub fn new_get(name_server_name: &str, message: &[u8]) -> HttpsResult<Request<()>> {
+    let path_and_query = format!(
+        "{}?dns={}",
+        crate::https::DNS_QUERY_PATH,
+        BASE64URL_NOPAD.encode(message)
+    );
+    let path_and_query = uri::PathAndQuery::from_str(&path_and_query)
+        .map_err(|e| ProtoError::from(format!("invalid query: {}", e)))?;
+    let url = uri(name_server_name, path_and_query)?;
+
+    // TODO: add user agent to TypedHeaders
+    let request = Request::builder()
+        .method("GET")
+        .uri(url)
+        .version(Version::HTTP_2)
+        .header(ACCEPT, crate::https::MIME_APPLICATION_DNS)
+        .body(())
+        .map_err(|e| ProtoError::from(format!("h2 stream errored: {}", e)))?;
+
+    Ok(request)
+}
+
+/// The length of the path and query of a GET request for a message of `message_len` bytes
+pub(crate) fn get_path_and_query_len(message_len: usize) -> usize {
+    crate::https::DNS_QUERY_PATH.len() + "?dns=".len() + BASE64URL_NOPAD.encode_len(message_len)
+}
+
+#[allow(clippy::field_reassign_with_default)] // https://github.com/rust-lang/rust-clippy/issues/6527
+pub(crate) fn uri(name_server_name: &str, path_and_query: uri::PathAndQuery) -> HttpsResult<Uri> {
+    let mut parts = uri::Parts::default();
+    parts.path_and_query = Some(path_and_query);
+    parts.scheme = Some(uri::Scheme::HTTPS);
+    parts.authority = Some(
+        uri::Authority::from_str(name_server_name)
+            .map_err(|e| ProtoError::from(format!("invalid authority: {}", e)))?,
+    );
+
+    Uri::from_parts(parts).map_err(|e| ProtoError::from(format!("uri parse error: {}", e)).into())
+}
+
+/// Returns the base64url decoded `dns` variable from the query of a GET request
+pub fn query_message(uri: &Uri) -> HttpsResult<Vec<u8>> {
+    let query = uri.query().ok_or("no query in GET request")?;
+    let dns = query
+        .split('&')
+        .find_map(|param| param.strip_prefix("dns="))
+        .ok_or("no dns variable in GET request")?;
+
+    BASE64URL_NOPAD
+        .decode(dns.as_bytes())
+        .map_err(|e| ProtoError::from(format!("bad base64url in dns variable: {}", e)).into())
+}
+
 /// Verifies the request is something we know what to deal with
 pub fn verify<T>(name_server: &str, request: &Request<T>) -> HttpsResult<()> {
     // Verify all HTTP parameters
@@ -94,9 +144,11 @@ pub fn verify<T>(name_server: &str, request: &Request<T>) -> HttpsResult<()> {
         return Err("no authority in HTTPS request".into());
     }
 
+    // GET requests have no body, and so no content type
     // TODO: switch to mime::APPLICATION_DNS when that stabilizes
     match request.headers().get(CONTENT_TYPE).map(|v| v.to_str()) {
         Some(Ok(ctype)) if ctype == crate::https::MIME_APPLICATION_DNS => {}
+        None if request.method() == Method::GET => {}
         _ => return Err("unsupported content type".into()),
     };
 
@@ -152,4 +204,31 @@ mod tests {
         let request = new("ns.example.com", 512).expect("error converting to http");
         assert!(verify("ns.example.com", &request).is_ok());
     }
+
+    #[test]
+    fn test_new_get_verify() {
+        use crate::op::{Edns, Message, PaddingPolicy};
+
+        let mut message = Message::new();
+        message.set_edns(Edns::new());
+        message.pad(PaddingPolicy::Block(128)).unwrap();
+        let message = message.to_vec().unwrap();
+
+        let request = new_get("ns.example.com", &message).expect("error converting to http");
+        assert!(verify("ns.example.com", &request).is_ok());
+        assert_eq!(request.method(), Method::GET);
+
+        // no base64url padding, 128 bytes would otherwise be padded
+        let query = request.uri().query().unwrap();
+        assert_eq!(query.matches('=').count(), 1);
+        assert_eq!(
+            request.uri().path_and_query().unwrap().as_str().len(),
+            get_path_and_query_len(message.len())
+        );
+
+        // the padded message is carried intact
+        let decoded = query_message(request.uri()).unwrap();
+        assert_eq!(decoded.len(), 128);
+        assert_eq!(decoded, message);
+    }
 }
@@ -0,0 +1,358 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS Stateful Operations, [RFC 8490](https://tools.ietf.org/html/rfc8490), for long-lived TCP and TLS sessions
+
+use std::convert::TryFrom;
+use std::time::Duration;
+
+use crate::error::*;
+use crate::op::{Header, MessageType, OpCode, ResponseCode};
+use crate::serialize::binary::*;
+
+/// The keepalive interval must not be shorter than ten seconds
+pub const MIN_KEEPALIVE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The value of a timeout which never expires
+const INFINITE: u32 = 0xFFFF_FFFF;
+
+/// A DSO message, the DNS header with all counts zero, followed by the TLVs
+///
+/// [RFC 8490](https://tools.ietf.org/html/rfc8490#section-5.4), DNS Stateful Operations, March 2019
+///
+/// ```text
+/// 5.4.  DSO Message Format
+///
+///                                                1  1  1  1  1  1
+///      0  1  2  3  4  5  6  7  8  9  0  1  2  3  4  5
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                  MESSAGE ID                   |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |QR| OPCODE(6) |            Z             | RCODE |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                 QDCOUNT (MUST BE ZERO)        |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                 ANCOUNT (MUST BE ZERO)        |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                 NSCOUNT (MUST BE ZERO)        |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                 ARCOUNT (MUST BE ZERO)        |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                                               |
+///    /                   DSO Data                    /
+///    /                                               /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+///
+/// A request with a message id of zero is unidirectional, no response is sent for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DsoMessage {
+    header: Header,
+    tlvs: Vec<DsoTlv>,
+}
+
+impl DsoMessage {
+    /// A new request, the first TLV is the primary TLV which specifies the operation
+    pub fn new_request(id: u16, tlvs: Vec<DsoTlv>) -> Self {
+        let mut header = Header::new();
+        header
+            .set_id(id)
+            .set_message_type(MessageType::Query)
+            .set_op_code(OpCode::Dso);
+
+        Self { header, tlvs }
+    }
+
+    /// A new response to the request
+    pub fn new_response(request: &Self, response_code: ResponseCode, tlvs: Vec<DsoTlv>) -> Self {
+        let mut header = Header::response_from_request(&request.header);
+        header.set_response_code(response_code);
+
+        Self { header, tlvs }
+    }
+
+    /// Returns true if the serialized message is a DSO message, without decoding it
+    pub fn is_dso(buffer: &[u8]) -> bool {
+        // the opcode is in bits 1-4 of the third byte
+        matches!(buffer.get(2), Some(byte) if (byte >> 3) & 0x0F == u8::from(OpCode::Dso))
+    }
+
+    /// The header of the message
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// The message id, zero for unidirectional messages
+    pub fn id(&self) -> u16 {
+        self.header.id()
+    }
+
+    /// True if this is a request which must not be answered
+    pub fn is_unidirectional(&self) -> bool {
+        self.header.message_type() == MessageType::Query && self.header.id() == 0
+    }
+
+    /// The response code, of responses
+    pub fn response_code(&self) -> ResponseCode {
+        self.header.response_code()
+    }
+
+    /// All TLVs of the message
+    pub fn tlvs(&self) -> &[DsoTlv] {
+        &self.tlvs
+    }
+
+    /// The first TLV of a request, which specifies the operation
+    pub fn primary_tlv(&self) -> Option<&DsoTlv> {
+        self.tlvs.first()
+    }
+
+    /// Decodes a message from the buffer
+    pub fn from_vec(buffer: &[u8]) -> ProtoResult<Self> {
+        let mut decoder = BinDecoder::new(buffer);
+        Self::read(&mut decoder)
+    }
+
+    /// Encodes the message into a buffer
+    pub fn to_vec(&self) -> ProtoResult<Vec<u8>> {
+        self.to_bytes()
+    }
+}
+
+impl BinEncodable for DsoMessage {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        self.header.emit(encoder)?;
+        for tlv in &self.tlvs {
+            tlv.emit(encoder)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'r> BinDecodable<'r> for DsoMessage {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let header = Header::read(decoder)?;
+        if header.op_code() != OpCode::Dso {
+            return Err(format!("not a DSO message: {}", header.op_code()).into());
+        }
+        if header.query_count() != 0
+            || header.answer_count() != 0
+            || header.name_server_count() != 0
+            || header.additional_count() != 0
+        {
+            return Err("DSO message with non-zero counts".into());
+        }
+
+        let mut tlvs = Vec::new();
+        while !decoder.is_empty() {
+            tlvs.push(DsoTlv::read(decoder)?);
+        }
+
+        Ok(Self { header, tlvs })
+    }
+}
+
+/// The TLVs carried by DSO messages
+///
+/// ```text
+/// 5.4.4.  DSO Data
+///
+///                                                1  1  1  1  1  1
+///      0  1  2  3  4  5  6  7  8  9  0  1  2  3  4  5
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                   DSO-TYPE                    |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                   DSO-LENGTH                  |
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    |                                               |
+///    /                   DSO-DATA                    /
+///    /                                               /
+///    +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DsoTlv {
+    /// The Keepalive TLV, DSO-TYPE 1, which establishes the session and negotiates its timeouts
+    Keepalive(Keepalive),
+    /// The Retry Delay TLV, DSO-TYPE 2, sent by the server to ask the client to close the session
+    RetryDelay(Duration),
+    /// Any other TLV, e.g. the Encryption Padding TLV, with its DSO-TYPE and DSO-DATA
+    Unknown(u16, Vec<u8>),
+}
+
+impl DsoTlv {
+    /// The DSO-TYPE of the TLV
+    pub fn dso_type(&self) -> u16 {
+        match self {
+            Self::Keepalive(..) => 1,
+            Self::RetryDelay(..) => 2,
+            Self::Unknown(dso_type, ..) => *dso_type,
+        }
+    }
+}
+
+impl BinEncodable for DsoTlv {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.dso_type())?;
+        match self {
+            Self::Keepalive(keepalive) => {
+                encoder.emit_u16(8)?;
+                encoder.emit_u32(keepalive.inactivity_timeout)?;
+                encoder.emit_u32(keepalive.keepalive_interval)
+            }
+            Self::RetryDelay(retry_delay) => {
+                encoder.emit_u16(4)?;
+                encoder.emit_u32(to_millis(Some(*retry_delay)))
+            }
+            Self::Unknown(_, data) => {
+                let len = u16::try_from(data.len())
+                    .map_err(|_| ProtoError::from("DSO-DATA is too long"))?;
+                encoder.emit_u16(len)?;
+                encoder.emit_vec(data)
+            }
+        }
+    }
+}
+
+impl<'r> BinDecodable<'r> for DsoTlv {
+    fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
+        let dso_type = decoder.read_u16()?.unverified(/*any u16 is a valid DSO-TYPE*/);
+        let len = decoder.read_u16()?.unverified(/*bounded by the remaining data*/);
+        let data = decoder.read_slice(usize::from(len))?.unverified(/*checked below*/);
+
+        let tlv = match (dso_type, data) {
+            (1, &[i0, i1, i2, i3, k0, k1, k2, k3]) => Self::Keepalive(Keepalive {
+                inactivity_timeout: u32::from_be_bytes([i0, i1, i2, i3]),
+                keepalive_interval: u32::from_be_bytes([k0, k1, k2, k3]),
+            }),
+            (2, &[r0, r1, r2, r3]) => {
+                Self::RetryDelay(Duration::from_millis(u64::from(u32::from_be_bytes([
+                    r0, r1, r2, r3,
+                ]))))
+            }
+            (1, _) | (2, _) => {
+                return Err(format!("invalid DSO-LENGTH {} of DSO-TYPE {}", len, dso_type).into())
+            }
+            (dso_type, data) => Self::Unknown(dso_type, data.to_vec()),
+        };
+
+        Ok(tlv)
+    }
+}
+
+/// The timeouts of a session, exchanged in the Keepalive TLV, [RFC 8490](https://tools.ietf.org/html/rfc8490#section-7.1)
+///
+/// The inactivity timeout is the time after which a client without outstanding operations is expected to close the
+///  session, the keepalive interval is the longest time the client may stay silent before sending a keepalive. On the
+///  wire both are in milliseconds, where 0xFFFFFFFF is infinite.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keepalive {
+    inactivity_timeout: u32,
+    keepalive_interval: u32,
+}
+
+impl Keepalive {
+    /// Creates the timeouts, `None` is an infinite timeout
+    pub fn new(inactivity_timeout: Option<Duration>, keepalive_interval: Option<Duration>) -> Self {
+        Self {
+            inactivity_timeout: to_millis(inactivity_timeout),
+            keepalive_interval: to_millis(keepalive_interval),
+        }
+    }
+
+    /// The time after which an idle client is expected to close the session, `None` if it's infinite
+    pub fn inactivity_timeout(&self) -> Option<Duration> {
+        from_millis(self.inactivity_timeout)
+    }
+
+    /// The maximum time between the messages of the client, `None` if it's infinite
+    pub fn keepalive_interval(&self) -> Option<Duration> {
+        from_millis(self.keepalive_interval)
+    }
+}
+
+fn to_millis(duration: Option<Duration>) -> u32 {
+    match duration {
+        Some(duration) => u32::try_from(duration.as_millis())
+            .unwrap_or(INFINITE - 1)
+            .min(INFINITE - 1),
+        None => INFINITE,
+    }
+}
+
+fn from_millis(millis: u32) -> Option<Duration> {
+    if millis == INFINITE {
+        None
+    } else {
+        Some(Duration::from_millis(u64::from(millis)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keepalive_round_trip() {
+        let keepalive = Keepalive::new(None, Some(Duration::from_secs(15)));
+        let request = DsoMessage::new_request(42, vec![DsoTlv::Keepalive(keepalive)]);
+
+        let bytes = request.to_vec().unwrap();
+        assert!(DsoMessage::is_dso(&bytes));
+        assert_eq!(
+            &bytes[Header::len()..],
+            &[0, 1, 0, 8, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0x3A, 0x98]
+        );
+
+        let read = DsoMessage::from_vec(&bytes).unwrap();
+        assert_eq!(read, request);
+        assert!(!read.is_unidirectional());
+        assert_eq!(
+            read.primary_tlv(),
+            Some(&DsoTlv::Keepalive(Keepalive::new(
+                None,
+                Some(Duration::from_secs(15))
+            )))
+        );
+
+        let response = DsoMessage::new_response(&read, ResponseCode::DSOTYPENI, vec![]);
+        let read = DsoMessage::from_vec(&response.to_vec().unwrap()).unwrap();
+        assert_eq!(read.id(), 42);
+        assert_eq!(read.header().message_type(), MessageType::Response);
+        assert_eq!(read.response_code(), ResponseCode::DSOTYPENI);
+    }
+
+    #[test]
+    fn test_unidirectional_and_unknown() {
+        let message = DsoMessage::new_request(
+            0,
+            vec![
+                DsoTlv::RetryDelay(Duration::from_secs(5)),
+                DsoTlv::Unknown(3, vec![0; 4]),
+            ],
+        );
+
+        let read = DsoMessage::from_vec(&message.to_vec().unwrap()).unwrap();
+        assert!(read.is_unidirectional());
+        assert_eq!(read.tlvs(), message.tlvs());
+    }
+
+    #[test]
+    fn test_invalid() {
+        // the Keepalive TLV has a fixed length
+        let bytes = [
+            0, 1, 0x30, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0, 4, 0, 0, 0, 0,
+        ];
+        assert!(DsoMessage::is_dso(&bytes));
+        assert!(DsoMessage::from_vec(&bytes).is_err());
+
+        // a query is not a DSO message
+        let bytes = [0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        assert!(!DsoMessage::is_dso(&bytes));
+        assert!(DsoMessage::from_vec(&bytes).is_err());
+    }
+}
@@ -18,6 +18,9 @@
 
 use std::fmt;
 
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
 use crate::error::*;
 use crate::rr::rdata::opt::{self, EdnsCode, EdnsOption};
 use crate::rr::rdata::OPT;
@@ -60,6 +63,26 @@ impl Edns {
         Self::default()
     }
 
+    /// Returns a builder for EDNS
+    ///
+    /// ```
+    /// use trust_dns_proto::op::Edns;
+    /// use trust_dns_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+    ///
+    /// let edns = Edns::builder()
+    ///     .dnssec_ok(true)
+    ///     .udp_payload(4096)
+    ///     .option(EdnsOption::Padding(0))
+    ///     .build();
+    ///
+    /// assert!(edns.dnssec_ok());
+    /// assert_eq!(edns.max_payload(), 4096);
+    /// assert_eq!(edns.option(EdnsCode::Padding), Some(&EdnsOption::Padding(0)));
+    /// ```
+    pub fn builder() -> EdnsBuilder {
+        EdnsBuilder::default()
+    }
+
     /// The high order bytes for the response code in the DNS Message
     pub fn rcode_high(&self) -> u8 {
         self.rcode_high
@@ -85,6 +108,26 @@ impl Edns {
         self.options.get(code)
     }
 
+    /// Iterates over the options with their codes, in no particular order
+    pub fn option_iter(&self) -> impl Iterator<Item = (EdnsCode, &EdnsOption)> {
+        self.options.iter()
+    }
+
+    /// Returns the TTL field of the OPT record
+    ///
+    /// The TTL of an OPT record holds the upper 8 bits of the extended response code, the version and
+    ///  the DO bit, see `rcode_high`, `version` and `dnssec_ok` for the individual values.
+    pub fn ttl(&self) -> u32 {
+        let mut ttl: u32 = u32::from(self.rcode_high) << 24;
+        ttl |= u32::from(self.version) << 16;
+
+        if self.dnssec_ok {
+            ttl |= 0x0000_8000;
+        }
+
+        ttl
+    }
+
     /// Returns the options portion of EDNS
     pub fn options(&self) -> &OPT {
         &self.options
@@ -127,6 +170,64 @@ impl Edns {
     }
 }
 
+/// A builder for EDNS, see `Edns::builder`
+#[derive(Clone, Debug, Default)]
+pub struct EdnsBuilder {
+    edns: Edns,
+}
+
+impl EdnsBuilder {
+    /// Sets the DO bit, to request DNSSEC records, default is false
+    pub fn dnssec_ok(&mut self, dnssec_ok: bool) -> &mut Self {
+        self.edns.set_dnssec_ok(dnssec_ok);
+        self
+    }
+
+    /// Sets the maximum UDP payload which can be supported, values lower than 512 are treated as 512,
+    ///  the default
+    pub fn udp_payload(&mut self, max_payload: u16) -> &mut Self {
+        self.edns.set_max_payload(max_payload);
+        self
+    }
+
+    /// Sets the EDNS version, default is 0
+    pub fn version(&mut self, version: u8) -> &mut Self {
+        self.edns.set_version(version);
+        self
+    }
+
+    /// Sets the upper 8 bits of the extended response code, default is 0
+    pub fn rcode_high(&mut self, rcode_high: u8) -> &mut Self {
+        self.edns.set_rcode_high(rcode_high);
+        self
+    }
+
+    /// Adds an option, an option previously added with the same code is replaced
+    pub fn option(&mut self, option: EdnsOption) -> &mut Self {
+        self.edns.options_mut().insert(option);
+        self
+    }
+
+    /// Builds the EDNS
+    pub fn build(&self) -> Edns {
+        self.edns.clone()
+    }
+}
+
+/// How the EDNS Padding option, [RFC 7830](https://tools.ietf.org/html/rfc7830), is added to messages
+///
+/// Padding hides the size of messages sent over encrypted transports, see
+///  [RFC 8467, Padding Policies for EDNS(0)](https://tools.ietf.org/html/rfc8467).
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PaddingPolicy {
+    /// Do not pad messages
+    None,
+    /// Pad messages to the next multiple of the block size, RFC 8467 recommends 128 octets for
+    ///  queries and 468 octets for responses
+    Block(u16),
+}
+
 // FIXME: this should be a TryFrom
 impl<'a> From<&'a Record> for Edns {
     fn from(value: &'a Record) -> Self {
@@ -171,14 +272,7 @@ impl<'a> From<&'a Edns> for Record {
         record.set_rr_type(RecordType::OPT);
         record.set_dns_class(DNSClass::for_opt(value.max_payload()));
 
-        // rebuild the TTL field
-        let mut ttl: u32 = u32::from(value.rcode_high()) << 24;
-        ttl |= u32::from(value.version()) << 16;
-
-        if value.dnssec_ok() {
-            ttl |= 0x0000_8000;
-        }
-        record.set_ttl(ttl);
+        record.set_ttl(value.ttl());
 
         // now for each option, write out the option array
         //  also, since this is a hash, there is no guarantee that ordering will be preserved from
@@ -196,15 +290,7 @@ impl BinEncodable for Edns {
         RecordType::OPT.emit(encoder)?; //self.rr_type.emit(encoder)?;
         DNSClass::for_opt(self.max_payload()).emit(encoder)?; // self.dns_class.emit(encoder)?;
 
-        // rebuild the TTL field
-        let mut ttl: u32 = u32::from(self.rcode_high()) << 24;
-        ttl |= u32::from(self.version()) << 16;
-
-        if self.dnssec_ok() {
-            ttl |= 0x0000_8000;
-        }
-
-        encoder.emit_u32(ttl)?;
+        encoder.emit_u32(self.ttl())?;
 
         // write the opts as rdata...
         let place = encoder.place::<u16>()?;
@@ -229,7 +315,7 @@ impl fmt::Display for Edns {
             version = version,
             dnssec_ok = dnssec_ok,
             max_payload = max_payload,
-            opts_len = self.options().as_ref().len()
+            opts_len = self.options().len()
         )
     }
 }
@@ -263,3 +349,43 @@ fn test_encode_decode() {
     edns.options_mut().remove(EdnsCode::DAU);
     assert!(edns.option(EdnsCode::DAU).is_none());
 }
+
+#[test]
+fn test_builder() {
+    let edns = Edns::builder()
+        .dnssec_ok(true)
+        .udp_payload(4096)
+        .version(1)
+        .rcode_high(0x01)
+        .option(EdnsOption::Padding(8))
+        .option(EdnsOption::Padding(16))
+        .option(EdnsOption::Unknown(65001, vec![1, 2]))
+        .build();
+
+    assert!(edns.dnssec_ok());
+    assert_eq!(edns.max_payload(), 4096);
+    assert_eq!(edns.version(), 1);
+    assert_eq!(edns.rcode_high(), 0x01);
+    assert_eq!(edns.ttl(), 0x0101_8000);
+
+    // last option wins
+    let mut options = edns.option_iter().collect::<Vec<_>>();
+    options.sort_by_key(|(code, _)| u16::from(*code));
+    assert_eq!(
+        options,
+        vec![
+            (EdnsCode::Padding, &EdnsOption::Padding(16)),
+            (
+                EdnsCode::Unknown(65001),
+                &EdnsOption::Unknown(65001, vec![1, 2])
+            ),
+        ]
+    );
+
+    let record: Record = (&edns).into();
+    assert_eq!(record.ttl(), 0x0101_8000);
+    assert_eq!(Edns::from(&record), edns);
+
+    assert_eq!(Edns::builder().udp_payload(0).build().max_payload(), 512);
+    assert_eq!(Edns::builder().build(), Edns::new());
+}
@@ -7,14 +7,17 @@
 
 //! Basic protocol message for DNS
 
-use std::{fmt, iter, mem, ops::Deref, sync::Arc};
+use std::{convert::TryFrom, fmt, iter, mem, ops::Deref, sync::Arc};
 
 use log::{debug, warn};
 
 use crate::{
     error::*,
-    op::{Edns, Header, MessageType, OpCode, Query, ResponseCode},
-    rr::{Record, RecordType},
+    op::{Edns, Header, MessageType, OpCode, PaddingPolicy, Query, ResponseCode},
+    rr::{
+        rdata::opt::{EdnsCode, EdnsOption},
+        Name, Record, RecordType,
+    },
     serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, EncodeMode},
     xfer::DnsResponse,
 };
@@ -641,11 +644,24 @@ impl Message {
     /// # Returns
     ///
     /// This returns a tuple of first standard Records, then a possibly associated Edns, and then finally any optionally associated SIG0 and TSIG records.
-    #[cfg_attr(not(feature = "dnssec"), allow(unused_mut))]
+    ///
+    /// A malformed OPT record is an error, see `ProtoErrorKind::MalformedEdns`.
     pub fn read_records(
         decoder: &mut BinDecoder<'_>,
         count: usize,
         is_additional: bool,
+    ) -> ProtoResult<(Vec<Record>, Option<Edns>, Vec<Record>)> {
+        Self::read_records_with(decoder, count, is_additional, false)
+    }
+
+    /// Reads the records as `read_records`, but with `ignore_malformed_edns` a malformed OPT record is dropped, along
+    ///  with any other OPT record of the message
+    #[cfg_attr(not(feature = "dnssec"), allow(unused_mut))]
+    fn read_records_with(
+        decoder: &mut BinDecoder<'_>,
+        count: usize,
+        is_additional: bool,
+        ignore_malformed_edns: bool,
     ) -> ProtoResult<(Vec<Record>, Option<Edns>, Vec<Record>)> {
         let mut records: Vec<Record> = Vec::with_capacity(count);
         let mut edns: Option<Edns> = None;
@@ -655,8 +671,30 @@ impl Message {
         let mut saw_sig0 = false;
         // tsig must be last, once this is set, disable.
         let mut saw_tsig = false;
+        // once an OPT record was malformed, the EDNS of the message is ignored
+        let mut malformed_edns = false;
         for _ in 0..count {
-            let record = Record::read(decoder)?;
+            let start = decoder.index();
+            let record = match Record::read(decoder) {
+                Ok(record) => record,
+                Err(e)
+                    if ignore_malformed_edns
+                        && matches!(
+                            e.kind(),
+                            ProtoErrorKind::MalformedEdns(_) | ProtoErrorKind::EdnsNameNotRoot(_)
+                        ) =>
+                {
+                    warn!("ignoring the EDNS of the message: {}", e);
+                    *decoder = decoder.clone(
+                        u16::try_from(start).map_err(|_| ProtoError::from("message too long"))?,
+                    );
+                    Self::skip_record(decoder)?;
+                    malformed_edns = true;
+                    edns = None;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
             if saw_tsig {
                 return Err("tsig must be final resource record".into());
             } // TSIG must be last and multiple TSIG records are not allowed
@@ -664,6 +702,16 @@ impl Message {
                 if saw_sig0 {
                     return Err("sig0 must be final resource record".into());
                 } // SIG0 must be last
+                if record.rr_type() == RecordType::OPT {
+                    if ignore_malformed_edns {
+                        warn!("ignoring OPT record outside of the additional section");
+                        continue;
+                    }
+                    return Err(ProtoErrorKind::MalformedEdns(
+                        "OPT record outside of the additional section",
+                    )
+                    .into());
+                }
                 records.push(record)
             } else {
                 match record.rr_type() {
@@ -684,8 +732,17 @@ impl Message {
                         if saw_sig0 {
                             return Err("sig0 must be final resource record".into());
                         } // SIG0 must be last
-                        if edns.is_some() {
-                            return Err("more than one edns record present".into());
+                        if edns.is_some() || malformed_edns {
+                            if !ignore_malformed_edns {
+                                return Err(ProtoErrorKind::MalformedEdns(
+                                    "more than one OPT record",
+                                )
+                                .into());
+                            }
+                            warn!("ignoring the EDNS of the message: more than one OPT record");
+                            malformed_edns = true;
+                            edns = None;
+                            continue;
                         }
                         edns = Some((&record).into());
                     }
@@ -702,6 +759,16 @@ impl Message {
         Ok((records, edns, sigs))
     }
 
+    /// Skips over a record without decoding its data
+    fn skip_record(decoder: &mut BinDecoder<'_>) -> ProtoResult<()> {
+        Name::read(decoder)?;
+        // the type, class and ttl
+        decoder.read_slice(8)?;
+        let rd_length = decoder.read_u16()?.unverified(/*the data is skipped*/);
+        decoder.read_slice(usize::from(rd_length))?;
+        Ok(())
+    }
+
     /// Decodes a message from the buffer.
     pub fn from_vec(buffer: &[u8]) -> ProtoResult<Self> {
         let mut decoder = BinDecoder::new(buffer);
@@ -722,6 +789,35 @@ impl Message {
         Ok(buffer)
     }
 
+    /// Pads the message with the EDNS Padding option, as described by `policy`
+    ///
+    /// This has no effect on messages without EDNS. Any padding already in the message is replaced,
+    ///  and as the padding depends on the length of the message, this should be the last change
+    ///  to the message, other than signing it in `finalize`.
+    pub fn pad(&mut self, policy: PaddingPolicy) -> ProtoResult<()> {
+        let block_size = match policy {
+            PaddingPolicy::None | PaddingPolicy::Block(0) => return Ok(()),
+            PaddingPolicy::Block(block_size) => usize::from(block_size),
+        };
+
+        let edns = match self.edns {
+            Some(ref mut edns) => edns,
+            None => return Ok(()),
+        };
+        edns.options_mut().remove(EdnsCode::Padding);
+
+        // the option code and length are part of the padded length
+        let len = self.to_vec()?.len() + 4;
+        let padding = (block_size - len % block_size) % block_size;
+
+        if let Some(ref mut edns) = self.edns {
+            edns.options_mut()
+                .insert(EdnsOption::Padding(padding as u16));
+        }
+
+        Ok(())
+    }
+
     /// Finalize the message prior to sending.
     ///
     /// Subsequent to calling this, the Message should not change.
@@ -924,6 +1020,62 @@ impl<'e, I: Iterator<Item = &'e E>, E: 'e + BinEncodable> EmitAndCount for I {
     }
 }
 
+/// Emits the records of a section in whole RRsets, for truncation to never leave an RRset partial
+///
+/// The records of an RRset, and the RRSIGs which cover it, are expected to be adjacent in the iterator.
+///  If the encoder runs out of space within an RRset, everything written of it is discarded and the
+///  count of the records of the complete RRsets before it is returned in `NotAllRecordsWritten`.
+pub struct RRSets<I>(pub I);
+
+impl<'r, I: Iterator<Item = &'r Record>> EmitAndCount for RRSets<I> {
+    fn emit(&mut self, encoder: &mut BinEncoder<'_>) -> ProtoResult<usize> {
+        let mut count = 0;
+        let mut rrset_start = (encoder.set_rollback(), count);
+        let mut previous: Option<&Record> = None;
+
+        for record in &mut self.0 {
+            if !matches!(previous, Some(previous) if is_same_rrset(previous, record)) {
+                rrset_start = (encoder.set_rollback(), count);
+            }
+
+            if let Err(e) = record.emit(encoder) {
+                if let ProtoErrorKind::MaxBufferSizeExceeded(_) = e.kind() {
+                    let (rollback, count) = rrset_start;
+                    rollback.rollback(encoder);
+                    return Err(ProtoErrorKind::NotAllRecordsWritten { count }.into());
+                }
+
+                return Err(e);
+            }
+
+            count += 1;
+            previous = Some(record);
+        }
+
+        Ok(count)
+    }
+}
+
+/// Returns true if the record belongs to the RRset of the previous record, or is an RRSIG which covers it
+fn is_same_rrset(previous: &Record, record: &Record) -> bool {
+    fn covered_type(record: &Record) -> RecordType {
+        #[cfg(feature = "dnssec")]
+        {
+            use crate::rr::{dnssec::rdata::DNSSECRData, RData};
+
+            if let Some(RData::DNSSEC(DNSSECRData::SIG(sig))) = record.data() {
+                return sig.type_covered();
+            }
+        }
+
+        record.rr_type()
+    }
+
+    previous.name() == record.name()
+        && previous.dns_class() == record.dns_class()
+        && covered_type(previous) == covered_type(record)
+}
+
 /// Emits the different sections of a message properly
 ///
 /// # Return
@@ -950,17 +1102,45 @@ where
     let place = encoder.place::<Header>()?;
 
     let query_count = queries.emit(encoder)?;
-    // TODO: need to do something on max records
-    //  return offset of last emitted record.
-    let answer_count = count_was_truncated(answers.emit(encoder))?;
-    let nameserver_count = count_was_truncated(name_servers.emit(encoder))?;
-    let mut additional_count = count_was_truncated(additionals.emit(encoder))?;
 
-    if let Some(mut edns) = edns.cloned() {
+    let edns = edns.map(|edns| {
         // need to commit the error code
+        let mut edns = edns.clone();
         edns.set_rcode_high(header.response_code().high());
+        Record::from(&edns)
+    });
+
+    // the space for the EDNS and signature records is reserved, so that they are not the ones
+    //  left out when the sections do not fit
+    let max_size = encoder.max_size();
+    let reserved = encoded_len(edns.iter())?
+        + if include_signature {
+            encoded_len(signature.iter())?
+        } else {
+            0
+        };
+    encoder.set_max_size(max_size.saturating_sub(reserved.min(usize::from(u16::MAX)) as u16));
+
+    // once a section is truncated the following ones are left out, rather than filled with
+    //  whatever still fits, so that the answers have priority
+    let mut emit_sections = |encoder: &mut BinEncoder<'_>| -> ProtoResult<_> {
+        let answer_count = count_was_truncated(answers.emit(encoder))?;
+        if answer_count.1 {
+            return Ok((answer_count, (0, false), (0, false)));
+        }
+        let nameserver_count = count_was_truncated(name_servers.emit(encoder))?;
+        if nameserver_count.1 {
+            return Ok((answer_count, nameserver_count, (0, false)));
+        }
+        let additional_count = count_was_truncated(additionals.emit(encoder))?;
+        Ok((answer_count, nameserver_count, additional_count))
+    };
+    let counts = emit_sections(encoder);
+    encoder.set_max_size(max_size);
+    let (answer_count, nameserver_count, mut additional_count) = counts?;
 
-        let count = count_was_truncated(encoder.emit_all(iter::once(&Record::from(&edns))))?;
+    if let Some(edns) = edns {
+        let count = count_was_truncated(encoder.emit_all(iter::once(&edns)))?;
         additional_count.0 += count.0;
         additional_count.1 |= count.1;
     } else if header.response_code().high() > 0 {
@@ -994,14 +1174,22 @@ where
     Ok(final_header)
 }
 
+/// Returns the length of the records when encoded on their own
+fn encoded_len<'r>(records: impl Iterator<Item = &'r Record>) -> ProtoResult<usize> {
+    let mut buffer = Vec::new();
+    let mut encoder = BinEncoder::new(&mut buffer);
+    encoder.emit_all(records)?;
+    Ok(encoder.len())
+}
+
 impl BinEncodable for Message {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
         emit_message_parts(
             &self.header,
             &mut self.queries.iter(),
-            &mut self.answers.iter(),
-            &mut self.name_servers.iter(),
-            &mut self.additionals.iter(),
+            &mut RRSets(self.answers.iter()),
+            &mut RRSets(self.name_servers.iter()),
+            &mut RRSets(self.additionals.iter()),
             self.edns.as_ref(),
             &self.signature,
             encoder,
@@ -1030,9 +1218,12 @@ impl<'r> BinDecodable<'r> for Message {
         let name_server_count = header.name_server_count() as usize;
         let additional_count = header.additional_count() as usize;
 
-        let (answers, _, _) = Self::read_records(decoder, answer_count, false)?;
-        let (name_servers, _, _) = Self::read_records(decoder, name_server_count, false)?;
-        let (additionals, edns, signature) = Self::read_records(decoder, additional_count, true)?;
+        // a malformed OPT record is only an error for the requests of clients, see `read_records`
+        let (answers, _, _) = Self::read_records_with(decoder, answer_count, false, true)?;
+        let (name_servers, _, _) =
+            Self::read_records_with(decoder, name_server_count, false, true)?;
+        let (additionals, edns, signature) =
+            Self::read_records_with(decoder, additional_count, true, true)?;
 
         // need to grab error code from EDNS (which might have a higher value)
         if let Some(edns) = &edns {
@@ -1123,6 +1314,39 @@ fn test_emit_and_read_query() {
     test_emit_and_read(message);
 }
 
+#[test]
+fn test_emit_and_read_multiple_queries() {
+    use std::str::FromStr;
+
+    use crate::rr::Name;
+
+    let mut message = Message::new();
+    message
+        .set_id(10)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ))
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::AAAA,
+        ));
+
+    let bytes = message.to_vec().unwrap();
+    let got = Message::from_vec(&bytes).unwrap();
+
+    assert_eq!(got.header().query_count(), 2);
+    assert_eq!(got.queries(), message.queries());
+    assert_eq!(got.queries()[1].query_type(), RecordType::AAAA);
+
+    message.update_counts();
+    assert_eq!(got, message);
+    assert_eq!(got.to_vec().unwrap(), bytes);
+}
+
 #[test]
 fn test_emit_and_read_records() {
     let mut message = Message::new();
@@ -1146,6 +1370,76 @@ fn test_emit_and_read_records() {
     test_emit_and_read(message);
 }
 
+#[test]
+fn test_read_malformed_edns() {
+    let mut message = Message::new();
+    message
+        .set_id(10)
+        .set_message_type(MessageType::Response)
+        .add_answer(Record::from_rdata(
+            Name::root(),
+            300,
+            crate::rr::RData::A(std::net::Ipv4Addr::LOCALHOST),
+        ));
+    let mut bytes = message.to_vec().unwrap();
+
+    // an OPT record of which the option length exceeds the record
+    bytes[11] = 1;
+    bytes.extend_from_slice(&[
+        0x00, 0x00, 0x29, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x00, 0x0c, 0x00, 0x04,
+    ]);
+
+    // the OPT record is ignored by the client
+    let read = Message::from_vec(&bytes).expect("the OPT record should be ignored");
+    assert!(read.extensions().is_none());
+    assert_eq!(read.answers().len(), 1);
+
+    // but it is an error for the requests a server reads
+    let mut decoder = BinDecoder::new(&bytes);
+    Header::read(&mut decoder).unwrap();
+    Message::read_records(&mut decoder, 1, false).unwrap();
+    let error = Message::read_records(&mut decoder, 1, true).unwrap_err();
+    assert!(matches!(error.kind(), ProtoErrorKind::MalformedEdns(_)));
+}
+
+#[test]
+fn test_emit_and_read_extended_response_code() {
+    for response_code in [
+        ResponseCode::BADVERS,
+        ResponseCode::BADKEY,
+        ResponseCode::BADTIME,
+        ResponseCode::BADCOOKIE,
+        ResponseCode::Unknown(3841),
+    ]
+    .iter()
+    .copied()
+    {
+        let mut message = Message::new();
+        message
+            .set_id(10)
+            .set_message_type(MessageType::Response)
+            .set_response_code(response_code)
+            .set_edns(Edns::new());
+
+        let bytes = message.to_vec().unwrap();
+        // the low 4 bits are in the header, the high 8 bits in the TTL of the OPT record
+        assert_eq!(bytes[3] & 0x0F, response_code.low());
+        let got = Message::from_vec(&bytes).unwrap();
+
+        assert_eq!(got.response_code(), response_code);
+        assert_eq!(
+            got.extensions().as_ref().unwrap().rcode_high(),
+            response_code.high()
+        );
+    }
+
+    assert_eq!(ResponseCode::BADCOOKIE.high(), 1);
+    assert_eq!(ResponseCode::BADCOOKIE.low(), 7);
+    assert_eq!(ResponseCode::from(1, 7), ResponseCode::BADCOOKIE);
+    assert_eq!(ResponseCode::from(1, 0), ResponseCode::BADVERS);
+    assert_eq!(ResponseCode::from(0, 3), ResponseCode::NXDomain);
+}
+
 #[cfg(test)]
 fn test_emit_and_read(message: Message) {
     let mut byte_vec: Vec<u8> = Vec::with_capacity(512);
@@ -1207,3 +1501,120 @@ fn rdata_zero_roundtrip() {
 
     assert!(Message::from_bytes(buf).is_err());
 }
+
+#[test]
+fn test_pad() {
+    use crate::rr::Name;
+
+    let mut message = Message::new();
+    message.add_query(Query::query(
+        Name::from_ascii("www.example.com.").unwrap(),
+        RecordType::A,
+    ));
+
+    // no EDNS, no padding
+    message.pad(PaddingPolicy::Block(128)).unwrap();
+    assert!(message.extensions().is_none());
+
+    message.set_edns(Edns::new());
+    message.pad(PaddingPolicy::Block(128)).unwrap();
+    assert_eq!(message.to_vec().unwrap().len(), 128);
+
+    // the padding is replaced, not added to
+    message.pad(PaddingPolicy::Block(64)).unwrap();
+    assert_eq!(message.to_vec().unwrap().len(), 64);
+    assert!(Message::from_vec(&message.to_vec().unwrap())
+        .unwrap()
+        .extensions()
+        .as_ref()
+        .unwrap()
+        .option(EdnsCode::Padding)
+        .is_some());
+
+    message.pad(PaddingPolicy::None).unwrap();
+    assert_eq!(message.to_vec().unwrap().len(), 64);
+}
+
+#[test]
+fn test_emit_truncated_whole_rrsets() {
+    use crate::rr::{Name, RData};
+    use std::net::Ipv4Addr;
+
+    let name = |i: u8| Name::from_ascii(format!("www{}.example.com.", i)).unwrap();
+    let mut message = Message::new();
+    for i in 0..4 {
+        for j in 0..10 {
+            message.add_answer(Record::from_rdata(
+                name(i),
+                3600,
+                RData::A(Ipv4Addr::new(192, 0, 2, j)),
+            ));
+        }
+    }
+    message.add_name_server(Record::from_rdata(
+        name(9),
+        3600,
+        RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+    ));
+    message.set_edns(Edns::new());
+
+    // room for two of the RRsets and part of the third
+    let mut buffer = Vec::new();
+    let mut encoder = BinEncoder::new(&mut buffer);
+    encoder.set_max_size(400);
+    message.emit(&mut encoder).unwrap();
+    assert!(buffer.len() <= 400);
+
+    let truncated = Message::from_vec(&buffer).unwrap();
+    assert!(truncated.truncated());
+    assert_eq!(truncated.answers(), &message.answers()[..20]);
+    assert!(truncated.name_servers().is_empty());
+    assert!(truncated.extensions().is_some());
+
+    // everything fits without a limit
+    assert!(!Message::from_vec(&message.to_vec().unwrap())
+        .unwrap()
+        .truncated());
+}
+
+#[cfg(feature = "dnssec")]
+#[test]
+fn test_is_same_rrset() {
+    use crate::rr::dnssec::{rdata::DNSSECRData, rdata::SIG, Algorithm};
+    use crate::rr::{Name, RData};
+    use std::net::Ipv4Addr;
+
+    let www = Name::from_ascii("www.example.com.").unwrap();
+    let a = Record::from_rdata(www.clone(), 3600, RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+    let rrsig = |type_covered| {
+        Record::from_rdata(
+            www.clone(),
+            3600,
+            RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+                type_covered,
+                Algorithm::ED25519,
+                3,
+                3600,
+                0,
+                0,
+                0,
+                Name::from_ascii("example.com.").unwrap(),
+                vec![],
+            ))),
+        )
+        .set_rr_type(RecordType::RRSIG)
+        .clone()
+    };
+
+    assert!(is_same_rrset(&a, &a));
+    assert!(is_same_rrset(&a, &rrsig(RecordType::A)));
+    assert!(!is_same_rrset(&a, &rrsig(RecordType::AAAA)));
+    assert!(!is_same_rrset(
+        &a,
+        &Record::from_rdata(
+            Name::from_ascii("ftp.example.com.").unwrap(),
+            3600,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        )
+    ));
+}
@@ -17,6 +17,7 @@
 //! Operations to send with a `Client` or server, e.g. `Query`, `Message`, or `UpdateMessage` can
 //! be used together to either query or update resource records sets.
 
+pub mod dso;
 mod edns;
 pub mod header;
 pub mod message;
@@ -24,7 +25,8 @@ pub mod op_code;
 pub mod query;
 pub mod response_code;
 
-pub use self::edns::Edns;
+pub use self::dso::{DsoMessage, DsoTlv, Keepalive};
+pub use self::edns::{Edns, EdnsBuilder, PaddingPolicy};
 pub use self::header::Header;
 pub use self::header::MessageType;
 pub use self::message::{
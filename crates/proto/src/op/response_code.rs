@@ -96,6 +96,9 @@ pub enum ResponseCode {
     /// Name not contained in zone [RFC 2136](https://tools.ietf.org/html/rfc2136)
     NotZone,
 
+    /// DSO-TYPE Not Implemented [RFC 8490](https://tools.ietf.org/html/rfc8490#section-10.2)
+    DSOTYPENI,
+
     /// Bad OPT Version [RFC 6891](https://tools.ietf.org/html/rfc6891#section-9)
     BADVERS,
 
@@ -150,8 +153,14 @@ impl ResponseCode {
     }
 
     /// Combines the EDNS high and low from the Header to produce the Extended ResponseCode
+    ///
+    /// The code 16 is shared by BADVERS and BADSIG, in the OPT record it is BADVERS, BADSIG only appears
+    ///  in the error field of TSIG records. So unlike `From<u16>` this returns BADVERS for 16.
     pub fn from(high: u8, low: u8) -> Self {
-        ((u16::from(high) << 4) | ((u16::from(low)) & 0x000F)).into()
+        match (u16::from(high) << 4) | ((u16::from(low)) & 0x000F) {
+            16 => Self::BADVERS,
+            code => code.into(),
+        }
     }
 
     /// Transforms the response code into the human message
@@ -168,6 +177,7 @@ impl ResponseCode {
             ResponseCode::NXRRSet => "RR Set does not exist", // 8     NXRRSet       RR Set that should exist does not   [RFC2136]
             ResponseCode::NotAuth => "Not authorized", // 9     NotAuth       Server Not Authoritative for zone   [RFC2136]
             ResponseCode::NotZone => "Name not in zone", // 10    NotZone       Name not contained in zone          [RFC2136]
+            ResponseCode::DSOTYPENI => "DSO-TYPE not implemented", // 11    DSOTYPENI     DSO-TYPE Not Implemented            [RFC8490]
             ResponseCode::BADVERS => "Bad option verions", // 16    BADVERS       Bad OPT Version                     [RFC6891]
             ResponseCode::BADSIG => "TSIG Failure", // 16    BADSIG        TSIG Signature Failure              [RFC2845]
             ResponseCode::BADKEY => "Key not recognized", // 17    BADKEY        Key not recognized                  [RFC2845]
@@ -220,8 +230,9 @@ impl From<ResponseCode> for u16 {
             ResponseCode::NXRRSet => 8, // 8   NXRRSet    RR Set that should exist does not     [RFC2136]
             ResponseCode::NotAuth => 9, // 9   NotAuth    Server Not Authoritative for zone     [RFC2136]
             ResponseCode::NotZone => 10, // 10  NotZone    Name not contained in zone            [RFC2136]
+            ResponseCode::DSOTYPENI => 11, // 11  DSOTYPENI  DSO-TYPE Not Implemented              [RFC8490]
             //
-            // 11-15    Unassigned
+            // 12-15    Unassigned
             //
             // 16  BADVERS  Bad OPT Version         [RFC6891]
             // 16  BADSIG   TSIG Signature Failure  [RFC2845]
@@ -266,6 +277,7 @@ impl From<u16> for ResponseCode {
             8 => Self::NXRRSet,  // 8    NXRRSet    RR Set that should exist does not    [RFC2136]
             9 => Self::NotAuth,  // 9    NotAuth    Server Not Authoritative for zone    [RFC2136]
             10 => Self::NotZone, // 10   NotZone    Name not contained in zone           [RFC2136]
+            11 => Self::DSOTYPENI, // 11   DSOTYPENI  DSO-TYPE Not Implemented             [RFC8490]
             // this looks to be backwards compat for 4 bit ResponseCodes.
             // 16    BADVERS    Bad OPT Version    [RFC6891]
             // 16 => ResponseCode::BADVERS,
@@ -14,12 +14,14 @@ use std::{
     task::{Context, Poll},
 };
 
+use bytes::Bytes;
 use futures_util::{future::FutureExt, stream::Stream};
 use quinn::{ClientConfig, Connection, Endpoint, NewConnection, OpenBi, TransportConfig, VarInt};
 use rustls::{version::TLS13, ClientConfig as TlsClientConfig};
 
 use crate::{
-    error::ProtoError,
+    error::{ProtoError, ProtoErrorKind},
+    op::Message,
     quic::quic_stream::{DoqErrorCode, QuicStream},
     udp::UdpSocket,
     xfer::{DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream},
@@ -52,21 +54,41 @@ impl QuicClientStream {
         QuicClientStreamBuilder::default()
     }
 
-    async fn inner_send(stream: OpenBi, message: DnsRequest) -> Result<DnsResponse, ProtoError> {
+    async fn inner_send(
+        stream: OpenBi,
+        mut message: DnsRequest,
+    ) -> Result<DnsResponse, ProtoError> {
         let (send_stream, recv_stream) = stream.await?;
 
         // RFC: The mapping specified here requires that the client selects a separate
         //  QUIC stream for each query. The server then uses the same stream to provide all the response messages for that query.
         let mut stream = QuicStream::new(send_stream, recv_stream);
 
-        stream.send(message.into_parts().0).await?;
+        // raw requests are sent as is and the bytes of the response are kept
+        let is_raw = message.is_raw();
+        if is_raw {
+            message.set_id(0);
+            stream.send_bytes(Bytes::from(message.to_wire()?)).await?;
+        } else {
+            stream.send(message.into_parts().0).await?;
+        }
 
         // The client MUST send the DNS query over the selected stream,
         // and MUST indicate through the STREAM FIN mechanism that no further data will be sent on that stream.
         stream.finish().await?;
 
-        let response = stream.receive().await?;
-        Ok(response.into())
+        if !is_raw {
+            let response = stream.receive().await?;
+            return Ok(response.into());
+        }
+
+        let buffer = stream.receive_bytes().await?;
+        let response = Message::from_vec(&buffer)?;
+        if response.id() != 0 {
+            return Err(ProtoErrorKind::QuicMessageIdNot0(response.id()).into());
+        }
+
+        Ok(DnsResponse::from(response).with_buffer(buffer.to_vec()))
     }
 }
 
@@ -149,6 +171,7 @@ pub struct QuicClientStreamBuilder {
     crypto_config: TlsClientConfig,
     transport_config: Arc<TransportConfig>,
     bind_addr: Option<SocketAddr>,
+    early_data: bool,
 }
 
 impl QuicClientStreamBuilder {
@@ -164,6 +187,20 @@ impl QuicClientStreamBuilder {
         self
     }
 
+    /// Allow 0-RTT data when resuming a connection, default is `false`
+    ///
+    /// ```text
+    /// 4.5. Session Resumption and 0-RTT
+    ///
+    /// A DoQ client MAY take advantage of the session resumption and 0-RTT mechanisms supported by QUIC.
+    /// 0-RTT support SHOULD be disabled by default in DoQ implementations. Clients SHOULD NOT send
+    /// queries which are not replay safe, e.g. dynamic updates, in 0-RTT data.
+    /// ```
+    pub fn early_data(&mut self, early_data: bool) -> &mut Self {
+        self.early_data = early_data;
+        self
+    }
+
     /// Creates a new QuicStream to the specified name_server
     ///
     /// # Arguments
@@ -197,15 +234,25 @@ impl QuicClientStreamBuilder {
             crypto_config.alpn_protocols = vec![quic_stream::DOQ_ALPN.to_vec()];
         }
 
+        // 0-RTT data can be replayed by an attacker, and dynamic updates are not idempotent,
+        //  so this is only enabled when explicitly requested, see `QuicClientStreamBuilder::early_data`
+        crypto_config.enable_early_data = self.early_data;
+
         let mut client_config = ClientConfig::new(Arc::new(crypto_config));
         client_config.transport = self.transport_config;
 
         endpoint.set_default_client_config(client_config);
 
         let connecting = endpoint.connect(name_server, &dns_name)?;
-        // TODO: for Client/Dynamic update, don't use RTT, for queries, do use it.
 
-        let connection = connecting.await?;
+        let connection = if self.early_data {
+            match connecting.into_0rtt() {
+                Ok((connection, _zero_rtt_accepted)) => connection,
+                Err(connecting) => connecting.await?,
+            }
+        } else {
+            connecting.await?
+        };
         let NewConnection {
             connection: quic_connection,
             ..
@@ -253,6 +300,7 @@ impl Default for QuicClientStreamBuilder {
             crypto_config: client_config,
             transport_config: Arc::new(transport_config),
             bind_addr: None,
+            early_data: false,
         }
     }
 }
@@ -53,7 +53,7 @@ pub enum DigestType {
     SHA384,
     /// Undefined
     SHA512,
-    /// This is a passthrough digest as ED25519 is self-packaged
+    /// This is a passthrough digest as ED25519 and ED448 are self-packaged
     ED25519,
 }
 
@@ -160,7 +160,7 @@ impl From<Algorithm> for DigestType {
             Algorithm::RSASHA256 | Algorithm::ECDSAP256SHA256 => Self::SHA256,
             Algorithm::RSASHA512 => Self::SHA512,
             Algorithm::ECDSAP384SHA384 => Self::SHA384,
-            Algorithm::ED25519 => Self::ED25519,
+            Algorithm::ED25519 | Algorithm::ED448 => Self::ED25519,
             Algorithm::Unknown(_) => Self::SHA512,
         }
     }
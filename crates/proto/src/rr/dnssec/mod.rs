@@ -29,6 +29,8 @@ mod supported_algorithm;
 pub mod tbs;
 mod trust_anchor;
 mod verifier;
+#[cfg(any(feature = "openssl", feature = "ring"))]
+mod zonemd;
 
 pub use self::algorithm::Algorithm;
 pub use self::digest_type::DigestType;
@@ -40,6 +42,9 @@ pub use self::supported_algorithm::SupportedAlgorithms;
 pub use self::tbs::TBS;
 pub use self::trust_anchor::TrustAnchor;
 pub use self::verifier::Verifier;
+#[cfg(any(feature = "openssl", feature = "ring"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "openssl", feature = "ring"))))]
+pub use self::zonemd::{verify_zonemd, zonemd_digest, ZonemdError};
 
 #[cfg(all(not(feature = "ring"), feature = "openssl"))]
 #[cfg_attr(docsrs, doc(cfg(all(not(feature = "ring"), feature = "openssl"))))]
@@ -22,6 +22,7 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use crate::error::*;
+use crate::rr::dnssec::rdata::DS;
 use crate::rr::dnssec::{Algorithm, Digest, DigestType};
 use crate::rr::record_data::RData;
 use crate::rr::Name;
@@ -264,6 +265,37 @@ impl DNSKEY {
         Err("Ring or OpenSSL must be enabled for this feature".into())
     }
 
+    /// Creates a DS record for this DNSKEY, for publishing in the parent zone of `name`
+    ///
+    /// The DS record holds the key tag and algorithm of this key along with the digest of the
+    ///  canonical owner name and the DNSKEY RDATA, see `to_digest`. [RFC 4509](https://tools.ietf.org/html/rfc4509)
+    ///  defines SHA-256 and [RFC 6605](https://tools.ietf.org/html/rfc6605) SHA-384 for DS records.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the label of the DNSKEY record, i.e. the apex of the child zone
+    /// * `digest_type` - the `DigestType` with which to create the digest of the key
+    #[cfg(any(feature = "openssl", feature = "ring"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "openssl", feature = "ring"))))]
+    pub fn to_ds(&self, name: &Name, digest_type: DigestType) -> ProtoResult<DS> {
+        let key_tag = self.calculate_key_tag()?;
+        let digest = self.to_digest(name, digest_type)?;
+
+        Ok(DS::new(
+            key_tag,
+            self.algorithm(),
+            digest_type,
+            digest.as_ref().to_vec(),
+        ))
+    }
+
+    /// This will always return an error unless the Ring or OpenSSL features are enabled
+    #[cfg(not(any(feature = "openssl", feature = "ring")))]
+    #[cfg_attr(docsrs, doc(cfg(not(any(feature = "openssl", feature = "ring")))))]
+    pub fn to_ds(&self, _: &Name, _: DigestType) -> ProtoResult<DS> {
+        Err("Ring or OpenSSL must be enabled for this feature".into())
+    }
+
     /// The key tag is calculated as a hash to more quickly lookup a DNSKEY.
     ///
     /// [RFC 2535](https://tools.ietf.org/html/rfc2535), Domain Name System Security Extensions, March 1999
@@ -892,7 +892,7 @@ impl fmt::Display for KEY {
             "{flags} {proto} {alg} {key}",
             flags = self.flags(),
             proto = u8::from(self.protocol),
-            alg = self.algorithm,
+            alg = u8::from(self.algorithm),
             key = data_encoding::BASE64.encode(&self.public_key)
         )
     }
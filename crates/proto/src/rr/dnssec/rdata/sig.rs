@@ -22,7 +22,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::error::*;
 use crate::rr::dnssec::Algorithm;
-use crate::rr::{Name, RecordType};
+use crate::rr::{Name, RecordType, SerialNumber};
 use crate::serialize::binary::*;
 
 /// [RFC 2535](https://tools.ietf.org/html/rfc2535#section-4), Domain Name System Security Extensions, March 1999
@@ -382,6 +382,15 @@ impl SIG {
         self.sig_inception
     }
 
+    /// Returns true if `now`, in seconds since the epoch, is within the inception and expiration of the signature
+    ///
+    /// The times are compared as serial numbers, [RFC 1982](https://tools.ietf.org/html/rfc1982).
+    pub fn is_valid_at(&self, now: u32) -> bool {
+        let now = SerialNumber::from(now);
+        SerialNumber::from(self.sig_inception) <= now
+            && now <= SerialNumber::from(self.sig_expiration)
+    }
+
     /// [RFC 2535](https://tools.ietf.org/html/rfc2535#section-4.1.6), Domain Name System Security Extensions, March 1999
     ///
     /// ```text
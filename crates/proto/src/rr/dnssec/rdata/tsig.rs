@@ -8,6 +8,7 @@
 //! TSIG for secret key authentication of transaction
 use std::convert::TryInto;
 use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
@@ -23,6 +24,9 @@ use crate::rr::record_type::RecordType;
 use crate::rr::{Name, Record};
 use crate::serialize::binary::*;
 
+/// Default fudge, in seconds, of the TSIG produced by `sign`, as recommended by RFC 8945
+pub const DEFAULT_FUDGE: u16 = 300;
+
 /// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-4.2)
 ///
 /// ```text
@@ -261,6 +265,21 @@ impl TSIG {
         &self.algorithm
     }
 
+    /// Returns the original id of the message, before any forwarding
+    pub fn oid(&self) -> u16 {
+        self.oid
+    }
+
+    /// Returns the extended error code, e.g. BADSIG (16), BADKEY (17) or BADTIME (18)
+    pub fn error(&self) -> u16 {
+        self.error
+    }
+
+    /// Returns the other data, for BADTIME this is the current time of the server
+    pub fn other(&self) -> &[u8] {
+        &self.other
+    }
+
     /// Emit TSIG RR and RDATA as used for computing MAC
     ///
     /// ```text
@@ -714,11 +733,13 @@ pub fn signed_bitmessage_to_buf(
         Query::read(&mut decoder)?;
     }
 
-    // read all records except for the last one (tsig)
+    // read all records except for the last one (tsig), including the OPT in the additional section
     let record_count = header.answer_count() as usize
         + header.name_server_count() as usize
         + header.additional_count() as usize;
-    Message::read_records(&mut decoder, record_count, false)?;
+    for _ in 0..record_count {
+        Record::read(&mut decoder)?;
+    }
 
     // keep position of data end
     let end_data = message.len() - decoder.len();
@@ -760,6 +781,95 @@ pub fn signed_bitmessage_to_buf(
     Ok((buf, sig))
 }
 
+/// Return the byte-message to be authenticated with the TSIG of a message following the first in
+/// a multi-message response, e.g. AXFR
+///
+/// [RFC 8945, Secret Key Transaction Authentication for DNS](https://tools.ietf.org/html/rfc8945#section-5.3.1)
+///
+/// ```text
+///    The digest components used to generate a TSIG on a
+///    subsequent message in a multiple message response are:
+///
+///        +==================+================+========================+
+///        | Source           | Field Name     | Notes                  |
+///        +==================+================+========================+
+///        | Previous TSIG RR | MAC Size       |                        |
+///        +------------------+----------------+------------------------+
+///        | Previous TSIG RR | MAC            |                        |
+///        +------------------+----------------+------------------------+
+///        | DNS Message      | All            | Excluding TSIG         |
+///        +------------------+----------------+------------------------+
+///        | TSIG RR          | Timers         | See Section 4.3.3      |
+///        +------------------+----------------+------------------------+
+/// ```
+///
+/// # Arguments
+///
+/// * `previous_hash` - hash of the previous message in the response
+/// * `message` - the message to authenticate. Should not be modified after calling chained_message_tbs
+/// except for adding the TSIG record
+/// * `pre_tsig` - TSIG rrdata, possibly with missing mac, only the time and fudge are used
+pub fn chained_message_tbs<M: BinEncodable>(
+    previous_hash: &[u8],
+    message: &M,
+    pre_tsig: &TSIG,
+) -> ProtoResult<Vec<u8>> {
+    let mut buf: Vec<u8> = Vec::with_capacity(512);
+    let mut encoder: BinEncoder<'_> = BinEncoder::with_mode(&mut buf, EncodeMode::Normal);
+
+    encoder.emit_u16(previous_hash.len() as u16)?;
+    encoder.emit_vec(previous_hash)?;
+    message.emit(&mut encoder)?;
+    encoder.emit_u16((pre_tsig.time >> 32) as u16)?;
+    encoder.emit_u32(pre_tsig.time as u32)?;
+    encoder.emit_u16(pre_tsig.fudge)?;
+    Ok(buf)
+}
+
+/// Signs the request with a TSIG, using the current time and the default fudge of 300 seconds
+///
+/// The TSIG record is appended to the additional section of the message, it must be the last record added.
+///
+/// # Arguments
+///
+/// * `message` - the request to sign
+/// * `key_name` - name of the key, should be the same as the name known by the remote server
+/// * `algorithm` - algorithm of the MAC, must be `supported()`
+/// * `key` - the shared secret
+///
+/// # Returns
+///
+/// The MAC of the request, which is needed to verify the response.
+pub fn sign(
+    message: &mut Message,
+    key_name: &Name,
+    algorithm: TsigAlgorithm,
+    key: &[u8],
+) -> ProtoResult<Vec<u8>> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| ProtoError::from("current time is before the unix epoch"))?
+        .as_secs();
+
+    let pre_tsig = TSIG::new(
+        algorithm,
+        now,
+        DEFAULT_FUDGE,
+        Vec::new(),
+        message.id(),
+        0,
+        Vec::new(),
+    );
+    let tbs = message_tbs(None, message, &pre_tsig, key_name)?;
+    let mac = pre_tsig.algorithm.mac_data(key, &tbs)?;
+
+    message.add_tsig(make_tsig_record(
+        key_name.clone(),
+        pre_tsig.set_mac(mac.clone()),
+    ));
+    Ok(mac)
+}
+
 /// Helper function to make a TSIG record from the name of the key, and the TSIG RData
 pub fn make_tsig_record(name: Name, rdata: TSIG) -> Record {
     // https://tools.ietf.org/html/rfc8945#section-4.2
@@ -864,6 +974,68 @@ mod tests {
         assert_eq!(tbs, tbv);
     }
 
+    #[test]
+    fn test_chained_encode() {
+        let mut message = Message::new();
+        message.add_answer(Record::new());
+
+        let key_name = Name::from_ascii("some.name").unwrap();
+        let previous_hash = b"previous signature";
+
+        let pre_tsig = TSIG::new(
+            TsigAlgorithm::HmacSha256,
+            12345,
+            60,
+            vec![],
+            message.id(),
+            0,
+            vec![],
+        );
+
+        let tbs = chained_message_tbs(previous_hash, &message, &pre_tsig).unwrap();
+
+        let pre_tsig = pre_tsig.set_mac(b"some signature".to_vec());
+        message.add_tsig(make_tsig_record(key_name, pre_tsig));
+
+        let message_byte = message.to_bytes().unwrap();
+
+        let tbv = signed_bitmessage_to_buf(Some(previous_hash), &message_byte, false)
+            .unwrap()
+            .0;
+
+        assert_eq!(tbs, tbv);
+    }
+
+    #[test]
+    #[cfg(any(feature = "ring", feature = "openssl"))]
+    fn test_sign() {
+        let mut message = Message::new();
+        message.set_id(123).add_query(Query::query(
+            Name::from_ascii("example.com.").unwrap(),
+            RecordType::AXFR,
+        ));
+
+        let key_name = Name::from_ascii("some.name").unwrap();
+        let key = &[0, 1, 2, 3, 4];
+
+        let mac = sign(&mut message, &key_name, TsigAlgorithm::HmacSha256, key).unwrap();
+
+        let message_byte = message.to_bytes().unwrap();
+        let (tbv, record) = signed_bitmessage_to_buf(None, &message_byte, true).unwrap();
+        let tsig = match record.data() {
+            Some(RData::DNSSEC(DNSSECRData::TSIG(tsig))) => tsig,
+            _ => panic!("expected a TSIG"),
+        };
+
+        assert_eq!(record.name(), &key_name);
+        assert_eq!(tsig.mac(), &mac[..]);
+        assert_eq!(tsig.fudge(), DEFAULT_FUDGE);
+        assert_eq!(tsig.oid(), 123);
+        TsigAlgorithm::HmacSha256
+            .verify_mac(key, &tbv, &mac)
+            .expect("did not verify");
+    }
+
     #[test]
     fn test_sign_encode_id_changed() {
         let mut message = Message::new();
@@ -44,10 +44,21 @@ impl SupportedAlgorithms {
     }
 
     /// Specify the entire set is supported
+    ///
+    /// This is the set which can be verified with the compiled-in backends, `ED25519` is only included with the
+    ///  `ring` feature, and `ED448` with the `openssl` feature.
     pub fn all() -> Self {
-        Self {
-            bit_map: 0b0111_1111,
-        }
+        #[allow(unused_mut)]
+        let mut supported = Self {
+            bit_map: 0b0011_1111,
+        };
+
+        #[cfg(feature = "ring")]
+        supported.set(Algorithm::ED25519);
+        #[cfg(feature = "openssl")]
+        supported.set(Algorithm::ED448);
+
+        supported
     }
 
     /// Based on the set of Algorithms, return the supported set
@@ -72,6 +83,7 @@ impl SupportedAlgorithms {
             Algorithm::ECDSAP256SHA256 => Some(4),
             Algorithm::ECDSAP384SHA384 => Some(5),
             Algorithm::ED25519 => Some(6),
+            Algorithm::ED448 => Some(7),
             Algorithm::RSAMD5 | Algorithm::DSA | Algorithm::Unknown(_) => None,
         };
 
@@ -89,6 +101,7 @@ impl SupportedAlgorithms {
             4 => Some(Algorithm::ECDSAP256SHA256),
             5 => Some(Algorithm::ECDSAP384SHA384),
             6 => Some(Algorithm::ED25519),
+            7 => Some(Algorithm::ED448),
             _ => None,
         }
     }
@@ -160,7 +173,7 @@ impl<'a> From<&'a [u8]> for SupportedAlgorithms {
 
 impl<'a> From<&'a SupportedAlgorithms> for Vec<u8> {
     fn from(value: &'a SupportedAlgorithms) -> Self {
-        let mut bytes = Self::with_capacity(8); // today this is less than 8
+        let mut bytes = Self::with_capacity(8); // today this is at most 8
 
         for a in value.iter() {
             bytes.push(a.into());
@@ -242,7 +255,10 @@ fn test_has() {
 
 fn test_iterator() {
     let supported = SupportedAlgorithms::all();
-    assert_eq!(supported.iter().count(), 7);
+    assert_eq!(
+        supported.iter().count(),
+        6 + usize::from(cfg!(feature = "ring")) + usize::from(cfg!(feature = "openssl"))
+    );
 
     // it just so happens that the iterator has a fixed order...
     let supported = SupportedAlgorithms::all();
@@ -253,7 +269,11 @@ fn test_iterator() {
     assert_eq!(iter.next(), Some(Algorithm::RSASHA512));
     assert_eq!(iter.next(), Some(Algorithm::ECDSAP256SHA256));
     assert_eq!(iter.next(), Some(Algorithm::ECDSAP384SHA384));
+    #[cfg(feature = "ring")]
     assert_eq!(iter.next(), Some(Algorithm::ED25519));
+    #[cfg(feature = "openssl")]
+    assert_eq!(iter.next(), Some(Algorithm::ED448));
+    assert_eq!(iter.next(), None);
 
     let mut supported = SupportedAlgorithms::new();
     supported.set(Algorithm::RSASHA256);
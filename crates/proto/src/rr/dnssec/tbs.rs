@@ -2,9 +2,11 @@
 
 use super::rdata::{sig, DNSSECRData, SIG};
 use crate::error::*;
+use crate::op::{Header, Query};
 use crate::rr::dnssec::Algorithm;
+use crate::rr::resource::sort_canonical;
 use crate::rr::{DNSClass, Name, RData, Record, RecordType};
-use crate::serialize::binary::{BinEncodable, BinEncoder, EncodeMode};
+use crate::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder, EncodeMode};
 
 /// Data To Be Signed.
 pub struct TBS(Vec<u8>);
@@ -55,6 +57,82 @@ pub fn message_tbs<M: BinEncodable>(message: &M, pre_sig0: &SIG) -> ProtoResult<
     Ok(TBS(buf))
 }
 
+/// Returns the to-be-signed serialization of a message as it was received, with the SIG(0) that signs it.
+///
+/// [RFC 2931](https://tools.ietf.org/html/rfc2931#section-3.1), the signature is over the RDATA of the SIG(0),
+///  less the signature, followed by the message as it was sent, with the SIG(0) removed and the ARCOUNT
+///  decremented by one. Unlike `message_tbs`, the message is not encoded again, so a signature over a
+///  message compressed differently than this library would can still be verified.
+///
+/// # Arguments
+///
+/// * `message` - the message as received, the SIG(0) must be the last record
+///
+/// # Returns
+///
+/// The data to be verified, and the SIG(0)
+pub fn signed_message_tbs(message: &[u8]) -> ProtoResult<(TBS, SIG)> {
+    let mut decoder = BinDecoder::new(message);
+
+    // remove the sig0 from the Additional count
+    let mut header = Header::read(&mut decoder)?;
+    let adc = header.additional_count();
+    if adc > 0 {
+        header.set_additional_count(adc - 1);
+    } else {
+        return Err("missing sig0 from message that must be verified".into());
+    }
+
+    let start_data = message.len() - decoder.len();
+
+    for _ in 0..header.query_count() {
+        Query::read(&mut decoder)?;
+    }
+
+    // read all records except for the last one, the sig0, including the OPT in the additional section
+    let record_count = header.answer_count() as usize
+        + header.name_server_count() as usize
+        + header.additional_count() as usize;
+    for _ in 0..record_count {
+        Record::read(&mut decoder)?;
+    }
+
+    let end_data = message.len() - decoder.len();
+
+    let sig0 = match Record::read(&mut decoder)?.into_data() {
+        Some(RData::DNSSEC(DNSSECRData::SIG(sig))) if sig.type_covered() == RecordType::ZERO => sig,
+        _ => return Err("signature is not sig0".into()),
+    };
+
+    let mut buf: Vec<u8> = Vec::with_capacity(message.len() + 64);
+    {
+        let mut encoder: BinEncoder<'_> = BinEncoder::with_mode(&mut buf, EncodeMode::Normal);
+        sig::emit_pre_sig(
+            &mut encoder,
+            sig0.type_covered(),
+            sig0.algorithm(),
+            sig0.num_labels(),
+            sig0.original_ttl(),
+            sig0.sig_expiration(),
+            sig0.sig_inception(),
+            sig0.key_tag(),
+            sig0.signer_name(),
+        )?;
+    }
+
+    // the header is emitted separately, as the encoding above is of names in canonical form, which
+    //  must not be used as pointers for compression
+    let mut message_buf: Vec<u8> = Vec::with_capacity(message.len());
+    {
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut message_buf);
+        header.emit(&mut encoder)?;
+        encoder.emit_vec(&message[start_data..end_data])?;
+    }
+
+    buf.append(&mut message_buf);
+    Ok((TBS(buf), sig0))
+}
+
 /// Returns the to-be-signed serialization of the given record set.
 ///
 /// # Arguments
@@ -101,8 +179,9 @@ pub fn rrset_tbs(
         }
     }
 
-    // put records in canonical order
-    rrset.sort();
+    // put records in canonical order, removing duplicates, RFC 4034 section 6.3
+    sort_canonical(&mut rrset);
+    rrset.dedup_by(|a, b| a.data() == b.data());
 
     let name = determine_name(name, num_labels)?;
 
@@ -18,13 +18,14 @@
 
 use std::default::Default;
 
+use crate::rr::dnssec::rdata::DNSKEY;
 use crate::rr::dnssec::PublicKey;
 
 const ROOT_ANCHOR_ORIG: &[u8] = include_bytes!("roots/19036.rsa");
 const ROOT_ANCHOR_2018: &[u8] = include_bytes!("roots/20326.rsa");
 
 /// The root set of trust anchors for validating DNSSec, anything in this set will be trusted
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TrustAnchor {
     // TODO: these should also store some information, or more specifically, metadata from the signed
     //  public certificate.
@@ -66,6 +67,17 @@ impl TrustAnchor {
         }
     }
 
+    /// inserts the public key of the DNSKEY to the trusted chain
+    ///
+    /// This seeds the trust anchor with a key of a zone, e.g. the KSK of the root zone as published
+    ///  by IANA, validation of the zones below it builds the chain of DS and DNSKEY records down
+    ///  from this key.
+    pub fn insert_dnskey(&mut self, dnskey: &DNSKEY) {
+        if !self.contains_dnskey_bytes(dnskey.public_key()) {
+            self.pkeys.push(dnskey.public_key().to_vec())
+        }
+    }
+
     /// get the trust anchor at the specified index
     pub fn get(&self, idx: usize) -> &[u8] {
         &self.pkeys[idx]
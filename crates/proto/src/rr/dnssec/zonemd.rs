@@ -0,0 +1,431 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Calculation and verification of ZONEMD zone digests, [RFC 8976](https://tools.ietf.org/html/rfc8976)
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use super::rdata::DNSSECRData;
+use super::DigestType;
+use crate::error::*;
+use crate::rr::rdata::zonemd::{HashAlgorithm, Scheme};
+use crate::rr::{Name, RData, Record, RecordType};
+use crate::serialize::binary::{BinEncodable, BinEncoder};
+
+/// An error verifying the ZONEMD records of a zone
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum ZonemdError {
+    /// The zone must have exactly one SOA record, at its apex
+    #[error("zone must have exactly one SOA record, found {0}")]
+    Soa(usize),
+
+    /// There are no ZONEMD records at the apex of the zone
+    #[error("no ZONEMD record at the zone apex: {0}")]
+    Missing(Name),
+
+    /// More than one ZONEMD record has the same scheme and hash algorithm
+    #[error("more than one ZONEMD record with scheme {0:?} and hash algorithm {1:?}")]
+    Duplicate(Scheme, HashAlgorithm),
+
+    /// None of the ZONEMD records use a supported scheme and hash algorithm
+    #[error("no ZONEMD record with a supported scheme and hash algorithm")]
+    Unsupported,
+
+    /// The serial of the ZONEMD records does not match the SOA serial
+    #[error("ZONEMD serial {zonemd} does not match the SOA serial {soa}")]
+    Serial {
+        /// the serial of the ZONEMD record
+        zonemd: u32,
+        /// the serial of the SOA record
+        soa: u32,
+    },
+
+    /// The digest calculated over the zone does not match the ZONEMD record
+    #[error("ZONEMD digest does not match the zone")]
+    Digest,
+
+    /// The zone could not be serialized for the digest
+    #[error("proto error: {0}")]
+    Proto(#[from] ProtoError),
+}
+
+/// Verifies the ZONEMD records at the apex of a zone
+///
+/// The `records` are all the records of the zone, its apex is the owner of the only SOA record.
+///  Verification succeeds if the digest of any ZONEMD record with the SOA serial and a supported
+///  scheme and hash algorithm matches the zone. Only the SIMPLE scheme, with the SHA-384 and
+///  SHA-512 hash algorithms, is supported.
+///
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-4), Message Digest for DNS Zones, February 2021
+///
+/// ```text
+/// 4.  Verifying Zone Digest
+///
+///    The recipient of a zone that has a ZONEMD RR can verify the zone by
+///    calculating the digest as follows.  If multiple ZONEMD RRs are
+///    present in the zone, e.g., during an algorithm rollover, a match
+///    using any one of the recipient's supported Scheme and Hash Algorithm
+///    algorithms is sufficient to verify the zone.  The verifier MAY ignore
+///    a ZONEMD RR if its Scheme and Hash Algorithm violates local policy.
+/// ```
+pub fn verify_zonemd(records: &[Record]) -> Result<(), ZonemdError> {
+    let soa = soa(records)?;
+    let apex = soa.name();
+    let serial = match soa.data() {
+        Some(RData::SOA(soa)) => soa.serial(),
+        _ => return Err(ZonemdError::Soa(0)),
+    };
+
+    let zonemds = records
+        .iter()
+        .filter(|r| r.rr_type() == RecordType::ZONEMD && r.name() == apex)
+        .filter_map(|r| match r.data() {
+            Some(RData::ZONEMD(zonemd)) => Some(zonemd),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if zonemds.is_empty() {
+        return Err(ZonemdError::Missing(apex.clone()));
+    }
+
+    let mut schemes = HashSet::new();
+    for zonemd in &zonemds {
+        if !schemes.insert((zonemd.scheme(), zonemd.hash_algorithm())) {
+            return Err(ZonemdError::Duplicate(
+                zonemd.scheme(),
+                zonemd.hash_algorithm(),
+            ));
+        }
+    }
+
+    let mut error = ZonemdError::Unsupported;
+    for zonemd in zonemds {
+        if zonemd.serial() != serial {
+            error = ZonemdError::Serial {
+                zonemd: zonemd.serial(),
+                soa: serial,
+            };
+            continue;
+        }
+        if zonemd.scheme() != Scheme::Simple
+            || zonemd.hash_algorithm().digest_len() != Some(zonemd.digest().len())
+        {
+            continue;
+        }
+
+        if zonemd_digest(records, zonemd.hash_algorithm())? == zonemd.digest() {
+            return Ok(());
+        }
+        error = ZonemdError::Digest;
+    }
+
+    Err(error)
+}
+
+/// Calculates the digest of a zone with the SIMPLE scheme
+///
+/// The apex ZONEMD records, and the RRSIG covering them, are excluded from the digest. Records
+///  outside of the zone are ignored.
+///
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-3.3.1), Message Digest for DNS Zones, February 2021
+///
+/// ```text
+/// 3.3.1.1.  SIMPLE Scheme Inclusion/Exclusion Rules
+///
+///    When iterating over records in the zone, the following inclusion/
+///    exclusion rules apply:
+///
+///    *  All records in the zone, including glue records, MUST be included
+///       unless excluded by a subsequent rule.
+///
+///    *  Occluded data ([RFC5936], Section 3.5) MUST be included.
+///
+///    *  If there are duplicate RRs with equal owner, class, type, and
+///       RDATA, only one instance is included ([RFC4034], Section 6.3) and
+///       the duplicates MUST be omitted.
+///
+///    *  The placeholder apex ZONEMD RR(s) MUST NOT be included.
+///
+///    *  If the zone is signed, DNSSEC RRs MUST be included, except:
+///
+///    *  The RRSIG covering the apex ZONEMD RRset MUST NOT be included
+///       because the RRSIG will be updated after all digests have been
+///       calculated.
+///
+/// 3.3.1.2.  SIMPLE Scheme Digest Calculation
+///
+///    A zone digest using the SIMPLE scheme is calculated by concatenating
+///    all RRs in the zone, in the format given in [RFC4034],
+///    Section 6.2, and in the order given in [RFC4034],
+///    Section 6.3, and then applying the hash algorithm to this data.
+/// ```
+pub fn zonemd_digest(
+    records: &[Record],
+    hash_algorithm: HashAlgorithm,
+) -> Result<Vec<u8>, ZonemdError> {
+    let digest_type = match hash_algorithm {
+        HashAlgorithm::SHA384 => DigestType::SHA384,
+        HashAlgorithm::SHA512 => DigestType::SHA512,
+        HashAlgorithm::Private(_) | HashAlgorithm::Unassigned(_) => {
+            return Err(ZonemdError::Unsupported)
+        }
+    };
+    let apex = soa(records)?.name();
+
+    let mut zone = records
+        .iter()
+        .filter(|r| apex.zone_of(r.name()))
+        .filter(|r| !(r.name() == apex && is_zonemd_or_signature(r)))
+        .map(|r| {
+            let rdata = r.data().map(RData::to_canonical_bytes).unwrap_or_default();
+            (r, rdata)
+        })
+        .collect::<Vec<_>>();
+
+    // canonical order of the zone, RFC 4034 section 6.3, removing duplicates
+    zone.sort_by(|(a, a_rdata), (b, b_rdata)| {
+        a.name()
+            .cmp(b.name())
+            .then_with(|| u16::from(a.dns_class()).cmp(&u16::from(b.dns_class())))
+            .then_with(|| u16::from(a.rr_type()).cmp(&u16::from(b.rr_type())))
+            .then_with(|| a_rdata.cmp(b_rdata))
+    });
+    zone.dedup_by(|(a, a_rdata), (b, b_rdata)| {
+        a.name() == b.name()
+            && a.dns_class() == b.dns_class()
+            && a.rr_type() == b.rr_type()
+            && a_rdata == b_rdata
+    });
+
+    let mut buf: Vec<u8> = Vec::new();
+    {
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut buf);
+        encoder.set_canonical_names(true);
+
+        // RR(i) = owner | type | class | TTL | RDATA length | RDATA, RFC 4034 section 6.2
+        for (record, rdata) in zone {
+            record
+                .name()
+                .to_lowercase()
+                .emit_as_canonical(&mut encoder, true)?;
+            record.rr_type().emit(&mut encoder)?;
+            record.dns_class().emit(&mut encoder)?;
+            encoder.emit_u32(record.ttl())?;
+            encoder.emit_u16(rdata.len() as u16)?;
+            encoder.emit_vec(&rdata)?;
+        }
+    }
+
+    Ok(digest_type.hash(&buf)?.as_ref().to_vec())
+}
+
+/// The only SOA record of the zone
+fn soa(records: &[Record]) -> Result<&Record, ZonemdError> {
+    let mut soas = records.iter().filter(|r| r.rr_type() == RecordType::SOA);
+    match (soas.next(), soas.count()) {
+        (Some(soa), 0) => Ok(soa),
+        (soa, count) => Err(ZonemdError::Soa(count + soa.map_or(0, |_| 1))),
+    }
+}
+
+fn is_zonemd_or_signature(record: &Record) -> bool {
+    match record.data() {
+        Some(RData::ZONEMD(..)) => true,
+        Some(RData::DNSSEC(DNSSECRData::SIG(sig))) => {
+            record.rr_type() == RecordType::RRSIG && sig.type_covered() == RecordType::ZONEMD
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::rr::rdata::{SOA, ZONEMD};
+
+    fn zone(zonemd: ZONEMD) -> Vec<Record> {
+        let origin = Name::from_str("example.").unwrap();
+        let ns1 = Name::from_str("ns1.example.").unwrap();
+        let ns2 = Name::from_str("ns2.example.").unwrap();
+        vec![
+            Record::from_rdata(
+                origin.clone(),
+                86400,
+                RData::SOA(SOA::new(
+                    ns1.clone(),
+                    Name::from_str("admin.example.").unwrap(),
+                    2018031900,
+                    1800,
+                    900,
+                    604800,
+                    86400,
+                )),
+            ),
+            Record::from_rdata(origin.clone(), 86400, RData::NS(ns1.clone())),
+            Record::from_rdata(origin.clone(), 86400, RData::NS(ns2.clone())),
+            Record::from_rdata(origin, 86400, RData::ZONEMD(zonemd)),
+            Record::from_rdata(ns1, 3600, RData::A([203, 0, 113, 63].into())),
+            Record::from_rdata(ns2, 3600, RData::AAAA("2001:db8::63".parse().unwrap())),
+        ]
+    }
+
+    /// RFC 8976, Appendix A.1. Simple EXAMPLE Zone
+    fn simple_zonemd() -> ZONEMD {
+        ZONEMD::new(
+            2018031900,
+            Scheme::Simple,
+            HashAlgorithm::SHA384,
+            vec![
+                0xc6, 0x80, 0x90, 0xd9, 0x0a, 0x7a, 0xed, 0x71, 0x6b, 0xc4, 0x59, 0xf9, 0x34, 0x0e,
+                0x3d, 0x7c, 0x13, 0x70, 0xd4, 0xd2, 0x4b, 0x7e, 0x2f, 0xc3, 0xa1, 0xdd, 0xc0, 0xb9,
+                0xa8, 0x71, 0x53, 0xb9, 0xa9, 0x71, 0x3b, 0x3c, 0x9a, 0xe5, 0xcc, 0x27, 0x77, 0x7f,
+                0x98, 0xb8, 0xe7, 0x30, 0x04, 0x4c,
+            ],
+        )
+    }
+
+    #[test]
+    fn test_verify_simple_zone() {
+        let records = zone(simple_zonemd());
+        verify_zonemd(&records).expect("zone should verify");
+
+        // order, case and duplicates are irrelevant
+        let mut shuffled = records.clone();
+        shuffled.reverse();
+        shuffled.push(records[4].clone());
+        shuffled[0].set_name(Name::from_str("NS2.Example.").unwrap());
+        verify_zonemd(&shuffled).expect("zone should verify");
+
+        // out of zone data is ignored
+        let mut out_of_zone = records;
+        out_of_zone.push(Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            3600,
+            RData::A([192, 0, 2, 1].into()),
+        ));
+        verify_zonemd(&out_of_zone).expect("zone should verify");
+    }
+
+    #[test]
+    fn test_verify_tampered_zone() {
+        let mut records = zone(simple_zonemd());
+        records[4].set_data(Some(RData::A([203, 0, 113, 64].into())));
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Digest
+        ));
+
+        let mut records = zone(simple_zonemd());
+        records[5].set_ttl(3601);
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Digest
+        ));
+
+        let mut records = zone(simple_zonemd());
+        records.push(Record::from_rdata(
+            Name::from_str("www.example.").unwrap(),
+            3600,
+            RData::A([192, 0, 2, 1].into()),
+        ));
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Digest
+        ));
+    }
+
+    #[test]
+    fn test_verify_sha512() {
+        let records = zone(simple_zonemd());
+        let digest = zonemd_digest(&records, HashAlgorithm::SHA512).unwrap();
+        assert_eq!(digest.len(), 64);
+
+        // a failing SHA-384 digest is superseded by a matching SHA-512 digest
+        let mut sha384 = simple_zonemd().digest().to_vec();
+        sha384[0] ^= 0xff;
+        let mut records = zone(ZONEMD::new(
+            2018031900,
+            Scheme::Simple,
+            HashAlgorithm::SHA384,
+            sha384,
+        ));
+        records.push(Record::from_rdata(
+            Name::from_str("example.").unwrap(),
+            86400,
+            RData::ZONEMD(ZONEMD::new(
+                2018031900,
+                Scheme::Simple,
+                HashAlgorithm::SHA512,
+                digest,
+            )),
+        ));
+        verify_zonemd(&records).expect("zone should verify");
+    }
+
+    #[test]
+    fn test_verify_errors() {
+        let records = zone(ZONEMD::new(
+            2018031901,
+            Scheme::Simple,
+            HashAlgorithm::SHA384,
+            simple_zonemd().digest().to_vec(),
+        ));
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Serial {
+                zonemd: 2018031901,
+                soa: 2018031900
+            }
+        ));
+
+        let records = zone(ZONEMD::new(
+            2018031900,
+            Scheme::Private(240),
+            HashAlgorithm::SHA384,
+            simple_zonemd().digest().to_vec(),
+        ));
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Unsupported
+        ));
+
+        let mut records = zone(simple_zonemd());
+        records.push(Record::from_rdata(
+            Name::from_str("example.").unwrap(),
+            86400,
+            RData::ZONEMD(ZONEMD::new(
+                2018031900,
+                Scheme::Simple,
+                HashAlgorithm::SHA384,
+                vec![0; 48],
+            )),
+        ));
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Duplicate(Scheme::Simple, HashAlgorithm::SHA384)
+        ));
+
+        let mut records = zone(simple_zonemd());
+        records.remove(3);
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Missing(..)
+        ));
+
+        let mut records = zone(simple_zonemd());
+        records.remove(0);
+        assert!(matches!(
+            verify_zonemd(&records).unwrap_err(),
+            ZonemdError::Soa(0)
+        ));
+    }
+}
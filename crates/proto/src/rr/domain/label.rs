@@ -60,14 +60,15 @@ impl Label {
             return Self::from_ascii(s);
         }
 
+        // UTS-46 nontransitional processing, as aligned with IDNA2008
         match idna::Config::default()
             .use_std3_ascii_rules(true)
-            .transitional_processing(true)
+            .transitional_processing(false)
             .verify_dns_length(true)
             .to_ascii(s)
         {
             Ok(puny) => Self::from_ascii(&puny),
-            e => Err(format!("Label contains invalid characters: {:?}", e).into()),
+            Err(e) => Err(format!("Label failed IDNA validation: {}: {:?}", s, e).into()),
         }
     }
 
@@ -268,6 +268,40 @@ impl Name {
         }
     }
 
+    /// Creates a new Name with the case of each ASCII letter chosen at random
+    ///
+    /// This is the DNS 0x20 encoding of [draft-vixie-dnsext-dns0x20](https://tools.ietf.org/html/draft-vixie-dnsext-dns0x20-00),
+    ///  a response which does not echo the name with exactly the same case is likely spoofed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use trust_dns_proto::rr::domain::Name;
+    ///
+    /// let name = Name::from_ascii("www.example.com.").unwrap();
+    /// let random = name.to_random_case();
+    /// assert_eq!(random, name);
+    /// assert!(random.to_lowercase().eq_case(&name));
+    /// ```
+    pub fn to_random_case(&self) -> Self {
+        let new_label_data = self
+            .label_data
+            .iter()
+            .map(|c| {
+                if rand::random() {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect();
+        Self {
+            is_fqdn: self.is_fqdn,
+            label_data: new_label_data,
+            label_ends: self.label_ends.clone(),
+        }
+    }
+
     /// Trims off the first part of the name, to help with searching for the domain piece
     ///
     /// # Examples
@@ -730,6 +764,44 @@ impl Name {
         format!("{}", self)
     }
 
+    /// Converts the Name labels to unicode, the inverse of `to_ascii` for IDNA names.
+    ///
+    /// Labels with the `xn--` prefix are decoded from punycode with UTS-46 processing, any label which
+    ///  is not valid IDNA is left in its escaped ascii form. This is equivalent to `to_utf8`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use trust_dns_proto::rr::Name;
+    ///
+    /// let name = Name::from_utf8("bücher.example.").unwrap();
+    /// assert_eq!(name.to_ascii(), "xn--bcher-kva.example.");
+    /// assert_eq!(name.to_unicode(), "bücher.example.");
+    /// ```
+    pub fn to_unicode(&self) -> String {
+        self.to_utf8()
+    }
+
+    /// Creates the name of the PTR record of the address, for reverse lookups
+    ///
+    /// The octets of an IPv4 address are reversed under `in-addr.arpa.`, the nibbles of an IPv6 address under
+    ///  `ip6.arpa.`, see [RFC 1035](https://tools.ietf.org/html/rfc1035#section-3.5) and
+    ///  [RFC 3596](https://tools.ietf.org/html/rfc3596#section-2.5).
+    ///
+    /// ```
+    /// use std::net::{IpAddr, Ipv4Addr};
+    /// use trust_dns_proto::rr::domain::Name;
+    ///
+    /// let name = Name::from_ip(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)));
+    /// assert_eq!(name.to_string(), "1.2.0.192.in-addr.arpa.");
+    /// ```
+    pub fn from_ip(ip: IpAddr) -> Self {
+        match ip {
+            IpAddr::V4(ip) => ip.into(),
+            IpAddr::V6(ip) => ip.into(),
+        }
+    }
+
     /// Converts a *.arpa Name in a PTR record back into an IpNet if possible.
     pub fn parse_arpa_name(&self) -> Result<IpNet, ProtoError> {
         if !self.is_fqdn() {
@@ -986,10 +1058,7 @@ impl<'a> IntoIterator for &'a Name {
 
 impl From<IpAddr> for Name {
     fn from(addr: IpAddr) -> Self {
-        match addr {
-            IpAddr::V4(ip) => ip.into(),
-            IpAddr::V6(ip) => ip.into(),
-        }
+        Self::from_ip(addr)
     }
 }
 
@@ -1098,6 +1167,55 @@ impl BinEncodable for Name {
     }
 }
 
+/// An iterator over the labels of a name, borrowed from the message it was read from
+///
+/// This is returned by [`BinDecoder::read_name_labels`], the name has already been validated, and
+///  compression pointers are followed as the labels are iterated. No labels are copied, which
+///  suits forwarders that only inspect the names of the messages they pass on, see
+///  [`Name::from_labels`] to collect them into an owned `Name`.
+#[derive(Clone)]
+pub struct LabelSlices<'a> {
+    buffer: &'a [u8],
+    index: usize,
+}
+
+impl<'a> LabelSlices<'a> {
+    /// Reads and validates the name at the current position of the decoder
+    pub(crate) fn read(decoder: &mut BinDecoder<'a>) -> Result<Self, DecodeError> {
+        let index = decoder.index();
+        read_inner(decoder, None, 0, &mut 0, &mut |_| Ok(()))?;
+
+        Ok(Self {
+            buffer: decoder.slice_from(0)?,
+            index,
+        })
+    }
+}
+
+impl<'a> Iterator for LabelSlices<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // the name was validated when read, so neither the lengths nor the pointers are checked again
+        loop {
+            let byte = *self.buffer.get(self.index)?;
+            match byte {
+                0 => return None,
+                byte if byte & 0b1100_0000 == 0b1100_0000 => {
+                    let low = *self.buffer.get(self.index + 1)?;
+                    self.index = (usize::from(byte & 0b0011_1111) << 8) | usize::from(low);
+                }
+                len => {
+                    let start = self.index + 1;
+                    let end = start + usize::from(len);
+                    self.index = end;
+                    return self.buffer.get(start..end);
+                }
+            }
+        }
+    }
+}
+
 impl<'r> BinDecodable<'r> for Name {
     /// parses the chain of labels
     ///  this has a max of 255 octets, with each label being less than 63.
@@ -1106,16 +1224,32 @@ impl<'r> BinDecodable<'r> for Name {
     fn read(decoder: &mut BinDecoder<'r>) -> ProtoResult<Self> {
         let mut name = Self::root(); // this is FQDN
 
-        read_inner(decoder, &mut name, None)?;
+        read_inner(decoder, None, 0, &mut 0, &mut |label| {
+            name.extend_name(label)
+                .map_err(|_| DecodeError::DomainNameTooLong(label.len()))
+        })?;
         Ok(name)
     }
 }
 
-fn read_inner(
-    decoder: &mut BinDecoder<'_>,
-    name: &mut Name,
+/// The maximum number of compression pointers followed while reading a single name
+///
+/// A name has at most 127 labels, so no valid message should need more than this.
+const MAX_POINTER_JUMPS: usize = 128;
+
+/// Reads the labels of a name, passing each one to `on_label`
+///
+/// `len` is the length of the name read so far, as in `Name::len`.
+fn read_inner<'a, F>(
+    decoder: &mut BinDecoder<'a>,
     max_idx: Option<usize>,
-) -> Result<(), DecodeError> {
+    jumps: usize,
+    len: &mut usize,
+    on_label: &mut F,
+) -> Result<(), DecodeError>
+where
+    F: FnMut(&'a [u8]) -> Result<(), DecodeError>,
+{
     let mut state: LabelParseState = LabelParseState::LabelLengthOrPointer;
     let name_start = decoder.index();
 
@@ -1155,8 +1289,11 @@ fn read_inner(
                     .verify_unwrap(|l| l.len() <= 63)
                     .map_err(|l| DecodeError::LabelBytesTooLong(l.len()))?;
 
-                name.extend_name(label)
-                    .map_err(|_| DecodeError::DomainNameTooLong(label.len()))?;
+                *len += label.len() + 1;
+                if *len > 255 {
+                    return Err(DecodeError::DomainNameTooLong(label.len()));
+                }
+                on_label(label)?;
 
                 // reset to collect more data
                 LabelParseState::LabelLengthOrPointer
@@ -1183,6 +1320,11 @@ fn read_inner(
             // domain header).  A zero offset specifies the first byte of the ID field,
             // etc.
             LabelParseState::Pointer => {
+                // bound the work done on chains of pointers to pointers
+                if jumps >= MAX_POINTER_JUMPS {
+                    return Err(DecodeError::PointerChainTooLong(MAX_POINTER_JUMPS));
+                }
+
                 let pointer_location = decoder.index();
                 let location = decoder
                     .read_u16()?
@@ -1191,7 +1333,8 @@ fn read_inner(
                         u & 0x3FFF
                     })
                     .verify_unwrap(|ptr| {
-                        // all labels must appear "prior" to this Name
+                        // all labels must appear "prior" to this Name, which also means each pointer
+                        //  strictly moves backward through the message and can not form a cycle
                         (*ptr as usize) < name_start
                     })
                     .map_err(|e| DecodeError::PointerNotPriorToLabel {
@@ -1200,7 +1343,7 @@ fn read_inner(
                     })?;
 
                 let mut pointer = decoder.clone(location);
-                read_inner(&mut pointer, name, Some(name_start))?;
+                read_inner(&mut pointer, Some(name_start), jumps + 1, len, on_label)?;
 
                 // Pointers always finish the name, break like Root.
                 break;
@@ -1214,9 +1357,8 @@ fn read_inner(
     }
 
     // TODO: should we consider checking this while the name is parsed?
-    let len = name.len();
-    if len >= 255 {
-        return Err(DecodeError::DomainNameTooLong(len));
+    if *len >= 255 {
+        return Err(DecodeError::DomainNameTooLong(*len));
     }
 
     Ok(())
@@ -1441,6 +1583,39 @@ mod tests {
         assert_eq!(fourth, r_test);
     }
 
+    #[test]
+    fn test_read_name_labels() {
+        let names = ["ra.rb.rc.", "rb.rc.", "z.ra.rb.rc.", "."]
+            .iter()
+            .map(|n| Name::from_str(n).unwrap())
+            .collect::<Vec<_>>();
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(512);
+        {
+            let mut e = BinEncoder::new(&mut bytes);
+            for name in &names {
+                name.emit(&mut e).unwrap();
+            }
+        }
+
+        let mut d = BinDecoder::new(&bytes);
+        for name in &names {
+            let labels = d.read_name_labels().unwrap();
+            assert!(labels.clone().eq(name.iter()));
+            assert_eq!(&Name::from_labels(labels).unwrap(), name);
+        }
+        assert!(d.is_empty());
+
+        // the same validation as reading a Name
+        let bytes = vec![0x01, b'a', 0xC0, 0x04, 0xC0, 0x00];
+        let mut d = BinDecoder::new(&bytes);
+        d.read_slice(4).unwrap();
+        assert!(matches!(
+            d.read_name_labels(),
+            Err(DecodeError::PointerNotPriorToLabel { idx: 2, ptr: 4 })
+        ));
+    }
+
     #[test]
     fn test_pointer_with_pointer_ending_labels() {
         let mut bytes: Vec<u8> = Vec::with_capacity(512);
@@ -1505,6 +1680,49 @@ mod tests {
         assert!(Name::read(&mut d).is_err());
     }
 
+    #[test]
+    fn test_pointer_cycle() {
+        // "a" at 0 is followed by a pointer to the pointer at 4, which points back to 0
+        let bytes = vec![0x01, b'a', 0xC0, 0x04, 0xC0, 0x00];
+        let mut d = BinDecoder::new(&bytes);
+        d.read_slice(4).unwrap();
+
+        let err = Name::read(&mut d).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ProtoErrorKind::PointerNotPriorToLabel { idx: 2, ptr: 4 }
+        ));
+    }
+
+    #[test]
+    fn test_pointer_chain_limit() {
+        // "a." followed by a chain of pointers, each pointing to the one before it
+        fn chain(jumps: usize) -> Vec<u8> {
+            let mut bytes = vec![0x01, b'a', 0x00];
+            let mut prior = 0_u16;
+            for _ in 0..jumps {
+                let here = bytes.len() as u16;
+                bytes.extend_from_slice(&(0xC000 | prior).to_be_bytes());
+                prior = here;
+            }
+            bytes
+        }
+
+        let bytes = chain(MAX_POINTER_JUMPS);
+        let mut d = BinDecoder::new(&bytes);
+        d.read_slice(bytes.len() - 2).unwrap();
+        assert_eq!(Name::read(&mut d).unwrap(), Name::from_ascii("a.").unwrap());
+
+        let bytes = chain(MAX_POINTER_JUMPS + 1);
+        let mut d = BinDecoder::new(&bytes);
+        d.read_slice(bytes.len() - 2).unwrap();
+        let err = Name::read(&mut d).unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            ProtoErrorKind::PointerChainTooLong(MAX_POINTER_JUMPS)
+        ));
+    }
+
     #[test]
     fn test_bin_overlap_enforced() {
         let mut bytes: Vec<u8> = Vec::with_capacity(512);
@@ -1669,6 +1887,24 @@ mod tests {
         assert_eq!(Into::<Name>::into(ip), name);
     }
 
+    #[test]
+    fn test_from_ip() {
+        let ip = IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0x1));
+        let name = Name::from_ip(ip);
+
+        assert!(name.is_fqdn());
+        assert_eq!(
+            name.to_string(),
+            "1.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.0.8.b.d.0.1.0.0.2.ip6.arpa."
+        );
+        assert_eq!(name.parse_arpa_name().unwrap().addr(), ip);
+
+        let ip = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let name = Name::from_ip(ip);
+        assert_eq!(name.to_string(), "1.2.0.192.in-addr.arpa.");
+        assert_eq!(name.parse_arpa_name().unwrap().addr(), ip);
+    }
+
     #[test]
     fn test_from_str() {
         assert_eq!(
@@ -1765,6 +2001,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_idna() {
+        let name = Name::from_utf8("bücher.example").unwrap();
+        assert_eq!(name.to_ascii(), "xn--bcher-kva.example");
+        assert_eq!(name.to_unicode(), "bücher.example");
+        assert_eq!(Name::from_utf8(name.to_ascii()).unwrap(), name);
+        assert_eq!(Name::from_ascii(name.to_ascii()).unwrap(), name);
+
+        // mixed encoded and unicode labels
+        let name = Name::from_utf8("xn--bcher-kva.bücher.example.").unwrap();
+        assert_eq!(name.to_ascii(), "xn--bcher-kva.xn--bcher-kva.example.");
+        assert_eq!(name.to_unicode(), "bücher.bücher.example.");
+
+        // nontransitional processing keeps the sharp s
+        assert_eq!(
+            Name::from_utf8("faß.example.").unwrap().to_ascii(),
+            "xn--fa-hia.example."
+        );
+
+        assert_eq!(Name::from_utf8(".").unwrap(), Name::root());
+        assert_eq!(Name::root().to_ascii(), ".");
+        assert_eq!(Name::root().to_unicode(), ".");
+
+        // invalid IDNA
+        assert!(Name::from_utf8("xn--zz.example.").is_err());
+        assert!(Name::from_utf8("bü cher.example.").is_err());
+        assert!(Name::from_utf8("bü_cher.example.").is_err());
+    }
+
     #[test]
     fn test_excessive_encoding_len() {
         use crate::error::ProtoErrorKind;
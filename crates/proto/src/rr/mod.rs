@@ -27,14 +27,16 @@ pub mod record_data;
 pub mod record_type;
 pub mod resource;
 mod rr_set;
+pub mod serial_number;
 pub mod type_bit_map;
 
 pub use self::dns_class::DNSClass;
 pub use self::domain::{IntoName, Name, TryParseIp};
-pub use self::record_data::RData;
+pub use self::record_data::{RData, RecordData};
 pub use self::record_type::RecordType;
-pub use self::resource::Record;
+pub use self::resource::{sort_canonical, Record};
 #[allow(deprecated)]
 pub use self::rr_set::IntoRecordSet;
 pub use self::rr_set::RecordSet;
 pub use self::rr_set::RrsetRecords;
+pub use self::serial_number::SerialNumber;
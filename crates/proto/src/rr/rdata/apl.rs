@@ -0,0 +1,377 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! APL records for lists of address prefixes
+
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// The address family of IPv4, as assigned by IANA
+pub const ADDRESS_FAMILY_IPV4: u16 = 1;
+/// The address family of IPv6, as assigned by IANA
+pub const ADDRESS_FAMILY_IPV6: u16 = 2;
+
+/// [RFC 3123](https://tools.ietf.org/html/rfc3123#section-4), A DNS RR Type for Lists of Address Prefixes, October 2001
+///
+/// ```text
+/// 4. APL RDATA format
+///
+///    The RDATA section consists of zero or more items (<apitem>) of the
+///    form
+///
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       |                          ADDRESSFAMILY        |
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       |             PREFIX    | N|         AFDLENGTH  |
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       /                          AFDPART              /
+///       |                                               |
+///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct APL {
+    items: Vec<AplItem>,
+}
+
+impl APL {
+    /// Creates a new APL record data from the items of the list
+    pub fn new(items: Vec<AplItem>) -> Self {
+        Self { items }
+    }
+
+    /// The address prefixes of the list, in order
+    pub fn items(&self) -> &[AplItem] {
+        &self.items
+    }
+}
+
+/// An address prefix of an [`APL`], an `<apitem>`
+///
+/// The trailing zero octets of the address family dependent part are trimmed, as required for the wire format.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct AplItem {
+    address_family: u16,
+    prefix: u8,
+    negation: bool,
+    afd_part: Vec<u8>,
+}
+
+impl AplItem {
+    /// Creates a new item from its fields
+    ///
+    /// # Arguments
+    ///
+    /// * `address_family` - the address family of the prefix, as assigned by IANA
+    /// * `prefix` - the length of the prefix in bits
+    /// * `negation` - if the prefix is excluded from the list
+    /// * `afd_part` - the address family dependent part, i.e. the address for IPv4 and IPv6
+    ///
+    /// # Errors
+    ///
+    /// If the prefix or the address are too long for an IPv4 or IPv6 address, or if the address family dependent
+    ///  part exceeds 127 octets. The items of records read from messages are not checked.
+    pub fn new(
+        address_family: u16,
+        prefix: u8,
+        negation: bool,
+        afd_part: Vec<u8>,
+    ) -> ProtoResult<Self> {
+        let afd_part = trim_trailing_zeros(afd_part);
+
+        let (max_prefix, max_len) = match address_family {
+            ADDRESS_FAMILY_IPV4 => (32, 4),
+            ADDRESS_FAMILY_IPV6 => (128, 16),
+            _ => (u8::MAX, 0x7F),
+        };
+        if prefix > max_prefix {
+            return Err(ProtoError::from(format!(
+                "APL prefix of {} bits too long for address family {}",
+                prefix, address_family
+            )));
+        }
+        if afd_part.len() > max_len {
+            return Err(ProtoError::from(format!(
+                "APL address of {} octets too long for address family {}",
+                afd_part.len(),
+                address_family
+            )));
+        }
+
+        Ok(Self {
+            address_family,
+            prefix,
+            negation,
+            afd_part,
+        })
+    }
+
+    /// Creates a new item for the IPv4 or IPv6 prefix
+    ///
+    /// # Errors
+    ///
+    /// If the prefix is longer than the address.
+    pub fn from_ip(address: IpAddr, prefix: u8, negation: bool) -> ProtoResult<Self> {
+        match address {
+            IpAddr::V4(address) => Self::new(
+                ADDRESS_FAMILY_IPV4,
+                prefix,
+                negation,
+                address.octets().to_vec(),
+            ),
+            IpAddr::V6(address) => Self::new(
+                ADDRESS_FAMILY_IPV6,
+                prefix,
+                negation,
+                address.octets().to_vec(),
+            ),
+        }
+    }
+
+    /// The address family of the prefix, as assigned by IANA
+    pub fn address_family(&self) -> u16 {
+        self.address_family
+    }
+
+    /// The length of the prefix in bits
+    pub fn prefix(&self) -> u8 {
+        self.prefix
+    }
+
+    /// Returns true if the prefix is excluded from the list, i.e. the `!` in the text form
+    pub fn negation(&self) -> bool {
+        self.negation
+    }
+
+    /// The address family dependent part, without trailing zero octets
+    pub fn afd_part(&self) -> &[u8] {
+        &self.afd_part
+    }
+
+    /// The address of an IPv4 or IPv6 prefix, `None` for other address families, or an address which is too long
+    pub fn address(&self) -> Option<IpAddr> {
+        match self.address_family {
+            ADDRESS_FAMILY_IPV4 if self.afd_part.len() <= 4 => {
+                let mut octets = [0; 4];
+                octets[..self.afd_part.len()].copy_from_slice(&self.afd_part);
+                Some(IpAddr::V4(Ipv4Addr::from(octets)))
+            }
+            ADDRESS_FAMILY_IPV6 if self.afd_part.len() <= 16 => {
+                let mut octets = [0; 16];
+                octets[..self.afd_part.len()].copy_from_slice(&self.afd_part);
+                Some(IpAddr::V6(Ipv6Addr::from(octets)))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn trim_trailing_zeros(mut afd_part: Vec<u8>) -> Vec<u8> {
+    let trailing_zeros = afd_part.iter().rev().take_while(|b| **b == 0).count();
+    afd_part.truncate(afd_part.len() - trailing_zeros);
+    afd_part
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoResult<APL> {
+    let rdata_length = rdata_length.map(usize::from).unverified();
+    let start = decoder.index();
+
+    let mut items = Vec::new();
+    while decoder.index() - start < rdata_length {
+        let address_family = decoder.read_u16()?.unverified(/*any family is valid*/);
+        let prefix = decoder.read_u8()?.unverified(/*any prefix is accepted*/);
+        let afd_length = decoder.read_u8()?.unverified(/*any length is valid*/);
+        let negation = afd_length & 0x80 != 0;
+        let afd_part = decoder
+            .read_vec(usize::from(afd_length & 0x7F))?
+            .unverified(/*any address is accepted*/);
+
+        // be lenient with the items of other servers, only those which are created are checked
+        items.push(AplItem {
+            address_family,
+            prefix,
+            negation,
+            afd_part: trim_trailing_zeros(afd_part),
+        });
+    }
+
+    if decoder.index() - start != rdata_length {
+        return Err(ProtoError::from("APL items exceed the rdata length"));
+    }
+
+    Ok(APL::new(items))
+}
+
+/// Write the RData from the given Decoder
+pub fn emit(encoder: &mut BinEncoder<'_>, apl: &APL) -> ProtoResult<()> {
+    for item in apl.items() {
+        // the trailing zero octets are trimmed on construction
+        let afd_length = item.afd_part().len() as u8;
+
+        encoder.emit_u16(item.address_family())?;
+        encoder.emit_u8(item.prefix())?;
+        encoder.emit_u8(if item.negation() {
+            afd_length | 0x80
+        } else {
+            afd_length
+        })?;
+        encoder.emit_vec(item.afd_part())?;
+    }
+
+    Ok(())
+}
+
+/// [RFC 3123](https://tools.ietf.org/html/rfc3123#section-5)
+///
+/// ```text
+/// 5. APL RR Master File Format
+///
+///    The textual presentation of an APL RR consists of zero or more
+///    instances of an address prefix, separated by whitespace.
+///
+///       [!]afi:address/prefix
+/// ```
+///
+/// Address families other than IPv4 and IPv6 have no defined text form, their address is written in hex.
+impl fmt::Display for APL {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        for (i, item) in self.items.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", item)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for AplItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        if self.negation {
+            f.write_str("!")?;
+        }
+
+        match self.address() {
+            Some(address) => write!(f, "{}:{}/{}", self.address_family, address, self.prefix),
+            None => write!(
+                f,
+                "{}:{}/{}",
+                self.address_family,
+                data_encoding::HEXUPPER.encode(&self.afd_part),
+                self.prefix
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::dbg_macro, clippy::print_stdout)]
+
+    use super::*;
+
+    fn test_items() -> Vec<AplItem> {
+        vec![
+            AplItem::from_ip(Ipv4Addr::new(192, 0, 2, 0).into(), 24, false).unwrap(),
+            AplItem::from_ip(Ipv4Addr::new(192, 0, 2, 128).into(), 25, true).unwrap(),
+            AplItem::from_ip("2001:db8::".parse().unwrap(), 32, false).unwrap(),
+            AplItem::from_ip(Ipv6Addr::UNSPECIFIED.into(), 0, true).unwrap(),
+        ]
+    }
+
+    #[test]
+    fn test() {
+        let rdata = APL::new(test_items());
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        let bytes = encoder.into_bytes();
+
+        println!("bytes: {:?}", bytes);
+        #[rustfmt::skip]
+        assert_eq!(
+            *bytes,
+            vec![
+                0, 1, 24, 3, 192, 0, 2,
+                0, 1, 25, 0x84, 192, 0, 2, 128,
+                0, 2, 32, 4, 0x20, 0x01, 0x0d, 0xb8,
+                0, 2, 0, 0x80,
+            ]
+        );
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let restrict = Restrict::new(bytes.len() as u16);
+        let read_rdata = read(&mut decoder, restrict).expect("Decoding error");
+        assert_eq!(rdata, read_rdata);
+    }
+
+    #[test]
+    fn test_empty() {
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(&[]);
+        let read_rdata = read(&mut decoder, Restrict::new(0)).expect("Decoding error");
+        assert!(read_rdata.items().is_empty());
+    }
+
+    #[test]
+    fn test_trailing_zeros() {
+        let item = AplItem::new(ADDRESS_FAMILY_IPV4, 16, false, vec![10, 1, 0, 0]).unwrap();
+        assert_eq!(item.afd_part(), &[10, 1]);
+        assert_eq!(item.address(), Some(Ipv4Addr::new(10, 1, 0, 0).into()));
+
+        // trailing zeros on the wire are accepted, but not kept
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(&[0, 1, 16, 4, 10, 1, 0, 0]);
+        let read_rdata = read(&mut decoder, Restrict::new(8)).expect("Decoding error");
+        assert_eq!(read_rdata.items(), &[item]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(AplItem::from_ip(Ipv4Addr::LOCALHOST.into(), 33, false).is_err());
+        assert!(AplItem::from_ip(Ipv6Addr::LOCALHOST.into(), 129, false).is_err());
+        assert!(AplItem::new(ADDRESS_FAMILY_IPV4, 8, false, vec![1, 2, 3, 4, 5]).is_err());
+
+        // the item is cut short
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(&[0, 1, 24, 3, 192, 0]);
+        assert!(read(&mut decoder, Restrict::new(6)).is_err());
+        // the item exceeds the rdata
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(&[0, 1, 24, 3, 192, 0, 2]);
+        assert!(read(&mut decoder, Restrict::new(5)).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let rdata = APL::new(test_items());
+        assert_eq!(
+            rdata.to_string(),
+            "1:192.0.2.0/24 !1:192.0.2.128/25 2:2001:db8::/32 !2:::/0"
+        );
+
+        let other = AplItem::new(3, 8, false, vec![0xAB]).unwrap();
+        assert_eq!(other.to_string(), "3:AB/8");
+    }
+
+    #[test]
+    fn test_lenient_read() {
+        // prefixes and addresses too long for the address family are read
+        let mut decoder: BinDecoder<'_> =
+            BinDecoder::new(&[0, 1, 33, 1, 10, 0, 1, 8, 5, 1, 2, 3, 4, 5]);
+        let read_rdata = read(&mut decoder, Restrict::new(14)).expect("Decoding error");
+        assert_eq!(read_rdata.items()[0].prefix(), 33);
+        assert_eq!(read_rdata.items()[1].address(), None);
+        assert_eq!(read_rdata.to_string(), "1:10.0.0.0/33 1:0102030405/8");
+    }
+}
@@ -215,6 +215,34 @@ impl CAA {
     pub fn value(&self) -> &Value {
         &self.value
     }
+
+    /// Returns the issuers of an `issue` or `issuewild` property, `None` for any other tag
+    ///
+    /// These are the CAs authorized to issue certificates for the associated record label, see [`CaaIssuer`].
+    ///  A property names at most one issuer, it is empty for a property without an issuer domain name, e.g.
+    ///  `issue ";"`, which authorizes no CA at all.
+    pub fn issuers(&self) -> Option<Vec<CaaIssuer>> {
+        match self.value {
+            Value::Issuer(ref name, ref key_values)
+                if self.tag.is_issue() || self.tag.is_issuewild() =>
+            {
+                Some(
+                    name.iter()
+                        .map(|name| CaaIssuer::new(Some(name.clone()), key_values.clone()))
+                        .collect(),
+                )
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the Url to report issuer errors to of an `iodef` property, `None` for any other tag
+    pub fn iodef_url(&self) -> Option<&Url> {
+        match self.value {
+            Value::Url(ref url) if self.tag.is_iodef() => Some(url),
+            _ => None,
+        }
+    }
 }
 
 /// Specifies in what contexts this key may be trusted for use
@@ -383,20 +411,6 @@ fn emit_value(encoder: &mut BinEncoder<'_>, value: &Value) -> ProtoResult<()> {
     }
 }
 
-enum ParseNameKeyPairState {
-    BeforeKey(Vec<KeyValue>),
-    Key {
-        first_char: bool,
-        key: String,
-        key_values: Vec<KeyValue>,
-    },
-    Value {
-        key: String,
-        value: String,
-        key_values: Vec<KeyValue>,
-    },
-}
-
 /// Reads the issuer field according to the spec
 ///
 /// [RFC 6844, DNS Certification Authority Authorization, January 2013](https://tools.ietf.org/html/rfc6844#section-5.2)
@@ -471,127 +485,112 @@ enum ParseNameKeyPairState {
 /// [RFC 6844bis, CAA Resource Record, May 2018](https://tools.ietf.org/html/draft-ietf-lamps-rfc6844bis-00)
 /// [RFC 6844, CAA Record Extensions, May 2018](https://tools.ietf.org/html/draft-ietf-acme-caa-04)
 ///
+/// [RFC 8659, DNS Certification Authority Authorization (CAA) Resource Record, November 2019](https://tools.ietf.org/html/rfc8659#section-4.2)
+///
+/// ```text
+/// issue-value = *WSP [issuer-domain-name *WSP]
+///    [";" *WSP [parameters *WSP]]
+///
+/// parameters = (parameter *WSP ";" *WSP parameters) / parameter
+/// parameter = tag *WSP "=" *WSP value
+/// tag = (ALPHA / DIGIT) *( *("-") (ALPHA / DIGIT))
+/// value = *(%x21-3A / %x3C-7E)
+/// ```
+///
+/// Whitespace is accepted around the domain name and parameters, malformed domain names, keys or values are errors.
+///
 /// This explicitly allows `-` in key names, diverging from the original RFC. To support this, key names will
 /// allow `-` as non-starting characters. Additionally, this significantly relaxes the characters allowed in the value
 /// to allow URL like characters (it does not validate URL syntax).
 pub fn read_issuer(bytes: &[u8]) -> ProtoResult<(Option<Name>, Vec<KeyValue>)> {
-    let mut byte_iter = bytes.iter();
+    let value = str::from_utf8(bytes)?;
+    let (name, parameters) = match value.find(';') {
+        Some(idx) => (&value[..idx], &value[idx + 1..]),
+        None => (value, ""),
+    };
 
     // we want to reuse the name parsing rules
-    let name: Option<Name> = {
-        let take_name = byte_iter.by_ref().take_while(|ch| char::from(**ch) != ';');
-        let name_str = take_name.cloned().collect::<Vec<u8>>();
-
-        if !name_str.is_empty() {
-            let name_str = str::from_utf8(&name_str)?;
-            Some(Name::parse(name_str, None)?)
-        } else {
-            None
-        }
+    let name = name.trim_matches(is_wsp);
+    let name = if !name.is_empty() {
+        Some(read_issuer_name(name)?)
+    } else {
+        None
     };
 
-    // initial state is looking for a key ';' is valid...
-    let mut state = ParseNameKeyPairState::BeforeKey(vec![]);
-
-    // run the state machine through all remaining data, collecting all key/value pairs.
-    for ch in byte_iter {
-        match state {
-            // Name was already successfully parsed, otherwise we couldn't get here.
-            ParseNameKeyPairState::BeforeKey(key_values) => {
-                match char::from(*ch) {
-                    // gobble ';', ' ', and tab
-                    ';' | ' ' | '\u{0009}' => state = ParseNameKeyPairState::BeforeKey(key_values),
-                    ch if ch.is_alphanumeric() && ch != '=' => {
-                        // We found the beginning of a new Key
-                        let mut key = String::new();
-                        key.push(ch);
-
-                        state = ParseNameKeyPairState::Key {
-                            first_char: true,
-                            key,
-                            key_values,
-                        }
-                    }
-                    ch => return Err(format!("bad character in CAA issuer key: {}", ch).into()),
-                }
-            }
-            ParseNameKeyPairState::Key {
-                first_char,
-                mut key,
-                key_values,
-            } => {
-                match char::from(*ch) {
-                    // transition to value
-                    '=' => {
-                        let value = String::new();
-                        state = ParseNameKeyPairState::Value {
-                            key,
-                            value,
-                            key_values,
-                        }
-                    }
-                    // push onto the existing key
-                    ch if (ch.is_alphanumeric() || (!first_char && ch == '-'))
-                        && ch != '='
-                        && ch != ';' =>
-                    {
-                        key.push(ch);
-                        state = ParseNameKeyPairState::Key {
-                            first_char: false,
-                            key,
-                            key_values,
-                        }
-                    }
-                    ch => return Err(format!("bad character in CAA issuer key: {}", ch).into()),
-                }
-            }
-            ParseNameKeyPairState::Value {
-                key,
-                mut value,
-                mut key_values,
-            } => {
-                match char::from(*ch) {
-                    // transition back to find another pair
-                    ';' => {
-                        key_values.push(KeyValue { key, value });
-                        state = ParseNameKeyPairState::BeforeKey(key_values);
-                    }
-                    // push onto the existing key
-                    ch if !ch.is_control() && !ch.is_whitespace() => {
-                        value.push(ch);
-
-                        state = ParseNameKeyPairState::Value {
-                            key,
-                            value,
-                            key_values,
-                        }
-                    }
-                    ch => return Err(format!("bad character in CAA issuer value: '{}'", ch).into()),
-                }
+    // empty parameters are tolerated, e.g. a trailing ';'
+    let mut key_values = vec![];
+    for parameter in parameters
+        .split(';')
+        .map(|parameter| parameter.trim_matches(is_wsp))
+        .filter(|parameter| !parameter.is_empty())
+    {
+        let (key, value) = match parameter.find('=') {
+            Some(idx) => (&parameter[..idx], &parameter[idx + 1..]),
+            None => return Err(format!("key missing value: {}", parameter).into()),
+        };
+        let (key, value) = (key.trim_matches(is_wsp), value.trim_matches(is_wsp));
+
+        let mut key_chars = key.chars();
+        match key_chars.next() {
+            Some(ch) if ch.is_ascii_alphanumeric() => (),
+            Some(ch) => return Err(format!("bad character in CAA issuer key: {}", ch).into()),
+            None => {
+                return Err(format!("missing key in CAA issuer parameter: {}", parameter).into())
             }
         }
-    }
-
-    // valid final states are BeforeKey, where there was a final ';' but nothing followed it.
-    //                        Value, where we collected the final chars of the value, but no more data
-    let key_values = match state {
-        ParseNameKeyPairState::BeforeKey(key_values) => key_values,
-        ParseNameKeyPairState::Value {
-            key,
-            value,
-            mut key_values,
-        } => {
-            key_values.push(KeyValue { key, value });
-            key_values
+        if let Some(ch) = key_chars.find(|ch| !ch.is_ascii_alphanumeric() && *ch != '-') {
+            return Err(format!("bad character in CAA issuer key: {}", ch).into());
         }
-        ParseNameKeyPairState::Key { key, .. } => {
-            return Err(format!("key missing value: {}", key).into());
+        if !is_ldh(key) {
+            return Err(format!("bad CAA issuer key: {}", key).into());
         }
-    };
+        if let Some(ch) = value
+            .chars()
+            .find(|ch| ch.is_control() || ch.is_whitespace())
+        {
+            return Err(format!("bad character in CAA issuer value: '{}'", ch).into());
+        }
+
+        key_values.push(KeyValue::new(key, value));
+    }
 
     Ok((name, key_values))
 }
 
+/// space = *(SP / HTAB)
+fn is_wsp(ch: char) -> bool {
+    ch == ' ' || ch == '\u{0009}'
+}
+
+/// Letters, digits and hyphens, starting and ending with a letter or digit
+///
+/// ```text
+/// label = (ALPHA / DIGIT) *( *("-") (ALPHA / DIGIT))
+/// tag = (ALPHA / DIGIT) *( *("-") (ALPHA / DIGIT))
+/// ```
+fn is_ldh(label: &str) -> bool {
+    label.starts_with(|ch: char| ch.is_ascii_alphanumeric())
+        && label.ends_with(|ch: char| ch.is_ascii_alphanumeric())
+        && label
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+}
+
+/// Reads the issuer domain name, this must be in LDH form
+///
+/// ```text
+/// issuer-domain-name = label *("." label)
+/// ```
+fn read_issuer_name(name: &str) -> ProtoResult<Name> {
+    // a trailing '.' is accepted as the fully qualified form
+    let labels = name.strip_suffix('.').unwrap_or(name);
+    if !labels.split('.').all(is_ldh) {
+        return Err(format!("bad CAA issuer domain name: {}", name).into());
+    }
+
+    Name::parse(name, None)
+}
+
 /// Incident Object Description Exchange Format
 ///
 /// [RFC 6844, DNS Certification Authority Authorization, January 2013](https://tools.ietf.org/html/rfc6844#section-5.4)
@@ -656,6 +655,66 @@ impl KeyValue {
     }
 }
 
+/// The issuer of an `issue` or `issuewild` property, along with its parameters
+///
+/// See [RFC 8659, DNS Certification Authority Authorization (CAA) Resource Record, November 2019](https://tools.ietf.org/html/rfc8659#section-4.2)
+///
+/// ```
+/// use trust_dns_proto::rr::Name;
+/// use trust_dns_proto::rr::rdata::caa::CaaIssuer;
+///
+/// let issuer: CaaIssuer = "ca.example.net; account=230123".parse().unwrap();
+///
+/// assert_eq!(issuer.name(), Some(&Name::from_ascii("ca.example.net").unwrap()));
+/// assert_eq!(issuer.parameter("account"), Some("230123"));
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct CaaIssuer {
+    name: Option<Name>,
+    key_values: Vec<KeyValue>,
+}
+
+impl CaaIssuer {
+    /// Construct a new issuer
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - authorized to issue certificates, `None` if no CA is authorized
+    /// * `key_values` - additional parameters for the issuer, e.g. 'account', etc.
+    pub fn new(name: Option<Name>, key_values: Vec<KeyValue>) -> Self {
+        Self { name, key_values }
+    }
+
+    /// The domain name of the CA authorized to issue certificates, `None` if no CA is authorized
+    pub fn name(&self) -> Option<&Name> {
+        self.name.as_ref()
+    }
+
+    /// All the parameters of the issuer, in the order they were specified
+    pub fn key_values(&self) -> &[KeyValue] {
+        &self.key_values
+    }
+
+    /// Returns the value of the first parameter with the given key
+    pub fn parameter(&self, key: &str) -> Option<&str> {
+        self.key_values
+            .iter()
+            .find(|key_value| key_value.key() == key)
+            .map(KeyValue::value)
+    }
+}
+
+impl str::FromStr for CaaIssuer {
+    type Err = ProtoError;
+
+    /// Parses the issuer from the value of an `issue` or `issuewild` property, see [`read_issuer`]
+    fn from_str(s: &str) -> ProtoResult<Self> {
+        let (name, key_values) = read_issuer(s.as_bytes())?;
+        Ok(Self::new(name, key_values))
+    }
+}
+
 /// Read the binary CAA format
 ///
 /// [RFC 6844, DNS Certification Authority Authorization, January 2013](https://tools.ietf.org/html/rfc6844#section-5.1)
@@ -756,6 +815,32 @@ pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoR
     })
 }
 
+/// Parses the property tag of the presentation format, [RFC 8659](https://tools.ietf.org/html/rfc8659#section-4.1)
+///
+/// ```text
+/// Tag:  The property identifier -- a sequence of ASCII characters.
+///
+///    Tags MAY contain ASCII characters "a" through "z", "A" through "Z",
+///    and the numbers 0 through 9.  Tags MUST NOT contain any other
+///    characters.  Matching of tags is case insensitive.
+/// ```
+pub fn parse_tag(tag: &str) -> ProtoResult<Property> {
+    check_tag(tag)?;
+    Ok(Property::from(tag.to_string()))
+}
+
+/// Checks the length, 1 to 15, and the characters of the tag
+fn check_tag(tag: &str) -> ProtoResult<()> {
+    if tag.is_empty() || tag.len() > 15 {
+        return Err(format!("CAA tag length out of bounds, 1-15: {}", tag).into());
+    }
+    if !tag.chars().all(|ch| ch.is_ascii_alphanumeric()) {
+        return Err(format!("CAA tag character(s) out of bounds: {}", tag).into());
+    }
+
+    Ok(())
+}
+
 // TODO: change this to return &str
 fn read_tag(decoder: &mut BinDecoder<'_>, len: Restrict<u8>) -> ProtoResult<String> {
     let len = len
@@ -780,6 +865,7 @@ fn read_tag(decoder: &mut BinDecoder<'_>, len: Restrict<u8>) -> ProtoResult<Stri
 /// writes out the tag in binary form to the buffer, returning the number of bytes written
 fn emit_tag(buf: &mut [u8], tag: &Property) -> ProtoResult<u8> {
     let property = tag.as_str();
+    check_tag(property)?;
     let property = property.as_bytes();
 
     let len = property.len();
@@ -1005,6 +1091,99 @@ mod tests {
         assert_eq!(read_issuer(b";").unwrap(), (None, vec![]));
     }
 
+    #[test]
+    fn test_read_issuer_whitespace() {
+        assert_eq!(
+            read_issuer(b" ca.example.net ;\taccount = 230123 ; policy=ev ").unwrap(),
+            (
+                Some(Name::parse("ca.example.net", None).unwrap()),
+                vec![
+                    KeyValue::new("account", "230123"),
+                    KeyValue::new("policy", "ev")
+                ],
+            )
+        );
+        assert_eq!(read_issuer(b" ; ").unwrap(), (None, vec![]));
+    }
+
+    #[test]
+    fn test_read_issuer_malformed() {
+        assert!(read_issuer(b"ca example.net").is_err());
+        assert!(read_issuer(b"-ca.example.net").is_err());
+        assert!(read_issuer(b"ca..example.net").is_err());
+        assert!(read_issuer(b"ca.example.net; account").is_err());
+        assert!(read_issuer(b"ca.example.net; =230123").is_err());
+        assert!(read_issuer(b"ca.example.net; -account=230123").is_err());
+        assert!(read_issuer(b"ca.example.net; account-=230123").is_err());
+        assert!(read_issuer(b"ca.example.net; acc_ount=230123").is_err());
+        assert!(read_issuer(b"ca.example.net; account=230 123").is_err());
+        assert!(read_issuer(b"ca.example.net; account=230123 policy=ev").is_err());
+        assert!(read_issuer(&[0xff]).is_err());
+    }
+
+    #[test]
+    fn test_parse_tag() {
+        assert_eq!(parse_tag("issue").unwrap(), Property::Issue);
+        assert_eq!(parse_tag("IssueWild").unwrap(), Property::IssueWild);
+        assert_eq!(
+            parse_tag("contactemail").unwrap(),
+            Property::Unknown("contactemail".to_string())
+        );
+        assert!(parse_tag("").is_err());
+        assert!(parse_tag("issue-wild").is_err());
+        assert!(parse_tag("issuewild-").is_err());
+        assert!(parse_tag("sixteencharacter").is_err());
+
+        // an invalid tag can not be emitted either
+        let caa = CAA {
+            issuer_critical: false,
+            tag: Property::Unknown("bad tag".to_string()),
+            value: Value::Unknown(vec![]),
+        };
+        let mut bytes = Vec::new();
+        assert!(emit(&mut BinEncoder::new(&mut bytes), &caa).is_err());
+    }
+
+    #[test]
+    fn test_issuer() {
+        let caa = CAA::new_issue(
+            false,
+            Some(Name::parse("example.net", None).unwrap()),
+            vec![KeyValue::new("account", "230123")],
+        );
+        let issuers = caa.issuers().expect("issue tag has issuers");
+        assert_eq!(issuers.len(), 1);
+        let issuer = &issuers[0];
+        assert_eq!(
+            issuer.name(),
+            Some(&Name::parse("example.net", None).unwrap())
+        );
+        assert_eq!(issuer.parameter("account"), Some("230123"));
+        assert_eq!(issuer.parameter("policy"), None);
+        assert!(caa.iodef_url().is_none());
+
+        let caa = CAA::new_issuewild(false, None, vec![]);
+        assert_eq!(caa.issuers(), Some(vec![]));
+
+        let url = Url::parse("mailto:security@example.com").unwrap();
+        let caa = CAA::new_iodef(false, url.clone());
+        assert!(caa.issuers().is_none());
+        assert_eq!(caa.iodef_url(), Some(&url));
+
+        assert_eq!(
+            "example.net; validationmethods=dns-01"
+                .parse::<CaaIssuer>()
+                .unwrap(),
+            CaaIssuer::new(
+                Some(Name::parse("example.net", None).unwrap()),
+                vec![KeyValue::new("validationmethods", "dns-01")],
+            )
+        );
+        assert!("example.net; validationmethods"
+            .parse::<CaaIssuer>()
+            .is_err());
+    }
+
     #[test]
     fn test_read_iodef() {
         assert_eq!(
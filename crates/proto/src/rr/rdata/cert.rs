@@ -0,0 +1,333 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! CERT records for storing certificates and certificate revocation lists
+use std::fmt;
+use std::str::FromStr;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// [RFC 4398](https://tools.ietf.org/html/rfc4398#section-2), Storing Certificates in the Domain Name System (DNS), March 2006
+///
+/// ```text
+/// 2.  The CERT Resource Record
+///
+///    The CERT resource record (RR) has the structure given below.  Its RR
+///    type code is 37.
+///
+///                        1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |             type              |             key tag           |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |   algorithm   |                                               /
+///    +---------------+            certificate or CRL                 /
+///    /                                                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-|
+///
+///    The type field is the certificate type as defined in Section 2.1
+///    below.
+///
+///    The key tag field is the 16-bit value computed for the key embedded
+///    in the certificate, using the RRSIG Key Tag algorithm described in
+///    Appendix B of [12].  This field is used as an efficiency measure to
+///    pick which CERT RRs may be applicable to a particular key.  The key
+///    tag can be calculated for the key in question, and then only CERT RRs
+///    with the same key tag need to be examined.  Note that two different
+///    keys can have the same key tag.  However, the key MUST be transformed
+///    to the format it would have as the public key portion of a DNSKEY RR
+///    before the key tag is computed.  This is only possible if the key is
+///    applicable to an algorithm and complies to limits (such as key size)
+///    defined for DNS security.  If it is not, the algorithm field MUST be
+///    zero and the tag field is meaningless and SHOULD be zero.
+///
+///    The algorithm field has the same meaning as the algorithm field in
+///    DNSKEY and RRSIG RRs [12], except that a zero algorithm field
+///    indicates that the algorithm is unknown to a secure DNS, which may
+///    simply be the result of the algorithm not having been standardized
+///    for DNSSEC [11].
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct CERT {
+    cert_type: CertType,
+    key_tag: u16,
+    algorithm: u8,
+    certificate: Vec<u8>,
+}
+
+impl CERT {
+    /// Creates a new CERT record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `cert_type` - the type of the certificate or CRL.
+    /// * `key_tag` - the key tag of the key in the certificate, zero if `algorithm` is zero.
+    /// * `algorithm` - the DNSSEC algorithm of the key in the certificate, zero if it is unknown to DNSSEC.
+    /// * `certificate` - the certificate or CRL, this will NOT be checked.
+    pub fn new(cert_type: CertType, key_tag: u16, algorithm: u8, certificate: Vec<u8>) -> Self {
+        Self {
+            cert_type,
+            key_tag,
+            algorithm,
+            certificate,
+        }
+    }
+
+    /// The type of the certificate or CRL
+    pub fn cert_type(&self) -> CertType {
+        self.cert_type
+    }
+
+    /// The key tag of the key in the certificate
+    pub fn key_tag(&self) -> u16 {
+        self.key_tag
+    }
+
+    /// The DNSSEC algorithm number of the key in the certificate, zero if it is unknown to DNSSEC
+    pub fn algorithm(&self) -> u8 {
+        self.algorithm
+    }
+
+    /// The certificate or CRL
+    pub fn certificate(&self) -> &[u8] {
+        &self.certificate
+    }
+}
+
+/// [RFC 4398](https://tools.ietf.org/html/rfc4398#section-2.1)
+///
+/// ```text
+/// 2.1.  Certificate Type Values
+///
+///    The following values are defined or reserved:
+///
+///          Value  Mnemonic  Certificate Type
+///          -----  --------  ----------------
+///              0            Reserved
+///              1  PKIX      X.509 as per PKIX
+///              2  SPKI      SPKI certificate
+///              3  PGP       OpenPGP packet
+///              4  IPKIX     The URL of an X.509 data object
+///              5  ISPKI     The URL of an SPKI certificate
+///              6  IPGP      The fingerprint and URL of an OpenPGP packet
+///              7  ACPKIX    Attribute Certificate
+///              8  IACPKIX   The URL of an Attribute Certificate
+///          9-252            Available for IANA assignment
+///            253  URI       URI private
+///            254  OID       OID private
+///            255            Reserved
+///      256-65279            Available for IANA assignment
+///    65280-65534            Experimental
+///          65535            Reserved
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CertType {
+    /// X.509 as per PKIX
+    PKIX,
+    /// SPKI certificate
+    SPKI,
+    /// OpenPGP packet
+    PGP,
+    /// The URL of an X.509 data object
+    IPKIX,
+    /// The URL of an SPKI certificate
+    ISPKI,
+    /// The fingerprint and URL of an OpenPGP packet
+    IPGP,
+    /// Attribute Certificate
+    ACPKIX,
+    /// The URL of an Attribute Certificate
+    IACPKIX,
+    /// URI private
+    URI,
+    /// OID private
+    OID,
+    /// Reserved, unassigned or experimental value
+    Unassigned(u16),
+}
+
+impl From<u16> for CertType {
+    fn from(cert_type: u16) -> Self {
+        match cert_type {
+            1 => Self::PKIX,
+            2 => Self::SPKI,
+            3 => Self::PGP,
+            4 => Self::IPKIX,
+            5 => Self::ISPKI,
+            6 => Self::IPGP,
+            7 => Self::ACPKIX,
+            8 => Self::IACPKIX,
+            253 => Self::URI,
+            254 => Self::OID,
+            _ => Self::Unassigned(cert_type),
+        }
+    }
+}
+
+impl From<CertType> for u16 {
+    fn from(cert_type: CertType) -> Self {
+        match cert_type {
+            CertType::PKIX => 1,
+            CertType::SPKI => 2,
+            CertType::PGP => 3,
+            CertType::IPKIX => 4,
+            CertType::ISPKI => 5,
+            CertType::IPGP => 6,
+            CertType::ACPKIX => 7,
+            CertType::IACPKIX => 8,
+            CertType::URI => 253,
+            CertType::OID => 254,
+            CertType::Unassigned(cert_type) => cert_type,
+        }
+    }
+}
+
+/// The mnemonic, or the value if there is none
+impl fmt::Display for CertType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::PKIX => f.write_str("PKIX"),
+            Self::SPKI => f.write_str("SPKI"),
+            Self::PGP => f.write_str("PGP"),
+            Self::IPKIX => f.write_str("IPKIX"),
+            Self::ISPKI => f.write_str("ISPKI"),
+            Self::IPGP => f.write_str("IPGP"),
+            Self::ACPKIX => f.write_str("ACPKIX"),
+            Self::IACPKIX => f.write_str("IACPKIX"),
+            Self::URI => f.write_str("URI"),
+            Self::OID => f.write_str("OID"),
+            Self::Unassigned(cert_type) => write!(f, "{}", cert_type),
+        }
+    }
+}
+
+/// Parses either the mnemonic or the value of the certificate type
+impl FromStr for CertType {
+    type Err = ProtoError;
+
+    fn from_str(s: &str) -> ProtoResult<Self> {
+        let cert_type = match s.to_ascii_uppercase().as_str() {
+            "PKIX" => Self::PKIX,
+            "SPKI" => Self::SPKI,
+            "PGP" => Self::PGP,
+            "IPKIX" => Self::IPKIX,
+            "ISPKI" => Self::ISPKI,
+            "IPGP" => Self::IPGP,
+            "ACPKIX" => Self::ACPKIX,
+            "IACPKIX" => Self::IACPKIX,
+            "URI" => Self::URI,
+            "OID" => Self::OID,
+            _ => s
+                .parse::<u16>()
+                .map(Self::from)
+                .map_err(|_| ProtoError::from(format!("unknown CERT type: {}", s)))?,
+        };
+
+        Ok(cert_type)
+    }
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoResult<CERT> {
+    let cert_type = decoder.read_u16()?.unverified().into();
+    let key_tag = decoder.read_u16()?.unverified();
+    let algorithm = decoder.read_u8()?.unverified();
+    let certificate_len = rdata_length
+        .map(|l| l as usize)
+        .checked_sub(5)
+        .map_err(|_| ProtoError::from("invalid rdata length in CERT"))?
+        .unverified();
+    let certificate = decoder.read_vec(certificate_len)?.unverified();
+    Ok(CERT::new(cert_type, key_tag, algorithm, certificate))
+}
+
+/// Write the RData from the given Decoder
+pub fn emit(encoder: &mut BinEncoder<'_>, cert: &CERT) -> ProtoResult<()> {
+    encoder.emit_u16(cert.cert_type().into())?;
+    encoder.emit_u16(cert.key_tag())?;
+    encoder.emit_u8(cert.algorithm())?;
+    encoder.emit_vec(cert.certificate())
+}
+
+/// [RFC 4398](https://tools.ietf.org/html/rfc4398#section-2.2)
+///
+/// ```text
+/// 2.2.  Text Representation of CERT RRs
+///
+///    The RDATA portion of a CERT RR has the type field as an unsigned
+///    decimal integer or as a mnemonic symbol as listed in Section 2.1,
+///    above.
+///
+///    The key tag field is represented as an unsigned decimal integer.
+///
+///    The algorithm field is represented as an unsigned decimal integer or
+///    a mnemonic symbol as listed in [12].
+///
+///    The certificate/CRL portion is represented in base 64 [16] and may be
+///    divided into any number of white-space-separated substrings, any of
+///    which may be empty, with the total being a valid base-64 encoding.
+/// ```
+impl fmt::Display for CERT {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{cert_type} {key_tag} {algorithm} {certificate}",
+            cert_type = self.cert_type,
+            key_tag = self.key_tag,
+            algorithm = self.algorithm,
+            certificate = data_encoding::BASE64.encode(&self.certificate),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::dbg_macro, clippy::print_stdout)]
+
+    use super::*;
+
+    #[test]
+    fn test() {
+        let rdata = CERT::new(CertType::PGP, 0, 0, b"not really a key".to_vec());
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        let bytes = encoder.into_bytes();
+
+        println!("bytes: {:?}", bytes);
+        assert_eq!(&bytes[..5], &[0, 3, 0, 0, 0]);
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let restrict = Restrict::new(bytes.len() as u16);
+        let read_rdata = read(&mut decoder, restrict).expect("Decoding error");
+        assert_eq!(rdata, read_rdata);
+    }
+
+    #[test]
+    fn test_cert_type() {
+        for value in 0..=u16::max_value() {
+            assert_eq!(u16::from(CertType::from(value)), value);
+        }
+
+        assert_eq!("PKIX".parse::<CertType>().unwrap(), CertType::PKIX);
+        assert_eq!("ipgp".parse::<CertType>().unwrap(), CertType::IPGP);
+        assert_eq!("3".parse::<CertType>().unwrap(), CertType::PGP);
+        assert_eq!(
+            "65280".parse::<CertType>().unwrap(),
+            CertType::Unassigned(65280)
+        );
+        assert!("X509".parse::<CertType>().is_err());
+        assert_eq!(CertType::Unassigned(65280).to_string(), "65280");
+    }
+}
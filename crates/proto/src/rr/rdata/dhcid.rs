@@ -0,0 +1,104 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DHCID records for associating DHCP clients with their names
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// [RFC 4701](https://tools.ietf.org/html/rfc4701#section-3.1), A DNS Resource Record (RR) for Encoding DHCP Information (DHCID RR), October 2006
+///
+/// ```text
+/// 3.1.  DHCID RDATA Format
+///
+///    The RDATA section of a DHCID RR in transmission contains RDLENGTH
+///    octets of binary data.  The format of this data and its
+///    interpretation by DHCP servers and clients are described below.
+///    DNS software should consider the RDATA section to be opaque.  DHCP
+///    clients or servers use the DHCID RR to associate a DHCP client's
+///    identity with a DNS name, so that multiple DHCP clients and servers
+///    may deterministically perform dynamic DNS updates to the same zone.
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct DHCID {
+    digest: Vec<u8>,
+}
+
+impl DHCID {
+    /// Creates a new DHCID record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `digest` - the identifier type code, digest type code and digest of the client identity, opaque to DNS.
+    pub fn new(digest: Vec<u8>) -> Self {
+        Self { digest }
+    }
+
+    /// The opaque data of the record, i.e. the identifier type code, digest type code and digest
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoResult<DHCID> {
+    let rdata_length = rdata_length.map(usize::from).unverified();
+    let digest = decoder.read_vec(rdata_length)?.unverified(/*opaque to DNS*/);
+    Ok(DHCID::new(digest))
+}
+
+/// Write the RData from the given Decoder
+pub fn emit(encoder: &mut BinEncoder<'_>, dhcid: &DHCID) -> ProtoResult<()> {
+    encoder.emit_vec(dhcid.digest())
+}
+
+/// [RFC 4701](https://tools.ietf.org/html/rfc4701#section-3.2)
+///
+/// ```text
+/// 3.2.  DHCID Presentation Format
+///
+///    In DNS master files, the RDATA is represented as a single block in
+///    base-64 encoding identical to that used for representing binary data
+///    in [3], Section 3.  The data may be divided up into any number of
+///    white-space-separated substrings, each of which is a valid base-64
+///    encoding, which are concatenated to obtain the full signature.
+///    These substrings can span lines using the standard parenthesis.
+/// ```
+impl fmt::Display for DHCID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        f.write_str(&data_encoding::BASE64.encode(&self.digest))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::dbg_macro, clippy::print_stdout)]
+
+    use super::*;
+
+    #[test]
+    fn test() {
+        let rdata = DHCID::new(vec![0x00, 0x01, 0x01, 0xde, 0xad, 0xbe, 0xef]);
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        let bytes = encoder.into_bytes();
+
+        println!("bytes: {:?}", bytes);
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let restrict = Restrict::new(bytes.len() as u16);
+        let read_rdata = read(&mut decoder, restrict).expect("Decoding error");
+        assert_eq!(rdata, read_rdata);
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! HIP records for Host Identity Protocol host identities and rendezvous servers
+use std::convert::TryFrom;
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::rr::domain::Name;
+use crate::serialize::binary::*;
+
+/// [RFC 8005](https://tools.ietf.org/html/rfc8005#section-5), Host Identity Protocol (HIP) Domain Name System (DNS) Extension, October 2016
+///
+/// ```text
+/// 5.  HIP RR Storage Format
+///
+///    The RDATA for a HIP RR consists of a public key algorithm type, the
+///    HIT length, a HIT encoded in binary format, the length of the public
+///    key, the public key encoded in binary format, and an optional
+///    variable length set of rendezvous servers.
+///
+///     0                   1                   2                   3
+///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |  HIT length   | PK algorithm  |          PK length            |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                                                               |
+///    ~                           HIT                                 ~
+///    |                                                               |
+///    +                     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                     |                                         |
+///    +-+-+-+-+-+-+-+-+-+-+-+                                         +
+///    |                           Public Key                          |
+///    ~                                                               ~
+///    |                                                               |
+///    +                               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                               |                               |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+///    |                                                               |
+///    ~                       Rendezvous Servers                      ~
+///    |                                                               |
+///    +             +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |             |
+///    +-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct HIP {
+    algorithm: u8,
+    hit: Vec<u8>,
+    public_key: Vec<u8>,
+    rendezvous_servers: Vec<Name>,
+}
+
+impl HIP {
+    /// Creates a new HIP record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `algorithm` - the IPSECKEY algorithm number of the public key.
+    /// * `hit` - the Host Identity Tag, at most 255 bytes.
+    /// * `public_key` - the Host Identity, i.e. the public key, at most 65535 bytes. This will NOT be checked.
+    /// * `rendezvous_servers` - the rendezvous servers of the host, in order of preference.
+    pub fn new(
+        algorithm: u8,
+        hit: Vec<u8>,
+        public_key: Vec<u8>,
+        rendezvous_servers: Vec<Name>,
+    ) -> Self {
+        Self {
+            algorithm,
+            hit,
+            public_key,
+            rendezvous_servers,
+        }
+    }
+
+    /// The IPSECKEY algorithm number of the public key
+    pub fn algorithm(&self) -> u8 {
+        self.algorithm
+    }
+
+    /// The Host Identity Tag
+    pub fn hit(&self) -> &[u8] {
+        &self.hit
+    }
+
+    /// The Host Identity, i.e. the public key
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// The rendezvous servers of the host, in order of preference
+    pub fn rendezvous_servers(&self) -> &[Name] {
+        &self.rendezvous_servers
+    }
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoResult<HIP> {
+    let start_idx = decoder.index();
+
+    let hit_len = decoder.read_u8()?.unverified(/*any length is valid*/);
+    let algorithm = decoder.read_u8()?.unverified();
+    let public_key_len = decoder.read_u16()?.unverified(/*any length is valid*/);
+    let hit = decoder.read_vec(hit_len as usize)?.unverified();
+    let public_key = decoder.read_vec(public_key_len as usize)?.unverified();
+
+    let mut rendezvous_servers = Vec::new();
+    while rdata_length
+        .map(|u| u as usize)
+        .checked_sub(decoder.index() - start_idx)
+        .map_err(|_| ProtoError::from("invalid rdata length in HIP"))?
+        .unverified(/*used only as a length check*/)
+        > 0
+    {
+        rendezvous_servers.push(Name::read(decoder)?);
+    }
+
+    Ok(HIP::new(algorithm, hit, public_key, rendezvous_servers))
+}
+
+/// Write the RData from the given Decoder
+///
+/// [RFC 8005](https://tools.ietf.org/html/rfc8005#section-5), the rendezvous servers are never compressed
+///
+/// ```text
+///    The Rendezvous Servers field indicates one or more variable length
+///    wire-encoded domain names of rendezvous servers, as described in
+///    Section 3.3 of [RFC1035].  The wire-encoded format is self-
+///    describing, so the length is implicit.  The domain names MUST NOT be
+///    compressed.
+/// ```
+pub fn emit(encoder: &mut BinEncoder<'_>, hip: &HIP) -> ProtoResult<()> {
+    let hit_len = u8::try_from(hip.hit.len())
+        .map_err(|_| ProtoError::from(format!("HIT too long: {}", hip.hit.len())))?;
+    let public_key_len = u16::try_from(hip.public_key.len()).map_err(|_| {
+        ProtoError::from(format!("HIP public key too long: {}", hip.public_key.len()))
+    })?;
+
+    encoder.emit_u8(hit_len)?;
+    encoder.emit_u8(hip.algorithm)?;
+    encoder.emit_u16(public_key_len)?;
+    encoder.emit_vec(&hip.hit)?;
+    encoder.emit_vec(&hip.public_key)?;
+    for rendezvous_server in &hip.rendezvous_servers {
+        rendezvous_server.emit_as_canonical(encoder, true)?;
+    }
+
+    Ok(())
+}
+
+/// [RFC 8005](https://tools.ietf.org/html/rfc8005#section-6)
+///
+/// ```text
+/// 6.  HIP RR Presentation Format
+///
+///    This section specifies the representation of the HIP RR in a zone
+///    master file.
+///
+///    The HIT length field is not represented, as it is implicitly known
+///    thanks to the HIT field representation.
+///
+///    The PK algorithm field is represented as unsigned integers.
+///
+///    The HIT field is represented as the Base16 encoding [RFC4648] (a.k.a.
+///    hex or hexadecimal) of the HIT.  The encoding MUST NOT contain
+///    whitespace.
+///
+///    The PK length field is not represented, as it is implicitly known
+///    thanks to the Public Key field representation containing Base64
+///    encoding of the PK.
+///
+///    The Public Key field is represented as the Base64 encoding of the
+///    HI.  The encoding MUST NOT contain whitespace.
+///
+///    The Rendezvous Servers field is represented by one or more domain
+///    names separated by whitespace.
+/// ```
+impl fmt::Display for HIP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{algorithm} {hit} {public_key}",
+            algorithm = self.algorithm,
+            hit = data_encoding::HEXUPPER.encode(&self.hit),
+            public_key = data_encoding::BASE64.encode(&self.public_key),
+        )?;
+
+        for rendezvous_server in &self.rendezvous_servers {
+            write!(f, " {}", rendezvous_server)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::dbg_macro, clippy::print_stdout)]
+
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn example() -> HIP {
+        HIP::new(
+            2,
+            data_encoding::HEXUPPER
+                .decode(b"200100107B1A74DF365639CC39F1D578")
+                .unwrap(),
+            b"not really a key".to_vec(),
+            vec![
+                Name::from_str("rvs1.example.com.").unwrap(),
+                Name::from_str("rvs2.example.com.").unwrap(),
+            ],
+        )
+    }
+
+    #[test]
+    fn test() {
+        let rdata = example();
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        let bytes = encoder.into_bytes();
+
+        println!("bytes: {:?}", bytes);
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let restrict = Restrict::new(bytes.len() as u16);
+        let read_rdata = read(&mut decoder, restrict).expect("Decoding error");
+        assert_eq!(rdata, read_rdata);
+    }
+
+    #[test]
+    fn test_rendezvous_servers_not_compressed() {
+        let rdata = example();
+        let name = Name::from_str("rvs1.example.com.").unwrap();
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        name.emit(&mut encoder).unwrap();
+        let start = encoder.offset();
+        emit(&mut encoder, &rdata).unwrap();
+        let bytes = encoder.into_bytes();
+
+        let names = &bytes[start + 4 + rdata.hit().len() + rdata.public_key().len()..];
+        let mut expected = Vec::new();
+        let mut encoder = BinEncoder::new(&mut expected);
+        for name in rdata.rendezvous_servers() {
+            name.emit_as_canonical(&mut encoder, true).unwrap();
+        }
+        assert_eq!(names, &expected[..]);
+        assert!(!names.iter().any(|b| b & 0xC0 == 0xC0));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            example().to_string(),
+            "2 200100107B1A74DF365639CC39F1D578 bm90IHJlYWxseSBhIGtleQ== rvs1.example.com. rvs2.example.com."
+        );
+    }
+}
@@ -0,0 +1,352 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! LOC records for the geographic location of hosts, networks and subnets
+
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// The latitude and longitude of the equator and prime meridian, respectively
+pub const EQUATOR: u32 = 1 << 31;
+
+/// Altitudes are relative to a base of 100,000m below the WGS 84 reference spheroid
+pub const ALTITUDE_BASE_CM: i64 = 10_000_000;
+
+/// Thousandths of a second of arc in a degree
+const THOUSANDTHS_PER_DEGREE: u32 = 3_600_000;
+
+/// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-2), A Means for Expressing Location Information in the Domain Name System, January 1996
+///
+/// ```text
+/// 2. RDATA Format
+///
+///        MSB                                           LSB
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       0|        VERSION        |         SIZE          |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       2|       HORIZ PRE       |       VERT PRE        |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       4|                   LATITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       6|                   LATITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///       8|                   LONGITUDE                   |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///      10|                   LONGITUDE                   |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///      12|                   ALTITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///      14|                   ALTITUDE                    |
+///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+///    (octet)
+/// ```
+///
+/// The values are kept as they are on the wire, only version 0 is supported.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct LOC {
+    size: u8,
+    horiz_pre: u8,
+    vert_pre: u8,
+    latitude: u32,
+    longitude: u32,
+    altitude: u32,
+}
+
+impl LOC {
+    /// Creates a new LOC record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `latitude` - the latitude in thousandths of a second of arc, `EQUATOR` is 0 degrees and north is greater.
+    /// * `longitude` - the longitude in thousandths of a second of arc, `EQUATOR` is the prime meridian and east
+    ///                 is greater.
+    /// * `altitude` - the altitude in centimeters above a base of 100,000m below the WGS 84 reference spheroid.
+    /// * `size` - the diameter of the sphere enclosing the location, see `precision_to_cm` for the encoding.
+    /// * `horiz_pre` - the horizontal precision of the location, encoded like `size`.
+    /// * `vert_pre` - the vertical precision of the location, encoded like `size`.
+    ///
+    /// # Return value
+    ///
+    /// The new LOC record data, or an error if a coordinate or precision is out of range.
+    pub fn new(
+        latitude: u32,
+        longitude: u32,
+        altitude: u32,
+        size: u8,
+        horiz_pre: u8,
+        vert_pre: u8,
+    ) -> ProtoResult<Self> {
+        let from_equator = |coordinate: u32| (i64::from(coordinate) - i64::from(EQUATOR)).abs();
+        if from_equator(latitude) > i64::from(90 * THOUSANDTHS_PER_DEGREE) {
+            return Err(format!("LOC latitude out of range: {}", latitude).into());
+        }
+        if from_equator(longitude) > i64::from(180 * THOUSANDTHS_PER_DEGREE) {
+            return Err(format!("LOC longitude out of range: {}", longitude).into());
+        }
+        for precision in [size, horiz_pre, vert_pre] {
+            if precision >> 4 > 9 || precision & 0x0F > 9 {
+                return Err(format!("LOC precision out of range: {:#04x}", precision).into());
+            }
+        }
+
+        Ok(Self {
+            size,
+            horiz_pre,
+            vert_pre,
+            latitude,
+            longitude,
+            altitude,
+        })
+    }
+
+    /// The latitude in thousandths of a second of arc, `EQUATOR` is 0 degrees and north is greater
+    pub fn latitude(&self) -> u32 {
+        self.latitude
+    }
+
+    /// The longitude in thousandths of a second of arc, `EQUATOR` is the prime meridian and east is greater
+    pub fn longitude(&self) -> u32 {
+        self.longitude
+    }
+
+    /// The altitude in centimeters above a base of 100,000m below the WGS 84 reference spheroid
+    pub fn altitude(&self) -> u32 {
+        self.altitude
+    }
+
+    /// The diameter of a sphere enclosing the location, as encoded on the wire
+    pub fn size(&self) -> u8 {
+        self.size
+    }
+
+    /// The horizontal precision of the location, as encoded on the wire
+    pub fn horiz_pre(&self) -> u8 {
+        self.horiz_pre
+    }
+
+    /// The vertical precision of the location, as encoded on the wire
+    pub fn vert_pre(&self) -> u8 {
+        self.vert_pre
+    }
+}
+
+/// Decodes a size or precision to centimeters
+///
+/// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-2)
+///
+/// ```text
+/// SIZE         The diameter of a sphere enclosing the described entity, in
+///              centimeters, expressed as a pair of four-bit unsigned
+///              integers, each ranging from zero to nine, with the most
+///              significant four bits representing the base and the second
+///              number representing the power of ten by which to multiply
+///              the base.
+/// ```
+pub fn precision_to_cm(precision: u8) -> u64 {
+    u64::from(precision >> 4) * 10_u64.pow(u32::from(precision & 0x0F))
+}
+
+/// Encodes centimeters as a size or precision, rounding down to the nearest representable value
+pub fn cm_to_precision(cm: u64) -> ProtoResult<u8> {
+    if cm > 9 * 10_u64.pow(9) {
+        return Err(format!("LOC precision out of range: {}cm", cm).into());
+    }
+
+    let mut exponent = 0;
+    while exponent < 9 && cm >= 10_u64.pow(exponent + 1) {
+        exponent += 1;
+    }
+    let base = cm / 10_u64.pow(exponent);
+
+    Ok((base as u8) << 4 | exponent as u8)
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder<'_>) -> ProtoResult<LOC> {
+    let version = decoder.read_u8()?.unverified();
+    if version != 0 {
+        return Err(format!("unsupported LOC version: {}", version).into());
+    }
+
+    let size = decoder.read_u8()?.unverified();
+    let horiz_pre = decoder.read_u8()?.unverified();
+    let vert_pre = decoder.read_u8()?.unverified();
+    let latitude = decoder.read_u32()?.unverified();
+    let longitude = decoder.read_u32()?.unverified();
+    let altitude = decoder.read_u32()?.unverified();
+
+    LOC::new(latitude, longitude, altitude, size, horiz_pre, vert_pre)
+}
+
+/// Write the RData from the given Decoder
+pub fn emit(encoder: &mut BinEncoder<'_>, loc: &LOC) -> ProtoResult<()> {
+    encoder.emit_u8(0)?;
+    encoder.emit_u8(loc.size)?;
+    encoder.emit_u8(loc.horiz_pre)?;
+    encoder.emit_u8(loc.vert_pre)?;
+    encoder.emit_u32(loc.latitude)?;
+    encoder.emit_u32(loc.longitude)?;
+    encoder.emit_u32(loc.altitude)?;
+
+    Ok(())
+}
+
+/// Writes the coordinate as degrees, minutes, seconds and hemisphere
+fn fmt_coordinate(
+    f: &mut fmt::Formatter<'_>,
+    coordinate: u32,
+    positive: char,
+    negative: char,
+) -> Result<(), fmt::Error> {
+    let (thousandths, hemisphere) = if coordinate < EQUATOR {
+        (EQUATOR - coordinate, negative)
+    } else {
+        (coordinate - EQUATOR, positive)
+    };
+
+    write!(
+        f,
+        "{degrees} {minutes} {seconds}.{thousandths:03} {hemisphere}",
+        degrees = thousandths / THOUSANDTHS_PER_DEGREE,
+        minutes = thousandths / 60_000 % 60,
+        seconds = thousandths / 1000 % 60,
+        thousandths = thousandths % 1000,
+        hemisphere = hemisphere,
+    )
+}
+
+/// Writes the size or precision in meters, with centimeters only if there are any
+fn fmt_precision(f: &mut fmt::Formatter<'_>, precision: u8) -> Result<(), fmt::Error> {
+    let cm = precision_to_cm(precision);
+    let (meters, cm) = (cm / 100, cm % 100);
+    if cm == 0 {
+        write!(f, "{}m", meters)
+    } else {
+        write!(f, "{}.{:02}m", meters, cm)
+    }
+}
+
+/// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-3)
+///
+/// ```text
+/// 3. Master File Format
+///
+///    The LOC record is expressed in a master file in the following format:
+///
+///    <owner> <TTL> <class> LOC ( d1 [m1 [s1]] {"N"|"S"} d2 [m2 [s2]]
+///                                {"E"|"W"} alt["m"] [siz["m"] [hp["m"]
+///                                [vp["m"]]]] )
+/// ```
+///
+/// All of the fields are written, e.g. `42 21 54.000 N 71 6 18.000 W -24.00m 30m 10000m 10m`.
+impl fmt::Display for LOC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        fmt_coordinate(f, self.latitude, 'N', 'S')?;
+        f.write_str(" ")?;
+        fmt_coordinate(f, self.longitude, 'E', 'W')?;
+
+        let altitude = i64::from(self.altitude) - ALTITUDE_BASE_CM;
+        write!(
+            f,
+            " {sign}{meters}.{cm:02}m ",
+            sign = if altitude < 0 { "-" } else { "" },
+            meters = altitude.abs() / 100,
+            cm = altitude.abs() % 100,
+        )?;
+
+        fmt_precision(f, self.size)?;
+        f.write_str(" ")?;
+        fmt_precision(f, self.horiz_pre)?;
+        f.write_str(" ")?;
+        fmt_precision(f, self.vert_pre)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::dbg_macro, clippy::print_stdout)]
+
+    use super::*;
+
+    fn example() -> LOC {
+        // 42 21 54 N 71 06 18 W -24m 30m
+        LOC::new(
+            EQUATOR + (42 * 3600 + 21 * 60 + 54) * 1000,
+            EQUATOR - (71 * 3600 + 6 * 60 + 18) * 1000,
+            (ALTITUDE_BASE_CM - 2400) as u32,
+            0x33,
+            0x16,
+            0x13,
+        )
+        .expect("valid LOC")
+    }
+
+    #[test]
+    fn test() {
+        let rdata = example();
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        let bytes = encoder.into_bytes();
+
+        println!("bytes: {:?}", bytes);
+        assert_eq!(bytes.len(), 16);
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let read_rdata = read(&mut decoder).expect("Decoding error");
+        assert_eq!(rdata, read_rdata);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            example().to_string(),
+            "42 21 54.000 N 71 6 18.000 W -24.00m 30m 10000m 10m"
+        );
+    }
+
+    #[test]
+    fn test_precision() {
+        assert_eq!(precision_to_cm(0x12), 100);
+        assert_eq!(precision_to_cm(0x16), 1_000_000);
+        assert_eq!(cm_to_precision(0).unwrap(), 0x00);
+        assert_eq!(cm_to_precision(100).unwrap(), 0x12);
+        assert_eq!(cm_to_precision(3000).unwrap(), 0x33);
+        assert_eq!(cm_to_precision(3050).unwrap(), 0x33);
+        assert_eq!(cm_to_precision(9_000_000_000).unwrap(), 0x99);
+        assert!(cm_to_precision(9_000_000_001).is_err());
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let north_pole = EQUATOR + 90 * THOUSANDTHS_PER_DEGREE;
+        assert!(LOC::new(north_pole, EQUATOR, 0, 0x12, 0x16, 0x13).is_ok());
+        assert!(LOC::new(north_pole + 1, EQUATOR, 0, 0x12, 0x16, 0x13).is_err());
+        assert!(LOC::new(
+            EQUATOR,
+            EQUATOR - 180 * THOUSANDTHS_PER_DEGREE - 1,
+            0,
+            0x12,
+            0x16,
+            0x13
+        )
+        .is_err());
+        assert!(LOC::new(EQUATOR, EQUATOR, 0, 0xA0, 0x16, 0x13).is_err());
+
+        let bytes = [
+            1, 0x12, 0x16, 0x13, 0x80, 0, 0, 0, 0x80, 0, 0, 0, 0, 0x98, 0x96, 0x80,
+        ];
+        assert!(read(&mut BinDecoder::new(&bytes)).is_err());
+    }
+}
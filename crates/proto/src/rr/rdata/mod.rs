@@ -21,9 +21,14 @@
 // each of these module's has the parser for that rdata embedded, to keep the file sizes down...
 pub mod a;
 pub mod aaaa;
+pub mod apl;
 pub mod caa;
+pub mod cert;
 pub mod csync;
+pub mod dhcid;
 pub mod hinfo;
+pub mod hip;
+pub mod loc;
 pub mod mx;
 pub mod name;
 pub mod naptr;
@@ -36,10 +41,17 @@ pub mod sshfp;
 pub mod svcb;
 pub mod tlsa;
 pub mod txt;
+pub mod uri;
+pub mod zonemd;
 
+pub use self::apl::APL;
 pub use self::caa::CAA;
+pub use self::cert::CERT;
 pub use self::csync::CSYNC;
+pub use self::dhcid::DHCID;
 pub use self::hinfo::HINFO;
+pub use self::hip::HIP;
+pub use self::loc::LOC;
 pub use self::mx::MX;
 pub use self::naptr::NAPTR;
 pub use self::null::NULL;
@@ -51,3 +63,5 @@ pub use self::sshfp::SSHFP;
 pub use self::svcb::SVCB;
 pub use self::tlsa::TLSA;
 pub use self::txt::TXT;
+pub use self::uri::URI;
+pub use self::zonemd::ZONEMD;
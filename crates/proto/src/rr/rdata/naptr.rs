@@ -8,6 +8,7 @@
 //! Dynamic Delegation Discovery System
 
 use std::fmt;
+use std::str;
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
@@ -85,6 +86,29 @@ impl NAPTR {
         }
     }
 
+    /// Returns a builder for a NAPTR record, validating the fields on `build`
+    ///
+    /// ```
+    /// use trust_dns_proto::rr::Name;
+    /// use trust_dns_proto::rr::rdata::NAPTR;
+    ///
+    /// let naptr = NAPTR::builder()
+    ///     .order(100)
+    ///     .preference(10)
+    ///     .flags("u")
+    ///     .service("E2U+sip")
+    ///     .regexp("!^.*$!sip:info@example.com!")
+    ///     .replacement(Name::root())
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(naptr.flags_str(), Some("u"));
+    /// assert_eq!(naptr.services_str(), Some("E2U+sip"));
+    /// ```
+    pub fn builder() -> NAPTRBuilder {
+        NAPTRBuilder::default()
+    }
+
     /// ```text
     ///   ORDER
     ///      A 16-bit unsigned integer specifying the order in which the NAPTR
@@ -194,6 +218,108 @@ impl NAPTR {
     pub fn replacement(&self) -> &Name {
         &self.replacement
     }
+
+    /// The flags as a string, `None` if they are not valid utf8
+    pub fn flags_str(&self) -> Option<&str> {
+        str::from_utf8(&self.flags).ok()
+    }
+
+    /// The services as a string, `None` if they are not valid utf8
+    pub fn services_str(&self) -> Option<&str> {
+        str::from_utf8(&self.services).ok()
+    }
+
+    /// The regexp as a string, `None` if it is not valid utf8
+    pub fn regexp_str(&self) -> Option<&str> {
+        str::from_utf8(&self.regexp).ok()
+    }
+}
+
+/// A builder for NAPTR records, see `NAPTR::builder`
+#[derive(Clone, Debug)]
+pub struct NAPTRBuilder {
+    order: u16,
+    preference: u16,
+    flags: String,
+    services: String,
+    regexp: String,
+    replacement: Name,
+}
+
+impl Default for NAPTRBuilder {
+    fn default() -> Self {
+        Self {
+            order: 0,
+            preference: 0,
+            flags: String::new(),
+            services: String::new(),
+            regexp: String::new(),
+            replacement: Name::root(),
+        }
+    }
+}
+
+impl NAPTRBuilder {
+    /// Sets the order in which the records MUST be processed, default is 0
+    pub fn order(&mut self, order: u16) -> &mut Self {
+        self.order = order;
+        self
+    }
+
+    /// Sets the preference among records of the same order, default is 0
+    pub fn preference(&mut self, preference: u16) -> &mut Self {
+        self.preference = preference;
+        self
+    }
+
+    /// Sets the flags, these must be single characters from the set A-Z and 0-9
+    pub fn flags<S: Into<String>>(&mut self, flags: S) -> &mut Self {
+        self.flags = flags.into();
+        self
+    }
+
+    /// Sets the service parameters, e.g. `E2U+sip`
+    pub fn service<S: Into<String>>(&mut self, service: S) -> &mut Self {
+        self.services = service.into();
+        self
+    }
+
+    /// Sets the substitution expression, this is mutually exclusive with the replacement
+    pub fn regexp<S: Into<String>>(&mut self, regexp: S) -> &mut Self {
+        self.regexp = regexp.into();
+        self
+    }
+
+    /// Sets the next domain-name to query, the root name (the default) if the regexp is used
+    pub fn replacement(&mut self, replacement: Name) -> &mut Self {
+        self.replacement = replacement;
+        self
+    }
+
+    /// Builds the NAPTR record
+    ///
+    /// # Errors
+    ///
+    /// If the flags are not within [a-zA-Z0-9], or both the regexp and replacement are set, which the RFC
+    ///  declares to be mutually exclusive.
+    pub fn build(&self) -> ProtoResult<NAPTR> {
+        if !verify_flags(self.flags.as_bytes()) {
+            return Err(format!("flags are not within range [a-zA-Z0-9]: {}", self.flags).into());
+        }
+
+        if !self.regexp.is_empty() && !self.replacement.is_root() {
+            return Err("regexp and replacement are mutually exclusive".into());
+        }
+
+        Ok(NAPTR::new(
+            self.order,
+            self.preference,
+            self.flags.as_bytes().into(),
+            self.services.as_bytes().into(),
+            self.regexp.as_bytes().into(),
+            self.replacement.clone(),
+        ))
+    }
 }
 
 /// verifies that the flags are valid
@@ -296,6 +422,43 @@ mod tests {
         assert_eq!(rdata, read_rdata);
     }
 
+    #[test]
+    fn test_builder() {
+        use std::str::FromStr;
+
+        let rdata = NAPTR::builder()
+            .order(100)
+            .preference(10)
+            .flags("u")
+            .service("E2U+sip")
+            .regexp("!^.*$!sip:info@example.com!")
+            .replacement(Name::root())
+            .build()
+            .unwrap();
+
+        assert_eq!(rdata.order(), 100);
+        assert_eq!(rdata.preference(), 10);
+        assert_eq!(rdata.flags(), b"u");
+        assert_eq!(rdata.services_str(), Some("E2U+sip"));
+        assert_eq!(rdata.regexp_str(), Some("!^.*$!sip:info@example.com!"));
+        assert!(rdata.replacement().is_root());
+
+        let rdata = NAPTR::builder()
+            .flags("S")
+            .service("SIP+D2U")
+            .replacement(Name::from_str("_sip._udp.example.com.").unwrap())
+            .build()
+            .unwrap();
+        assert_eq!(rdata.regexp(), b"");
+
+        assert!(NAPTR::builder().flags("u!").build().is_err());
+        assert!(NAPTR::builder()
+            .regexp("!^.*$!sip:info@example.com!")
+            .replacement(Name::from_str("example.com.").unwrap())
+            .build()
+            .is_err());
+    }
+
     #[test]
     fn test_bad_data() {
         use std::str::FromStr;
@@ -18,7 +18,7 @@
 use std::fmt;
 
 #[cfg(feature = "serde-config")]
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::error::*;
 use crate::serialize::binary::*;
@@ -40,7 +40,6 @@ use crate::serialize::binary::*;
 /// allowed in Zone Files.  NULLs are used as placeholders in some
 /// experimental extensions of the DNS.
 /// ```
-#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
 #[derive(Default, Debug, PartialEq, Eq, Hash, Clone)]
 pub struct NULL {
     anything: Vec<u8>,
@@ -94,6 +93,31 @@ impl fmt::Display for NULL {
     }
 }
 
+/// The data is serialized as a hex string, e.g. for the data of unknown record types
+#[cfg(feature = "serde-config")]
+impl Serialize for NULL {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&data_encoding::HEXUPPER.encode(&self.anything))
+    }
+}
+
+#[cfg(feature = "serde-config")]
+impl<'de> Deserialize<'de> for NULL {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        let anything = data_encoding::HEXUPPER_PERMISSIVE
+            .decode(s.as_bytes())
+            .map_err(de::Error::custom)?;
+        Ok(Self { anything })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
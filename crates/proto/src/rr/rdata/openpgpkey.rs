@@ -12,6 +12,10 @@ use std::fmt;
 use serde::{Deserialize, Serialize};
 
 use crate::error::*;
+#[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+use crate::rr::dnssec::DigestType;
+#[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+use crate::rr::Name;
 use crate::serialize::binary::*;
 
 /// [RFC 7929](https://tools.ietf.org/html/rfc7929#section-2.1)
@@ -43,6 +47,49 @@ impl OPENPGPKEY {
     pub fn public_key(&self) -> &[u8] {
         &self.public_key
     }
+
+    /// The name of the OPENPGPKEY record for an email address, requires one of the
+    /// `dnssec-openssl` or `dnssec-ring` features
+    ///
+    /// # Arguments
+    ///
+    /// * `local_part` - the part of the email address before the `@`, it is used as is
+    /// * `domain` - the part of the email address after the `@`
+    ///
+    /// [RFC 7929](https://tools.ietf.org/html/rfc7929#section-3)
+    ///
+    /// ```text
+    /// 3.  Location of the OPENPGPKEY Record
+    ///
+    ///    2.  The local-part is hashed using the SHA2-256 [RFC5754] algorithm,
+    ///        with the hash truncated to 28 octets and represented in its
+    ///        hexadecimal representation, to become the left-most label in the
+    ///        prepared domain name.
+    ///
+    ///    3.  The string "_openpgpkey" becomes the second left-most label in
+    ///        the prepared domain name.
+    ///
+    ///    4.  The domain name (the "right-hand side" of the email address,
+    ///        called the "domain" in [RFC2822]) is appended to the result of
+    ///        step 2 and step 3 to complete the prepared domain name.
+    /// ```
+    #[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring"))))
+    )]
+    pub fn owner_name(local_part: &str, domain: &Name) -> ProtoResult<Name> {
+        hashed_owner_name(local_part, "_openpgpkey", domain)
+    }
+}
+
+/// The name for an email address, as used by OPENPGPKEY and SMIMEA records
+#[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+pub(crate) fn hashed_owner_name(local_part: &str, label: &str, domain: &Name) -> ProtoResult<Name> {
+    let digest = DigestType::SHA256.hash(local_part.as_bytes())?;
+    let hash = data_encoding::HEXLOWER.encode(&digest.as_ref()[..28]);
+
+    Name::from_labels(vec![hash.as_bytes(), label.as_bytes()])?.append_domain(domain)
 }
 
 /// Read the RData from the given decoder.
@@ -76,4 +123,39 @@ impl fmt::Display for OPENPGPKEY {
     }
 }
 
-// TODO test
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode() {
+        let rdata = OPENPGPKEY::new(b"trust_dns is awesome".to_vec());
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).expect("failed to emit OPENPGPKEY");
+        let bytes = encoder.into_bytes();
+        assert_eq!(bytes, b"trust_dns is awesome");
+
+        let mut decoder = BinDecoder::new(bytes);
+        let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16))
+            .expect("failed to read OPENPGPKEY");
+        assert_eq!(read_rdata, rdata);
+        assert_eq!(rdata.to_string(), "dHJ1c3RfZG5zIGlzIGF3ZXNvbWU=");
+    }
+
+    #[test]
+    #[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+    fn test_owner_name() {
+        use std::str::FromStr;
+
+        // RFC 7929, section 3, hugh@example.com
+        assert_eq!(
+            OPENPGPKEY::owner_name("hugh", &Name::from_str("example.com.").unwrap()).unwrap(),
+            Name::from_str(
+                "c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6._openpgpkey.example.com."
+            )
+            .unwrap()
+        );
+    }
+}
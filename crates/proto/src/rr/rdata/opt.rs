@@ -17,6 +17,10 @@
 //! option record for passing protocol options between the client and server
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
@@ -169,6 +173,8 @@ use crate::rr::dnssec::SupportedAlgorithms;
 #[derive(Default, Debug, PartialEq, Eq, Clone)]
 pub struct OPT {
     options: HashMap<EdnsCode, EdnsOption>,
+    /// Extended DNS Errors, [RFC 8914](https://tools.ietf.org/html/rfc8914#section-2) allows more than one
+    extended_errors: Vec<EdnsOption>,
 }
 
 impl OPT {
@@ -182,37 +188,91 @@ impl OPT {
     ///
     /// The newly created OPT data
     pub fn new(options: HashMap<EdnsCode, EdnsOption>) -> Self {
-        Self { options }
+        let mut opt = Self::default();
+        for (_, option) in options {
+            opt.insert(option);
+        }
+
+        opt
     }
 
     #[deprecated(note = "Please use as_ref() or as_mut() for shared/mutable references")]
-    /// The entire map of options
+    /// The entire map of options, without the Extended DNS Errors
     pub fn options(&self) -> &HashMap<EdnsCode, EdnsOption> {
         &self.options
     }
 
-    /// Get a single option based on the code
+    /// Get a single option based on the code, the first one for Extended DNS Errors
     pub fn get(&self, code: EdnsCode) -> Option<&EdnsOption> {
-        self.options.get(&code)
+        if code == EdnsCode::ExtendedError {
+            self.extended_errors.first()
+        } else {
+            self.options.get(&code)
+        }
+    }
+
+    /// Iterates over the options with their codes, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (EdnsCode, &EdnsOption)> {
+        self.options
+            .iter()
+            .map(|(code, option)| (*code, option))
+            .chain(
+                self.extended_errors
+                    .iter()
+                    .map(|option| (EdnsCode::ExtendedError, option)),
+            )
+    }
+
+    /// Iterates over the Extended DNS Errors, in the order in which they were inserted or read
+    pub fn extended_errors(&self) -> impl Iterator<Item = &ExtendedError> {
+        self.extended_errors
+            .iter()
+            .filter_map(|option| match option {
+                EdnsOption::ExtendedError(error) => Some(error),
+                _ => None,
+            })
     }
 
     /// Insert a new option, the key is derived from the `EdnsOption`
+    ///
+    /// An option with the same code is replaced, the last option inserted for a code wins. This is also
+    ///  the behavior when reading OPT data which contains the same code more than once. Extended DNS
+    ///  Errors are the exception, they are all kept.
     pub fn insert(&mut self, option: EdnsOption) {
-        self.options.insert((&option).into(), option);
+        if let EdnsOption::ExtendedError(..) = option {
+            self.extended_errors.push(option);
+        } else {
+            self.options.insert((&option).into(), option);
+        }
     }
 
-    /// Remove an option, the key is derived from the `EdnsOption`
+    /// Remove an option, the key is derived from the `EdnsOption`, all of them for Extended DNS Errors
     pub fn remove(&mut self, option: EdnsCode) {
+        if option == EdnsCode::ExtendedError {
+            self.extended_errors.clear();
+        }
         self.options.remove(&option);
     }
+
+    /// Returns true if there are no options
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty() && self.extended_errors.is_empty()
+    }
+
+    /// The number of options
+    pub fn len(&self) -> usize {
+        self.options.len() + self.extended_errors.len()
+    }
 }
 
+/// The map of options, without the Extended DNS Errors, see `OPT::extended_errors`
 impl AsMut<HashMap<EdnsCode, EdnsOption>> for OPT {
     fn as_mut(&mut self) -> &mut HashMap<EdnsCode, EdnsOption> {
         &mut self.options
     }
 }
 
+/// The map of options, without the Extended DNS Errors, see `OPT::extended_errors`
 impl AsRef<HashMap<EdnsCode, EdnsOption>> for OPT {
     fn as_ref(&self) -> &HashMap<EdnsCode, EdnsOption> {
         &self.options
@@ -222,7 +282,7 @@ impl AsRef<HashMap<EdnsCode, EdnsOption>> for OPT {
 /// Read the RData from the given Decoder
 pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoResult<OPT> {
     let mut state: OptReadState = OptReadState::ReadCode;
-    let mut options: HashMap<EdnsCode, EdnsOption> = HashMap::new();
+    let mut opt = OPT::default();
     let start_idx = decoder.index();
 
     // There is no unsafe direct use of the rdata length after this point
@@ -248,7 +308,7 @@ pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoR
                 // The data state does not process 0-length correctly, since it always reads at
                 // least 1 byte, thus making the length check fail.
                 state = if length == 0 {
-                    options.insert(code, (code, &[] as &[u8]).into());
+                    opt.insert((code, &[] as &[u8]).into());
                     OptReadState::ReadCode
                 } else {
                     OptReadState::Data {
@@ -268,7 +328,7 @@ pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoR
                 // TODO: can this be replaced by read_slice()?
                 collected.push(decoder.pop()?.unverified(/*byte array is safe*/));
                 if length == collected.len() {
-                    options.insert(code, (code, &collected as &[u8]).into());
+                    opt.insert((code, &collected as &[u8]).into());
                     state = OptReadState::ReadCode;
                 } else {
                     state = OptReadState::Data {
@@ -282,20 +342,19 @@ pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoR
     }
 
     if state != OptReadState::ReadCode {
-        // there was some problem parsing the data for the options, ignoring them
-        // TODO: should we ignore all of the EDNS data in this case?
+        // an option runs past the end of the record, a FORMERR per RFC 6891
         warn!("incomplete or poorly formatted EDNS options: {:?}", state);
-        options.clear();
+        return Err(ProtoErrorKind::MalformedEdns("option length exceeds the OPT record").into());
     }
 
     // the record data is stored as unstructured data, the expectation is that this will be processed after initial parsing.
-    Ok(OPT::new(options))
+    Ok(opt)
 }
 
 /// Write the RData from the given Decoder
 pub fn emit(encoder: &mut BinEncoder<'_>, opt: &OPT) -> ProtoResult<()> {
-    for (edns_code, edns_option) in opt.as_ref().iter() {
-        encoder.emit_u16(u16::from(*edns_code))?;
+    for (edns_code, edns_option) in opt.iter() {
+        encoder.emit_u16(u16::from(edns_code))?;
         encoder.emit_u16(edns_option.len())?;
         edns_option.emit(encoder)?
     }
@@ -359,6 +418,9 @@ pub enum EdnsCode {
     /// [RFC 7901, CHAIN Query Requests in DNS, Optional](https://tools.ietf.org/html/rfc7901)
     Chain,
 
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    ExtendedError,
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16),
 }
@@ -381,6 +443,7 @@ impl From<u16> for EdnsCode {
             11 => Self::Keepalive,
             12 => Self::Padding,
             13 => Self::Chain,
+            15 => Self::ExtendedError,
             _ => Self::Unknown(value),
         }
     }
@@ -403,6 +466,7 @@ impl From<EdnsCode> for u16 {
             EdnsCode::Keepalive => 11,
             EdnsCode::Padding => 12,
             EdnsCode::Chain => 13,
+            EdnsCode::ExtendedError => 15,
             EdnsCode::Unknown(value) => value,
         }
     }
@@ -432,6 +496,24 @@ pub enum EdnsOption {
     #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
     N3U(SupportedAlgorithms),
 
+    /// [RFC 7871, Client Subnet, Optional](https://tools.ietf.org/html/rfc7871)
+    Subnet(ClientSubnet),
+
+    /// [RFC 7873, DNS Cookies](https://tools.ietf.org/html/rfc7873)
+    Cookie(Cookie),
+
+    /// [RFC 8914, Extended DNS Errors](https://tools.ietf.org/html/rfc8914)
+    ExtendedError(ExtendedError),
+
+    /// [RFC 7830, The EDNS(0) Padding](https://tools.ietf.org/html/rfc7830), the number of padding octets
+    Padding(u16),
+
+    /// [RFC 7828, edns-tcp-keepalive](https://tools.ietf.org/html/rfc7828), the idle timeout in units of 100
+    ///  milliseconds
+    ///
+    /// The timeout is absent in queries, and present in the responses of servers.
+    TcpKeepalive(Option<u16>),
+
     /// Unknown, used to deal with unknown or unsupported codes
     Unknown(u16, Vec<u8>),
 }
@@ -444,6 +526,11 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.len(),
+            EdnsOption::Subnet(ref subnet) => subnet.len(),
+            EdnsOption::Cookie(ref cookie) => cookie.len(),
+            EdnsOption::ExtendedError(ref error) => error.len(),
+            EdnsOption::Padding(len) => len,
+            EdnsOption::TcpKeepalive(timeout) => timeout.map_or(0, |_| 2),
             EdnsOption::Unknown(_, ref data) => data.len() as u16, // TODO: should we verify?
         }
     }
@@ -455,6 +542,11 @@ impl EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.is_empty(),
+            EdnsOption::Subnet(..) | EdnsOption::Cookie(..) | EdnsOption::ExtendedError(..) => {
+                false
+            }
+            EdnsOption::Padding(len) => len == 0,
+            EdnsOption::TcpKeepalive(timeout) => timeout.is_none(),
             EdnsOption::Unknown(_, ref data) => data.is_empty(),
         }
     }
@@ -467,6 +559,14 @@ impl BinEncodable for EdnsOption {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.emit(encoder),
+            EdnsOption::Subnet(ref subnet) => subnet.emit(encoder),
+            EdnsOption::Cookie(ref cookie) => cookie.emit(encoder),
+            EdnsOption::ExtendedError(ref error) => error.emit(encoder),
+            EdnsOption::Padding(len) => (0..len).try_for_each(|_| encoder.emit(0)),
+            EdnsOption::TcpKeepalive(timeout) => match timeout {
+                Some(timeout) => encoder.emit_u16(timeout),
+                None => Ok(()),
+            },
             EdnsOption::Unknown(_, ref data) => encoder.emit_vec(data), // gah, clone needed or make a crazy api.
         }
     }
@@ -474,7 +574,6 @@ impl BinEncodable for EdnsOption {
 
 /// only the supported extensions are listed right now.
 impl<'a> From<(EdnsCode, &'a [u8])> for EdnsOption {
-    #[allow(clippy::match_single_binding)]
     fn from(value: (EdnsCode, &'a [u8])) -> Self {
         match value.0 {
             #[cfg(feature = "dnssec")]
@@ -483,6 +582,37 @@ impl<'a> From<(EdnsCode, &'a [u8])> for EdnsOption {
             EdnsCode::DHU => Self::DHU(value.1.into()),
             #[cfg(feature = "dnssec")]
             EdnsCode::N3U => Self::N3U(value.1.into()),
+            EdnsCode::Subnet => match ClientSubnet::try_from(value.1) {
+                Ok(subnet) => Self::Subnet(subnet),
+                Err(e) => {
+                    warn!("bad EDNS Client Subnet option, ignoring: {}", e);
+                    Self::Unknown(value.0.into(), value.1.to_vec())
+                }
+            },
+            EdnsCode::Cookie => match Cookie::try_from(value.1) {
+                Ok(cookie) => Self::Cookie(cookie),
+                Err(e) => {
+                    warn!("bad EDNS Cookie option, ignoring: {}", e);
+                    Self::Unknown(value.0.into(), value.1.to_vec())
+                }
+            },
+            EdnsCode::ExtendedError => match ExtendedError::try_from(value.1) {
+                Ok(error) => Self::ExtendedError(error),
+                Err(e) => {
+                    warn!("bad EDNS Extended Error option, ignoring: {}", e);
+                    Self::Unknown(value.0.into(), value.1.to_vec())
+                }
+            },
+            // the contents of the padding should be zero, but must be ignored by the receiver
+            EdnsCode::Padding => Self::Padding(value.1.len() as u16),
+            EdnsCode::Keepalive => match *value.1 {
+                [] => Self::TcpKeepalive(None),
+                [high, low] => Self::TcpKeepalive(Some(u16::from_be_bytes([high, low]))),
+                _ => {
+                    warn!("bad EDNS TCP Keepalive option, ignoring: {:?}", value.1);
+                    Self::Unknown(value.0.into(), value.1.to_vec())
+                }
+            },
             _ => Self::Unknown(value.0.into(), value.1.to_vec()),
         }
     }
@@ -495,6 +625,35 @@ impl<'a> From<&'a EdnsOption> for Vec<u8> {
             EdnsOption::DAU(ref algorithms)
             | EdnsOption::DHU(ref algorithms)
             | EdnsOption::N3U(ref algorithms) => algorithms.into(),
+            EdnsOption::Subnet(ref subnet) => {
+                let mut bytes = Vec::with_capacity(subnet.len() as usize);
+                let mut encoder = BinEncoder::new(&mut bytes);
+                // encoding into a Vec can not fail
+                subnet
+                    .emit(&mut encoder)
+                    .expect("failed to encode ClientSubnet");
+                bytes
+            }
+            EdnsOption::Cookie(ref cookie) => {
+                let mut bytes = Vec::with_capacity(cookie.len() as usize);
+                let mut encoder = BinEncoder::new(&mut bytes);
+                // encoding into a Vec can not fail
+                cookie.emit(&mut encoder).expect("failed to encode Cookie");
+                bytes
+            }
+            EdnsOption::ExtendedError(ref error) => {
+                let mut bytes = Vec::with_capacity(error.len() as usize);
+                let mut encoder = BinEncoder::new(&mut bytes);
+                // encoding into a Vec can not fail
+                error
+                    .emit(&mut encoder)
+                    .expect("failed to encode ExtendedError");
+                bytes
+            }
+            EdnsOption::Padding(len) => vec![0; len as usize],
+            EdnsOption::TcpKeepalive(timeout) => timeout
+                .map(|timeout| timeout.to_be_bytes().to_vec())
+                .unwrap_or_default(),
             EdnsOption::Unknown(_, ref data) => data.clone(), // gah, clone needed or make a crazy api.
         }
     }
@@ -509,11 +668,591 @@ impl<'a> From<&'a EdnsOption> for EdnsCode {
             EdnsOption::DHU(..) => Self::DHU,
             #[cfg(feature = "dnssec")]
             EdnsOption::N3U(..) => Self::N3U,
+            EdnsOption::Subnet(..) => Self::Subnet,
+            EdnsOption::Cookie(..) => Self::Cookie,
+            EdnsOption::ExtendedError(..) => Self::ExtendedError,
+            EdnsOption::Padding(..) => Self::Padding,
+            EdnsOption::TcpKeepalive(..) => Self::Keepalive,
             EdnsOption::Unknown(code, _) => code.into(),
         }
     }
 }
 
+/// [RFC 7871, Client Subnet in DNS Queries, May 2016](https://tools.ietf.org/html/rfc7871#section-6)
+///
+/// ```text
+/// 6.  Option Format
+///
+///                 +0 (MSB)                            +1 (LSB)
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    0: |                          OPTION-CODE                          |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    2: |                         OPTION-LENGTH                         |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    4: |                            FAMILY                             |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    6: |     SOURCE PREFIX-LENGTH      |     SCOPE PREFIX-LENGTH       |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    8: |                           ADDRESS...                          /
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///
+///    o  ADDRESS, variable number of octets, contains either an IPv4 or
+///       IPv6 address, depending on FAMILY, which MUST be truncated to the
+///       number of bits indicated by the SOURCE PREFIX-LENGTH field,
+///       padding with 0 bits to pad to the end of the last octet needed.
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct ClientSubnet {
+    address: IpAddr,
+    source_prefix: u8,
+    scope_prefix: u8,
+}
+
+impl ClientSubnet {
+    /// Construct a new Client Subnet option
+    ///
+    /// # Arguments
+    ///
+    /// * `address` - the address of the client, only the first `source_prefix` bits will be sent
+    /// * `source_prefix` - the number of significant bits of `address` to send, this is capped to the size of the address
+    /// * `scope_prefix` - the number of bits the response covers, this MUST be 0 in queries
+    pub fn new(address: IpAddr, source_prefix: u8, scope_prefix: u8) -> Self {
+        let max_prefix = Self::max_prefix(&address);
+
+        Self {
+            address,
+            source_prefix: source_prefix.min(max_prefix),
+            scope_prefix: scope_prefix.min(max_prefix),
+        }
+    }
+
+    /// The address of the client, truncated to the `source_prefix` when sent
+    pub fn addr(&self) -> IpAddr {
+        self.address
+    }
+
+    /// The address family, 1 for IPv4 and 2 for IPv6, see [IANA Address Family Numbers](https://www.iana.org/assignments/address-family-numbers)
+    pub fn family(&self) -> u16 {
+        match self.address {
+            IpAddr::V4(..) => 1,
+            IpAddr::V6(..) => 2,
+        }
+    }
+
+    /// The leftmost number of significant bits of `addr` used in the query
+    pub fn source_prefix(&self) -> u8 {
+        self.source_prefix
+    }
+
+    /// The leftmost number of bits of `addr` that the response covers, as set by the server
+    pub fn scope_prefix(&self) -> u8 {
+        self.scope_prefix
+    }
+
+    /// Set the scope prefix, this is what servers use to signal the coverage of the response
+    pub fn set_scope_prefix(&mut self, scope_prefix: u8) {
+        self.scope_prefix = scope_prefix.min(Self::max_prefix(&self.address));
+    }
+
+    /// Returns the length in bytes of the encoded option data
+    pub(crate) fn len(&self) -> u16 {
+        // FAMILY + SOURCE PREFIX-LENGTH + SCOPE PREFIX-LENGTH + ADDRESS
+        2 + 1 + 1 + u16::from(self.addr_len())
+    }
+
+    fn addr_len(&self) -> u8 {
+        Self::prefix_octets(self.source_prefix)
+    }
+
+    /// The number of octets needed to hold the prefix
+    fn prefix_octets(prefix: u8) -> u8 {
+        (prefix >> 3) + u8::from(prefix & 0b111 != 0)
+    }
+
+    fn max_prefix(address: &IpAddr) -> u8 {
+        match address {
+            IpAddr::V4(..) => 32,
+            IpAddr::V6(..) => 128,
+        }
+    }
+}
+
+impl BinEncodable for ClientSubnet {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.family())?;
+        encoder.emit_u8(self.source_prefix)?;
+        encoder.emit_u8(self.scope_prefix)?;
+
+        let mut octets = match self.address {
+            IpAddr::V4(addr) => addr.octets().to_vec(),
+            IpAddr::V6(addr) => addr.octets().to_vec(),
+        };
+
+        // the address MUST be truncated to the source prefix, and padded with 0 bits to the end of the last octet
+        let addr_len = self.addr_len() as usize;
+        octets.truncate(addr_len);
+        let trailing_bits = self.source_prefix % 8;
+        if trailing_bits != 0 {
+            if let Some(last) = octets.last_mut() {
+                *last &= 0xFF << (8 - trailing_bits);
+            }
+        }
+
+        encoder.emit_vec(&octets)
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ClientSubnet {
+    type Error = ProtoError;
+
+    fn try_from(value: &'a [u8]) -> ProtoResult<Self> {
+        let mut decoder = BinDecoder::new(value);
+
+        let family = decoder.read_u16()?.unverified(/*validated in match below*/);
+        let source_prefix = decoder.read_u8()?.unverified(/*validated against family below*/);
+        let scope_prefix = decoder.read_u8()?.unverified(/*validated against family below*/);
+
+        let max_prefix = match family {
+            1 => 32,
+            2 => 128,
+            _ => {
+                return Err(ProtoError::from(format!(
+                    "unsupported family in ClientSubnet: {}",
+                    family
+                )))
+            }
+        };
+
+        if source_prefix > max_prefix || scope_prefix > max_prefix {
+            return Err(ProtoError::from(format!(
+                "prefix length exceeds address size in ClientSubnet: {}/{}",
+                source_prefix, scope_prefix
+            )));
+        }
+
+        let addr_len = Self::prefix_octets(source_prefix) as usize;
+        if decoder.len() != addr_len {
+            return Err(ProtoError::from(format!(
+                "address length in ClientSubnet does not match source prefix: {} != {}",
+                decoder.len(),
+                addr_len
+            )));
+        }
+
+        let addr = decoder.read_slice(addr_len)?.unverified(/*length verified above*/);
+        let address = if family == 1 {
+            let mut octets = [0u8; 4];
+            octets[..addr_len].copy_from_slice(addr);
+            IpAddr::from(Ipv4Addr::from(octets))
+        } else {
+            let mut octets = [0u8; 16];
+            octets[..addr_len].copy_from_slice(addr);
+            IpAddr::from(Ipv6Addr::from(octets))
+        };
+
+        Ok(Self {
+            address,
+            source_prefix,
+            scope_prefix,
+        })
+    }
+}
+
+impl fmt::Display for ClientSubnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}/{}", self.address, self.source_prefix)
+    }
+}
+
+impl FromStr for ClientSubnet {
+    type Err = ProtoError;
+
+    /// Parses a subnet in CIDR notation, e.g. `192.0.2.0/24`, an address without a prefix is treated as a host address
+    fn from_str(s: &str) -> ProtoResult<Self> {
+        let (address, source_prefix) = match s.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (s, None),
+        };
+
+        let address = IpAddr::from_str(address)
+            .map_err(|e| ProtoError::from(format!("bad address in ClientSubnet {}: {}", s, e)))?;
+        let source_prefix = match source_prefix {
+            Some(prefix) => u8::from_str(prefix).map_err(|e| {
+                ProtoError::from(format!("bad prefix in ClientSubnet {}: {}", s, e))
+            })?,
+            None => Self::max_prefix(&address),
+        };
+
+        if source_prefix > Self::max_prefix(&address) {
+            return Err(ProtoError::from(format!(
+                "prefix is too large for address in ClientSubnet: {}",
+                s
+            )));
+        }
+
+        Ok(Self::new(address, source_prefix, 0))
+    }
+}
+
+/// [RFC 7873, Domain Name System (DNS) Cookies, May 2016](https://tools.ietf.org/html/rfc7873#section-4)
+///
+/// ```text
+/// 4.  DNS Cookie Option
+///
+///    The DNS Cookie Option is an OPT RR [RFC6891] option that can be
+///    included in the RDATA portion of an OPT RR in DNS requests and
+///    responses.  The option length varies, depending on the circumstances
+///    in which it is being used.  There are two cases, as described below.
+///    Both use the same OPTION-CODE; they are distinguished by their
+///    length.
+///
+///    In a request sent by a client to a server when the client does not
+///    know the server's cookie, its length is 8, consisting of an 8-byte
+///    Client Cookie.
+///
+///    In a request sent by a client when a Server Cookie is known, and in
+///    all responses to such a request, the length is variable -- from 16 to
+///    40 bytes, consisting of an 8-byte Client Cookie followed by the
+///    variable-length (8 bytes to 32 bytes) Server Cookie.
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub struct Cookie {
+    client: [u8; 8],
+    server: Option<Vec<u8>>,
+}
+
+impl Cookie {
+    /// Construct a new Cookie option
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - the client cookie, this should be unique per server
+    /// * `server` - the server cookie, as learned from the previous response from the server, must be between 8 and 32 bytes
+    pub fn new(client: [u8; 8], server: Option<Vec<u8>>) -> ProtoResult<Self> {
+        if let Some(ref server) = server {
+            Self::verify_server_len(server.len())?;
+        }
+
+        Ok(Self { client, server })
+    }
+
+    /// The client cookie
+    pub fn client(&self) -> &[u8; 8] {
+        &self.client
+    }
+
+    /// The server cookie, if known
+    pub fn server(&self) -> Option<&[u8]> {
+        self.server.as_deref()
+    }
+
+    /// Returns the length in bytes of the encoded option data
+    pub(crate) fn len(&self) -> u16 {
+        8 + self.server.as_ref().map_or(0, |server| server.len() as u16)
+    }
+
+    fn verify_server_len(len: usize) -> ProtoResult<()> {
+        if !(8..=32).contains(&len) {
+            return Err(ProtoError::from(format!(
+                "server cookie must be between 8 and 32 bytes: {}",
+                len
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl BinEncodable for Cookie {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_vec(&self.client)?;
+
+        if let Some(ref server) = self.server {
+            encoder.emit_vec(server)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for Cookie {
+    type Error = ProtoError;
+
+    fn try_from(value: &'a [u8]) -> ProtoResult<Self> {
+        if value.len() < 8 {
+            return Err(ProtoError::from(format!(
+                "client cookie must be 8 bytes: {}",
+                value.len()
+            )));
+        }
+
+        let (client_bytes, server) = value.split_at(8);
+        let mut client = [0u8; 8];
+        client.copy_from_slice(client_bytes);
+
+        let server = if server.is_empty() {
+            None
+        } else {
+            Self::verify_server_len(server.len())?;
+            Some(server.to_vec())
+        };
+
+        Ok(Self { client, server })
+    }
+}
+
+/// [RFC 8914, Extended DNS Errors, October 2020](https://tools.ietf.org/html/rfc8914#section-2)
+///
+/// ```text
+/// 2.  Extended DNS Error EDNS0 Option Format
+///
+///                                                 1   1   1   1   1   1
+///         0   1   2   3   4   5   6   7   8   9   0   1   2   3   4   5
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    0: |                            OPTION-CODE                        |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    2: |                           OPTION-LENGTH                       |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    4: | INFO-CODE                                                     |
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///    6: / EXTRA-TEXT ...                                                /
+///       +---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+---+
+///
+///    EXTRA-TEXT: a variable-length, UTF-8-encoded [RFC5198] text field
+///       that may hold additional textual information.
+/// ```
+///
+/// *note* as the options of an OPT record are keyed by their code, only one Extended DNS Error is retained per message.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Hash)]
+pub struct ExtendedError {
+    info_code: InfoCode,
+    extra_text: String,
+}
+
+impl ExtendedError {
+    /// Construct a new Extended DNS Error
+    pub fn new(info_code: InfoCode, extra_text: String) -> Self {
+        Self {
+            info_code,
+            extra_text,
+        }
+    }
+
+    /// The INFO-CODE, the reason for the error
+    pub fn info_code(&self) -> InfoCode {
+        self.info_code
+    }
+
+    /// Additional textual information about the error, may be empty
+    pub fn extra_text(&self) -> &str {
+        &self.extra_text
+    }
+
+    /// Returns the length in bytes of the encoded option data
+    pub(crate) fn len(&self) -> u16 {
+        2 + self.extra_text.len() as u16
+    }
+}
+
+impl BinEncodable for ExtendedError {
+    fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
+        encoder.emit_u16(self.info_code.into())?;
+        encoder.emit_vec(self.extra_text.as_bytes())
+    }
+}
+
+impl<'a> TryFrom<&'a [u8]> for ExtendedError {
+    type Error = ProtoError;
+
+    /// The EXTRA-TEXT is decoded lossily, malformed UTF-8 will not fail the decoding
+    fn try_from(value: &'a [u8]) -> ProtoResult<Self> {
+        let mut decoder = BinDecoder::new(value);
+
+        let info_code = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+        let extra_text = decoder.read_slice(decoder.len())?.unverified(/*decoded lossily*/);
+
+        Ok(Self {
+            info_code: info_code.into(),
+            extra_text: String::from_utf8_lossy(extra_text).into_owned(),
+        })
+    }
+}
+
+impl fmt::Display for ExtendedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        if self.extra_text.is_empty() {
+            write!(f, "{}", self.info_code)
+        } else {
+            write!(f, "{}: {}", self.info_code, self.extra_text)
+        }
+    }
+}
+
+/// The INFO-CODE of an Extended DNS Error
+///
+/// [RFC 8914, Extended DNS Errors, October 2020](https://tools.ietf.org/html/rfc8914#section-4)
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialOrd, PartialEq, Eq, Clone, Copy, Hash)]
+#[non_exhaustive]
+pub enum InfoCode {
+    /// 0 - Other Error, not matching any other code
+    Other,
+    /// 1 - Unsupported DNSKEY Algorithm
+    UnsupportedDnskeyAlgorithm,
+    /// 2 - Unsupported DS Digest Type
+    UnsupportedDsDigestType,
+    /// 3 - Stale Answer, from the cache and has expired, [RFC 8767](https://tools.ietf.org/html/rfc8767)
+    StaleAnswer,
+    /// 4 - Forged Answer, e.g. blocked by a policy
+    ForgedAnswer,
+    /// 5 - DNSSEC Indeterminate
+    DnssecIndeterminate,
+    /// 6 - DNSSEC Bogus, validation failed
+    DnssecBogus,
+    /// 7 - Signature Expired
+    SignatureExpired,
+    /// 8 - Signature Not Yet Valid
+    SignatureNotYetValid,
+    /// 9 - DNSKEY Missing
+    DnskeyMissing,
+    /// 10 - RRSIGs Missing
+    RrsigsMissing,
+    /// 11 - No Zone Key Bit Set
+    NoZoneKeyBitSet,
+    /// 12 - NSEC Missing
+    NsecMissing,
+    /// 13 - Cached Error, SERVFAIL returned from the cache
+    CachedError,
+    /// 14 - Not Ready, the server is unable to answer yet
+    NotReady,
+    /// 15 - Blocked, by the operator of the server
+    Blocked,
+    /// 16 - Censored, by an external requirement
+    Censored,
+    /// 17 - Filtered, as requested by the client
+    Filtered,
+    /// 18 - Prohibited, the client is not authorized
+    Prohibited,
+    /// 19 - Stale NXDOMAIN Answer
+    StaleNxDomainAnswer,
+    /// 20 - Not Authoritative
+    NotAuthoritative,
+    /// 21 - Not Supported, the requested operation or query is not supported
+    NotSupported,
+    /// 22 - No Reachable Authority
+    NoReachableAuthority,
+    /// 23 - Network Error
+    NetworkError,
+    /// 24 - Invalid Data, the zone data is invalid
+    InvalidData,
+    /// Unknown, or unassigned, codes
+    Unknown(u16),
+}
+
+impl From<u16> for InfoCode {
+    fn from(value: u16) -> Self {
+        match value {
+            0 => Self::Other,
+            1 => Self::UnsupportedDnskeyAlgorithm,
+            2 => Self::UnsupportedDsDigestType,
+            3 => Self::StaleAnswer,
+            4 => Self::ForgedAnswer,
+            5 => Self::DnssecIndeterminate,
+            6 => Self::DnssecBogus,
+            7 => Self::SignatureExpired,
+            8 => Self::SignatureNotYetValid,
+            9 => Self::DnskeyMissing,
+            10 => Self::RrsigsMissing,
+            11 => Self::NoZoneKeyBitSet,
+            12 => Self::NsecMissing,
+            13 => Self::CachedError,
+            14 => Self::NotReady,
+            15 => Self::Blocked,
+            16 => Self::Censored,
+            17 => Self::Filtered,
+            18 => Self::Prohibited,
+            19 => Self::StaleNxDomainAnswer,
+            20 => Self::NotAuthoritative,
+            21 => Self::NotSupported,
+            22 => Self::NoReachableAuthority,
+            23 => Self::NetworkError,
+            24 => Self::InvalidData,
+            _ => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<InfoCode> for u16 {
+    fn from(value: InfoCode) -> Self {
+        match value {
+            InfoCode::Other => 0,
+            InfoCode::UnsupportedDnskeyAlgorithm => 1,
+            InfoCode::UnsupportedDsDigestType => 2,
+            InfoCode::StaleAnswer => 3,
+            InfoCode::ForgedAnswer => 4,
+            InfoCode::DnssecIndeterminate => 5,
+            InfoCode::DnssecBogus => 6,
+            InfoCode::SignatureExpired => 7,
+            InfoCode::SignatureNotYetValid => 8,
+            InfoCode::DnskeyMissing => 9,
+            InfoCode::RrsigsMissing => 10,
+            InfoCode::NoZoneKeyBitSet => 11,
+            InfoCode::NsecMissing => 12,
+            InfoCode::CachedError => 13,
+            InfoCode::NotReady => 14,
+            InfoCode::Blocked => 15,
+            InfoCode::Censored => 16,
+            InfoCode::Filtered => 17,
+            InfoCode::Prohibited => 18,
+            InfoCode::StaleNxDomainAnswer => 19,
+            InfoCode::NotAuthoritative => 20,
+            InfoCode::NotSupported => 21,
+            InfoCode::NoReachableAuthority => 22,
+            InfoCode::NetworkError => 23,
+            InfoCode::InvalidData => 24,
+            InfoCode::Unknown(value) => value,
+        }
+    }
+}
+
+impl fmt::Display for InfoCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let purpose = match self {
+            Self::Other => "Other Error",
+            Self::UnsupportedDnskeyAlgorithm => "Unsupported DNSKEY Algorithm",
+            Self::UnsupportedDsDigestType => "Unsupported DS Digest Type",
+            Self::StaleAnswer => "Stale Answer",
+            Self::ForgedAnswer => "Forged Answer",
+            Self::DnssecIndeterminate => "DNSSEC Indeterminate",
+            Self::DnssecBogus => "DNSSEC Bogus",
+            Self::SignatureExpired => "Signature Expired",
+            Self::SignatureNotYetValid => "Signature Not Yet Valid",
+            Self::DnskeyMissing => "DNSKEY Missing",
+            Self::RrsigsMissing => "RRSIGs Missing",
+            Self::NoZoneKeyBitSet => "No Zone Key Bit Set",
+            Self::NsecMissing => "NSEC Missing",
+            Self::CachedError => "Cached Error",
+            Self::NotReady => "Not Ready",
+            Self::Blocked => "Blocked",
+            Self::Censored => "Censored",
+            Self::Filtered => "Filtered",
+            Self::Prohibited => "Prohibited",
+            Self::StaleNxDomainAnswer => "Stale NXDOMAIN Answer",
+            Self::NotAuthoritative => "Not Authoritative",
+            Self::NotSupported => "Not Supported",
+            Self::NoReachableAuthority => "No Reachable Authority",
+            Self::NetworkError => "Network Error",
+            Self::InvalidData => "Invalid Data",
+            Self::Unknown(code) => return write!(f, "Unknown Error ({})", code),
+        };
+
+        f.write_str(purpose)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -556,13 +1295,247 @@ mod tests {
 
         let opt = read_rdata.unwrap();
         let mut options = HashMap::default();
-        options.insert(EdnsCode::Subnet, EdnsOption::Unknown(8, vec![0, 1, 0, 0]));
+        options.insert(
+            EdnsCode::Subnet,
+            EdnsOption::Subnet(ClientSubnet::new(Ipv4Addr::UNSPECIFIED.into(), 0, 0)),
+        );
         options.insert(
             EdnsCode::Cookie,
-            EdnsOption::Unknown(10, vec![0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f]),
+            EdnsOption::Cookie(
+                Cookie::new([0x0b, 0x64, 0xb4, 0xdc, 0xd7, 0xb0, 0xcc, 0x8f], None).unwrap(),
+            ),
         );
-        options.insert(EdnsCode::Keepalive, EdnsOption::Unknown(11, vec![]));
+        options.insert(EdnsCode::Keepalive, EdnsOption::TcpKeepalive(None));
         let options = OPT::new(options);
         assert_eq!(opt, options);
     }
+
+    #[test]
+    fn test_read_duplicate_option_last_wins() {
+        let bytes: &[u8] = &[
+            0xfd, 0xe9, 0x00, 0x01, 0x01, // first 65001
+            0x00, 0x0c, 0x00, 0x00, // padding
+            0xfd, 0xe9, 0x00, 0x02, 0x02, 0x03, // second 65001
+        ];
+
+        let mut decoder = BinDecoder::new(bytes);
+        let opt = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+
+        let mut options = opt.iter().collect::<Vec<_>>();
+        options.sort_by_key(|(code, _)| u16::from(*code));
+        assert_eq!(
+            options,
+            vec![
+                (EdnsCode::Padding, &EdnsOption::Padding(0)),
+                (
+                    EdnsCode::Unknown(65001),
+                    &EdnsOption::Unknown(65001, vec![2, 3])
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_truncated_option() {
+        // the length of the option exceeds the record
+        let bytes: &[u8] = &[0x00, 0x0c, 0x00, 0x04, 0x00, 0x00];
+        let mut decoder = BinDecoder::new(bytes);
+        let error = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap_err();
+        assert!(matches!(error.kind(), ProtoErrorKind::MalformedEdns(_)));
+
+        // the option code is cut short, followed by the next record of the message
+        let bytes: &[u8] = &[0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00];
+        let mut decoder = BinDecoder::new(bytes);
+        let error = read(&mut decoder, Restrict::new(5)).unwrap_err();
+        assert!(matches!(error.kind(), ProtoErrorKind::MalformedEdns(_)));
+    }
+
+    #[test]
+    fn test_client_subnet() {
+        let subnet = ClientSubnet::from_str("192.0.2.129/25").unwrap();
+        assert_eq!(subnet.family(), 1);
+        assert_eq!(subnet.source_prefix(), 25);
+        assert_eq!(subnet.scope_prefix(), 0);
+        assert_eq!(subnet.len(), 8);
+
+        let bytes: Vec<u8> = (&EdnsOption::Subnet(subnet)).into();
+        // the address is truncated to the source prefix
+        assert_eq!(bytes, vec![0, 1, 25, 0, 192, 0, 2, 128]);
+
+        let subnet = ClientSubnet::try_from(&bytes as &[u8]).unwrap();
+        assert_eq!(subnet.addr(), IpAddr::from(Ipv4Addr::new(192, 0, 2, 128)));
+        assert_eq!(subnet.source_prefix(), 25);
+
+        let mut rdata = OPT::default();
+        rdata.insert(EdnsOption::Subnet(
+            ClientSubnet::from_str("2001:db8::/56").unwrap(),
+        ));
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).unwrap();
+        let bytes = encoder.into_bytes();
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+        assert_eq!(rdata, read_rdata);
+    }
+
+    #[test]
+    fn test_client_subnet_scope_from_response() {
+        // family 2, source /56, scope /48
+        let bytes: &[u8] = &[0, 2, 56, 48, 0x20, 0x01, 0x0d, 0xb8, 0, 0, 0];
+        let option = EdnsOption::from((EdnsCode::Subnet, bytes));
+
+        let subnet = match option {
+            EdnsOption::Subnet(subnet) => subnet,
+            _ => panic!("expected a ClientSubnet: {:?}", option),
+        };
+        assert_eq!(subnet.scope_prefix(), 48);
+        assert_eq!(
+            subnet.addr(),
+            IpAddr::from(Ipv6Addr::from_str("2001:db8::").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_bad_client_subnet() {
+        // address longer than the prefix, this is kept as raw data
+        let bytes: &[u8] = &[0, 1, 8, 0, 192, 0];
+        assert_eq!(
+            EdnsOption::from((EdnsCode::Subnet, bytes)),
+            EdnsOption::Unknown(8, bytes.to_vec())
+        );
+
+        assert!(ClientSubnet::from_str("192.0.2.0/33").is_err());
+    }
+
+    #[test]
+    fn test_cookie() {
+        let cookie = Cookie::new(*b"clientck", Some(b"servercookie".to_vec())).unwrap();
+        assert_eq!(cookie.len(), 20);
+
+        let bytes: Vec<u8> = (&EdnsOption::Cookie(cookie.clone())).into();
+        assert_eq!(bytes, b"clientckservercookie".to_vec());
+        assert_eq!(
+            EdnsOption::from((EdnsCode::Cookie, &bytes as &[u8])),
+            EdnsOption::Cookie(cookie)
+        );
+
+        // server cookies must be 8 to 32 bytes
+        assert!(Cookie::new(*b"clientck", Some(b"short".to_vec())).is_err());
+        assert!(Cookie::new(*b"clientck", Some(vec![0; 33])).is_err());
+
+        let bytes: &[u8] = b"clientckshort";
+        assert_eq!(
+            EdnsOption::from((EdnsCode::Cookie, bytes)),
+            EdnsOption::Unknown(10, bytes.to_vec())
+        );
+    }
+
+    #[test]
+    fn test_extended_error() {
+        let error = ExtendedError::new(InfoCode::DnssecBogus, "bad signature".to_string());
+
+        let bytes: Vec<u8> = (&EdnsOption::ExtendedError(error.clone())).into();
+        assert_eq!(&bytes[..2], &[0, 6]);
+        assert_eq!(
+            EdnsOption::from((EdnsCode::ExtendedError, &bytes as &[u8])),
+            EdnsOption::ExtendedError(error)
+        );
+
+        // malformed UTF-8 is decoded lossily
+        let bytes: &[u8] = &[0, 18, b'n', b'o', 0xFF];
+        let error = ExtendedError::try_from(bytes).unwrap();
+        assert_eq!(error.info_code(), InfoCode::Prohibited);
+        assert_eq!(error.extra_text(), "no\u{FFFD}");
+
+        assert_eq!(InfoCode::from(600), InfoCode::Unknown(600));
+        assert_eq!(u16::from(InfoCode::Unknown(600)), 600);
+    }
+
+    #[test]
+    fn test_multiple_extended_errors() {
+        let errors = vec![
+            ExtendedError::new(InfoCode::DnssecBogus, "bad signature".to_string()),
+            ExtendedError::new(InfoCode::StaleAnswer, String::new()),
+        ];
+
+        let mut opt = OPT::default();
+        opt.insert(EdnsOption::Padding(0));
+        for error in &errors {
+            opt.insert(EdnsOption::ExtendedError(error.clone()));
+        }
+        assert_eq!(opt.len(), 3);
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &opt).unwrap();
+
+        let mut decoder = BinDecoder::new(&bytes);
+        let read_opt = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+        assert_eq!(
+            read_opt.extended_errors().collect::<Vec<_>>(),
+            vec![&errors[0], &errors[1]]
+        );
+        assert_eq!(
+            read_opt.get(EdnsCode::ExtendedError),
+            Some(&EdnsOption::ExtendedError(errors[0].clone()))
+        );
+        assert_eq!(
+            read_opt.get(EdnsCode::Padding),
+            Some(&EdnsOption::Padding(0))
+        );
+
+        let mut opt = read_opt;
+        opt.remove(EdnsCode::ExtendedError);
+        assert_eq!(opt.extended_errors().count(), 0);
+        assert_eq!(opt.len(), 1);
+    }
+
+    #[test]
+    fn test_padding() {
+        let mut rdata = OPT::default();
+        rdata.insert(EdnsOption::Padding(5));
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).unwrap();
+        assert_eq!(bytes, vec![0, 12, 0, 5, 0, 0, 0, 0, 0]);
+
+        // the contents are ignored
+        let bytes: &[u8] = &[1, 2, 3];
+        assert_eq!(
+            EdnsOption::from((EdnsCode::Padding, bytes)),
+            EdnsOption::Padding(3)
+        );
+    }
+
+    #[test]
+    fn test_tcp_keepalive() {
+        let mut rdata = OPT::default();
+        rdata.insert(EdnsOption::TcpKeepalive(Some(1200)));
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).unwrap();
+        assert_eq!(bytes, vec![0, 11, 0, 2, 0x04, 0xb0]);
+
+        let mut decoder = BinDecoder::new(&bytes);
+        let read_rdata = read(&mut decoder, Restrict::new(bytes.len() as u16)).unwrap();
+        assert_eq!(read_rdata, rdata);
+
+        // queries carry no timeout
+        let empty: &[u8] = &[];
+        let option = EdnsOption::from((EdnsCode::Keepalive, empty));
+        assert_eq!(option, EdnsOption::TcpKeepalive(None));
+        assert!(option.is_empty());
+        assert!(Vec::<u8>::from(&option).is_empty());
+
+        let bad: &[u8] = &[1, 2, 3];
+        assert_eq!(
+            EdnsOption::from((EdnsCode::Keepalive, bad)),
+            EdnsOption::Unknown(11, vec![1, 2, 3])
+        );
+    }
 }
@@ -150,6 +150,90 @@ impl SVCB {
     pub fn svc_params(&self) -> &[(SvcParamKey, SvcParamValue)] {
         &self.svc_params
     }
+
+    /// Returns the value associated to the given key, if present
+    pub fn svc_param(&self, key: SvcParamKey) -> Option<&SvcParamValue> {
+        self.svc_params
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// The list of keys that must be understood by the client for this record to be usable, see [`Mandatory`]
+    pub fn mandatory(&self) -> Option<&[SvcParamKey]> {
+        self.svc_param(SvcParamKey::Mandatory)
+            .and_then(SvcParamValue::as_mandatory)
+            .map(|mandatory| mandatory.0.as_slice())
+    }
+
+    /// The ALPN protocol identifiers supported by this endpoint, see [`Alpn`]
+    pub fn alpn(&self) -> Option<&Alpn> {
+        self.svc_param(SvcParamKey::Alpn)
+            .and_then(SvcParamValue::as_alpn)
+    }
+
+    /// Returns true if the `no-default-alpn` key is present, i.e. the default protocol of the scheme is not supported
+    pub fn no_default_alpn(&self) -> bool {
+        self.svc_param(SvcParamKey::NoDefaultAlpn).is_some()
+    }
+
+    /// The port that should be used to connect to this endpoint, if different from the default
+    pub fn port(&self) -> Option<u16> {
+        self.svc_param(SvcParamKey::Port)
+            .and_then(SvcParamValue::as_port)
+            .copied()
+    }
+
+    /// The IPv4 address hints for the target, empty if none were present
+    pub fn ipv4hint(&self) -> &[Ipv4Addr] {
+        self.svc_param(SvcParamKey::Ipv4Hint)
+            .and_then(SvcParamValue::as_ipv4_hint)
+            .map_or(&[], |hint| hint.0.as_slice())
+    }
+
+    /// The Encrypted ClientHello configuration, see [`EchConfig`]
+    pub fn ech_config(&self) -> Option<&EchConfig> {
+        self.svc_param(SvcParamKey::EchConfig)
+            .and_then(SvcParamValue::as_ech_config)
+    }
+
+    /// The IPv6 address hints for the target, empty if none were present
+    pub fn ipv6hint(&self) -> &[Ipv6Addr] {
+        self.svc_param(SvcParamKey::Ipv6Hint)
+            .and_then(SvcParamValue::as_ipv6_hint)
+            .map_or(&[], |hint| hint.0.as_slice())
+    }
+
+    /// Validates that all keys listed in the `mandatory` parameter are present in the SvcParams
+    ///
+    /// ```text
+    ///    For self-consistency (Section 2.4.3), listed keys MUST also appear in the
+    ///    SvcParams.
+    ///
+    ///    This SvcParamKey is always automatically mandatory, and MUST NOT
+    ///    appear in its own value-list.
+    /// ```
+    fn validate_mandatory(&self) -> ProtoResult<()> {
+        let mandatory = match self.mandatory() {
+            Some(mandatory) => mandatory,
+            None => return Ok(()),
+        };
+
+        for key in mandatory {
+            if *key == SvcParamKey::Mandatory {
+                return Err(ProtoError::from("mandatory key must not list itself"));
+            }
+
+            if self.svc_param(*key).is_none() {
+                return Err(ProtoError::from(format!(
+                    "mandatory key {} is missing from SvcParams",
+                    key
+                )));
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// ```text
@@ -1017,7 +1101,8 @@ impl<'r> BinDecodable<'r> for Unknown {
 
 impl BinEncodable for Unknown {
     fn emit(&self, encoder: &mut BinEncoder<'_>) -> ProtoResult<()> {
-        encoder.emit_character_data(&self.0)?;
+        // the data is stored exactly as read, the length is written by SvcParamValue
+        encoder.emit_vec(&self.0)?;
 
         Ok(())
     }
@@ -1086,15 +1171,20 @@ pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoR
             .unverified(); // valid len
     }
 
-    Ok(SVCB {
+    let svcb = SVCB {
         svc_priority,
         target_name,
         svc_params,
-    })
+    };
+
+    svcb.validate_mandatory()?;
+    Ok(svcb)
 }
 
 /// Write the RData from the given Decoder
 pub fn emit(encoder: &mut BinEncoder<'_>, svcb: &SVCB) -> ProtoResult<()> {
+    svcb.validate_mandatory()?;
+
     svcb.svc_priority.emit(encoder)?;
     svcb.target_name.emit(encoder)?;
 
@@ -1241,6 +1331,87 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_encode_decode_svcb_unknown() {
+        test_encode_decode(SVCB::new(
+            1,
+            Name::from_utf8(".").unwrap(),
+            vec![
+                (
+                    SvcParamKey::Key(65333),
+                    SvcParamValue::Unknown(Unknown(b"ex1".to_vec())),
+                ),
+                (
+                    SvcParamKey::Key65535,
+                    SvcParamValue::Unknown(Unknown(vec![])),
+                ),
+            ],
+        ));
+    }
+
+    #[test]
+    fn test_typed_accessors() {
+        let svcb = SVCB::new(
+            1,
+            Name::from_utf8(".").unwrap(),
+            vec![
+                (
+                    SvcParamKey::Mandatory,
+                    SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Port])),
+                ),
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["h2".to_string(), "h3".to_string()])),
+                ),
+                (SvcParamKey::Port, SvcParamValue::Port(8443)),
+                (
+                    SvcParamKey::Ipv4Hint,
+                    SvcParamValue::Ipv4Hint(IpHint(vec![Ipv4Addr::new(192, 0, 2, 1)])),
+                ),
+            ],
+        );
+
+        assert_eq!(svcb.mandatory(), Some(&[SvcParamKey::Port][..]));
+        assert_eq!(
+            svcb.alpn().map(|alpn| alpn.0.as_slice()),
+            Some(&["h2".to_string(), "h3".to_string()][..])
+        );
+        assert!(!svcb.no_default_alpn());
+        assert_eq!(svcb.port(), Some(8443));
+        assert_eq!(svcb.ipv4hint(), &[Ipv4Addr::new(192, 0, 2, 1)]);
+        assert!(svcb.ipv6hint().is_empty());
+        assert!(svcb.ech_config().is_none());
+
+        test_encode_decode(svcb);
+    }
+
+    #[test]
+    fn test_mandatory_key_missing() {
+        let svcb = SVCB::new(
+            1,
+            Name::from_utf8(".").unwrap(),
+            vec![
+                (
+                    SvcParamKey::Mandatory,
+                    SvcParamValue::Mandatory(Mandatory(vec![SvcParamKey::Port])),
+                ),
+                (
+                    SvcParamKey::Alpn,
+                    SvcParamValue::Alpn(Alpn(vec!["h2".to_string()])),
+                ),
+            ],
+        );
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &svcb).is_err());
+
+        // priority 1, target ".", mandatory=port, alpn=h2
+        const BUF: &[u8] = &[0, 1, 0, 0, 0, 0, 2, 0, 3, 0, 1, 0, 3, 2, b'h', b'2'];
+        let mut decoder = BinDecoder::new(BUF);
+        assert!(read(&mut decoder, Restrict::new(BUF.len() as u16)).is_err());
+    }
+
     #[test]
     fn test_no_panic() {
         const BUF: &[u8] = &[
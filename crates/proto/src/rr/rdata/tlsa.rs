@@ -10,10 +10,15 @@ use std::fmt;
 
 #[cfg(feature = "serde-config")]
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
 use super::sshfp;
 
 use crate::error::*;
+#[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+use crate::rr::dnssec::DigestType;
+#[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+use crate::rr::Name;
 use crate::serialize::binary::*;
 
 /// [RFC 6698, DNS-Based Authentication for TLS](https://tools.ietf.org/html/rfc6698#section-2.1)
@@ -335,6 +340,277 @@ impl TLSA {
     pub fn cert_data(&self) -> &[u8] {
         &self.cert_data
     }
+
+    /// The name of the SMIMEA record for an email address, requires one of the
+    /// `dnssec-openssl` or `dnssec-ring` features
+    ///
+    /// SMIMEA records use the same format as TLSA records, see `RData::SMIMEA`.
+    ///
+    /// # Arguments
+    ///
+    /// * `local_part` - the part of the email address before the `@`, it is used as is
+    /// * `domain` - the part of the email address after the `@`
+    ///
+    /// [RFC 8162](https://tools.ietf.org/html/rfc8162#section-3)
+    ///
+    /// ```text
+    /// 3.  Location of the SMIMEA Record
+    ///
+    ///    2.  The local-part is hashed using the SHA2-256 [RFC5754] algorithm,
+    ///        with the hash truncated to 28 octets and represented in its
+    ///        hexadecimal representation, to become the left-most label in the
+    ///        prepared domain name.
+    ///
+    ///    3.  The string "_smimecert" becomes the second left-most label in
+    ///        the prepared domain name.
+    ///
+    ///    4.  The domain name (the "right-hand side" of the email address,
+    ///        called the "domain" in [RFC5322]) is appended to the result of
+    ///        step 2 and step 3 to complete the prepared domain name.
+    /// ```
+    #[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+    #[cfg_attr(
+        docsrs,
+        doc(cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring"))))
+    )]
+    pub fn smimea_owner_name(local_part: &str, domain: &Name) -> ProtoResult<Name> {
+        super::openpgpkey::hashed_owner_name(local_part, "_smimecert", domain)
+    }
+
+    /// Checks the DER encoded certificate against this association
+    ///
+    /// The selector picks either the full certificate or its SubjectPublicKeyInfo, which is then
+    /// compared exactly or by its SHA-256 or SHA-512 digest, as specified by the matching type.
+    /// The digest matching types require one of the `dnssec-openssl` or `dnssec-ring` features.
+    ///
+    /// This only checks the association, no chain validation is performed. For the trust anchor
+    /// usages the certificate should be each of the certificates in the presented chain, for the
+    /// end entity usages it should be the server certificate. The returned [`DaneMatch`] tells the
+    /// caller whether PKIX validation of the chain is additionally required.
+    pub fn verify(&self, cert_der: &[u8]) -> Result<DaneMatch, DaneError> {
+        let dane_match = match self.cert_usage {
+            CertUsage::CA => DaneMatch::PkixTrustAnchor,
+            CertUsage::Service => DaneMatch::PkixEndEntity,
+            CertUsage::TrustAnchor => DaneMatch::DaneTrustAnchor,
+            CertUsage::DomainIssued => DaneMatch::DaneEndEntity,
+            usage => return Err(DaneError::UnsupportedUsage(usage)),
+        };
+
+        let cert = read_certificate(cert_der)?;
+        let selected = match self.selector {
+            Selector::Full => cert.element,
+            Selector::Spki => subject_public_key_info(cert.contents)?,
+            selector => return Err(DaneError::UnsupportedSelector(selector)),
+        };
+
+        let matches = match self.matching {
+            Matching::Raw => selected == self.cert_data.as_slice(),
+            Matching::Sha256 | Matching::Sha512 => {
+                digest_matches(self.matching, selected, &self.cert_data)?
+            }
+            matching => return Err(DaneError::UnsupportedMatching(matching)),
+        };
+
+        if matches {
+            Ok(dane_match)
+        } else {
+            Err(DaneError::Mismatch)
+        }
+    }
+}
+
+/// The result of a successful [`TLSA::verify`], derived from the certificate usage
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum DaneMatch {
+    /// PKIX-TA(0), the matched certificate must be a CA in a PKIX validated path
+    PkixTrustAnchor,
+    /// PKIX-EE(1), the matched end entity certificate must also pass PKIX validation
+    PkixEndEntity,
+    /// DANE-TA(2), the matched certificate is to be used as the trust anchor for the path
+    DaneTrustAnchor,
+    /// DANE-EE(3), the matched end entity certificate is trusted without PKIX validation
+    DaneEndEntity,
+}
+
+impl DaneMatch {
+    /// Returns true if the chain must still be validated against the PKIX trust anchors
+    pub fn requires_pkix_validation(self) -> bool {
+        matches!(self, Self::PkixTrustAnchor | Self::PkixEndEntity)
+    }
+
+    /// Returns true if the association is for a CA or trust anchor, rather than the end entity
+    pub fn is_trust_anchor(self) -> bool {
+        matches!(self, Self::PkixTrustAnchor | Self::DaneTrustAnchor)
+    }
+}
+
+/// Errors from [`TLSA::verify`]
+#[derive(Debug, Error, Clone)]
+#[non_exhaustive]
+pub enum DaneError {
+    /// The certificate did not match the association data
+    #[error("certificate does not match the TLSA association data")]
+    Mismatch,
+
+    /// The certificate could not be parsed
+    #[error("malformed certificate: {0}")]
+    MalformedCertificate(&'static str),
+
+    /// The certificate usage is not one of those defined in RFC 6698
+    #[error("unsupported TLSA certificate usage: {0:?}")]
+    UnsupportedUsage(CertUsage),
+
+    /// The selector is not one of those defined in RFC 6698
+    #[error("unsupported TLSA selector: {0:?}")]
+    UnsupportedSelector(Selector),
+
+    /// The matching type is not one of those defined in RFC 6698
+    #[error("unsupported TLSA matching type: {0:?}")]
+    UnsupportedMatching(Matching),
+
+    /// The digest of the selected data could not be computed
+    #[error("digest error: {0}")]
+    Digest(#[from] ProtoError),
+}
+
+const DER_SEQUENCE: u8 = 0x30;
+const DER_EXPLICIT_VERSION: u8 = 0xA0;
+
+/// A single DER element
+struct Der<'a> {
+    tag: u8,
+    /// the complete encoding, including the tag and length
+    element: &'a [u8],
+    contents: &'a [u8],
+    /// the data following the element
+    rest: &'a [u8],
+}
+
+/// Reads a single DER element
+fn read_der(data: &[u8]) -> Result<Der<'_>, DaneError> {
+    let truncated = DaneError::MalformedCertificate("truncated DER element");
+
+    let (&tag, after_tag) = data.split_first().ok_or_else(|| truncated.clone())?;
+    // multi-byte tags are not used in the certificate structure we walk
+    if tag & 0x1F == 0x1F {
+        return Err(DaneError::MalformedCertificate("unsupported DER tag"));
+    }
+
+    let (&first, after_len) = after_tag.split_first().ok_or_else(|| truncated.clone())?;
+    let (len, contents) = if first & 0x80 == 0 {
+        (first as usize, after_len)
+    } else {
+        let num_bytes = (first & 0x7F) as usize;
+        if num_bytes == 0 || num_bytes > 4 || after_len.len() < num_bytes {
+            return Err(DaneError::MalformedCertificate("invalid DER length"));
+        }
+
+        let (len_bytes, contents) = after_len.split_at(num_bytes);
+        let len = len_bytes
+            .iter()
+            .fold(0_usize, |len, &b| (len << 8) | b as usize);
+        (len, contents)
+    };
+
+    if contents.len() < len {
+        return Err(truncated);
+    }
+
+    let header_len = data.len() - contents.len();
+    let (element, rest) = data.split_at(header_len + len);
+    Ok(Der {
+        tag,
+        element,
+        contents: &contents[..len],
+        rest,
+    })
+}
+
+/// Reads the Certificate sequence of a DER encoded certificate
+fn read_certificate(cert_der: &[u8]) -> Result<Der<'_>, DaneError> {
+    let cert = read_der(cert_der)?;
+    if cert.tag != DER_SEQUENCE || !cert.rest.is_empty() {
+        return Err(DaneError::MalformedCertificate(
+            "certificate is not a single sequence",
+        ));
+    }
+
+    Ok(cert)
+}
+
+/// Returns the DER encoded SubjectPublicKeyInfo of a DER encoded certificate
+#[cfg(feature = "dns-over-rustls")]
+pub(crate) fn certificate_spki(cert_der: &[u8]) -> Result<&[u8], DaneError> {
+    subject_public_key_info(read_certificate(cert_der)?.contents)
+}
+
+/// Returns the DER encoded SubjectPublicKeyInfo from the contents of the Certificate sequence
+///
+/// ```text
+/// Certificate  ::=  SEQUENCE  {
+///      tbsCertificate       TBSCertificate,
+///      signatureAlgorithm   AlgorithmIdentifier,
+///      signatureValue       BIT STRING  }
+///
+/// TBSCertificate  ::=  SEQUENCE  {
+///      version         [0]  EXPLICIT Version DEFAULT v1,
+///      serialNumber         CertificateSerialNumber,
+///      signature            AlgorithmIdentifier,
+///      issuer               Name,
+///      validity             Validity,
+///      subject              Name,
+///      subjectPublicKeyInfo SubjectPublicKeyInfo,
+///      ... }
+/// ```
+fn subject_public_key_info(cert: &[u8]) -> Result<&[u8], DaneError> {
+    let tbs = read_der(cert)?;
+    if tbs.tag != DER_SEQUENCE {
+        return Err(DaneError::MalformedCertificate(
+            "tbsCertificate is not a sequence",
+        ));
+    }
+
+    let version = read_der(tbs.contents)?;
+    let mut rest = if version.tag == DER_EXPLICIT_VERSION {
+        version.rest
+    } else {
+        // v1 certificates have no version, the first element is the serial number
+        tbs.contents
+    };
+
+    // serialNumber, signature, issuer, validity and subject
+    for _ in 0..5 {
+        rest = read_der(rest)?.rest;
+    }
+
+    let spki = read_der(rest)?;
+    if spki.tag != DER_SEQUENCE {
+        return Err(DaneError::MalformedCertificate(
+            "subjectPublicKeyInfo is not a sequence",
+        ));
+    }
+
+    Ok(spki.element)
+}
+
+#[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+fn digest_matches(matching: Matching, data: &[u8], expected: &[u8]) -> Result<bool, DaneError> {
+    let digest_type = match matching {
+        Matching::Sha256 => DigestType::SHA256,
+        Matching::Sha512 => DigestType::SHA512,
+        matching => return Err(DaneError::UnsupportedMatching(matching)),
+    };
+
+    let digest = digest_type.hash(data)?;
+    Ok(digest.as_ref() == expected)
+}
+
+#[cfg(not(all(feature = "dnssec", any(feature = "openssl", feature = "ring"))))]
+fn digest_matches(_: Matching, _: &[u8], _: &[u8]) -> Result<bool, DaneError> {
+    Err(
+        ProtoError::from("TLSA digests require the dnssec feature and one of openssl or ring")
+            .into(),
+    )
 }
 
 /// Read the RData from the given Decoder
@@ -539,4 +815,165 @@ mod tests {
             vec![1, 2, 3, 4, 5, 6, 7, 8],
         ));
     }
+
+    const CA_DER: &[u8] = include_bytes!("../../../../../tests/test-data/ca.der");
+
+    #[test]
+    fn test_verify_full_raw() {
+        let tlsa = TLSA::new(
+            CertUsage::DomainIssued,
+            Selector::Full,
+            Matching::Raw,
+            CA_DER.to_vec(),
+        );
+        assert_eq!(tlsa.verify(CA_DER).unwrap(), DaneMatch::DaneEndEntity);
+
+        let mut other = CA_DER.to_vec();
+        let last = other.len() - 1;
+        other[last] ^= 0xFF;
+        assert!(matches!(tlsa.verify(&other), Err(DaneError::Mismatch)));
+    }
+
+    #[test]
+    fn test_verify_spki_raw() {
+        let cert = read_der(CA_DER).unwrap();
+        let spki = subject_public_key_info(cert.contents).unwrap();
+        // SEQUENCE { AlgorithmIdentifier, BIT STRING }
+        let der = read_der(spki).unwrap();
+        assert_eq!(der.tag, DER_SEQUENCE);
+        assert!(der.rest.is_empty());
+        let algorithm = read_der(der.contents).unwrap();
+        assert_eq!(algorithm.tag, DER_SEQUENCE);
+        let key = read_der(algorithm.rest).unwrap();
+        assert_eq!(key.tag, 0x03);
+        assert!(key.rest.is_empty());
+
+        let tlsa = TLSA::new(CertUsage::CA, Selector::Spki, Matching::Raw, spki.to_vec());
+        let dane_match = tlsa.verify(CA_DER).unwrap();
+        assert_eq!(dane_match, DaneMatch::PkixTrustAnchor);
+        assert!(dane_match.requires_pkix_validation());
+        assert!(dane_match.is_trust_anchor());
+    }
+
+    #[test]
+    #[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+    fn test_smimea_owner_name() {
+        use std::str::FromStr;
+
+        assert_eq!(
+            TLSA::smimea_owner_name("hugh", &Name::from_str("example.com.").unwrap()).unwrap(),
+            Name::from_str(
+                "c93f1e400f26708f98cb19d936620da35eec8f72e57f9eec01c1afd6._smimecert.example.com."
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "dnssec", any(feature = "openssl", feature = "ring")))]
+    fn test_verify_digests() {
+        // openssl x509 -inform der -in ca.der -pubkey -noout | openssl pkey -pubin -outform der | sha256sum
+        let spki_sha256 = sshfp::HEX
+            .decode(b"16677acccf77b6d15690c9b354ec1ebc3c02f211feb1ccd83156b86abc7a500a")
+            .unwrap();
+        let tlsa = TLSA::new(
+            CertUsage::Service,
+            Selector::Spki,
+            Matching::Sha256,
+            spki_sha256,
+        );
+        let dane_match = tlsa.verify(CA_DER).unwrap();
+        assert_eq!(dane_match, DaneMatch::PkixEndEntity);
+        assert!(!dane_match.is_trust_anchor());
+
+        // sha256sum ca.der
+        let cert_sha256 = sshfp::HEX
+            .decode(b"bcc9a428e53146dddcbc07e4433584997a55fc7b876cff2681718459148d8655")
+            .unwrap();
+        let tlsa = TLSA::new(
+            CertUsage::TrustAnchor,
+            Selector::Full,
+            Matching::Sha256,
+            cert_sha256,
+        );
+        let dane_match = tlsa.verify(CA_DER).unwrap();
+        assert_eq!(dane_match, DaneMatch::DaneTrustAnchor);
+        assert!(!dane_match.requires_pkix_validation());
+
+        let cert_sha512 = DigestType::SHA512.hash(CA_DER).unwrap();
+        let tlsa = TLSA::new(
+            CertUsage::DomainIssued,
+            Selector::Full,
+            Matching::Sha512,
+            cert_sha512.as_ref().to_vec(),
+        );
+        assert_eq!(tlsa.verify(CA_DER).unwrap(), DaneMatch::DaneEndEntity);
+
+        let tlsa = TLSA::new(
+            CertUsage::DomainIssued,
+            Selector::Spki,
+            Matching::Sha512,
+            cert_sha512.as_ref().to_vec(),
+        );
+        assert!(matches!(tlsa.verify(CA_DER), Err(DaneError::Mismatch)));
+    }
+
+    #[test]
+    fn test_verify_unsupported() {
+        let tlsa = TLSA::new(
+            CertUsage::Private,
+            Selector::Full,
+            Matching::Raw,
+            CA_DER.to_vec(),
+        );
+        assert!(matches!(
+            tlsa.verify(CA_DER),
+            Err(DaneError::UnsupportedUsage(CertUsage::Private))
+        ));
+
+        let tlsa = TLSA::new(
+            CertUsage::DomainIssued,
+            Selector::Unassigned(2),
+            Matching::Raw,
+            CA_DER.to_vec(),
+        );
+        assert!(matches!(
+            tlsa.verify(CA_DER),
+            Err(DaneError::UnsupportedSelector(Selector::Unassigned(2)))
+        ));
+
+        let tlsa = TLSA::new(
+            CertUsage::DomainIssued,
+            Selector::Full,
+            Matching::Unassigned(3),
+            CA_DER.to_vec(),
+        );
+        assert!(matches!(
+            tlsa.verify(CA_DER),
+            Err(DaneError::UnsupportedMatching(Matching::Unassigned(3)))
+        ));
+    }
+
+    #[test]
+    fn test_verify_malformed() {
+        let tlsa = TLSA::new(
+            CertUsage::DomainIssued,
+            Selector::Spki,
+            Matching::Raw,
+            vec![],
+        );
+
+        assert!(matches!(
+            tlsa.verify(&CA_DER[..CA_DER.len() - 1]),
+            Err(DaneError::MalformedCertificate(_))
+        ));
+        assert!(matches!(
+            tlsa.verify(&[0x30, 0x82, 0x01]),
+            Err(DaneError::MalformedCertificate(_))
+        ));
+        assert!(matches!(
+            tlsa.verify(&[0x30, 0x00]),
+            Err(DaneError::MalformedCertificate(_))
+        ));
+    }
 }
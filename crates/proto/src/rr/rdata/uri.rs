@@ -0,0 +1,253 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! URI records for publishing mappings from hostnames to URIs
+
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// [RFC 7553](https://tools.ietf.org/html/rfc7553#section-4), The Uniform Resource Identifier (URI) DNS Resource Record, June 2015
+///
+/// ```text
+/// 4.5.  URI RDATA Wire Format
+///
+///    The RDATA for a URI RR consists of a 2-octet Priority field, a
+///    2-octet Weight field, and a variable-length Target field.
+///
+///    Priority and Weight are unsigned integers in network byte order.
+///
+///    The remaining data in the RDATA contains the Target field.  The
+///    Target field contains the URI as a sequence of octets (without the
+///    enclosing double-quote characters used in the presentation format).
+///
+///    The length of the Target field MUST be greater than zero.
+///
+///                         1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |          Priority             |          Weight               |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    /                                                               /
+///    /                             Target                            /
+///    /                                                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct URI {
+    priority: u16,
+    weight: u16,
+    target: String,
+}
+
+impl URI {
+    /// Creates a new URI record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - lower values are preferred, as with SRV.
+    /// * `weight` - relative weight for entries with the same priority.
+    /// * `target` - the URI, it must have a scheme and can not contain whitespace, control characters, `"` or `\`.
+    ///
+    /// # Return value
+    ///
+    /// The new URI record data, or an error if the target is not a URI. The targets of records read from messages are
+    ///  not checked, other than being non-empty.
+    pub fn new(priority: u16, weight: u16, target: String) -> ProtoResult<Self> {
+        check_target(&target)?;
+
+        Ok(Self {
+            priority,
+            weight,
+            target,
+        })
+    }
+
+    /// ```text
+    /// 4.2.  Priority
+    ///
+    ///    This field holds the priority of the target URI in this RR.  Its
+    ///    range is 0-65535.  A client MUST attempt to contact the URI with the
+    ///    lowest-numbered priority it can reach; URIs with the same priority
+    ///    SHOULD be selected according to probabilities defined by the weight
+    ///    field.
+    /// ```
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// ```text
+    /// 4.3.  Weight
+    ///
+    ///    This field holds the server selection mechanism.  The weight field
+    ///    specifies a relative weight for entries with the same priority.
+    ///    Larger weights SHOULD be given a proportionately higher probability
+    ///    of being selected.  The range of this number is 0-65535.
+    /// ```
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+
+    /// ```text
+    /// 4.4.  Target
+    ///
+    ///    This field holds the URI of the target, enclosed in double-quote
+    ///    characters ('"'), where the URI is as specified in RFC 3986
+    ///    [RFC3986].
+    /// ```
+    pub fn target(&self) -> &str {
+        &self.target
+    }
+}
+
+/// Checks that the target is a URI, `scheme ":" hier-part` of [RFC 3986](https://tools.ietf.org/html/rfc3986#section-3),
+///  which can be written between the double quotes of the presentation format
+fn check_target(target: &str) -> ProtoResult<()> {
+    let scheme = match target.split_once(':') {
+        Some((scheme, _)) => scheme,
+        None => return Err(format!("URI target has no scheme: {}", target).into()),
+    };
+
+    // scheme = ALPHA *( ALPHA / DIGIT / "+" / "-" / "." )
+    let mut chars = scheme.chars();
+    if !matches!(chars.next(), Some(ch) if ch.is_ascii_alphabetic())
+        || !chars.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '+' | '-' | '.'))
+    {
+        return Err(format!("URI target has an invalid scheme: {}", target).into());
+    }
+
+    if target
+        .chars()
+        .any(|ch| ch.is_whitespace() || ch.is_control() || matches!(ch, '"' | '\\'))
+    {
+        return Err(format!("URI target has invalid characters: {}", target).into());
+    }
+
+    Ok(())
+}
+
+/// Read the RData from the given Decoder
+pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoResult<URI> {
+    let priority = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+    let weight = decoder.read_u16()?.unverified(/*any u16 is valid*/);
+
+    let target_len = rdata_length
+        .map(usize::from)
+        .checked_sub(4)
+        .map_err(|_| ProtoError::from("URI rdata too short"))?
+        .verify_unwrap(|len| *len > 0)
+        .map_err(|_| ProtoError::from("URI target is empty"))?;
+    let target = decoder.read_vec(target_len)?.unverified(/*validated below*/);
+    let target =
+        String::from_utf8(target).map_err(|_| ProtoError::from("URI target is not UTF-8"))?;
+
+    // be lenient with the targets of other servers, only those which are created are checked
+    Ok(URI {
+        priority,
+        weight,
+        target,
+    })
+}
+
+/// Write the RData from the given Decoder
+pub fn emit(encoder: &mut BinEncoder<'_>, uri: &URI) -> ProtoResult<()> {
+    encoder.emit_u16(uri.priority())?;
+    encoder.emit_u16(uri.weight())?;
+    encoder.emit_vec(uri.target().as_bytes())
+}
+
+/// [RFC 7553](https://tools.ietf.org/html/rfc7553#section-4)
+///
+/// ```text
+/// 4.  The Format of the URI RR
+///
+///    The URI RR has the following presentation format:
+///
+///       _service._proto.name.  TTL  class  URI  priority  weight  target
+///
+///    For example:
+///
+///       _ftp._tcp.example.com. IN URI 10 1 "ftp://ftp.example.com/public"
+/// ```
+impl fmt::Display for URI {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{} {} \"", self.priority, self.weight)?;
+
+        // targets read from messages are not checked, escape anything which can't be written between quotes
+        for ch in self.target.chars() {
+            match ch {
+                '"' | '\\' => write!(f, "\\{}", ch)?,
+                ch if ch.is_whitespace() || ch.is_control() => {
+                    let mut buf = [0; 4];
+                    for byte in ch.encode_utf8(&mut buf).bytes() {
+                        write!(f, "\\{:03}", byte)?;
+                    }
+                }
+                ch => write!(f, "{}", ch)?,
+            }
+        }
+
+        write!(f, "\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::dbg_macro, clippy::print_stdout)]
+
+    use super::*;
+
+    #[test]
+    fn test() {
+        let rdata = URI::new(10, 1, "ftp://ftp.example.com/public".to_string()).unwrap();
+
+        let mut bytes = Vec::new();
+        let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut bytes);
+        assert!(emit(&mut encoder, &rdata).is_ok());
+        let bytes = encoder.into_bytes();
+
+        println!("bytes: {:?}", bytes);
+
+        let mut decoder: BinDecoder<'_> = BinDecoder::new(bytes);
+        let restrict = Restrict::new(bytes.len() as u16);
+        let read_rdata = read(&mut decoder, restrict).expect("Decoding error");
+        assert_eq!(rdata, read_rdata);
+
+        assert_eq!(rdata.to_string(), "10 1 \"ftp://ftp.example.com/public\"");
+    }
+
+    #[test]
+    fn test_invalid_target() {
+        assert!(URI::new(10, 1, "mailto:user@example.com".to_string()).is_ok());
+
+        assert!(URI::new(10, 1, "".to_string()).is_err());
+        assert!(URI::new(10, 1, "ftp.example.com/public".to_string()).is_err());
+        assert!(URI::new(10, 1, "1ftp://ftp.example.com".to_string()).is_err());
+        assert!(URI::new(10, 1, "ftp://ftp.example.com/my files".to_string()).is_err());
+        assert!(URI::new(10, 1, "ftp://ftp.example.com/\"public\"".to_string()).is_err());
+
+        // the target must not be empty on the wire
+        let bytes = [0, 10, 0, 1];
+        let mut decoder = BinDecoder::new(&bytes);
+        assert!(read(&mut decoder, Restrict::new(bytes.len() as u16)).is_err());
+    }
+
+    #[test]
+    fn test_lenient_read() {
+        // a target which is not a URI is still read, and escaped when displayed
+        let bytes = b"\x00\x0a\x00\x01my \"files\"";
+        let mut decoder = BinDecoder::new(bytes);
+        let rdata = read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("Decoding error");
+        assert_eq!(rdata.target(), "my \"files\"");
+        assert_eq!(rdata.to_string(), "10 1 \"my\\032\\\"files\\\"\"");
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! ZONEMD records for message digests of whole zones
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+use super::sshfp::HEX;
+use crate::error::*;
+use crate::serialize::binary::*;
+
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-2.2)
+///
+/// ```text
+/// 2.2.  ZONEMD RDATA Wire Format
+///
+///    The ZONEMD RDATA wire format is encoded as follows:
+///
+///                         1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |                             Serial                            |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///    |    Scheme     |Hash Algorithm |                               |
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               |
+///    |                             Digest                            |
+///    /                                                               /
+///    /                                                               /
+///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+///
+/// 2.2.1.  The Serial Field
+///
+///    The Serial field is a 32-bit unsigned integer in network byte order.
+///    It is the serial number from the zone's SOA record ([RFC1035],
+///    Section 3.3.13) for which the zone digest was generated.
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct ZONEMD {
+    serial: u32,
+    scheme: Scheme,
+    hash_algorithm: HashAlgorithm,
+    digest: Vec<u8>,
+}
+
+impl ZONEMD {
+    /// Creates a new ZONEMD record data.
+    ///
+    /// # Arguments
+    ///
+    /// * `serial` - the serial of the SOA record the digest was generated for.
+    /// * `scheme` - the method by which the zone data was collated and presented to the hash function.
+    /// * `hash_algorithm` - the hash function used to create the digest.
+    /// * `digest` - the digest of the zone.
+    pub fn new(
+        serial: u32,
+        scheme: Scheme,
+        hash_algorithm: HashAlgorithm,
+        digest: Vec<u8>,
+    ) -> Self {
+        Self {
+            serial,
+            scheme,
+            hash_algorithm,
+            digest,
+        }
+    }
+
+    /// The serial of the SOA record the digest was generated for.
+    pub fn serial(&self) -> u32 {
+        self.serial
+    }
+
+    /// The method by which the zone data was collated and presented to the hash function.
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// The hash function used to create the digest.
+    pub fn hash_algorithm(&self) -> HashAlgorithm {
+        self.hash_algorithm
+    }
+
+    /// The digest of the zone.
+    pub fn digest(&self) -> &[u8] {
+        &self.digest
+    }
+}
+
+/// ```text
+/// 2.2.2.  The Scheme Field
+///
+///    The Scheme field is an 8-bit unsigned integer that identifies the
+///    methods by which data is collated and presented as input to the
+///    hashing function.
+///
+///    Herein, SIMPLE, with Scheme value 1, is the only standardized Scheme
+///    defined for ZONEMD records and it MUST be implemented.  The Scheme
+///    registry is further described in Section 6.
+///
+///    Scheme values 240-254 are allocated for Private Use.
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum Scheme {
+    /// The SIMPLE scheme, a digest over the whole zone in canonical order
+    Simple,
+
+    /// Private use value, 240-254
+    Private(u8),
+
+    /// Reserved or unassigned value
+    Unassigned(u8),
+}
+
+impl From<u8> for Scheme {
+    fn from(scheme: u8) -> Self {
+        match scheme {
+            1 => Self::Simple,
+            240..=254 => Self::Private(scheme),
+            _ => Self::Unassigned(scheme),
+        }
+    }
+}
+
+impl From<Scheme> for u8 {
+    fn from(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::Simple => 1,
+            Scheme::Private(scheme) | Scheme::Unassigned(scheme) => scheme,
+        }
+    }
+}
+
+/// ```text
+/// 2.2.3.  The Hash Algorithm Field
+///
+///    The Hash Algorithm field is an 8-bit unsigned integer that identifies
+///    the cryptographic hash algorithm used to construct the digest.
+///
+///    Herein, SHA384 [RFC6234], with Hash Algorithm value 1, is the only
+///    standardized Hash Algorithm defined for ZONEMD records that MUST be
+///    implemented.  When SHA384 is used, the size of the Digest field is 48
+///    octets.  The result of the SHA384 digest algorithm MUST NOT be
+///    truncated, and the entire 48-octet digest is published in the ZONEMD
+///    record.
+///
+///    SHA512 [RFC6234], with Hash Algorithm value 2, is also defined for
+///    ZONEMD records and SHOULD be implemented.  When SHA512 is used, the
+///    size of the Digest field is 64 octets.  The result of the SHA512
+///    digest algorithm MUST NOT be truncated, and the entire 64-octet
+///    digest is published in the ZONEMD record.
+///
+///    Hash Algorithm values 240-254 are allocated for Private Use.
+/// ```
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum HashAlgorithm {
+    /// SHA-384, with a 48 octet digest
+    SHA384,
+
+    /// SHA-512, with a 64 octet digest
+    SHA512,
+
+    /// Private use value, 240-254
+    Private(u8),
+
+    /// Reserved or unassigned value
+    Unassigned(u8),
+}
+
+impl HashAlgorithm {
+    /// The length of the digest, if the algorithm is known
+    pub fn digest_len(self) -> Option<usize> {
+        match self {
+            Self::SHA384 => Some(48),
+            Self::SHA512 => Some(64),
+            Self::Private(_) | Self::Unassigned(_) => None,
+        }
+    }
+}
+
+impl From<u8> for HashAlgorithm {
+    fn from(hash_algorithm: u8) -> Self {
+        match hash_algorithm {
+            1 => Self::SHA384,
+            2 => Self::SHA512,
+            240..=254 => Self::Private(hash_algorithm),
+            _ => Self::Unassigned(hash_algorithm),
+        }
+    }
+}
+
+impl From<HashAlgorithm> for u8 {
+    fn from(hash_algorithm: HashAlgorithm) -> Self {
+        match hash_algorithm {
+            HashAlgorithm::SHA384 => 1,
+            HashAlgorithm::SHA512 => 2,
+            HashAlgorithm::Private(hash_algorithm) | HashAlgorithm::Unassigned(hash_algorithm) => {
+                hash_algorithm
+            }
+        }
+    }
+}
+
+/// Read the RData from the given decoder.
+pub fn read(decoder: &mut BinDecoder<'_>, rdata_length: Restrict<u16>) -> ProtoResult<ZONEMD> {
+    let serial = decoder.read_u32()?.unverified(/*any serial is valid*/);
+    let scheme = decoder.read_u8()?.unverified(/*unknown schemes are preserved*/).into();
+    let hash_algorithm = decoder.read_u8()?.unverified(/*unknown algorithms are preserved*/).into();
+    let digest_len = rdata_length
+        .map(usize::from)
+        .checked_sub(6)
+        .map_err(|_| ProtoError::from("invalid rdata length in ZONEMD"))?
+        .unverified(/*used only as length safely*/);
+    let digest =
+        decoder.read_vec(digest_len)?.unverified(/*the digest is checked by verification*/);
+
+    Ok(ZONEMD::new(serial, scheme, hash_algorithm, digest))
+}
+
+/// Write the RData using the given encoder.
+pub fn emit(encoder: &mut BinEncoder<'_>, zonemd: &ZONEMD) -> ProtoResult<()> {
+    encoder.emit_u32(zonemd.serial())?;
+    encoder.emit_u8(zonemd.scheme().into())?;
+    encoder.emit_u8(zonemd.hash_algorithm().into())?;
+    encoder.emit_vec(zonemd.digest())
+}
+
+/// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-2.3)
+///
+/// ```text
+/// 2.3.  ZONEMD Presentation Format
+///
+///    The presentation format of the RDATA portion is as follows:
+///
+///    The Serial field MUST be represented as an unsigned decimal integer.
+///
+///    The Scheme field MUST be represented as an unsigned decimal integer.
+///
+///    The Hash Algorithm field MUST be represented as an unsigned decimal
+///    integer.
+///
+///    The Digest MUST be represented as a sequence of case-insensitive
+///    hexadecimal digits.  Whitespace is allowed within the hexadecimal
+///    text.
+/// ```
+impl fmt::Display for ZONEMD {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{serial} {scheme} {hash_algorithm} {digest}",
+            serial = self.serial,
+            scheme = u8::from(self.scheme),
+            hash_algorithm = u8::from(self.hash_algorithm),
+            digest = HEX.encode(&self.digest),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scheme_and_hash_algorithm() {
+        assert_eq!(Scheme::Simple, 1.into());
+        assert_eq!(Scheme::Unassigned(0), 0.into());
+        assert_eq!(Scheme::Private(240), 240.into());
+        assert_eq!(Scheme::Unassigned(255), 255.into());
+        assert_eq!(1u8, u8::from(Scheme::Simple));
+        assert_eq!(254u8, u8::from(Scheme::Private(254)));
+
+        assert_eq!(HashAlgorithm::SHA384, 1.into());
+        assert_eq!(HashAlgorithm::SHA512, 2.into());
+        assert_eq!(HashAlgorithm::Unassigned(3), 3.into());
+        assert_eq!(HashAlgorithm::Private(241), 241.into());
+        assert_eq!(1u8, u8::from(HashAlgorithm::SHA384));
+        assert_eq!(2u8, u8::from(HashAlgorithm::SHA512));
+        assert_eq!(HashAlgorithm::SHA384.digest_len(), Some(48));
+        assert_eq!(HashAlgorithm::Private(241).digest_len(), None);
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let rdata = ZONEMD::new(
+            2018031900,
+            Scheme::Simple,
+            HashAlgorithm::Unassigned(7),
+            vec![0xc6, 0x80, 0x90, 0xd9],
+        );
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        emit(&mut encoder, &rdata).expect("failed to emit ZONEMD");
+        let bytes = encoder.into_bytes();
+        assert_eq!(
+            bytes,
+            &[0x78, 0x48, 0xb9, 0x1c, 1, 7, 0xc6, 0x80, 0x90, 0xd9]
+        );
+
+        let mut decoder = BinDecoder::new(bytes);
+        let read_rdata =
+            read(&mut decoder, Restrict::new(bytes.len() as u16)).expect("failed to read ZONEMD");
+        assert_eq!(read_rdata, rdata);
+
+        let mut decoder = BinDecoder::new(&bytes[..5]);
+        assert!(read(&mut decoder, Restrict::new(5)).is_err());
+
+        assert_eq!(rdata.to_string(), "2018031900 1 7 c68090d9");
+    }
+}
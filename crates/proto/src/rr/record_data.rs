@@ -24,7 +24,8 @@ use log::{trace, warn};
 use super::domain::Name;
 use super::rdata;
 use super::rdata::{
-    CAA, CSYNC, HINFO, MX, NAPTR, NULL, OPENPGPKEY, OPT, SOA, SRV, SSHFP, SVCB, TLSA, TXT,
+    APL, CAA, CERT, CSYNC, DHCID, HINFO, HIP, LOC, MX, NAPTR, NULL, OPENPGPKEY, OPT, SOA, SRV,
+    SSHFP, SVCB, TLSA, TXT, URI, ZONEMD,
 };
 use super::record_type::RecordType;
 use crate::error::*;
@@ -106,6 +107,31 @@ pub enum RData {
     /// ```
     ANAME(Name),
 
+    /// [RFC 3123](https://tools.ietf.org/html/rfc3123#section-4), A DNS RR Type for Lists of Address Prefixes, October 2001
+    ///
+    /// ```text
+    /// 4. APL RDATA format
+    ///
+    ///    The RDATA section consists of zero or more items (<apitem>) of the
+    ///    form
+    ///
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       |                          ADDRESSFAMILY        |
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       |             PREFIX    | N|         AFDLENGTH  |
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       /                          AFDPART              /
+    ///       |                                               |
+    ///       +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    /// ```
+    APL(APL),
+
+    /// [IANA](https://www.iana.org/assignments/dns-parameters/AVC/avc-completed-template), Application Visibility and Control
+    ///
+    /// The presentation and wire format is identical to that of TXT, the character-strings hold
+    ///  application visibility and control information, e.g. `"app-name:WebEx|app-class:T-SHARE"`.
+    AVC(TXT),
+
     /// ```text
     /// -- RFC 6844          Certification Authority Authorization     January 2013
     ///
@@ -136,6 +162,21 @@ pub enum RData {
     /// ```
     CAA(CAA),
 
+    /// [RFC 4398](https://tools.ietf.org/html/rfc4398#section-2), Storing Certificates in the Domain Name System (DNS), March 2006
+    ///
+    /// ```text
+    ///                        1 1 1 1 1 1 1 1 1 1 2 2 2 2 2 2 2 2 2 2 3 3
+    ///    0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    ///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///    |             type              |             key tag           |
+    ///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///    |   algorithm   |                                               /
+    ///    +---------------+            certificate or CRL                 /
+    ///    /                                                               /
+    ///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-|
+    /// ```
+    CERT(CERT),
+
     /// ```text
     ///   3.3. Standard RRs
     ///
@@ -188,6 +229,33 @@ pub enum RData {
     /// ```
     CSYNC(CSYNC),
 
+    /// [RFC 4701](https://tools.ietf.org/html/rfc4701#section-3.1), A DNS Resource Record (RR) for Encoding DHCP Information (DHCID RR), October 2006
+    ///
+    /// ```text
+    /// 3.1.  DHCID RDATA Format
+    ///
+    ///    The RDATA section of a DHCID RR in transmission contains RDLENGTH
+    ///    octets of binary data.  The format of this data and its
+    ///    interpretation by DHCP servers and clients are described below.
+    ///    DNS software should consider the RDATA section to be opaque.
+    /// ```
+    DHCID(DHCID),
+
+    /// [RFC 6672](https://tools.ietf.org/html/rfc6672#section-2.1), DNAME Redirection in the DNS, June 2012
+    ///
+    /// ```text
+    /// 2.1.  Format of the DNAME RR
+    ///
+    ///    The DNAME RR has mnemonic DNAME and type code 39 (decimal).  It is
+    ///    not class-sensitive.
+    ///
+    ///    Its RDATA is comprised of a single field, <target>, which contains a
+    ///    fully qualified domain name that MUST be sent in uncompressed form
+    ///    [RFC1035] [RFC3597].  The <target> field MUST be present.  The
+    ///    presentation format of <target> is that of a domain name [RFC1035].
+    /// ```
+    DNAME(Name),
+
     /// ```text
     /// 3.3.2. HINFO RDATA format
     ///
@@ -214,6 +282,35 @@ pub enum RData {
     /// `HINFO` is also used by [RFC 8482](https://tools.ietf.org/html/rfc8482)
     HINFO(HINFO),
 
+    /// [RFC 8005](https://tools.ietf.org/html/rfc8005#section-5), Host Identity Protocol (HIP) Domain Name System (DNS) Extension, October 2016
+    ///
+    /// ```text
+    ///     0                   1                   2                   3
+    ///     0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1 2 3 4 5 6 7 8 9 0 1
+    ///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///    |  HIT length   | PK algorithm  |          PK length            |
+    ///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///    |                                                               |
+    ///    ~                           HIT                                 ~
+    ///    |                                                               |
+    ///    +                     +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///    |                     |                                         |
+    ///    +-+-+-+-+-+-+-+-+-+-+-+                                         +
+    ///    |                           Public Key                          |
+    ///    ~                                                               ~
+    ///    |                                                               |
+    ///    +                               +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///    |                               |                               |
+    ///    +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+                               +
+    ///    |                                                               |
+    ///    ~                       Rendezvous Servers                      ~
+    ///    |                                                               |
+    ///    +             +-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+-+
+    ///    |             |
+    ///    +-+-+-+-+-+-+-+
+    /// ```
+    HIP(HIP),
+
     /// [RFC draft-ietf-dnsop-svcb-https-03, DNS SVCB and HTTPS RRs](https://datatracker.ietf.org/doc/html/draft-ietf-dnsop-svcb-https-03#section-8)
     ///
     /// ```text
@@ -237,6 +334,33 @@ pub enum RData {
     /// ```
     HTTPS(SVCB),
 
+    /// [RFC 1876](https://tools.ietf.org/html/rfc1876#section-2), A Means for Expressing Location Information in the Domain Name System, January 1996
+    ///
+    /// ```text
+    /// 2. RDATA Format
+    ///
+    ///        MSB                                           LSB
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       0|        VERSION        |         SIZE          |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       2|       HORIZ PRE       |       VERT PRE        |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       4|                   LATITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       6|                   LATITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///       8|                   LONGITUDE                   |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///      10|                   LONGITUDE                   |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///      12|                   ALTITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///      14|                   ALTITUDE                    |
+    ///        +--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+--+
+    ///    (octet)
+    /// ```
+    LOC(LOC),
+
     /// ```text
     /// 3.3.9. MX RDATA format
     ///
@@ -545,6 +669,30 @@ pub enum RData {
     /// ```
     SOA(SOA),
 
+    /// [RFC 8162](https://tools.ietf.org/html/rfc8162#section-2), Using Secure DNS to Associate Certificates with Domain Names for S/MIME, May 2017
+    ///
+    /// ```text
+    /// 2.  The SMIMEA Resource Record
+    ///
+    ///    The SMIMEA DNS resource record (RR) is used to associate an end
+    ///    entity certificate or public key with the associated email address,
+    ///    thus forming a "SMIMEA certificate association".  The semantics of
+    ///    how the SMIMEA resource record is interpreted are given later in
+    ///    this document.  Note that the information returned in the SMIMEA
+    ///    record might be for the end entity certificate, or it might be for
+    ///    the trust anchor or an intermediate certificate.  This mechanism is
+    ///    similar to the one given in [RFC6698] for TLSA.
+    ///
+    ///    The type value for the SMIMEA RRtype is defined in Section 8.  The
+    ///    SMIMEA resource record is class independent.
+    ///
+    /// 2.1.  The SMIMEA RDATA Wire Format
+    ///
+    ///    The SMIMEA wire format and presentation format are the same as for
+    ///    the TLSA record as described in Section 2.1 of [RFC6698].
+    /// ```
+    SMIMEA(TLSA),
+
     /// ```text
     /// RFC 2782                       DNS SRV RR                  February 2000
     ///
@@ -672,6 +820,29 @@ pub enum RData {
     /// ```
     TXT(TXT),
 
+    /// [RFC 7553](https://tools.ietf.org/html/rfc7553#section-4.5), The Uniform Resource Identifier (URI) DNS Resource Record, June 2015
+    ///
+    /// ```text
+    /// 4.5.  URI RDATA Wire Format
+    ///
+    ///    The RDATA for a URI RR consists of a 2-octet Priority field, a
+    ///    2-octet Weight field, and a variable-length Target field.
+    /// ```
+    URI(URI),
+
+    /// [RFC 8976](https://tools.ietf.org/html/rfc8976#section-2), Message Digest for DNS Zones, February 2021
+    ///
+    /// ```text
+    /// 2.  The ZONEMD Resource Record
+    ///
+    ///    This section describes the ZONEMD resource record, including its
+    ///    fields, wire format, and presentation format.  The Type value for
+    ///    the ZONEMD RR is 63.  The ZONEMD RR is class independent.  The RDATA
+    ///    of the resource record consists of four fields: Serial, Scheme, Hash
+    ///    Algorithm, and Digest.
+    /// ```
+    ZONEMD(ZONEMD),
+
     /// A DNSSEC- or SIG(0)- specific record. See `DNSSECRData` for details.
     ///
     /// These types are in `DNSSECRData` to make them easy to disable when
@@ -694,10 +865,13 @@ pub enum RData {
 }
 
 impl RData {
-    fn to_bytes(&self) -> Vec<u8> {
+    /// The canonical form of the RDATA, [RFC 4034](https://tools.ietf.org/html/rfc4034#section-6.2),
+    ///  names are not compressed and are lowercased where the type requires it
+    pub(crate) fn to_canonical_bytes(&self) -> Vec<u8> {
         let mut buf: Vec<u8> = Vec::new();
         {
             let mut encoder: BinEncoder<'_> = BinEncoder::new(&mut buf);
+            encoder.set_canonical_names(true);
             self.emit(&mut encoder).unwrap_or_else(|_| {
                 warn!("could not encode RDATA: {:?}", self);
             });
@@ -705,6 +879,18 @@ impl RData {
         buf
     }
 
+    /// Compares the RDATA in the canonical order of the records of an RRset,
+    ///  [RFC 4034](https://tools.ietf.org/html/rfc4034#section-6.3)
+    ///
+    /// The canonical forms are compared as left-justified octet sequences, in which the absence of
+    ///  an octet sorts before a zero octet. This is the order in which an RRset is signed.
+    pub fn canonical_order(&self, other: &Self) -> Ordering {
+        // TODO: how about we just store the bytes with the decoded data?
+        //  the decoded data is useful for queries, the encoded data is needed for transfers, signing
+        //  and ordering.
+        self.to_canonical_bytes().cmp(&other.to_canonical_bytes())
+    }
+
     /// Read the RData from the given Decoder
     pub fn read(
         decoder: &mut BinDecoder<'_>,
@@ -729,10 +915,22 @@ impl RData {
             rt @ RecordType::ANY | rt @ RecordType::AXFR | rt @ RecordType::IXFR => {
                 return Err(ProtoErrorKind::UnknownRecordTypeValue(rt.into()).into());
             }
+            RecordType::APL => {
+                trace!("reading APL");
+                rdata::apl::read(decoder, rdata_length).map(Self::APL)
+            }
+            RecordType::AVC => {
+                trace!("reading AVC");
+                rdata::txt::read(decoder, rdata_length).map(Self::AVC)
+            }
             RecordType::CAA => {
                 trace!("reading CAA");
                 rdata::caa::read(decoder, rdata_length).map(Self::CAA)
             }
+            RecordType::CERT => {
+                trace!("reading CERT");
+                rdata::cert::read(decoder, rdata_length).map(Self::CERT)
+            }
             RecordType::CNAME => {
                 trace!("reading CNAME");
                 rdata::name::read(decoder).map(Self::CNAME)
@@ -741,14 +939,30 @@ impl RData {
                 trace!("reading CSYNC");
                 rdata::csync::read(decoder, rdata_length).map(Self::CSYNC)
             }
+            RecordType::DHCID => {
+                trace!("reading DHCID");
+                rdata::dhcid::read(decoder, rdata_length).map(Self::DHCID)
+            }
+            RecordType::DNAME => {
+                trace!("reading DNAME");
+                rdata::name::read(decoder).map(Self::DNAME)
+            }
             RecordType::HINFO => {
                 trace!("reading HINFO");
                 rdata::hinfo::read(decoder).map(Self::HINFO)
             }
+            RecordType::HIP => {
+                trace!("reading HIP");
+                rdata::hip::read(decoder, rdata_length).map(Self::HIP)
+            }
             RecordType::HTTPS => {
                 trace!("reading HTTPS");
                 rdata::svcb::read(decoder, rdata_length).map(Self::HTTPS)
             }
+            RecordType::LOC => {
+                trace!("reading LOC");
+                rdata::loc::read(decoder).map(Self::LOC)
+            }
             RecordType::ZERO => {
                 trace!("reading EMPTY");
                 // we should never get here, since ZERO should be 0 length, and None in the Record.
@@ -784,6 +998,10 @@ impl RData {
                 trace!("reading PTR");
                 rdata::name::read(decoder).map(Self::PTR)
             }
+            RecordType::SMIMEA => {
+                trace!("reading SMIMEA");
+                rdata::tlsa::read(decoder, rdata_length).map(Self::SMIMEA)
+            }
             RecordType::SOA => {
                 trace!("reading SOA");
                 rdata::soa::read(decoder).map(Self::SOA)
@@ -808,6 +1026,14 @@ impl RData {
                 trace!("reading TXT");
                 rdata::txt::read(decoder, rdata_length).map(Self::TXT)
             }
+            RecordType::URI => {
+                trace!("reading URI");
+                rdata::uri::read(decoder, rdata_length).map(Self::URI)
+            }
+            RecordType::ZONEMD => {
+                trace!("reading ZONEMD");
+                rdata::zonemd::read(decoder, rdata_length).map(Self::ZONEMD)
+            }
             #[cfg(feature = "dnssec")]
             r if r.is_dnssec() => {
                 DNSSECRData::read(decoder, record_type, rdata_length).map(Self::DNSSEC)
@@ -917,9 +1143,20 @@ impl RData {
             RData::CNAME(ref name) | RData::NS(ref name) | RData::PTR(ref name) => {
                 rdata::name::emit(encoder, name)
             }
+            RData::APL(ref apl) => rdata::apl::emit(encoder, apl),
+            RData::AVC(ref avc) => rdata::txt::emit(encoder, avc),
+            RData::CERT(ref cert) => rdata::cert::emit(encoder, cert),
             RData::CSYNC(ref csync) => rdata::csync::emit(encoder, csync),
+            RData::DHCID(ref dhcid) => rdata::dhcid::emit(encoder, dhcid),
+            // the target is never compressed, rfc6672
+            RData::DNAME(ref name) => {
+                encoder.with_canonical_names(|encoder| rdata::name::emit(encoder, name))
+            }
             RData::HINFO(ref hinfo) => rdata::hinfo::emit(encoder, hinfo),
+            // the rendezvous servers are never compressed, see rfc8005
+            RData::HIP(ref hip) => rdata::hip::emit(encoder, hip),
             RData::HTTPS(ref svcb) => rdata::svcb::emit(encoder, svcb),
+            RData::LOC(ref loc) => rdata::loc::emit(encoder, loc),
             RData::ZERO => Ok(()),
             // to_lowercase for rfc4034 and rfc6840
             RData::MX(ref mx) => rdata::mx::emit(encoder, mx),
@@ -941,10 +1178,12 @@ impl RData {
                 encoder.with_canonical_names(|encoder| rdata::sshfp::emit(encoder, sshfp))
             }
             RData::SVCB(ref svcb) => rdata::svcb::emit(encoder, svcb),
-            RData::TLSA(ref tlsa) => {
+            RData::SMIMEA(ref tlsa) | RData::TLSA(ref tlsa) => {
                 encoder.with_canonical_names(|encoder| rdata::tlsa::emit(encoder, tlsa))
             }
             RData::TXT(ref txt) => rdata::txt::emit(encoder, txt),
+            RData::URI(ref uri) => rdata::uri::emit(encoder, uri),
+            RData::ZONEMD(ref zonemd) => rdata::zonemd::emit(encoder, zonemd),
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => encoder.with_canonical_names(|encoder| rdata.emit(encoder)),
             RData::Unknown { ref rdata, .. } => rdata::null::emit(encoder, rdata),
@@ -957,11 +1196,18 @@ impl RData {
             RData::A(..) => RecordType::A,
             RData::AAAA(..) => RecordType::AAAA,
             RData::ANAME(..) => RecordType::ANAME,
+            RData::APL(..) => RecordType::APL,
+            RData::AVC(..) => RecordType::AVC,
             RData::CAA(..) => RecordType::CAA,
+            RData::CERT(..) => RecordType::CERT,
             RData::CNAME(..) => RecordType::CNAME,
             RData::CSYNC(..) => RecordType::CSYNC,
+            RData::DHCID(..) => RecordType::DHCID,
+            RData::DNAME(..) => RecordType::DNAME,
             RData::HINFO(..) => RecordType::HINFO,
+            RData::HIP(..) => RecordType::HIP,
             RData::HTTPS(..) => RecordType::HTTPS,
+            RData::LOC(..) => RecordType::LOC,
             RData::MX(..) => RecordType::MX,
             RData::NAPTR(..) => RecordType::NAPTR,
             RData::NS(..) => RecordType::NS,
@@ -969,12 +1215,15 @@ impl RData {
             RData::OPENPGPKEY(..) => RecordType::OPENPGPKEY,
             RData::OPT(..) => RecordType::OPT,
             RData::PTR(..) => RecordType::PTR,
+            RData::SMIMEA(..) => RecordType::SMIMEA,
             RData::SOA(..) => RecordType::SOA,
             RData::SRV(..) => RecordType::SRV,
             RData::SSHFP(..) => RecordType::SSHFP,
             RData::SVCB(..) => RecordType::SVCB,
             RData::TLSA(..) => RecordType::TLSA,
             RData::TXT(..) => RecordType::TXT,
+            RData::URI(..) => RecordType::URI,
+            RData::ZONEMD(..) => RecordType::ZONEMD,
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => DNSSECRData::to_record_type(rdata),
             RData::Unknown { code, .. } => RecordType::Unknown(code),
@@ -992,6 +1241,63 @@ impl RData {
     }
 }
 
+/// The data of a record type, which can be borrowed out of an [`RData`] of that type
+///
+/// A type which is the data of several record types, e.g. `Name` of CNAME, NS and PTR, is borrowed out of any of them.
+pub trait RecordData {
+    /// Borrows the data of this type out of the `rdata`, `None` if it holds the data of another type
+    fn try_borrow(rdata: &RData) -> Option<&Self>;
+}
+
+macro_rules! record_data {
+    ($data:ty, $($variant:ident)|+) => {
+        impl RecordData for $data {
+            fn try_borrow(rdata: &RData) -> Option<&Self> {
+                match rdata {
+                    $(RData::$variant(data))|+ => Some(data),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+record_data!(Ipv4Addr, A);
+record_data!(Ipv6Addr, AAAA);
+record_data!(Name, ANAME | CNAME | DNAME | NS | PTR);
+record_data!(APL, APL);
+record_data!(CAA, CAA);
+record_data!(CERT, CERT);
+record_data!(CSYNC, CSYNC);
+record_data!(DHCID, DHCID);
+record_data!(HINFO, HINFO);
+record_data!(HIP, HIP);
+record_data!(LOC, LOC);
+record_data!(MX, MX);
+record_data!(NAPTR, NAPTR);
+record_data!(OPENPGPKEY, OPENPGPKEY);
+record_data!(OPT, OPT);
+record_data!(SOA, SOA);
+record_data!(SRV, SRV);
+record_data!(SSHFP, SSHFP);
+record_data!(SVCB, HTTPS | SVCB);
+record_data!(TLSA, SMIMEA | TLSA);
+record_data!(TXT, AVC | TXT);
+record_data!(URI, URI);
+record_data!(ZONEMD, ZONEMD);
+#[cfg(feature = "dnssec")]
+record_data!(DNSSECRData, DNSSEC);
+
+// the data of record types without a variant of their own is kept as NULL
+impl RecordData for NULL {
+    fn try_borrow(rdata: &RData) -> Option<&Self> {
+        match rdata {
+            RData::NULL(data) | RData::Unknown { rdata: data, .. } => Some(data),
+            _ => None,
+        }
+    }
+}
+
 impl fmt::Display for RData {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         fn w<D: fmt::Display>(f: &mut fmt::Formatter<'_>, d: D) -> Result<(), fmt::Error> {
@@ -1002,12 +1308,19 @@ impl fmt::Display for RData {
             RData::A(address) => w(f, address),
             RData::AAAA(ref address) => w(f, address),
             RData::ANAME(ref name) => w(f, name),
+            RData::APL(ref apl) => w(f, apl),
+            RData::AVC(ref avc) => w(f, avc),
             RData::CAA(ref caa) => w(f, caa),
             // to_lowercase for rfc4034 and rfc6840
             RData::CNAME(ref name) | RData::NS(ref name) | RData::PTR(ref name) => w(f, name),
+            RData::CERT(ref cert) => w(f, cert),
             RData::CSYNC(ref csync) => w(f, csync),
+            RData::DHCID(ref dhcid) => w(f, dhcid),
+            RData::DNAME(ref name) => w(f, name),
             RData::HINFO(ref hinfo) => w(f, hinfo),
+            RData::HIP(ref hip) => w(f, hip),
             RData::HTTPS(ref svcb) => w(f, svcb),
+            RData::LOC(ref loc) => w(f, loc),
             RData::ZERO => Ok(()),
             // to_lowercase for rfc4034 and rfc6840
             RData::MX(ref mx) => w(f, mx),
@@ -1022,11 +1335,20 @@ impl fmt::Display for RData {
             RData::SRV(ref srv) => w(f, srv),
             RData::SSHFP(ref sshfp) => w(f, sshfp),
             RData::SVCB(ref svcb) => w(f, svcb),
-            RData::TLSA(ref tlsa) => w(f, tlsa),
+            RData::SMIMEA(ref tlsa) | RData::TLSA(ref tlsa) => w(f, tlsa),
             RData::TXT(ref txt) => w(f, txt),
+            RData::URI(ref uri) => w(f, uri),
+            RData::ZONEMD(ref zonemd) => w(f, zonemd),
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => w(f, rdata),
-            RData::Unknown { ref rdata, .. } => w(f, rdata),
+            // the generic representation of RFC 3597
+            RData::Unknown { ref rdata, .. } => {
+                write!(f, "\\# {}", rdata.anything().len())?;
+                if !rdata.anything().is_empty() {
+                    write!(f, " {}", data_encoding::HEXUPPER.encode(rdata.anything()))?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -1056,10 +1378,7 @@ impl Ord for RData {
     //    accepts), it MUST remove all but one of the duplicate RR(s) for the
     //    purposes of calculating the canonical form of the RRset.
     fn cmp(&self, other: &Self) -> Ordering {
-        // TODO: how about we just store the bytes with the decoded data?
-        //  the decoded data is useful for queries, the encoded data is needed for transfers, signing
-        //  and ordering.
-        self.to_bytes().cmp(&other.to_bytes())
+        self.canonical_order(other)
     }
 }
 
@@ -1088,6 +1407,12 @@ mod tests {
                     b'o', b'm', 0,
                 ],
             ),
+            (
+                RData::DNAME(Name::from_str("example.net").unwrap()),
+                vec![
+                    7, b'e', b'x', b'a', b'm', b'p', b'l', b'e', 3, b'n', b'e', b't', 0,
+                ],
+            ),
             (
                 RData::MX(MX::new(256, Name::from_str("n").unwrap())),
                 vec![1, 0, 1, b'n', 0],
@@ -1158,6 +1483,66 @@ mod tests {
                 RData::HINFO(HINFO::new("cpu".to_string(), "os".to_string())),
                 vec![3, b'c', b'p', b'u', 2, b'o', b's'],
             ),
+            (
+                RData::LOC(
+                    LOC::new(0x8000_0000, 0x8000_0000, 0x0098_9680, 0x12, 0x16, 0x13).unwrap(),
+                ),
+                vec![
+                    0, 0x12, 0x16, 0x13, 0x80, 0, 0, 0, 0x80, 0, 0, 0, 0, 0x98, 0x96, 0x80,
+                ],
+            ),
+            (
+                RData::URI(URI::new(10, 1, "ftp://a".to_string()).unwrap()),
+                vec![0, 10, 0, 1, b'f', b't', b'p', b':', b'/', b'/', b'a'],
+            ),
+            (
+                RData::OPENPGPKEY(OPENPGPKEY::new(vec![0x99, 0x01, 0x0D])),
+                vec![0x99, 0x01, 0x0D],
+            ),
+            (
+                RData::SMIMEA(TLSA::new(
+                    rdata::tlsa::CertUsage::DomainIssued,
+                    rdata::tlsa::Selector::Spki,
+                    rdata::tlsa::Matching::Sha256,
+                    vec![0xAB, 0xCD],
+                )),
+                vec![3, 1, 1, 0xAB, 0xCD],
+            ),
+            (
+                RData::APL(APL::new(vec![rdata::apl::AplItem::from_ip(
+                    Ipv4Addr::new(192, 0, 2, 128).into(),
+                    25,
+                    true,
+                )
+                .unwrap()])),
+                vec![0, 1, 25, 0x84, 192, 0, 2, 128],
+            ),
+            (
+                RData::AVC(TXT::new(vec!["app-name:WebEx".to_string()])),
+                vec![
+                    14, b'a', b'p', b'p', b'-', b'n', b'a', b'm', b'e', b':', b'W', b'e', b'b',
+                    b'E', b'x',
+                ],
+            ),
+            (
+                RData::ZONEMD(ZONEMD::new(
+                    1,
+                    rdata::zonemd::Scheme::Simple,
+                    rdata::zonemd::HashAlgorithm::SHA384,
+                    vec![0xAB; 12],
+                )),
+                vec![
+                    0, 0, 0, 1, 1, 1, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB, 0xAB,
+                    0xAB, 0xAB,
+                ],
+            ),
+            (
+                RData::Unknown {
+                    code: 65534,
+                    rdata: NULL::with(vec![0xDE, 0xAD, 0xBE, 0xEF]),
+                },
+                vec![0xDE, 0xAD, 0xBE, 0xEF],
+            ),
         ]
     }
 
@@ -1227,6 +1612,27 @@ mod tests {
         assert_eq!(ordered, unordered);
     }
 
+    #[test]
+    fn test_canonical_order() {
+        let upper = RData::MX(MX::new(10, Name::from_ascii("B.example.com.").unwrap()));
+        let lower = RData::MX(MX::new(10, Name::from_str("a.example.com.").unwrap()));
+
+        // names are compared lowercased, `B` would sort before `a` otherwise
+        assert_eq!(upper.canonical_order(&lower), Ordering::Greater);
+        assert_eq!(
+            upper.canonical_order(&RData::MX(MX::new(
+                10,
+                Name::from_str("b.example.com.").unwrap()
+            ))),
+            Ordering::Equal
+        );
+
+        // the absence of an octet sorts before a zero octet
+        let short = RData::TXT(TXT::new(vec!["a".to_string()]));
+        let long = RData::TXT(TXT::new(vec!["a".to_string(), "".to_string()]));
+        assert_eq!(short.canonical_order(&long), Ordering::Less);
+    }
+
     #[test]
     fn test_read() {
         for (test_pass, (expect, binary)) in get_data().into_iter().enumerate() {
@@ -1251,11 +1657,18 @@ mod tests {
             RData::A(..) => RecordType::A,
             RData::AAAA(..) => RecordType::AAAA,
             RData::ANAME(..) => RecordType::ANAME,
+            RData::APL(..) => RecordType::APL,
+            RData::AVC(..) => RecordType::AVC,
             RData::CAA(..) => RecordType::CAA,
+            RData::CERT(..) => RecordType::CERT,
             RData::CNAME(..) => RecordType::CNAME,
             RData::CSYNC(..) => RecordType::CSYNC,
+            RData::DHCID(..) => RecordType::DHCID,
+            RData::DNAME(..) => RecordType::DNAME,
             RData::HINFO(..) => RecordType::HINFO,
+            RData::HIP(..) => RecordType::HIP,
             RData::HTTPS(..) => RecordType::HTTPS,
+            RData::LOC(..) => RecordType::LOC,
             RData::MX(..) => RecordType::MX,
             RData::NAPTR(..) => RecordType::NAPTR,
             RData::NS(..) => RecordType::NS,
@@ -1263,12 +1676,15 @@ mod tests {
             RData::OPENPGPKEY(..) => RecordType::OPENPGPKEY,
             RData::OPT(..) => RecordType::OPT,
             RData::PTR(..) => RecordType::PTR,
+            RData::SMIMEA(..) => RecordType::SMIMEA,
             RData::SOA(..) => RecordType::SOA,
             RData::SRV(..) => RecordType::SRV,
             RData::SSHFP(..) => RecordType::SSHFP,
             RData::SVCB(..) => RecordType::SVCB,
             RData::TLSA(..) => RecordType::TLSA,
             RData::TXT(..) => RecordType::TXT,
+            RData::URI(..) => RecordType::URI,
+            RData::ZONEMD(..) => RecordType::ZONEMD,
             #[cfg(feature = "dnssec")]
             RData::DNSSEC(ref rdata) => rdata.to_record_type(),
             RData::Unknown { code, .. } => RecordType::Unknown(code),
@@ -1280,4 +1696,46 @@ mod tests {
     fn test_write_to() {
         test_emit_data_set(get_data(), |e, d| d.emit(e));
     }
+
+    #[test]
+    fn test_unknown_not_compressed() {
+        use crate::rr::Record;
+
+        // the rdata looks like the owner name, but must never be replaced by a pointer
+        let name = Name::from_str("www.example.com.").unwrap();
+        let name_bytes = name.to_bytes().unwrap();
+        let rdata = RData::Unknown {
+            code: 65534,
+            rdata: NULL::with(name_bytes.clone()),
+        };
+        let record = Record::from_rdata(name.clone(), 3600, rdata.clone());
+
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        name.emit(&mut encoder).unwrap();
+        record.emit(&mut encoder).unwrap();
+
+        assert!(bytes.ends_with(&name_bytes));
+
+        let mut decoder = BinDecoder::new(&bytes);
+        Name::read(&mut decoder).unwrap();
+        let read = Record::read(&mut decoder).unwrap();
+        assert_eq!(read.record_type(), RecordType::Unknown(65534));
+        assert_eq!(read.data(), Some(&rdata));
+    }
+
+    #[test]
+    fn test_unknown_display() {
+        let rdata = RData::Unknown {
+            code: 65534,
+            rdata: NULL::with(vec![0xDE, 0xAD, 0xBE, 0xEF]),
+        };
+        assert_eq!(rdata.to_string(), "\\# 4 DEADBEEF");
+
+        let rdata = RData::Unknown {
+            code: 65534,
+            rdata: NULL::new(),
+        };
+        assert_eq!(rdata.to_string(), "\\# 0");
+    }
 }
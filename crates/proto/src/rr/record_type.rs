@@ -40,7 +40,10 @@ pub enum RecordType {
     //  AFSDB,      //	18	RFC 1183	AFS database record
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) All cached records, aka ANY
     ANY,
-    //  APL,        //	42	RFC 3123	Address Prefix List
+    /// [RFC 3123](https://tools.ietf.org/html/rfc3123) Address Prefix List
+    APL,
+    /// [IANA](https://www.iana.org/assignments/dns-parameters/AVC/avc-completed-template) Application Visibility and Control
+    AVC,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Authoritative Zone Transfer
     AXFR,
     /// [RFC 6844](https://tools.ietf.org/html/rfc6844) Certification Authority Authorization
@@ -49,12 +52,15 @@ pub enum RecordType {
     CDS,
     /// [RFC 7344](https://tools.ietf.org/html/rfc7344) Child DNSKEY
     CDNSKEY,
-    //  CERT,       // 37 RFC 4398 Certificate record
+    /// [RFC 4398](https://tools.ietf.org/html/rfc4398) Certificate record
+    CERT,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Canonical name record
     CNAME,
-    //  DHCID,      // 49 RFC 4701 DHCP identifier
+    /// [RFC 4701](https://tools.ietf.org/html/rfc4701) DHCP identifier
+    DHCID,
     //  DLV,        //	32769	RFC 4431	DNSSEC Lookaside Validation record
-    //  DNAME,      // 39 RFC 2672 Delegation Name
+    /// [RFC 6672](https://tools.ietf.org/html/rfc6672) Delegation name, redirection of a subtree
+    DNAME,
     /// [RFC 7477](https://tools.ietf.org/html/rfc4034) Child-to-parent synchronization record
     CSYNC,
     /// [RFC 4034](https://tools.ietf.org/html/rfc4034) DNS Key record: RSASHA256 and RSASHA512, RFC5702
@@ -63,7 +69,8 @@ pub enum RecordType {
     DS,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) host information
     HINFO,
-    //  HIP,        // 55 RFC 5205 Host Identity Protocol
+    /// [RFC 8005](https://tools.ietf.org/html/rfc8005) Host Identity Protocol
+    HIP,
     /// [RFC draft-ietf-dnsop-svcb-https-03](https://tools.ietf.org/html/draft-ietf-dnsop-svcb-httpssvc-03) DNS SVCB and HTTPS RRs
     HTTPS,
     //  IPSECKEY,   // 45 RFC 4025 IPsec Key
@@ -72,7 +79,8 @@ pub enum RecordType {
     //  KX,         // 36 RFC 2230 Key eXchanger record
     /// [RFC 2535](https://tools.ietf.org/html/rfc2535) and [RFC 2930](https://tools.ietf.org/html/rfc2930) Key record
     KEY,
-    //  LOC,        // 29 RFC 1876 Location record
+    /// [RFC 1876](https://tools.ietf.org/html/rfc1876) Location record
+    LOC,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Mail exchange record
     MX,
     /// [RFC 3403](https://tools.ietf.org/html/rfc3403) Naming Authority Pointer
@@ -98,6 +106,8 @@ pub enum RecordType {
     RRSIG,
     /// [RFC 2535](https://tools.ietf.org/html/rfc2535) (and [RFC 2931](https://tools.ietf.org/html/rfc2931)) Signature, to support [RFC 2137](https://tools.ietf.org/html/rfc2137) Update.
     SIG,
+    /// [RFC 8162](https://tools.ietf.org/html/rfc8162) S/MIME cert association
+    SMIMEA,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) and [RFC 2308](https://tools.ietf.org/html/rfc2308) Start of [a zone of] authority record
     SOA,
     /// [RFC 2782](https://tools.ietf.org/html/rfc2782) Service locator
@@ -114,11 +124,15 @@ pub enum RecordType {
     TSIG,
     /// [RFC 1035](https://tools.ietf.org/html/rfc1035) Text record
     TXT,
+    /// [RFC 7553](https://tools.ietf.org/html/rfc7553) Uniform Resource Identifier
+    URI,
     /// Unknown Record type, or unsupported
     Unknown(u16),
 
     /// This corresponds to a record type of 0, unspecified
     ZERO,
+    /// [RFC 8976](https://tools.ietf.org/html/rfc8976) Message Digest for DNS Zones
+    ZONEMD,
 }
 
 impl RecordType {
@@ -203,17 +217,24 @@ impl FromStr for RecordType {
             "A" => Ok(Self::A),
             "AAAA" => Ok(Self::AAAA),
             "ANAME" => Ok(Self::ANAME),
+            "APL" => Ok(Self::APL),
+            "AVC" => Ok(Self::AVC),
             "AXFR" => Ok(Self::AXFR),
             "CAA" => Ok(Self::CAA),
             "CDNSKEY" => Ok(Self::CDNSKEY),
             "CDS" => Ok(Self::CDS),
+            "CERT" => Ok(Self::CERT),
             "CNAME" => Ok(Self::CNAME),
             "CSYNC" => Ok(Self::CSYNC),
+            "DHCID" => Ok(Self::DHCID),
+            "DNAME" => Ok(Self::DNAME),
             "DNSKEY" => Ok(Self::DNSKEY),
             "DS" => Ok(Self::DS),
             "HINFO" => Ok(Self::HINFO),
+            "HIP" => Ok(Self::HIP),
             "HTTPS" => Ok(Self::HTTPS),
             "KEY" => Ok(Self::KEY),
+            "LOC" => Ok(Self::LOC),
             "MX" => Ok(Self::MX),
             "NAPTR" => Ok(Self::NAPTR),
             "NSEC" => Ok(Self::NSEC),
@@ -225,6 +246,7 @@ impl FromStr for RecordType {
             "PTR" => Ok(Self::PTR),
             "RRSIG" => Ok(Self::RRSIG),
             "SIG" => Ok(Self::SIG),
+            "SMIMEA" => Ok(Self::SMIMEA),
             "SOA" => Ok(Self::SOA),
             "SRV" => Ok(Self::SRV),
             "SSHFP" => Ok(Self::SSHFP),
@@ -232,8 +254,15 @@ impl FromStr for RecordType {
             "TLSA" => Ok(Self::TLSA),
             "TXT" => Ok(Self::TXT),
             "TSIG" => Ok(Self::TSIG),
+            "URI" => Ok(Self::URI),
+            "ZONEMD" => Ok(Self::ZONEMD),
             "ANY" | "*" => Ok(Self::ANY),
-            _ => Err(ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
+            // the generic representation of RFC 3597, e.g. TYPE731
+            _ => str
+                .strip_prefix("TYPE")
+                .and_then(|code| code.parse::<u16>().ok())
+                .map(Self::from)
+                .ok_or_else(|| ProtoErrorKind::UnknownRecordTypeStr(str.to_string()).into()),
         }
     }
 }
@@ -254,18 +283,25 @@ impl From<u16> for RecordType {
             // TODO: wrong value here, see https://github.com/bluejekyll/trust-dns/issues/723
             65305 => Self::ANAME,
             255 => Self::ANY,
+            42 => Self::APL,
+            258 => Self::AVC,
             251 => Self::IXFR,
             252 => Self::AXFR,
             257 => Self::CAA,
             59 => Self::CDS,
             60 => Self::CDNSKEY,
+            37 => Self::CERT,
             5 => Self::CNAME,
             62 => Self::CSYNC,
+            49 => Self::DHCID,
+            39 => Self::DNAME,
             48 => Self::DNSKEY,
             43 => Self::DS,
             13 => Self::HINFO,
+            55 => Self::HIP,
             65 => Self::HTTPS,
             25 => Self::KEY,
+            29 => Self::LOC,
             15 => Self::MX,
             35 => Self::NAPTR,
             2 => Self::NS,
@@ -278,6 +314,7 @@ impl From<u16> for RecordType {
             12 => Self::PTR,
             46 => Self::RRSIG,
             24 => Self::SIG,
+            53 => Self::SMIMEA,
             6 => Self::SOA,
             33 => Self::SRV,
             44 => Self::SSHFP,
@@ -285,7 +322,9 @@ impl From<u16> for RecordType {
             52 => Self::TLSA,
             250 => Self::TSIG,
             16 => Self::TXT,
+            256 => Self::URI,
             0 => Self::ZERO,
+            63 => Self::ZONEMD,
             // all unknown record types
             _ => Self::Unknown(value),
         }
@@ -330,17 +369,24 @@ impl From<RecordType> for &'static str {
             RecordType::AAAA => "AAAA",
             RecordType::ANAME => "ANAME",
             RecordType::ANY => "ANY",
+            RecordType::APL => "APL",
+            RecordType::AVC => "AVC",
             RecordType::AXFR => "AXFR",
             RecordType::CAA => "CAA",
             RecordType::CDNSKEY => "CDNSKEY",
             RecordType::CDS => "CDS",
+            RecordType::CERT => "CERT",
             RecordType::CNAME => "CNAME",
             RecordType::CSYNC => "CSYNC",
+            RecordType::DHCID => "DHCID",
+            RecordType::DNAME => "DNAME",
             RecordType::DNSKEY => "DNSKEY",
             RecordType::DS => "DS",
             RecordType::HINFO => "HINFO",
+            RecordType::HIP => "HIP",
             RecordType::HTTPS => "HTTPS",
             RecordType::KEY => "KEY",
+            RecordType::LOC => "LOC",
             RecordType::IXFR => "IXFR",
             RecordType::MX => "MX",
             RecordType::NAPTR => "NAPTR",
@@ -354,6 +400,7 @@ impl From<RecordType> for &'static str {
             RecordType::PTR => "PTR",
             RecordType::RRSIG => "RRSIG",
             RecordType::SIG => "SIG",
+            RecordType::SMIMEA => "SMIMEA",
             RecordType::SOA => "SOA",
             RecordType::SRV => "SRV",
             RecordType::SSHFP => "SSHFP",
@@ -361,7 +408,9 @@ impl From<RecordType> for &'static str {
             RecordType::TLSA => "TLSA",
             RecordType::TSIG => "TSIG",
             RecordType::TXT => "TXT",
+            RecordType::URI => "URI",
             RecordType::ZERO => "ZERO",
+            RecordType::ZONEMD => "ZONEMD",
             RecordType::Unknown(_) => "Unknown",
         }
     }
@@ -384,17 +433,24 @@ impl From<RecordType> for u16 {
             // TODO: wrong value here, see https://github.com/bluejekyll/trust-dns/issues/723
             RecordType::ANAME => 65305,
             RecordType::ANY => 255,
+            RecordType::APL => 42,
+            RecordType::AVC => 258,
             RecordType::AXFR => 252,
             RecordType::CAA => 257,
             RecordType::CDNSKEY => 60,
             RecordType::CDS => 59,
+            RecordType::CERT => 37,
             RecordType::CNAME => 5,
             RecordType::CSYNC => 62,
+            RecordType::DHCID => 49,
+            RecordType::DNAME => 39,
             RecordType::DNSKEY => 48,
             RecordType::DS => 43,
             RecordType::HINFO => 13,
+            RecordType::HIP => 55,
             RecordType::HTTPS => 65,
             RecordType::KEY => 25,
+            RecordType::LOC => 29,
             RecordType::IXFR => 251,
             RecordType::MX => 15,
             RecordType::NAPTR => 35,
@@ -408,6 +464,7 @@ impl From<RecordType> for u16 {
             RecordType::PTR => 12,
             RecordType::RRSIG => 46,
             RecordType::SIG => 24,
+            RecordType::SMIMEA => 53,
             RecordType::SOA => 6,
             RecordType::SRV => 33,
             RecordType::SSHFP => 44,
@@ -415,7 +472,9 @@ impl From<RecordType> for u16 {
             RecordType::TLSA => 52,
             RecordType::TSIG => 250,
             RecordType::TXT => 16,
+            RecordType::URI => 256,
             RecordType::ZERO => 0,
+            RecordType::ZONEMD => 63,
             RecordType::Unknown(code) => code,
         }
     }
@@ -437,7 +496,11 @@ impl Ord for RecordType {
 
 impl Display for RecordType {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), fmt::Error> {
-        f.write_str(Into::<&str>::into(*self))
+        match *self {
+            // the generic representation of RFC 3597
+            Self::Unknown(code) => write!(f, "TYPE{}", code),
+            _ => f.write_str(Into::<&str>::into(*self)),
+        }
     }
 }
 
@@ -460,6 +523,7 @@ mod tests {
             RecordType::MX,
             RecordType::TXT,
             RecordType::AAAA,
+            RecordType::LOC,
             RecordType::SRV,
             RecordType::CSYNC,
             RecordType::AXFR,
@@ -481,6 +545,7 @@ mod tests {
             RecordType::AAAA,
             RecordType::HINFO,
             RecordType::CSYNC,
+            RecordType::LOC,
         ];
 
         unordered.sort();
@@ -500,21 +565,31 @@ mod tests {
             "A",
             "AAAA",
             "ANAME",
+            "APL",
+            "AVC",
             "CAA",
+            "CERT",
             "CNAME",
             "CSYNC",
+            "DHCID",
+            "DNAME",
             "HINFO",
+            "HIP",
+            "LOC",
             "NULL",
             "MX",
             "NAPTR",
             "NS",
             "OPENPGPKEY",
             "PTR",
+            "SMIMEA",
             "SOA",
             "SRV",
             "SSHFP",
             "TLSA",
             "TXT",
+            "URI",
+            "ZONEMD",
             "ANY",
             "AXFR",
         ];
@@ -543,4 +618,16 @@ mod tests {
             assert!(rtypes.insert(rtype));
         }
     }
+
+    #[test]
+    fn test_record_type_parse_generic() {
+        assert_eq!("TYPE1".parse::<RecordType>().unwrap(), RecordType::A);
+        assert_eq!(
+            "TYPE65534".parse::<RecordType>().unwrap(),
+            RecordType::Unknown(65534)
+        );
+        assert_eq!(RecordType::Unknown(65534).to_string(), "TYPE65534");
+        assert!("TYPE".parse::<RecordType>().is_err());
+        assert!("TYPE65536".parse::<RecordType>().is_err());
+    }
 }
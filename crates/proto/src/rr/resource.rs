@@ -16,6 +16,7 @@
 
 //! resource record implementation
 
+use std::borrow::Borrow;
 use std::cmp::Ordering;
 use std::fmt;
 
@@ -672,6 +673,23 @@ impl PartialOrd<Self> for Record {
     }
 }
 
+/// Sorts the records into canonical order, [RFC 4034](https://tools.ietf.org/html/rfc4034#section-6)
+///
+/// The records are ordered by owner name in the canonical name order, see the `Ord` of [`Name`], then
+///  by class and type code, and the records of an RRset by their RDATA, see [`RData::canonical_order`].
+///  This is the order in which the records of an RRset are signed, and a deterministic order of a zone.
+pub fn sort_canonical<R: Borrow<Record>>(records: &mut [R]) {
+    records.sort_by_cached_key(|record| {
+        let record = record.borrow();
+        (
+            record.name().clone(),
+            record.dns_class(),
+            record.rr_type(),
+            record.data().map(RData::to_canonical_bytes),
+        )
+    });
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(clippy::dbg_macro, clippy::print_stdout)]
@@ -748,6 +766,81 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_sort_canonical() {
+        use crate::rr::rdata::MX;
+
+        let record = |exchange| {
+            Record::from_rdata(
+                Name::from_str("example.com.").unwrap(),
+                300,
+                RData::MX(MX::new(10, Name::from_ascii(exchange).unwrap())),
+            )
+        };
+        let exchanges = |rrset: &[Record]| {
+            rrset
+                .iter()
+                .map(|record| record.data().unwrap().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let mut rrset = vec![
+            record("C.example.com."),
+            record("b.example.com."),
+            record("A.example.com."),
+        ];
+        sort_canonical(&mut rrset);
+
+        assert_eq!(
+            exchanges(&rrset),
+            vec![
+                "10 A.example.com.",
+                "10 b.example.com.",
+                "10 C.example.com."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_canonical_zone() {
+        let record =
+            |name: &str, rdata| Record::from_rdata(Name::from_ascii(name).unwrap(), 300, rdata);
+        let a = |last| RData::A(Ipv4Addr::new(192, 0, 2, last));
+        let aaaa = RData::AAAA("2001:db8::1".parse().unwrap());
+
+        let mut records = vec![
+            record("z.example.", a(1)),
+            record("b.A.example.", a(1)),
+            record("a.example.", aaaa.clone()),
+            record("example.", a(2)),
+            record("A.example.", a(2)),
+            record("a.example.", a(1)),
+            record("*.z.example.", a(1)),
+            record("yljkjljk.a.example.", a(1)),
+            record("example.", a(1)),
+        ];
+        sort_canonical(&mut records);
+
+        let sorted = records
+            .iter()
+            .map(|r| format!("{} {} {}", r.name(), r.rr_type(), r.data().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sorted,
+            vec![
+                "example. A 192.0.2.1",
+                "example. A 192.0.2.2",
+                "a.example. A 192.0.2.1",
+                "A.example. A 192.0.2.2",
+                "a.example. AAAA 2001:db8::1",
+                "b.A.example. A 192.0.2.1",
+                "yljkjljk.a.example. A 192.0.2.1",
+                "z.example. A 192.0.2.1",
+                "*.z.example. A 192.0.2.1",
+            ]
+        );
+    }
+
     #[cfg(feature = "mdns")]
     #[test]
     fn test_mdns_cache_flush_bit_handling() {
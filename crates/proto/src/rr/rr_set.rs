@@ -960,15 +960,19 @@ mod test {
         rrset.insert_rrsig(rrsig_ecp384);
         rrset.insert_rrsig(rrsig_ed25519);
 
-        assert!(rrset
-            .records_with_rrsigs(SupportedAlgorithms::all(),)
-            .any(
-                |r| if let Some(RData::DNSSEC(DNSSECRData::SIG(ref sig))) = r.data() {
-                    sig.algorithm() == Algorithm::ED25519
-                } else {
-                    false
-                },
-            ));
+        // ED25519 is only supported with ring, see `SupportedAlgorithms::all`
+        assert_eq!(
+            rrset
+                .records_with_rrsigs(SupportedAlgorithms::all())
+                .any(|r| {
+                    if let Some(RData::DNSSEC(DNSSECRData::SIG(ref sig))) = r.data() {
+                        sig.algorithm() == Algorithm::ED25519
+                    } else {
+                        false
+                    }
+                }),
+            cfg!(feature = "ring")
+        );
 
         let mut supported_algorithms = SupportedAlgorithms::new();
         supported_algorithms.set(Algorithm::ECDSAP384SHA384);
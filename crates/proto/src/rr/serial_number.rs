@@ -0,0 +1,152 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Serial number arithmetic, as used by the SOA serial
+
+use std::cmp::Ordering;
+use std::fmt;
+
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
+
+/// A serial number, compared with the arithmetic of [RFC 1982](https://tools.ietf.org/html/rfc1982)
+///
+/// ```text
+/// 3.2. Comparison
+///
+///    Any two serial numbers, s1 and s2, may be compared.  The definition
+///    of the result of this comparison is as follows.
+///
+///    For the purposes of this definition, consider two integers, i1 and
+///    i2, from the unbounded set of non-negative integers, such that i1 and
+///    s1 have the same numeric value, as do i2 and s2.  Arithmetic and
+///    comparisons applied to i1 and i2 use ordinary unbounded integer
+///    arithmetic.
+///
+///    Then, s1 is said to be equal to s2 if and only if i1 is equal to i2,
+///    in all other cases, s1 is not equal to s2.
+///
+///    s1 is said to be less than s2 if, and only if, s1 is not equal to s2,
+///    and
+///
+///         (i1 < i2 and i2 - i1 < 2^(SERIAL_BITS - 1)) or
+///         (i1 > i2 and i1 - i2 > 2^(SERIAL_BITS - 1))
+///
+///    s1 is said to be greater than s2 if, and only if, s1 is not equal to
+///    s2, and
+///
+///         (i1 < i2 and i2 - i1 > 2^(SERIAL_BITS - 1)) or
+///         (i1 > i2 and i1 - i2 < 2^(SERIAL_BITS - 1))
+///
+///    Note that there are some pairs of values s1 and s2 for which s1 is
+///    not equal to s2, but for which s1 is neither greater than, nor less
+///    than, s2.
+/// ```
+///
+/// The last point means that this is only `PartialOrd`, `partial_cmp` returns `None` for those pairs.
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct SerialNumber(u32);
+
+impl SerialNumber {
+    const HALF: u32 = 1 << 31;
+
+    /// Returns the serial number as a u32
+    pub fn get(self) -> u32 {
+        self.0
+    }
+
+    /// Adds `n` to the serial number, wrapping around on overflow
+    ///
+    /// ```text
+    /// 3.1. Addition
+    ///
+    ///    Serial numbers may be incremented by the addition of a positive
+    ///    integer n, where n is taken from the range of integers
+    ///    [0 .. (2^(SERIAL_BITS - 1) - 1)].
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If `n` is greater than `2^31 - 1`, which is an undefined addition
+    pub fn wrapping_add(self, n: u32) -> Self {
+        assert!(n < Self::HALF, "serial number addition out of range: {}", n);
+        Self(self.0.wrapping_add(n))
+    }
+}
+
+impl From<u32> for SerialNumber {
+    fn from(serial: u32) -> Self {
+        Self(serial)
+    }
+}
+
+impl From<SerialNumber> for u32 {
+    fn from(serial: SerialNumber) -> Self {
+        serial.0
+    }
+}
+
+impl PartialOrd for SerialNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        let (i1, i2) = (self.0, other.0);
+
+        if i1 == i2 {
+            Some(Ordering::Equal)
+        } else if (i1 < i2 && i2 - i1 < Self::HALF) || (i1 > i2 && i1 - i2 > Self::HALF) {
+            Some(Ordering::Less)
+        } else if (i1 < i2 && i2 - i1 > Self::HALF) || (i1 > i2 && i1 - i2 < Self::HALF) {
+            Some(Ordering::Greater)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for SerialNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serial_cmp() {
+        let one = SerialNumber::from(1);
+        let max = SerialNumber::from(u32::max_value());
+
+        assert!(one > SerialNumber::from(0));
+        assert!(one < SerialNumber::from(2));
+        assert_eq!(one, SerialNumber::from(1));
+
+        // wrapped around
+        assert!(max < SerialNumber::from(0));
+        assert!(max < one);
+        assert!(SerialNumber::from(0) > max);
+
+        // undefined, exactly half the space apart
+        assert_eq!(SerialNumber::from(0).partial_cmp(&(1 << 31).into()), None);
+        assert_eq!(SerialNumber::from(1 << 31).partial_cmp(&0.into()), None);
+    }
+
+    #[test]
+    fn test_serial_add() {
+        let max = SerialNumber::from(u32::max_value());
+
+        assert_eq!(max.wrapping_add(1), SerialNumber::from(0));
+        assert!(max.wrapping_add((1 << 31) - 1) > max);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_serial_add_out_of_range() {
+        SerialNumber::from(0).wrapping_add(1 << 31);
+    }
+}
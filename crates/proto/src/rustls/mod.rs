@@ -7,14 +7,19 @@
 
 //! TLS protocol related components for DNS over TLS
 
+pub mod spki_pin;
 pub mod tls_client_stream;
 pub mod tls_server;
 pub mod tls_stream;
 
+pub use self::spki_pin::spki_sha256;
 pub use self::tls_client_stream::{
-    tls_client_connect, tls_client_connect_with_bind_addr, TlsClientStream,
+    tls_client_connect, tls_client_connect_with_bind_addr, tls_client_connect_with_spki_pins,
+    TlsClientStream,
+};
+pub use self::tls_stream::{
+    tls_connect, tls_connect_with_bind_addr, tls_connect_with_spki_pins, tls_from_stream, TlsStream,
 };
-pub use self::tls_stream::{tls_connect, tls_connect_with_bind_addr, tls_from_stream, TlsStream};
 
 #[cfg(test)]
 pub(crate) mod tests;
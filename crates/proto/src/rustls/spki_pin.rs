@@ -0,0 +1,113 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Pinning of the public key of the certificate presented by the server
+
+use std::io;
+
+use ring::digest;
+use rustls::Certificate;
+
+use crate::error::{ProtoError, ProtoResult};
+use crate::rr::rdata::tlsa::certificate_spki;
+
+/// The SHA-256 digest of the DER encoded SubjectPublicKeyInfo of a certificate, the value to pin
+///
+/// This is the same digest as computed by
+///  `openssl x509 -noout -pubkey | openssl pkey -pubin -outform der | openssl dgst -sha256`.
+pub fn spki_sha256(cert_der: &[u8]) -> ProtoResult<[u8; 32]> {
+    let spki = certificate_spki(cert_der).map_err(|e| ProtoError::from(e.to_string()))?;
+
+    let mut sha256 = [0; 32];
+    sha256.copy_from_slice(digest::digest(&digest::SHA256, spki).as_ref());
+    Ok(sha256)
+}
+
+/// Checks the end-entity certificate of the server against the pins, if there are any
+///
+/// The certificate has been validated during the handshake already, the pins are an additional restriction.
+pub(crate) fn verify_spki_pins(
+    spki_pins: &[[u8; 32]],
+    peer_certificates: Option<&[Certificate]>,
+) -> io::Result<()> {
+    if spki_pins.is_empty() {
+        return Ok(());
+    }
+
+    let end_entity = peer_certificates
+        .and_then(<[Certificate]>::first)
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::ConnectionRefused,
+                "tls error: no certificate to check against the pinned public keys",
+            )
+        })?;
+    let sha256 = spki_sha256(&end_entity.0)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("tls error: {}", e)))?;
+
+    if !spki_pins.contains(&sha256) {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            "tls error: the public key of the certificate matches none of the pins",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use crate::rustls::tls_server::read_cert;
+
+    const CA_SPKI_SHA256: [u8; 32] = [
+        0x16, 0x67, 0x7a, 0xcc, 0xcf, 0x77, 0xb6, 0xd1, 0x56, 0x90, 0xc9, 0xb3, 0x54, 0xec, 0x1e,
+        0xbc, 0x3c, 0x02, 0xf2, 0x11, 0xfe, 0xb1, 0xcc, 0xd8, 0x31, 0x56, 0xb8, 0x6a, 0xbc, 0x7a,
+        0x50, 0x0a,
+    ];
+    const CERT_SPKI_SHA256: [u8; 32] = [
+        0x2b, 0x31, 0xb0, 0xe9, 0xb3, 0xd7, 0x2d, 0xbb, 0xa1, 0x94, 0x01, 0x73, 0x11, 0xee, 0xf3,
+        0x43, 0x5b, 0x21, 0x78, 0xfa, 0x1f, 0x08, 0xab, 0x7c, 0xfd, 0xe3, 0x2d, 0x85, 0x2a, 0x85,
+        0xfd, 0x0e,
+    ];
+
+    fn test_data(file: &str) -> String {
+        let server_path = env::var("TDNS_WORKSPACE_ROOT").unwrap_or_else(|_| "../..".to_owned());
+        format!("{}/tests/test-data/{}", server_path, file)
+    }
+
+    #[test]
+    fn test_spki_sha256() {
+        // a v1 certificate, without the version field
+        let ca = fs::read(test_data("ca.der")).expect("failed to read ca.der");
+        assert_eq!(spki_sha256(&ca).unwrap(), CA_SPKI_SHA256);
+
+        let cert = read_cert(Path::new(&test_data("cert.pem"))).expect("failed to read cert.pem");
+        assert_eq!(spki_sha256(&cert[0].0).unwrap(), CERT_SPKI_SHA256);
+
+        assert!(spki_sha256(&ca[..ca.len() / 2]).is_err());
+        assert!(spki_sha256(&[]).is_err());
+    }
+
+    #[test]
+    fn test_verify_spki_pins() {
+        let cert = read_cert(Path::new(&test_data("cert.pem"))).expect("failed to read cert.pem");
+
+        assert!(verify_spki_pins(&[CERT_SPKI_SHA256], Some(&cert)).is_ok());
+        assert!(verify_spki_pins(&[CA_SPKI_SHA256, CERT_SPKI_SHA256], Some(&cert)).is_ok());
+        assert!(verify_spki_pins(&[], Some(&cert)).is_ok());
+
+        // only the end-entity certificate is pinned
+        let err = verify_spki_pins(&[CA_SPKI_SHA256], Some(&cert)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        assert!(verify_spki_pins(&[CERT_SPKI_SHA256], None).is_err());
+    }
+}
@@ -18,7 +18,7 @@ use rustls::ClientConfig;
 use crate::error::ProtoError;
 use crate::iocompat::AsyncIoStdAsTokio;
 use crate::iocompat::AsyncIoTokioAsStd;
-use crate::rustls::tls_stream::tls_connect_with_bind_addr;
+use crate::rustls::tls_stream::tls_connect_with_spki_pins;
 use crate::tcp::{Connect, TcpClientStream};
 use crate::xfer::BufDnsStreamHandle;
 
@@ -61,9 +61,36 @@ pub fn tls_client_connect_with_bind_addr<S: Connect>(
 ) -> (
     Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send + Unpin>>,
     BufDnsStreamHandle,
+) {
+    tls_client_connect_with_spki_pins(name_server, bind_addr, dns_name, client_config, Vec::new())
+}
+
+/// Creates a new TlsStream to the specified name_server, which must present a certificate with one of the pinned public keys
+///
+/// See [`tls_connect_with_spki_pins`] for how the pins are checked.
+///
+/// # Arguments
+///
+/// * `name_server` - IP and Port for the remote DNS resolver
+/// * `bind_addr` - IP and port to connect from
+/// * `dns_name` - The DNS name, Subject Public Key Info (SPKI) name, as associated to a certificate
+/// * `spki_pins` - SHA-256 digests of the SubjectPublicKeyInfo, see [`spki_sha256`], no pins accepts any certificate
+///
+/// [`tls_connect_with_spki_pins`]: crate::rustls::tls_connect_with_spki_pins
+/// [`spki_sha256`]: crate::rustls::spki_sha256
+#[allow(clippy::type_complexity)]
+pub fn tls_client_connect_with_spki_pins<S: Connect>(
+    name_server: SocketAddr,
+    bind_addr: Option<SocketAddr>,
+    dns_name: String,
+    client_config: Arc<ClientConfig>,
+    spki_pins: Vec<[u8; 32]>,
+) -> (
+    Pin<Box<dyn Future<Output = Result<TlsClientStream<S>, ProtoError>> + Send + Unpin>>,
+    BufDnsStreamHandle,
 ) {
     let (stream_future, sender) =
-        tls_connect_with_bind_addr(name_server, bind_addr, dns_name, client_config);
+        tls_connect_with_spki_pins(name_server, bind_addr, dns_name, client_config, spki_pins);
 
     let new_future = Box::pin(
         stream_future
@@ -21,6 +21,7 @@ use tokio::net::TcpStream as TokioTcpStream;
 use tokio_rustls::TlsConnector;
 
 use crate::iocompat::{AsyncIoStdAsTokio, AsyncIoTokioAsStd};
+use crate::rustls::spki_pin::verify_spki_pins;
 use crate::tcp::Connect;
 use crate::tcp::{DnsTcpStream, TcpStream};
 use crate::xfer::{BufDnsStreamHandle, StreamReceiver};
@@ -118,9 +119,47 @@ pub fn tls_connect_with_bind_addr<S: Connect>(
         >,
     >,
     BufDnsStreamHandle,
+) {
+    tls_connect_with_spki_pins(name_server, bind_addr, dns_name, client_config, Vec::new())
+}
+
+/// Creates a new TlsStream to the specified name_server, which must present a certificate with one of the pinned public keys
+///
+/// The pins are checked after the handshake, in addition to the validation of the certificate by the `ClientConfig`. To
+///  rely on the pins alone, the `ClientConfig` must be built with a verifier accepting any certificate, see the
+///  `dangerous_configuration` feature of rustls. TLS 1.3 early data is not sent to pinned name servers, as it would be
+///  sent before the certificate can be checked.
+///
+/// # Arguments
+///
+/// * `name_server` - IP and Port for the remote DNS resolver
+/// * `bind_addr` - IP and port to connect from
+/// * `dns_name` - The DNS name,  Subject Public Key Info (SPKI) name, as associated to a certificate
+/// * `spki_pins` - SHA-256 digests of the SubjectPublicKeyInfo, see [`spki_sha256`], no pins accepts any certificate
+///
+/// [`spki_sha256`]: crate::rustls::spki_sha256
+#[allow(clippy::type_complexity)]
+pub fn tls_connect_with_spki_pins<S: Connect>(
+    name_server: SocketAddr,
+    bind_addr: Option<SocketAddr>,
+    dns_name: String,
+    client_config: Arc<ClientConfig>,
+    spki_pins: Vec<[u8; 32]>,
+) -> (
+    Pin<
+        Box<
+            dyn Future<
+                    Output = Result<
+                        TlsStream<AsyncIoTokioAsStd<TokioTlsClientStream<S>>>,
+                        io::Error,
+                    >,
+                > + Send,
+        >,
+    >,
+    BufDnsStreamHandle,
 ) {
     let (message_sender, outbound_messages) = BufDnsStreamHandle::new(name_server);
-    let early_data_enabled = client_config.enable_early_data;
+    let early_data_enabled = client_config.enable_early_data && spki_pins.is_empty();
     let tls_connector = TlsConnector::from(client_config).early_data(early_data_enabled);
 
     // This set of futures collapses the next tcp socket into a stream which can be used for
@@ -130,6 +169,7 @@ pub fn tls_connect_with_bind_addr<S: Connect>(
         name_server,
         bind_addr,
         dns_name,
+        spki_pins,
         outbound_messages,
     ));
 
@@ -141,6 +181,7 @@ async fn connect_tls<S: Connect>(
     name_server: SocketAddr,
     bind_addr: Option<SocketAddr>,
     dns_name: String,
+    spki_pins: Vec<[u8; 32]>,
     outbound_messages: StreamReceiver,
 ) -> io::Result<TcpStream<AsyncIoTokioAsStd<TokioTlsClientStream<S>>>> {
     let tcp = S::connect_with_bind(name_server, bind_addr).await?;
@@ -159,6 +200,7 @@ async fn connect_tls<S: Connect>(
             )
         })
         .await?;
+    verify_spki_pins(&spki_pins, s.get_ref().1.peer_certificates())?;
 
     Ok(TcpStream::from_stream_with_receiver(
         AsyncIoTokioAsStd(s),
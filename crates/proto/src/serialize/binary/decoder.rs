@@ -14,6 +14,7 @@
  * limitations under the License.
  */
 
+use crate::rr::domain::LabelSlices;
 use crate::serialize::binary::Restrict;
 use thiserror::Error;
 
@@ -55,6 +56,10 @@ pub enum DecodeError {
         ptr: u16,
     },
 
+    /// Too many compression pointers were followed while reading a name
+    #[error("compression pointer chain exceeds {0} jumps")]
+    PointerChainTooLong(usize),
+
     /// Label bytes exceeded the limit of 63
     #[error("label bytes exceed 63: {0}")]
     LabelBytesTooLong(usize),
@@ -150,11 +155,19 @@ impl<'a> BinDecoder<'a> {
     /// # Returns
     ///
     /// A String version of the character data
-    pub fn read_character_data(&mut self) -> DecodeResult<Restrict<&[u8]>> {
+    pub fn read_character_data(&mut self) -> DecodeResult<Restrict<&'a [u8]>> {
         let length = self.pop()?.unverified() as usize;
         self.read_slice(length)
     }
 
+    /// Reads a domain name, returning its labels as slices of the buffer rather than as a `Name`
+    ///
+    /// The name is validated as with `Name::read`, and the decoder is moved past it in the same
+    ///  way, but none of the labels are copied.
+    pub fn read_name_labels(&mut self) -> DecodeResult<LabelSlices<'a>> {
+        LabelSlices::read(self)
+    }
+
     /// Reads a Vec out of the buffer
     ///
     /// # Arguments
@@ -43,6 +43,11 @@ mod private {
             self.max_size = max as usize;
         }
 
+        /// Returns the maximum size enforced
+        pub(crate) fn max_size(&self) -> usize {
+            self.max_size
+        }
+
         /// returns an error if the maximum buffer size would be exceeded with the addition number of elements
         ///
         /// and reserves the additional space in the buffer
@@ -145,6 +150,11 @@ impl<'a> BinEncoder<'a> {
         self.buffer.set_max_size(max);
     }
 
+    /// Returns the maximum size of the buffer, see `set_max_size`
+    pub fn max_size(&self) -> u16 {
+        self.buffer.max_size() as u16
+    }
+
     /// Returns a reference to the internal buffer
     pub fn into_bytes(self) -> &'a Vec<u8> {
         self.buffer.into_bytes()
@@ -439,7 +449,7 @@ impl<'a> BinEncoder<'a> {
         emit_result
     }
 
-    fn set_rollback(&self) -> Rollback {
+    pub(crate) fn set_rollback(&self) -> Rollback {
         Rollback {
             rollback_index: self.offset(),
         }
@@ -489,8 +499,10 @@ pub(crate) struct Rollback {
 }
 
 impl Rollback {
+    /// Discards everything written after the rollback point, including the label pointers into it
     pub(crate) fn rollback(self, encoder: &mut BinEncoder<'_>) {
-        encoder.set_offset(self.rollback_index)
+        encoder.set_offset(self.rollback_index);
+        encoder.trim();
     }
 }
 
@@ -5,12 +5,11 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::borrow::Borrow;
 use std::fmt::{self, Display};
 use std::marker::PhantomData;
 use std::net::SocketAddr;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
@@ -19,7 +18,8 @@ use log::{debug, warn};
 
 use crate::error::ProtoError;
 use crate::op::message::NoopMessageFinalizer;
-use crate::op::{MessageFinalizer, MessageVerifier};
+use crate::op::{Message, MessageFinalizer, MessageVerifier, Query, ResponseCode};
+use crate::rr::rdata::opt::{Cookie, EdnsCode, EdnsOption};
 use crate::udp::udp_stream::{NextRandomUdpSocket, UdpSocket};
 use crate::xfer::{DnsRequest, DnsRequestSender, DnsResponse, DnsResponseStream, SerialMessage};
 use crate::Time;
@@ -39,6 +39,7 @@ where
     timeout: Duration,
     is_shutdown: bool,
     signer: Option<Arc<MF>>,
+    cookies: Arc<Cookies>,
     marker: PhantomData<S>,
 }
 
@@ -146,15 +147,11 @@ fn random_query_id() -> u16 {
 impl<S: UdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
     for UdpClientStream<S, MF>
 {
-    fn send_message(&mut self, mut message: DnsRequest) -> DnsResponseStream {
+    fn send_message(&mut self, message: DnsRequest) -> DnsResponseStream {
         if self.is_shutdown {
             panic!("can not send messages after stream is shutdown")
         }
 
-        // associated the ID for this request, b/c this connection is unique to socket port, the ID
-        //   does not need to be globally unique
-        message.set_id(random_query_id());
-
         let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
             Ok(now) => now.as_secs(),
             Err(_) => return ProtoError::from("Current time is before the Unix epoch.").into(),
@@ -163,42 +160,46 @@ impl<S: UdpSocket + Send + 'static, MF: MessageFinalizer> DnsRequestSender
         // TODO: truncates u64 to u32, error on overflow?
         let now = now as u32;
 
-        let mut verifier = None;
-        if let Some(ref signer) = self.signer {
-            if signer.should_finalize_message(&message) {
-                match message.finalize::<MF>(signer.borrow(), now) {
-                    Ok(answer_verifier) => verifier = answer_verifier,
-                    Err(e) => {
-                        debug!("could not sign message: {}", e);
-                        return e.into();
-                    }
-                }
-            }
-        }
-
-        let bytes = match message.to_vec() {
-            Ok(bytes) => bytes,
-            Err(err) => {
-                return err.into();
-            }
-        };
-
-        let message_id = message.id();
-        let message = SerialMessage::new(bytes, self.name_server);
+        let name_server = self.name_server;
         let bind_addr = self.bind_addr;
-
-        debug!(
-            "final message: {}",
-            message
-                .to_message()
-                .expect("bizarre we just made this message")
-        );
+        let signer = self.signer.clone();
+        let cookies = Arc::clone(&self.cookies);
 
         S::Time::timeout::<Pin<Box<dyn Future<Output = Result<DnsResponse, ProtoError>> + Send>>>(
             self.timeout,
-            Box::pin(send_serial_message::<S>(
-                message, message_id, verifier, bind_addr,
-            )),
+            Box::pin(async move {
+                let response = send_with_cookie::<S, MF>(
+                    message.clone(),
+                    name_server,
+                    bind_addr,
+                    signer.as_deref(),
+                    now,
+                    &cookies,
+                )
+                .await?;
+
+                // the server rejected the cookie, but will have sent back a new server cookie, retry once with it
+                //   https://tools.ietf.org/html/rfc7873#section-5.3
+                if response.response_code() == ResponseCode::BADCOOKIE
+                    && server_cookie(&response).is_some()
+                {
+                    debug!(
+                        "BADCOOKIE from {}, retrying with server cookie",
+                        name_server
+                    );
+                    return send_with_cookie::<S, MF>(
+                        message,
+                        name_server,
+                        bind_addr,
+                        signer.as_deref(),
+                        now,
+                        &cookies,
+                    )
+                    .await;
+                }
+
+                Ok(response)
+            }),
         )
         .into()
     }
@@ -250,16 +251,130 @@ impl<S: Send + Unpin, MF: MessageFinalizer> Future for UdpClientConnect<S, MF> {
             is_shutdown: false,
             timeout: self.timeout,
             signer: self.signer.take(),
+            cookies: Arc::new(Cookies::new()),
             marker: PhantomData,
         }))
     }
 }
 
+/// DNS Cookie state for a single name server, see [RFC 7873](https://tools.ietf.org/html/rfc7873)
+///
+/// The client cookie is random for each name server, the server cookie is learned from the responses.
+struct Cookies {
+    client: [u8; 8],
+    server: Mutex<Option<Vec<u8>>>,
+}
+
+impl Cookies {
+    fn new() -> Self {
+        Self {
+            client: rand::random(),
+            server: Mutex::new(None),
+        }
+    }
+
+    /// The cookie to send in the next request
+    fn cookie(&self) -> Cookie {
+        let server = self.server.lock().expect("cookie lock poisoned").clone();
+
+        Cookie::new(self.client, server).expect("server cookie was already verified")
+    }
+
+    /// Verifies the client cookie of the response, and stores the server cookie
+    ///
+    /// Returns false if the response should be dropped, i.e. the client cookie does not match.
+    fn verify(&self, response: &Message) -> bool {
+        let cookie = match response_cookie(response) {
+            Some(cookie) => cookie,
+            None => return true,
+        };
+
+        if cookie.client() != &self.client {
+            return false;
+        }
+
+        if let Some(server) = cookie.server() {
+            *self.server.lock().expect("cookie lock poisoned") = Some(server.to_vec());
+        }
+
+        true
+    }
+}
+
+fn response_cookie(response: &Message) -> Option<&Cookie> {
+    match response.extensions().as_ref()?.option(EdnsCode::Cookie)? {
+        EdnsOption::Cookie(cookie) => Some(cookie),
+        _ => None,
+    }
+}
+
+fn server_cookie(response: &Message) -> Option<&[u8]> {
+    response_cookie(response).and_then(Cookie::server)
+}
+
+/// Adds the cookie, if EDNS is enabled, and signs the message before sending it
+async fn send_with_cookie<S: UdpSocket + Send, MF: MessageFinalizer>(
+    mut message: DnsRequest,
+    name_server: SocketAddr,
+    bind_addr: Option<SocketAddr>,
+    signer: Option<&MF>,
+    now: u32,
+    cookies: &Cookies,
+) -> Result<DnsResponse, ProtoError> {
+    // associated the ID for this request, b/c this connection is unique to socket port, the ID
+    //   does not need to be globally unique
+    message.set_id(random_query_id());
+
+    // raw requests are sent as is, without a cookie or a signature
+    let is_raw = message.is_raw();
+
+    // cookies are only sent along with EDNS
+    if let Some(edns) = message.extensions_mut() {
+        if !is_raw {
+            edns.options_mut()
+                .insert(EdnsOption::Cookie(cookies.cookie()));
+        }
+    }
+
+    let mut verifier = None;
+    if let Some(signer) = signer {
+        if !is_raw && signer.should_finalize_message(&message) {
+            match message.finalize::<MF>(signer, now) {
+                Ok(answer_verifier) => verifier = answer_verifier,
+                Err(e) => {
+                    debug!("could not sign message: {}", e);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    let bytes = message.to_wire()?;
+    let message_id = message.id();
+    let queries = message.queries().to_vec();
+    let message = SerialMessage::new(bytes, name_server);
+
+    debug!(
+        "final message: {}",
+        message
+            .to_message()
+            .expect("bizarre we just made this message")
+    );
+
+    send_serial_message::<S>(
+        message, message_id, &queries, verifier, bind_addr, cookies, is_raw,
+    )
+    .await
+}
+
 async fn send_serial_message<S: UdpSocket + Send>(
     msg: SerialMessage,
     msg_id: u16,
+    queries: &[Query],
     verifier: Option<MessageVerifier>,
     bind_addr: Option<SocketAddr>,
+    cookies: &Cookies,
+    is_raw: bool,
 ) -> Result<DnsResponse, ProtoError> {
     let name_server = msg.addr();
     let socket: S = NextRandomUdpSocket::new(&name_server, &bind_addr).await?;
@@ -297,14 +412,32 @@ async fn send_serial_message<S: UdpSocket + Send>(
             continue;
         }
 
-        // TODO: match query strings from request and response?
-
         match response.to_message() {
             Ok(message) => {
                 if msg_id == message.id() {
                     debug!("received message id: {}", message.id());
+
+                    // on the wrong client cookie, attempted poison? the cookie of a raw request is not ours
+                    if !is_raw && !cookies.verify(&message) {
+                        warn!("client cookie does not match for id: {}, dropped", msg_id);
+                        continue;
+                    }
+
+                    // the name, type and class of the question must be echoed, some errors are returned without it
+                    if !message.queries().is_empty() && message.queries() != queries {
+                        warn!(
+                            "queries do not match for id: {}, expected: {:?} got: {:?}, dropped",
+                            msg_id,
+                            queries,
+                            message.queries()
+                        );
+                        continue;
+                    }
+
                     if let Some(mut verifier) = verifier {
                         return verifier(response.bytes());
+                    } else if is_raw {
+                        return Ok(DnsResponse::from(message).with_buffer(response.into_parts().0));
                     } else {
                         return Ok(DnsResponse::from(message));
                     }
@@ -340,7 +473,7 @@ mod tests {
     use crate::TokioTime;
     #[cfg(not(target_os = "linux"))]
     use std::net::Ipv6Addr;
-    use std::net::{IpAddr, Ipv4Addr};
+    use std::net::{IpAddr, Ipv4Addr, SocketAddr};
     use tokio::{net::UdpSocket as TokioUdpSocket, runtime::Runtime};
 
     #[test]
@@ -361,4 +494,179 @@ mod tests {
             io_loop,
         )
     }
+
+    #[test]
+    fn test_udp_client_stream_bad_cookie() {
+        use std::str::FromStr;
+        use std::time::Duration;
+
+        use super::UdpClientStream;
+        use crate::op::{Edns, Message, Query, ResponseCode};
+        use crate::rr::rdata::opt::{Cookie, EdnsCode, EdnsOption};
+        use crate::rr::{Name, RecordType};
+        use crate::xfer::{DnsRequest, DnsRequestOptions, DnsRequestSender, FirstAnswer};
+
+        const SERVER_COOKIE: &[u8] = b"servercookie";
+
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+
+        let server =
+            std::net::UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0))
+                .unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let server_addr = server.local_addr().unwrap();
+
+        // rejects any request without the server cookie
+        let server_handle = std::thread::spawn(move || {
+            let mut buffer = [0_u8; 512];
+            let mut response_codes = Vec::new();
+
+            for _ in 0..3 {
+                let (len, addr) = server.recv_from(&mut buffer).expect("receive failed");
+                let request = Message::from_vec(&buffer[0..len]).expect("failed parse of request");
+
+                let cookie = match request
+                    .extensions()
+                    .as_ref()
+                    .and_then(|edns| edns.option(EdnsCode::Cookie))
+                {
+                    Some(EdnsOption::Cookie(cookie)) => cookie.clone(),
+                    option => panic!("expected a cookie: {:?}", option),
+                };
+
+                let response_code = if cookie.server() == Some(SERVER_COOKIE) {
+                    ResponseCode::NoError
+                } else {
+                    ResponseCode::BADCOOKIE
+                };
+                response_codes.push(response_code);
+
+                let mut edns = Edns::new();
+                edns.options_mut().insert(EdnsOption::Cookie(
+                    Cookie::new(*cookie.client(), Some(SERVER_COOKIE.to_vec())).unwrap(),
+                ));
+
+                let mut response =
+                    Message::error_msg(request.id(), request.op_code(), response_code);
+                response.add_queries(request.queries().to_vec());
+                response.set_edns(edns);
+
+                let bytes = response.to_vec().unwrap();
+                server.send_to(&bytes, addr).expect("send failed");
+            }
+
+            response_codes
+        });
+
+        let stream =
+            UdpClientStream::<TokioUdpSocket>::with_timeout(server_addr, Duration::from_secs(5));
+        let mut stream = io_loop.block_on(stream).expect("failed to create stream");
+
+        let mut query = Message::new();
+        query
+            .add_query(Query::query(
+                Name::from_str("www.example.com.").unwrap(),
+                RecordType::A,
+            ))
+            .set_edns(Edns::new());
+
+        // the first query is retried with the learned server cookie, the second uses it immediately
+        for _ in 0..2 {
+            let response = io_loop
+                .block_on(
+                    stream
+                        .send_message(DnsRequest::new(query.clone(), DnsRequestOptions::default()))
+                        .first_answer(),
+                )
+                .expect("query failed");
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+        }
+
+        assert_eq!(
+            server_handle.join().expect("server thread failed"),
+            vec![
+                ResponseCode::BADCOOKIE,
+                ResponseCode::NoError,
+                ResponseCode::NoError
+            ]
+        );
+    }
+
+    #[test]
+    fn test_udp_client_stream_chaos_query() {
+        use std::str::FromStr;
+        use std::time::Duration;
+
+        use super::UdpClientStream;
+        use crate::op::{Message, MessageType, Query};
+        use crate::rr::rdata::TXT;
+        use crate::rr::{DNSClass, Name, RData, Record, RecordType};
+        use crate::xfer::{DnsRequest, DnsRequestOptions, DnsRequestSender, FirstAnswer};
+
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+
+        let server =
+            std::net::UdpSocket::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0))
+                .unwrap();
+        server
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let server_addr = server.local_addr().unwrap();
+
+        // answers first for the IN class, which must be dropped, then for the CH class of the query
+        let server_handle = std::thread::spawn(move || {
+            let mut buffer = [0_u8; 512];
+            let (len, addr) = server.recv_from(&mut buffer).expect("receive failed");
+            let request = Message::from_vec(&buffer[0..len]).expect("failed parse of request");
+
+            for &class in &[DNSClass::IN, DNSClass::CH] {
+                let mut query = request.queries()[0].clone();
+                query.set_query_class(class);
+
+                let mut record = Record::from_rdata(
+                    query.name().clone(),
+                    0,
+                    RData::TXT(TXT::new(vec!["trust-dns".to_string()])),
+                );
+                record.set_dns_class(class);
+
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .add_query(query)
+                    .add_answer(record);
+
+                let bytes = response.to_vec().unwrap();
+                server.send_to(&bytes, addr).expect("send failed");
+            }
+        });
+
+        let stream =
+            UdpClientStream::<TokioUdpSocket>::with_timeout(server_addr, Duration::from_secs(5));
+        let mut stream = io_loop.block_on(stream).expect("failed to create stream");
+
+        let mut query = Query::query(Name::from_str("version.bind.").unwrap(), RecordType::TXT);
+        query.set_query_class(DNSClass::CH);
+        let mut message = Message::new();
+        message.add_query(query);
+
+        let response = io_loop
+            .block_on(
+                stream
+                    .send_message(DnsRequest::new(message, DnsRequestOptions::default()))
+                    .first_answer(),
+            )
+            .expect("query failed");
+        server_handle.join().expect("server thread failed");
+
+        assert_eq!(response.queries()[0].query_class(), DNSClass::CH);
+        assert_eq!(response.answers()[0].dns_class(), DNSClass::CH);
+        assert_eq!(
+            response.answers()[0].data(),
+            Some(&RData::TXT(TXT::new(vec!["trust-dns".to_string()])))
+        );
+    }
 }
@@ -99,8 +99,9 @@ impl DnsHandle for DnsExchange {
 
     fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(&mut self, request: R) -> Self::Response {
         DnsExchangeSend {
-            result: self.sender.send(request),
-            _sender: self.sender.clone(), // TODO: this shouldn't be necessary, currently the presence of Senders is what allows the background to track current users, it generally is dropped right after send, this makes sure that there is at least one active after send
+            request: Some(request.into()),
+            result: None,
+            sender: self.sender.clone(), // TODO: this shouldn't be necessary, currently the presence of Senders is what allows the background to track current users, it generally is dropped right after send, this makes sure that there is at least one active after send
         }
     }
 }
@@ -108,16 +109,35 @@ impl DnsHandle for DnsExchange {
 /// A Stream that will resolve to Responses after sending the request
 #[must_use = "futures do nothing unless polled"]
 pub struct DnsExchangeSend {
-    result: DnsResponseReceiver,
-    _sender: BufDnsRequestStreamHandle,
+    request: Option<DnsRequest>,
+    result: Option<DnsResponseReceiver>,
+    sender: BufDnsRequestStreamHandle,
 }
 
 impl Stream for DnsExchangeSend {
     type Item = Result<DnsResponse, ProtoError>;
 
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // the request is sent once there is room for it in the buffer of the exchange
+        if let Some(request) = self.request.take() {
+            match self.sender.poll_ready(cx) {
+                Poll::Ready(Ok(())) => {
+                    let result = self.sender.send(request);
+                    self.result = Some(result);
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e))),
+                Poll::Pending => {
+                    self.request = Some(request);
+                    return Poll::Pending;
+                }
+            }
+        }
+
         // as long as there is no result, poll the exchange
-        self.result.poll_next_unpin(cx)
+        match self.result {
+            Some(ref mut result) => result.poll_next_unpin(cx),
+            None => Poll::Ready(None),
+        }
     }
 }
 
@@ -163,15 +183,10 @@ where
             match io_stream.as_mut().poll_next(cx) {
                 // The stream is ready
                 Poll::Ready(Some(Ok(()))) => (),
-                Poll::Pending => {
-                    if io_stream.is_shutdown() {
-                        // the io_stream is in a shutdown state, we are only waiting for final results...
-                        return Poll::Pending;
-                    }
-
-                    // NotReady and not shutdown, see if there are more messages to send
-                    ()
-                } // underlying stream is complete.
+                // the stream is not ready to send, or is in a shutdown state and only waiting for final
+                //  results, it wakes this task once it is ready again
+                Poll::Pending => return Poll::Pending,
+                // underlying stream is complete.
                 Poll::Ready(None) => {
                     debug!("io_stream is done, shutting down");
                     // TODO: return shutdown error to anything in the stream?
@@ -9,17 +9,17 @@
 use std::error::Error;
 
 use futures_util::stream::Stream;
-use log::debug;
+use log::{debug, warn};
 use rand;
 
-use crate::op::{Message, MessageType, OpCode, Query};
+use crate::op::{Message, MessageType, OpCode, PaddingPolicy, Query};
+use crate::rr::rdata::opt::EdnsOption;
 use crate::xfer::{DnsRequest, DnsRequestOptions, DnsResponse, SerialMessage};
 use crate::{error::*, op::Edns};
 
-// TODO: this should be configurable
 // > An EDNS buffer size of 1232 bytes will avoid fragmentation on nearly all current networks.
 // https://dnsflagday.net/2020/
-const MAX_PAYLOAD_LEN: u16 = 1232;
+pub(crate) const MAX_PAYLOAD_LEN: u16 = 1232;
 
 /// Implementations of Sinks for sending DNS messages
 pub trait DnsStreamHandle: 'static + Send {
@@ -80,15 +80,142 @@ fn build_message(query: Query, options: DnsRequestOptions) -> Message {
         .set_id(id)
         .set_message_type(MessageType::Query)
         .set_op_code(OpCode::Query)
-        .set_recursion_desired(true);
+        .set_recursion_desired(options.recursion_desired)
+        .set_checking_disabled(options.checking_disabled);
 
     // Extended dns
-    if options.use_edns {
-        message
+    if options.use_edns
+        || options.dnssec_ok
+        || options.client_subnet.is_some()
+        || options.padding != PaddingPolicy::None
+    {
+        let edns = message
             .extensions_mut()
             .get_or_insert_with(Edns::new)
-            .set_max_payload(MAX_PAYLOAD_LEN)
-            .set_version(0);
+            .set_max_payload(options.max_payload_len)
+            .set_version(0)
+            .set_dnssec_ok(options.dnssec_ok);
+
+        if let Some(client_subnet) = options.client_subnet {
+            edns.options_mut().insert(EdnsOption::Subnet(client_subnet));
+        }
+    }
+
+    // padding must be last, as it depends on the length of the message
+    if let Err(e) = message.pad(options.padding) {
+        warn!("failed to pad message: {}", e);
     }
     message
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+
+    use super::*;
+    use crate::rr::rdata::opt::{ClientSubnet, EdnsCode};
+    use crate::rr::{Name, RecordType};
+
+    #[test]
+    fn test_build_message_client_subnet() {
+        let query = Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+        let subnet = ClientSubnet::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 0)), 24, 0);
+
+        let options = DnsRequestOptions {
+            client_subnet: Some(subnet),
+            ..DnsRequestOptions::default()
+        };
+
+        let message = build_message(query, options);
+        let edns = message
+            .extensions()
+            .as_ref()
+            .expect("edns should be enabled");
+        assert_eq!(
+            edns.option(EdnsCode::Subnet),
+            Some(&EdnsOption::Subnet(subnet))
+        );
+    }
+
+    #[test]
+    fn test_build_message_dnssec_ok() {
+        let query = Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+
+        let message = build_message(query.clone(), DnsRequestOptions::default());
+        assert!(message.extensions().is_none());
+
+        let options = DnsRequestOptions {
+            dnssec_ok: true,
+            ..DnsRequestOptions::default()
+        };
+
+        let message = build_message(query, options);
+        let edns = message
+            .extensions()
+            .as_ref()
+            .expect("edns should be enabled");
+        assert!(edns.dnssec_ok());
+    }
+
+    #[test]
+    fn test_build_message_max_payload() {
+        let query = Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+
+        let options = DnsRequestOptions {
+            use_edns: true,
+            ..DnsRequestOptions::default()
+        };
+
+        let message = build_message(query.clone(), options);
+        let edns = message
+            .extensions()
+            .as_ref()
+            .expect("edns should be enabled");
+        assert_eq!(edns.max_payload(), 1232);
+
+        let options = DnsRequestOptions {
+            max_payload_len: 4096,
+            ..options
+        };
+
+        let message = build_message(query, options);
+        let edns = message
+            .extensions()
+            .as_ref()
+            .expect("edns should be enabled");
+        assert_eq!(edns.max_payload(), 4096);
+    }
+
+    #[test]
+    fn test_build_message_header_flags() {
+        let query = Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+
+        let message = build_message(query.clone(), DnsRequestOptions::default());
+        assert!(message.recursion_desired());
+        assert!(!message.checking_disabled());
+
+        let options = DnsRequestOptions {
+            recursion_desired: false,
+            checking_disabled: true,
+            ..DnsRequestOptions::default()
+        };
+
+        let message = build_message(query, options);
+        assert!(!message.recursion_desired());
+        assert!(message.checking_disabled());
+    }
+
+    #[test]
+    fn test_build_message_padding() {
+        let query = Query::query(Name::from_ascii("www.example.com.").unwrap(), RecordType::A);
+
+        let options = DnsRequestOptions {
+            padding: PaddingPolicy::Block(128),
+            ..DnsRequestOptions::default()
+        };
+
+        let message = build_message(query, options);
+        assert!(message.extensions().is_some());
+        assert_eq!(message.to_vec().unwrap().len(), 128);
+    }
+}
@@ -25,7 +25,12 @@ use rand;
 use rand::distributions::{Distribution, Standard};
 
 use crate::error::*;
-use crate::op::{MessageFinalizer, MessageVerifier};
+use crate::op::dso::MIN_KEEPALIVE_INTERVAL;
+use crate::op::{
+    DsoMessage, DsoTlv, Edns, Keepalive, Message, MessageFinalizer, MessageType, MessageVerifier,
+    ResponseCode,
+};
+use crate::rr::rdata::opt::{EdnsCode, EdnsOption};
 use crate::xfer::{
     ignore_send, BufDnsStreamHandle, DnsClientStream, DnsRequest, DnsRequestSender, DnsResponse,
     DnsResponseStream, SerialMessage, CHANNEL_BUFFER_SIZE,
@@ -34,6 +39,8 @@ use crate::DnsStreamHandle;
 use crate::Time;
 
 const QOS_MAX_RECEIVE_MSGS: usize = 100; // max number of messages to receive from the UDP socket
+/// The maximum number of outstanding requests on one stream, half of the ID space so a free ID is quickly found
+const MAX_ACTIVE_REQUESTS: usize = 0xFFFF / 2;
 
 struct ActiveRequest {
     // the completion is the channel for a response to the original request
@@ -41,6 +48,8 @@ struct ActiveRequest {
     request_id: u16,
     timeout: Box<dyn Future<Output = ()> + Send + Unpin>,
     verifier: Option<MessageVerifier>,
+    // the received bytes are only kept for raw requests
+    keep_buffer: bool,
 }
 
 impl ActiveRequest {
@@ -49,6 +58,7 @@ impl ActiveRequest {
         request_id: u16,
         timeout: Box<dyn Future<Output = ()> + Send + Unpin>,
         verifier: Option<MessageVerifier>,
+        keep_buffer: bool,
     ) -> Self {
         Self {
             completion,
@@ -56,6 +66,7 @@ impl ActiveRequest {
             // request,
             timeout,
             verifier,
+            keep_buffer,
         }
     }
 
@@ -80,11 +91,98 @@ impl ActiveRequest {
     }
 }
 
+/// A DSO session, [RFC 8490](https://tools.ietf.org/html/rfc8490), which keeps an idle connection open
+struct DsoSession {
+    /// the timeouts requested from the server
+    keepalive: Keepalive,
+    /// the id of the outstanding keepalive request
+    request_id: Option<u16>,
+    /// the keepalive interval of the established session, `None` if it's infinite or not yet established
+    keepalive_interval: Option<Duration>,
+    /// fires once the connection was idle for the keepalive interval
+    timer: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl DsoSession {
+    fn new(keepalive: Keepalive) -> Self {
+        Self {
+            keepalive,
+            request_id: None,
+            keepalive_interval: None,
+            timer: None,
+        }
+    }
+
+    /// Restarts the keepalive interval, after sending a message
+    fn reset_timer<T: Time>(&mut self) {
+        self.timer = self.keepalive_interval.map(T::delay_for);
+    }
+
+    /// Applies the timeouts of the server, from a response or a unidirectional message
+    fn update<T: Time>(&mut self, message: &DsoMessage) {
+        let keepalive = message.tlvs().iter().find_map(|tlv| match tlv {
+            DsoTlv::Keepalive(keepalive) => Some(keepalive),
+            _ => None,
+        });
+
+        if let Some(keepalive) = keepalive {
+            debug!(
+                "DSO session established, inactivity timeout: {:?} keepalive interval: {:?}",
+                keepalive.inactivity_timeout(),
+                keepalive.keepalive_interval()
+            );
+            self.keepalive_interval = keepalive
+                .keepalive_interval()
+                .map(|interval| interval.max(MIN_KEEPALIVE_INTERVAL));
+            self.reset_timer::<T>();
+        }
+    }
+}
+
+/// The edns-tcp-keepalive option, [RFC 7828](https://tools.ietf.org/html/rfc7828), which closes an idle connection
+struct TcpKeepalive {
+    /// the idle timeout of the client, until the server replies with its own
+    idle_timeout: Duration,
+    /// fires once no message was sent or received for the idle timeout
+    timer: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+}
+
+impl TcpKeepalive {
+    fn new(idle_timeout: Duration) -> Self {
+        Self {
+            idle_timeout,
+            timer: None,
+        }
+    }
+
+    /// Applies the timeout of the server from a response, which is in units of 100 milliseconds
+    fn update(&mut self, message: &Message) {
+        let timeout = message
+            .extensions()
+            .as_ref()
+            .and_then(|edns| edns.option(EdnsCode::Keepalive));
+
+        if let Some(&EdnsOption::TcpKeepalive(Some(timeout))) = timeout {
+            let idle_timeout = Duration::from_millis(u64::from(timeout) * 100);
+            if idle_timeout != self.idle_timeout {
+                debug!("server set the TCP idle timeout: {:?}", idle_timeout);
+                self.idle_timeout = idle_timeout;
+            }
+        }
+
+        self.timer = None;
+    }
+}
+
 /// A DNS Client implemented over futures-rs.
 ///
 /// This Client is generic and capable of wrapping UDP, TCP, and other underlying DNS protocol
 ///  implementations. This should be used for underlying protocols that do not natively support
 ///  multiplexed sessions.
+///
+/// Requests are pipelined, any number of requests may be outstanding on the stream at once. Each request is
+///  assigned an ID which is not used by any other outstanding request, responses are dispatched to the request by
+///  that ID. The ID is freed once the request is complete, or once the response stream of the request is dropped.
 #[must_use = "futures do nothing unless polled"]
 pub struct DnsMultiplexer<S, MF>
 where
@@ -97,6 +195,8 @@ where
     active_requests: HashMap<u16, ActiveRequest>,
     signer: Option<Arc<MF>>,
     is_shutdown: bool,
+    dso: Option<DsoSession>,
+    tcp_keepalive: Option<TcpKeepalive>,
 }
 
 impl<S, MF> DnsMultiplexer<S, MF>
@@ -148,6 +248,8 @@ where
             stream_handle: Some(stream_handle),
             timeout_duration,
             signer,
+            dso_keepalive: None,
+            tcp_keepalive: None,
         }
     }
 
@@ -196,6 +298,129 @@ where
         ))
     }
 
+    /// Sends a DSO keepalive request, which establishes the session or keeps it alive
+    fn send_dso_keepalive(&mut self) {
+        let id = self.next_random_query_id();
+        let dso = match self.dso {
+            Some(ref mut dso) => dso,
+            None => return,
+        };
+
+        // zero is the id of unidirectional messages, which are not answered
+        if let Ok(id @ 1..=0xFFFF) = id {
+            let request = DsoMessage::new_request(id, vec![DsoTlv::Keepalive(dso.keepalive)]);
+            match request.to_vec() {
+                Ok(buffer) => {
+                    let message = SerialMessage::new(buffer, self.stream.name_server_addr());
+                    match self.stream_handle.send(message) {
+                        Ok(()) => dso.request_id = Some(id),
+                        Err(e) => debug!("could not send DSO keepalive: {}", e),
+                    }
+                }
+                Err(e) => debug!("could not encode DSO keepalive: {}", e),
+            }
+        }
+
+        dso.reset_timer::<S::Time>();
+    }
+
+    /// Handles the DSO messages of the server, these are not responses to queries
+    fn handle_dso(&mut self, buffer: &SerialMessage) {
+        let message = match DsoMessage::from_vec(buffer.bytes()) {
+            Ok(message) => message,
+            Err(e) => {
+                debug!("error decoding DSO message: {}", e);
+                return;
+            }
+        };
+
+        let dso = match self.dso {
+            Some(ref mut dso) => dso,
+            None => {
+                debug!("unexpected DSO message without a session: {}", message.id());
+                return;
+            }
+        };
+
+        match message.header().message_type() {
+            MessageType::Response => {
+                if dso.request_id != Some(message.id()) {
+                    debug!("unexpected DSO response id: {}", message.id());
+                    return;
+                }
+                dso.request_id = None;
+
+                if message.response_code() != ResponseCode::NoError {
+                    debug!(
+                        "DSO is not supported by {}: {}",
+                        self.stream,
+                        message.response_code()
+                    );
+                    self.dso = None;
+                    return;
+                }
+
+                dso.update::<S::Time>(&message);
+            }
+            MessageType::Query if message.is_unidirectional() => match message.primary_tlv() {
+                Some(DsoTlv::Keepalive(..)) => dso.update::<S::Time>(&message),
+                Some(DsoTlv::RetryDelay(retry_delay)) => {
+                    // the server asks to close the session, once the outstanding requests are complete
+                    debug!(
+                        "DSO session closed by {}, retry delay: {:?}",
+                        self.stream, retry_delay
+                    );
+                    self.is_shutdown = true;
+                }
+                tlv => debug!("ignoring unidirectional DSO message: {:?}", tlv),
+            },
+            MessageType::Query => {
+                // no requests of the server are supported
+                let response = DsoMessage::new_response(&message, ResponseCode::DSOTYPENI, vec![]);
+                let sent = response.to_vec().and_then(|buffer| {
+                    let message = SerialMessage::new(buffer, self.stream.name_server_addr());
+                    self.stream_handle.send(message)
+                });
+                if let Err(e) = sent {
+                    debug!("could not send DSO response: {}", e);
+                }
+            }
+        }
+    }
+
+    /// Sends a keepalive whenever the connection was idle for the keepalive interval
+    fn poll_dso_keepalive(&mut self, cx: &mut Context<'_>) {
+        while let Some(timer) = self.dso.as_mut().and_then(|dso| dso.timer.as_mut()) {
+            if timer.poll_unpin(cx).is_pending() {
+                break;
+            }
+
+            self.send_dso_keepalive();
+        }
+    }
+
+    /// Returns true once the connection was idle for the timeout of the edns-tcp-keepalive option
+    ///
+    /// The timer is restarted by every message, and only expires while no requests are outstanding.
+    fn poll_tcp_keepalive(&mut self, cx: &mut Context<'_>) -> bool {
+        let keepalive = match self.tcp_keepalive {
+            Some(ref mut keepalive) => keepalive,
+            None => return false,
+        };
+
+        let idle_timeout = keepalive.idle_timeout;
+        let timer = keepalive
+            .timer
+            .get_or_insert_with(|| S::Time::delay_for(idle_timeout));
+        if timer.poll_unpin(cx).is_pending() {
+            return false;
+        }
+
+        // the outstanding requests complete or time out first, which restarts the timer
+        keepalive.timer = None;
+        self.active_requests.is_empty()
+    }
+
     /// Closes all outstanding completes with a closed stream error
     fn stream_closed_close_all(&mut self, error: ProtoError) {
         if !self.active_requests.is_empty() {
@@ -223,6 +448,35 @@ where
     stream_handle: Option<BufDnsStreamHandle>,
     timeout_duration: Duration,
     signer: Option<Arc<MF>>,
+    dso_keepalive: Option<Keepalive>,
+    tcp_keepalive: Option<Duration>,
+}
+
+impl<F, S, MF> DnsMultiplexerConnect<F, S, MF>
+where
+    F: Future<Output = Result<S, ProtoError>> + Send + Unpin + 'static,
+    S: Stream<Item = Result<SerialMessage, ProtoError>> + Unpin,
+    MF: MessageFinalizer + Send + Sync + 'static,
+{
+    /// Establishes a DSO session, [RFC 8490](https://tools.ietf.org/html/rfc8490), with the Keepalive TLV
+    ///
+    /// The timeouts are requested from the server once connected. If the server supports DSO, a keepalive is sent
+    ///  whenever no message was sent for the keepalive interval of the server, which keeps the connection open
+    ///  past the idle timeout of the server. This is only meaningful for TCP and TLS connections.
+    pub fn with_dso_keepalive(mut self, keepalive: Keepalive) -> Self {
+        self.dso_keepalive = Some(keepalive);
+        self
+    }
+
+    /// Advertises the edns-tcp-keepalive option, [RFC 7828](https://tools.ietf.org/html/rfc7828), in all queries
+    ///
+    /// The connection is closed once no message was sent or received for the idle timeout, while no requests are
+    ///  outstanding. A server replying with the option sets its own timeout, which replaces `idle_timeout`. This is
+    ///  only meaningful for TCP and TLS connections.
+    pub fn with_tcp_keepalive(mut self, idle_timeout: Duration) -> Self {
+        self.tcp_keepalive = Some(idle_timeout);
+        self
+    }
 }
 
 impl<F, S, MF> Future for DnsMultiplexerConnect<F, S, MF>
@@ -236,7 +490,7 @@ where
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let stream: S = ready!(self.stream.poll_unpin(cx))?;
 
-        Poll::Ready(Ok(DnsMultiplexer {
+        let mut multiplexer = DnsMultiplexer {
             stream,
             timeout_duration: self.timeout_duration,
             stream_handle: self
@@ -246,7 +500,12 @@ where
             active_requests: HashMap::new(),
             signer: self.signer.clone(),
             is_shutdown: false,
-        }))
+            dso: self.dso_keepalive.map(DsoSession::new),
+            tcp_keepalive: self.tcp_keepalive.map(TcpKeepalive::new),
+        };
+        multiplexer.send_dso_keepalive();
+
+        Poll::Ready(Ok(multiplexer))
     }
 }
 
@@ -265,12 +524,12 @@ where
     S: DnsClientStream + Unpin + 'static,
     MF: MessageFinalizer + Send + Sync + 'static,
 {
-    fn send_message(&mut self, request: DnsRequest) -> DnsResponseStream {
+    fn send_message(&mut self, mut request: DnsRequest) -> DnsResponseStream {
         if self.is_shutdown {
             panic!("can not send messages after stream is shutdown")
         }
 
-        if self.active_requests.len() > CHANNEL_BUFFER_SIZE {
+        if self.active_requests.len() >= MAX_ACTIVE_REQUESTS {
             return ProtoError::from(ProtoErrorKind::Busy).into();
         }
 
@@ -279,9 +538,22 @@ where
             Err(e) => return e.into(),
         };
 
-        let (mut request, _) = request.into_parts();
         request.set_id(query_id);
 
+        // raw requests are sent as is, without options or a signature
+        let is_raw = request.is_raw();
+
+        if let Some(ref mut keepalive) = self.tcp_keepalive {
+            if !is_raw {
+                request
+                    .extensions_mut()
+                    .get_or_insert_with(Edns::new)
+                    .options_mut()
+                    .insert(EdnsOption::TcpKeepalive(None));
+            }
+            keepalive.timer = None;
+        }
+
         let now = match SystemTime::now().duration_since(UNIX_EPOCH) {
             Ok(now) => now.as_secs(),
             Err(_) => return ProtoError::from("Current time is before the Unix epoch.").into(),
@@ -292,7 +564,7 @@ where
 
         let mut verifier = None;
         if let Some(ref signer) = self.signer {
-            if signer.should_finalize_message(&request) {
+            if !is_raw && signer.should_finalize_message(&request) {
                 match request.finalize::<MF>(signer.borrow(), now) {
                     Ok(answer_verifier) => verifier = answer_verifier,
                     Err(e) => {
@@ -310,9 +582,9 @@ where
 
         // send the message
         let active_request =
-            ActiveRequest::new(complete, request.id(), Box::new(timeout), verifier);
+            ActiveRequest::new(complete, request.id(), Box::new(timeout), verifier, is_raw);
 
-        match request.to_vec() {
+        match request.to_wire() {
             Ok(buffer) => {
                 debug!("sending message id: {}", active_request.request_id());
                 let serial_message = SerialMessage::new(buffer, self.stream.name_server_addr());
@@ -332,6 +604,10 @@ where
                         .insert(active_request.request_id(), active_request),
                     Err(err) => return err.into(),
                 };
+
+                if let Some(ref mut dso) = self.dso {
+                    dso.reset_timer::<S::Time>();
+                }
             }
             Err(e) => {
                 debug!(
@@ -381,8 +657,19 @@ where
                 Poll::Ready(Some(Ok(buffer))) => {
                     messages_received = i;
 
+                    // DSO messages are about the session, and never the response to a query
+                    if DsoMessage::is_dso(buffer.bytes()) {
+                        self.handle_dso(&buffer);
+                        continue;
+                    }
+
                     //   deserialize or log decode_error
-                    match buffer.to_message() {
+                    let message = buffer.to_message();
+                    if let (Some(keepalive), Ok(message)) = (&mut self.tcp_keepalive, &message) {
+                        keepalive.update(message);
+                    }
+
+                    match message {
                         Ok(message) => match self.active_requests.entry(message.id()) {
                             Entry::Occupied(mut request_entry) => {
                                 // send the response, complete the request...
@@ -394,9 +681,11 @@ where
                                             .try_send(verifier(buffer.bytes())),
                                     );
                                 } else {
-                                    ignore_send(
-                                        active_request.completion.try_send(Ok(message.into())),
-                                    );
+                                    let mut response = DnsResponse::from(message);
+                                    if active_request.keep_buffer {
+                                        response = response.with_buffer(buffer.into_parts().0);
+                                    }
+                                    ignore_send(active_request.completion.try_send(Ok(response)));
                                 }
                             }
                             Entry::Vacant(..) => debug!("unexpected request_id: {}", message.id()),
@@ -428,8 +717,25 @@ where
             cx.waker().wake_by_ref();
         }
 
-        // Finally, return not ready to keep the 'driver task' alive.
-        Poll::Pending
+        self.poll_dso_keepalive(cx);
+
+        if self.poll_tcp_keepalive(cx) {
+            debug!("closing idle connection: {}", self);
+            self.is_shutdown = true;
+            return Poll::Ready(None);
+        }
+
+        // a shutdown stream only waits for the outstanding requests to complete
+        if self.is_shutdown {
+            return Poll::Pending;
+        }
+
+        // Finally, the stream is ready to send another message once there is room in the buffer of the stream handle
+        match self.stream_handle.poll_ready(cx) {
+            Poll::Ready(Ok(())) => Poll::Ready(Some(Ok(()))),
+            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e))),
+            Poll::Pending => Poll::Pending,
+        }
     }
 }
 
@@ -529,6 +835,16 @@ mod test {
         multiplexer
     }
 
+    /// Polls the multiplexer until it is done, being ready to send another message does not end it
+    fn drive(
+        multiplexer: &mut DnsMultiplexer<MockClientStream, NoopMessageFinalizer>,
+    ) -> impl Future<Output = ()> + '_ {
+        future::poll_fn(move |cx| match multiplexer.poll_next_unpin(cx) {
+            Poll::Ready(None) => Poll::Ready(()),
+            _ => Poll::Pending,
+        })
+    }
+
     fn a_query_answer() -> (DnsRequest, Vec<Message>) {
         let name = Name::from_ascii("www.example.com").unwrap();
 
@@ -668,7 +984,7 @@ mod test {
         let mut multiplexer = get_mocked_multiplexer(answer).await;
         let response = multiplexer.send_message(query);
         let response = tokio::select! {
-            _ = multiplexer.next() => {
+            _ = drive(&mut multiplexer) => {
                 // polling multiplexer to make it run
                 panic!("should never end")
             },
@@ -683,7 +999,7 @@ mod test {
         let mut multiplexer = get_mocked_multiplexer(answer).await;
         let response = multiplexer.send_message(query);
         let response = tokio::select! {
-            _ = multiplexer.next() => {
+            _ = drive(&mut multiplexer) => {
                 // polling multiplexer to make it run
                 panic!("should never end")
             },
@@ -699,7 +1015,7 @@ mod test {
         let mut multiplexer = get_mocked_multiplexer(answer).await;
         let response = multiplexer.send_message(query);
         let response = tokio::select! {
-            _ = multiplexer.next() => {
+            _ = drive(&mut multiplexer) => {
                 // polling multiplexer to make it run
                 panic!("should never end")
             },
@@ -711,4 +1027,25 @@ mod test {
             axfr_response().len()
         );
     }
+
+    #[tokio::test]
+    async fn test_multiplexer_dropped_request_frees_id() {
+        let mut multiplexer = get_mocked_multiplexer(vec![]).await;
+        let first = multiplexer.send_message(a_query_answer().0);
+        let second = multiplexer.send_message(a_query_answer().0);
+
+        // each outstanding request has its own ID
+        assert_eq!(multiplexer.active_requests.len(), 2);
+
+        // the ID of a dropped request is freed the next time the multiplexer is polled
+        drop(first);
+        future::poll_fn(|cx| {
+            let _ = multiplexer.poll_next_unpin(cx);
+            Poll::Ready(())
+        })
+        .await;
+        assert_eq!(multiplexer.active_requests.len(), 1);
+
+        drop(second);
+    }
 }
@@ -9,10 +9,13 @@
 
 use std::ops::{Deref, DerefMut};
 
-use crate::op::Message;
+use crate::error::ProtoResult;
+use crate::op::{Message, PaddingPolicy};
+use crate::rr::rdata::opt::ClientSubnet;
+use crate::xfer::dns_handle::MAX_PAYLOAD_LEN;
 
 /// A set of options for expressing options to how requests should be treated
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[non_exhaustive]
 pub struct DnsRequestOptions {
     /// When true, the underlying DNS protocols will not return on the first response received.
@@ -25,6 +28,38 @@ pub struct DnsRequestOptions {
     // TODO: add EDNS options here?
     /// When true, will add EDNS options to the request.
     pub use_edns: bool,
+    /// When set, the EDNS Client Subnet option will be added to the request, this implies `use_edns`.
+    pub client_subnet: Option<ClientSubnet>,
+    /// How the request is padded with the EDNS Padding option, padding implies `use_edns`.
+    pub padding: PaddingPolicy,
+    /// When true, the DNSSEC OK (DO) bit will be set in the EDNS of the request, this implies `use_edns`.
+    pub dnssec_ok: bool,
+    /// When true, the Recursion Desired (RD) bit will be set in the request, the default
+    pub recursion_desired: bool,
+    /// When true, the Checking Disabled (CD) bit will be set in the request
+    ///
+    /// The name server is asked to return the data without validating it, which may be bogus. A handle
+    ///  validating DNSSEC itself passes such requests through without validating the response either.
+    pub checking_disabled: bool,
+    /// The UDP payload size advertised in the EDNS of the request, 1232 by default, which avoids fragmentation on
+    ///  nearly all networks per [DNS Flag Day 2020](https://dnsflagday.net/2020/)
+    pub max_payload_len: u16,
+}
+
+impl Default for DnsRequestOptions {
+    #[allow(deprecated)]
+    fn default() -> Self {
+        Self {
+            expects_multiple_responses: false,
+            use_edns: false,
+            client_subnet: None,
+            padding: PaddingPolicy::None,
+            dnssec_ok: false,
+            recursion_desired: true,
+            checking_disabled: false,
+            max_payload_len: MAX_PAYLOAD_LEN,
+        }
+    }
 }
 
 /// A DNS request object
@@ -34,12 +69,49 @@ pub struct DnsRequestOptions {
 pub struct DnsRequest {
     message: Message,
     options: DnsRequestOptions,
+    raw: Option<Vec<u8>>,
 }
 
 impl DnsRequest {
     /// Returns a new DnsRequest object
     pub fn new(message: Message, options: DnsRequestOptions) -> Self {
-        Self { message, options }
+        Self {
+            message,
+            options,
+            raw: None,
+        }
+    }
+
+    /// Returns a new DnsRequest which is sent as the serialized message, instead of encoding the `Message`
+    ///
+    /// Only the id is replaced by the transport, the message is neither signed nor are any EDNS options added.
+    ///  The bytes of the response are kept for such requests, see `DnsResponse::as_buffer`.
+    pub fn from_bytes(raw: Vec<u8>, options: DnsRequestOptions) -> ProtoResult<Self> {
+        let message = Message::from_vec(&raw)?;
+
+        Ok(Self {
+            message,
+            options,
+            raw: Some(raw),
+        })
+    }
+
+    /// Returns true if the request is sent as serialized, see `from_bytes`
+    pub fn is_raw(&self) -> bool {
+        self.raw.is_some()
+    }
+
+    /// The bytes to send, the serialized message with the current id for raw requests, otherwise the encoded `Message`
+    pub(crate) fn to_wire(&self) -> ProtoResult<Vec<u8>> {
+        match self.raw {
+            Some(ref raw) => {
+                // the message was decoded, the header of at least 12 bytes is present
+                let mut raw = raw.clone();
+                raw[..2].copy_from_slice(&self.message.id().to_be_bytes());
+                Ok(raw)
+            }
+            None => self.message.to_vec(),
+        }
     }
 
     /// Get the set of request options associated with this request
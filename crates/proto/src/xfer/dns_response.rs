@@ -9,6 +9,7 @@
 
 use std::future::Future;
 use std::io;
+use std::net::SocketAddr;
 use std::ops::{Deref, DerefMut};
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -122,16 +123,38 @@ type TimeoutFuture = Pin<
     Box<dyn Future<Output = Result<Result<DnsResponse, ProtoError>, io::Error>> + Send + 'static>,
 >;
 
-// TODO: this needs to have the IP addr of the remote system...
 // TODO: see https://github.com/bluejekyll/trust-dns/issues/383 for removing vec of messages and instead returning a Stream
 /// A DNS response object
 ///
 /// For Most DNS requests, only one response is expected, the exception is a multicast request.
 #[derive(Clone, Debug)]
-pub struct DnsResponse(Message);
+pub struct DnsResponse {
+    message: Message,
+    buffer: Option<Vec<u8>>,
+    dnssec_status: DnssecStatus,
+    server_addr: Option<SocketAddr>,
+}
 
 // TODO: when `impl Trait` lands in stable, remove this, and expose FlatMap over answers, et al.
 impl DnsResponse {
+    /// Keep the bytes the message was decoded from, only done for raw requests, see `DnsRequest::from_bytes`
+    pub(crate) fn with_buffer(mut self, buffer: Vec<u8>) -> Self {
+        self.buffer = Some(buffer);
+        self
+    }
+
+    /// The bytes of the response as they were received, if the request was sent raw and the transport keeps them
+    ///
+    /// These are not updated by changes to the message, e.g. through `DerefMut`.
+    pub fn as_buffer(&self) -> Option<&[u8]> {
+        self.buffer.as_deref()
+    }
+
+    /// Takes the bytes of the response as they were received, see `as_buffer`
+    pub fn take_buffer(&mut self) -> Option<Vec<u8>> {
+        self.buffer.take()
+    }
+
     /// Retrieves the SOA from the response. This will only exist if it was an authoritative response.
     pub fn soa(&self) -> Option<SOA> {
         self.name_servers()
@@ -196,9 +219,14 @@ impl DnsResponse {
     ///   and would make sensible a default.  Values exceeding one day have
     ///   been found to be problematic.
     /// ```
+    ///
+    /// The NSEC and NSEC3 records proving the negative answer must not be cached for longer than the
+    ///  SOA either, [RFC 9077](https://tools.ietf.org/html/rfc9077#section-3), so the TTL is also capped
+    ///  by theirs.
     pub fn negative_ttl(&self) -> Option<u32> {
         // TODO: should this ensure that the SOA zone matches the Queried Zone?
-        self.name_servers()
+        let soa_ttl = self
+            .name_servers()
             .iter()
             .filter_map(|record| {
                 record
@@ -207,7 +235,15 @@ impl DnsResponse {
                     .map(|soa| (record.ttl(), soa))
             })
             .next()
-            .map(|(ttl, soa)| (ttl as u32).min(soa.minimum()))
+            .map(|(ttl, soa)| ttl.min(soa.minimum()))?;
+
+        Some(
+            self.name_servers()
+                .iter()
+                .filter(|record| matches!(record.rr_type(), RecordType::NSEC | RecordType::NSEC3))
+                .map(|record| record.ttl())
+                .fold(soa_ttl, u32::min),
+        )
     }
 
     /// Does the response contain any records matching the query name and type?
@@ -283,9 +319,31 @@ impl DnsResponse {
         }
     }
 
+    /// The outcome of DNSSEC validation of this response, see [`DnssecStatus`]
+    pub fn dnssec_status(&self) -> DnssecStatus {
+        self.dnssec_status
+    }
+
+    /// Set the outcome of DNSSEC validation of this response, this is done by the `DnssecDnsHandle`
+    pub fn set_dnssec_status(&mut self, dnssec_status: DnssecStatus) -> &mut Self {
+        self.dnssec_status = dnssec_status;
+        self
+    }
+
+    /// The address of the name server which sent this response, if known
+    pub fn server_addr(&self) -> Option<SocketAddr> {
+        self.server_addr
+    }
+
+    /// Set the address of the name server which sent this response
+    pub fn set_server_addr(&mut self, server_addr: Option<SocketAddr>) -> &mut Self {
+        self.server_addr = server_addr;
+        self
+    }
+
     /// Take the inner Message from the response
     pub fn into_inner(self) -> Message {
-        self.0
+        self.message
     }
 }
 
@@ -293,25 +351,65 @@ impl Deref for DnsResponse {
     type Target = Message;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.message
     }
 }
 
 impl DerefMut for DnsResponse {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.message
     }
 }
 
 impl From<DnsResponse> for Message {
     fn from(response: DnsResponse) -> Self {
-        response.0
+        response.message
     }
 }
 
 impl From<Message> for DnsResponse {
     fn from(message: Message) -> Self {
-        Self(message)
+        Self {
+            message,
+            buffer: None,
+            dnssec_status: DnssecStatus::default(),
+            server_addr: None,
+        }
+    }
+}
+
+/// The security status of a response, as defined in [RFC 4033](https://tools.ietf.org/html/rfc4033#section-5)
+///
+/// The variants are ordered from least to most trustworthy, such that the status of data gathered from multiple
+///  responses, e.g. a CNAME chain, is the minimum of the status of each response.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DnssecStatus {
+    /// The response failed validation, e.g. signatures were missing, expired or did not verify
+    Bogus,
+    /// The response was not validated, no trust anchor was consulted to determine if it should be signed (default)
+    Indeterminate,
+    /// The response was proven to come from an unsigned zone
+    Insecure,
+    /// The response was validated back to a trust anchor
+    Secure,
+}
+
+impl DnssecStatus {
+    /// Returns true if the data was validated back to a trust anchor
+    pub fn is_secure(self) -> bool {
+        self == Self::Secure
+    }
+
+    /// Returns true if validation of the data failed
+    pub fn is_bogus(self) -> bool {
+        self == Self::Bogus
+    }
+}
+
+impl Default for DnssecStatus {
+    /// Returns [`DnssecStatus::Indeterminate`] as the default.
+    fn default() -> Self {
+        Self::Indeterminate
     }
 }
 
@@ -720,6 +818,30 @@ mod tests {
         assert!(response.contains_answer())
     }
 
+    #[test]
+    fn test_negative_ttl() {
+        let mut message = Message::default();
+        message.set_response_code(ResponseCode::NXDomain);
+        message.add_query(an_query());
+        message.add_name_server(soa());
+        assert_eq!(DnsResponse::from(message.clone()).negative_ttl(), Some(5));
+
+        // the NSEC records proving the name error have a lower TTL than the SOA
+        let mut nsec = Record::with(example(), RecordType::NSEC, 3);
+        nsec.set_data(Some(RData::Unknown {
+            code: u16::from(RecordType::NSEC),
+            rdata: crate::rr::rdata::NULL::new(),
+        }));
+        message.add_name_server(nsec);
+        assert_eq!(DnsResponse::from(message).negative_ttl(), Some(3));
+
+        // without an SOA the response is not cacheable
+        let mut message = Message::default();
+        message.set_response_code(ResponseCode::NXDomain);
+        message.add_query(an_query());
+        assert_eq!(DnsResponse::from(message).negative_ttl(), None);
+    }
+
     #[test]
     fn test_nx_type1() {
         let mut message = Message::default();
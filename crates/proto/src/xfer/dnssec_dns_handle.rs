@@ -11,15 +11,17 @@ use std::clone::Clone;
 use std::collections::HashSet;
 use std::error::Error;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use futures_util::future;
 use futures_util::future::{Future, FutureExt, TryFutureExt};
 use futures_util::stream;
 use futures_util::stream::{Stream, TryStreamExt};
 use log::{debug, trace};
+use lru_cache::LruCache;
 
-use crate::op::{OpCode, Query};
+use crate::op::{OpCode, Query, ResponseCode};
 use crate::rr::dnssec::rdata::{DNSSECRData, DNSKEY, SIG};
 #[cfg(feature = "dnssec")]
 use crate::rr::dnssec::Verifier;
@@ -27,7 +29,7 @@ use crate::rr::dnssec::{Algorithm, SupportedAlgorithms, TrustAnchor};
 use crate::rr::rdata::opt::EdnsOption;
 use crate::rr::{DNSClass, Name, RData, Record, RecordType};
 use crate::xfer::dns_handle::DnsHandle;
-use crate::xfer::{DnsRequest, DnsRequestOptions, DnsResponse, FirstAnswer};
+use crate::xfer::{DnsRequest, DnsRequestOptions, DnsResponse, DnssecStatus, FirstAnswer};
 use crate::{error::*, op::Edns};
 
 #[derive(Debug)]
@@ -38,6 +40,15 @@ struct Rrset {
     pub(crate) records: Vec<Record>,
 }
 
+/// The validated DNSKEYs of each zone, along with when they expire
+type DnskeyCache = LruCache<Name, (Instant, Arc<[Record]>)>;
+
+/// The number of zones for which the validated DNSKEYs are cached
+const DNSKEY_CACHE_SIZE: usize = 64;
+
+/// The longest time for which validated DNSKEYs are cached, regardless of their TTL
+const MAX_DNSKEY_CACHE_TTL: Duration = Duration::from_secs(86400);
+
 /// Performs DNSSec validation of all DNS responses from the wrapped DnsHandle
 ///
 /// This wraps a DnsHandle, changing the implementation `send()` to validate all
@@ -51,9 +62,11 @@ where
 {
     handle: H,
     trust_anchor: Arc<TrustAnchor>,
+    dnskey_cache: Arc<Mutex<DnskeyCache>>,
     request_depth: usize,
     minimum_key_len: usize,
     minimum_algorithm: Algorithm, // used to prevent down grade attacks...
+    retain_dnssec_records: bool,
 }
 
 impl<H> DnssecDnsHandle<H>
@@ -77,16 +90,29 @@ where
     /// # Arguments
     /// * `handle` - handle to use for all connections to a remote server.
     /// * `trust_anchor` - custom DNSKEYs that will be trusted, can be used to pin trusted keys.
-    pub fn with_trust_anchor(handle: H, trust_anchor: TrustAnchor) -> Self {
+    pub fn with_trust_anchor(handle: H, trust_anchor: impl Into<Arc<TrustAnchor>>) -> Self {
         Self {
             handle,
-            trust_anchor: Arc::new(trust_anchor),
+            trust_anchor: trust_anchor.into(),
+            dnskey_cache: Arc::new(Mutex::new(LruCache::new(DNSKEY_CACHE_SIZE))),
             request_depth: 0,
             minimum_key_len: 0,
             minimum_algorithm: Algorithm::RSASHA256,
+            retain_dnssec_records: false,
         }
     }
 
+    /// Retain the DNSSEC records of validated responses
+    ///
+    /// By default the RRSIGs are stripped from responses once they have been used for validation. When set, the
+    ///  RRSIGs covering the validated RRSETs are kept in the response. Additionally, responses which fail
+    ///  validation are returned as a [`ProtoErrorKind::Bogus`] error carrying the response with all of its
+    ///  records, for inspection.
+    pub fn retain_dnssec_records(mut self, retain_dnssec_records: bool) -> Self {
+        self.retain_dnssec_records = retain_dnssec_records;
+        self
+    }
+
     /// An internal function used to clone the handle, but maintain some information back to the
     ///  original handle, such as the request_depth such that infinite recursion does
     ///  not occur.
@@ -94,9 +120,12 @@ where
         Self {
             handle: self.handle.clone(),
             trust_anchor: Arc::clone(&self.trust_anchor),
+            dnskey_cache: Arc::clone(&self.dnskey_cache),
             request_depth: self.request_depth + 1,
             minimum_key_len: self.minimum_key_len,
             minimum_algorithm: self.minimum_algorithm,
+            // the chain of trust must always be proven, only the outermost response retains records
+            retain_dnssec_records: false,
         }
     }
 }
@@ -133,6 +162,7 @@ where
                 .cloned()
                 .expect("no queries in request");
             let handle: Self = self.clone_with_context();
+            let retain_dnssec_records = self.retain_dnssec_records;
 
             // TODO: cache response of the server about understood algorithms
             #[cfg(feature = "dnssec")]
@@ -146,6 +176,10 @@ where
                 {
                     algorithms.set(Algorithm::ED25519);
                 }
+                #[cfg(feature = "openssl")]
+                {
+                    algorithms.set(Algorithm::ED448);
+                }
                 algorithms.set(Algorithm::ECDSAP256SHA256);
                 algorithms.set(Algorithm::ECDSAP384SHA384);
                 algorithms.set(Algorithm::RSASHA256);
@@ -157,6 +191,12 @@ where
                 edns.options_mut().insert(dhu);
             }
 
+            // the data is requested as is, it is neither validated upstream nor here
+            if request.options().checking_disabled {
+                request.set_checking_disabled(true);
+                return Box::pin(self.handle.send(request));
+            }
+
             request.set_authentic_data(true);
             request.set_checking_disabled(false);
             let dns_class = request
@@ -175,12 +215,16 @@ where
                             message_response.id(),
                             handle.trust_anchor.len(),
                         );
-                        verify_rrsets(handle.clone(), message_response, dns_class)
+                        verify_rrsets(
+                            handle.clone(),
+                            message_response,
+                            dns_class,
+                            retain_dnssec_records,
+                        )
                     })
-                    .and_then(move |verified_message| {
+                    .and_then(move |mut verified_message| {
                         // at this point all of the message is verified.
-                        //  This is where NSEC (and possibly NSEC3) validation occurs
-                        // As of now, only NSEC is supported.
+                        //  This is where NSEC and NSEC3 validation occurs
                         if verified_message.answers().is_empty() {
                             // get SOA name
                             let soa_name = if let Some(soa_name) = verified_message
@@ -203,11 +247,42 @@ where
                                 .filter(|rr| is_dnssec(rr, RecordType::NSEC))
                                 .collect::<Vec<_>>();
 
-                            if !verify_nsec(&query, soa_name, nsecs.as_slice()) {
+                            let nsec3s = verified_message
+                                .name_servers()
+                                .iter()
+                                .filter(|rr| is_dnssec(rr, RecordType::NSEC3))
+                                .collect::<Vec<_>>();
+
+                            let dnssec_status = if nsecs.is_empty() && !nsec3s.is_empty() {
+                                match verify_nsec3(
+                                    &query,
+                                    soa_name,
+                                    verified_message.response_code(),
+                                    nsec3s.as_slice(),
+                                ) {
+                                    Ok(dnssec_status) => dnssec_status,
+                                    Err(e) => {
+                                        debug!(
+                                            "could not validate negative response with NSEC3: {}",
+                                            e
+                                        );
+                                        return future::err(Self::Error::from(ProtoError::from(
+                                            "could not validate negative response with NSEC3",
+                                        )));
+                                    }
+                                }
+                            } else if verify_nsec(&query, soa_name, nsecs.as_slice()) {
+                                DnssecStatus::Secure
+                            } else {
                                 // TODO change this to remove the NSECs, like we do for the others?
                                 return future::err(Self::Error::from(ProtoError::from(
                                     "could not validate negative response with NSEC",
                                 )));
+                            };
+
+                            // an opt-out proof can only lower the status of the response
+                            if dnssec_status < verified_message.dnssec_status() {
+                                verified_message.set_dnssec_status(dnssec_status);
                             }
                         }
 
@@ -227,6 +302,7 @@ async fn verify_rrsets<H, E>(
     handle: DnssecDnsHandle<H>,
     message_result: DnsResponse,
     dns_class: DNSClass,
+    retain_dnssec_records: bool,
 ) -> Result<DnsResponse, E>
 where
     H: DnsHandle<Error = E> + Sync + Unpin,
@@ -316,16 +392,27 @@ where
     }
 
     // spawn a select_all over this vec, these are the individual RRSet validators
-    verify_all_rrsets(message_result, rrsets_to_verify).await
+    verify_all_rrsets(message_result, rrsets_to_verify, retain_dnssec_records).await
 }
 
 fn is_dnssec(rr: &Record, dnssec_type: RecordType) -> bool {
     rr.rr_type().is_dnssec() && dnssec_type.is_dnssec() && rr.record_type() == dnssec_type
 }
 
+/// Returns true if the record is an RRSIG covering one of the `rrsets`
+fn is_covering_rrsig(rr: &Record, rrsets: &HashSet<(Name, RecordType)>) -> bool {
+    if let Some(RData::DNSSEC(DNSSECRData::SIG(ref rrsig))) = rr.data() {
+        is_dnssec(rr, RecordType::RRSIG)
+            && rrsets.contains(&(rr.name().clone(), rrsig.type_covered()))
+    } else {
+        false
+    }
+}
+
 async fn verify_all_rrsets<F, E>(
     message_result: DnsResponse,
     rrsets: Vec<F>,
+    retain_dnssec_records: bool,
 ) -> Result<DnsResponse, E>
 where
     F: Future<Output = Result<Rrset, E>> + Send + Unpin,
@@ -374,13 +461,28 @@ where
         }
     }
 
+    let mut message_result = message_result;
+
+    // when retaining records, the failed message is attached to the error for inspection
+    if retain_dnssec_records {
+        if let Some(e) = last_validation_err {
+            message_result.set_dnssec_status(DnssecStatus::Bogus);
+            return Err(E::from(ProtoError::from(ProtoErrorKind::Bogus {
+                error: e.to_string(),
+                response: Box::new(message_result),
+            })));
+        }
+    }
+
     // check if any are valid, otherwise return whatever error caused it to fail
     if verified_rrsets.is_empty() && last_validation_err.is_some() {
         return Err(last_validation_err.expect("can not be none based on above check"));
     }
 
-    // validated not none above...
-    let mut message_result = message_result;
+    let is_verified = |record: &Record| {
+        verified_rrsets.contains(&(record.name().clone(), record.rr_type()))
+            || (retain_dnssec_records && is_covering_rrsig(record, &verified_rrsets))
+    };
 
     // take all the rrsets from the Message, filter down each set to the validated rrsets
     // TODO: does the section in the message matter here?
@@ -390,25 +492,26 @@ where
         .take_answers()
         .into_iter()
         .chain(message_result.take_additionals().into_iter())
-        .filter(|record| verified_rrsets.contains(&(record.name().clone(), record.rr_type())))
+        .filter(|record| is_verified(record))
         .collect::<Vec<Record>>();
 
     let name_servers = message_result
         .take_name_servers()
         .into_iter()
-        .filter(|record| verified_rrsets.contains(&(record.name().clone(), record.rr_type())))
+        .filter(|record| is_verified(record))
         .collect::<Vec<Record>>();
 
     let additionals = message_result
         .take_additionals()
         .into_iter()
-        .filter(|record| verified_rrsets.contains(&(record.name().clone(), record.rr_type())))
+        .filter(|record| is_verified(record))
         .collect::<Vec<Record>>();
 
     // add the filtered records back to the message
     message_result.insert_answers(answers);
     message_result.insert_name_servers(name_servers);
     message_result.insert_additionals(additionals);
+    message_result.set_dnssec_status(DnssecStatus::Secure);
 
     // breaks out of the loop... and returns the filtered Message.
     Ok(message_result)
@@ -629,6 +732,413 @@ fn test_preserve() {
     assert_eq!(vec, vec![1, 2, 3]);
 }
 
+#[cfg(feature = "dnssec-ring")]
+#[test]
+fn test_verify_rrset_with_colliding_key_tags() {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use crate::rr::dnssec::tbs;
+
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let public_key = key_pair.public_key().as_ref().to_vec();
+
+    // swapping bytes two apart keeps the checksum of the key tag, but changes the key
+    let mut other_key = public_key.clone();
+    let i = (0..other_key.len() - 2)
+        .find(|&i| other_key[i] != other_key[i + 2])
+        .unwrap();
+    other_key.swap(i, i + 2);
+
+    let dnskey = DNSKEY::new(true, true, false, Algorithm::ED25519, public_key);
+    let other = DNSKEY::new(true, true, false, Algorithm::ED25519, other_key);
+    let key_tag = dnskey.calculate_key_tag().unwrap();
+    assert_eq!(other.calculate_key_tag().unwrap(), key_tag);
+
+    let origin = Name::from_str("example.com.").unwrap();
+    let name = Name::from_str("www.example.com.").unwrap();
+    let rrset = Rrset {
+        name: name.clone(),
+        record_type: RecordType::A,
+        record_class: DNSClass::IN,
+        records: vec![Record::from_rdata(
+            name,
+            86400,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        )],
+    };
+
+    let sig = |signature| {
+        SIG::new(
+            RecordType::A,
+            Algorithm::ED25519,
+            3,
+            86400,
+            5,
+            0,
+            key_tag,
+            origin.clone(),
+            signature,
+        )
+    };
+    let tbs =
+        tbs::rrset_tbs_with_sig(&rrset.name, DNSClass::IN, &sig(vec![]), &rrset.records).unwrap();
+    let sig = sig(key_pair.sign(tbs.as_ref()).as_ref().to_vec());
+
+    let dnskey = Record::from_rdata(
+        origin.clone(),
+        86400,
+        RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
+    );
+    let other = Record::from_rdata(origin, 86400, RData::DNSSEC(DNSSECRData::DNSKEY(other)));
+
+    // only the second of the keys with the tag verifies the RRSIG
+    assert!(verify_rrset_with_dnskeys([other.clone(), dnskey].iter(), &sig, &rrset).is_ok());
+    assert!(verify_rrset_with_dnskeys([other].iter(), &sig, &rrset).is_err());
+    assert!(verify_rrset_with_dnskeys(std::iter::empty(), &sig, &rrset).is_err());
+}
+
+#[test]
+fn test_verify_all_rrsets_retain_dnssec_records() {
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+
+    use futures_executor::block_on;
+
+    use crate::op::Message;
+
+    let name = Name::from_str("www.example.com.").unwrap();
+    let a = Record::from_rdata(name.clone(), 86400, RData::A(Ipv4Addr::new(127, 0, 0, 1)));
+    let mut rrsig = Record::from_rdata(
+        name.clone(),
+        86400,
+        RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+            RecordType::A,
+            Algorithm::ED25519,
+            3,
+            86400,
+            5,
+            0,
+            2,
+            Name::from_str("example.com.").unwrap(),
+            vec![],
+        ))),
+    );
+    rrsig.set_rr_type(RecordType::RRSIG);
+
+    let mut message = Message::new();
+    message.insert_answers(vec![a.clone(), rrsig.clone()]);
+    let message = DnsResponse::from(message);
+
+    let verified = || {
+        future::ok::<_, ProtoError>(Rrset {
+            name: name.clone(),
+            record_type: RecordType::A,
+            record_class: DNSClass::IN,
+            records: vec![a.clone()],
+        })
+    };
+    let failed = || future::err::<Rrset, _>(ProtoError::from("bad signature"));
+
+    // RRSIGs are stripped by default
+    let response = block_on(verify_all_rrsets(message.clone(), vec![verified()], false)).unwrap();
+    assert_eq!(response.dnssec_status(), DnssecStatus::Secure);
+    assert_eq!(response.answers(), &[a.clone()]);
+
+    let response = block_on(verify_all_rrsets(message.clone(), vec![verified()], true)).unwrap();
+    assert_eq!(response.dnssec_status(), DnssecStatus::Secure);
+    assert_eq!(response.answers(), &[a.clone(), rrsig.clone()]);
+
+    // failures are errors, when retained the response is attached
+    assert!(block_on(verify_all_rrsets(message.clone(), vec![failed()], false)).is_err());
+
+    let error = block_on(verify_all_rrsets(message, vec![failed()], true)).unwrap_err();
+    match error.kind() {
+        ProtoErrorKind::Bogus { response, .. } => {
+            assert_eq!(response.dnssec_status(), DnssecStatus::Bogus);
+            assert_eq!(response.answers(), &[a, rrsig]);
+        }
+        kind => panic!("unexpected error kind: {:?}", kind),
+    }
+}
+
+#[cfg(feature = "dnssec-ring")]
+#[test]
+fn test_validate_chain_from_trust_anchor() {
+    use std::collections::HashMap;
+    use std::net::Ipv4Addr;
+    use std::str::FromStr;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures_executor::block_on;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    use crate::op::Message;
+    use crate::rr::dnssec::{tbs, DigestType};
+
+    /// Answers queries from a fixed set of records, counting the queries made
+    #[derive(Clone)]
+    struct ZoneHandle {
+        records: Arc<HashMap<(Name, RecordType), Vec<Record>>>,
+        queries: Arc<AtomicUsize>,
+    }
+
+    impl DnsHandle for ZoneHandle {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ProtoError>> + Send>>;
+        type Error = ProtoError;
+
+        fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
+            let request = request.into();
+            let query = request.queries()[0].clone();
+            self.queries.fetch_add(1, Ordering::SeqCst);
+
+            let mut message = Message::new();
+            message.set_id(request.id());
+            if let Some(records) = self
+                .records
+                .get(&(query.name().clone(), query.query_type()))
+            {
+                message.insert_answers(records.clone());
+            }
+            message.add_query(query);
+
+            Box::pin(stream::once(future::ok(DnsResponse::from(message))))
+        }
+    }
+
+    let generate_key = || {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let dnskey = DNSKEY::new(
+            true,
+            true,
+            false,
+            Algorithm::ED25519,
+            key_pair.public_key().as_ref().to_vec(),
+        );
+        (key_pair, dnskey)
+    };
+
+    // the records followed by the RRSIG over them, which expires at the given time
+    let unix_now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32;
+    let signed_until = |key_pair: &Ed25519KeyPair,
+                        dnskey: &DNSKEY,
+                        signer: &Name,
+                        records: Vec<Record>,
+                        sig_expiration: u32| {
+        let name = records[0].name().clone();
+        let sig = |signature| {
+            SIG::new(
+                records[0].rr_type(),
+                Algorithm::ED25519,
+                name.num_labels(),
+                86400,
+                sig_expiration,
+                0,
+                dnskey.calculate_key_tag().unwrap(),
+                signer.clone(),
+                signature,
+            )
+        };
+        let tbs = tbs::rrset_tbs_with_sig(&name, DNSClass::IN, &sig(vec![]), &records).unwrap();
+        let sig = sig(key_pair.sign(tbs.as_ref()).as_ref().to_vec());
+
+        let mut rrsig = Record::from_rdata(name, 86400, RData::DNSSEC(DNSSECRData::SIG(sig)));
+        rrsig.set_rr_type(RecordType::RRSIG);
+
+        let mut records = records;
+        records.push(rrsig);
+        records
+    };
+    let signed =
+        |key_pair: &Ed25519KeyPair, dnskey: &DNSKEY, signer: &Name, records: Vec<Record>| {
+            signed_until(key_pair, dnskey, signer, records, unix_now + 86400)
+        };
+
+    let root = Name::root();
+    let zone = Name::from_str("example.").unwrap();
+    let www = Name::from_str("www.example.").unwrap();
+    let (root_key_pair, root_dnskey) = generate_key();
+    let (zone_key_pair, zone_dnskey) = generate_key();
+
+    let dnskey_record = |name: &Name, dnskey: &DNSKEY| {
+        Record::from_rdata(
+            name.clone(),
+            86400,
+            RData::DNSSEC(DNSSECRData::DNSKEY(dnskey.clone())),
+        )
+    };
+    let ds = zone_dnskey.to_ds(&zone, DigestType::SHA256).unwrap();
+    let a = Record::from_rdata(www.clone(), 300, RData::A(Ipv4Addr::new(192, 0, 2, 1)));
+
+    let mut records = HashMap::new();
+    records.insert(
+        (root.clone(), RecordType::DNSKEY),
+        signed(
+            &root_key_pair,
+            &root_dnskey,
+            &root,
+            vec![dnskey_record(&root, &root_dnskey)],
+        ),
+    );
+    records.insert(
+        (zone.clone(), RecordType::DS),
+        signed(
+            &root_key_pair,
+            &root_dnskey,
+            &root,
+            vec![Record::from_rdata(
+                zone.clone(),
+                86400,
+                RData::DNSSEC(DNSSECRData::DS(ds)),
+            )],
+        ),
+    );
+    records.insert(
+        (zone.clone(), RecordType::DNSKEY),
+        signed(
+            &zone_key_pair,
+            &zone_dnskey,
+            &zone,
+            vec![dnskey_record(&zone, &zone_dnskey)],
+        ),
+    );
+    records.insert(
+        (www.clone(), RecordType::A),
+        signed(&zone_key_pair, &zone_dnskey, &zone, vec![a.clone()]),
+    );
+
+    // a record which does not match its RRSIG
+    let mut tampered = signed(&zone_key_pair, &zone_dnskey, &zone, vec![a.clone()]);
+    tampered[0].set_data(Some(RData::A(Ipv4Addr::new(192, 0, 2, 66))));
+    records.insert((www.clone(), RecordType::AAAA), tampered);
+
+    // a zone with DNSKEYs whose RRSIG has expired
+    let expired = Name::from_str("expired.").unwrap();
+    let www_expired = Name::from_str("www.expired.").unwrap();
+    records.insert(
+        (expired.clone(), RecordType::DS),
+        signed(
+            &root_key_pair,
+            &root_dnskey,
+            &root,
+            vec![Record::from_rdata(
+                expired.clone(),
+                86400,
+                RData::DNSSEC(DNSSECRData::DS(
+                    zone_dnskey.to_ds(&expired, DigestType::SHA256).unwrap(),
+                )),
+            )],
+        ),
+    );
+    records.insert(
+        (expired.clone(), RecordType::DNSKEY),
+        signed_until(
+            &zone_key_pair,
+            &zone_dnskey,
+            &expired,
+            vec![dnskey_record(&expired, &zone_dnskey)],
+            unix_now - 1,
+        ),
+    );
+    records.insert(
+        (www_expired.clone(), RecordType::A),
+        signed(
+            &zone_key_pair,
+            &zone_dnskey,
+            &expired,
+            vec![Record::from_rdata(
+                www_expired.clone(),
+                300,
+                RData::A(Ipv4Addr::new(192, 0, 2, 2)),
+            )],
+        ),
+    );
+
+    let mut trust_anchor = TrustAnchor::new();
+    trust_anchor.insert_dnskey(&root_dnskey);
+
+    let queries = Arc::new(AtomicUsize::new(0));
+    let mut handle = DnssecDnsHandle::with_trust_anchor(
+        ZoneHandle {
+            records: Arc::new(records),
+            queries: Arc::clone(&queries),
+        },
+        trust_anchor,
+    )
+    .retain_dnssec_records(true);
+
+    let lookup = |handle: &mut DnssecDnsHandle<ZoneHandle>, record_type| {
+        block_on(
+            handle
+                .lookup(
+                    Query::query(www.clone(), record_type),
+                    DnsRequestOptions::default(),
+                )
+                .first_answer(),
+        )
+        .unwrap()
+    };
+
+    // the chain is built from the trust anchor: DNSKEY ., DS example. and DNSKEY example.
+    let response = lookup(&mut handle, RecordType::A);
+    assert_eq!(response.dnssec_status(), DnssecStatus::Secure);
+    assert_eq!(response.answers()[0], a);
+    assert_eq!(queries.load(Ordering::SeqCst), 4);
+
+    // the validated DNSKEYs of example. are cached
+    let response = lookup(&mut handle, RecordType::A);
+    assert_eq!(response.dnssec_status(), DnssecStatus::Secure);
+    assert_eq!(queries.load(Ordering::SeqCst), 5);
+
+    let error = block_on(
+        handle
+            .lookup(
+                Query::query(www.clone(), RecordType::AAAA),
+                DnsRequestOptions::default(),
+            )
+            .first_answer(),
+    )
+    .unwrap_err();
+    assert!(matches!(error.kind(), ProtoErrorKind::Bogus { .. }));
+    assert_eq!(queries.load(Ordering::SeqCst), 6);
+
+    // DNSKEYs are not cached beyond the expiration of their RRSIG, the DNSKEY of . still is
+    for expected_queries in &[9, 12] {
+        let response = block_on(
+            handle
+                .lookup(
+                    Query::query(www_expired.clone(), RecordType::A),
+                    DnsRequestOptions::default(),
+                )
+                .first_answer(),
+        )
+        .unwrap();
+        assert_eq!(response.dnssec_status(), DnssecStatus::Secure);
+        assert_eq!(queries.load(Ordering::SeqCst), *expected_queries);
+    }
+
+    // without the root key there is no chain of trust
+    let mut handle = DnssecDnsHandle::with_trust_anchor(handle.handle.clone(), TrustAnchor::new());
+    assert!(block_on(
+        handle
+            .lookup(
+                Query::query(www.clone(), RecordType::A),
+                DnsRequestOptions::default(),
+            )
+            .first_answer()
+    )
+    .is_err());
+}
+
 /// Verifies that a given RRSET is validly signed by any of the specified RRSIGs.
 ///
 /// Invalid RRSIGs will be ignored. RRSIGs will only be validated against DNSKEYs which can
@@ -684,14 +1194,7 @@ where
                 .filter_map(|sig| {
                     let rrset = Arc::clone(&rrset);
 
-                    if rrset.records.iter().any(|r| {
-                        if let Some(RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey))) = r.data() {
-                            let dnskey_name = r.name();
-                            verify_rrset_with_dnskey(dnskey_name, dnskey, &sig, &rrset).is_ok()
-                        } else {
-                            panic!("expected a DNSKEY here: {:?}", r.data());
-                        }
-                    }) {
+                    if verify_rrset_with_dnskeys(rrset.records.iter(), &sig, &rrset).is_ok() {
                         Some(())
                     } else {
                         None
@@ -717,44 +1220,27 @@ where
     //         susceptible until that algorithm is removed as an option.
     //        dns over TLS will mitigate this.
     //  TODO: strip RRSIGS to accepted algorithms and make algorithms configurable.
-    let verifications = rrsigs.into_iter()
+    let verifications = rrsigs
+        .into_iter()
         // this filter is technically unnecessary, can probably remove it...
         .filter(|rrsig| is_dnssec(rrsig, RecordType::RRSIG))
-        .map(|rrsig|
+        .map(|rrsig| {
             if let Some(RData::DNSSEC(DNSSECRData::SIG(sig))) = rrsig.into_data() {
                 // setting up the context explicitly.
                 sig
             } else {
                 panic!("expected a SIG here");
             }
-        )
+        })
         .map(|sig| {
             let rrset = Arc::clone(&rrset);
-            let mut handle = handle.clone_with_context();
+            let handle = handle.clone_with_context();
 
-            handle
-                .lookup(
-                    Query::query(sig.signer_name().clone(), RecordType::DNSKEY),
-                    DnsRequestOptions::default()
-                )
-                .first_answer()
-                .and_then(move |message|
-                    // DNSKEYs are validated by the inner query
-                    future::ready(message
-                        .answers()
-                        .iter()
-                        .filter(|r| is_dnssec(r, RecordType::DNSKEY))
-                        .find(|r|
-                            if let Some(RData::DNSSEC(DNSSECRData::DNSKEY(ref dnskey))) = r.data() {
-                                let dnskey_name = r.name();
-                                verify_rrset_with_dnskey(dnskey_name, dnskey, &sig, &rrset).is_ok()
-                            } else {
-                                panic!("expected a DNSKEY here: {:?}", r.data());
-                            }
-                        )
-                        .map(|_| ())
-                        .ok_or_else(|| E::from(ProtoError::from(ProtoErrorKind::Message("validation failed")))))
-                )
+            async move {
+                let dnskeys = validated_dnskeys(handle, sig.signer_name().clone()).await?;
+                verify_rrset_with_dnskeys(dnskeys.iter(), &sig, &rrset).map_err(E::from)
+            }
+            .boxed()
         })
         .collect::<Vec<_>>();
 
@@ -779,6 +1265,118 @@ where
     select.await
 }
 
+/// Returns the validated DNSKEYs of the zone, from the cache of the handle or else a lookup
+///
+/// The DNSKEYs returned by the lookup have been validated down the chain from the trust anchor,
+///  they are cached for the lowest TTL among them, at most a day, so that the chain is only built once.
+///  They are not cached beyond the expiration of the RRSIGs over them, see
+///  [RFC 4035 section 5.3.3](https://tools.ietf.org/html/rfc4035#section-5.3.3).
+async fn validated_dnskeys<H, E>(
+    mut handle: DnssecDnsHandle<H>,
+    zone: Name,
+) -> Result<Arc<[Record]>, E>
+where
+    H: DnsHandle<Error = E> + Sync + Unpin,
+    E: From<ProtoError> + Error + Clone + Send + Unpin + 'static,
+{
+    let now = Instant::now();
+    {
+        let mut cache = handle.dnskey_cache.lock().expect("dnskey cache poisoned");
+        match cache.get_mut(&zone) {
+            Some((expires, dnskeys)) if *expires > now => return Ok(Arc::clone(dnskeys)),
+            Some(_) => {
+                cache.remove(&zone);
+            }
+            None => (),
+        }
+    }
+
+    // DNSKEYs are validated by the inner query, which retains their RRSIGs
+    handle.retain_dnssec_records = true;
+    let message = handle
+        .lookup(
+            Query::query(zone.clone(), RecordType::DNSKEY),
+            DnsRequestOptions::default(),
+        )
+        .first_answer()
+        .await?;
+    let dnskeys = message
+        .answers()
+        .iter()
+        .filter(|r| is_dnssec(r, RecordType::DNSKEY))
+        .cloned()
+        .collect::<Arc<[Record]>>();
+
+    let sig_expiration = message
+        .answers()
+        .iter()
+        .filter(|r| is_dnssec(r, RecordType::RRSIG))
+        .filter_map(|r| match r.data() {
+            Some(RData::DNSSEC(DNSSECRData::SIG(sig)))
+                if sig.type_covered() == RecordType::DNSKEY =>
+            {
+                Some(sig.sig_expiration())
+            }
+            _ => None,
+        })
+        .min();
+
+    if let Some(ttl) = dnskeys.iter().map(Record::ttl).min() {
+        let mut valid_for = Duration::from_secs(u64::from(ttl)).min(MAX_DNSKEY_CACHE_TTL);
+        if let Some(sig_expiration) = sig_expiration {
+            let unix_now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            valid_for = valid_for.min(Duration::from_secs(
+                u64::from(sig_expiration).saturating_sub(unix_now),
+            ));
+        }
+
+        if valid_for > Duration::from_secs(0) {
+            handle
+                .dnskey_cache
+                .lock()
+                .expect("dnskey cache poisoned")
+                .insert(zone, (now + valid_for, Arc::clone(&dnskeys)));
+        }
+    }
+
+    Ok(dnskeys)
+}
+
+/// Verifies the given SIG of the RRSET with any of the DNSKEYs which could have made it.
+///
+/// Key tags are not unique, so every DNSKEY with the key tag and algorithm of the SIG is tried, the
+///  SIG is valid if any of them verifies it.
+fn verify_rrset_with_dnskeys<'r>(
+    dnskeys: impl Iterator<Item = &'r Record>,
+    sig: &SIG,
+    rrset: &Rrset,
+) -> ProtoResult<()> {
+    let mut result =
+        Err(ProtoErrorKind::Message("no DNSKEY matches the key tag of the RRSIG").into());
+
+    for record in dnskeys.filter(|r| is_dnssec(r, RecordType::DNSKEY)) {
+        let dnskey = match record.data() {
+            Some(RData::DNSSEC(DNSSECRData::DNSKEY(dnskey))) => dnskey,
+            _ => continue,
+        };
+        if dnskey.algorithm() != sig.algorithm()
+            || dnskey.calculate_key_tag().ok() != Some(sig.key_tag())
+        {
+            continue;
+        }
+
+        result = verify_rrset_with_dnskey(record.name(), dnskey, sig, rrset);
+        if result.is_ok() {
+            break;
+        }
+    }
+
+    result
+}
+
 /// Verifies the given SIG of the RRSET with the DNSKEY.
 #[cfg(feature = "dnssec")]
 fn verify_rrset_with_dnskey(
@@ -941,3 +1539,241 @@ pub fn verify_nsec(query: &Query, soa_name: &Name, nsecs: &[&Record]) -> bool {
         verify_nsec_coverage(&wildcard)
     }
 }
+
+/// The maximum number of additional hash iterations of NSEC3 records which will be computed
+///
+/// [RFC 9276](https://tools.ietf.org/html/rfc9276#section-3.2), responses above this limit are
+///  treated as insecure, rather than spending the work on validating them.
+pub const MAX_NSEC3_ITERATIONS: u16 = 150;
+
+/// Verifies NSEC3 records, returning the status of the proven denial of existence
+///
+/// ```text
+/// RFC 5155                         NSEC3                        March 2008
+///
+/// 8.3.  Closest Encloser Proof
+///
+///  For some NSEC3 responses, the NSEC3 RR set is used to prove the
+///  existence of the closest encloser name and the non-existence of the
+///  next closer name.
+///
+///  In order to verify the closest encloser proof, the validator MUST
+///  find the longest name, X, such that
+///
+///  o  X is an ancestor of QNAME that is matched by an NSEC3 RR present
+///     in the response.  This is a candidate for the closest encloser, and
+///
+///  o  The name one label longer than X (but still an ancestor of -- or
+///     equal to -- QNAME) is covered by an NSEC3 RR present in the
+///     response.
+///
+/// 8.4.  Validating Name Error Responses
+///
+///  A validator MUST verify that there is a closest encloser proof for
+///  QNAME present in the response and that there is an NSEC3 RR that
+///  covers the wildcard at the closest encloser (i.e., the name formed by
+///  prepending the asterisk label to the closest encloser).
+///
+/// 8.5.  Validating No Data Responses, QTYPE is not DS
+///
+///  The validator MUST verify that an NSEC3 RR that matches QNAME is
+///  present and that both the QTYPE and the CNAME type are not set in its
+///  Type Bit Maps field.
+///
+/// 8.6.  Validating No Data Responses, QTYPE is DS
+///
+///  If there is no NSEC3 RR present that matches QNAME, the validator
+///  MUST verify that a closest provable encloser proof for QNAME is
+///  present in the response, and that the NSEC3 RR that covers the "next
+///  closer" name has the Opt-Out bit set.
+///
+/// 8.7.  Validating Wildcard No Data Responses
+///
+///  The validator MUST verify a closest encloser proof for QNAME and MUST
+///  find an NSEC3 RR present in the response that matches the wildcard
+///  name generated by prepending the asterisk label to the closest
+///  encloser.  Furthermore, the bits corresponding to both QTYPE and
+///  CNAME MUST NOT be set in the wildcard matching NSEC3 RR.
+/// ```
+///
+/// The proof must agree with the response code, NOERROR for the proofs of a matching name or wildcard, and
+///  NXDOMAIN for the proof that the name doesn't exist, otherwise the response is bogus.
+///
+/// When the proof relies on an NSEC3 record with the Opt-Out flag, an unsigned delegation may exist
+///  for the name, and the denial is only `DnssecStatus::Insecure`. The same is true for NSEC3 records
+///  with more than `MAX_NSEC3_ITERATIONS` iterations, which are not hashed at all.
+#[cfg(any(feature = "openssl", feature = "ring"))]
+#[doc(hidden)]
+pub fn verify_nsec3(
+    query: &Query,
+    soa_name: &Name,
+    response_code: ResponseCode,
+    nsec3s: &[&Record],
+) -> ProtoResult<DnssecStatus> {
+    use data_encoding::BASE32_DNSSEC;
+
+    use crate::rr::dnssec::rdata::NSEC3;
+
+    // all NSEC3 records of the zone are directly below the apex, the first label being the hash
+    let nsec3s = nsec3s
+        .iter()
+        .filter(|record| record.name().base_name() == *soa_name)
+        .filter_map(|record| {
+            let label = record.name().iter().next()?;
+            let hash = BASE32_DNSSEC.decode(&label.to_ascii_lowercase()).ok()?;
+            record
+                .data()
+                .and_then(RData::as_dnssec)
+                .and_then(DNSSECRData::as_nsec3)
+                .map(|rdata| (hash, rdata))
+        })
+        .collect::<Vec<(Vec<u8>, &NSEC3)>>();
+
+    let params = if let Some((_, rdata)) = nsec3s.first() {
+        *rdata
+    } else {
+        return Err(ProtoError::from(
+            "no NSEC3 records of the zone in the response",
+        ));
+    };
+
+    if nsec3s.iter().any(|(_, rdata)| {
+        rdata.hash_algorithm() != params.hash_algorithm()
+            || rdata.iterations() != params.iterations()
+            || rdata.salt() != params.salt()
+    }) {
+        return Err(ProtoError::from("NSEC3 records with differing parameters"));
+    }
+
+    if params.iterations() > MAX_NSEC3_ITERATIONS {
+        debug!(
+            "NSEC3 iterations {} above the limit of {}, treating as insecure",
+            params.iterations(),
+            MAX_NSEC3_ITERATIONS
+        );
+        return Ok(DnssecStatus::Insecure);
+    }
+
+    let hash = |name: &Name| -> ProtoResult<Vec<u8>> {
+        params
+            .hash_algorithm()
+            .hash(params.salt(), name, params.iterations())
+            .map(|digest| digest.as_ref().to_vec())
+    };
+
+    let find_matching = |name: &Name| -> ProtoResult<Option<&NSEC3>> {
+        let hash = hash(name)?;
+        Ok(nsec3s
+            .iter()
+            .find(|(owner, _)| *owner == hash)
+            .map(|(_, rdata)| *rdata))
+    };
+
+    let find_covering = |name: &Name| -> ProtoResult<Option<&NSEC3>> {
+        let hash = hash(name)?;
+        Ok(nsec3s
+            .iter()
+            .find(|(owner, rdata)| {
+                let next = rdata.next_hashed_owner_name();
+                // the last record in the zone wraps around to the first
+                if owner.as_slice() < next {
+                    owner.as_slice() < hash.as_slice() && hash.as_slice() < next
+                } else {
+                    owner.as_slice() < hash.as_slice() || hash.as_slice() < next
+                }
+            })
+            .map(|(_, rdata)| *rdata))
+    };
+
+    // neither the type nor a CNAME may exist at the matched name
+    let is_no_data = |rdata: &NSEC3| {
+        !rdata.type_bit_maps().contains(&query.query_type())
+            && !rdata.type_bit_maps().contains(&RecordType::CNAME)
+    };
+
+    if !soa_name.zone_of(query.name()) {
+        return Err(ProtoError::from("NSEC3 query name is not in the zone"));
+    }
+
+    // the name exists, but not the type
+    if let Some(rdata) = find_matching(query.name())? {
+        return if response_code != ResponseCode::NoError {
+            Err(ProtoError::from(format!(
+                "NSEC3 proves the existence of the name, but the response is {}",
+                response_code
+            )))
+        } else if is_no_data(rdata) {
+            Ok(DnssecStatus::Secure)
+        } else {
+            Err(ProtoError::from("NSEC3 proves the existence of the type"))
+        };
+    }
+
+    // closest encloser proof, the apex of the zone is the last candidate
+    //  the next closer name is the one label longer than the closest encloser
+    let mut next_closer = query.name().clone();
+    loop {
+        if next_closer.num_labels() <= soa_name.num_labels() {
+            return Err(ProtoError::from("NSEC3 missing the closest encloser"));
+        }
+
+        let candidate = next_closer.base_name();
+        if find_matching(&candidate)?.is_some() {
+            break;
+        }
+        next_closer = candidate;
+    }
+
+    let covering = if let Some(rdata) = find_covering(&next_closer)? {
+        rdata
+    } else {
+        return Err(ProtoError::from("NSEC3 missing the next closer name"));
+    };
+
+    // there may be an unsigned delegation, which is not covered by the NSEC3 chain
+    if covering.opt_out() {
+        return Ok(DnssecStatus::Insecure);
+    }
+
+    // the wildcard at the closest encloser
+    let wildcard = next_closer.into_wildcard();
+    if let Some(rdata) = find_matching(&wildcard)? {
+        // the wildcard exists, but not the type
+        if response_code != ResponseCode::NoError {
+            Err(ProtoError::from(format!(
+                "NSEC3 proves the existence of the wildcard, but the response is {}",
+                response_code
+            )))
+        } else if is_no_data(rdata) {
+            Ok(DnssecStatus::Secure)
+        } else {
+            Err(ProtoError::from(
+                "NSEC3 proves the existence of the wildcard type",
+            ))
+        }
+    } else if find_covering(&wildcard)?.is_some() {
+        // the name doesn't exist
+        if response_code == ResponseCode::NXDomain {
+            Ok(DnssecStatus::Secure)
+        } else {
+            Err(ProtoError::from(format!(
+                "NSEC3 proves the name doesn't exist, but the response is {}",
+                response_code
+            )))
+        }
+    } else {
+        Err(ProtoError::from("NSEC3 missing the wildcard proof"))
+    }
+}
+
+/// Will always return an error. To enable NSEC3 verification compile with the openssl or ring feature.
+#[cfg(not(any(feature = "openssl", feature = "ring")))]
+#[doc(hidden)]
+pub fn verify_nsec3(
+    _: &Query,
+    _: &Name,
+    _: ResponseCode,
+    _: &[&Record],
+) -> ProtoResult<DnssecStatus> {
+    Err(ProtoErrorKind::Message("openssl or ring feature(s) not enabled").into())
+}
@@ -11,7 +11,7 @@ use std::task::{Context, Poll};
 
 use futures_channel::mpsc;
 use futures_channel::oneshot;
-use futures_util::future::Future;
+use futures_util::future::{self, Future};
 use futures_util::ready;
 use futures_util::stream::{Fuse, Peekable, Stream, StreamExt};
 use log::{debug, warn};
@@ -36,11 +36,11 @@ pub use self::dns_exchange::{
 pub use self::dns_handle::{DnsHandle, DnsStreamHandle};
 pub use self::dns_multiplexer::{DnsMultiplexer, DnsMultiplexerConnect};
 pub use self::dns_request::{DnsRequest, DnsRequestOptions};
-pub use self::dns_response::{DnsResponse, DnsResponseStream};
+pub use self::dns_response::{DnsResponse, DnsResponseStream, DnssecStatus};
 #[cfg(feature = "dnssec")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
 pub use self::dnssec_dns_handle::DnssecDnsHandle;
-pub use self::retry_dns_handle::RetryDnsHandle;
+pub use self::retry_dns_handle::{BackoffPolicy, RetryDnsHandle};
 pub use self::serial_message::SerialMessage;
 
 /// Ignores the result of a send operation and logs and ignores errors
@@ -108,6 +108,21 @@ impl BufDnsStreamHandle {
             sender: self.sender.clone(),
         }
     }
+
+    /// Waits until there is room in the buffer for another message
+    ///
+    /// `send` fails if the buffer is full, senders of many messages in a row, e.g. the responses of a zone transfer,
+    ///  should wait on this before each of them.
+    pub async fn ready(&mut self) -> Result<(), ProtoError> {
+        future::poll_fn(|cx| self.poll_ready(cx)).await
+    }
+
+    /// Polls until there is room in the buffer for another message, see `ready`
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), ProtoError>> {
+        self.sender
+            .poll_ready(cx)
+            .map_err(|e| ProtoError::from(format!("mpsc::SendError {}", e)))
+    }
 }
 
 impl DnsStreamHandle for BufDnsStreamHandle {
@@ -162,6 +177,15 @@ macro_rules! try_oneshot {
     };
 }
 
+impl BufDnsRequestStreamHandle {
+    /// Polls until there is room in the buffer for another request, `send` fails with `Busy` if there is not
+    pub(crate) fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), ProtoError>> {
+        self.sender
+            .poll_ready(cx)
+            .map_err(|e| ProtoError::from(format!("mpsc::SendError {}", e)))
+    }
+}
+
 impl DnsHandle for BufDnsRequestStreamHandle {
     type Response = DnsResponseReceiver;
     type Error = ProtoError;
@@ -7,14 +7,67 @@
 
 //! `RetryDnsHandle` allows for DnsQueries to be reattempted on failure
 
+use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
+use futures_util::ready;
 use futures_util::stream::{Stream, StreamExt};
+use rand::Rng;
+#[cfg(feature = "serde-config")]
+use serde::{Deserialize, Serialize};
 
 use crate::error::{ProtoError, ProtoErrorKind};
 use crate::xfer::{DnsRequest, DnsResponse};
-use crate::DnsHandle;
+use crate::{DnsHandle, Time};
+
+type Delay = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// How long to wait before each retry of a failed query, see `RetryDnsHandle::with_backoff`
+#[cfg_attr(feature = "serde-config", derive(Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackoffPolicy {
+    /// The same delay before every retry, zero to retry immediately
+    Fixed(Duration),
+    /// A delay of `base` before the first retry, doubled before each following retry, up to `max`
+    Exponential {
+        /// The delay before the first retry
+        base: Duration,
+        /// The longest delay before a retry
+        max: Duration,
+        /// Wait for a random duration between zero and the delay instead, so that clients which
+        ///  failed together do not retry together
+        jitter: bool,
+    },
+}
+
+impl BackoffPolicy {
+    /// Returns the delay before the given retry, the first retry being 0
+    pub fn delay(&self, retry: u32) -> Duration {
+        match *self {
+            Self::Fixed(delay) => delay,
+            Self::Exponential { base, max, jitter } => {
+                let delay = 1_u32
+                    .checked_shl(retry)
+                    .and_then(|factor| base.checked_mul(factor))
+                    .map_or(max, |delay| delay.min(max));
+
+                if jitter {
+                    rand::thread_rng().gen_range(Duration::ZERO..=delay)
+                } else {
+                    delay
+                }
+            }
+        }
+    }
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        Self::Fixed(Duration::ZERO)
+    }
+}
 
 /// Can be used to reattempt queries if they fail
 ///
@@ -37,6 +90,8 @@ where
 {
     handle: H,
     attempts: usize,
+    backoff: BackoffPolicy,
+    delay_for: Option<fn(Duration) -> Delay>,
 }
 
 impl<H> RetryDnsHandle<H>
@@ -51,10 +106,30 @@ where
     /// * `handle` - handle to the dns connection
     /// * `attempts` - number of attempts before failing
     pub fn new(handle: H, attempts: usize) -> Self {
-        Self { handle, attempts }
+        Self {
+            handle,
+            attempts,
+            backoff: BackoffPolicy::default(),
+            delay_for: None,
+        }
+    }
+
+    /// Waits before each retry, as specified by the policy, instead of retrying immediately
+    ///
+    /// `T` is the timer used to wait.
+    pub fn with_backoff<T: Time>(self, backoff: BackoffPolicy) -> Self {
+        Self {
+            backoff,
+            delay_for: Some(delay_for::<T>),
+            ..self
+        }
     }
 }
 
+fn delay_for<T: Time>(duration: Duration) -> Delay {
+    T::delay_for(duration)
+}
+
 impl<H> DnsHandle for RetryDnsHandle<H>
 where
     H: DnsHandle + Send + Unpin + 'static,
@@ -75,6 +150,10 @@ where
             handle: self.handle.clone(),
             stream,
             remaining_attempts: self.attempts,
+            backoff: self.backoff,
+            delay_for: self.delay_for,
+            retries: 0,
+            delay: None,
         })
     }
 }
@@ -88,6 +167,10 @@ where
     handle: H,
     stream: <H as DnsHandle>::Response,
     remaining_attempts: usize,
+    backoff: BackoffPolicy,
+    delay_for: Option<fn(Duration) -> Delay>,
+    retries: u32,
+    delay: Option<Delay>,
 }
 
 impl<H: DnsHandle + Unpin> Stream for RetrySendStream<H>
@@ -100,6 +183,15 @@ where
         // loop over the stream, on errors, spawn a new stream
         //  on ready and not ready return.
         loop {
+            // wait out the backoff before sending the retry
+            if let Some(delay) = self.delay.as_mut() {
+                ready!(delay.as_mut().poll(cx));
+                self.delay = None;
+
+                let request = self.request.clone();
+                self.stream = self.handle.send(request);
+            }
+
             match self.stream.poll_next_unpin(cx) {
                 Poll::Ready(Some(Err(e))) => {
                     if self.remaining_attempts == 0 || !e.should_retry() {
@@ -110,6 +202,13 @@ where
                         self.remaining_attempts -= 1;
                     }
 
+                    let delay = self.backoff.delay(self.retries);
+                    self.retries = self.retries.saturating_add(1);
+                    if let (Some(delay_for), true) = (self.delay_for, delay > Duration::ZERO) {
+                        self.delay = Some(delay_for(delay));
+                        continue;
+                    }
+
                     // TODO: if the "sent" Message is part of the error result,
                     //  then we can just reuse it... and no clone necessary
                     let request = self.request.clone();
@@ -145,9 +244,11 @@ mod test {
     use crate::error::*;
     use crate::op::*;
     use crate::xfer::FirstAnswer;
+    use async_trait::async_trait;
     use futures_executor::block_on;
     use futures_util::future::*;
     use futures_util::stream::*;
+    use std::cell::RefCell;
     use std::sync::{
         atomic::{AtomicU16, Ordering},
         Arc,
@@ -207,4 +308,77 @@ mod test {
         let test1 = Message::new();
         assert!(block_on(client.send(test1).first_answer()).is_err());
     }
+
+    thread_local! {
+        static DELAYS: RefCell<Vec<Duration>> = RefCell::default();
+    }
+
+    /// Records the delays instead of waiting
+    struct MockTime;
+
+    #[async_trait]
+    impl Time for MockTime {
+        async fn delay_for(duration: Duration) {
+            DELAYS.with(|delays| delays.borrow_mut().push(duration));
+        }
+
+        async fn timeout<F>(_: Duration, future: F) -> Result<F::Output, std::io::Error>
+        where
+            F: 'static + Future + Send,
+        {
+            Ok(future.await)
+        }
+    }
+
+    #[test]
+    fn test_backoff_delay() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(BackoffPolicy::default().delay(3), Duration::ZERO);
+        assert_eq!(BackoffPolicy::Fixed(ms(100)).delay(3), ms(100));
+
+        let exponential = BackoffPolicy::Exponential {
+            base: ms(100),
+            max: ms(1000),
+            jitter: false,
+        };
+        let delays = (0..6).map(|i| exponential.delay(i)).collect::<Vec<_>>();
+        let expected = [100, 200, 400, 800, 1000, 1000].iter().map(|&d| ms(d));
+        assert!(delays.iter().copied().eq(expected));
+        assert_eq!(exponential.delay(u32::MAX), ms(1000));
+
+        let jittered = BackoffPolicy::Exponential {
+            base: ms(100),
+            max: ms(1000),
+            jitter: true,
+        };
+        for i in 0..6 {
+            assert!(jittered.delay(i) <= delays[i as usize]);
+        }
+    }
+
+    #[test]
+    fn test_retry_backoff() {
+        let mut handle = RetryDnsHandle::new(
+            TestClient {
+                last_succeed: true,
+                retries: 3,
+                attempts: Arc::new(AtomicU16::new(0)),
+            },
+            3,
+        )
+        .with_backoff::<MockTime>(BackoffPolicy::Exponential {
+            base: Duration::from_millis(100),
+            max: Duration::from_millis(250),
+            jitter: false,
+        });
+
+        DELAYS.with(|delays| delays.borrow_mut().clear());
+        let result = block_on(handle.send(Message::new()).first_answer()).expect("should succeed");
+        assert_eq!(result.id(), 3);
+
+        let delays = DELAYS.with(|delays| delays.borrow().clone());
+        let expected = [100, 200, 250].iter().map(|&d| Duration::from_millis(d));
+        assert!(delays.into_iter().eq(expected));
+    }
 }
@@ -56,3 +56,52 @@ fn test_ds_display() {
     let exp_result = "60485 5 1 2BB183AF5F22588179A53B0A98631FAD1A292118";
     assert_eq!(result, exp_result);
 }
+
+#[test]
+#[allow(deprecated)]
+fn test_dnskey_to_ds() {
+    // RFC 4509, section 2.3
+    let dnskey = DNSKEY::new(
+        true,
+        false,
+        false,
+        Algorithm::RSASHA1,
+        include_bytes!("test-data/rfc4034-5.4.key").to_vec(),
+    );
+    let ds = dnskey
+        .to_ds(
+            &Name::parse("dskey.example.com.", None).unwrap(),
+            DigestType::SHA256,
+        )
+        .unwrap();
+    assert_eq!(
+        ds.to_string(),
+        "60485 5 2 D4B7D520E7BB5F0F67674A0CCEB1E3E0614B93C4F9E99B8383F6A1E4469DA50A"
+    );
+    assert!(ds
+        .covers(&Name::parse("dskey.example.com.", None).unwrap(), &dnskey)
+        .unwrap());
+
+    // RFC 6605, section 6.2
+    let dnskey = DNSKEY::new(
+        true,
+        true,
+        false,
+        Algorithm::ECDSAP384SHA384,
+        data_encoding::BASE64
+            .decode(
+                b"xKYaNhWdGOfJ+nPrL8/arkwf2EY3MDJ+SErKivBVSum1w/egsXvSADtNJhyem5RCOpgQ6K8X1DRSEkrbYQ+OB+v8/uX45NBwY8rp65F6Glur8I/mlVNgF6W/qTI37m40",
+            )
+            .unwrap(),
+    );
+    let ds = dnskey
+        .to_ds(
+            &Name::parse("example.net.", None).unwrap(),
+            DigestType::SHA384,
+        )
+        .unwrap();
+    assert_eq!(
+        ds.to_string(),
+        "10771 14 4 72D7B62976CE06438E9C0BF319013CF801F09ECC84B8D7E9495F27E305C6A9B0563A9B5F4D288405C3008A946DF983D6"
+    );
+}
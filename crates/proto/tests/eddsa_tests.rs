@@ -0,0 +1,134 @@
+#![cfg(any(feature = "dnssec-openssl", feature = "dnssec-ring"))]
+
+//! The examples of [RFC 8080](https://tools.ietf.org/html/rfc8080#section-6), EdDSA for DNSSEC
+
+use std::str::FromStr;
+
+use data_encoding::{BASE64, HEXLOWER};
+
+use trust_dns_proto::rr::dnssec::rdata::{DNSKEY, SIG};
+use trust_dns_proto::rr::dnssec::{Algorithm, DigestType, Verifier};
+use trust_dns_proto::rr::rdata::MX;
+use trust_dns_proto::rr::{DNSClass, Name, RData, Record, RecordType};
+
+fn dnskey(algorithm: Algorithm, public_key: &str) -> DNSKEY {
+    DNSKEY::new(
+        true,
+        true,
+        false,
+        algorithm,
+        BASE64.decode(public_key.as_bytes()).unwrap(),
+    )
+}
+
+fn rrsig(algorithm: Algorithm, key_tag: u16, signature: &str) -> SIG {
+    SIG::new(
+        RecordType::MX,
+        algorithm,
+        2,
+        3600,
+        1440021600,
+        1438207200,
+        key_tag,
+        Name::from_str("example.com.").unwrap(),
+        BASE64.decode(signature.as_bytes()).unwrap(),
+    )
+}
+
+fn mx() -> Vec<Record> {
+    vec![Record::from_rdata(
+        Name::from_str("example.com.").unwrap(),
+        3600,
+        RData::MX(MX::new(10, Name::from_str("mail.example.com.").unwrap())),
+    )]
+}
+
+fn verify(dnskey: &DNSKEY, rrsig: &SIG) -> bool {
+    dnskey
+        .verify_rrsig(
+            &Name::from_str("example.com.").unwrap(),
+            DNSClass::IN,
+            rrsig,
+            &mx(),
+        )
+        .is_ok()
+}
+
+fn ds_digest(dnskey: &DNSKEY) -> String {
+    HEXLOWER.encode(
+        dnskey
+            .to_digest(&Name::from_str("example.com.").unwrap(), DigestType::SHA256)
+            .unwrap()
+            .as_ref(),
+    )
+}
+
+#[cfg(feature = "ring")]
+#[test]
+fn test_ed25519() {
+    // RFC 8080, section 6.1
+    let key = dnskey(
+        Algorithm::ED25519,
+        "l02Woi0iS8Aa25FQkUd9RMzZHJpBoRQwAQEX1SxZJA4=",
+    );
+    assert_eq!(key.calculate_key_tag().unwrap(), 3613);
+    assert_eq!(
+        ds_digest(&key),
+        "3aa5ab37efce57f737fc1627013fee07bdf241bd10f3b1964ab55c78e79a304b"
+    );
+
+    let sig = rrsig(
+        Algorithm::ED25519,
+        3613,
+        "oL9krJun7xfBOIWcGHi7mag5/hdZrKWw15jPGrHpjQeRAvTdszaPD+QLs3fx8A4M3e23mRZ9VrbpMngwcrqNAg==",
+    );
+    assert!(verify(&key, &sig));
+
+    // RFC 8080, section 6.2
+    let other_key = dnskey(
+        Algorithm::ED25519,
+        "zPnZ/QwEe7S8C5SPz2OfS5RR40ATk2/rYnE9xHIEijs=",
+    );
+    assert_eq!(other_key.calculate_key_tag().unwrap(), 35217);
+    assert_eq!(
+        ds_digest(&other_key),
+        "401781b934e392de492ec77ae2e15d70f6575a1c0bc59c5275c04ebe80c6614c"
+    );
+
+    let other_sig = rrsig(
+        Algorithm::ED25519,
+        35217,
+        "zXQ0bkYgQTEFyfLyi9QoiY6D8ZdYo4wyUhVioYZXFdT410QPRITQSqJSnzQoSm5poJ7gD7AQR0O7KuI5k2pcBg==",
+    );
+    assert!(verify(&other_key, &other_sig));
+
+    // the signature of one key must not verify with the other
+    assert!(!verify(&key, &other_sig));
+    assert!(!verify(&other_key, &sig));
+}
+
+#[cfg(feature = "openssl")]
+#[test]
+fn test_ed448() {
+    // RFC 8080, section 7.1
+    let key = dnskey(
+        Algorithm::ED448,
+        "3kgROaDjrh0H2iuixWBrc8g2EpBBLCdGzHmn+G2MpTPhpj/OiBVHHSfPodx1FYYUcJKm1MDpJtIA",
+    );
+    assert_eq!(key.calculate_key_tag().unwrap(), 9713);
+    assert_eq!(
+        ds_digest(&key),
+        "6ccf18d5bc5d7fc2fceb1d59d17321402f2aa8d368048db93dd811f5cb2b19c7"
+    );
+
+    let sig = rrsig(
+        Algorithm::ED448,
+        9713,
+        "3cPAHkmlnxcDHMyg7vFC34l0blBhuG1qpwLmjInI8w1CMB29FkEAIJUA0amxWndkmnBZ6SKiwZSAxGILn/NBtOXft0+Gj7FSvOKxE/07+4RQvE581N3Aj/JtIyaiYVdnYtyMWbSNyGEY2213WKsJlwEA",
+    );
+    assert!(verify(&key, &sig));
+
+    let mut signature = sig.sig().to_vec();
+    signature[10] = !signature[10];
+    assert!(!verify(&key, &sig.set_sig(signature)));
+}
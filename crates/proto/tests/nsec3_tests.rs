@@ -0,0 +1,377 @@
+#![cfg(feature = "dnssec")]
+#![cfg(any(feature = "openssl", feature = "ring"))]
+
+//! The examples of [RFC 5155](https://tools.ietf.org/html/rfc5155#appendix-B), DNSSEC Hashed Authenticated Denial of Existence
+
+use std::str::FromStr;
+
+use data_encoding::{BASE32_DNSSEC, HEXLOWER};
+
+use trust_dns_proto::op::{Query, ResponseCode};
+use trust_dns_proto::rr::dnssec::rdata::{DNSSECRData, NSEC3};
+use trust_dns_proto::rr::dnssec::Nsec3HashAlgorithm;
+use trust_dns_proto::rr::{Name, RData, Record, RecordType};
+use trust_dns_proto::xfer::dnssec_dns_handle::{verify_nsec3, MAX_NSEC3_ITERATIONS};
+use trust_dns_proto::xfer::DnssecStatus;
+
+/// The NSEC3 chain of the example zone in Appendix A, `NSEC3 1 1 12 aabbccdd`
+const CHAIN: &[(&str, &str, &[RecordType])] = &[
+    (
+        "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom",
+        "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+        &[
+            RecordType::NS,
+            RecordType::SOA,
+            RecordType::MX,
+            RecordType::RRSIG,
+            RecordType::DNSKEY,
+            RecordType::NSEC3PARAM,
+        ],
+    ),
+    (
+        "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+        "2vptu5timamqttgl4luu9kg21e0aor3s",
+        &[RecordType::A, RecordType::RRSIG],
+    ),
+    (
+        "2vptu5timamqttgl4luu9kg21e0aor3s",
+        "35mthgpgcu1qg68fab165klnsnk3dpvl",
+        &[RecordType::MX, RecordType::RRSIG],
+    ),
+    (
+        "35mthgpgcu1qg68fab165klnsnk3dpvl",
+        "b4um86eghhds6nea196smvmlo4ors995",
+        &[RecordType::NS, RecordType::DS, RecordType::RRSIG],
+    ),
+    (
+        "b4um86eghhds6nea196smvmlo4ors995",
+        "gjeqe526plbf1g8mklp59enfd789njgi",
+        &[RecordType::MX, RecordType::RRSIG],
+    ),
+    (
+        "gjeqe526plbf1g8mklp59enfd789njgi",
+        "ji6neoaepv8b5o6k4ev33abha8ht9fgc",
+        &[
+            RecordType::A,
+            RecordType::HINFO,
+            RecordType::AAAA,
+            RecordType::RRSIG,
+        ],
+    ),
+    (
+        "ji6neoaepv8b5o6k4ev33abha8ht9fgc",
+        "k8udemvp1j2f7eg6jebps17vp3n8i58h",
+        &[],
+    ),
+    (
+        "k8udemvp1j2f7eg6jebps17vp3n8i58h",
+        "kohar7mbb8dc2ce8a9qvl8hon4k53uhi",
+        &[],
+    ),
+    (
+        "kohar7mbb8dc2ce8a9qvl8hon4k53uhi",
+        "q04jkcevqvmu85r014c7dkba38o0ji5r",
+        &[RecordType::A, RecordType::RRSIG],
+    ),
+    (
+        "q04jkcevqvmu85r014c7dkba38o0ji5r",
+        "r53bq7cc2uvmubfu5ocmm6pers9tk9en",
+        &[RecordType::A, RecordType::RRSIG],
+    ),
+    (
+        "r53bq7cc2uvmubfu5ocmm6pers9tk9en",
+        "t644ebqk9bibcna874givr6joj62mlhv",
+        &[RecordType::MX, RecordType::RRSIG],
+    ),
+    (
+        "t644ebqk9bibcna874givr6joj62mlhv",
+        "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom",
+        &[
+            RecordType::A,
+            RecordType::HINFO,
+            RecordType::AAAA,
+            RecordType::RRSIG,
+        ],
+    ),
+];
+
+fn nsec3(owner: &str, opt_out: bool, iterations: u16) -> Record {
+    let (_, next, types) = CHAIN
+        .iter()
+        .find(|(hash, _, _)| *hash == owner)
+        .expect("not in the chain");
+
+    Record::from_rdata(
+        Name::from_str(&format!("{}.example.", owner)).unwrap(),
+        3600,
+        RData::DNSSEC(DNSSECRData::NSEC3(NSEC3::new(
+            Nsec3HashAlgorithm::SHA1,
+            opt_out,
+            iterations,
+            HEXLOWER.decode(b"aabbccdd").unwrap(),
+            BASE32_DNSSEC.decode(next.as_bytes()).unwrap(),
+            types.to_vec(),
+        ))),
+    )
+}
+
+fn verify(
+    name: &str,
+    query_type: RecordType,
+    response_code: ResponseCode,
+    nsec3s: &[Record],
+) -> Option<DnssecStatus> {
+    let query = Query::query(Name::from_str(name).unwrap(), query_type);
+    let nsec3s = nsec3s.iter().collect::<Vec<_>>();
+
+    verify_nsec3(
+        &query,
+        &Name::from_str("example.").unwrap(),
+        response_code,
+        &nsec3s,
+    )
+    .ok()
+}
+
+/// B.1.  Name Error
+#[test]
+fn test_name_error() {
+    let nsec3s = [
+        // closest encloser x.w.example
+        nsec3("b4um86eghhds6nea196smvmlo4ors995", false, 12),
+        // covers the next closer name c.x.w.example
+        nsec3("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom", false, 12),
+        // covers the wildcard *.x.w.example
+        nsec3("35mthgpgcu1qg68fab165klnsnk3dpvl", false, 12),
+    ];
+
+    assert_eq!(
+        verify(
+            "a.c.x.w.example.",
+            RecordType::A,
+            ResponseCode::NXDomain,
+            &nsec3s
+        ),
+        Some(DnssecStatus::Secure)
+    );
+
+    // the name doesn't exist, so it can't be NOERROR
+    assert_eq!(
+        verify(
+            "a.c.x.w.example.",
+            RecordType::A,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        None
+    );
+
+    // each record is necessary for the proof
+    for i in 0..nsec3s.len() {
+        let mut partial = nsec3s.to_vec();
+        partial.remove(i);
+        assert_eq!(
+            verify(
+                "a.c.x.w.example.",
+                RecordType::A,
+                ResponseCode::NXDomain,
+                &partial
+            ),
+            None
+        );
+    }
+
+    // the next closer name may be an unsigned delegation, as in the original opt-out zone
+    let nsec3s = [
+        nsec3("b4um86eghhds6nea196smvmlo4ors995", true, 12),
+        nsec3("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom", true, 12),
+        nsec3("35mthgpgcu1qg68fab165klnsnk3dpvl", true, 12),
+    ];
+
+    assert_eq!(
+        verify(
+            "a.c.x.w.example.",
+            RecordType::A,
+            ResponseCode::NXDomain,
+            &nsec3s
+        ),
+        Some(DnssecStatus::Insecure)
+    );
+}
+
+/// B.2.  No Data Error
+#[test]
+fn test_no_data() {
+    let nsec3s = [nsec3("2t7b4g4vsa5smi47k61mv5bv1a22bojr", false, 12)];
+
+    assert_eq!(
+        verify(
+            "ns1.example.",
+            RecordType::MX,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        Some(DnssecStatus::Secure)
+    );
+
+    // the type exists at the name
+    assert_eq!(
+        verify(
+            "ns1.example.",
+            RecordType::A,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        None
+    );
+
+    // the name exists, so it can't be NXDOMAIN
+    assert_eq!(
+        verify(
+            "ns1.example.",
+            RecordType::MX,
+            ResponseCode::NXDomain,
+            &nsec3s
+        ),
+        None
+    );
+}
+
+/// B.2.1.  No Data Error, Empty Non-Terminal
+#[test]
+fn test_no_data_empty_non_terminal() {
+    let nsec3s = [nsec3("ji6neoaepv8b5o6k4ev33abha8ht9fgc", false, 12)];
+
+    assert_eq!(
+        verify(
+            "y.w.example.",
+            RecordType::A,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        Some(DnssecStatus::Secure)
+    );
+}
+
+/// B.5.  Wildcard No Data Error
+#[test]
+fn test_wildcard_no_data() {
+    let nsec3s = [
+        // closest encloser w.example
+        nsec3("k8udemvp1j2f7eg6jebps17vp3n8i58h", false, 12),
+        // covers the next closer name z.w.example
+        nsec3("q04jkcevqvmu85r014c7dkba38o0ji5r", false, 12),
+        // matches the wildcard *.w.example
+        nsec3("r53bq7cc2uvmubfu5ocmm6pers9tk9en", false, 12),
+    ];
+
+    assert_eq!(
+        verify(
+            "a.z.w.example.",
+            RecordType::AAAA,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        Some(DnssecStatus::Secure)
+    );
+
+    // the wildcard would have answered
+    assert_eq!(
+        verify(
+            "a.z.w.example.",
+            RecordType::MX,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        None
+    );
+}
+
+/// 8.6.  Validating No Data Responses, QTYPE is DS
+#[test]
+fn test_ds_opt_out() {
+    let nsec3s = [
+        // closest encloser example
+        nsec3("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom", true, 12),
+        // covers the unsigned delegation c.example
+        nsec3("35mthgpgcu1qg68fab165klnsnk3dpvl", true, 12),
+    ];
+
+    assert_eq!(
+        verify("c.example.", RecordType::DS, ResponseCode::NoError, &nsec3s),
+        Some(DnssecStatus::Insecure)
+    );
+
+    // without opt-out the proof of the wildcard is missing
+    let nsec3s = [
+        nsec3("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom", false, 12),
+        nsec3("35mthgpgcu1qg68fab165klnsnk3dpvl", false, 12),
+    ];
+
+    assert_eq!(
+        verify("c.example.", RecordType::DS, ResponseCode::NoError, &nsec3s),
+        None
+    );
+}
+
+#[test]
+fn test_iterations() {
+    // the hashes don't match with the wrong number of iterations
+    let nsec3s = [nsec3("2t7b4g4vsa5smi47k61mv5bv1a22bojr", false, 11)];
+    assert_eq!(
+        verify(
+            "ns1.example.",
+            RecordType::MX,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        None
+    );
+
+    // above the limit nothing is hashed
+    let nsec3s = [nsec3(
+        "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+        false,
+        MAX_NSEC3_ITERATIONS + 1,
+    )];
+    assert_eq!(
+        verify(
+            "ns1.example.",
+            RecordType::MX,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        Some(DnssecStatus::Insecure)
+    );
+
+    // all records must use the same parameters
+    let nsec3s = [
+        nsec3("b4um86eghhds6nea196smvmlo4ors995", false, 12),
+        nsec3("0p9mhaveqvm6t7vbl5lop2u3t2rp3tom", false, 12),
+        nsec3("35mthgpgcu1qg68fab165klnsnk3dpvl", false, 13),
+    ];
+    assert_eq!(
+        verify(
+            "a.c.x.w.example.",
+            RecordType::A,
+            ResponseCode::NXDomain,
+            &nsec3s
+        ),
+        None
+    );
+}
+
+#[test]
+fn test_out_of_zone() {
+    let nsec3s = [nsec3("2t7b4g4vsa5smi47k61mv5bv1a22bojr", false, 12)];
+
+    assert_eq!(
+        verify(
+            "ns1.example.com.",
+            RecordType::MX,
+            ResponseCode::NoError,
+            &nsec3s
+        ),
+        None
+    );
+}
@@ -0,0 +1,119 @@
+#![cfg(feature = "serde-config")]
+
+//! JSON round trips of records with the `serde-config` feature
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::str::FromStr;
+
+use trust_dns_proto::rr::rdata::caa::KeyValue;
+use trust_dns_proto::rr::rdata::sshfp::{Algorithm, FingerprintType};
+use trust_dns_proto::rr::rdata::tlsa::{CertUsage, Matching, Selector};
+use trust_dns_proto::rr::rdata::{CAA, MX, NAPTR, NULL, SOA, SRV, SSHFP, TLSA, TXT, URI};
+use trust_dns_proto::rr::{DNSClass, Name, RData, Record, RecordType};
+
+fn name(name: &str) -> Name {
+    Name::from_str(name).unwrap()
+}
+
+fn round_trip(rdata: RData) -> serde_json::Value {
+    let mut record = Record::from_rdata(name("www.example.com."), 300, rdata);
+    record.set_dns_class(DNSClass::CH);
+
+    let json = serde_json::to_string(&record).expect("failed to serialize");
+    let read: Record = serde_json::from_str(&json).expect("failed to deserialize");
+    assert_eq!(read, record, "{}", json);
+
+    serde_json::from_str(&json).unwrap()
+}
+
+#[test]
+fn test_common_types() {
+    let rdatas = vec![
+        RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        RData::AAAA(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+        RData::CNAME(name("cname.example.com.")),
+        RData::NS(name("ns.example.com.")),
+        RData::PTR(name("ptr.example.com.")),
+        RData::MX(MX::new(10, name("mx.example.com."))),
+        RData::SOA(SOA::new(
+            name("ns.example.com."),
+            name("hostmaster.example.com."),
+            2022010101,
+            7200,
+            3600,
+            1209600,
+            300,
+        )),
+        RData::SRV(SRV::new(1, 2, 443, name("srv.example.com."))),
+        RData::TXT(TXT::new(vec!["v=spf1 -all".to_string(), "two".to_string()])),
+        RData::CAA(CAA::new_issue(
+            false,
+            Some(name("ca.example.net")),
+            vec![KeyValue::new("account", "230123")],
+        )),
+        RData::TLSA(TLSA::new(
+            CertUsage::DomainIssued,
+            Selector::Spki,
+            Matching::Sha256,
+            vec![0xde, 0xad, 0xbe, 0xef],
+        )),
+        RData::SSHFP(SSHFP::new(
+            Algorithm::Ed25519,
+            FingerprintType::SHA256,
+            vec![0xde, 0xad, 0xbe, 0xef],
+        )),
+        RData::NAPTR(NAPTR::new(
+            100,
+            10,
+            b"S".to_vec().into_boxed_slice(),
+            b"SIP+D2U".to_vec().into_boxed_slice(),
+            b"".to_vec().into_boxed_slice(),
+            name("_sip._udp.example.com."),
+        )),
+        RData::URI(URI::new(10, 1, "ftp://ftp.example.com/public".to_string()).unwrap()),
+        RData::NULL(NULL::with(vec![0, 1, 2])),
+    ];
+
+    for rdata in rdatas {
+        let record_type = rdata.to_record_type();
+        let json = round_trip(rdata);
+
+        // the rdata is tagged with the record type
+        assert!(
+            json["rdata"].get(record_type.to_string()).is_some(),
+            "{}",
+            json
+        );
+    }
+}
+
+#[test]
+fn test_name_as_string() {
+    let json = round_trip(RData::CNAME(name("cname.example.com.")));
+
+    assert_eq!(json["name_labels"], "www.example.com.");
+    assert_eq!(json["rdata"]["CNAME"], "cname.example.com.");
+}
+
+#[test]
+fn test_unknown_as_hex() {
+    let json = round_trip(RData::Unknown {
+        code: 65280,
+        rdata: NULL::with(vec![0xde, 0xad, 0xbe, 0xef]),
+    });
+
+    assert_eq!(json["rr_type"], serde_json::json!({ "Unknown": 65280 }));
+    assert_eq!(json["rdata"]["Unknown"]["rdata"], "DEADBEEF");
+
+    // lowercase is accepted as well
+    let json = json.to_string().replace("DEADBEEF", "deadbeef");
+    let record: Record = serde_json::from_str(&json).unwrap();
+    assert_eq!(record.rr_type(), RecordType::Unknown(65280));
+    assert_eq!(
+        record.data(),
+        Some(&RData::Unknown {
+            code: 65280,
+            rdata: NULL::with(vec![0xde, 0xad, 0xbe, 0xef]),
+        })
+    );
+}
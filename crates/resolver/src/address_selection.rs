@@ -0,0 +1,442 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Destination address selection, [RFC 6724](https://tools.ietf.org/html/rfc6724#section-6)
+//!
+//! The source address for each destination is the one the OS would use, this is discovered by connecting
+//!  (no packets are sent) a UDP socket to the destination, the same as `getaddrinfo` does. This means Rule 3,
+//!  avoid deprecated addresses, is already applied by the OS source address selection. Rules 4 and 7 are not
+//!  applicable, as there is no information about home addresses or encapsulating transitions.
+
+use std::cmp::Ordering;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::time::{Duration, Instant};
+
+use lru_cache::LruCache;
+use parking_lot::Mutex;
+
+/// Any port will do, connecting a UDP socket only consults the routing table
+const PROBE_PORT: u16 = 53;
+
+/// The number of destinations for which the source address is remembered
+const SOURCE_CACHE_SIZE: usize = 256;
+
+/// How long the source address of a destination is remembered, routes and local addresses may change
+const SOURCE_CACHE_TTL: Duration = Duration::from_secs(60);
+
+lazy_static! {
+    /// The source addresses of recent destinations, with the time they were probed
+    static ref SOURCE_CACHE: Mutex<LruCache<IpAddr, (Instant, Option<IpAddr>)>> =
+        Mutex::new(LruCache::new(SOURCE_CACHE_SIZE));
+}
+
+/// Sorts the items by their address per RFC 6724, the relative order of otherwise equal addresses is retained
+pub(crate) fn sort_by_address<T, A>(items: &mut Vec<T>, address: A)
+where
+    A: Fn(&T) -> IpAddr,
+{
+    sort_by_address_with(items, address, cached_source_address)
+}
+
+/// Sorts the items by their address per RFC 6724, with the source address for each destination from `source`
+fn sort_by_address_with<T, A, S>(items: &mut Vec<T>, address: A, source: S)
+where
+    A: Fn(&T) -> IpAddr,
+    S: Fn(IpAddr) -> Option<IpAddr>,
+{
+    if items.len() < 2 {
+        return;
+    }
+
+    let mut destinations = items
+        .drain(..)
+        .map(|item| {
+            let destination = address(&item);
+            (item, destination, source(destination))
+        })
+        .collect::<Vec<_>>();
+
+    destinations
+        .sort_by(|(_, a, source_a), (_, b, source_b)| compare(*a, *source_a, *b, *source_b));
+
+    items.extend(destinations.into_iter().map(|(item, ..)| item));
+}
+
+/// The source address of `source_address`, probed at most once per `SOURCE_CACHE_TTL` for each destination
+///
+/// Sorting runs for every lookup, including answers from the cache, which should not cost system calls.
+fn cached_source_address(destination: IpAddr) -> Option<IpAddr> {
+    let now = Instant::now();
+    if let Some((probed, source)) = SOURCE_CACHE.lock().get_mut(&destination) {
+        if now.saturating_duration_since(*probed) < SOURCE_CACHE_TTL {
+            return *source;
+        }
+    }
+
+    let source = source_address(destination);
+    SOURCE_CACHE.lock().insert(destination, (now, source));
+    source
+}
+
+/// The source address the OS would select for the destination, None if the destination is unreachable
+fn source_address(destination: IpAddr) -> Option<IpAddr> {
+    let bind_addr = match destination {
+        IpAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+        IpAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+    };
+
+    let socket = UdpSocket::bind(bind_addr).ok()?;
+    socket.connect((destination, PROBE_PORT)).ok()?;
+    socket.local_addr().ok().map(|addr| addr.ip())
+}
+
+/// Compares two destinations, `Ordering::Less` if `a` is preferred
+///
+/// ```text
+/// 6.  Destination Address Selection
+///
+///    Rule 1:  Avoid unusable destinations.
+///    Rule 2:  Prefer matching scope.
+///    Rule 3:  Avoid deprecated addresses.
+///    Rule 4:  Prefer home addresses.
+///    Rule 5:  Prefer matching label.
+///    Rule 6:  Prefer higher precedence.
+///    Rule 7:  Prefer native transport.
+///    Rule 8:  Prefer smaller scope.
+///    Rule 9:  Use longest matching prefix.
+///    Rule 10:  Otherwise, leave the order unchanged.
+/// ```
+fn compare(a: IpAddr, source_a: Option<IpAddr>, b: IpAddr, source_b: Option<IpAddr>) -> Ordering {
+    // Rule 1
+    let (source_a, source_b) = match (source_a, source_b) {
+        (Some(source_a), Some(source_b)) => (source_a, source_b),
+        (Some(_), None) => return Ordering::Less,
+        (None, Some(_)) => return Ordering::Greater,
+        (None, None) => return Ordering::Equal,
+    };
+
+    let policy_a = Policy::of(a);
+    let policy_b = Policy::of(b);
+
+    // Rule 2
+    let matching_scope_a = scope(a) == scope(source_a);
+    let matching_scope_b = scope(b) == scope(source_b);
+    if matching_scope_a != matching_scope_b {
+        return preferred(matching_scope_a);
+    }
+
+    // Rule 5
+    let matching_label_a = policy_a.label == Policy::of(source_a).label;
+    let matching_label_b = policy_b.label == Policy::of(source_b).label;
+    if matching_label_a != matching_label_b {
+        return preferred(matching_label_a);
+    }
+
+    // Rule 6
+    if policy_a.precedence != policy_b.precedence {
+        return policy_b.precedence.cmp(&policy_a.precedence);
+    }
+
+    // Rule 8
+    if scope(a) != scope(b) {
+        return scope(a).cmp(&scope(b));
+    }
+
+    // Rule 9, only IPv6 as the prefix of IPv4 addresses is not meaningful for this
+    if let (IpAddr::V6(a), IpAddr::V6(source_a), IpAddr::V6(b), IpAddr::V6(source_b)) =
+        (a, source_a, b, source_b)
+    {
+        return common_prefix_len(b, source_b).cmp(&common_prefix_len(a, source_a));
+    }
+
+    // Rule 10
+    Ordering::Equal
+}
+
+fn preferred(a: bool) -> Ordering {
+    if a {
+        Ordering::Less
+    } else {
+        Ordering::Greater
+    }
+}
+
+/// Scope values, as defined by [RFC 4291](https://tools.ietf.org/html/rfc4291#section-2.7) for multicast
+///
+/// ```text
+/// 3.1.  Scope Comparisons
+///
+///    Multicast destination addresses have a 4-bit scope field that
+///    controls the propagation of the multicast packet.  The IPv6
+///    addressing architecture defines scope field values for interface-
+///    local (0x1), link-local (0x2), admin-local (0x4), site-local (0x5),
+///    organization-local (0x8), and global (0xE) scopes [RFC4291].
+///
+///    Use of the source address selection algorithm in the presence of
+///    multicast destination addresses requires the comparison of a unicast
+///    address scope with a multicast address scope.  We map unicast link-
+///    local to multicast link-local, unicast site-local to multicast site-
+///    local, and unicast global scope to multicast global scope.  For
+///    example, unicast site-local is equal to multicast site-local, which
+///    is smaller than multicast organization-local, which is smaller than
+///    unicast global, which is equal to multicast global.
+///
+/// 3.2.  IPv4 Addresses and IPv4-Mapped Addresses
+///
+///    IPv4 addresses are assigned scopes as follows.  IPv4 auto-
+///    configuration addresses [RFC3927], which have the prefix 169.254/16,
+///    are assigned link-local scope.  IPv4 loopback addresses ([RFC1122],
+///    Section 3.2.1.3), which have the prefix 127/8, are assigned link-
+///    local scope (analogously to the treatment of the IPv6 loopback
+///    address ([RFC4291], Section 2.5.3)).  Other IPv4 addresses are
+///    assigned global scope.
+/// ```
+fn scope(addr: IpAddr) -> u8 {
+    const LINK_LOCAL: u8 = 0x2;
+    const SITE_LOCAL: u8 = 0x5;
+    const GLOBAL: u8 = 0xe;
+
+    match addr {
+        IpAddr::V4(addr) if addr.is_loopback() || addr.is_link_local() => LINK_LOCAL,
+        IpAddr::V4(_) => GLOBAL,
+        IpAddr::V6(addr) => {
+            let first = addr.segments()[0];
+
+            if addr.is_multicast() {
+                (first & 0x000f) as u8
+            } else if addr.is_loopback() || first & 0xffc0 == 0xfe80 {
+                LINK_LOCAL
+            } else if first & 0xffc0 == 0xfec0 {
+                SITE_LOCAL
+            } else {
+                GLOBAL
+            }
+        }
+    }
+}
+
+/// The length of the common prefix, up to the length of the prefix portion (64 bits) of the source
+fn common_prefix_len(destination: Ipv6Addr, source: Ipv6Addr) -> u32 {
+    let common = (u128::from(destination) ^ u128::from(source)).leading_zeros();
+    common.min(64)
+}
+
+/// An entry of the policy table
+struct Policy {
+    prefix: Ipv6Addr,
+    prefix_len: u32,
+    precedence: u8,
+    label: u8,
+}
+
+/// The default policy table, ordered by the length of the prefix, longest first
+///
+/// ```text
+/// 2.1.  Policy Table
+///
+///       Prefix        Precedence Label
+///       ::1/128               50     0
+///       ::/0                  40     1
+///       ::ffff:0:0/96         35     4
+///       2002::/16             30     2
+///       2001::/32              5     5
+///       fc00::/7               3    13
+///       ::/96                  1     3
+///       fec0::/10              1    11
+///       3ffe::/16              1    12
+/// ```
+const POLICY_TABLE: &[Policy] = &[
+    Policy::new(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1), 128, 50, 0),
+    Policy::new(Ipv6Addr::new(0, 0, 0, 0, 0, 0xffff, 0, 0), 96, 35, 4),
+    Policy::new(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0), 96, 1, 3),
+    Policy::new(Ipv6Addr::new(0x2001, 0, 0, 0, 0, 0, 0, 0), 32, 5, 5),
+    Policy::new(Ipv6Addr::new(0x2002, 0, 0, 0, 0, 0, 0, 0), 16, 30, 2),
+    Policy::new(Ipv6Addr::new(0x3ffe, 0, 0, 0, 0, 0, 0, 0), 16, 1, 12),
+    Policy::new(Ipv6Addr::new(0xfec0, 0, 0, 0, 0, 0, 0, 0), 10, 1, 11),
+    Policy::new(Ipv6Addr::new(0xfc00, 0, 0, 0, 0, 0, 0, 0), 7, 3, 13),
+    Policy::new(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0), 0, 40, 1),
+];
+
+impl Policy {
+    const fn new(prefix: Ipv6Addr, prefix_len: u32, precedence: u8, label: u8) -> Self {
+        Self {
+            prefix,
+            prefix_len,
+            precedence,
+            label,
+        }
+    }
+
+    /// Returns the entry with the longest matching prefix, IPv4 addresses are looked up by their IPv4-mapped address
+    fn of(addr: IpAddr) -> &'static Self {
+        let addr = match addr {
+            IpAddr::V4(addr) => addr.to_ipv6_mapped(),
+            IpAddr::V6(addr) => addr,
+        };
+
+        POLICY_TABLE
+            .iter()
+            .find(|policy| {
+                policy.prefix_len == 0
+                    || (u128::from(addr) ^ u128::from(policy.prefix)) >> (128 - policy.prefix_len)
+                        == 0
+            })
+            .expect("::/0 matches all addresses")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sorts the candidates, with sources as the local addresses, each destination uses the source with the
+    ///  same family and the longest matching prefix
+    fn sorted(candidates: &[&str], sources: &[&str]) -> Vec<IpAddr> {
+        let sources = sources
+            .iter()
+            .map(|s| s.parse::<IpAddr>().unwrap())
+            .collect::<Vec<_>>();
+        let mut addresses = candidates
+            .iter()
+            .map(|s| s.parse::<IpAddr>().unwrap())
+            .collect::<Vec<_>>();
+
+        sort_by_address_with(
+            &mut addresses,
+            |addr| *addr,
+            |destination| {
+                sources
+                    .iter()
+                    .filter(|source| source.is_ipv4() == destination.is_ipv4())
+                    .max_by_key(|source| match (destination, source) {
+                        (IpAddr::V6(d), IpAddr::V6(s)) => common_prefix_len(d, *s),
+                        _ => 0,
+                    })
+                    .cloned()
+            },
+        );
+
+        addresses
+    }
+
+    fn ips(addresses: &[&str]) -> Vec<IpAddr> {
+        addresses.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_scope() {
+        assert_eq!(scope("127.0.0.1".parse().unwrap()), 0x2);
+        assert_eq!(scope("169.254.13.78".parse().unwrap()), 0x2);
+        assert_eq!(scope("10.1.2.3".parse().unwrap()), 0xe);
+        assert_eq!(scope("::1".parse().unwrap()), 0x2);
+        assert_eq!(scope("fe80::1".parse().unwrap()), 0x2);
+        assert_eq!(scope("fec0::1".parse().unwrap()), 0x5);
+        assert_eq!(scope("ff05::1".parse().unwrap()), 0x5);
+        assert_eq!(scope("2001:db8::1".parse().unwrap()), 0xe);
+    }
+
+    #[test]
+    fn test_policy() {
+        assert_eq!(Policy::of("::1".parse().unwrap()).precedence, 50);
+        assert_eq!(Policy::of("10.1.2.3".parse().unwrap()).label, 4);
+        assert_eq!(Policy::of("2002:c633:6401::1".parse().unwrap()).label, 2);
+        assert_eq!(Policy::of("2001::1".parse().unwrap()).label, 5);
+        assert_eq!(Policy::of("2001:db8::1".parse().unwrap()).label, 1);
+        assert_eq!(Policy::of("fd00::1".parse().unwrap()).label, 13);
+        assert_eq!(Policy::of("::10.1.2.3".parse().unwrap()).label, 3);
+    }
+
+    /// The examples of [RFC 6724, section 10.2](https://tools.ietf.org/html/rfc6724#section-10.2)
+    #[test]
+    fn test_rfc_examples() {
+        // Prefer matching scope.
+        assert_eq!(
+            sorted(
+                &["2001:db8:1::1", "198.51.100.121"],
+                &["2001:db8:1::2", "fe80::1", "169.254.13.78"]
+            ),
+            ips(&["2001:db8:1::1", "198.51.100.121"])
+        );
+        assert_eq!(
+            sorted(
+                &["2001:db8:1::1", "198.51.100.121"],
+                &["fe80::1", "198.51.100.117"]
+            ),
+            ips(&["198.51.100.121", "2001:db8:1::1"])
+        );
+
+        // Prefer higher precedence.
+        assert_eq!(
+            sorted(
+                &["10.1.2.3", "2001:db8:1::1"],
+                &["2001:db8:1::2", "fe80::1", "10.1.2.4"]
+            ),
+            ips(&["2001:db8:1::1", "10.1.2.3"])
+        );
+
+        // Prefer smaller scope.
+        assert_eq!(
+            sorted(&["2001:db8:1::1", "fe80::1"], &["2001:db8:1::2", "fe80::2"]),
+            ips(&["fe80::1", "2001:db8:1::1"])
+        );
+
+        // Longest matching prefix.
+        assert_eq!(
+            sorted(
+                &["2001:db8:3ffe::1", "2001:db8:1::1"],
+                &["2001:db8:1::2", "2001:db8:3f44::2", "fe80::2"]
+            ),
+            ips(&["2001:db8:1::1", "2001:db8:3ffe::1"])
+        );
+
+        // Prefer matching label.
+        assert_eq!(
+            sorted(
+                &["2002:c633:6401::1", "2001:db8:1::1"],
+                &["2002:c633:6401::2", "fe80::2"]
+            ),
+            ips(&["2002:c633:6401::1", "2001:db8:1::1"])
+        );
+        assert_eq!(
+            sorted(
+                &["2002:c633:6401::1", "2001:db8:1::1"],
+                &["2002:c633:6401::2", "2001:db8:1::2", "fe80::2"]
+            ),
+            ips(&["2001:db8:1::1", "2002:c633:6401::1"])
+        );
+    }
+
+    #[test]
+    fn test_source_address_cached() {
+        let localhost = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        assert_eq!(cached_source_address(localhost), Some(localhost));
+
+        let (probed, source) = *SOURCE_CACHE.lock().get_mut(&localhost).unwrap();
+        assert_eq!(source, Some(localhost));
+
+        // the second lookup is answered from the cache
+        assert_eq!(cached_source_address(localhost), Some(localhost));
+        assert_eq!(SOURCE_CACHE.lock().get_mut(&localhost).unwrap().0, probed);
+    }
+
+    #[test]
+    fn test_unusable_last() {
+        // no IPv6 source, i.e. no IPv6 connectivity
+        assert_eq!(
+            sorted(&["2001:db8:1::1", "10.1.2.3"], &["10.1.2.4"]),
+            ips(&["10.1.2.3", "2001:db8:1::1"])
+        );
+    }
+
+    #[test]
+    fn test_stable() {
+        assert_eq!(
+            sorted(&["10.1.2.3", "10.1.2.5", "10.1.2.4"], &["10.1.2.9"]),
+            ips(&["10.1.2.3", "10.1.2.5", "10.1.2.4"])
+        );
+    }
+}
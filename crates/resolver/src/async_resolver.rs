@@ -8,27 +8,31 @@
 //! Structs for creating and using a AsyncResolver
 use std::fmt;
 use std::future::Future;
-use std::net::IpAddr;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv6Addr};
 use std::sync::Arc;
+use std::time::Duration;
 
-use futures_util::{self, future};
+use futures_util::{self, future, FutureExt};
+use ipnet::Ipv6Net;
 use proto::error::ProtoResult;
 use proto::op::Query;
 use proto::rr::domain::usage::ONION;
 use proto::rr::domain::TryParseIp;
-use proto::rr::{IntoName, Name, Record, RecordType};
+use proto::rr::{DNSClass, IntoName, Name, Record, RecordType};
 use proto::xfer::{DnsRequestOptions, RetryDnsHandle};
-use proto::DnsHandle;
+use proto::{DnsHandle, Time};
 
-use crate::caching_client::CachingClient;
+use crate::caching_client::{CachingClient, RefreshSpawner};
 use crate::config::{ResolverConfig, ResolverOpts};
-use crate::dns_lru::{self, DnsLru};
+use crate::dns64;
+use crate::dns_lru::{self, CacheEvent, CacheStats, DnsLru};
 use crate::error::*;
 use crate::lookup::{self, Lookup, LookupEither, LookupFuture};
 use crate::lookup_ip::{LookupIp, LookupIpFuture};
 use crate::name_server::{
     ConnectionProvider, GenericConnection, GenericConnectionProvider, NameServerPool,
-    RuntimeProvider,
+    ResponseObserver, ResponseObserverSlot, RuntimeProvider,
 };
 #[cfg(feature = "tokio-runtime")]
 use crate::name_server::{TokioConnection, TokioConnectionProvider, TokioHandle};
@@ -67,6 +71,7 @@ pub struct AsyncResolver<C: DnsHandle<Error = ResolveError>, P: ConnectionProvid
     options: ResolverOpts,
     client_cache: CachingClient<LookupEither<C, P>, ResolveError>,
     hosts: Option<Arc<Hosts>>,
+    response_observer: Arc<ResponseObserverSlot>,
 }
 
 /// An AsyncResolver used with Tokio
@@ -91,22 +96,7 @@ macro_rules! lookup_fn {
                 }
             };
 
-            let mut request_opts = DnsRequestOptions::default();
-            request_opts.use_edns = self.options.edns0;
-
-            self.inner_lookup(name, $r, request_opts).await
-        }
-    };
-    ($p:ident, $l:ty, $r:path, $t:ty) => {
-        /// Performs a lookup for the associated type.
-        ///
-        /// # Arguments
-        ///
-        /// * `query` - a type which can be converted to `Name` via `From`.
-        pub async fn $p(&self, query: $t) -> Result<$l, ResolveError> {
-            let name = Name::from(query);
-            self.inner_lookup(name, $r, DnsRequestOptions::default())
-                .await
+            self.inner_lookup(name, $r, self.request_options()).await
         }
     };
 }
@@ -214,14 +204,29 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
         options: ResolverOpts,
         conn_provider: P,
     ) -> Result<Self, ResolveError> {
-        let pool = NameServerPool::from_config_with_provider(&config, &options, conn_provider);
+        if let Some(ref prefix) = options.dns64_prefix {
+            dns64::check_prefix(prefix)?;
+        }
+
+        let pool =
+            NameServerPool::from_config_with_provider(&config, &options, conn_provider.clone());
+        let response_observer = pool.response_observer();
         let either;
-        let client = RetryDnsHandle::new(pool, options.attempts);
+        let client = RetryDnsHandle::new(pool, options.attempts)
+            .with_backoff::<P::Time>(options.retry_backoff);
         if options.validate {
             #[cfg(feature = "dnssec")]
             {
                 use proto::xfer::DnssecDnsHandle;
-                either = LookupEither::Secure(DnssecDnsHandle::new(client));
+                let handle = match config.trust_anchor() {
+                    Some(trust_anchor) => {
+                        DnssecDnsHandle::with_trust_anchor(client, Arc::clone(trust_anchor))
+                    }
+                    None => DnssecDnsHandle::new(client),
+                };
+                either = LookupEither::Secure(
+                    handle.retain_dnssec_records(options.retain_dnssec_records),
+                );
             }
 
             #[cfg(not(feature = "dnssec"))]
@@ -242,11 +247,20 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
 
         trace!("handle passed back");
         let lru = DnsLru::new(options.cache_size, dns_lru::TtlConfig::from_opts(&options));
+        let client_cache = CachingClient::with_cache(lru, either, options.preserve_intermediates)
+            .retain_dnssec_records(options.retain_dnssec_records)
+            .qname_minimization(options.qname_minimization)
+            .max_cname_chain(options.max_cname_chain)
+            .address_filter(options.filter_addresses)
+            .refresh_spawner(Some(RefreshSpawner::new(move |refresh| {
+                conn_provider.spawn_bg(refresh)
+            })));
         Ok(Self {
             config,
             options,
-            client_cache: CachingClient::with_cache(lru, either, options.preserve_intermediates),
+            client_cache,
             hosts,
+            response_observer,
         })
     }
 
@@ -264,6 +278,58 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
         Self::new_with_conn(config, options, conn_provider)
     }
 
+    /// Returns the hit, miss, insert and eviction counters and the number of entries of the cache
+    ///
+    /// The counters are shared by all clones of this resolver, reading them does not lock the cache.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.client_cache.cache_stats()
+    }
+
+    /// Sets a listener which is called for every insertion into and eviction from the cache, e.g. to export metrics
+    ///
+    /// The listener is shared by all clones of this resolver and replaces any previously set listener. It is called
+    ///  from within lookups, so it should return quickly, and it must not set or clear the listener itself.
+    pub fn set_cache_listener<F>(&self, listener: F)
+    where
+        F: Fn(CacheEvent<'_>) + Send + Sync + 'static,
+    {
+        self.client_cache.set_cache_listener(listener)
+    }
+
+    /// Removes the listener set with [`Self::set_cache_listener`]
+    pub fn clear_cache_listener(&self) {
+        self.client_cache.clear_cache_listener()
+    }
+
+    /// Writes the current entries of the cache to the writer, returning the number of entries written
+    ///
+    /// Together with [`Self::load_cache`] this allows a restarted process to start with a warm cache.
+    pub fn dump_cache<W: Write>(&self, writer: W) -> Result<usize, ResolveError> {
+        self.client_cache.dump(writer)
+    }
+
+    /// Loads the entries written by [`Self::dump_cache`], returning the number of entries loaded
+    ///
+    /// The TTLs are reduced by the time elapsed since the dump, entries which have expired since are dropped.
+    pub fn load_cache<R: Read>(&self, reader: R) -> Result<usize, ResolveError> {
+        self.client_cache.load(reader)
+    }
+
+    /// Sets an observer which is called for every response received from a name server, e.g. to export metrics
+    ///
+    /// The observer is called with the query, the name server which answered, the response and the round trip time.
+    ///  Lookups answered from the cache or the hosts file don't reach a name server and are not observed. The
+    ///  observer is shared by all clones of this resolver and replaces any previously set observer. It is called from
+    ///  within lookups, so it should return quickly, and it must not set or clear the observer itself.
+    pub fn set_response_observer(&self, observer: Arc<dyn ResponseObserver>) {
+        self.response_observer.set(Some(observer))
+    }
+
+    /// Removes the observer set with [`Self::set_response_observer`]
+    pub fn clear_response_observer(&self) {
+        self.response_observer.set(None)
+    }
+
     /// Generic lookup for any RecordType
     ///
     /// *WARNING* this interface may change in the future, see if one of the specializations would be better.
@@ -272,6 +338,7 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
     ///
     /// * `name` - name of the record to lookup, if name is not a valid domain name, an error will be returned
     /// * `record_type` - type of record to lookup, all RecordData responses will be filtered to this type
+    /// * `options` - options of the request, e.g. the DO, RD and CD bits, see `Lookup::authentic_data` for the response
     ///
     /// # Returns
     ///
@@ -281,6 +348,30 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
         name: N,
         record_type: RecordType,
         options: DnsRequestOptions,
+    ) -> impl Future<Output = Result<Lookup, ResolveError>> + Send + Unpin + 'static {
+        self.lookup_with_class(name, DNSClass::IN, record_type, options)
+    }
+
+    /// Generic lookup for any RecordType in any DNSClass, e.g. `CH` for the `version.bind.` of a name server
+    ///
+    /// *WARNING* this interface may change in the future, see if one of the specializations would be better.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the record to lookup, if name is not a valid domain name, an error will be returned
+    /// * `query_class` - class of the records to lookup, all RecordData responses will be filtered to this class
+    /// * `record_type` - type of record to lookup, all RecordData responses will be filtered to this type
+    /// * `options` - options of the request, e.g. the DO, RD and CD bits, see `Lookup::authentic_data` for the response
+    ///
+    /// # Returns
+    ///
+    //  A future for the returned Lookup RData
+    pub fn lookup_with_class<N: IntoName>(
+        &self,
+        name: N,
+        query_class: DNSClass,
+        record_type: RecordType,
+        options: DnsRequestOptions,
     ) -> impl Future<Output = Result<Lookup, ResolveError>> + Send + Unpin + 'static {
         let name = match name.into_name() {
             Ok(name) => name,
@@ -288,14 +379,50 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
         };
 
         let names = self.build_names(name);
-        future::Either::Right(LookupFuture::lookup(
-            names,
-            record_type,
-            options,
-            self.client_cache.clone(),
+        future::Either::Right(Self::with_deadline(
+            self.options.total_deadline,
+            LookupFuture::lookup_with_class(
+                names,
+                query_class,
+                record_type,
+                options,
+                self.client_cache.clone(),
+            ),
         ))
     }
 
+    /// Bounds the lookup by the `ResolverOpts::total_deadline`, if any
+    fn with_deadline<F, T>(
+        deadline: Option<Duration>,
+        lookup: F,
+    ) -> impl Future<Output = Result<T, ResolveError>> + Send + Unpin + 'static
+    where
+        F: Future<Output = Result<T, ResolveError>> + Send + Unpin + 'static,
+        T: Send + 'static,
+    {
+        match deadline {
+            Some(deadline) => {
+                future::Either::Left(P::Time::timeout(deadline, lookup).map(move |result| {
+                    result.unwrap_or_else(|_| {
+                        debug!("lookup exceeded its total deadline of {:?}", deadline);
+                        Err(ResolveErrorKind::DeadlineExceeded(deadline).into())
+                    })
+                }))
+            }
+            None => future::Either::Right(lookup),
+        }
+    }
+
+    /// The request options for queries, as derived from the `ResolverOpts`
+    fn request_options(&self) -> DnsRequestOptions {
+        let mut request_opts = DnsRequestOptions::default();
+        request_opts.use_edns = self.options.edns0;
+        request_opts.max_payload_len = self.options.edns_udp_size;
+        request_opts.client_subnet = self.options.client_subnet;
+
+        request_opts
+    }
+
     fn push_name(name: Name, names: &mut Vec<Name>) {
         if !names.contains(&name) {
             names.push(name);
@@ -425,23 +552,129 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
         let names = self.build_names(name);
         let hosts = self.hosts.as_ref().cloned();
 
-        LookupIpFuture::lookup(
-            names,
-            self.options.ip_strategy,
-            self.client_cache.clone(),
-            DnsRequestOptions::default(),
-            hosts,
-            finally_ip_addr.and_then(Record::into_data),
+        let lookup = Self::with_deadline(
+            self.options.total_deadline,
+            LookupIpFuture::lookup(
+                names,
+                self.options.ip_strategy,
+                self.client_cache.clone(),
+                self.request_options(),
+                hosts,
+                finally_ip_addr.and_then(Record::into_data),
+                self.options.dns64_prefix,
+            ),
+        )
+        .await?;
+
+        let lookup = if self.options.sort_addresses {
+            lookup.sort_addresses()
+        } else {
+            lookup
+        };
+
+        if self.options.rotate {
+            if let Some(rotation) = self.client_cache.next_rotation(lookup.query()) {
+                return Ok(lookup.rotate_addresses(rotation));
+            }
+        }
+
+        Ok(lookup)
+    }
+
+    /// Performs a lookup of the HTTPS records for the name, following AliasMode records
+    ///
+    /// The result holds the ServiceMode records of the final name in order of SvcPriority, with the
+    ///  target names and the ipv4hint and ipv6hint addresses for connecting to the service,
+    ///  see [RFC 9460](https://tools.ietf.org/html/rfc9460).
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - a string which parses to a domain name, failure to parse will return an error
+    pub async fn https_lookup<N: IntoName>(
+        &self,
+        query: N,
+    ) -> Result<lookup::SvcbLookup, ResolveError> {
+        let name = query.into_name()?;
+        let options = self.request_options();
+
+        lookup::svcb_lookup(name, |name| self.lookup(name, RecordType::HTTPS, options)).await
+    }
+
+    /// Looks up the addresses of a service, [RFC 2782](https://tools.ietf.org/html/rfc2782)
+    ///
+    /// The SRV records of `_service._proto.name` are queried and their targets resolved. The result
+    ///  lists the addresses in the order in which they should be tried. If the single target of the
+    ///  records is `.`, the service is not available and `ResolveErrorKind::NoService` is returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - the symbolic name of the service, without the leading `_`, e.g. `xmpp-server`
+    /// * `proto` - the protocol of the service, without the leading `_`, e.g. `tcp`
+    /// * `name` - the domain of the service
+    pub async fn lookup_service<N: IntoName>(
+        &self,
+        service: &str,
+        proto: &str,
+        name: N,
+    ) -> Result<lookup::ServiceLookup, ResolveError> {
+        let name = name.into_name()?;
+        let mut srv_name =
+            Name::from_ascii(format!("_{}._{}", service, proto))?.append_name(&name)?;
+        srv_name.set_fqdn(name.is_fqdn());
+
+        let srv = self.srv_lookup(srv_name).await?;
+        lookup::service_lookup(srv, |target| self.lookup_ip(target)).await
+    }
+
+    /// Looks up the names of an address, by querying the PTR records of its reverse name
+    ///
+    /// The name is built with [`Name::from_ip`], under `in-addr.arpa.` for IPv4 and `ip6.arpa.` for IPv6.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - the address to look up
+    pub async fn reverse_lookup(
+        &self,
+        query: IpAddr,
+    ) -> Result<lookup::ReverseLookup, ResolveError> {
+        self.inner_lookup(
+            Name::from_ip(query),
+            RecordType::PTR,
+            self.request_options(),
         )
         .await
     }
 
-    lookup_fn!(
-        reverse_lookup,
-        lookup::ReverseLookup,
-        RecordType::PTR,
-        IpAddr
-    );
+    /// Discovers the NAT64 prefix of the network, [RFC 7050](https://tools.ietf.org/html/rfc7050)
+    ///
+    /// The AAAA records of `ipv4only.arpa.` are queried, which only has A records, so any answer
+    ///  was synthesized by a DNS64 server. The prefix is derived from the position of the well-known
+    ///  IPv4 addresses in the answer. If several prefixes are found, the first one is returned.
+    ///
+    /// The prefix can be set as the `ResolverOpts::dns64_prefix` to synthesize AAAA records locally,
+    ///  e.g. when DNSSEC validation is enabled and the DNS64 server's answers would be bogus.
+    pub async fn discover_nat64_prefix(&self) -> Result<Ipv6Net, ResolveError> {
+        let lookup = self
+            .lookup(
+                dns64::IPV4_ONLY_ARPA,
+                RecordType::AAAA,
+                self.request_options(),
+            )
+            .await?;
+
+        let prefixes = dns64::discover_prefixes(lookup.rdata_iter::<Ipv6Addr>().copied());
+        if prefixes.len() > 1 {
+            debug!("multiple nat64 prefixes discovered: {:?}", prefixes);
+        }
+
+        prefixes.into_iter().next().ok_or_else(|| {
+            ResolveError::from(format!(
+                "no nat64 prefix in the answer for {}",
+                dns64::IPV4_ONLY_ARPA
+            ))
+        })
+    }
+
     lookup_fn!(ipv4_lookup, lookup::Ipv4Lookup, RecordType::A);
     lookup_fn!(ipv6_lookup, lookup::Ipv6Lookup, RecordType::AAAA);
     lookup_fn!(mx_lookup, lookup::MxLookup, RecordType::MX);
@@ -450,6 +683,7 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> AsyncR
     lookup_fn!(srv_lookup, lookup::SrvLookup, RecordType::SRV);
     lookup_fn!(tlsa_lookup, lookup::TlsaLookup, RecordType::TLSA);
     lookup_fn!(txt_lookup, lookup::TxtLookup, RecordType::TXT);
+    lookup_fn!(uri_lookup, lookup::UriLookup, RecordType::URI);
 }
 
 impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> fmt::Debug
@@ -1334,4 +1568,291 @@ mod tests {
             assert_eq!(resolver.build_names(name.clone()).len(), 2);
         }
     }
+
+    #[test]
+    fn test_build_names_search_order() {
+        let handle = TokioHandle;
+        let config = ResolverConfig::from_parts(
+            Some(Name::from_ascii("example.com.").unwrap()),
+            vec![
+                Name::from_ascii("a.example.com.").unwrap(),
+                Name::from_ascii("b.example.com.").unwrap(),
+            ],
+            vec![],
+        );
+        let resolver =
+            AsyncResolver::<GenericConnection, GenericConnectionProvider<TokioRuntime>>::new(
+                config,
+                ResolverOpts {
+                    ndots: 2,
+                    ..ResolverOpts::default()
+                },
+                handle,
+            )
+            .expect("failed to create resolver");
+
+        // names are tried in the reverse order, i.e. popped from the end
+        let search_order = |name: &str| -> Vec<String> {
+            let mut names = resolver.build_names(Name::from_ascii(name).unwrap());
+            names.reverse();
+            names.iter().map(Name::to_string).collect()
+        };
+
+        // fewer dots than ndots, the bare name is tried last
+        assert_eq!(
+            search_order("www.host"),
+            vec![
+                "www.host.example.com.",
+                "www.host.a.example.com.",
+                "www.host.b.example.com.",
+                "www.host",
+            ]
+        );
+        // at least ndots dots, the bare name is tried first
+        assert_eq!(
+            search_order("www.host.net"),
+            vec![
+                "www.host.net",
+                "www.host.net.example.com.",
+                "www.host.net.a.example.com.",
+                "www.host.net.b.example.com.",
+            ]
+        );
+        // absolute names are never searched
+        assert_eq!(search_order("www.host."), vec!["www.host."]);
+    }
+
+    /// A resolver for a name server which never responds
+    fn unresponsive_resolver(
+        options: ResolverOpts,
+    ) -> (
+        std::net::UdpSocket,
+        AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>,
+    ) {
+        use crate::config::{NameServerConfigGroup, ResolverConfig};
+
+        let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+        let config = ResolverConfig::from_parts(
+            None,
+            vec![],
+            NameServerConfigGroup::from_ips_clear(
+                &[socket.local_addr().unwrap().ip()],
+                socket.local_addr().unwrap().port(),
+                true,
+            ),
+        );
+
+        let resolver =
+            AsyncResolver::new(config, options, TokioHandle).expect("failed to create resolver");
+        (socket, resolver)
+    }
+
+    /// A resolver for a name server on a background thread, which answers every request with the response filled in by
+    ///  the handler, the id, message type and queries of the response are already set from the request
+    fn mock_udp_resolver<F>(
+        options: ResolverOpts,
+        mut handler: F,
+    ) -> AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>
+    where
+        F: FnMut(&proto::op::Message, &mut proto::op::Message) + Send + 'static,
+    {
+        use proto::op::{Message, MessageType};
+
+        let (socket, resolver) = unresponsive_resolver(options);
+        socket
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        std::thread::spawn(move || {
+            let mut buf = [0_u8; 512];
+            while let Ok((len, src)) = socket.recv_from(&mut buf) {
+                let request = Message::from_vec(&buf[..len]).unwrap();
+                let mut response = Message::new();
+                response
+                    .set_id(request.id())
+                    .set_message_type(MessageType::Response)
+                    .add_queries(request.queries().to_vec());
+                handler(&request, &mut response);
+                socket.send_to(&response.to_vec().unwrap(), src).unwrap();
+            }
+        });
+
+        resolver
+    }
+
+    #[test]
+    fn test_attempt_timeout() {
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+        let mut options = ResolverOpts::default();
+        options.timeout = Duration::from_millis(100);
+        options.attempts = 0;
+        let (_socket, resolver) = unresponsive_resolver(options);
+
+        let error = io_loop
+            .block_on(resolver.lookup_ip("www.example.com."))
+            .expect_err("lookup should have timed out");
+        assert!(
+            matches!(error.kind(), ResolveErrorKind::Timeout),
+            "expected Timeout, got: {:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_total_deadline() {
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+        let mut options = ResolverOpts::default();
+        options.timeout = Duration::from_secs(5);
+        options.total_deadline = Some(Duration::from_millis(100));
+        let (_socket, resolver) = unresponsive_resolver(options);
+
+        let start = std::time::Instant::now();
+        let error = io_loop
+            .block_on(resolver.lookup(
+                "www.example.com.",
+                RecordType::A,
+                DnsRequestOptions::default(),
+            ))
+            .expect_err("lookup should have exceeded the deadline");
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert!(
+            matches!(error.kind(), ResolveErrorKind::DeadlineExceeded(deadline) if *deadline == Duration::from_millis(100)),
+            "expected DeadlineExceeded, got: {:?}",
+            error
+        );
+    }
+
+    #[test]
+    fn test_rotate() {
+        use std::net::Ipv4Addr;
+
+        use crate::config::LookupIpStrategy;
+        use proto::rr::RData;
+
+        // answers every query with three addresses
+        let options = ResolverOpts {
+            rotate: true,
+            ip_strategy: LookupIpStrategy::Ipv4Only,
+            ..ResolverOpts::default()
+        };
+        let resolver = mock_udp_resolver(options, |request, response| {
+            for i in 1..=3 {
+                response.add_answer(Record::from_rdata(
+                    request.queries()[0].name().clone(),
+                    300,
+                    RData::A(Ipv4Addr::new(192, 0, 2, i)),
+                ));
+            }
+        });
+
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+        let lookups = (0..4)
+            .map(|_| {
+                io_loop
+                    .block_on(resolver.lookup_ip("www.example.com."))
+                    .expect("lookup failed")
+                    .iter()
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        // rotated within the sorted order
+        let first = &lookups[0];
+        assert_eq!(first.len(), 3);
+        for (i, lookup) in lookups.iter().enumerate() {
+            let mut expected = first.clone();
+            expected.rotate_left(i % 3);
+            assert_eq!(*lookup, expected, "lookup {}", i);
+        }
+    }
+
+    #[test]
+    fn test_request_flags() {
+        use std::net::Ipv4Addr;
+        use std::sync::mpsc;
+
+        use proto::rr::RData;
+
+        // reports the RD, CD and DO bits of every request, the response is authentic and echoes CD
+        let (sender, requests) = mpsc::channel();
+        let resolver = mock_udp_resolver(ResolverOpts::default(), move |request, response| {
+            let dnssec_ok = matches!(request.extensions(), Some(edns) if edns.dnssec_ok());
+            sender
+                .send((
+                    request.recursion_desired(),
+                    request.checking_disabled(),
+                    dnssec_ok,
+                ))
+                .unwrap();
+
+            response
+                .set_authentic_data(true)
+                .set_checking_disabled(request.checking_disabled())
+                .add_answer(Record::from_rdata(
+                    request.queries()[0].name().clone(),
+                    300,
+                    RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+                ));
+        });
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+        let mut options = DnsRequestOptions::default();
+        options.recursion_desired = false;
+        options.checking_disabled = true;
+        options.dnssec_ok = true;
+        let lookup = io_loop
+            .block_on(resolver.lookup("www.example.com.", RecordType::A, options))
+            .expect("lookup failed");
+        assert_eq!(requests.try_recv().unwrap(), (false, true, true));
+        assert!(lookup.authentic_data());
+        assert!(lookup.checking_disabled());
+
+        // the unchecked answer was not cached
+        let lookup = io_loop
+            .block_on(resolver.lookup(
+                "www.example.com.",
+                RecordType::A,
+                DnsRequestOptions::default(),
+            ))
+            .expect("lookup failed");
+        assert_eq!(requests.try_recv().unwrap(), (true, false, false));
+        assert!(lookup.authentic_data());
+        assert!(!lookup.checking_disabled());
+
+        // but this one was, and the cache does not vouch for it
+        let lookup = io_loop
+            .block_on(resolver.lookup(
+                "www.example.com.",
+                RecordType::A,
+                DnsRequestOptions::default(),
+            ))
+            .expect("lookup failed");
+        assert!(requests.try_recv().is_err());
+        assert!(!lookup.authentic_data());
+        assert!(!lookup.checking_disabled());
+    }
+
+    #[test]
+    fn test_discover_nat64_prefix() {
+        use proto::rr::RData;
+
+        // a DNS64 server with the well-known prefix, answering the AAAA query of ipv4only.arpa.
+        let resolver = mock_udp_resolver(ResolverOpts::default(), |request, response| {
+            let query = &request.queries()[0];
+            assert_eq!(query.name(), &Name::from_ascii("ipv4only.arpa.").unwrap());
+            assert_eq!(query.query_type(), RecordType::AAAA);
+
+            for address in &["64:ff9b::c000:aa", "64:ff9b::c000:ab"] {
+                response.add_answer(Record::from_rdata(
+                    query.name().clone(),
+                    300,
+                    RData::AAAA(address.parse().unwrap()),
+                ));
+            }
+        });
+
+        let io_loop = Runtime::new().expect("failed to create tokio runtime");
+        let prefix = io_loop
+            .block_on(resolver.discover_nat64_prefix())
+            .expect("discovery failed");
+        assert_eq!(prefix, "64:ff9b::/96".parse().unwrap());
+    }
 }
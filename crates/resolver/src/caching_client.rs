@@ -8,14 +8,19 @@
 //! Caching related functionality for the Resolver.
 
 use std::borrow::Cow;
+use std::collections::HashSet;
 use std::error::Error;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::fmt;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::pin::Pin;
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant, SystemTime};
 
 use futures_util::future::Future;
+use lru_cache::LruCache;
+use parking_lot::Mutex;
 
 use proto::error::ProtoError;
 use proto::op::{Query, ResponseCode};
@@ -23,16 +28,27 @@ use proto::rr::domain::usage::{
     ResolverUsage, DEFAULT, INVALID, IN_ADDR_ARPA_127, IP6_ARPA_1, LOCAL,
     LOCALHOST as LOCALHOST_usage, ONION,
 };
+use proto::rr::rdata::opt::{ClientSubnet, EdnsCode, EdnsOption, ExtendedError};
 use proto::rr::rdata::SOA;
 use proto::rr::{DNSClass, Name, RData, Record, RecordType};
-use proto::xfer::{DnsHandle, DnsRequestOptions, DnsResponse, FirstAnswer};
+use proto::xfer::{DnsHandle, DnsRequestOptions, DnsResponse, DnssecStatus, FirstAnswer};
 
-use crate::dns_lru::DnsLru;
-use crate::dns_lru::{self, TtlConfig};
+use crate::config::AddressFilter;
+use crate::dns_lru::{self, CacheEvent, CacheStats, DnsLru, TtlConfig};
 use crate::error::*;
 use crate::lookup::Lookup;
 
-const MAX_QUERY_DEPTH: u8 = 8; // arbitrarily chosen number...
+const MAX_QUERY_DEPTH: usize = 8; // arbitrarily chosen number...
+
+/// The most labels added one at a time by QNAME minimization, the rest of the name is queried at once, see
+///  [RFC 9156](https://tools.ietf.org/html/rfc9156#section-2.3)
+const MAX_MINIMISE_COUNT: usize = 10;
+
+/// The number of steps of QNAME minimization which fell back to full names that are remembered
+const MINIMISE_FALLBACK_CACHE_SIZE: usize = 128;
+
+/// How long the names below a step of QNAME minimization which fell back are queried in full
+const MINIMISE_FALLBACK_TTL: Duration = Duration::from_secs(300);
 
 lazy_static! {
     static ref LOCALHOST: RData = RData::PTR(Name::from_ascii("localhost.").unwrap());
@@ -41,11 +57,11 @@ lazy_static! {
 }
 
 struct DepthTracker {
-    query_depth: Arc<AtomicU8>,
+    query_depth: Arc<AtomicUsize>,
 }
 
 impl DepthTracker {
-    fn track(query_depth: Arc<AtomicU8>) -> Self {
+    fn track(query_depth: Arc<AtomicUsize>) -> Self {
         query_depth.fetch_add(1, Ordering::Release);
         Self { query_depth }
     }
@@ -57,6 +73,59 @@ impl Drop for DepthTracker {
     }
 }
 
+/// Substitutes the `owner` of a DNAME at the end of `name` with the `target`, see
+///  [RFC 6672](https://tools.ietf.org/html/rfc6672#section-2.2)
+///
+/// This fails if the substituted name is longer than the maximum length of a name.
+fn dname_substitution(name: &Name, owner: &Name, target: &Name) -> Result<Name, ProtoError> {
+    let labels = name.iter().len() - owner.iter().len();
+    Name::from_labels(name.iter().take(labels))?.append_domain(target)
+}
+
+/// Marks a stale cache entry as being refreshed for as long as this lives
+struct RefreshTracker {
+    lru: DnsLru,
+    query: Query,
+}
+
+impl RefreshTracker {
+    fn track(lru: DnsLru, query: Query) -> Self {
+        Self { lru, query }
+    }
+}
+
+impl Drop for RefreshTracker {
+    fn drop(&mut self) {
+        self.lru.end_refresh(&self.query);
+    }
+}
+
+/// Runs the refresh of a stale cache entry in the background, see `ConnectionProvider::spawn_bg`
+#[derive(Clone)]
+pub(crate) struct RefreshSpawner(Arc<dyn Fn(Refresh) -> Result<(), Refresh> + Send + Sync>);
+
+type Refresh = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+impl RefreshSpawner {
+    /// The `spawn` function returns the refresh if it could not be spawned
+    pub(crate) fn new<F>(spawn: F) -> Self
+    where
+        F: Fn(Refresh) -> Result<(), Refresh> + Send + Sync + 'static,
+    {
+        Self(Arc::new(spawn))
+    }
+
+    fn spawn(&self, refresh: Refresh) -> Result<(), Refresh> {
+        (self.0)(refresh)
+    }
+}
+
+impl fmt::Debug for RefreshSpawner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RefreshSpawner")
+    }
+}
+
 // TODO: need to consider this storage type as it compares to Authority in server...
 //       should it just be an variation on Authority?
 #[derive(Clone, Debug)]
@@ -68,8 +137,14 @@ where
 {
     lru: DnsLru,
     client: C,
-    query_depth: Arc<AtomicU8>,
+    query_depth: Arc<AtomicUsize>,
+    max_cname_chain: usize,
     preserve_intermediates: bool,
+    retain_dnssec_records: bool,
+    // the steps of QNAME minimization which fell back, if it is enabled
+    minimise_fallbacks: Option<Arc<Mutex<LruCache<Name, Instant>>>>,
+    address_filter: Option<AddressFilter>,
+    refresh_spawner: Option<RefreshSpawner>,
 }
 
 impl<C, E> CachingClient<C, E>
@@ -87,12 +162,68 @@ where
     }
 
     pub(crate) fn with_cache(lru: DnsLru, client: C, preserve_intermediates: bool) -> Self {
-        let query_depth = Arc::new(AtomicU8::new(0));
+        let query_depth = Arc::new(AtomicUsize::new(0));
         Self {
             lru,
             client,
             query_depth,
+            max_cname_chain: MAX_QUERY_DEPTH,
             preserve_intermediates,
+            retain_dnssec_records: false,
+            minimise_fallbacks: None,
+            address_filter: None,
+            refresh_spawner: None,
+        }
+    }
+
+    /// Keep the RRSIG, DNSKEY, NSEC and NSEC3 records of responses in the returned `Lookup`s
+    pub(crate) fn retain_dnssec_records(mut self, retain_dnssec_records: bool) -> Self {
+        self.retain_dnssec_records = retain_dnssec_records;
+        self
+    }
+
+    /// Walk down to names one label at a time before querying them, see `ResolverOpts::qname_minimization`
+    pub(crate) fn qname_minimization(mut self, qname_minimization: bool) -> Self {
+        self.minimise_fallbacks = if qname_minimization {
+            Some(Arc::new(Mutex::new(LruCache::new(
+                MINIMISE_FALLBACK_CACHE_SIZE,
+            ))))
+        } else {
+            None
+        };
+        self
+    }
+
+    /// The maximum number of queries made for a lookup while following a chain of CNAMEs
+    pub(crate) fn max_cname_chain(mut self, max_cname_chain: usize) -> Self {
+        self.max_cname_chain = max_cname_chain;
+        self
+    }
+
+    /// Remove the A and AAAA records of the addresses matching the filter from responses
+    pub(crate) fn address_filter(mut self, address_filter: Option<AddressFilter>) -> Self {
+        self.address_filter = address_filter;
+        self
+    }
+
+    /// Refresh stale entries in the background, instead of in the first lookup which finds them, see
+    ///  [RFC 8767](https://tools.ietf.org/html/rfc8767#section-5)
+    pub(crate) fn refresh_spawner(mut self, refresh_spawner: Option<RefreshSpawner>) -> Self {
+        self.refresh_spawner = refresh_spawner;
+        self
+    }
+
+    /// True if the record is an A or AAAA record of an address which is filtered
+    fn is_filtered(&self, record: &Record) -> bool {
+        let address_filter = match &self.address_filter {
+            Some(address_filter) => address_filter,
+            None => return false,
+        };
+
+        match record.data() {
+            Some(RData::A(addr)) => address_filter.is_filtered(IpAddr::V4(*addr)),
+            Some(RData::AAAA(addr)) => address_filter.is_filtered(IpAddr::V6(*addr)),
+            _ => false,
         }
     }
 
@@ -102,14 +233,28 @@ where
         query: Query,
         options: DnsRequestOptions,
     ) -> Pin<Box<dyn Future<Output = Result<Lookup, ResolveError>> + Send>> {
-        Box::pin(Self::inner_lookup(query, options, self.clone(), vec![]))
+        let mut client = self.clone();
+
+        // unchecked data may be bogus, it must neither be answered from nor enter the shared cache
+        if options.checking_disabled {
+            client.lru = client.lru.isolated(client.max_cname_chain + 1);
+        }
+
+        Box::pin(Self::inner_lookup(
+            query,
+            options,
+            client,
+            vec![],
+            HashSet::new(),
+        ))
     }
 
     async fn inner_lookup(
         query: Query,
         options: DnsRequestOptions,
-        mut client: Self,
+        client: Self,
         preserved_records: Vec<(Record, u32)>,
+        visited_names: HashSet<Name>,
     ) -> Result<Lookup, ResolveError> {
         // see https://tools.ietf.org/html/rfc6761
         //
@@ -169,13 +314,77 @@ where
         }
 
         let _tracker = DepthTracker::track(client.query_depth.clone());
-        let is_dnssec = client.client.is_verifying_dnssec();
 
         // first transition any polling that is needed (mutable refs...)
         if let Some(cached_lookup) = client.lookup_from_cache(&query) {
             return cached_lookup;
         };
 
+        // an expired answer may still be served while it is being refreshed, see https://tools.ietf.org/html/rfc8767
+        //  only one lookup performs the refresh, all others are answered from the stale entry in the meantime
+        let stale = client.lru.get_stale(&query, Instant::now());
+        let _refresh = match stale {
+            Some(ref stale) if !client.lru.start_refresh(&query) => return Ok(stale.clone()),
+            Some(ref stale) => {
+                let refresh = RefreshTracker::track(client.lru.clone(), query.clone());
+                match client.refresh_spawner.clone() {
+                    // answer now, the cache is updated once the refresh completes
+                    Some(spawner) => {
+                        let upstream = Self::query_upstream(
+                            query.clone(),
+                            options,
+                            client.clone(),
+                            preserved_records,
+                            visited_names,
+                            Some(stale.clone()),
+                        );
+                        let spawned = spawner.spawn(Box::pin(async move {
+                            let _refresh = refresh;
+                            if let Err(e) = upstream.await {
+                                debug!("refresh of stale answer failed: {}", e);
+                            }
+                        }));
+
+                        // without a background task the refresh is awaited here
+                        if let Err(refresh) = spawned {
+                            refresh.await;
+                            if let Some(lookup) = client.lookup_from_cache(&query) {
+                                return lookup;
+                            }
+                        }
+                        return Ok(stale.clone());
+                    }
+                    None => Some(refresh),
+                }
+            }
+            None => None,
+        };
+
+        if client.minimise_fallbacks.is_some() {
+            client.minimise(query.name(), options).await;
+        }
+
+        Self::query_upstream(
+            query,
+            options,
+            client,
+            preserved_records,
+            visited_names,
+            stale,
+        )
+        .await
+    }
+
+    /// Sends the query to the name servers and caches the response, a failure to answer is covered by the stale lookup
+    async fn query_upstream(
+        query: Query,
+        options: DnsRequestOptions,
+        mut client: Self,
+        preserved_records: Vec<(Record, u32)>,
+        visited_names: HashSet<Name>,
+        stale: Option<Lookup>,
+    ) -> Result<Lookup, ResolveError> {
+        let is_dnssec = client.client.is_verifying_dnssec();
         let response_message = client
             .client
             .lookup(query.clone(), options)
@@ -191,6 +400,31 @@ where
             response_message
         };
 
+        // the upstream could not be reached, or failed to answer, keep serving the stale answer
+        let response_message = match (response_message, stale) {
+            (Err(e), Some(stale)) if Self::is_upstream_failure(&e) => {
+                debug!("serving stale answer for {}: {}", query, e);
+                return Ok(stale);
+            }
+            (response_message, _) => response_message,
+        };
+
+        // the cached lookup does not keep the name server, only the one returned now
+        let server_addr = response_message
+            .as_ref()
+            .ok()
+            .and_then(DnsResponse::server_addr);
+        let (authentic_data, checking_disabled) = response_message
+            .as_ref()
+            .map(|response| {
+                (
+                    response.header().authentic_data(),
+                    response.header().checking_disabled(),
+                )
+            })
+            .unwrap_or_default();
+        let client_subnet = response_message.as_ref().ok().and_then(Self::client_subnet);
+
         // TODO: take all records and cache them?
         //  if it's DNSSec they must be signed, otherwise?
         let records: Result<Records, ResolveError> = match response_message {
@@ -203,6 +437,7 @@ where
                         negative_ttl,
                         response_code,
                         trusted,
+                        extended_errors,
                     },
                 ..
             }) => {
@@ -214,6 +449,7 @@ where
                     negative_ttl,
                     response_code,
                     trusted,
+                    extended_errors,
                 ))
             }
             Err(e) => return Err(e),
@@ -226,6 +462,7 @@ where
                     &query,
                     response_message,
                     preserved_records,
+                    visited_names,
                 )?;
 
                 Ok(records)
@@ -238,8 +475,98 @@ where
                 next: future,
                 min_ttl: ttl,
             }) => client.cname(future.await?, query, ttl),
-            Ok(Records::Exists(rdata)) => client.cache(query, Ok(rdata)),
-            Err(e) => client.cache(query, Err(e)),
+            Ok(Records::Exists(rdata, dnssec_status)) => {
+                client.cache(query, Ok(rdata), dnssec_status).map(|lookup| {
+                    lookup
+                        .with_server_addr(server_addr)
+                        .with_response_flags(authentic_data, checking_disabled)
+                        .with_client_subnet(client_subnet)
+                })
+            }
+            Err(e) => client.cache(query, Err(e), DnssecStatus::default()),
+        }
+    }
+
+    /// Queries the NS records of the ancestors of the name, from below the closest zone cut in the cache down to
+    ///  the parent of the name, see [RFC 7816](https://tools.ietf.org/html/rfc7816#section-3)
+    ///
+    /// The answers are cached, so that later lookups start below the steps taken now. A step which is answered
+    ///  neither with NS records nor NODATA is remembered as a fallback, the names below it are then queried in full
+    ///  without walking down again until `MINIMISE_FALLBACK_TTL` has passed.
+    async fn minimise(&self, name: &Name, options: DnsRequestOptions) {
+        let fallbacks = match self.minimise_fallbacks {
+            Some(ref fallbacks) => Arc::clone(fallbacks),
+            None => return,
+        };
+
+        let now = Instant::now();
+        let num_labels = name.num_labels() as usize;
+        let mut zone_cut = 0;
+        for labels in (1..num_labels).rev() {
+            let step = name.trim_to(labels);
+            if let Some(until) = fallbacks.lock().get_mut(&step) {
+                if *until > now {
+                    return;
+                }
+            }
+
+            match self.lru.get(&Query::query(step, RecordType::NS), now) {
+                Some(Ok(_)) => {
+                    zone_cut = labels;
+                    break;
+                }
+                Some(Err(ref e)) if Self::is_nodata(e) => {
+                    zone_cut = labels;
+                    break;
+                }
+                Some(Err(_)) => return,
+                None => continue,
+            }
+        }
+
+        // the steps themselves are not minimised again, their ancestors are walked here
+        let mut client = self.clone();
+        client.minimise_fallbacks = None;
+
+        let last = num_labels.min(zone_cut + MAX_MINIMISE_COUNT);
+        for labels in (zone_cut + 1)..last {
+            let step = name.trim_to(labels);
+            match client
+                .lookup(Query::query(step.clone(), RecordType::NS), options)
+                .await
+            {
+                Ok(_) => continue,
+                Err(ref e) if Self::is_nodata(e) => continue,
+                Err(e) => {
+                    debug!(
+                        "qname minimization of {} falls back at {}: {}",
+                        name, step, e
+                    );
+                    fallbacks
+                        .lock()
+                        .insert(step, Instant::now() + MINIMISE_FALLBACK_TTL);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Returns true if the error is a NODATA answer, i.e. the name exists without records of the type
+    fn is_nodata(error: &ResolveError) -> bool {
+        matches!(
+            error.kind(),
+            ResolveErrorKind::NoRecordsFound {
+                response_code: ResponseCode::NoError,
+                ..
+            }
+        )
+    }
+
+    /// The EDNS Client Subnet option of the response, with the scope of the answer
+    fn client_subnet(response: &DnsResponse) -> Option<ClientSubnet> {
+        match response.extensions().as_ref()?.option(EdnsCode::Subnet) {
+            Some(EdnsOption::Subnet(client_subnet)) => Some(*client_subnet),
+            _ => None,
         }
     }
 
@@ -248,6 +575,17 @@ where
         self.lru.get(query, Instant::now())
     }
 
+    /// Returns true if the error is not an answer to the query, i.e. neither NXDOMAIN nor NODATA
+    fn is_upstream_failure(error: &ResolveError) -> bool {
+        match error.kind() {
+            ResolveErrorKind::NoRecordsFound { response_code, .. } => !matches!(
+                response_code,
+                ResponseCode::NXDomain | ResponseCode::NoError
+            ),
+            _ => true,
+        }
+    }
+
     /// See https://tools.ietf.org/html/rfc2308
     ///
     /// For now we will regard NXDomain to strictly mean the query failed
@@ -265,6 +603,8 @@ where
     /// * `message` - message to extract SOA, etc, from for caching failed requests
     /// * `valid_nsec` - species that in DNSSec mode, this request is safe to cache
     /// * `negative_ttl` - this should be the SOA minimum for negative ttl
+    /// * `extended_errors` - any Extended DNS Errors returned with the response
+    #[allow(clippy::too_many_arguments)]
     fn handle_nxdomain(
         is_dnssec: bool,
         valid_nsec: bool,
@@ -273,6 +613,7 @@ where
         negative_ttl: Option<u32>,
         response_code: ResponseCode,
         trusted: bool,
+        extended_errors: Vec<ExtendedError>,
     ) -> ResolveError {
         if valid_nsec || !is_dnssec {
             // only trust if there were validated NSEC records
@@ -282,6 +623,7 @@ where
                 negative_ttl,
                 response_code,
                 trusted: true,
+                extended_errors,
             }
             .into()
         } else {
@@ -292,6 +634,7 @@ where
                 negative_ttl: None,
                 response_code,
                 trusted,
+                extended_errors,
             }
             .into()
         }
@@ -305,6 +648,7 @@ where
         query: &Query,
         mut response: DnsResponse,
         mut preserved_records: Vec<(Record, u32)>,
+        mut visited_names: HashSet<Name>,
     ) -> Result<Records, ResolveError> {
         // initial ttl is what CNAMES for min usage
         const INITIAL_TTL: u32 = dns_lru::MAX_TTL;
@@ -313,6 +657,8 @@ where
         let soa = response.soa();
         let negative_ttl = response.negative_ttl();
         let response_code = response.response_code();
+        let extended_errors = ResolveError::extended_errors_from(&response);
+        let dnssec_status = response.dnssec_status();
 
         // seek out CNAMES, this is only performed if the query is not a CNAME, ANY, or SRV
         // FIXME: for SRV this evaluation is inadequate. CNAME is a single chain to a single record
@@ -320,6 +666,11 @@ where
         //   be a list of names found for SRV records.
         let (search_name, cname_ttl, was_cname, preserved_records) = {
             // this will only search for CNAMEs if the request was not meant to be for one of the triggers for recursion
+            // the names of the chain so far, a CNAME back to any of them is a loop
+            visited_names.insert(query.name().clone());
+            let mut cname_loop = false;
+            let mut dname_too_long = false;
+
             let (search_name, cname_ttl, was_cname) =
                 if query.query_type().is_any() || query.query_type().is_cname() {
                     (Cow::Borrowed(query.name()), INITIAL_TTL, false)
@@ -328,6 +679,7 @@ where
                     //   this works by folding the last CNAME found into the final folded result.
                     //   it assumes that the CNAMEs are in chained order in the DnsResponse Message...
                     // For SRV, the name added for the search becomes the target name.
+                    // For DNAME, the CNAME to the substituted name is synthesized.
                     //
                     // TODO: should this include the additionals?
                     response.answers().iter().fold(
@@ -339,9 +691,30 @@ where
                                     let ttl = cname_ttl.min(r.ttl());
                                     debug_assert_eq!(r.rr_type(), RecordType::CNAME);
                                     if search_name.as_ref() == r.name() {
+                                        cname_loop |= !visited_names.insert(cname.clone());
                                         return (Cow::Owned(cname.clone()), ttl, true);
                                     }
                                 }
+                                // the DNAME only redirects the names below its owner, not the owner itself
+                                Some(RData::DNAME(ref target))
+                                    if r.name() != search_name.as_ref()
+                                        && r.name().zone_of(search_name.as_ref()) =>
+                                {
+                                    let ttl = cname_ttl.min(r.ttl());
+                                    match dname_substitution(&search_name, r.name(), target) {
+                                        Ok(cname) => {
+                                            cname_loop |= !visited_names.insert(cname.clone());
+                                            return (Cow::Owned(cname), ttl, true);
+                                        }
+                                        Err(e) => {
+                                            debug!(
+                                                "DNAME substitution of {} failed: {}",
+                                                search_name, e
+                                            );
+                                            dname_too_long = true;
+                                        }
+                                    }
+                                }
                                 Some(RData::SRV(ref srv)) => {
                                     // take the minimum TTL of the cname_ttl and the next record in the chain
                                     let ttl = cname_ttl.min(r.ttl());
@@ -358,6 +731,21 @@ where
                     )
                 };
 
+            if cname_loop {
+                return Err(ResolveErrorKind::CnameLoop(Box::new(search_name.into_owned())).into());
+            }
+
+            // the substituted name would exceed the maximum length of a name, rfc6672 section 2.2
+            if dname_too_long {
+                return Err(ResolveError::nx_error(
+                    query.clone(),
+                    None,
+                    None,
+                    ResponseCode::YXDomain,
+                    false,
+                ));
+            }
+
             // take all answers. // TODO: following CNAMES?
             let answers = response.take_answers();
             let additionals = response.take_additionals();
@@ -366,6 +754,8 @@ where
             // set of names that still require resolution
             // TODO: this needs to be enhanced for SRV
             let mut found_name = false;
+            // the minimum TTL of the filtered addresses of the name, if any
+            let mut filtered_ttl: Option<u32> = None;
 
             // After following all the CNAMES to the last one, try and lookup the final name
             let records = answers
@@ -376,6 +766,12 @@ where
                 .filter_map(|r| {
                     // because this resolved potentially recursively, we want the min TTL from the chain
                     let ttl = cname_ttl.min(r.ttl());
+                    if client.is_filtered(&r) {
+                        if search_name.as_ref() == r.name() || query.name() == r.name() {
+                            filtered_ttl = Some(filtered_ttl.map_or(ttl, |t| t.min(ttl)));
+                        }
+                        return None;
+                    }
                     // TODO: disable name validation with ResolverOpts? glibc feature...
                     // restrict to the RData type requested
                     if query.query_class() == r.dns_class() {
@@ -389,7 +785,7 @@ where
                         }
                         // CNAME evaluation, it's an A/AAAA lookup and the record is from the CNAME lookup chain.
                         if client.preserve_intermediates
-                            && r.rr_type() == RecordType::CNAME
+                            && matches!(r.rr_type(), RecordType::CNAME | RecordType::DNAME)
                             && (query.query_type() == RecordType::A
                                 || query.query_type() == RecordType::AAAA)
                         {
@@ -405,6 +801,17 @@ where
                             Some((r, ttl))
                         } else if query.query_type().is_ns() && r.rr_type().is_ip_addr() {
                             Some((r, ttl))
+                        } else if client.retain_dnssec_records
+                            && matches!(
+                                r.rr_type(),
+                                RecordType::RRSIG
+                                    | RecordType::DNSKEY
+                                    | RecordType::NSEC
+                                    | RecordType::NSEC3
+                            )
+                        {
+                            // the validating handle only leaves the records used to validate the response
+                            Some((r, ttl))
                         } else {
                             None
                         }
@@ -417,7 +824,22 @@ where
             // adding the newly collected records to the preserved records
             preserved_records.extend(records);
             if !preserved_records.is_empty() && found_name {
-                return Ok(Records::Exists(preserved_records));
+                return Ok(Records::Exists(preserved_records, dnssec_status));
+            }
+
+            // all the addresses of the name were filtered, NODATA for as long as they would have been cached
+            if let (false, Some(filtered_ttl)) = (found_name, filtered_ttl) {
+                debug!("all addresses of {} were filtered", search_name);
+                return Err(Self::handle_nxdomain(
+                    is_dnssec,
+                    true,
+                    query.clone(),
+                    soa,
+                    Some(filtered_ttl),
+                    ResponseCode::NoError,
+                    false,
+                    extended_errors,
+                ));
             }
 
             (
@@ -431,7 +853,7 @@ where
         // TODO: for SRV records we *could* do an implicit lookup, but, this requires knowing the type of IP desired
         //    for now, we'll make the API require the user to perform a follow up to the lookups.
         // It was a CNAME, but not included in the request...
-        if was_cname && client.query_depth.load(Ordering::Acquire) < MAX_QUERY_DEPTH {
+        if was_cname && client.query_depth.load(Ordering::Acquire) < client.max_cname_chain {
             let next_query = Query::query(search_name, query.query_type());
             Ok(Records::CnameChain {
                 next: Box::pin(Self::inner_lookup(
@@ -439,6 +861,7 @@ where
                     options,
                     client.clone(),
                     preserved_records,
+                    visited_names,
                 )),
                 min_ttl: cname_ttl,
             })
@@ -454,6 +877,7 @@ where
                 negative_ttl,
                 response_code,
                 false,
+                extended_errors,
             ))
         }
     }
@@ -468,23 +892,63 @@ where
         &self,
         query: Query,
         records: Result<Vec<(Record, u32)>, ResolveError>,
+        dnssec_status: DnssecStatus,
     ) -> Result<Lookup, ResolveError> {
         // this will put this object into an inconsistent state, but no one should call poll again...
         match records {
-            Ok(rdata) => Ok(self.lru.insert(query, rdata, Instant::now())),
+            Ok(rdata) => Ok(self.lru.insert(query, rdata, dnssec_status, Instant::now())),
             Err(err) => Err(self.lru.negative(query, err, Instant::now())),
         }
     }
 
+    /// Returns the count of rotations of the cached answer to the query and advances it, None if it is not cached
+    pub(crate) fn next_rotation(&self, query: &Query) -> Option<usize> {
+        self.lru.next_rotation(query)
+    }
+
     /// Flushes/Removes all entries from the cache
     pub fn clear_cache(&mut self) {
         self.lru.clear();
     }
+
+    /// Returns the current counters of the cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.lru.stats()
+    }
+
+    /// Sets the listener which is called for every insertion into and eviction from the cache
+    pub fn set_cache_listener<F>(&self, listener: F)
+    where
+        F: Fn(CacheEvent<'_>) + Send + Sync + 'static,
+    {
+        self.lru.set_listener(Some(Box::new(listener)));
+    }
+
+    /// Removes the listener set with `set_cache_listener`
+    pub fn clear_cache_listener(&self) {
+        self.lru.set_listener(None);
+    }
+
+    /// Writes the current entries of the cache to the writer, returning the number of entries written
+    ///
+    /// Positive and negative (`NoRecordsFound`) answers are written with their remaining TTL, the dump
+    ///  can be loaded with `load`, e.g. to start a restarted process with a warm cache.
+    pub fn dump<W: Write>(&self, writer: W) -> Result<usize, ResolveError> {
+        self.lru.dump(writer, Instant::now(), SystemTime::now())
+    }
+
+    /// Loads the entries written by `dump` into the cache, returning the number of entries loaded
+    ///
+    /// The TTLs are reduced by the time elapsed since the dump, according to the wall clock, and entries
+    ///  which have expired since are dropped. Entries already in the cache are not replaced.
+    pub fn load<R: Read>(&self, reader: R) -> Result<usize, ResolveError> {
+        self.lru.load(reader, Instant::now(), SystemTime::now())
+    }
 }
 
 enum Records {
-    /// The records exists, a vec of rdata with ttl
-    Exists(Vec<(Record, u32)>),
+    /// The records exists, a vec of rdata with ttl, and the DNSSEC status of the response
+    Exists(Vec<(Record, u32)>, DnssecStatus),
     /// Future lookup for recursive cname records
     CnameChain {
         next: Pin<Box<dyn Future<Output = Result<Lookup, ResolveError>> + Send>>,
@@ -522,6 +986,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
         .unwrap_err()
         .kind()
@@ -533,6 +998,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_extended_errors() {
+        use proto::op::Edns;
+        use proto::rr::rdata::opt::{EdnsOption, InfoCode};
+
+        let mut message = Message::new();
+        message.set_response_code(ResponseCode::ServFail);
+        let mut edns = Edns::new();
+        edns.options_mut()
+            .insert(EdnsOption::ExtendedError(ExtendedError::new(
+                InfoCode::DnssecBogus,
+                "validation failure".to_string(),
+            )));
+        message.set_edns(edns);
+
+        let client = mock(vec![Ok(message.into())]);
+        let client = CachingClient::new(0, client, false);
+
+        let error = block_on(CachingClient::inner_lookup(
+            Query::new(),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .unwrap_err();
+
+        assert_eq!(error.extended_errors().len(), 1);
+        assert_eq!(
+            error.extended_errors()[0].info_code(),
+            InfoCode::DnssecBogus
+        );
+        assert_eq!(
+            error.extended_errors()[0].extra_text(),
+            "validation failure"
+        );
+    }
+
+    #[test]
+    fn test_client_subnet_scope() {
+        use proto::op::Edns;
+
+        let query = Query::query(Name::root(), RecordType::A);
+        let mut message = Message::new();
+        message.add_query(query.clone());
+        message.add_answer(Record::from_rdata(
+            Name::root(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        ));
+        let mut client_subnet = ClientSubnet::from_str("192.0.2.0/24").unwrap();
+        client_subnet.set_scope_prefix(16);
+        let mut edns = Edns::new();
+        edns.options_mut().insert(EdnsOption::Subnet(client_subnet));
+        message.set_edns(edns);
+
+        let client = mock(vec![Ok(message.into())]);
+        let mut client = CachingClient::new(1, client, false);
+
+        let lookup = block_on(client.lookup(query.clone(), DnsRequestOptions::default())).unwrap();
+        assert_eq!(lookup.client_subnet(), Some(client_subnet));
+        assert_eq!(lookup.client_subnet().unwrap().scope_prefix(), 16);
+
+        // the cached answer did not come with the option
+        let lookup = block_on(client.lookup(query, DnsRequestOptions::default())).unwrap();
+        assert_eq!(lookup.client_subnet(), None);
+    }
+
     #[test]
     fn test_from_cache() {
         let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
@@ -547,6 +1080,7 @@ mod tests {
                 ),
                 u32::max_value(),
             )],
+            DnssecStatus::Indeterminate,
             Instant::now(),
         );
 
@@ -558,6 +1092,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
         .unwrap();
 
@@ -567,6 +1102,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_dump_and_load() {
+        let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        cache.insert(
+            query.clone(),
+            vec![(
+                Record::from_rdata(
+                    query.name().clone(),
+                    300,
+                    RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+                ),
+                300,
+            )],
+            DnssecStatus::Indeterminate,
+            Instant::now(),
+        );
+        let client = CachingClient::with_cache(cache, mock(vec![]), false);
+
+        let mut dump = Vec::new();
+        assert_eq!(client.dump(&mut dump).unwrap(), 1);
+
+        // a restart, at least one second later by the wall clock
+        std::thread::sleep(Duration::from_millis(1100));
+        let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
+        let client = CachingClient::with_cache(cache, mock(vec![empty()]), false);
+        assert_eq!(client.load(&dump[..]).unwrap(), 1);
+
+        let lookup = block_on(CachingClient::inner_lookup(
+            query,
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .unwrap();
+
+        let record = lookup.record_iter().next().unwrap();
+        assert_eq!(record.data(), Some(&RData::A(Ipv4Addr::new(127, 0, 0, 1))));
+        assert!(record.ttl() < 300 && record.ttl() > 200);
+        assert!(lookup.valid_until() < Instant::now() + Duration::from_secs(299));
+    }
+
     #[test]
     fn test_no_cache_insert() {
         let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
@@ -579,6 +1157,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
         .unwrap();
 
@@ -596,6 +1175,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
         .unwrap();
 
@@ -605,74 +1185,453 @@ mod tests {
         );
     }
 
-    #[allow(clippy::unnecessary_wraps)]
-    pub(crate) fn cname_message() -> Result<DnsResponse, ResolveError> {
-        let mut message = Message::new();
-        message.add_query(Query::query(
-            Name::from_str("www.example.com.").unwrap(),
-            RecordType::A,
-        ));
-        message.insert_answers(vec![Record::from_rdata(
-            Name::from_str("www.example.com.").unwrap(),
-            86400,
-            RData::CNAME(Name::from_str("actual.example.com.").unwrap()),
-        )]);
-        Ok(message.into())
-    }
-
-    #[allow(clippy::unnecessary_wraps)]
-    pub(crate) fn srv_message() -> Result<DnsResponse, ResolveError> {
-        let mut message = Message::new();
-        message.add_query(Query::query(
-            Name::from_str("_443._tcp.www.example.com.").unwrap(),
-            RecordType::SRV,
-        ));
-        message.insert_answers(vec![Record::from_rdata(
-            Name::from_str("_443._tcp.www.example.com.").unwrap(),
-            86400,
-            RData::SRV(SRV::new(
+    fn stale_cache() -> DnsLru {
+        let ttls = dns_lru::TtlConfig {
+            serve_stale_ttl: Some(Duration::from_secs(3600)),
+            ..dns_lru::TtlConfig::default()
+        };
+        let cache = DnsLru::new(1, ttls);
+        let query = Query::new();
+        cache.insert(
+            query.clone(),
+            vec![(
+                Record::from_rdata(
+                    query.name().clone(),
+                    1,
+                    RData::A(Ipv4Addr::new(127, 0, 0, 2)),
+                ),
                 1,
-                2,
-                443,
-                Name::from_str("www.example.com.").unwrap(),
-            )),
-        )]);
-        Ok(message.into())
-    }
+            )],
+            DnssecStatus::Indeterminate,
+            Instant::now() - Duration::from_secs(10),
+        );
 
-    #[allow(clippy::unnecessary_wraps)]
-    pub(crate) fn ns_message() -> Result<DnsResponse, ResolveError> {
-        let mut message = Message::new();
-        message.add_query(Query::query(
-            Name::from_str("www.example.com.").unwrap(),
-            RecordType::NS,
-        ));
-        message.insert_answers(vec![Record::from_rdata(
-            Name::from_str("www.example.com.").unwrap(),
-            86400,
-            RData::NS(Name::from_str("www.example.com.").unwrap()),
-        )]);
-        Ok(message.into())
+        cache
     }
 
-    fn no_recursion_on_query_test(query_type: RecordType) {
-        let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
-
-        // the cname should succeed, we shouldn't query again after that, which would cause an error...
-        let client = mock(vec![error(), cname_message()]);
+    #[test]
+    fn test_serve_stale_on_failure() {
+        let cache = stale_cache();
+        let client = mock(vec![error()]);
         let client = CachingClient::with_cache(cache, client, false);
 
         let ips = block_on(CachingClient::inner_lookup(
-            Query::query(Name::from_str("www.example.com.").unwrap(), query_type),
+            Query::new(),
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
-        .expect("lookup failed");
+        .unwrap();
 
         assert_eq!(
             ips.iter().cloned().collect::<Vec<_>>(),
-            vec![RData::CNAME(Name::from_str("actual.example.com.").unwrap())]
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+        );
+        assert_eq!(
+            ips.record_iter().next().unwrap().ttl(),
+            dns_lru::STALE_ANSWER_TTL
+        );
+    }
+
+    #[test]
+    fn test_serve_stale_refresh() {
+        let cache = stale_cache();
+
+        // a refresh is in flight, answered from the stale entry
+        assert!(cache.start_refresh(&Query::new()));
+        let client = mock(vec![v4_message()]);
+        let client = CachingClient::with_cache(cache.clone(), client, false);
+        let ips = block_on(CachingClient::inner_lookup(
+            Query::new(),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+        );
+        cache.end_refresh(&Query::new());
+
+        // the refresh succeeds and updates the cache
+        let client = mock(vec![v4_message()]);
+        let client = CachingClient::with_cache(cache.clone(), client, false);
+        let ips = block_on(CachingClient::inner_lookup(
+            Query::new(),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+
+        let client = mock(vec![error()]);
+        let client = CachingClient::with_cache(cache, client, false);
+        let ips = block_on(CachingClient::inner_lookup(
+            Query::new(),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
+    #[test]
+    fn test_serve_stale_background_refresh() {
+        use parking_lot::Mutex;
+
+        let cache = stale_cache();
+        let spawned = Arc::new(Mutex::new(Vec::new()));
+        let spawner = {
+            let spawned = Arc::clone(&spawned);
+            RefreshSpawner::new(move |refresh| {
+                spawned.lock().push(refresh);
+                Ok(())
+            })
+        };
+        let client = mock(vec![v4_message()]);
+        let client = CachingClient::with_cache(cache, client, false).refresh_spawner(Some(spawner));
+
+        // the stale answer is returned at once, for as long as the refresh is in flight
+        for _ in 0..2 {
+            let ips = block_on(CachingClient::inner_lookup(
+                Query::new(),
+                DnsRequestOptions::default(),
+                client.clone(),
+                vec![],
+                HashSet::new(),
+            ))
+            .unwrap();
+            assert_eq!(
+                ips.iter().cloned().collect::<Vec<_>>(),
+                vec![RData::A(Ipv4Addr::new(127, 0, 0, 2))]
+            );
+        }
+        assert_eq!(spawned.lock().len(), 1);
+
+        // the refresh completes in the background and updates the cache
+        let refresh = spawned.lock().pop().unwrap();
+        block_on(refresh);
+        let ips = block_on(CachingClient::inner_lookup(
+            Query::new(),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .unwrap();
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+        assert!(spawned.lock().is_empty());
+    }
+
+    #[test]
+    fn test_no_stale_on_nxdomain() {
+        let cache = stale_cache();
+        let mut message = Message::new();
+        message.set_response_code(ResponseCode::NXDomain);
+        let client = mock(vec![Ok(message.into())]);
+        let client = CachingClient::with_cache(cache, client, false);
+
+        assert!(block_on(CachingClient::inner_lookup(
+            Query::new(),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_negative_soa() {
+        let query = Query::query(Name::from_str("nx.example.com.").unwrap(), RecordType::A);
+        let soa = SOA::new(
+            Name::from_str("ns.example.com.").unwrap(),
+            Name::from_str("hostmaster.example.com.").unwrap(),
+            1,
+            3600,
+            600,
+            604800,
+            300,
+        );
+
+        let mut message = Message::new();
+        message.add_query(query.clone());
+        message.set_response_code(ResponseCode::NXDomain);
+        message.add_name_server(Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            3600,
+            RData::SOA(soa.clone()),
+        ));
+
+        let client = mock(vec![Ok(message.into())]);
+        let client = CachingClient::new(0, client, false);
+
+        let error = block_on(CachingClient::inner_lookup(
+            query,
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .unwrap_err();
+
+        assert_eq!(error.soa(), Some(&soa));
+        assert_eq!(error.soa().map(SOA::minimum), Some(300));
+        assert_eq!(error.negative_ttl(), Some(300));
+
+        let error = ResolveError::from("not a response");
+        assert!(error.soa().is_none());
+        assert!(error.negative_ttl().is_none());
+    }
+
+    #[test]
+    fn test_filter_addresses() {
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let message = |addrs: &[Ipv4Addr]| {
+            let mut message = Message::new();
+            message.add_query(query.clone());
+            for addr in addrs {
+                message.add_answer(Record::from_rdata(
+                    query.name().clone(),
+                    300,
+                    RData::A(*addr),
+                ));
+            }
+            Ok(message.into())
+        };
+        let lookup = |response, filter| {
+            let client = CachingClient::new(1, mock(vec![response]), false).address_filter(filter);
+            block_on(CachingClient::inner_lookup(
+                query.clone(),
+                DnsRequestOptions::default(),
+                client,
+                vec![],
+                HashSet::new(),
+            ))
+        };
+        let public = Ipv4Addr::new(93, 184, 216, 34);
+
+        // the loopback address is removed, the public one kept
+        let lookup_ip = lookup(
+            message(&[Ipv4Addr::LOCALHOST, public]),
+            Some(AddressFilter::PrivateAndBogon),
+        )
+        .unwrap();
+        assert_eq!(
+            lookup_ip.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(public)]
+        );
+
+        // with only the loopback address, the name has no addresses
+        let error = lookup(
+            message(&[Ipv4Addr::LOCALHOST]),
+            Some(AddressFilter::PrivateAndBogon),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ResolveErrorKind::NoRecordsFound {
+                response_code: ResponseCode::NoError,
+                negative_ttl: Some(300),
+                ..
+            }
+        ));
+
+        // custom prefixes
+        let error = lookup(
+            message(&[public]),
+            Some(AddressFilter::Prefixes(Box::leak(Box::new([
+                "93.184.0.0/16".parse().unwrap(),
+            ])))),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error.kind(),
+            ResolveErrorKind::NoRecordsFound { .. }
+        ));
+
+        // nothing is filtered by default
+        let lookup_ip = lookup(message(&[Ipv4Addr::LOCALHOST]), None).unwrap();
+        assert_eq!(lookup_ip.iter().count(), 1);
+
+        let filter = AddressFilter::PrivateAndBogon;
+        for filtered in &[
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.0.1",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::127.0.0.1",
+            "64:ff9b::c0a8:101",
+            "2002:a00:1::1",
+            "2002:c0a8:101::",
+        ] {
+            assert!(
+                filter.is_filtered(filtered.parse().unwrap()),
+                "{}",
+                filtered
+            );
+        }
+        for allowed in &[
+            "93.184.216.34",
+            "8.8.8.8",
+            "2606:2800:220:1::1",
+            "::ffff:8.8.8.8",
+            "64:ff9b::8.8.8.8",
+            "2002:808:808::1",
+        ] {
+            assert!(!filter.is_filtered(allowed.parse().unwrap()), "{}", allowed);
+        }
+    }
+
+    #[test]
+    fn test_negative_max_ttl_caps_soa_minimum() {
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+        let soa = Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            86400,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.example.com.").unwrap(),
+                Name::from_str("hostmaster.example.com.").unwrap(),
+                1,
+                3600,
+                600,
+                604800,
+                86400,
+            )),
+        );
+
+        for response_code in [ResponseCode::NXDomain, ResponseCode::NoError] {
+            let ttls = dns_lru::TtlConfig {
+                negative_max_ttl: Some(Duration::from_secs(300)),
+                ..dns_lru::TtlConfig::default()
+            };
+            let cache = DnsLru::new(1, ttls);
+
+            let mut message = Message::new();
+            message.add_query(query.clone());
+            message.set_response_code(response_code);
+            message.add_name_server(soa.clone());
+
+            let client = mock(vec![Ok(message.into())]);
+            let client = CachingClient::with_cache(cache.clone(), client, false);
+            let now = Instant::now();
+
+            let error = block_on(CachingClient::inner_lookup(
+                query.clone(),
+                DnsRequestOptions::default(),
+                client,
+                vec![],
+                HashSet::new(),
+            ))
+            .unwrap_err();
+
+            if let ResolveErrorKind::NoRecordsFound { negative_ttl, .. } = error.kind() {
+                assert_eq!(*negative_ttl, Some(300), "{}", response_code);
+            } else {
+                panic!("wrong error received: {}", error);
+            }
+
+            assert!(cache
+                .get(&query, now + Duration::from_secs(299))
+                .expect("negative response should be cached")
+                .is_err());
+            assert!(cache.get(&query, now + Duration::from_secs(301)).is_none());
+        }
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub(crate) fn cname_message() -> Result<DnsResponse, ResolveError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ));
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            86400,
+            RData::CNAME(Name::from_str("actual.example.com.").unwrap()),
+        )]);
+        Ok(message.into())
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub(crate) fn srv_message() -> Result<DnsResponse, ResolveError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(
+            Name::from_str("_443._tcp.www.example.com.").unwrap(),
+            RecordType::SRV,
+        ));
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("_443._tcp.www.example.com.").unwrap(),
+            86400,
+            RData::SRV(SRV::new(
+                1,
+                2,
+                443,
+                Name::from_str("www.example.com.").unwrap(),
+            )),
+        )]);
+        Ok(message.into())
+    }
+
+    #[allow(clippy::unnecessary_wraps)]
+    pub(crate) fn ns_message() -> Result<DnsResponse, ResolveError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::NS,
+        ));
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            86400,
+            RData::NS(Name::from_str("www.example.com.").unwrap()),
+        )]);
+        Ok(message.into())
+    }
+
+    fn no_recursion_on_query_test(query_type: RecordType) {
+        let cache = DnsLru::new(1, dns_lru::TtlConfig::default());
+
+        // the cname should succeed, we shouldn't query again after that, which would cause an error...
+        let client = mock(vec![error(), cname_message()]);
+        let client = CachingClient::with_cache(cache, client, false);
+
+        let ips = block_on(CachingClient::inner_lookup(
+            Query::query(Name::from_str("www.example.com.").unwrap(), query_type),
+            DnsRequestOptions::default(),
+            client,
+            vec![],
+            HashSet::new(),
+        ))
+        .expect("lookup failed");
+
+        assert_eq!(
+            ips.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::CNAME(Name::from_str("actual.example.com.").unwrap())]
         );
     }
 
@@ -702,6 +1661,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
         .expect("lookup failed");
 
@@ -750,6 +1710,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
         .expect("lookup failed");
 
@@ -848,6 +1809,7 @@ mod tests {
             DnsRequestOptions::default(),
             client,
             vec![],
+            HashSet::new(),
         ))
         .expect("lookup failed");
 
@@ -885,10 +1847,11 @@ mod tests {
             &Query::query(Name::from_str("ttl.example.com.").unwrap(), RecordType::A),
             message.into(),
             vec![],
+            HashSet::new(),
         );
 
         if let Ok(records) = records {
-            if let Records::Exists(records) = records {
+            if let Records::Exists(records, _) = records {
                 for (record, ttl) in records.iter() {
                     if record.record_type() == RecordType::CNAME {
                         continue;
@@ -909,6 +1872,246 @@ mod tests {
         cname_ttl_test(2, 1);
     }
 
+    fn cname_message_for(name: &str, cname: &str) -> Result<DnsResponse, ResolveError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(Name::from_str(name).unwrap(), RecordType::A));
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str(name).unwrap(),
+            86400,
+            RData::CNAME(Name::from_str(cname).unwrap()),
+        )]);
+        Ok(message.into())
+    }
+
+    fn assert_cname_loop(result: Result<Lookup, ResolveError>, name: &str) {
+        match result.expect_err("CNAME loop should fail").kind() {
+            ResolveErrorKind::CnameLoop(looped) => {
+                assert_eq!(**looped, Name::from_str(name).unwrap())
+            }
+            other => panic!("expected ResolveErrorKind::CnameLoop, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cname_loop() {
+        // the two CNAMEs of the cycle are each returned in their own response
+        let client = mock(vec![
+            cname_message_for("b.example.com.", "a.example.com."),
+            cname_message_for("a.example.com.", "b.example.com."),
+        ]);
+        let mut client = CachingClient::new(0, client, false);
+
+        assert_cname_loop(
+            block_on(client.lookup(
+                Query::query(Name::from_str("a.example.com.").unwrap(), RecordType::A),
+                DnsRequestOptions::default(),
+            )),
+            "a.example.com.",
+        );
+    }
+
+    #[test]
+    fn test_cname_loop_in_response() {
+        let mut message = Message::new();
+        message.add_query(Query::query(
+            Name::from_str("a.example.com.").unwrap(),
+            RecordType::A,
+        ));
+        message.insert_answers(vec![
+            Record::from_rdata(
+                Name::from_str("a.example.com.").unwrap(),
+                86400,
+                RData::CNAME(Name::from_str("b.example.com.").unwrap()),
+            ),
+            Record::from_rdata(
+                Name::from_str("b.example.com.").unwrap(),
+                86400,
+                RData::CNAME(Name::from_str("a.example.com.").unwrap()),
+            ),
+        ]);
+        let mut client = CachingClient::new(0, mock(vec![Ok(message.into())]), true);
+
+        assert_cname_loop(
+            block_on(client.lookup(
+                Query::query(Name::from_str("a.example.com.").unwrap(), RecordType::A),
+                DnsRequestOptions::default(),
+            )),
+            "a.example.com.",
+        );
+    }
+
+    #[test]
+    fn test_max_cname_chain() {
+        // a chain without loops, each CNAME in its own response
+        let client = mock(vec![
+            cname_message_for("c.example.com.", "d.example.com."),
+            cname_message_for("b.example.com.", "c.example.com."),
+            cname_message_for("a.example.com.", "b.example.com."),
+        ]);
+        let mut client = CachingClient::new(0, client, false).max_cname_chain(2);
+
+        match block_on(client.lookup(
+            Query::query(Name::from_str("a.example.com.").unwrap(), RecordType::A),
+            DnsRequestOptions::default(),
+        ))
+        .expect_err("chain is longer than the limit")
+        .kind()
+        {
+            ResolveErrorKind::NoRecordsFound { query, .. } => {
+                assert_eq!(*query.name(), Name::from_str("b.example.com.").unwrap())
+            }
+            other => panic!("expected ResolveErrorKind::NoRecordsFound, got {:?}", other),
+        }
+    }
+
+    fn dname_message(query: &str, target: &str) -> Message {
+        let mut message = Message::new();
+        message.add_query(Query::query(Name::from_str(query).unwrap(), RecordType::A));
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("old.example.").unwrap(),
+            86400,
+            RData::DNAME(Name::from_str(target).unwrap()),
+        )]);
+        message
+    }
+
+    #[test]
+    fn test_dname() {
+        let mut message = Message::new();
+        message.add_query(Query::query(
+            Name::from_str("host.new.example.").unwrap(),
+            RecordType::A,
+        ));
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("host.new.example.").unwrap(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        )]);
+
+        let client = mock(vec![
+            Ok(message.into()),
+            Ok(dname_message("host.old.example.", "new.example.").into()),
+        ]);
+        let mut client = CachingClient::new(0, client, false);
+
+        let lookup = block_on(client.lookup(
+            Query::query(Name::from_str("host.old.example.").unwrap(), RecordType::A),
+            DnsRequestOptions::default(),
+        ))
+        .expect("lookup failed");
+
+        assert_eq!(
+            lookup.record_iter().map(Record::name).collect::<Vec<_>>(),
+            vec![&Name::from_str("host.new.example.").unwrap()]
+        );
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+    }
+
+    #[test]
+    fn test_dname_in_response() {
+        // the name server synthesized the CNAME, and answered for the substituted name
+        let mut message = dname_message("host.old.example.", "new.example.");
+        message.add_answers(vec![
+            Record::from_rdata(
+                Name::from_str("host.old.example.").unwrap(),
+                86400,
+                RData::CNAME(Name::from_str("host.new.example.").unwrap()),
+            ),
+            Record::from_rdata(
+                Name::from_str("host.new.example.").unwrap(),
+                86400,
+                RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+            ),
+        ]);
+        let mut client = CachingClient::new(0, mock(vec![Ok(message.into())]), true);
+
+        let lookup = block_on(client.lookup(
+            Query::query(Name::from_str("host.old.example.").unwrap(), RecordType::A),
+            DnsRequestOptions::default(),
+        ))
+        .expect("lookup failed");
+
+        assert!(lookup.iter().any(|r| matches!(r, RData::DNAME(..))));
+        assert!(lookup
+            .iter()
+            .any(|r| *r == RData::A(Ipv4Addr::new(127, 0, 0, 1))));
+    }
+
+    #[test]
+    fn test_dname_in_authority_not_followed() {
+        let mut referral = Message::new();
+        referral.add_query(Query::query(
+            Name::from_str("host.old.example.").unwrap(),
+            RecordType::A,
+        ));
+        referral.insert_name_servers(vec![Record::from_rdata(
+            Name::from_str("old.example.").unwrap(),
+            86400,
+            RData::DNAME(Name::from_str("new.example.").unwrap()),
+        )]);
+
+        let mut message = Message::new();
+        message.insert_answers(vec![Record::from_rdata(
+            Name::from_str("host.new.example.").unwrap(),
+            86400,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        )]);
+
+        let client = mock(vec![Ok(message.into()), Ok(referral.into())]);
+        let mut client = CachingClient::new(0, client, false);
+
+        assert!(block_on(client.lookup(
+            Query::query(Name::from_str("host.old.example.").unwrap(), RecordType::A),
+            DnsRequestOptions::default(),
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_dname_does_not_redirect_owner() {
+        let mut client = CachingClient::new(
+            0,
+            mock(vec![Ok(
+                dname_message("old.example.", "new.example.").into()
+            )]),
+            false,
+        );
+
+        assert!(block_on(client.lookup(
+            Query::query(Name::from_str("old.example.").unwrap(), RecordType::A),
+            DnsRequestOptions::default(),
+        ))
+        .is_err());
+    }
+
+    #[test]
+    fn test_dname_substitution_too_long() {
+        // the query is 250 octets long, the substituted name 257
+        let query = format!(
+            "{0}.{0}.{0}.{1}.old.example.",
+            "a".repeat(63),
+            "a".repeat(45)
+        );
+        let message = dname_message(&query, "new-target.example.");
+        let mut client = CachingClient::new(0, mock(vec![Ok(message.into())]), false);
+
+        match block_on(client.lookup(
+            Query::query(Name::from_str(&query).unwrap(), RecordType::A),
+            DnsRequestOptions::default(),
+        ))
+        .expect_err("substituted name is too long")
+        .kind()
+        {
+            ResolveErrorKind::NoRecordsFound { response_code, .. } => {
+                assert_eq!(*response_code, ResponseCode::YXDomain)
+            }
+            other => panic!("expected ResolveErrorKind::NoRecordsFound, got {:?}", other),
+        }
+    }
+
     #[test]
     fn test_early_return_localhost() {
         let cache = DnsLru::new(0, dns_lru::TtlConfig::default());
@@ -984,6 +2187,245 @@ mod tests {
         .is_err());
     }
 
+    #[cfg(feature = "dnssec")]
+    fn signed_message(dnssec_status: DnssecStatus) -> DnsResponse {
+        use proto::rr::dnssec::rdata::{DNSSECRData, SIG};
+        use proto::rr::dnssec::Algorithm;
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let mut rrsig = Record::from_rdata(
+            name.clone(),
+            86400,
+            RData::DNSSEC(DNSSECRData::SIG(SIG::new(
+                RecordType::A,
+                Algorithm::ED25519,
+                3,
+                86400,
+                5,
+                0,
+                2,
+                Name::from_str("example.com.").unwrap(),
+                vec![],
+            ))),
+        );
+        rrsig.set_rr_type(RecordType::RRSIG);
+
+        let mut message = Message::new();
+        message.add_query(Query::query(name.clone(), RecordType::A));
+        message.insert_answers(vec![
+            Record::from_rdata(name, 86400, RData::A(Ipv4Addr::new(127, 0, 0, 1))),
+            rrsig,
+        ]);
+
+        let mut response = DnsResponse::from(message);
+        response.set_dnssec_status(dnssec_status);
+        response
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_retain_dnssec_records() {
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+        // without retaining, the RRSIGs are dropped
+        let client = mock(vec![Ok(signed_message(DnssecStatus::Secure))]);
+        let mut client = CachingClient::new(1, client, false);
+        let lookup = block_on(client.lookup(query.clone(), DnsRequestOptions::default()))
+            .expect("lookup failed");
+        assert_eq!(lookup.dnssec_status(), DnssecStatus::Secure);
+        assert_eq!(lookup.rrsigs().count(), 0);
+
+        let client = mock(vec![Ok(signed_message(DnssecStatus::Secure))]);
+        let mut client = CachingClient::new(1, client, false).retain_dnssec_records(true);
+        let lookup = block_on(client.lookup(query.clone(), DnsRequestOptions::default()))
+            .expect("lookup failed");
+        assert_eq!(lookup.dnssec_status(), DnssecStatus::Secure);
+        assert_eq!(
+            lookup
+                .rrsigs()
+                .map(|rrsig| rrsig.type_covered())
+                .collect::<Vec<_>>(),
+            vec![RecordType::A]
+        );
+
+        // the status is retained in the cache
+        let lookup =
+            block_on(client.lookup(query, DnsRequestOptions::default())).expect("lookup failed");
+        assert_eq!(lookup.dnssec_status(), DnssecStatus::Secure);
+        assert_eq!(lookup.rrsigs().count(), 1);
+    }
+
+    #[cfg(feature = "dnssec")]
+    #[test]
+    fn test_bogus_not_cached() {
+        use proto::error::ProtoErrorKind;
+
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+        // the second lookup must go back to the upstream
+        let bogus = ProtoError::from(ProtoErrorKind::Bogus {
+            error: "bad signature".to_string(),
+            response: Box::new(signed_message(DnssecStatus::Bogus)),
+        });
+        let client = mock(vec![
+            Ok(signed_message(DnssecStatus::Secure)),
+            Err(ResolveError::from(bogus)),
+        ]);
+        let mut client = CachingClient::new(1, client, false).retain_dnssec_records(true);
+
+        let error = block_on(client.lookup(query.clone(), DnsRequestOptions::default()))
+            .expect_err("lookup should fail");
+        match error.kind() {
+            ResolveErrorKind::Proto(error) => match error.kind() {
+                ProtoErrorKind::Bogus { response, .. } => {
+                    assert_eq!(response.dnssec_status(), DnssecStatus::Bogus);
+                    assert_eq!(response.answers().len(), 2);
+                }
+                kind => panic!("unexpected error kind: {:?}", kind),
+            },
+            kind => panic!("unexpected error kind: {:?}", kind),
+        }
+
+        let lookup =
+            block_on(client.lookup(query, DnsRequestOptions::default())).expect("lookup failed");
+        assert_eq!(lookup.dnssec_status(), DnssecStatus::Secure);
+    }
+
+    /// Answers NS queries of zone cuts with NS records, NXDOMAIN for `b.example.com.` and an A record otherwise
+    #[derive(Clone, Default)]
+    struct ZoneCutHandle {
+        queries: Arc<Mutex<Vec<Query>>>,
+    }
+
+    impl DnsHandle for ZoneCutHandle {
+        type Response = Pin<
+            Box<dyn futures_util::Stream<Item = Result<DnsResponse, ResolveError>> + Send + Unpin>,
+        >;
+        type Error = ResolveError;
+
+        fn send<R: Into<proto::xfer::DnsRequest>>(&mut self, request: R) -> Self::Response {
+            let request = request.into();
+            let query = request.queries()[0].clone();
+            self.queries.lock().push(query.clone());
+
+            let mut message = Message::new();
+            message.add_query(query.clone());
+            if query.name() == &Name::from_str("b.example.com.").unwrap() {
+                message.set_response_code(ResponseCode::NXDomain);
+            } else if query.query_type() == RecordType::NS {
+                message.add_answer(Record::from_rdata(
+                    query.name().clone(),
+                    86400,
+                    RData::NS(Name::from_str("ns.example.net.").unwrap()),
+                ));
+            } else {
+                message.add_answer(Record::from_rdata(
+                    query.name().clone(),
+                    86400,
+                    RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+                ));
+            }
+
+            Box::pin(futures_util::stream::once(futures_util::future::ok(
+                message.into(),
+            )))
+        }
+    }
+
+    #[test]
+    fn test_qname_minimization() {
+        let handle = ZoneCutHandle::default();
+        let mut client = CachingClient::new(16, handle.clone(), false).qname_minimization(true);
+        let ns = |name: &str| Query::query(Name::from_str(name).unwrap(), RecordType::NS);
+
+        // the NXDOMAIN of the empty non-terminal falls back to the full name
+        let query = Query::query(Name::from_str("a.b.example.com.").unwrap(), RecordType::A);
+        let lookup = block_on(client.lookup(query.clone(), DnsRequestOptions::default())).unwrap();
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::new(192, 0, 2, 1))]
+        );
+        assert_eq!(
+            handle.queries.lock().drain(..).collect::<Vec<_>>(),
+            vec![
+                ns("com."),
+                ns("example.com."),
+                ns("b.example.com."),
+                query.clone()
+            ]
+        );
+        assert!(client.lookup_from_cache(&query).unwrap().is_ok());
+
+        // the fallback is remembered for the names below the step
+        let query = Query::query(Name::from_str("c.a.b.example.com.").unwrap(), RecordType::A);
+        block_on(client.lookup(query.clone(), DnsRequestOptions::default())).unwrap();
+        assert_eq!(
+            handle.queries.lock().drain(..).collect::<Vec<_>>(),
+            vec![query]
+        );
+
+        // the walk starts below the zone cut now in the cache
+        let query = Query::query(Name::from_str("c.d.example.com.").unwrap(), RecordType::A);
+        block_on(client.lookup(query.clone(), DnsRequestOptions::default())).unwrap();
+        assert_eq!(
+            handle.queries.lock().drain(..).collect::<Vec<_>>(),
+            vec![ns("d.example.com."), query]
+        );
+    }
+
+    #[test]
+    fn test_qname_minimization_disabled() {
+        let handle = ZoneCutHandle::default();
+        let mut client = CachingClient::new(16, handle.clone(), false);
+
+        let query = Query::query(Name::from_str("a.b.example.com.").unwrap(), RecordType::A);
+        block_on(client.lookup(query.clone(), DnsRequestOptions::default())).unwrap();
+        assert_eq!(
+            handle.queries.lock().drain(..).collect::<Vec<_>>(),
+            vec![query]
+        );
+    }
+
+    #[test]
+    fn test_parallel_lookups_share_cache() {
+        const THREADS: usize = 8;
+        const LOOKUPS: usize = 200;
+        const NAMES: usize = 16;
+
+        let handle = ZoneCutHandle::default();
+        let client = CachingClient::new(64, handle.clone(), false);
+
+        let threads = (0..THREADS)
+            .map(|thread| {
+                let mut client = client.clone();
+                std::thread::spawn(move || {
+                    for i in 0..LOOKUPS {
+                        let name = format!("www{}.example.com.", (thread + i) % NAMES);
+                        let query = Query::query(Name::from_str(&name).unwrap(), RecordType::A);
+                        let lookup = block_on(client.lookup(query, DnsRequestOptions::default()))
+                            .expect("lookup failed");
+                        assert_eq!(
+                            lookup.iter().cloned().collect::<Vec<_>>(),
+                            vec![RData::A(Ipv4Addr::new(192, 0, 2, 1))]
+                        );
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // every lookup checked the cache once, and only the misses were sent upstream
+        let stats = client.cache_stats();
+        let total = (THREADS * LOOKUPS) as u64;
+        assert_eq!(stats.hits + stats.misses, total);
+        assert_eq!(handle.queries.lock().len() as u64, stats.misses);
+        assert!(stats.misses >= NAMES as u64);
+        assert!(stats.inserts <= stats.misses);
+        assert_eq!(stats.entries, NAMES);
+    }
+
     #[test]
     fn test_early_return_invalid() {
         let cache = DnsLru::new(0, dns_lru::TtlConfig::default());
@@ -11,10 +11,16 @@ use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::ops::{Deref, DerefMut};
 use std::time::Duration;
 
-#[cfg(feature = "dns-over-rustls")]
+#[cfg(any(feature = "dns-over-rustls", feature = "dnssec"))]
 use std::sync::Arc;
 
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+use proto::op::PaddingPolicy;
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::TrustAnchor;
+use proto::rr::rdata::opt::ClientSubnet;
 use proto::rr::Name;
+pub use proto::xfer::BackoffPolicy;
 #[cfg(feature = "dns-over-rustls")]
 use rustls::ClientConfig;
 
@@ -36,6 +42,10 @@ pub struct ResolverConfig {
     search: Vec<Name>,
     // nameservers to use for resolution.
     name_servers: NameServerConfigGroup,
+    // the DNSKEYs from which the chain of trust is built
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(feature = "serde-config", serde(skip))]
+    trust_anchor: Option<Arc<TrustAnchor>>,
 }
 
 impl ResolverConfig {
@@ -46,6 +56,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::new(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -60,6 +72,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::google(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -74,6 +88,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::cloudflare(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -90,6 +106,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::cloudflare_tls(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -106,6 +124,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::cloudflare_https(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -120,6 +140,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::quad9(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -136,6 +158,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::quad9_tls(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -152,6 +176,8 @@ impl ResolverConfig {
             domain: None,
             search: vec![],
             name_servers: NameServerConfigGroup::quad9_https(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -171,6 +197,8 @@ impl ResolverConfig {
             domain,
             search,
             name_servers: name_servers.into(),
+            #[cfg(feature = "dnssec")]
+            trust_anchor: None,
         }
     }
 
@@ -210,6 +238,24 @@ impl ResolverConfig {
         &self.name_servers
     }
 
+    /// Returns the DNSKEYs from which the chain of trust is built when `ResolverOpts::validate` is set, see
+    ///  `set_trust_anchor`
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn trust_anchor(&self) -> Option<&Arc<TrustAnchor>> {
+        self.trust_anchor.as_ref()
+    }
+
+    /// Sets the DNSKEYs from which the chain of trust is built when `ResolverOpts::validate` is set
+    ///
+    /// Defaults to `None`, which uses the root zone keys built into `TrustAnchor::default`. The DNSKEYs
+    ///  validated down the chain are cached for their TTL.
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn set_trust_anchor(&mut self, trust_anchor: Option<Arc<TrustAnchor>>) {
+        self.trust_anchor = trust_anchor;
+    }
+
     /// return the associated TlsClientConfig
     #[cfg(feature = "dns-over-rustls")]
     #[cfg_attr(docsrs, doc(cfg(feature = "dns-over-rustls")))]
@@ -394,6 +440,10 @@ pub struct NameServerConfig {
     #[cfg_attr(feature = "serde-config", serde(default))]
     pub protocol: Protocol,
     /// SPKI name, only relevant for TLS connections
+    ///
+    /// The name of an encrypted server, e.g. `dns.example` of a DNS-over-HTTPS server, is only used for SNI and to
+    ///  verify its certificate, it's never resolved. The server is connected to at `socket_addr`, to bootstrap it by
+    ///  several addresses configure one name server for each, e.g. with `NameServerConfigGroup::from_ips_https`.
     #[cfg_attr(feature = "serde-config", serde(default))]
     pub tls_dns_name: Option<String>,
     /// Whether to trust `NXDOMAIN` responses from upstream nameservers.
@@ -510,7 +560,11 @@ impl NameServerConfigGroup {
         name_servers
     }
 
-    #[cfg(any(feature = "dns-over-tls", feature = "dns-over-https"))]
+    #[cfg(any(
+        feature = "dns-over-tls",
+        feature = "dns-over-https",
+        feature = "dns-over-quic"
+    ))]
     fn from_ips_encrypted(
         ips: &[IpAddr],
         port: u16,
@@ -567,6 +621,20 @@ impl NameServerConfigGroup {
         Self::from_ips_encrypted(ips, port, tls_dns_name, Protocol::Https, trust_nx_responses)
     }
 
+    /// Configure a NameServer address and port for DNS-over-QUIC
+    ///
+    /// This will create a QUIC connections.
+    #[cfg(feature = "dns-over-quic")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dns-over-quic")))]
+    pub fn from_ips_quic(
+        ips: &[IpAddr],
+        port: u16,
+        tls_dns_name: String,
+        trust_nx_responses: bool,
+    ) -> Self {
+        Self::from_ips_encrypted(ips, port, tls_dns_name, Protocol::Quic, trust_nx_responses)
+    }
+
     /// Creates a default configuration, using `8.8.8.8`, `8.8.4.4` and `2001:4860:4860::8888`, `2001:4860:4860::8844` (thank you, Google).
     ///
     /// Please see Google's [privacy statement](https://developers.google.com/speed/public-dns/privacy) for important information about what they track, many ISP's track similar information in DNS. To use the system configuration see: `Resolver::from_system_conf` and `AsyncResolver::from_system_conf`
@@ -729,6 +797,110 @@ impl Default for LookupIpStrategy {
     }
 }
 
+/// The order in which the name servers are queried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub enum ServerOrderingStrategy {
+    /// Prefer the name servers with the lowest smoothed round trip time, name servers which recently failed are
+    ///  tried last until their cooldown has passed (default)
+    QueryStatistics,
+    /// Always query the name servers in the order of the configuration
+    UserProvidedOrder,
+    /// Start each query with the next name server of the configuration, spreading the load across all of them
+    RoundRobin,
+}
+
+impl Default for ServerOrderingStrategy {
+    /// Returns [`ServerOrderingStrategy::QueryStatistics`] as the default.
+    fn default() -> Self {
+        Self::QueryStatistics
+    }
+}
+
+/// Addresses which are removed from the A and AAAA answers of lookups, see [`ResolverOpts::filter_addresses`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-config", derive(Serialize, Deserialize))]
+pub enum AddressFilter {
+    /// The private, loopback, link-local and other special purpose addresses which are not routable on the Internet,
+    ///  e.g. `10.0.0.0/8`, `127.0.0.0/8` and `fc00::/7`
+    ///
+    /// The IPv6 addresses which embed an IPv4 address are filtered by the IPv4 address: those mapped into IPv6,
+    ///  `::ffff:0:0/96`, IPv4-compatible, `::/96`, translated by the well-known NAT64 prefix, `64:ff9b::/96`, and
+    ///  of 6to4, `2002::/16`.
+    PrivateAndBogon,
+    /// The addresses within any of these prefixes
+    ///
+    /// The prefixes are borrowed for the lifetime of the program, which keeps `ResolverOpts` `Copy`. A list built
+    ///  at runtime can be leaked once, e.g. `AddressFilter::Prefixes(Box::leak(prefixes.into_boxed_slice()))`.
+    ///  This filter can't be (de)serialized.
+    #[cfg_attr(feature = "serde-config", serde(skip))]
+    Prefixes(&'static [IpNet]),
+}
+
+/// The IPv4 special purpose prefixes, from the [IANA registry](https://www.iana.org/assignments/iana-ipv4-special-registry)
+const BOGON_V4: &[(Ipv4Addr, u8)] = &[
+    (Ipv4Addr::new(0, 0, 0, 0), 8),
+    (Ipv4Addr::new(10, 0, 0, 0), 8),
+    (Ipv4Addr::new(100, 64, 0, 0), 10),
+    (Ipv4Addr::new(127, 0, 0, 0), 8),
+    (Ipv4Addr::new(169, 254, 0, 0), 16),
+    (Ipv4Addr::new(172, 16, 0, 0), 12),
+    (Ipv4Addr::new(192, 0, 0, 0), 24),
+    (Ipv4Addr::new(192, 0, 2, 0), 24),
+    (Ipv4Addr::new(192, 168, 0, 0), 16),
+    (Ipv4Addr::new(198, 18, 0, 0), 15),
+    (Ipv4Addr::new(198, 51, 100, 0), 24),
+    (Ipv4Addr::new(203, 0, 113, 0), 24),
+    (Ipv4Addr::new(224, 0, 0, 0), 4),
+    (Ipv4Addr::new(240, 0, 0, 0), 4),
+];
+
+/// The IPv6 special purpose prefixes, from the [IANA registry](https://www.iana.org/assignments/iana-ipv6-special-registry)
+const BOGON_V6: &[(Ipv6Addr, u8)] = &[
+    (Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0), 128),
+    (Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1), 128),
+    (Ipv6Addr::new(0x100, 0, 0, 0, 0, 0, 0, 0), 64),
+    (Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 0), 32),
+    (Ipv6Addr::new(0xfc00, 0, 0, 0, 0, 0, 0, 0), 7),
+    (Ipv6Addr::new(0xfe80, 0, 0, 0, 0, 0, 0, 0), 10),
+    (Ipv6Addr::new(0xfec0, 0, 0, 0, 0, 0, 0, 0), 10),
+    (Ipv6Addr::new(0xff00, 0, 0, 0, 0, 0, 0, 0), 8),
+];
+
+impl AddressFilter {
+    /// Returns true if the address is filtered
+    pub fn is_filtered(&self, addr: IpAddr) -> bool {
+        match self {
+            Self::PrivateAndBogon => match addr {
+                IpAddr::V4(addr) => is_bogon_v4(addr),
+                IpAddr::V6(addr) => match addr.octets() {
+                    // mapped ::ffff:0:0/96 and compatible ::/96
+                    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, a, b, c, d]
+                    | [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, a, b, c, d]
+                    // NAT64 64:ff9b::/96
+                    | [0, 0x64, 0xff, 0x9b, 0, 0, 0, 0, 0, 0, 0, 0, a, b, c, d]
+                    // 6to4 2002::/16
+                    | [0x20, 0x02, a, b, c, d, ..] => is_bogon_v4(Ipv4Addr::new(a, b, c, d)),
+                    _ => BOGON_V6.iter().any(|(net, len)| {
+                        Ipv6Net::new(*net, *len)
+                            .expect("invalid bogon prefix")
+                            .contains(&addr)
+                    }),
+                },
+            },
+            Self::Prefixes(prefixes) => prefixes.iter().any(|prefix| prefix.contains(&addr)),
+        }
+    }
+}
+
+fn is_bogon_v4(addr: Ipv4Addr) -> bool {
+    BOGON_V4.iter().any(|(net, len)| {
+        Ipv4Net::new(*net, *len)
+            .expect("invalid bogon prefix")
+            .contains(&addr)
+    })
+}
+
 /// Configuration for the Resolver
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 #[cfg_attr(
@@ -745,18 +917,51 @@ pub struct ResolverOpts {
     ///  the search
     pub ndots: usize,
     /// Specify the timeout for a request. Defaults to 5 seconds
+    ///
+    /// This bounds each attempt of a request to a single name server, an attempt which times out fails with
+    ///  `ResolveErrorKind::Timeout`, and is retried with the other name servers.
     pub timeout: Duration,
+    /// Optional deadline for a lookup as a whole, across all attempts, name servers and search names.
+    ///
+    /// A lookup which does not complete within this is abandoned with `ResolveErrorKind::DeadlineExceeded`, even
+    ///  while attempts remain. Defaults to `None`, where a lookup is only bounded by `timeout` and `attempts`.
+    pub total_deadline: Option<Duration>,
     /// Number of retries after lookup failure before giving up. Defaults to 2
     pub attempts: usize,
-    /// Rotate through the resource records in the response (if there is more than one for a given name)
+    /// How long to wait before each retry after a lookup failure, the number of retries is still bounded by `attempts`
+    ///
+    /// Defaults to `BackoffPolicy::Fixed(Duration::ZERO)`, retrying immediately.
+    pub retry_backoff: BackoffPolicy,
+    /// Rotate through the addresses of IP lookups (if there is more than one for a given name).
+    ///
+    /// Each lookup of a cached name returns its addresses rotated by one more position than the previous lookup,
+    ///  spreading connections across all of them. The rotation is applied after `sort_addresses`. Defaults to `false`.
     pub rotate: bool,
     /// Validate the names in the response, not implemented don't really see the point unless you need to support
     ///  badly configured DNS
     pub check_names: bool,
     /// Enable edns, for larger records
     pub edns0: bool,
+    /// The UDP payload size advertised with EDNS. Defaults to 1232, per [DNS Flag Day 2020](https://dnsflagday.net/2020/)
+    ///
+    /// Responses larger than this are truncated by the name server and retried over TCP. Larger sizes risk the
+    ///  fragmentation of responses, which are dropped on some network paths, see `edns_udp_shrink`.
+    pub edns_udp_size: u16,
+    /// Retry a UDP query, which timed out while advertising a payload size larger than 1232, with a payload size
+    ///  of 1232 before falling back to TCP
+    ///
+    /// The smaller size is advertised to that name server from then on. This only has an effect when `edns_udp_size`
+    ///  is larger than 1232. Defaults to `true`.
+    pub edns_udp_shrink: bool,
     /// Use DNSSec to validate the request
     pub validate: bool,
+    /// Retain the RRSIG, DNSKEY, NSEC and NSEC3 records of validated responses in the returned `Lookup`
+    ///
+    /// This only has an effect when `validate` is set. The outcome of validation is available from
+    ///  `Lookup::dnssec_status`. Responses that fail validation are always returned as an error, with this set
+    ///  the error is a `ProtoErrorKind::Bogus` which carries the failed response for inspection. Bogus responses
+    ///  are never cached. Defaults to `false`.
+    pub retain_dnssec_records: bool,
     /// The ip_strategy for the Resolver to use when lookup Ipv4 or Ipv6 addresses
     pub ip_strategy: LookupIpStrategy,
     /// Cache size is in number of records (some records can be large)
@@ -768,7 +973,10 @@ pub struct ResolverOpts {
     /// If this is set, any positive responses with a TTL lower than this value will have a TTL of
     /// `positive_min_ttl` instead. Otherwise, this will default to 0 seconds.
     pub positive_min_ttl: Option<Duration>,
-    /// Optional minimum TTL for negative (`NXDOMAIN`) responses.
+    /// Optional minimum TTL for negative (`NXDOMAIN` and `NODATA`) responses.
+    ///
+    /// The TTL of negative responses is derived from the SOA record in the authority section, the
+    /// lesser of its TTL and minimum field, see [RFC 2308](https://tools.ietf.org/html/rfc2308#section-5).
     ///
     /// If this is set, any negative responses with a TTL lower than this value will have a TTL of
     /// `negative_min_ttl` instead. Otherwise, this will default to 0 seconds.
@@ -780,22 +988,122 @@ pub struct ResolverOpts {
     ///
     /// [`MAX_TTL`]: ../dns_lru/const.MAX_TTL.html
     pub positive_max_ttl: Option<Duration>,
-    /// Optional maximum TTL for negative (`NXDOMAIN`) responses.
+    /// Optional maximum TTL for negative (`NXDOMAIN` and `NODATA`) responses.
     ///
     /// If this is set, any negative responses with a TTL higher than this value will have a TTL of
-    /// `negative_max_ttl` instead. Otherwise, this will default to [`MAX_TTL`] seconds.
+    /// `negative_max_ttl` instead. Otherwise, this will default to [`MAX_TTL`] seconds. This guards
+    /// against authorities with very large SOA minimums pinning negative answers in the cache.
     ///
     /// [`MAX_TTL`]: ../dns_lru/const.MAX_TTL.html
     pub negative_max_ttl: Option<Duration>,
+    /// Optional window for serving stale answers, [RFC 8767](https://tools.ietf.org/html/rfc8767).
+    ///
+    /// If this is set, positive responses are kept in the cache for this long after their TTL has
+    /// expired. A lookup for an expired entry returns the stale answer with a TTL of 30 seconds and
+    /// starts a refresh in the background, which replaces the entry once the upstream answers.
+    /// Should the upstream be unavailable, the stale answer is served until the window elapses.
+    /// Negative responses are never served stale.
+    pub serve_stale_ttl: Option<Duration>,
     /// Number of concurrent requests per query
     ///
     /// Where more than one nameserver is configured, this configures the resolver to send queries
     /// to a number of servers in parallel. Defaults to 2; 0 or 1 will execute requests serially.
+    /// `usize::MAX` sends each query to all of the name servers at once, the first answer wins and the
+    /// other requests are cancelled.
     pub num_concurrent_reqs: usize,
+    /// How long to wait for a positive answer from the other concurrent requests after a negative one
+    ///
+    /// A trusted negative answer (`NXDOMAIN` or no records) otherwise ends a query immediately, even when a slower
+    ///  name server among the concurrent requests would answer the query. With a grace period, such a positive
+    ///  answer is preferred if it arrives within the period. Only applies with `num_concurrent_reqs` above 1.
+    ///  Defaults to `None`, the first trusted answer wins.
+    pub negative_grace_period: Option<Duration>,
+    /// The order in which the name servers are queried, defaults to [`ServerOrderingStrategy::QueryStatistics`]
+    ///
+    /// With the default, the success, failure and round trip time of each name server are tracked, and the
+    ///  healthy, faster servers are preferred.
+    pub server_ordering_strategy: ServerOrderingStrategy,
     /// Preserve all intermediate records in the lookup response, suchas CNAME records
     pub preserve_intermediates: bool,
+    /// Minimize the names sent to the name servers, [RFC 7816](https://tools.ietf.org/html/rfc7816).
+    ///
+    /// Before a name is queried, NS queries walk down one label at a time from the closest zone cut known to the
+    ///  cache, so that each name server is only sent the labels needed for its referral. A step which fails or
+    ///  answers NXDOMAIN, as some servers do for empty non-terminals, falls back to querying the full name at once,
+    ///  and that fallback is remembered for later names below the step. The answer is cached under the full name.
+    ///  Defaults to `false`.
+    pub qname_minimization: bool,
+    /// The maximum number of queries made for a lookup while following a chain of CNAMEs and DNAMEs, including the
+    ///  first query.
+    ///
+    /// CNAMEs answered within the same response are followed without further queries. A chain which is longer than
+    ///  this fails with `ResolveErrorKind::NoRecordsFound`, a chain which leads back to one of its names fails with
+    ///  `ResolveErrorKind::CnameLoop`. Defaults to 8.
+    pub max_cname_chain: usize,
     /// Try queries over TCP if they fail over UDP.
     pub try_tcp_on_error: bool,
+    /// Optional EDNS Client Subnet, [RFC 7871](https://tools.ietf.org/html/rfc7871), to send with queries.
+    ///
+    /// This allows CDN-aware authorities to tailor responses to the network of the client. The scope used by the
+    ///  server is returned in the EDNS options of the response. Setting this implies EDNS.
+    pub client_subnet: Option<ClientSubnet>,
+    /// Sort the addresses of IP lookups per the destination address selection of [RFC 6724](https://tools.ietf.org/html/rfc6724).
+    ///
+    /// Defaults to `true` to match the behavior of `getaddrinfo`, this prefers addresses reachable from the local
+    ///  addresses of matching scope and family.
+    pub sort_addresses: bool,
+    /// Padding of queries with the EDNS Padding option, [RFC 7830](https://tools.ietf.org/html/rfc7830).
+    ///
+    /// This is only applied to queries over encrypted transports, i.e. TLS, HTTPS and QUIC, where it hides the size
+    ///  of the query. Defaults to padding to a multiple of 128 bytes, per [RFC 8467](https://tools.ietf.org/html/rfc8467).
+    pub padding: PaddingPolicy,
+    /// Randomize the case of the letters of query names, the DNS 0x20 encoding of
+    ///  [draft-vixie-dnsext-dns0x20](https://tools.ietf.org/html/draft-vixie-dnsext-dns0x20-00).
+    ///
+    /// Responses which do not echo the name with exactly the same case are treated as spoofed and the query is
+    ///  retried. This makes off-path spoofing harder, but some servers lowercase the names of queries and can't be
+    ///  used with it. Defaults to `false`.
+    pub case_randomization: bool,
+    /// The local address (IP and port) to send queries from, e.g. on multi-homed hosts.
+    ///
+    /// This applies to the name servers without their own `NameServerConfig::bind_addr` and with an address of the
+    ///  same family. With a port of 0 the ports of UDP queries are still randomized. Defaults to `None`, the system
+    ///  chooses the address.
+    pub bind_addr: Option<SocketAddr>,
+    /// Resolve `.local.` names with one-shot multicast DNS queries, [RFC 6762](https://tools.ietf.org/html/rfc6762#section-5.1).
+    ///
+    /// The query is sent to the mDNS multicast groups, and the answers of all responders received within a short
+    ///  window are returned together. Defaults to `false`, in which case `.local.` names are sent to the configured
+    ///  name servers like any other name.
+    #[cfg(feature = "mdns")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "mdns")))]
+    pub use_mdns: bool,
+    /// Synthesize AAAA records from A records with this NAT64 prefix, [RFC 6147](https://tools.ietf.org/html/rfc6147).
+    ///
+    /// When set, IP lookups for names without AAAA records return the IPv4 addresses of their A records embedded in
+    ///  the prefix, per [RFC 6052](https://tools.ietf.org/html/rfc6052#section-2.2). The prefix length must be one of
+    ///  32, 40, 48, 56, 64 or 96, e.g. the well-known prefix `64:ff9b::/96`. Defaults to `None`, no synthesis.
+    pub dns64_prefix: Option<Ipv6Net>,
+    /// Establish DNS Stateful Operations sessions, [RFC 8490](https://tools.ietf.org/html/rfc8490), on TCP and TLS
+    ///  connections, requesting this keepalive interval.
+    ///
+    /// The name server decides on the interval, which is at least ten seconds. Once a session is established, a
+    ///  keepalive is sent whenever the connection was idle for the interval, so that name servers supporting DSO keep
+    ///  it open instead of closing it as idle. Defaults to `None`, no sessions are requested.
+    pub dso_keepalive_interval: Option<Duration>,
+    /// Advertise the edns-tcp-keepalive option, [RFC 7828](https://tools.ietf.org/html/rfc7828), on TCP and TLS
+    ///  connections, closing them once idle for this timeout.
+    ///
+    /// A name server replying with the option sets the idle timeout of the connection instead. Defaults to `None`,
+    ///  the option is not sent and connections are kept open until the name server closes them.
+    pub tcp_keepalive: Option<Duration>,
+    /// Remove the addresses matching this filter from the A and AAAA answers of lookups, e.g. for protection from
+    ///  DNS rebinding.
+    ///
+    /// The records of filtered addresses are dropped before the response is cached. A lookup of which all the
+    ///  addresses are filtered fails with `ResolveErrorKind::NoRecordsFound`, as if the name had no records of the
+    ///  type. Defaults to `None`, no addresses are filtered.
+    pub filter_addresses: Option<AddressFilter>,
 }
 
 impl Default for ResolverOpts {
@@ -806,11 +1114,16 @@ impl Default for ResolverOpts {
         Self {
             ndots: 1,
             timeout: Duration::from_secs(5),
+            total_deadline: None,
             attempts: 2,
+            retry_backoff: BackoffPolicy::default(),
             rotate: false,
             check_names: true,
             edns0: false,
+            edns_udp_size: 1232,
+            edns_udp_shrink: true,
             validate: false,
+            retain_dnssec_records: false,
             ip_strategy: LookupIpStrategy::default(),
             cache_size: 32,
             use_hosts_file: true,
@@ -818,12 +1131,28 @@ impl Default for ResolverOpts {
             negative_min_ttl: None,
             positive_max_ttl: None,
             negative_max_ttl: None,
+            serve_stale_ttl: None,
             num_concurrent_reqs: 2,
+            negative_grace_period: None,
+            server_ordering_strategy: ServerOrderingStrategy::default(),
 
             // Defaults to `true` to match the behavior of dig and nslookup.
             preserve_intermediates: true,
+            qname_minimization: false,
 
+            max_cname_chain: 8,
             try_tcp_on_error: false,
+            client_subnet: None,
+            sort_addresses: true,
+            padding: PaddingPolicy::Block(128),
+            case_randomization: false,
+            bind_addr: None,
+            #[cfg(feature = "mdns")]
+            use_mdns: false,
+            dns64_prefix: None,
+            dso_keepalive_interval: None,
+            tcp_keepalive: None,
+            filter_addresses: None,
         }
     }
 }
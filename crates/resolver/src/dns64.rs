@@ -0,0 +1,250 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! DNS64, synthesis of AAAA records from A records for NAT64, [RFC 6147](https://tools.ietf.org/html/rfc6147)
+//!
+//! The IPv4 addresses are embedded in the NAT64 prefix per [RFC 6052](https://tools.ietf.org/html/rfc6052#section-2.2).
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use ipnet::Ipv6Net;
+
+/// The prefix lengths defined by RFC 6052
+const PREFIX_LENS: [u8; 6] = [32, 40, 48, 56, 64, 96];
+
+/// Bits 64 to 71 of the synthesized address, the "u" octet, must be zero
+const U_OCTET: usize = 8;
+
+/// The well-known prefix, `64:ff9b::/96`, [RFC 6052](https://tools.ietf.org/html/rfc6052#section-2.1)
+const WELL_KNOWN_PREFIX: Ipv6Addr = Ipv6Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0);
+
+/// The name queried for the discovery of the prefix, [RFC 7050](https://tools.ietf.org/html/rfc7050#section-2)
+pub(crate) const IPV4_ONLY_ARPA: &str = "ipv4only.arpa.";
+
+/// The well-known IPv4 addresses of `ipv4only.arpa.`, [RFC 7050](https://tools.ietf.org/html/rfc7050#section-2.2)
+const WELL_KNOWN_IPV4: [Ipv4Addr; 2] =
+    [Ipv4Addr::new(192, 0, 0, 170), Ipv4Addr::new(192, 0, 0, 171)];
+
+/// Checks that the prefix can be used for synthesis
+pub(crate) fn check_prefix(prefix: &Ipv6Net) -> Result<(), String> {
+    if !PREFIX_LENS.contains(&prefix.prefix_len()) {
+        return Err(format!(
+            "invalid dns64 prefix length {}, must be one of {:?}",
+            prefix, PREFIX_LENS
+        ));
+    }
+
+    if prefix.prefix_len() > 64 && prefix.network().octets()[U_OCTET] != 0 {
+        return Err(format!(
+            "invalid dns64 prefix {}, bits 64 to 71 must be zero",
+            prefix
+        ));
+    }
+
+    Ok(())
+}
+
+/// Embeds the IPv4 address in the prefix, None if the address must not be translated
+///
+/// The prefix must have been checked with `check_prefix`.
+pub(crate) fn synthesize(prefix: &Ipv6Net, ipv4: Ipv4Addr) -> Option<Ipv6Addr> {
+    if is_excluded(prefix, ipv4) {
+        return None;
+    }
+
+    let mut octets = prefix.network().octets();
+    let mut idx = usize::from(prefix.prefix_len() / 8);
+    for octet in ipv4.octets() {
+        if idx == U_OCTET {
+            idx += 1;
+        }
+        octets[idx] = octet;
+        idx += 1;
+    }
+
+    Some(Ipv6Addr::from(octets))
+}
+
+/// The IPv4 address embedded at the prefix length, the inverse of `synthesize`
+fn extract(ipv6: Ipv6Addr, prefix_len: u8) -> Ipv4Addr {
+    let octets = ipv6.octets();
+    let mut ipv4 = [0_u8; 4];
+    let mut idx = usize::from(prefix_len / 8);
+    for octet in ipv4.iter_mut() {
+        if idx == U_OCTET {
+            idx += 1;
+        }
+        *octet = octets[idx];
+        idx += 1;
+    }
+
+    Ipv4Addr::from(ipv4)
+}
+
+/// Derives the prefixes from the synthesized addresses of `ipv4only.arpa.`, [RFC 7050](https://tools.ietf.org/html/rfc7050#section-3)
+///
+/// Each address is searched for one of the well-known IPv4 addresses, starting with the longest
+///  prefix length, so that a well-known address which happens to be part of the prefix is not
+///  mistaken for the embedded one. Addresses without a well-known address are ignored, the
+///  distinct prefixes are returned in the order of the addresses.
+pub(crate) fn discover_prefixes<I: IntoIterator<Item = Ipv6Addr>>(addresses: I) -> Vec<Ipv6Net> {
+    let mut prefixes = Vec::new();
+    for address in addresses {
+        let prefix = PREFIX_LENS.iter().rev().find_map(|&prefix_len| {
+            let ipv4 = extract(address, prefix_len);
+            if !WELL_KNOWN_IPV4.contains(&ipv4) {
+                return None;
+            }
+
+            let prefix = Ipv6Net::new(address, prefix_len).ok()?.trunc();
+            if check_prefix(&prefix).is_ok() && synthesize(&prefix, ipv4) == Some(address) {
+                Some(prefix)
+            } else {
+                None
+            }
+        });
+
+        match prefix {
+            Some(prefix) if !prefixes.contains(&prefix) => prefixes.push(prefix),
+            Some(_) => (),
+            None => debug!("no well-known address embedded in {}", address),
+        }
+    }
+
+    prefixes
+}
+
+/// Addresses which are never reachable through NAT64, [RFC 6147](https://tools.ietf.org/html/rfc6147#section-5.1.4)
+///
+/// Additionally the well-known prefix must not be used for non-global addresses, [RFC 6052](https://tools.ietf.org/html/rfc6052#section-3.1)
+fn is_excluded(prefix: &Ipv6Net, ipv4: Ipv4Addr) -> bool {
+    let octets = ipv4.octets();
+    let not_global = ipv4.is_private() || (octets[0] == 100 && octets[1] & 0xc0 == 64);
+
+    octets[0] == 0
+        || ipv4.is_loopback()
+        || ipv4.is_link_local()
+        || ipv4.is_broadcast()
+        || (not_global && prefix.network() == WELL_KNOWN_PREFIX && prefix.prefix_len() == 96)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn net(prefix: &str) -> Ipv6Net {
+        prefix.parse().unwrap()
+    }
+
+    #[test]
+    fn test_check_prefix() {
+        for prefix in &[
+            "64:ff9b::/96",
+            "2001:db8::/32",
+            "2001:db8:100::/40",
+            "2001:db8:122::/48",
+            "2001:db8:122:300::/56",
+            "2001:db8:122:344::/64",
+            "2001:db8:122:344::/96",
+        ] {
+            assert!(check_prefix(&net(prefix)).is_ok(), "{}", prefix);
+        }
+
+        assert!(check_prefix(&net("64:ff9b::/80")).is_err());
+        assert!(check_prefix(&net("64:ff9b::/128")).is_err());
+        assert!(check_prefix(&net("2001:db8:122:344:100::/96")).is_err());
+    }
+
+    #[test]
+    fn test_synthesize() {
+        let ipv4 = Ipv4Addr::new(192, 0, 2, 33);
+
+        // RFC 6052, section 2.4
+        for (prefix, expected) in &[
+            ("2001:db8::/32", "2001:db8:c000:221::"),
+            ("2001:db8:100::/40", "2001:db8:1c0:2:21::"),
+            ("2001:db8:122::/48", "2001:db8:122:c000:2:2100::"),
+            ("2001:db8:122:300::/56", "2001:db8:122:3c0:0:221::"),
+            ("2001:db8:122:344::/64", "2001:db8:122:344:c0:2:2100:0"),
+            ("2001:db8:122:344::/96", "2001:db8:122:344::192.0.2.33"),
+        ] {
+            assert_eq!(
+                synthesize(&net(prefix), ipv4),
+                Some(expected.parse().unwrap()),
+                "{}",
+                prefix
+            );
+        }
+
+        assert_eq!(
+            synthesize(&net("64:ff9b::/96"), Ipv4Addr::new(192, 0, 2, 1)),
+            Some("64:ff9b::c000:201".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_excluded() {
+        let well_known = net("64:ff9b::/96");
+        let network_specific = net("2001:db8:122:344::/96");
+
+        for ipv4 in &[
+            Ipv4Addr::new(0, 0, 0, 0),
+            Ipv4Addr::new(127, 0, 0, 1),
+            Ipv4Addr::new(169, 254, 0, 1),
+            Ipv4Addr::new(255, 255, 255, 255),
+        ] {
+            assert_eq!(synthesize(&well_known, *ipv4), None, "{}", ipv4);
+            assert_eq!(synthesize(&network_specific, *ipv4), None, "{}", ipv4);
+        }
+
+        // non-global addresses may only be translated with a network-specific prefix
+        for ipv4 in &[
+            Ipv4Addr::new(10, 0, 0, 1),
+            Ipv4Addr::new(172, 16, 0, 1),
+            Ipv4Addr::new(192, 168, 0, 1),
+            Ipv4Addr::new(100, 64, 0, 1),
+        ] {
+            assert_eq!(synthesize(&well_known, *ipv4), None, "{}", ipv4);
+            assert!(synthesize(&network_specific, *ipv4).is_some(), "{}", ipv4);
+        }
+    }
+
+    #[test]
+    fn test_discover_prefixes() {
+        for prefix in &[
+            "64:ff9b::/96",
+            "2001:db8::/32",
+            "2001:db8:100::/40",
+            "2001:db8:122::/48",
+            "2001:db8:122:300::/56",
+            "2001:db8:122:344::/64",
+            "2001:db8:122:344::/96",
+        ] {
+            let prefix = net(prefix);
+            let addresses = WELL_KNOWN_IPV4
+                .iter()
+                .map(|ipv4| synthesize(&prefix, *ipv4).unwrap());
+            assert_eq!(discover_prefixes(addresses), vec![prefix]);
+        }
+
+        // the well-known address is part of the prefix, the embedded one is at /96
+        let prefix = net("2001:db8:c000:aa::/96");
+        let address = synthesize(&prefix, WELL_KNOWN_IPV4[1]).unwrap();
+        assert_eq!(discover_prefixes(vec![address]), vec![prefix]);
+
+        // every candidate in the order of the answers, addresses without a well-known address skipped
+        assert_eq!(
+            discover_prefixes(vec![
+                "2001:db8:122:344::192.0.0.171".parse().unwrap(),
+                "2001:db8:122:344::192.0.2.1".parse().unwrap(),
+                "64:ff9b::192.0.0.170".parse().unwrap(),
+                "2001:db8:122:344::192.0.0.170".parse().unwrap(),
+            ]),
+            vec![net("2001:db8:122:344::/96"), net("64:ff9b::/96")]
+        );
+    }
+}
@@ -8,14 +8,21 @@
 //! An LRU cache designed for work with DNS lookups
 
 use std::convert::TryFrom;
+use std::fmt;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use lru_cache::LruCache;
-use parking_lot::Mutex;
+use parking_lot::{Mutex, RwLock};
 
-use proto::op::Query;
+use proto::error::{ProtoErrorKind, ProtoResult};
+use proto::op::{Query, ResponseCode};
+use proto::rr::rdata::soa;
 use proto::rr::Record;
+use proto::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
+use proto::xfer::DnssecStatus;
 
 use crate::config;
 use crate::error::*;
@@ -25,11 +32,23 @@ use crate::lookup::Lookup;
 ///   Setting this to a value of 1 day, in seconds
 pub(crate) const MAX_TTL: u32 = 86400_u32;
 
+/// The TTL of stale answers, as recommended by https://tools.ietf.org/html/rfc8767#section-4
+pub(crate) const STALE_ANSWER_TTL: u32 = 30_u32;
+
+/// Identifies the format of a dump of the cache, see `DnsLru::dump`
+const DUMP_MAGIC: &[u8] = b"TDNSLRU\x01";
+const DUMP_POSITIVE: u8 = 0;
+const DUMP_NEGATIVE: u8 = 1;
+
 #[derive(Debug)]
 struct LruValue {
     // In the None case, this represents an NXDomain
     lookup: Result<Lookup, ResolveError>,
     valid_until: Instant,
+    // set while a lookup is refreshing this (stale) entry
+    refreshing: bool,
+    // the count of rotations of the addresses of this entry, see `next_rotation`
+    rotation: usize,
 }
 
 impl LruValue {
@@ -42,11 +61,81 @@ impl LruValue {
     fn ttl(&self, now: Instant) -> Duration {
         self.valid_until.saturating_duration_since(now)
     }
+
+    /// Returns true if this is a positive answer that may still be served stale
+    fn is_stale(&self, now: Instant, serve_stale_ttl: Duration) -> bool {
+        self.lookup.is_ok() && now <= self.valid_until + serve_stale_ttl
+    }
+}
+
+/// A snapshot of the counters of the cache, see [`crate::AsyncResolver::cache_stats`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Lookups which were answered from the cache, positively or negatively
+    pub hits: u64,
+    /// Lookups for which the cache had no current entry
+    pub misses: u64,
+    /// Entries added to the cache, including those replacing an entry for the same query
+    pub inserts: u64,
+    /// Entries removed from the cache, because it was full, the entry had expired, or the cache was cleared
+    pub evictions: u64,
+    /// The number of entries currently in the cache
+    pub entries: usize,
+}
+
+/// A change to the contents of the cache, see [`crate::AsyncResolver::set_cache_listener`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CacheEvent<'a> {
+    /// An entry was added for the query
+    Insert(&'a Query),
+    /// The entry for the query was removed
+    Evict(&'a Query),
+}
+
+type CacheListener = Box<dyn Fn(CacheEvent<'_>) + Send + Sync>;
+
+/// Counters and the listener of the cache, which are kept outside of the lock of the cache
+#[derive(Default)]
+struct CacheMetrics {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    inserts: AtomicU64,
+    evictions: AtomicU64,
+    entries: AtomicUsize,
+    listener: RwLock<Option<CacheListener>>,
+}
+
+impl CacheMetrics {
+    fn notify(&self, event: CacheEvent<'_>) {
+        if let Some(ref listener) = *self.listener.read() {
+            listener(event);
+        }
+    }
+
+    fn evicted(&self, query: &Query) {
+        self.evictions.fetch_add(1, Ordering::Relaxed);
+        self.notify(CacheEvent::Evict(query));
+    }
+}
+
+impl fmt::Debug for CacheMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheMetrics")
+            .field("hits", &self.hits)
+            .field("misses", &self.misses)
+            .field("inserts", &self.inserts)
+            .field("evictions", &self.evictions)
+            .field("entries", &self.entries)
+            .field("listener", &self.listener.read().is_some())
+            .finish()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub(crate) struct DnsLru {
     cache: Arc<Mutex<LruCache<Query, LruValue>>>,
+    metrics: Arc<CacheMetrics>,
     /// A minimum TTL value for positive responses.
     ///
     /// Positive responses with TTLs under `positive_max_ttl` will use
@@ -83,6 +172,11 @@ pub(crate) struct DnsLru {
     ///
     /// [`MAX_TTL`]: const.MAX_TTL.html
     negative_max_ttl: Duration,
+    /// How long positive responses may be served after their TTL has expired.
+    ///
+    /// If this value is not set on the `TtlConfig` used to construct this
+    /// `DnsLru`, it will default to 0, i.e. stale answers are never served.
+    serve_stale_ttl: Duration,
 }
 
 /// The time-to-live, TTL, configuration for use by the cache.
@@ -114,6 +208,9 @@ pub(crate) struct TtlConfig {
     /// `NXDOMAIN` responses with TTLs over `negative_max_ttl` will use
     /// `negative_max_ttl` instead.
     pub(crate) negative_max_ttl: Option<Duration>,
+    /// An optional window after the expiration of positive responses during
+    /// which they may still be served, see https://tools.ietf.org/html/rfc8767
+    pub(crate) serve_stale_ttl: Option<Duration>,
 }
 
 impl TtlConfig {
@@ -123,6 +220,7 @@ impl TtlConfig {
             negative_min_ttl: opts.negative_min_ttl,
             positive_max_ttl: opts.positive_max_ttl,
             negative_max_ttl: opts.negative_max_ttl,
+            serve_stale_ttl: opts.serve_stale_ttl,
         }
     }
 }
@@ -134,27 +232,95 @@ impl DnsLru {
             negative_min_ttl,
             positive_max_ttl,
             negative_max_ttl,
+            serve_stale_ttl,
         } = ttl_cfg;
         let cache = Arc::new(Mutex::new(LruCache::new(capacity)));
         Self {
             cache,
+            metrics: Arc::new(CacheMetrics::default()),
             positive_min_ttl: positive_min_ttl.unwrap_or_else(|| Duration::from_secs(0)),
             negative_min_ttl: negative_min_ttl.unwrap_or_else(|| Duration::from_secs(0)),
             positive_max_ttl: positive_max_ttl
                 .unwrap_or_else(|| Duration::from_secs(u64::from(MAX_TTL))),
             negative_max_ttl: negative_max_ttl
                 .unwrap_or_else(|| Duration::from_secs(u64::from(MAX_TTL))),
+            serve_stale_ttl: serve_stale_ttl.unwrap_or_else(|| Duration::from_secs(0)),
+        }
+    }
+
+    /// Returns an empty cache with the same TTL configuration, which shares nothing with this one
+    pub(crate) fn isolated(&self, capacity: usize) -> Self {
+        Self {
+            cache: Arc::new(Mutex::new(LruCache::new(capacity))),
+            metrics: Arc::new(CacheMetrics::default()),
+            ..self.clone()
         }
     }
 
     pub(crate) fn clear(&self) {
-        self.cache.lock().clear();
+        let cleared = {
+            let mut cache = self.cache.lock();
+            let cleared = cache
+                .iter()
+                .map(|(query, _)| query.clone())
+                .collect::<Vec<_>>();
+            cache.clear();
+            self.metrics.entries.store(0, Ordering::Relaxed);
+            cleared
+        };
+
+        for query in &cleared {
+            self.metrics.evicted(query);
+        }
+    }
+
+    /// Returns the current counters of the cache, this does not lock the cache
+    pub(crate) fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.metrics.hits.load(Ordering::Relaxed),
+            misses: self.metrics.misses.load(Ordering::Relaxed),
+            inserts: self.metrics.inserts.load(Ordering::Relaxed),
+            evictions: self.metrics.evictions.load(Ordering::Relaxed),
+            entries: self.metrics.entries.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Sets the listener which is called for every insertion into and eviction from the cache, replacing any previous one
+    pub(crate) fn set_listener(&self, listener: Option<CacheListener>) {
+        *self.metrics.listener.write() = listener;
+    }
+
+    /// Inserts the value, evicting the least recently used entry if the cache is full
+    fn insert_value(&self, query: Query, value: LruValue) {
+        let evicted = {
+            let mut cache = self.cache.lock();
+            if cache.capacity() == 0 {
+                return;
+            }
+
+            let evicted = if cache.len() >= cache.capacity() && !cache.contains_key(&query) {
+                cache.remove_lru().map(|(query, _)| query)
+            } else {
+                None
+            };
+
+            cache.insert(query.clone(), value);
+            self.metrics.entries.store(cache.len(), Ordering::Relaxed);
+            evicted
+        };
+
+        if let Some(ref evicted) = evicted {
+            self.metrics.evicted(evicted);
+        }
+        self.metrics.inserts.fetch_add(1, Ordering::Relaxed);
+        self.metrics.notify(CacheEvent::Insert(&query));
     }
 
     pub(crate) fn insert(
         &self,
         query: Query,
         records_and_ttl: Vec<(Record, u32)>,
+        dnssec_status: DnssecStatus,
         now: Instant,
     ) -> Lookup {
         let len = records_and_ttl.len();
@@ -174,13 +340,22 @@ impl DnsLru {
         let ttl = self.positive_min_ttl.max(ttl);
         let valid_until = now + ttl;
 
+        let lookup = Lookup::new_with_deadline(query.clone(), Arc::from(records), valid_until)
+            .with_dnssec_status(dnssec_status);
+
+        // bogus answers are handed back to the caller, but never cached
+        if dnssec_status.is_bogus() {
+            return lookup;
+        }
+
         // insert into the LRU
-        let lookup = Lookup::new_with_deadline(query.clone(), Arc::from(records), valid_until);
-        self.cache.lock().insert(
+        self.insert_value(
             query,
             LruValue {
                 lookup: Ok(lookup.clone()),
                 valid_until,
+                refreshing: false,
+                rotation: 0,
             },
         );
 
@@ -189,14 +364,25 @@ impl DnsLru {
 
     /// Generally for inserting a set of records that have already been cached, but with a different Query.
     pub(crate) fn duplicate(&self, query: Query, lookup: Lookup, ttl: u32, now: Instant) -> Lookup {
+        if lookup.dnssec_status().is_bogus() {
+            return lookup;
+        }
+
         let ttl = Duration::from_secs(u64::from(ttl));
         let valid_until = now + ttl;
 
-        self.cache.lock().insert(
+        // an answer from the cache did not come from a name server
+        self.insert_value(
             query,
             LruValue {
-                lookup: Ok(lookup.clone()),
+                lookup: Ok(lookup
+                    .clone()
+                    .with_server_addr(None)
+                    .with_response_flags(false, false)
+                    .with_client_subnet(None)),
                 valid_until,
+                refreshing: false,
+                rotation: 0,
             },
         );
 
@@ -245,11 +431,13 @@ impl DnsLru {
             {
                 let error = error.clone();
 
-                self.cache.lock().insert(
+                self.insert_value(
                     query,
                     LruValue {
                         lookup: Err(error),
                         valid_until,
+                        refreshing: false,
+                        rotation: 0,
                     },
                 );
             }
@@ -274,20 +462,326 @@ impl DnsLru {
                 }
                 Some(result)
             } else {
-                out_of_date = true;
+                out_of_date = !value.is_stale(now, self.serve_stale_ttl);
                 None
             }
         });
 
-        // in this case, we can preemptively remove out of data elements
+        // in this case, we can preemptively remove out of data elements, unless they may still be served stale
         // this assumes time is always moving forward, this would only not be true in contrived situations where now
         //  is not current time, like tests...
         if out_of_date {
             cache.remove(query);
+            self.metrics.entries.store(cache.len(), Ordering::Relaxed);
+        }
+        drop(cache);
+
+        if lookup.is_some() {
+            self.metrics.hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.metrics.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        if out_of_date {
+            self.metrics.evicted(query);
         }
 
         lookup
     }
+
+    /// Returns an expired positive answer that is still within the stale window, see https://tools.ietf.org/html/rfc8767
+    ///
+    /// The returned `Lookup` and its records have a TTL of [`STALE_ANSWER_TTL`]. Negative answers are never returned,
+    ///  those are only served for their normal TTL by `get`.
+    pub(crate) fn get_stale(&self, query: &Query, now: Instant) -> Option<Lookup> {
+        let mut cache = self.cache.lock();
+        let value = cache.get_mut(query)?;
+
+        if value.is_current(now) || !value.is_stale(now, self.serve_stale_ttl) {
+            return None;
+        }
+
+        let lookup = value.lookup.as_ref().ok()?;
+        let records = lookup
+            .record_iter()
+            .map(|record| {
+                let mut record = record.clone();
+                record.set_ttl(STALE_ANSWER_TTL);
+                record
+            })
+            .collect::<Vec<_>>();
+        let valid_until = now + Duration::from_secs(u64::from(STALE_ANSWER_TTL));
+
+        Some(
+            Lookup::new_with_deadline(query.clone(), Arc::from(records), valid_until)
+                .with_dnssec_status(lookup.dnssec_status()),
+        )
+    }
+
+    /// Marks a stale entry as being refreshed, returns false if a refresh is already in progress.
+    ///
+    /// The mark is cleared when the entry is replaced, or with `end_refresh`.
+    pub(crate) fn start_refresh(&self, query: &Query) -> bool {
+        match self.cache.lock().get_mut(query) {
+            Some(value) if value.refreshing => false,
+            Some(value) => {
+                value.refreshing = true;
+                true
+            }
+            None => true,
+        }
+    }
+
+    /// Clears the mark set by `start_refresh`
+    pub(crate) fn end_refresh(&self, query: &Query) {
+        if let Some(value) = self.cache.lock().get_mut(query) {
+            value.refreshing = false;
+        }
+    }
+
+    /// Returns the count of rotations of the entry for the query and advances it, for `ResolverOpts::rotate`
+    ///
+    /// Returns None if the query is not in the cache.
+    pub(crate) fn next_rotation(&self, query: &Query) -> Option<usize> {
+        self.cache.lock().get_mut(query).map(|value| {
+            let rotation = value.rotation;
+            value.rotation = rotation.wrapping_add(1);
+            rotation
+        })
+    }
+
+    /// Writes the current entries of the cache to the writer, returning the number of entries written
+    ///
+    /// Positive answers and `NoRecordsFound` answers are written with their remaining TTL, in the order
+    ///  of least to most recently used. Expired entries and other errors are not written. The dump
+    ///  is stamped with the wall clock, so that `load` can account for the time elapsed since.
+    ///
+    /// Each entry is encoded on its own and prefixed with its length, so the dump is not bounded by the
+    ///  size of a message, an entry which does not fit in one is skipped.
+    pub(crate) fn dump<W: Write>(
+        &self,
+        mut writer: W,
+        now: Instant,
+        wall_clock: SystemTime,
+    ) -> ResolveResult<usize> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(DUMP_MAGIC);
+        buf.extend_from_slice(&unix_secs(wall_clock).to_be_bytes());
+
+        let mut count = 0;
+        {
+            let cache = self.cache.lock();
+            for (query, value) in cache.iter().filter(|(_, value)| value.is_current(now)) {
+                let ttl = u32::try_from(value.ttl(now).as_secs()).unwrap_or(MAX_TTL);
+
+                let mut entry = Vec::new();
+                match Self::dump_entry(&mut BinEncoder::new(&mut entry), query, &value.lookup, ttl)
+                {
+                    Ok(true) => (),
+                    Ok(false) => continue,
+                    Err(e) => match e.kind() {
+                        ProtoErrorKind::MaxBufferSizeExceeded(_) => {
+                            debug!("not dumping the cache entry of {}: {}", query, e);
+                            continue;
+                        }
+                        _ => return Err(e.into()),
+                    },
+                }
+
+                // the encoder is bounded by u16::MAX
+                buf.extend_from_slice(&(entry.len() as u16).to_be_bytes());
+                buf.extend_from_slice(&entry);
+                count += 1;
+            }
+        }
+
+        writer.write_all(&buf)?;
+        Ok(count)
+    }
+
+    fn dump_entry(
+        encoder: &mut BinEncoder<'_>,
+        query: &Query,
+        lookup: &Result<Lookup, ResolveError>,
+        ttl: u32,
+    ) -> ProtoResult<bool> {
+        match lookup {
+            Ok(lookup) => {
+                encoder.emit_u8(DUMP_POSITIVE)?;
+                query.emit(encoder)?;
+                encoder.emit_u32(ttl)?;
+                encoder.emit_u8(match lookup.dnssec_status() {
+                    DnssecStatus::Bogus => 3,
+                    DnssecStatus::Secure => 2,
+                    DnssecStatus::Insecure => 1,
+                    DnssecStatus::Indeterminate => 0,
+                })?;
+                encoder.emit_u16(u16::try_from(lookup.len()).unwrap_or(u16::MAX))?;
+                for record in lookup.record_iter().take(usize::from(u16::MAX)) {
+                    let mut record = record.clone();
+                    record.set_ttl(record.ttl().min(ttl));
+                    record.emit(encoder)?;
+                }
+            }
+            Err(error) => match error.kind() {
+                ResolveErrorKind::NoRecordsFound {
+                    soa,
+                    response_code,
+                    trusted,
+                    ..
+                } => {
+                    encoder.emit_u8(DUMP_NEGATIVE)?;
+                    query.emit(encoder)?;
+                    encoder.emit_u32(ttl)?;
+                    encoder.emit_u16((*response_code).into())?;
+                    encoder.emit_u8(u8::from(*trusted))?;
+                    match soa {
+                        Some(soa) => {
+                            encoder.emit_u8(1)?;
+                            soa::emit(encoder, soa)?;
+                        }
+                        None => encoder.emit_u8(0)?,
+                    }
+                }
+                _ => return Ok(false),
+            },
+        }
+
+        Ok(true)
+    }
+
+    /// Loads the entries written by `dump` into the cache, returning the number of entries loaded
+    ///
+    /// The TTLs of the entries and their records are reduced by the wall clock time elapsed since the
+    ///  dump, entries which have expired since are dropped. Queries already in the cache are not replaced.
+    pub(crate) fn load<R: Read>(
+        &self,
+        mut reader: R,
+        now: Instant,
+        wall_clock: SystemTime,
+    ) -> ResolveResult<usize> {
+        let mut buf = Vec::new();
+        reader.read_to_end(&mut buf)?;
+
+        let mut decoder = BinDecoder::new(&buf);
+        let (magic, dumped_at) = Self::load_header(&mut decoder)?;
+        if magic != DUMP_MAGIC {
+            return Err(ResolveErrorKind::Message("not a dump of the cache").into());
+        }
+        let elapsed = unix_secs(wall_clock).saturating_sub(dumped_at);
+
+        let mut count = 0;
+        while !decoder.is_empty() {
+            let (query, value) = match Self::load_entry(&mut decoder, elapsed, now)? {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if self.cache.lock().contains_key(&query) {
+                continue;
+            }
+
+            // negative entries are subject to the negative TTL bounds, as when they were first cached
+            match value.lookup {
+                Err(error) => {
+                    self.negative(query, error, now);
+                }
+                Ok(_) => self.insert_value(query, value),
+            }
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    fn load_header<'a>(decoder: &mut BinDecoder<'a>) -> ProtoResult<(&'a [u8], u64)> {
+        let magic = decoder.read_slice(DUMP_MAGIC.len())?.unverified(/*compared by the caller*/);
+        let mut dumped_at = [0_u8; 8];
+        dumped_at.copy_from_slice(decoder.read_slice(8)?.unverified(/*any time is valid*/));
+
+        Ok((magic, u64::from_be_bytes(dumped_at)))
+    }
+
+    /// Reads an entry written by `dump_entry` and its length, returns None if it has expired
+    fn load_entry(
+        decoder: &mut BinDecoder<'_>,
+        elapsed: u64,
+        now: Instant,
+    ) -> ProtoResult<Option<(Query, LruValue)>> {
+        let len = decoder.read_u16()?.unverified(/*bounded by read_slice*/);
+        let entry = decoder.read_slice(usize::from(len))?.unverified(/*decoded below*/);
+        let decoder = &mut BinDecoder::new(entry);
+
+        let kind = decoder.read_u8()?.unverified(/*checked below*/);
+        let query = Query::read(decoder)?;
+        let ttl = decoder.read_u32()?.unverified(/*any ttl is valid*/);
+        let remaining = u32::try_from(u64::from(ttl).saturating_sub(elapsed)).unwrap_or(0);
+        let valid_until = now + Duration::from_secs(u64::from(remaining));
+
+        let lookup = match kind {
+            DUMP_POSITIVE => {
+                // the dump is not signed, so the records are not trusted to be secure anymore
+                let dnssec_status = match decoder.read_u8()?.unverified(/*unknown is indeterminate*/)
+                {
+                    3 => DnssecStatus::Bogus,
+                    1 => DnssecStatus::Insecure,
+                    _ => DnssecStatus::Indeterminate,
+                };
+                let count = decoder.read_u16()?.unverified(/*bounded by the data*/);
+                let mut records = Vec::with_capacity(usize::from(count).min(decoder.len()));
+                for _ in 0..count {
+                    let mut record = Record::read(decoder)?;
+                    record.set_ttl(record.ttl().saturating_sub(ttl - remaining));
+                    records.push(record);
+                }
+
+                Ok(
+                    Lookup::new_with_deadline(query.clone(), Arc::from(records), valid_until)
+                        .with_dnssec_status(dnssec_status),
+                )
+            }
+            DUMP_NEGATIVE => {
+                let response_code: ResponseCode =
+                    decoder.read_u16()?.unverified(/*any code is valid*/).into();
+                let trusted = decoder.read_u8()?.unverified(/*any value is valid*/) != 0;
+                let soa = match decoder.read_u8()?.unverified(/*any value is valid*/) {
+                    0 => None,
+                    _ => Some(Box::new(soa::read(decoder)?)),
+                };
+
+                Err(ResolveErrorKind::NoRecordsFound {
+                    query: Box::new(query.clone()),
+                    soa,
+                    negative_ttl: Some(remaining),
+                    response_code,
+                    trusted,
+                    extended_errors: Vec::new(),
+                }
+                .into())
+            }
+            kind => return Err(format!("unknown kind of cache entry: {}", kind).into()),
+        };
+
+        if remaining == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            query,
+            LruValue {
+                lookup,
+                valid_until,
+                refreshing: false,
+                rotation: 0,
+            },
+        )))
+    }
+}
+
+/// The seconds since the Unix epoch, or 0 if the clock is before it
+fn unix_secs(wall_clock: SystemTime) -> u64 {
+    wall_clock
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or(0)
 }
 
 // see also the lookup_tests.rs in integration-tests crate
@@ -312,6 +806,8 @@ mod tests {
         let value = LruValue {
             lookup: Err(ResolveErrorKind::Message("test error").into()),
             valid_until: future,
+            refreshing: false,
+            rotation: 0,
         };
 
         assert!(value.is_current(now));
@@ -340,7 +836,7 @@ mod tests {
         };
         let lru = DnsLru::new(1, ttls);
 
-        let rc_ips = lru.insert(query.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(query.clone(), ips_ttl, DnssecStatus::Indeterminate, now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the cache's min TTL, since the
         // query's TTL was below the minimum.
@@ -352,7 +848,7 @@ mod tests {
             3,
         )];
 
-        let rc_ips = lru.insert(query, ips_ttl, now);
+        let rc_ips = lru.insert(query, ips_ttl, DnssecStatus::Indeterminate, now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the record's TTL, since it's
         // greater than the cache's minimum.
@@ -379,6 +875,7 @@ mod tests {
             negative_ttl: Some(1),
             response_code: ResponseCode::NoError,
             trusted: false,
+            extended_errors: vec![],
         };
         let nx_error = lru.negative(name.clone(), err.into(), now);
         match nx_error.kind() {
@@ -397,6 +894,7 @@ mod tests {
             negative_ttl: Some(3),
             response_code: ResponseCode::NoError,
             trusted: false,
+            extended_errors: vec![],
         };
         let nx_error = lru.negative(name, err.into(), now);
         match nx_error.kind() {
@@ -430,7 +928,7 @@ mod tests {
         };
         let lru = DnsLru::new(1, ttls);
 
-        let rc_ips = lru.insert(query.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(query.clone(), ips_ttl, DnssecStatus::Indeterminate, now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the cache's min TTL, since the
         // query's TTL was above the maximum.
@@ -442,7 +940,7 @@ mod tests {
             59,
         )];
 
-        let rc_ips = lru.insert(query, ips_ttl, now);
+        let rc_ips = lru.insert(query, ips_ttl, DnssecStatus::Indeterminate, now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
         // the returned lookup should use the record's TTL, since it's
         // below than the cache's maximum.
@@ -469,6 +967,7 @@ mod tests {
             negative_ttl: Some(62),
             response_code: ResponseCode::NoError,
             trusted: false,
+            extended_errors: vec![],
         };
         let nx_error = lru.negative(name.clone(), err.into(), now);
         match nx_error.kind() {
@@ -487,6 +986,7 @@ mod tests {
             negative_ttl: Some(59),
             response_code: ResponseCode::NoError,
             trusted: false,
+            extended_errors: vec![],
         };
         let nx_error = lru.negative(name, err.into(), now);
         match nx_error.kind() {
@@ -513,7 +1013,7 @@ mod tests {
         let ips = vec![RData::A(Ipv4Addr::new(127, 0, 0, 1))];
         let lru = DnsLru::new(1, TtlConfig::default());
 
-        let rc_ips = lru.insert(query.clone(), ips_ttl, now);
+        let rc_ips = lru.insert(query.clone(), ips_ttl, DnssecStatus::Indeterminate, now);
         assert_eq!(*rc_ips.iter().next().unwrap(), ips[0]);
 
         let rc_ips = lru.get(&query, now).unwrap().expect("records should exist");
@@ -542,7 +1042,7 @@ mod tests {
         ];
         let lru = DnsLru::new(1, TtlConfig::default());
 
-        lru.insert(query.clone(), ips_ttl, now);
+        lru.insert(query.clone(), ips_ttl, DnssecStatus::Indeterminate, now);
 
         // still valid
         let rc_ips = lru
@@ -584,7 +1084,7 @@ mod tests {
             ..TtlConfig::default()
         };
         let lru = DnsLru::new(1, ttls);
-        lru.insert(query.clone(), ips_ttl, now);
+        lru.insert(query.clone(), ips_ttl, DnssecStatus::Indeterminate, now);
 
         // still valid
         let rc_ips = lru
@@ -644,7 +1144,7 @@ mod tests {
             ..TtlConfig::default()
         };
         let lru = DnsLru::new(1, ttls);
-        lru.insert(query.clone(), ips_ttl, now);
+        lru.insert(query.clone(), ips_ttl, DnssecStatus::Indeterminate, now);
 
         // still valid
         let rc_ips = lru
@@ -667,4 +1167,388 @@ mod tests {
         let rc_ips = lru.get(&query, now + Duration::from_secs(3));
         assert!(rc_ips.is_none());
     }
+
+    #[test]
+    fn test_serve_stale() {
+        let now = Instant::now();
+        let name = Name::from_str("www.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let ips_ttl = vec![(
+            Record::from_rdata(name, 60, RData::A(Ipv4Addr::new(127, 0, 0, 1))),
+            60,
+        )];
+
+        let ttls = TtlConfig {
+            serve_stale_ttl: Some(Duration::from_secs(120)),
+            ..TtlConfig::default()
+        };
+        let lru = DnsLru::new(1, ttls);
+        lru.insert(query.clone(), ips_ttl, DnssecStatus::Indeterminate, now);
+
+        // not stale yet
+        assert!(lru
+            .get_stale(&query, now + Duration::from_secs(30))
+            .is_none());
+
+        // expired, but still within the stale window
+        let expired = now + Duration::from_secs(61);
+        assert!(lru.get(&query, expired).is_none());
+        let stale = lru
+            .get_stale(&query, expired)
+            .expect("stale records should exist");
+        assert_eq!(
+            *stale.iter().next().unwrap(),
+            RData::A(Ipv4Addr::new(127, 0, 0, 1))
+        );
+        assert_eq!(stale.record_iter().next().unwrap().ttl(), STALE_ANSWER_TTL);
+        assert_eq!(
+            stale.valid_until(),
+            expired + Duration::from_secs(u64::from(STALE_ANSWER_TTL))
+        );
+
+        // only one refresh at a time
+        assert!(lru.start_refresh(&query));
+        assert!(!lru.start_refresh(&query));
+        lru.end_refresh(&query);
+        assert!(lru.start_refresh(&query));
+
+        // past the stale window
+        let gone = now + Duration::from_secs(181);
+        assert!(lru.get(&query, gone).is_none());
+        assert!(lru.get_stale(&query, gone).is_none());
+    }
+
+    #[test]
+    fn test_never_serve_stale_negative() {
+        let now = Instant::now();
+        let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+
+        let ttls = TtlConfig {
+            serve_stale_ttl: Some(Duration::from_secs(120)),
+            ..TtlConfig::default()
+        };
+        let lru = DnsLru::new(1, ttls);
+
+        let err = ResolveErrorKind::NoRecordsFound {
+            query: Box::new(query.clone()),
+            soa: None,
+            negative_ttl: Some(60),
+            response_code: ResponseCode::NXDomain,
+            trusted: false,
+            extended_errors: vec![],
+        };
+        lru.negative(query.clone(), err.into(), now);
+
+        assert!(lru.get(&query, now + Duration::from_secs(60)).is_some());
+
+        let expired = now + Duration::from_secs(61);
+        assert!(lru.get_stale(&query, expired).is_none());
+        assert!(lru.get(&query, expired).is_none());
+    }
+
+    #[test]
+    fn test_dump_and_load_dnssec_status() {
+        let now = Instant::now();
+        let wall_clock = SystemTime::now();
+
+        let lru = DnsLru::new(4, TtlConfig::default());
+        let mut queries = Vec::new();
+        for (i, status) in [DnssecStatus::Secure, DnssecStatus::Bogus]
+            .iter()
+            .enumerate()
+        {
+            let name = Name::from_str(&format!("www{}.example.com.", i)).unwrap();
+            let query = Query::query(name.clone(), RecordType::A);
+            let record = Record::from_rdata(name, 300, RData::A(Ipv4Addr::LOCALHOST));
+            let valid_until = now + Duration::from_secs(300);
+            let lookup = Lookup::new_with_deadline(query.clone(), Arc::from([record]), valid_until)
+                .with_dnssec_status(*status);
+
+            // bogus lookups are not cached by insert
+            lru.insert_value(
+                query.clone(),
+                LruValue {
+                    lookup: Ok(lookup),
+                    valid_until,
+                    refreshing: false,
+                    rotation: 0,
+                },
+            );
+            queries.push(query);
+        }
+
+        let mut dump = Vec::new();
+        assert_eq!(lru.dump(&mut dump, now, wall_clock).unwrap(), 2);
+
+        let reloaded = DnsLru::new(4, TtlConfig::default());
+        assert_eq!(reloaded.load(&dump[..], now, wall_clock).unwrap(), 2);
+
+        // secure entries are downgraded, bogus entries stay bogus
+        let status = |query| reloaded.get(query, now).unwrap().unwrap().dnssec_status();
+        assert_eq!(status(&queries[0]), DnssecStatus::Indeterminate);
+        assert_eq!(status(&queries[1]), DnssecStatus::Bogus);
+    }
+
+    #[test]
+    fn test_dump_and_load_over_64k() {
+        let now = Instant::now();
+        let wall_clock = SystemTime::now();
+
+        let lru = DnsLru::new(2048, TtlConfig::default());
+        for i in 0..2048 {
+            let name = Name::from_str(&format!("www{}.example.com.", i)).unwrap();
+            let query = Query::query(name.clone(), RecordType::A);
+            let record = Record::from_rdata(name, 300, RData::A(Ipv4Addr::LOCALHOST));
+            lru.insert(query, vec![(record, 300)], DnssecStatus::Insecure, now);
+        }
+
+        let mut dump = Vec::new();
+        assert_eq!(lru.dump(&mut dump, now, wall_clock).unwrap(), 2048);
+        assert!(dump.len() > usize::from(u16::MAX));
+
+        let reloaded = DnsLru::new(2048, TtlConfig::default());
+        assert_eq!(reloaded.load(&dump[..], now, wall_clock).unwrap(), 2048);
+
+        let query = Query::query(
+            Name::from_str("www2047.example.com.").unwrap(),
+            RecordType::A,
+        );
+        let lookup = reloaded.get(&query, now).unwrap().unwrap();
+        assert_eq!(
+            lookup.iter().cloned().collect::<Vec<_>>(),
+            vec![RData::A(Ipv4Addr::LOCALHOST)]
+        );
+    }
+
+    #[test]
+    fn test_dump_and_load() {
+        let now = Instant::now();
+        let wall_clock = SystemTime::now();
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let query = Query::query(name.clone(), RecordType::A);
+        let record = Record::from_rdata(name, 300, RData::A(Ipv4Addr::LOCALHOST));
+        let negative_query =
+            Query::query(Name::from_str("nx.example.com.").unwrap(), RecordType::A);
+        let err = ResolveErrorKind::NoRecordsFound {
+            query: Box::new(negative_query.clone()),
+            soa: None,
+            negative_ttl: Some(150),
+            response_code: ResponseCode::NXDomain,
+            trusted: true,
+            extended_errors: vec![],
+        };
+
+        let lru = DnsLru::new(4, TtlConfig::default());
+        lru.insert(
+            query.clone(),
+            vec![(record, 300)],
+            DnssecStatus::Insecure,
+            now,
+        );
+        lru.negative(negative_query.clone(), err.into(), now);
+
+        let mut dump = Vec::new();
+        assert_eq!(lru.dump(&mut dump, now, wall_clock).unwrap(), 2);
+
+        // reloaded 100 seconds later
+        let later = Instant::now();
+        let reloaded = DnsLru::new(4, TtlConfig::default());
+        let loaded = reloaded
+            .load(&dump[..], later, wall_clock + Duration::from_secs(100))
+            .unwrap();
+        assert_eq!(loaded, 2);
+
+        let lookup = reloaded.get(&query, later).unwrap().unwrap();
+        assert_eq!(lookup.valid_until(), later + Duration::from_secs(200));
+        assert_eq!(lookup.dnssec_status(), DnssecStatus::Insecure);
+        let records = lookup.record_iter().collect::<Vec<_>>();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].ttl(), 200);
+        assert_eq!(records[0].data(), Some(&RData::A(Ipv4Addr::LOCALHOST)));
+
+        match reloaded
+            .get(&negative_query, later)
+            .unwrap()
+            .unwrap_err()
+            .kind()
+        {
+            ResolveErrorKind::NoRecordsFound {
+                negative_ttl,
+                response_code,
+                trusted,
+                ..
+            } => {
+                assert_eq!(*negative_ttl, Some(50));
+                assert_eq!(*response_code, ResponseCode::NXDomain);
+                assert!(*trusted);
+            }
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+        assert!(reloaded
+            .get(&negative_query, later + Duration::from_secs(51))
+            .is_none());
+
+        // the negative entry is capped by the negative TTL bound of the cache it is loaded into
+        let ttls = TtlConfig {
+            negative_max_ttl: Some(Duration::from_secs(30)),
+            ..TtlConfig::default()
+        };
+        let reloaded = DnsLru::new(4, ttls);
+        reloaded
+            .load(&dump[..], later, wall_clock + Duration::from_secs(100))
+            .unwrap();
+        assert!(reloaded
+            .get(&negative_query, later + Duration::from_secs(29))
+            .is_some());
+        assert!(reloaded
+            .get(&negative_query, later + Duration::from_secs(31))
+            .is_none());
+
+        // the negative entry expired since the dump
+        let reloaded = DnsLru::new(4, TtlConfig::default());
+        let loaded = reloaded
+            .load(&dump[..], later, wall_clock + Duration::from_secs(200))
+            .unwrap();
+        assert_eq!(loaded, 1);
+        assert!(reloaded.get(&negative_query, later).is_none());
+
+        // everything expired since the dump
+        let reloaded = DnsLru::new(4, TtlConfig::default());
+        let loaded = reloaded
+            .load(&dump[..], later, wall_clock + Duration::from_secs(400))
+            .unwrap();
+        assert_eq!(loaded, 0);
+        assert!(reloaded.get(&query, later).is_none());
+
+        // not a dump
+        assert!(reloaded.load(&b"garbage"[..], later, wall_clock).is_err());
+        assert!(reloaded
+            .load(&dump[..dump.len() - 1], later, wall_clock)
+            .is_err());
+    }
+
+    fn a_query(i: usize) -> (Query, Vec<(Record, u32)>) {
+        let name = Name::from_str(&format!("www{}.example.com.", i)).unwrap();
+        let record = Record::from_rdata(name.clone(), 60, RData::A(Ipv4Addr::LOCALHOST));
+        (Query::query(name, RecordType::A), vec![(record, 60)])
+    }
+
+    #[test]
+    fn test_stats() {
+        let now = Instant::now();
+        let lru = DnsLru::new(2, TtlConfig::default());
+
+        let (query, records) = a_query(0);
+        assert!(lru.get(&query, now).is_none());
+        lru.insert(query.clone(), records, DnssecStatus::Indeterminate, now);
+        assert!(lru.get(&query, now).is_some());
+        assert_eq!(
+            lru.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                inserts: 1,
+                evictions: 0,
+                entries: 1,
+            }
+        );
+
+        // the expired entry is evicted on the next get
+        assert!(lru.get(&query, now + Duration::from_secs(61)).is_none());
+        assert_eq!(lru.stats().evictions, 1);
+        assert_eq!(lru.stats().entries, 0);
+
+        for i in 1..=3 {
+            let (query, records) = a_query(i);
+            lru.insert(query, records, DnssecStatus::Indeterminate, now);
+        }
+        assert_eq!(lru.stats().evictions, 2);
+        assert_eq!(lru.stats().entries, 2);
+
+        lru.clear();
+        assert_eq!(lru.stats().evictions, 4);
+        assert_eq!(lru.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_listener() {
+        let now = Instant::now();
+        let lru = DnsLru::new(1, TtlConfig::default());
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let listener_events = Arc::clone(&events);
+        lru.set_listener(Some(Box::new(move |event: CacheEvent<'_>| {
+            let event = match event {
+                CacheEvent::Insert(query) => format!("insert {}", query.name()),
+                CacheEvent::Evict(query) => format!("evict {}", query.name()),
+            };
+            listener_events.lock().push(event);
+        })));
+
+        for i in 0..2 {
+            let (query, records) = a_query(i);
+            lru.insert(query, records, DnssecStatus::Indeterminate, now);
+        }
+        lru.set_listener(None);
+        lru.clear();
+
+        assert_eq!(
+            *events.lock(),
+            vec![
+                "insert www0.example.com.",
+                "evict www0.example.com.",
+                "insert www1.example.com.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_stats_add_up() {
+        const THREADS: usize = 8;
+        const QUERIES: usize = 500;
+
+        let now = Instant::now();
+        let lru = DnsLru::new(64, TtlConfig::default());
+        let inserted = Arc::new(AtomicU64::new(0));
+        let evicted = Arc::new(AtomicU64::new(0));
+
+        let listener_inserted = Arc::clone(&inserted);
+        let listener_evicted = Arc::clone(&evicted);
+        lru.set_listener(Some(Box::new(move |event: CacheEvent<'_>| {
+            match event {
+                CacheEvent::Insert(_) => &listener_inserted,
+                CacheEvent::Evict(_) => &listener_evicted,
+            }
+            .fetch_add(1, Ordering::Relaxed);
+        })));
+
+        let threads = (0..THREADS)
+            .map(|thread| {
+                let lru = lru.clone();
+                std::thread::spawn(move || {
+                    for i in 0..QUERIES {
+                        // every query is only inserted once, so no entry is ever replaced
+                        let (query, records) = a_query(thread * QUERIES + i);
+                        lru.get(&query, now);
+                        lru.insert(query.clone(), records, DnssecStatus::Indeterminate, now);
+                        lru.get(&query, now);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let stats = lru.stats();
+        let total = (THREADS * QUERIES) as u64;
+        assert_eq!(stats.hits + stats.misses, 2 * total);
+        assert!(stats.misses >= total);
+        assert_eq!(stats.inserts, total);
+        assert_eq!(stats.entries, 64);
+        assert_eq!(stats.inserts - stats.evictions, stats.entries as u64);
+        assert_eq!(inserted.load(Ordering::Relaxed), stats.inserts);
+        assert_eq!(evicted.load(Ordering::Relaxed), stats.evictions);
+    }
 }
@@ -47,8 +47,8 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> DnsSdH
         let this = self.clone();
 
         let ptr_future = async move {
+            // the responses of all responders are collected by the mDNS query
             let mut options = DnsRequestOptions::default();
-            options.expects_multiple_responses = true;
             // TODO: This should use the AsyncResolver's options.edns0
             // setting, but options is private.
             options.use_edns = false;
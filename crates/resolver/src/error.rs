@@ -8,13 +8,16 @@
 //! Error types for the crate
 
 use std::cmp::Ordering;
+use std::time::Duration;
 use std::{fmt, io, sync};
 
 use thiserror::Error;
 
 use crate::proto::error::{ProtoError, ProtoErrorKind};
-use crate::proto::op::{Query, ResponseCode};
+use crate::proto::op::{Message, Query, ResponseCode};
+use crate::proto::rr::rdata::opt::ExtendedError;
 use crate::proto::rr::rdata::SOA;
+use crate::proto::rr::Name;
 use crate::proto::xfer::retry_dns_handle::RetryableError;
 use crate::proto::xfer::DnsResponse;
 #[cfg(feature = "backtrace")]
@@ -55,8 +58,19 @@ pub enum ResolveErrorKind {
         response_code: ResponseCode,
         /// If we trust `NXDOMAIN` errors from this server
         trusted: bool,
+        /// Extended DNS Errors, RFC 8914, returned by the server
+        extended_errors: Vec<ExtendedError>,
     },
 
+    /// The SRV records of a service have the single target `.`, the service is decidedly not
+    ///  available at the domain, [RFC 2782](https://tools.ietf.org/html/rfc2782)
+    #[error("service is not available at {0}")]
+    NoService(Box<Name>),
+
+    /// A chain of CNAMEs leads back to this name, which was already visited by the lookup
+    #[error("CNAME loop detected at {0}")]
+    CnameLoop(Box<Name>),
+
     // foreign
     /// An error got returned from IO
     #[error("io error: {0}")]
@@ -66,9 +80,13 @@ pub enum ResolveErrorKind {
     #[error("proto error: {0}")]
     Proto(#[from] ProtoError),
 
-    /// A request timed out
+    /// A request to a single name server timed out, see `ResolverOpts::timeout`
     #[error("request timed out")]
     Timeout,
+
+    /// The lookup as a whole, across all attempts and name servers, ran past `ResolverOpts::total_deadline`
+    #[error("lookup exceeded its total deadline of {0:?}")]
+    DeadlineExceeded(Duration),
 }
 
 impl Clone for ResolveErrorKind {
@@ -78,23 +96,28 @@ impl Clone for ResolveErrorKind {
             NoConnections => NoConnections,
             Message(msg) => Message(msg),
             Msg(ref msg) => Msg(msg.clone()),
+            NoService(ref name) => NoService(name.clone()),
+            CnameLoop(ref name) => CnameLoop(name.clone()),
             NoRecordsFound {
                 ref query,
                 ref soa,
                 negative_ttl,
                 response_code,
                 trusted,
+                ref extended_errors,
             } => NoRecordsFound {
                 query: query.clone(),
                 soa: soa.clone(),
                 negative_ttl: *negative_ttl,
                 response_code: *response_code,
                 trusted: *trusted,
+                extended_errors: extended_errors.clone(),
             },
             // foreign
             Io(io) => Self::from(std::io::Error::from(io.kind())),
             Proto(proto) => Self::from(proto.clone()),
             Timeout => Timeout,
+            DeadlineExceeded(deadline) => DeadlineExceeded(*deadline),
         }
     }
 }
@@ -121,6 +144,7 @@ impl ResolveError {
             negative_ttl,
             response_code,
             trusted,
+            extended_errors: Vec::new(),
         }
         .into()
     }
@@ -130,6 +154,49 @@ impl ResolveError {
         &self.kind
     }
 
+    /// Extended DNS Errors, RFC 8914, returned by the server with the failed response
+    ///
+    /// This is empty for errors that were not caused by a response, i.e. everything other than `NoRecordsFound`
+    pub fn extended_errors(&self) -> &[ExtendedError] {
+        match self.kind {
+            ResolveErrorKind::NoRecordsFound {
+                ref extended_errors,
+                ..
+            } => extended_errors,
+            _ => &[],
+        }
+    }
+
+    /// The SOA record from the authority section of a negative response, `NXDOMAIN` or no records
+    ///
+    /// This is `None` for all errors other than `NoRecordsFound`, and for negative responses without an SOA
+    pub fn soa(&self) -> Option<&SOA> {
+        match self.kind {
+            ResolveErrorKind::NoRecordsFound { ref soa, .. } => soa.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// The TTL for caching a negative response, the lesser of the TTL and minimum of its SOA, RFC 2308
+    ///
+    /// This is `None` for all errors other than `NoRecordsFound`, and for negative responses which may not be cached,
+    ///  e.g. without an SOA or those which could not be validated with DNSSEC.
+    pub fn negative_ttl(&self) -> Option<u32> {
+        match self.kind {
+            ResolveErrorKind::NoRecordsFound { negative_ttl, .. } => negative_ttl,
+            _ => None,
+        }
+    }
+
+    /// Collects the Extended DNS Errors from the EDNS options of the response
+    pub(crate) fn extended_errors_from(response: &Message) -> Vec<ExtendedError> {
+        response
+            .extensions()
+            .as_ref()
+            .map(|edns| edns.options().extended_errors().cloned().collect())
+            .unwrap_or_default()
+    }
+
     pub(crate) fn no_connections() -> Self {
         Self {
             kind: ResolveErrorKind::NoConnections,
@@ -156,6 +223,7 @@ impl ResolveError {
             | response_code @ ResponseCode::NXRRSet
             | response_code @ ResponseCode::NotAuth
             | response_code @ ResponseCode::NotZone
+            | response_code @ ResponseCode::DSOTYPENI
             | response_code @ ResponseCode::BADVERS
             | response_code @ ResponseCode::BADSIG
             | response_code @ ResponseCode::BADKEY
@@ -167,6 +235,7 @@ impl ResolveError {
             | response_code @ ResponseCode::BADCOOKIE => {
                 let mut response = response;
                 let soa = response.soa();
+                let extended_errors = Self::extended_errors_from(&response);
                 let query = response.take_queries().drain(..).next().unwrap_or_default();
                 let error_kind = ResolveErrorKind::NoRecordsFound {
                     query: Box::new(query),
@@ -174,6 +243,7 @@ impl ResolveError {
                     negative_ttl: None,
                     response_code,
                     trusted: false,
+                    extended_errors,
                 };
 
                 Err(Self::from(error_kind))
@@ -190,6 +260,7 @@ impl ResolveError {
                 let soa = response.soa();
                 let negative_ttl = response.negative_ttl();
                 let trusted = if response_code == ResponseCode::NoError { false } else { trust_nx };
+                let extended_errors = Self::extended_errors_from(&response);
                 let query = response.take_queries().drain(..).next().unwrap_or_default();
                 let error_kind = ResolveErrorKind::NoRecordsFound {
                     query: Box::new(query),
@@ -197,6 +268,7 @@ impl ResolveError {
                     negative_ttl,
                     response_code,
                     trusted,
+                    extended_errors,
                 };
 
                 Err(Self::from(error_kind))
@@ -244,8 +316,49 @@ impl ResolveError {
             _ => (),
         }
 
+        match (kind, other) {
+            (ResolveErrorKind::DeadlineExceeded(_), ResolveErrorKind::DeadlineExceeded(_)) => {
+                return Ordering::Equal
+            }
+            (ResolveErrorKind::DeadlineExceeded(_), _) => return Ordering::Greater,
+            (_, ResolveErrorKind::DeadlineExceeded(_)) => return Ordering::Less,
+            _ => (),
+        }
+
         Ordering::Equal
     }
+
+    /// Selects the error to report once every name of a search list has failed.
+    ///
+    /// `self` is the error of a name which was tried earlier in the search list than the name which
+    ///  produced `other`. The error which carries the most information about the failed names is kept;
+    ///  a name which exists without the requested records (`NoError`) is preferred over one that does
+    ///  not exist at all (`NXDomain`), as the stub resolvers in libc do. On a tie the earlier name wins.
+    pub(crate) fn most_specific_for_search(self, other: Self) -> Self {
+        let prefer_other = match self.cmp_specificity(&other) {
+            Ordering::Less => true,
+            Ordering::Equal => matches!(
+                (self.kind(), other.kind()),
+                (
+                    ResolveErrorKind::NoRecordsFound {
+                        response_code: ResponseCode::NXDomain,
+                        ..
+                    },
+                    ResolveErrorKind::NoRecordsFound {
+                        response_code: ResponseCode::NoError,
+                        ..
+                    },
+                )
+            ),
+            Ordering::Greater => false,
+        };
+
+        if prefer_other {
+            other
+        } else {
+            self
+        }
+    }
 }
 
 impl RetryableError for ResolveError {
@@ -254,7 +367,10 @@ impl RetryableError for ResolveError {
             ResolveErrorKind::Message(_)
             | ResolveErrorKind::Msg(_)
             | ResolveErrorKind::NoConnections
-            | ResolveErrorKind::NoRecordsFound { .. } => false,
+            | ResolveErrorKind::NoService(_)
+            | ResolveErrorKind::CnameLoop(_)
+            | ResolveErrorKind::NoRecordsFound { .. }
+            | ResolveErrorKind::DeadlineExceeded(_) => false,
             ResolveErrorKind::Io(_) | ResolveErrorKind::Proto(_) | ResolveErrorKind::Timeout => {
                 true
             }
@@ -338,7 +454,9 @@ impl From<ProtoError> for ResolveError {
 impl From<ResolveError> for io::Error {
     fn from(e: ResolveError) -> Self {
         match e.kind() {
-            ResolveErrorKind::Timeout => Self::new(io::ErrorKind::TimedOut, e),
+            ResolveErrorKind::Timeout | ResolveErrorKind::DeadlineExceeded(_) => {
+                Self::new(io::ErrorKind::TimedOut, e)
+            }
             _ => Self::new(io::ErrorKind::Other, e),
         }
     }
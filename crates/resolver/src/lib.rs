@@ -257,9 +257,11 @@ extern crate log;
 extern crate serde;
 pub extern crate trust_dns_proto as proto;
 
+mod address_selection;
 mod async_resolver;
 pub mod caching_client;
 pub mod config;
+mod dns64;
 mod dns_lru;
 pub mod dns_sd;
 pub mod error;
@@ -288,8 +290,9 @@ pub use async_resolver::AsyncResolver;
 #[cfg(feature = "tokio-runtime")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
 pub use async_resolver::TokioAsyncResolver;
+pub use dns_lru::{CacheEvent, CacheStats};
 pub use hosts::Hosts;
-pub use name_server::ConnectionProvider;
+pub use name_server::{ConnectionProvider, ResponseObserver};
 #[cfg(feature = "tokio-runtime")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
 pub use name_server::{TokioConnection, TokioConnectionProvider, TokioHandle};
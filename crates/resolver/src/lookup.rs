@@ -9,7 +9,7 @@
 
 use std::cmp::min;
 use std::error::Error;
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::pin::Pin;
 use std::slice::Iter;
 use std::sync::Arc;
@@ -18,12 +18,16 @@ use std::time::{Duration, Instant};
 
 use futures_util::stream::Stream;
 use futures_util::{future, future::Future, FutureExt};
+use rand::Rng;
 
 use proto::error::ProtoError;
 use proto::op::Query;
+#[cfg(feature = "dnssec")]
+use proto::rr::dnssec::rdata::{DNSSECRData, SIG};
 use proto::rr::rdata;
-use proto::rr::{Name, RData, Record, RecordType};
-use proto::xfer::{DnsRequest, DnsRequestOptions, DnsResponse};
+use proto::rr::rdata::opt::ClientSubnet;
+use proto::rr::{DNSClass, Name, RData, Record, RecordData, RecordType};
+use proto::xfer::{DnsRequest, DnsRequestOptions, DnsResponse, DnssecStatus};
 #[cfg(feature = "dnssec")]
 use proto::DnssecDnsHandle;
 use proto::{DnsHandle, RetryDnsHandle};
@@ -31,7 +35,7 @@ use proto::{DnsHandle, RetryDnsHandle};
 use crate::caching_client::CachingClient;
 use crate::dns_lru::MAX_TTL;
 use crate::error::*;
-use crate::lookup_ip::LookupIpIter;
+use crate::lookup_ip::{LookupIp, LookupIpIter};
 use crate::name_server::{ConnectionProvider, NameServerPool};
 
 /// Result of a DNS query when querying for any record type supported by the Trust-DNS Proto library.
@@ -42,6 +46,11 @@ pub struct Lookup {
     query: Query,
     records: Arc<[Record]>,
     valid_until: Instant,
+    dnssec_status: DnssecStatus,
+    server_addr: Option<SocketAddr>,
+    authentic_data: bool,
+    checking_disabled: bool,
+    client_subnet: Option<ClientSubnet>,
 }
 
 impl Lookup {
@@ -58,6 +67,11 @@ impl Lookup {
             query,
             records,
             valid_until,
+            dnssec_status: DnssecStatus::default(),
+            server_addr: None,
+            authentic_data: false,
+            checking_disabled: false,
+            client_subnet: None,
         }
     }
 
@@ -67,6 +81,11 @@ impl Lookup {
             query,
             records,
             valid_until,
+            dnssec_status: DnssecStatus::default(),
+            server_addr: None,
+            authentic_data: false,
+            checking_disabled: false,
+            client_subnet: None,
         }
     }
 
@@ -85,11 +104,104 @@ impl Lookup {
         LookupRecordIter(self.records.iter())
     }
 
+    /// Returns a borrowed iterator of the data of the records of the queried type, as the type of that data
+    ///
+    /// This is the typed `iter` for any record type, e.g. `lookup.rdata_iter::<SSHFP>()` of an SSHFP lookup. The
+    ///  records of other types, e.g. the CNAMEs leading to the answer, are skipped, unless the query was for ANY.
+    pub fn rdata_iter<'a, T: RecordData + 'a>(&'a self) -> impl Iterator<Item = &'a T> + 'a {
+        let query_type = self.query.query_type();
+        self.records
+            .iter()
+            .filter(move |record| query_type.is_any() || record.rr_type() == query_type)
+            .filter_map(Record::data)
+            .filter_map(T::try_borrow)
+    }
+
     /// Returns the `Instant` at which this `Lookup` is no longer valid.
     pub fn valid_until(&self) -> Instant {
         self.valid_until
     }
 
+    /// Returns the outcome of DNSSEC validation of the records in this `Lookup`.
+    ///
+    /// This is [`DnssecStatus::Indeterminate`] unless the resolver was configured to validate, see
+    ///  `ResolverOpts::validate`. Responses which fail validation are returned as errors, when
+    ///  `ResolverOpts::retain_dnssec_records` is enabled the error carries the failed response.
+    pub fn dnssec_status(&self) -> DnssecStatus {
+        self.dnssec_status
+    }
+
+    /// Returns a borrowed iterator of the RRSIGs retained in this `Lookup`.
+    ///
+    /// This is always empty unless `ResolverOpts::retain_dnssec_records` is enabled.
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn rrsigs(&self) -> impl Iterator<Item = &SIG> {
+        self.records
+            .iter()
+            .filter(|record| record.rr_type() == RecordType::RRSIG)
+            .filter_map(|record| match record.data() {
+                Some(RData::DNSSEC(DNSSECRData::SIG(ref rrsig))) => Some(rrsig),
+                _ => None,
+            })
+    }
+
+    /// Returns the address of the name server which answered, `None` if the answer came from the cache or hosts
+    pub fn server_addr(&self) -> Option<SocketAddr> {
+        self.server_addr
+    }
+
+    /// Returns true if the Authentic Data (AD) bit was set in the response, `false` if the answer came from the cache or hosts
+    ///
+    /// The name server claims to have validated the data, this is only meaningful if the path to it is trusted.
+    pub fn authentic_data(&self) -> bool {
+        self.authentic_data
+    }
+
+    /// Returns true if the Checking Disabled (CD) bit was set in the response, `false` if the answer came from the cache or hosts
+    ///
+    /// See `DnsRequestOptions::checking_disabled`, such data may not have been validated.
+    pub fn checking_disabled(&self) -> bool {
+        self.checking_disabled
+    }
+
+    /// Returns the EDNS Client Subnet option of the response, `None` if the answer came from the cache or hosts
+    ///
+    /// The scope prefix is the number of bits of the client's address the answer is valid for, see
+    ///  [RFC 7871](https://tools.ietf.org/html/rfc7871#section-7.2.1) and `ResolverOpts::client_subnet`.
+    pub fn client_subnet(&self) -> Option<ClientSubnet> {
+        self.client_subnet
+    }
+
+    /// Returns this `Lookup` with the given DNSSEC status
+    pub(crate) fn with_dnssec_status(mut self, dnssec_status: DnssecStatus) -> Self {
+        self.dnssec_status = dnssec_status;
+        self
+    }
+
+    /// Returns this `Lookup` with the address of the name server which answered
+    pub(crate) fn with_server_addr(mut self, server_addr: Option<SocketAddr>) -> Self {
+        self.server_addr = server_addr;
+        self
+    }
+
+    /// Returns this `Lookup` with the AD and CD bits of the response which answered
+    pub(crate) fn with_response_flags(
+        mut self,
+        authentic_data: bool,
+        checking_disabled: bool,
+    ) -> Self {
+        self.authentic_data = authentic_data;
+        self.checking_disabled = checking_disabled;
+        self
+    }
+
+    /// Returns this `Lookup` with the EDNS Client Subnet option of the response which answered
+    pub(crate) fn with_client_subnet(mut self, client_subnet: Option<ClientSubnet>) -> Self {
+        self.client_subnet = client_subnet;
+        self
+    }
+
     #[doc(hidden)]
     pub fn is_empty(&self) -> bool {
         self.records.is_empty()
@@ -112,7 +224,17 @@ impl Lookup {
 
         // Choose the sooner deadline of the two lookups.
         let valid_until = min(self.valid_until(), other.valid_until());
+        // and the least secure of the two statuses
+        let dnssec_status = min(self.dnssec_status(), other.dnssec_status());
         Self::new_with_deadline(self.query.clone(), Arc::from(records), valid_until)
+            .with_dnssec_status(dnssec_status)
+            .with_server_addr(self.server_addr.or(other.server_addr))
+            .with_client_subnet(self.client_subnet.or(other.client_subnet))
+            // only authentic if both are, and unchecked if either is
+            .with_response_flags(
+                self.authentic_data && other.authentic_data,
+                self.checking_disabled || other.checking_disabled,
+            )
     }
 }
 
@@ -217,9 +339,11 @@ where
 {
     client_cache: CachingClient<C, E>,
     names: Vec<Name>,
+    query_class: DNSClass,
     record_type: RecordType,
     options: DnsRequestOptions,
     query: Pin<Box<dyn Future<Output = Result<Lookup, ResolveError>> + Send>>,
+    error: Option<ResolveError>,
 }
 
 impl<C, E> LookupFuture<C, E>
@@ -236,7 +360,26 @@ where
     /// * `client_cache` - cache with a connection to use for performing all lookups
     #[doc(hidden)]
     pub fn lookup(
+        names: Vec<Name>,
+        record_type: RecordType,
+        options: DnsRequestOptions,
+        client_cache: CachingClient<C, E>,
+    ) -> Self {
+        Self::lookup_with_class(names, DNSClass::IN, record_type, options, client_cache)
+    }
+
+    /// Perform a lookup from a name, class and type to a set of RDatas
+    ///
+    /// # Arguments
+    ///
+    /// * `names` - a set of DNS names to attempt to resolve, they will be attempted in queue order, i.e. the first is `names.pop()`. Upon each failure, the next will be attempted.
+    /// * `query_class` - class of the records being sought
+    /// * `record_type` - type of record being sought
+    /// * `client_cache` - cache with a connection to use for performing all lookups
+    #[doc(hidden)]
+    pub fn lookup_with_class(
         mut names: Vec<Name>,
+        query_class: DNSClass,
         record_type: RecordType,
         options: DnsRequestOptions,
         mut client_cache: CachingClient<C, E>,
@@ -247,7 +390,7 @@ where
 
         let query: Pin<Box<dyn Future<Output = Result<Lookup, ResolveError>> + Send>> = match name {
             Ok(name) => client_cache
-                .lookup(Query::query(name, record_type), options)
+                .lookup(class_query(name, query_class, record_type), options)
                 .boxed(),
             Err(err) => future::err(err).boxed(),
         };
@@ -255,9 +398,11 @@ where
         Self {
             client_cache,
             names,
+            query_class,
             record_type,
             options,
             query,
+            error: None,
         }
     }
 }
@@ -271,48 +416,58 @@ where
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         loop {
-            // Try polling the underlying DNS query.
-            let query = self.query.as_mut().poll_unpin(cx);
-
-            // Determine whether or not we will attempt to retry the query.
-            let should_retry = match query {
+            // Try polling the underlying DNS query, and determine whether or
+            // not we will attempt to retry the query with the next name.
+            let query = match self.query.as_mut().poll_unpin(cx) {
                 // If the query is NotReady, yield immediately.
                 Poll::Pending => return Poll::Pending,
-                // If the query returned a successful lookup, we will attempt
-                // to retry if the lookup is empty. Otherwise, we will return
-                // that lookup.
-                Poll::Ready(Ok(ref lookup)) => lookup.records.len() == 0,
-                // If the query failed, we will attempt to retry.
-                Poll::Ready(Err(_)) => true,
+                // If the query returned a non-empty lookup, return it.
+                Poll::Ready(Ok(lookup)) if !lookup.records.is_empty() => {
+                    return Poll::Ready(Ok(lookup))
+                }
+                // If the lookup was empty, we will attempt to retry.
+                Poll::Ready(Ok(lookup)) => Ok(lookup),
+                // If the query failed, we will attempt to retry, remembering
+                // the most specific error across all of the names tried.
+                Poll::Ready(Err(err)) => Err(match self.error.take() {
+                    Some(error) => error.most_specific_for_search(err),
+                    None => err,
+                }),
             };
 
-            if should_retry {
-                if let Some(name) = self.names.pop() {
-                    let record_type = self.record_type;
-                    let options = self.options;
-
-                    // If there's another name left to try, build a new query
-                    // for that next name and continue looping.
-                    self.query = self
-                        .client_cache
-                        .lookup(Query::query(name, record_type), options);
-                    // Continue looping with the new query. It will be polled
-                    // on the next iteration of the loop.
-                    continue;
+            if let Some(name) = self.names.pop() {
+                let query_class = self.query_class;
+                let record_type = self.record_type;
+                let options = self.options;
+
+                if let Err(err) = query {
+                    debug!("{}, trying the next search name: {}", err, name);
+                    self.error = Some(err);
                 }
+
+                // If there's another name left to try, build a new query
+                // for that next name and continue looping.
+                self.query = self
+                    .client_cache
+                    .lookup(class_query(name, query_class, record_type), options);
+                // Continue looping with the new query. It will be polled
+                // on the next iteration of the loop.
+                continue;
             }
-            // If we didn't have to retry the query, or we weren't able to
-            // retry because we've exhausted the names to search, return the
-            // current query.
-            return query;
-            // If we skipped retrying the  query, this will return the
-            // successful lookup, otherwise, if the retry failed, this will
-            // return the last  query result --- either an empty lookup or the
-            // last error we saw.
+
+            // We've exhausted the names to search, return either the last
+            // empty lookup or the most specific error of all the names.
+            return Poll::Ready(query);
         }
     }
 }
 
+fn class_query(name: Name, query_class: DNSClass, record_type: RecordType) -> Query {
+    let mut query = Query::query(name, record_type);
+    query.set_query_class(query_class);
+    query
+}
+
 /// The result of an SRV lookup
 #[derive(Debug, Clone)]
 pub struct SrvLookup(Lookup);
@@ -392,6 +547,333 @@ impl Iterator for SrvLookupIntoIter {
     }
 }
 
+/// The maximum number of AliasMode records which are followed for an HTTPS lookup
+const MAX_SVCB_ALIASES: usize = 8;
+
+/// The result of an HTTPS lookup
+///
+/// The ServiceMode records are ordered by their SvcPriority, the most preferred first. If the records
+///  of the name are in AliasMode, no ServiceMode records are returned,
+///  [RFC 9460](https://tools.ietf.org/html/rfc9460#section-2.4.2).
+#[derive(Debug, Clone)]
+pub struct SvcbLookup(Lookup);
+
+impl SvcbLookup {
+    /// Returns an iterator over the ServiceMode SVCB RData, in order of priority
+    pub fn iter(&self) -> SvcbLookupIter<'_> {
+        SvcbLookupIter(self.0.iter())
+    }
+
+    /// Returns a reference to the Query that was used to produce this result.
+    pub fn query(&self) -> &Query {
+        self.0.query()
+    }
+
+    /// Returns the `Instant` at which this result is no longer valid.
+    pub fn valid_until(&self) -> Instant {
+        self.0.valid_until()
+    }
+
+    /// Returns the target names of the ServiceMode records, in order of priority
+    ///
+    /// A TargetName of `.` is the owner name of the record.
+    pub fn target_iter(&self) -> impl Iterator<Item = &Name> {
+        self.0
+            .records
+            .iter()
+            .filter_map(|record| match record.data() {
+                Some(RData::HTTPS(svcb)) | Some(RData::SVCB(svcb)) if svcb.svc_priority() != 0 => {
+                    if svcb.target_name().is_root() {
+                        Some(record.name())
+                    } else {
+                        Some(svcb.target_name())
+                    }
+                }
+                _ => None,
+            })
+    }
+
+    /// Returns the addresses of the ipv4hint and ipv6hint parameters, in order of priority
+    ///
+    /// *Note*: hints may be stale, the addresses of the target name take precedence once resolved.
+    pub fn ip_iter(&self) -> impl Iterator<Item = IpAddr> + '_ {
+        self.iter().flat_map(|svcb| {
+            svcb.ipv4hint()
+                .iter()
+                .map(|ip| IpAddr::V4(*ip))
+                .chain(svcb.ipv6hint().iter().map(|ip| IpAddr::V6(*ip)))
+        })
+    }
+
+    /// Return a reference to the inner lookup
+    ///
+    /// This can be useful for getting all records from the request
+    pub fn as_lookup(&self) -> &Lookup {
+        &self.0
+    }
+
+    /// Returns the target of the AliasMode record of the lookup, if any
+    fn alias(lookup: &Lookup) -> Option<&Name> {
+        lookup.iter().find_map(|rdata| match rdata {
+            RData::HTTPS(svcb) | RData::SVCB(svcb) if svcb.svc_priority() == 0 => {
+                Some(svcb.target_name())
+            }
+            _ => None,
+        })
+    }
+}
+
+impl From<Lookup> for SvcbLookup {
+    fn from(lookup: Lookup) -> Self {
+        let priority = |record: &Record| match record.data() {
+            Some(RData::HTTPS(svcb)) | Some(RData::SVCB(svcb)) => Some(svcb.svc_priority()),
+            _ => None,
+        };
+
+        // ServiceMode records are ignored in the presence of an AliasMode record
+        let is_alias = Self::alias(&lookup).is_some();
+        let mut records = lookup
+            .records
+            .iter()
+            .filter(|record| !is_alias || priority(record).unwrap_or(0) == 0)
+            .cloned()
+            .collect::<Vec<_>>();
+        records.sort_by_key(priority);
+
+        Self(Lookup {
+            records: Arc::from(records),
+            ..lookup
+        })
+    }
+}
+
+/// An iterator over the ServiceMode records of the Lookup
+pub struct SvcbLookupIter<'i>(LookupIter<'i>);
+
+impl<'i> Iterator for SvcbLookupIter<'i> {
+    type Item = &'i rdata::SVCB;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let iter: &mut _ = &mut self.0;
+        iter.filter_map(|rdata| match *rdata {
+            RData::HTTPS(ref data) | RData::SVCB(ref data) if data.svc_priority() != 0 => {
+                Some(data)
+            }
+            _ => None,
+        })
+        .next()
+    }
+}
+
+/// Looks up the HTTPS records of the name, following AliasMode records to their target
+///
+/// `lookup` performs the HTTPS query for a name. The number of aliases followed is limited, as is
+///  following an alias back to a name of the chain.
+pub(crate) async fn svcb_lookup<F, L>(name: Name, mut lookup: F) -> Result<SvcbLookup, ResolveError>
+where
+    F: FnMut(Name) -> L,
+    L: Future<Output = Result<Lookup, ResolveError>>,
+{
+    let mut names = vec![name.clone()];
+    let mut name = name;
+
+    loop {
+        let result = lookup(name).await?;
+        let target = match SvcbLookup::alias(&result) {
+            // a target of `.` means the service is not available, there are no ServiceMode records
+            Some(target) if !target.is_root() => target.clone(),
+            _ => return Ok(SvcbLookup::from(result)),
+        };
+
+        if names.contains(&target) {
+            return Err(ResolveError::from(format!(
+                "HTTPS alias loop at {}",
+                target
+            )));
+        }
+        if names.len() > MAX_SVCB_ALIASES {
+            return Err(ResolveError::from("too many HTTPS aliases"));
+        }
+
+        debug!("following HTTPS alias to {}", target);
+        names.push(target.clone());
+        name = target;
+    }
+}
+
+/// An address of a target of a service, see [`ServiceLookup`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceTarget {
+    name: Name,
+    addr: SocketAddr,
+    priority: u16,
+    weight: u16,
+}
+
+impl ServiceTarget {
+    /// The target name of the SRV record
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// An address of the target, with the port of the SRV record
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The priority of the SRV record, lower values are preferred
+    pub fn priority(&self) -> u16 {
+        self.priority
+    }
+
+    /// The weight of the SRV record, relative to the others of the same priority
+    pub fn weight(&self) -> u16 {
+        self.weight
+    }
+}
+
+/// The result of a service lookup, the addresses of the targets of the SRV records
+///
+/// The targets are in the order in which they should be tried, by priority and within a priority in
+///  the weighted random order of [RFC 2782](https://tools.ietf.org/html/rfc2782). All addresses of a
+///  target are listed together.
+#[derive(Debug, Clone)]
+pub struct ServiceLookup {
+    query: Query,
+    targets: Vec<ServiceTarget>,
+    valid_until: Instant,
+}
+
+impl ServiceLookup {
+    /// Returns an iterator over the addresses of the targets, in the order in which they should be tried
+    pub fn iter(&self) -> std::slice::Iter<'_, ServiceTarget> {
+        self.targets.iter()
+    }
+
+    /// Returns a reference to the SRV Query that was used to produce this result.
+    pub fn query(&self) -> &Query {
+        &self.query
+    }
+
+    /// Returns the `Instant` at which this result is no longer valid.
+    pub fn valid_until(&self) -> Instant {
+        self.valid_until
+    }
+}
+
+impl IntoIterator for ServiceLookup {
+    type Item = ServiceTarget;
+    type IntoIter = std::vec::IntoIter<ServiceTarget>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.targets.into_iter()
+    }
+}
+
+/// Orders the SRV records in which their targets should be tried, [RFC 2782](https://tools.ietf.org/html/rfc2782)
+///
+/// Records are ordered by priority. Within a priority, the records with a weight of 0 are placed first and
+///  then records are selected one by one: `random(sum)` picks a number in `0..=sum`, the sum of the weights
+///  of the remaining records, and the first record whose running sum of weights reaches it is next.
+fn order_srvs<R>(mut srvs: Vec<rdata::SRV>, mut random: R) -> Vec<rdata::SRV>
+where
+    R: FnMut(u32) -> u32,
+{
+    srvs.sort_by_key(|srv| (srv.priority(), srv.weight() != 0));
+
+    let mut ordered = Vec::with_capacity(srvs.len());
+    while let Some(priority) = srvs.first().map(rdata::SRV::priority) {
+        let end = srvs
+            .iter()
+            .position(|srv| srv.priority() != priority)
+            .unwrap_or(srvs.len());
+        let mut tier = srvs.drain(..end).collect::<Vec<_>>();
+
+        while !tier.is_empty() {
+            let sum = tier.iter().map(|srv| u32::from(srv.weight())).sum();
+            let selected = random(sum);
+
+            let mut running_sum = 0;
+            let next = tier
+                .iter()
+                .position(|srv| {
+                    running_sum += u32::from(srv.weight());
+                    running_sum >= selected
+                })
+                .unwrap_or(0);
+            ordered.push(tier.remove(next));
+        }
+    }
+
+    ordered
+}
+
+/// Resolves the targets of the SRV records of a service to their addresses
+///
+/// The addresses in the additional records of the SRV lookup are used when present, otherwise
+///  `lookup_ip` resolves the target. Targets which fail to resolve are skipped.
+pub(crate) async fn service_lookup<F, L>(
+    srv: SrvLookup,
+    lookup_ip: F,
+) -> Result<ServiceLookup, ResolveError>
+where
+    F: Fn(Name) -> L,
+    L: Future<Output = Result<LookupIp, ResolveError>>,
+{
+    let srvs = srv.iter().cloned().collect::<Vec<_>>();
+    if let [only] = srvs.as_slice() {
+        if only.target().is_root() {
+            let name = srv.query().name().clone();
+            return Err(ResolveErrorKind::NoService(Box::new(name)).into());
+        }
+    }
+
+    let srvs = order_srvs(srvs, |sum| rand::thread_rng().gen_range(0..=sum));
+    let mut targets = Vec::new();
+    let mut valid_until = srv.as_lookup().valid_until();
+    let mut last_err = None;
+
+    for record in srvs.iter().filter(|record| !record.target().is_root()) {
+        let target = record.target();
+        let mut ips = srv
+            .as_lookup()
+            .record_iter()
+            .filter(|r| r.name() == target)
+            .filter_map(|r| r.data().and_then(RData::to_ip_addr))
+            .collect::<Vec<_>>();
+
+        if ips.is_empty() {
+            match lookup_ip(target.clone()).await {
+                Ok(lookup) => {
+                    valid_until = min(valid_until, lookup.valid_until());
+                    ips.extend(lookup.iter());
+                }
+                Err(e) => {
+                    debug!("skipping SRV target {}: {}", target, e);
+                    last_err = Some(e);
+                    continue;
+                }
+            }
+        }
+
+        targets.extend(ips.into_iter().map(|ip| ServiceTarget {
+            name: target.clone(),
+            addr: SocketAddr::new(ip, record.port()),
+            priority: record.priority(),
+            weight: record.weight(),
+        }));
+    }
+
+    match last_err {
+        Some(e) if targets.is_empty() => Err(e),
+        _ => Ok(ServiceLookup {
+            query: srv.query().clone(),
+            targets,
+            valid_until,
+        }),
+    }
+}
+
 /// Creates a Lookup result type from the specified components
 macro_rules! lookup_type {
     ($l:ident, $i:ident, $ii:ident, $r:path, $t:path) => {
@@ -525,6 +1007,13 @@ lookup_type!(
     rdata::SOA
 );
 lookup_type!(NsLookup, NsLookupIter, NsLookupIntoIter, RData::NS, Name);
+lookup_type!(
+    UriLookup,
+    UriLookupIter,
+    UriLookupIntoIter,
+    RData::URI,
+    rdata::URI
+);
 
 #[cfg(test)]
 pub mod tests {
@@ -536,7 +1025,7 @@ pub mod tests {
     use futures_util::future;
     use futures_util::stream::once;
 
-    use proto::op::{Message, Query};
+    use proto::op::{Message, Query, ResponseCode};
     use proto::rr::{Name, RData, Record, RecordType};
     use proto::xfer::{DnsRequest, DnsRequestOptions};
 
@@ -577,6 +1066,13 @@ pub mod tests {
         Ok(Message::new().into())
     }
 
+    pub fn negative(name: &str, response_code: ResponseCode) -> Result<DnsResponse, ResolveError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(Name::from_str(name).unwrap(), RecordType::A));
+        message.set_response_code(response_code);
+        Ok(message.into())
+    }
+
     pub fn error() -> Result<DnsResponse, ResolveError> {
         Err(ResolveError::from(ProtoError::from(std::io::Error::from(
             std::io::ErrorKind::Other,
@@ -623,6 +1119,105 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_lookup_rdata_iter() {
+        use proto::rr::rdata::sshfp::{Algorithm, FingerprintType, SSHFP};
+
+        let name = Name::from_str("www.example.com.").unwrap();
+        let host = Name::from_str("host.example.com.").unwrap();
+        let sshfp = |algorithm| SSHFP::new(algorithm, FingerprintType::SHA256, vec![0xAB; 32]);
+
+        let cname = Record::from_rdata(name.clone(), 86400, RData::CNAME(host.clone()));
+        let records = vec![
+            Record::from_rdata(host.clone(), 86400, RData::SSHFP(sshfp(Algorithm::Ed25519))),
+            Record::from_rdata(host.clone(), 86400, RData::SSHFP(sshfp(Algorithm::ECDSA))),
+        ];
+
+        let mut message = Message::new();
+        message.add_query(Query::query(name.clone(), RecordType::SSHFP));
+        message.insert_answers(
+            std::iter::once(cname.clone())
+                .chain(records.clone())
+                .collect(),
+        );
+
+        let lookup = block_on(LookupFuture::lookup(
+            vec![name.clone()],
+            RecordType::SSHFP,
+            DnsRequestOptions::default(),
+            CachingClient::new(0, mock(vec![Ok(message.into())]), false),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            lookup.rdata_iter::<SSHFP>().collect::<Vec<_>>(),
+            vec![&sshfp(Algorithm::Ed25519), &sshfp(Algorithm::ECDSA)]
+        );
+        assert_eq!(lookup.rdata_iter::<rdata::TXT>().count(), 0);
+
+        // only the data of the queried type, not of the CNAME leading to it
+        let lookup = Lookup::new_with_max_ttl(
+            Query::query(name, RecordType::SSHFP),
+            std::iter::once(cname).chain(records).collect(),
+        );
+        assert_eq!(lookup.iter().count(), 3);
+        assert_eq!(lookup.rdata_iter::<SSHFP>().count(), 2);
+        assert_eq!(lookup.rdata_iter::<Name>().count(), 0);
+
+        // the data of unknown record types is NULL
+        let unknown = rdata::NULL::with(vec![0xAB; 4]);
+        let lookup = Lookup::new_with_max_ttl(
+            Query::query(host.clone(), RecordType::Unknown(65280)),
+            Arc::from([Record::from_rdata(
+                host,
+                86400,
+                RData::Unknown {
+                    code: 65280,
+                    rdata: unknown.clone(),
+                },
+            )]),
+        );
+        assert_eq!(
+            lookup.rdata_iter::<rdata::NULL>().collect::<Vec<_>>(),
+            vec![&unknown]
+        );
+    }
+
+    #[test]
+    fn test_lookup_with_class() {
+        let name = Name::from_str("version.bind.").unwrap();
+        let version = RData::TXT(rdata::TXT::new(vec!["trust-dns".to_string()]));
+
+        let mut query = Query::query(name.clone(), RecordType::TXT);
+        query.set_query_class(DNSClass::CH);
+        let mut record = Record::from_rdata(name.clone(), 0, version.clone());
+        record.set_dns_class(DNSClass::CH);
+
+        let mut message = Message::new();
+        message.add_query(query.clone());
+        message.insert_answers(vec![
+            record,
+            // records of other classes are not returned
+            Record::from_rdata(
+                name.clone(),
+                0,
+                RData::TXT(rdata::TXT::new(vec!["other".to_string()])),
+            ),
+        ]);
+
+        let lookup = block_on(LookupFuture::lookup_with_class(
+            vec![name],
+            DNSClass::CH,
+            RecordType::TXT,
+            DnsRequestOptions::default(),
+            CachingClient::new(0, mock(vec![Ok(message.into())]), false),
+        ))
+        .unwrap();
+
+        assert_eq!(*lookup.query(), query);
+        assert_eq!(lookup.iter().cloned().collect::<Vec<_>>(), vec![version]);
+    }
+
     #[test]
     fn test_error() {
         assert!(block_on(LookupFuture::lookup(
@@ -656,6 +1251,106 @@ pub mod tests {
         }
     }
 
+    fn search_error(
+        messages: Vec<Result<DnsResponse, ResolveError>>,
+    ) -> (Box<Query>, ResponseCode) {
+        // names are tried in order a, b, then the bare name
+        let names = vec![
+            Name::from_str("www").unwrap(),
+            Name::from_str("www.b.example.com.").unwrap(),
+            Name::from_str("www.a.example.com.").unwrap(),
+        ];
+
+        match block_on(LookupFuture::lookup(
+            names,
+            RecordType::A,
+            DnsRequestOptions::default(),
+            CachingClient::new(0, mock(messages), false),
+        ))
+        .unwrap_err()
+        .kind()
+        {
+            ResolveErrorKind::NoRecordsFound {
+                query,
+                response_code,
+                ..
+            } => (query.clone(), *response_code),
+            kind => panic!("wrong error recieved: {}", kind),
+        }
+    }
+
+    #[test]
+    fn test_search_all_nx_domain() {
+        // the first name of the search list is reported
+        let (query, response_code) = search_error(vec![
+            negative("www.", ResponseCode::NXDomain),
+            negative("www.b.example.com.", ResponseCode::NXDomain),
+            negative("www.a.example.com.", ResponseCode::NXDomain),
+        ]);
+        assert_eq!(
+            *query,
+            Query::query(Name::from_str("www.a.example.com.").unwrap(), RecordType::A)
+        );
+        assert_eq!(response_code, ResponseCode::NXDomain);
+    }
+
+    #[test]
+    fn test_search_prefers_no_data() {
+        // the name which exists, but without the records, is reported
+        let (query, response_code) = search_error(vec![
+            negative("www.", ResponseCode::NXDomain),
+            negative("www.b.example.com.", ResponseCode::NoError),
+            negative("www.a.example.com.", ResponseCode::NXDomain),
+        ]);
+        assert_eq!(
+            *query,
+            Query::query(Name::from_str("www.b.example.com.").unwrap(), RecordType::A)
+        );
+        assert_eq!(response_code, ResponseCode::NoError);
+    }
+
+    #[test]
+    fn test_search_prefers_response_over_error() {
+        // a failure of the last name does not hide the responses to the others
+        let (query, response_code) = search_error(vec![
+            error(),
+            negative("www.b.example.com.", ResponseCode::NXDomain),
+            negative("www.a.example.com.", ResponseCode::NXDomain),
+        ]);
+        assert_eq!(
+            *query,
+            Query::query(Name::from_str("www.a.example.com.").unwrap(), RecordType::A)
+        );
+        assert_eq!(response_code, ResponseCode::NXDomain);
+    }
+
+    #[test]
+    fn test_search_stops_at_first_answer() {
+        let lookup = block_on(LookupFuture::lookup(
+            vec![Name::root(), Name::from_str("www.example.com.").unwrap()],
+            RecordType::A,
+            DnsRequestOptions::default(),
+            CachingClient::new(
+                0,
+                mock(vec![
+                    error(),
+                    v4_message(),
+                    negative("www.example.com.", ResponseCode::NXDomain),
+                ]),
+                false,
+            ),
+        ))
+        .unwrap();
+
+        assert_eq!(
+            lookup
+                .iter()
+                .map(|r| r.to_ip_addr().unwrap())
+                .collect::<Vec<IpAddr>>(),
+            vec![Ipv4Addr::new(127, 0, 0, 1)]
+        );
+    }
+
     #[test]
     fn test_lookup_into_iter_arc() {
         let mut lookup = LookupIntoIter {
@@ -684,4 +1379,279 @@ pub mod tests {
         );
         assert_eq!(lookup.next(), None);
     }
+
+    fn https(name: &str, svcbs: Vec<rdata::SVCB>) -> Lookup {
+        let name = Name::from_str(name).unwrap();
+        let records = svcbs
+            .into_iter()
+            .map(|svcb| Record::from_rdata(name.clone(), 300, RData::HTTPS(svcb)))
+            .collect::<Vec<_>>();
+
+        Lookup::new_with_max_ttl(Query::query(name, RecordType::HTTPS), Arc::from(records))
+    }
+
+    fn service(priority: u16, target: &str, ipv4hint: Ipv4Addr) -> rdata::SVCB {
+        use proto::rr::rdata::svcb::{IpHint, SvcParamKey, SvcParamValue};
+
+        rdata::SVCB::new(
+            priority,
+            Name::from_str(target).unwrap(),
+            vec![(
+                SvcParamKey::Ipv4Hint,
+                SvcParamValue::Ipv4Hint(IpHint(vec![ipv4hint])),
+            )],
+        )
+    }
+
+    fn alias(target: &str) -> rdata::SVCB {
+        rdata::SVCB::new(0, Name::from_str(target).unwrap(), vec![])
+    }
+
+    #[test]
+    fn test_svcb_lookup_priority() {
+        let lookup = SvcbLookup::from(https(
+            "www.example.com.",
+            vec![
+                service(2, "svc2.example.net.", Ipv4Addr::new(127, 0, 0, 2)),
+                service(1, ".", Ipv4Addr::new(127, 0, 0, 1)),
+                service(3, "svc3.example.net.", Ipv4Addr::new(127, 0, 0, 3)),
+            ],
+        ));
+
+        assert_eq!(
+            lookup
+                .iter()
+                .map(rdata::SVCB::svc_priority)
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+        // the owner name is the target of `.`
+        assert_eq!(
+            lookup
+                .target_iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["www.example.com.", "svc2.example.net.", "svc3.example.net."]
+        );
+        assert_eq!(
+            lookup.ip_iter().collect::<Vec<_>>(),
+            vec![
+                IpAddr::from(Ipv4Addr::new(127, 0, 0, 1)),
+                IpAddr::from(Ipv4Addr::new(127, 0, 0, 2)),
+                IpAddr::from(Ipv4Addr::new(127, 0, 0, 3)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_svcb_lookup_ignores_service_mode_with_alias() {
+        let lookup = SvcbLookup::from(https(
+            "www.example.com.",
+            vec![
+                service(1, ".", Ipv4Addr::new(127, 0, 0, 1)),
+                alias("svc.example.net."),
+            ],
+        ));
+
+        assert_eq!(lookup.iter().count(), 0);
+        assert_eq!(lookup.ip_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_svcb_lookup_follows_aliases() {
+        let lookup = block_on(svcb_lookup(
+            Name::from_str("www.example.com.").unwrap(),
+            |name| {
+                future::ok(match name.to_string().as_str() {
+                    "www.example.com." => {
+                        https("www.example.com.", vec![alias("cdn.example.net.")])
+                    }
+                    "cdn.example.net." => https(
+                        "cdn.example.net.",
+                        vec![service(1, ".", Ipv4Addr::new(127, 0, 0, 1))],
+                    ),
+                    _ => unreachable!(),
+                })
+            },
+        ))
+        .unwrap();
+
+        assert_eq!(
+            lookup.query().name(),
+            &Name::from_str("cdn.example.net.").unwrap()
+        );
+        assert_eq!(
+            lookup.ip_iter().collect::<Vec<_>>(),
+            vec![IpAddr::from(Ipv4Addr::new(127, 0, 0, 1))]
+        );
+
+        // the service is not available
+        let lookup = block_on(svcb_lookup(
+            Name::from_str("www.example.com.").unwrap(),
+            |_| future::ok(https("www.example.com.", vec![alias(".")])),
+        ))
+        .unwrap();
+        assert_eq!(lookup.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_svcb_lookup_alias_loop() {
+        let result = block_on(svcb_lookup(
+            Name::from_str("a.example.com.").unwrap(),
+            |name| {
+                future::ok(match name.to_string().as_str() {
+                    "a.example.com." => https("a.example.com.", vec![alias("b.example.com.")]),
+                    _ => https("b.example.com.", vec![alias("a.example.com.")]),
+                })
+            },
+        ));
+        assert!(result.is_err());
+
+        // a chain without a loop is limited as well
+        let mut count = 0;
+        let result = block_on(svcb_lookup(
+            Name::from_str("www.example.com.").unwrap(),
+            |name| {
+                count += 1;
+                future::ok(https(
+                    &name.to_string(),
+                    vec![alias(&format!("{}.example.com.", count))],
+                ))
+            },
+        ));
+        assert!(result.is_err());
+        assert_eq!(count, MAX_SVCB_ALIASES + 1);
+    }
+
+    fn srv(priority: u16, weight: u16, target: &str) -> rdata::SRV {
+        rdata::SRV::new(priority, weight, 443, Name::from_str(target).unwrap())
+    }
+
+    fn srv_lookup(srvs: Vec<rdata::SRV>, additionals: Vec<Record>) -> SrvLookup {
+        let name = Name::from_str("_https._tcp.example.com.").unwrap();
+        let records = srvs
+            .into_iter()
+            .map(|srv| Record::from_rdata(name.clone(), 300, RData::SRV(srv)))
+            .chain(additionals)
+            .collect::<Vec<_>>();
+
+        SrvLookup::from(Lookup::new_with_max_ttl(
+            Query::query(name, RecordType::SRV),
+            Arc::from(records),
+        ))
+    }
+
+    #[test]
+    fn test_order_srvs() {
+        let srvs = vec![
+            srv(20, 5, "d.example.com."),
+            srv(10, 10, "b.example.com."),
+            srv(10, 0, "a.example.com."),
+            srv(10, 20, "c.example.com."),
+        ];
+        let targets = |srvs: Vec<rdata::SRV>| {
+            srvs.iter()
+                .map(|srv| srv.target().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        // the lowest number selects the first of the remaining records, weight 0 first
+        assert_eq!(
+            targets(order_srvs(srvs.clone(), |_| 0)),
+            vec![
+                "a.example.com.",
+                "b.example.com.",
+                "c.example.com.",
+                "d.example.com."
+            ]
+        );
+
+        // the sum selects the last of the remaining records
+        assert_eq!(
+            targets(order_srvs(srvs.clone(), |sum| sum)),
+            vec![
+                "c.example.com.",
+                "b.example.com.",
+                "a.example.com.",
+                "d.example.com."
+            ]
+        );
+
+        // with running sums of 0, 10 and 30, 10 selects b, then with 0 and 20, 1 selects c
+        let mut numbers = vec![10, 1].into_iter();
+        assert_eq!(
+            targets(order_srvs(srvs, |_| numbers.next().unwrap_or(0))),
+            vec![
+                "b.example.com.",
+                "c.example.com.",
+                "a.example.com.",
+                "d.example.com."
+            ]
+        );
+    }
+
+    #[test]
+    fn test_service_lookup() {
+        let additional = Record::from_rdata(
+            Name::from_str("a.example.com.").unwrap(),
+            300,
+            RData::A(Ipv4Addr::new(127, 0, 0, 1)),
+        );
+        let lookup = block_on(service_lookup(
+            srv_lookup(
+                vec![
+                    srv(10, 0, "a.example.com."),
+                    srv(20, 0, "b.example.com."),
+                    srv(30, 0, "c.example.com."),
+                ],
+                vec![additional],
+            ),
+            |name| match name.to_string().as_str() {
+                "b.example.com." => future::ok(LookupIp::from(Lookup::from_rdata(
+                    Query::query(name, RecordType::AAAA),
+                    RData::AAAA(Ipv6Addr::LOCALHOST),
+                ))),
+                "c.example.com." => future::err(ResolveError::from("no addresses")),
+                _ => unreachable!(),
+            },
+        ))
+        .unwrap();
+
+        assert_eq!(
+            lookup
+                .iter()
+                .map(|target| (target.name().to_string(), target.addr()))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "a.example.com.".to_string(),
+                    SocketAddr::from((Ipv4Addr::new(127, 0, 0, 1), 443))
+                ),
+                (
+                    "b.example.com.".to_string(),
+                    SocketAddr::from((Ipv6Addr::LOCALHOST, 443))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_service_lookup_no_service() {
+        let result = block_on(service_lookup(
+            srv_lookup(vec![srv(0, 0, ".")], vec![]),
+            |_| future::err::<LookupIp, _>(ResolveError::from("unreachable")),
+        ));
+
+        assert!(matches!(
+            result.unwrap_err().kind(),
+            ResolveErrorKind::NoService(name) if name.to_string() == "_https._tcp.example.com."
+        ));
+
+        // all targets failed to resolve
+        let result = block_on(service_lookup(
+            srv_lookup(vec![srv(0, 0, "a.example.com.")], vec![]),
+            |_| future::err::<LookupIp, _>(ResolveError::from("no addresses")),
+        ));
+        assert!(result.is_err());
+    }
 }
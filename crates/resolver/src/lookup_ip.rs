@@ -10,21 +10,24 @@
 //! At it's heart LookupIp uses Lookup for performing all lookups. It is unlike other standard lookups in that there are customizations around A and AAAA resolutions.
 
 use std::error::Error;
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Instant;
 
 use futures_util::{future, future::Either, future::Future, FutureExt};
+use ipnet::Ipv6Net;
 
 use proto::error::ProtoError;
 use proto::op::Query;
 use proto::rr::{Name, RData, Record, RecordType};
 use proto::xfer::{DnsHandle, DnsRequestOptions};
 
+use crate::address_selection;
 use crate::caching_client::CachingClient;
 use crate::config::LookupIpStrategy;
+use crate::dns64;
 use crate::dns_lru::MAX_TTL;
 use crate::error::*;
 use crate::hosts::Hosts;
@@ -52,12 +55,91 @@ impl LookupIp {
         self.0.valid_until()
     }
 
+    /// Returns the address of the name server which answered, `None` if the answer came from the cache or hosts
+    pub fn server_addr(&self) -> Option<SocketAddr> {
+        self.0.server_addr()
+    }
+
+    /// Returns true if the Authentic Data (AD) bit was set in the response, see `Lookup::authentic_data`
+    pub fn authentic_data(&self) -> bool {
+        self.0.authentic_data()
+    }
+
+    /// Returns true if the Checking Disabled (CD) bit was set in the response, see `Lookup::checking_disabled`
+    pub fn checking_disabled(&self) -> bool {
+        self.0.checking_disabled()
+    }
+
     /// Return a reference to the inner lookup
     ///
     /// This can be useful for getting all records from the request
     pub fn as_lookup(&self) -> &Lookup {
         &self.0
     }
+
+    /// Sorts the addresses by the destination address selection of RFC 6724, other records retain their position
+    pub(crate) fn sort_addresses(self) -> Self {
+        self.reorder_addresses(|addresses| {
+            address_selection::sort_by_address(addresses, |record| {
+                address(record).expect("only address records")
+            })
+        })
+    }
+
+    /// Rotates the addresses left by `rotation` positions, modulo their count, other records retain their position
+    pub(crate) fn rotate_addresses(self, rotation: usize) -> Self {
+        self.reorder_addresses(|addresses| {
+            let len = addresses.len();
+            addresses.rotate_left(rotation % len)
+        })
+    }
+
+    /// Reorders the address records, if there are at least two, the other records retain their position
+    fn reorder_addresses<F: FnOnce(&mut Vec<Record>)>(self, reorder: F) -> Self {
+        let mut addresses = self
+            .0
+            .record_iter()
+            .filter(|record| address(record).is_some())
+            .cloned()
+            .collect::<Vec<_>>();
+        if addresses.len() < 2 {
+            return self;
+        }
+
+        reorder(&mut addresses);
+
+        let mut addresses = addresses.into_iter();
+        let records = self
+            .0
+            .record_iter()
+            .map(|record| match address(record) {
+                Some(_) => addresses
+                    .next()
+                    .expect("as many addresses as address records"),
+                None => record.clone(),
+            })
+            .collect::<Vec<_>>();
+
+        Self(
+            Lookup::new_with_deadline(
+                self.0.query().clone(),
+                Arc::from(records),
+                self.0.valid_until(),
+            )
+            .with_dnssec_status(self.0.dnssec_status())
+            .with_server_addr(self.0.server_addr())
+            .with_response_flags(self.0.authentic_data(), self.0.checking_disabled()),
+        )
+    }
+}
+
+/// The address of A and AAAA records
+fn address(record: &Record) -> Option<IpAddr> {
+    match record.data() {
+        Some(RData::A(ip)) => Some(IpAddr::from(*ip)),
+        Some(RData::AAAA(ip)) => Some(IpAddr::from(*ip)),
+        _ => None,
+    }
 }
 
 impl From<Lookup> for LookupIp {
@@ -132,6 +214,8 @@ where
     query: Pin<Box<dyn Future<Output = Result<Lookup, ResolveError>> + Send>>,
     hosts: Option<Arc<Hosts>>,
     finally_ip_addr: Option<RData>,
+    dns64_prefix: Option<Ipv6Net>,
+    error: Option<ResolveError>,
 }
 
 impl<C, E> Future for LookupIpFuture<C, E>
@@ -143,53 +227,57 @@ where
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         loop {
-            // Try polling the underlying DNS query.
-            let query = self.query.as_mut().poll(cx);
-
-            // Determine whether or not we will attempt to retry the query.
-            let should_retry = match query {
+            // Try polling the underlying DNS query, and determine whether or
+            // not we will attempt to retry the query with the next name.
+            let query = match self.query.as_mut().poll(cx) {
                 // If the query is NotReady, yield immediately.
                 Poll::Pending => return Poll::Pending,
-                // If the query returned a successful lookup, we will attempt
-                // to retry if the lookup is empty. Otherwise, we will return
-                // that lookup.
-                Poll::Ready(Ok(ref lookup)) => lookup.is_empty(),
-                // If the query failed, we will attempt to retry.
-                Poll::Ready(Err(_)) => true,
+                // If the query returned a non-empty lookup, return it.
+                Poll::Ready(Ok(lookup)) if !lookup.is_empty() => {
+                    return Poll::Ready(Ok(lookup.into()))
+                }
+                // If the lookup was empty, we will attempt to retry.
+                Poll::Ready(Ok(lookup)) => Ok(lookup),
+                // If the query failed, we will attempt to retry, remembering
+                // the most specific error across all of the names tried.
+                Poll::Ready(Err(err)) => Err(match self.error.take() {
+                    Some(error) => error.most_specific_for_search(err),
+                    None => err,
+                }),
             };
 
-            if should_retry {
-                if let Some(name) = self.names.pop() {
-                    // If there's another name left to try, build a new query
-                    // for that next name and continue looping.
-                    self.query = strategic_lookup(
-                        name,
-                        self.strategy,
-                        self.client_cache.clone(),
-                        self.options,
-                        self.hosts.clone(),
-                    )
-                    .boxed();
-                    // Continue looping with the new query. It will be polled
-                    // on the next iteration of the loop.
-                    continue;
-                } else if let Some(ip_addr) = self.finally_ip_addr.take() {
-                    // Otherwise, if there's an IP address to fall back to,
-                    // we'll return it.
-                    let record = Record::from_rdata(Name::new(), MAX_TTL, ip_addr);
-                    let lookup = Lookup::new_with_max_ttl(Query::new(), Arc::from([record]));
-                    return Poll::Ready(Ok(lookup.into()));
+            if let Some(name) = self.names.pop() {
+                if let Err(err) = query {
+                    debug!("{}, trying the next search name: {}", err, name);
+                    self.error = Some(err);
                 }
-            };
 
-            // If we didn't have to retry the query, or we weren't able to
-            // retry because we've exhausted the names to search and have no
-            // fallback IP address, return the current query.
-            return query.map(|f| f.map(LookupIp::from));
-            // If we skipped retrying the  query, this will return the
-            // successful lookup, otherwise, if the retry failed, this will
-            // return the last  query result --- either an empty lookup or the
-            // last error we saw.
+                // If there's another name left to try, build a new query
+                // for that next name and continue looping.
+                self.query = strategic_lookup(
+                    name,
+                    self.strategy,
+                    self.client_cache.clone(),
+                    self.options,
+                    self.hosts.clone(),
+                    self.dns64_prefix,
+                )
+                .boxed();
+                // Continue looping with the new query. It will be polled
+                // on the next iteration of the loop.
+                continue;
+            } else if let Some(ip_addr) = self.finally_ip_addr.take() {
+                // Otherwise, if there's an IP address to fall back to,
+                // we'll return it.
+                let record = Record::from_rdata(Name::new(), MAX_TTL, ip_addr);
+                let lookup = Lookup::new_with_max_ttl(Query::new(), Arc::from([record]));
+                return Poll::Ready(Ok(lookup.into()));
+            }
+
+            // We've exhausted the names to search and have no fallback IP
+            // address, return either the last empty lookup or the most
+            // specific error of all the names.
+            return Poll::Ready(query.map(LookupIp::from));
         }
     }
 }
@@ -206,26 +294,44 @@ where
     /// * `names` - a set of DNS names to attempt to resolve, they will be attempted in queue order, i.e. the first is `names.pop()`. Upon each failure, the next will be attempted.
     /// * `strategy` - the lookup IP strategy to use
     /// * `client_cache` - cache with a connection to use for performing all lookups
+    /// * `dns64_prefix` - the NAT64 prefix to synthesize AAAA records with, see `ResolverOpts::dns64_prefix`
     pub fn lookup(
-        names: Vec<Name>,
+        mut names: Vec<Name>,
         strategy: LookupIpStrategy,
         client_cache: CachingClient<C, E>,
         options: DnsRequestOptions,
         hosts: Option<Arc<Hosts>>,
         finally_ip_addr: Option<RData>,
+        dns64_prefix: Option<Ipv6Net>,
     ) -> Self {
-        let empty =
-            ResolveError::from(ResolveErrorKind::Message("can not lookup IPs for no names"));
+        let query = match names.pop() {
+            Some(name) => strategic_lookup(
+                name,
+                strategy,
+                client_cache.clone(),
+                options,
+                hosts.clone(),
+                dns64_prefix,
+            )
+            .boxed(),
+            // If there are no names, this will be returned immediately, unless
+            // there is an IP address to fall back to.
+            None => future::err(ResolveError::from(ResolveErrorKind::Message(
+                "can not lookup IPs for no names",
+            )))
+            .boxed(),
+        };
+
         Self {
             names,
             strategy,
             client_cache,
-            // If there are no names remaining, this will be returned immediately,
-            // otherwise, it will be retried.
-            query: future::err(empty).boxed(),
+            query,
             options,
             hosts,
             finally_ip_addr,
+            dns64_prefix,
+            error: None,
         }
     }
 }
@@ -237,17 +343,99 @@ async fn strategic_lookup<C, E>(
     client: CachingClient<C, E>,
     options: DnsRequestOptions,
     hosts: Option<Arc<Hosts>>,
+    dns64_prefix: Option<Ipv6Net>,
 ) -> Result<Lookup, ResolveError>
 where
     C: DnsHandle<Error = E> + 'static,
     E: Into<ResolveError> + From<ProtoError> + Error + Clone + Send + Unpin + 'static,
 {
-    match strategy {
+    let dns64 = dns64_prefix.map(|prefix| (prefix, name.clone(), client.clone(), hosts.clone()));
+
+    let lookup = match strategy {
         LookupIpStrategy::Ipv4Only => ipv4_only(name, client, options, hosts).await,
         LookupIpStrategy::Ipv6Only => ipv6_only(name, client, options, hosts).await,
         LookupIpStrategy::Ipv4AndIpv6 => ipv4_and_ipv6(name, client, options, hosts).await,
         LookupIpStrategy::Ipv6thenIpv4 => ipv6_then_ipv4(name, client, options, hosts).await,
         LookupIpStrategy::Ipv4thenIpv6 => ipv4_then_ipv6(name, client, options, hosts).await,
+    };
+
+    match dns64 {
+        Some((prefix, name, client, hosts)) => {
+            dns64_synthesis(name, strategy, prefix, lookup, client, options, hosts).await
+        }
+        None => lookup,
+    }
+}
+
+/// Synthesizes AAAA records from the A records of the name, if it has no AAAA records, [RFC 6147](https://tools.ietf.org/html/rfc6147#section-5.1)
+///
+/// Synthesis only happens for the strategies which ask for AAAA records before, or together with, A records. The
+///  synthesized records replace the A records, except for `Ipv4AndIpv6` which returns both.
+async fn dns64_synthesis<C, E>(
+    name: Name,
+    strategy: LookupIpStrategy,
+    prefix: Ipv6Net,
+    lookup: Result<Lookup, ResolveError>,
+    client: CachingClient<C, E>,
+    options: DnsRequestOptions,
+    hosts: Option<Arc<Hosts>>,
+) -> Result<Lookup, ResolveError>
+where
+    C: DnsHandle<Error = E> + 'static,
+    E: Into<ResolveError> + From<ProtoError> + Error + Clone + Send + Unpin + 'static,
+{
+    let has_aaaa = |lookup: &Lookup| {
+        lookup
+            .iter()
+            .any(|rdata| rdata.to_record_type() == RecordType::AAAA)
+    };
+
+    let ipv4_lookup = match (strategy, &lookup) {
+        (LookupIpStrategy::Ipv4Only, _) | (LookupIpStrategy::Ipv4thenIpv6, _) => return lookup,
+        (_, Ok(ips)) if has_aaaa(ips) => return lookup,
+        // only AAAA records were requested, the A records are needed for synthesis
+        (LookupIpStrategy::Ipv6Only, _) => {
+            match hosts_lookup(
+                Query::query(name.clone(), RecordType::A),
+                client,
+                options,
+                hosts,
+            )
+            .await
+            {
+                Ok(ipv4_lookup) => ipv4_lookup,
+                Err(_) => return lookup,
+            }
+        }
+        (_, Ok(ips)) => ips.clone(),
+        (_, Err(_)) => return lookup,
+    };
+
+    let synthesized = ipv4_lookup
+        .record_iter()
+        .filter_map(|record| match record.data() {
+            Some(RData::A(ipv4)) => dns64::synthesize(&prefix, *ipv4).map(|ipv6| {
+                Record::from_rdata(record.name().clone(), record.ttl(), RData::AAAA(ipv6))
+            }),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    if synthesized.is_empty() {
+        return lookup;
+    }
+
+    let synthesized = Lookup::new_with_deadline(
+        Query::query(name, RecordType::AAAA),
+        Arc::from(synthesized),
+        ipv4_lookup.valid_until(),
+    )
+    .with_server_addr(ipv4_lookup.server_addr())
+    // the synthesized records were not authenticated by the name server
+    .with_response_flags(false, ipv4_lookup.checking_disabled());
+
+    match strategy {
+        LookupIpStrategy::Ipv4AndIpv6 => Ok(ipv4_lookup.append(synthesized)),
+        _ => Ok(synthesized),
     }
 }
 
@@ -733,4 +921,148 @@ pub mod tests {
             vec![Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)]
         );
     }
+
+    #[test]
+    fn test_sort_addresses_retains_records() {
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let records = vec![
+            Record::from_rdata(name.clone(), 86400, RData::A(Ipv4Addr::new(192, 0, 2, 1))),
+            Record::from_rdata(name.clone(), 86400, RData::CNAME(Name::root())),
+            Record::from_rdata(name.clone(), 86400, RData::AAAA(Ipv6Addr::LOCALHOST)),
+            Record::from_rdata(name, 86400, RData::A(Ipv4Addr::LOCALHOST)),
+        ];
+        let lookup = LookupIp::from(Lookup::new_with_max_ttl(
+            Query::new(),
+            Arc::from(records.clone()),
+        ));
+
+        let sorted = lookup.clone().sort_addresses();
+
+        // the order depends on the local addresses, but no records are added or lost
+        assert_eq!(sorted.as_lookup().records()[1], records[1]);
+        assert_eq!(sorted.valid_until(), lookup.valid_until());
+        let mut addresses = sorted.iter().collect::<Vec<_>>();
+        addresses.sort();
+        let mut expected = lookup.iter().collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(addresses, expected);
+    }
+
+    #[test]
+    fn test_rotate_addresses() {
+        let name = Name::from_ascii("www.example.com.").unwrap();
+        let records = vec![
+            Record::from_rdata(name.clone(), 86400, RData::A(Ipv4Addr::new(192, 0, 2, 1))),
+            Record::from_rdata(name.clone(), 86400, RData::CNAME(Name::root())),
+            Record::from_rdata(name.clone(), 86400, RData::A(Ipv4Addr::new(192, 0, 2, 2))),
+            Record::from_rdata(name, 86400, RData::A(Ipv4Addr::new(192, 0, 2, 3))),
+        ];
+        let lookup = LookupIp::from(Lookup::new_with_max_ttl(
+            Query::new(),
+            Arc::from(records.clone()),
+        ));
+
+        let addresses = |rotation| {
+            let rotated = lookup.clone().rotate_addresses(rotation);
+            assert_eq!(rotated.as_lookup().records()[1], records[1]);
+            rotated
+                .iter()
+                .map(|ip| match ip {
+                    IpAddr::V4(ip) => ip.octets()[3],
+                    IpAddr::V6(_) => panic!("no IPv6 addresses"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(addresses(0), vec![1, 2, 3]);
+        assert_eq!(addresses(1), vec![2, 3, 1]);
+        assert_eq!(addresses(2), vec![3, 1, 2]);
+        assert_eq!(addresses(3), vec![1, 2, 3]);
+    }
+
+    fn dns64_lookup(
+        strategy: LookupIpStrategy,
+        messages: Vec<Result<DnsResponse, ResolveError>>,
+    ) -> Vec<IpAddr> {
+        block_on(strategic_lookup(
+            Name::root(),
+            strategy,
+            CachingClient::new(0, mock(messages), false),
+            DnsRequestOptions::default(),
+            None,
+            Some("64:ff9b::/96".parse().unwrap()),
+        ))
+        .unwrap()
+        .iter()
+        .map(|r| r.to_ip_addr().unwrap())
+        .collect()
+    }
+
+    fn dns64_v4_message() -> Result<DnsResponse, ResolveError> {
+        let mut message = Message::new();
+        message.add_query(Query::query(Name::root(), RecordType::A));
+        message.insert_answers(vec![Record::from_rdata(
+            Name::root(),
+            86400,
+            RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+        )]);
+
+        Ok(message.into())
+    }
+
+    #[test]
+    fn test_dns64_synthesis() {
+        let synthesized: IpAddr = "64:ff9b::c000:201".parse().unwrap();
+
+        // no AAAA records, the A record is queried for synthesis
+        assert_eq!(
+            dns64_lookup(
+                LookupIpStrategy::Ipv6Only,
+                vec![dns64_v4_message(), empty()]
+            ),
+            vec![synthesized]
+        );
+        assert_eq!(
+            dns64_lookup(
+                LookupIpStrategy::Ipv6thenIpv4,
+                vec![dns64_v4_message(), empty()]
+            ),
+            vec![synthesized]
+        );
+        assert_eq!(
+            dns64_lookup(
+                LookupIpStrategy::Ipv4AndIpv6,
+                vec![empty(), dns64_v4_message()]
+            ),
+            vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)), synthesized]
+        );
+
+        // IPv4 is preferred, no synthesis
+        assert_eq!(
+            dns64_lookup(LookupIpStrategy::Ipv4thenIpv6, vec![dns64_v4_message()]),
+            vec![IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1))]
+        );
+    }
+
+    #[test]
+    fn test_dns64_real_aaaa() {
+        // a real AAAA record suppresses synthesis
+        assert_eq!(
+            dns64_lookup(
+                LookupIpStrategy::Ipv6Only,
+                vec![dns64_v4_message(), v6_message()]
+            ),
+            vec![Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1)]
+        );
+        assert_eq!(
+            dns64_lookup(
+                LookupIpStrategy::Ipv4AndIpv6,
+                vec![v6_message(), dns64_v4_message()]
+            ),
+            vec![
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::V6(Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 1))
+            ]
+        );
+    }
 }
@@ -6,6 +6,7 @@
 // copied, modified, or distributed except according to those terms.
 
 use std::marker::Unpin;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
@@ -34,7 +35,7 @@ use proto::quic::{QuicClientConnect, QuicClientStream};
 use proto::{
     self,
     error::ProtoError,
-    op::NoopMessageFinalizer,
+    op::{Keepalive, NoopMessageFinalizer},
     tcp::Connect,
     tcp::TcpClientConnect,
     tcp::TcpClientStream,
@@ -42,7 +43,7 @@ use proto::{
     udp::{UdpClientStream, UdpSocket},
     xfer::{
         DnsExchange, DnsExchangeConnect, DnsExchangeSend, DnsHandle, DnsMultiplexer,
-        DnsMultiplexerConnect, DnsRequest, DnsResponse,
+        DnsMultiplexerConnect, DnsRequest, DnsResponse, SerialMessage,
     },
     Time,
 };
@@ -69,6 +70,16 @@ pub trait ConnectionProvider: 'static + Clone + Send + Sync + Unpin {
     /// The returned handle should
     fn new_connection(&self, config: &NameServerConfig, options: &ResolverOpts)
         -> Self::FutureConn;
+
+    /// Runs the future as a background task, e.g. the refresh of a stale cache entry
+    ///
+    /// The default spawns nothing and returns the future, which is then run by the caller.
+    fn spawn_bg<F>(&self, future: F) -> Result<(), F>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        Err(future)
+    }
 }
 
 /// RuntimeProvider defines which async runtime that handles IO and timers.
@@ -103,6 +114,37 @@ impl<R: RuntimeProvider> GenericConnectionProvider<R> {
     }
 }
 
+/// The address to bind to for the connection, `NameServerConfig::bind_addr` or else `ResolverOpts::bind_addr`, if it
+///  is of the same family as the address of the name server
+fn bind_addr(config: &NameServerConfig, options: &ResolverOpts) -> Option<SocketAddr> {
+    config.bind_addr.or_else(|| {
+        options
+            .bind_addr
+            .filter(|bind_addr| bind_addr.is_ipv4() == config.socket_addr.is_ipv4())
+    })
+}
+
+/// Requests a DSO session on the TCP or TLS connection, if `ResolverOpts::dso_keepalive_interval` is set, and
+///  advertises the edns-tcp-keepalive option, if `ResolverOpts::tcp_keepalive` is set
+fn keepalive<F, S>(
+    dns_conn: DnsMultiplexerConnect<F, S, NoopMessageFinalizer>,
+    options: &ResolverOpts,
+) -> DnsMultiplexerConnect<F, S, NoopMessageFinalizer>
+where
+    F: Future<Output = Result<S, ProtoError>> + Send + Unpin + 'static,
+    S: Stream<Item = Result<SerialMessage, ProtoError>> + Unpin,
+{
+    let dns_conn = match options.dso_keepalive_interval {
+        Some(interval) => dns_conn.with_dso_keepalive(Keepalive::new(None, Some(interval))),
+        None => dns_conn,
+    };
+
+    match options.tcp_keepalive {
+        Some(idle_timeout) => dns_conn.with_tcp_keepalive(idle_timeout),
+        None => dns_conn,
+    }
+}
+
 impl<R> ConnectionProvider for GenericConnectionProvider<R>
 where
     R: RuntimeProvider,
@@ -123,7 +165,7 @@ where
             Protocol::Udp => {
                 let stream = UdpClientStream::<R::Udp>::with_bind_addr_and_timeout(
                     config.socket_addr,
-                    config.bind_addr,
+                    bind_addr(config, options),
                     options.timeout,
                 );
                 let exchange = DnsExchange::connect(stream);
@@ -131,7 +173,7 @@ where
             }
             Protocol::Tcp => {
                 let socket_addr = config.socket_addr;
-                let bind_addr = config.bind_addr;
+                let bind_addr = bind_addr(config, options);
                 let timeout = options.timeout;
 
                 let (stream, handle) = TcpClientStream::<R::Tcp>::with_bind_addr_and_timeout(
@@ -147,13 +189,14 @@ where
                     NoopMessageFinalizer::new(),
                 );
 
+                let dns_conn = keepalive(dns_conn, options);
                 let exchange = DnsExchange::connect(dns_conn);
                 ConnectionConnect::Tcp(exchange)
             }
             #[cfg(feature = "dns-over-tls")]
             Protocol::Tls => {
                 let socket_addr = config.socket_addr;
-                let bind_addr = config.bind_addr;
+                let bind_addr = bind_addr(config, options);
                 let timeout = options.timeout;
                 let tls_dns_name = config.tls_dns_name.clone().unwrap_or_default();
                 #[cfg(feature = "dns-over-rustls")]
@@ -179,13 +222,14 @@ where
                     NoopMessageFinalizer::new(),
                 );
 
+                let dns_conn = keepalive(dns_conn, options);
                 let exchange = DnsExchange::connect(dns_conn);
                 ConnectionConnect::Tls(exchange)
             }
             #[cfg(feature = "dns-over-https")]
             Protocol::Https => {
                 let socket_addr = config.socket_addr;
-                let bind_addr = config.bind_addr;
+                let bind_addr = bind_addr(config, options);
                 let tls_dns_name = config.tls_dns_name.clone().unwrap_or_default();
                 #[cfg(feature = "dns-over-rustls")]
                 let client_config = config.tls_config.clone();
@@ -201,7 +245,7 @@ where
             #[cfg(feature = "dns-over-quic")]
             Protocol::Quic => {
                 let socket_addr = config.socket_addr;
-                let bind_addr = config.bind_addr;
+                let bind_addr = bind_addr(config, options);
                 let tls_dns_name = config.tls_dns_name.clone().unwrap_or_default();
                 #[cfg(feature = "dns-over-rustls")]
                 let client_config = config.tls_config.clone();
@@ -219,8 +263,11 @@ where
                 let socket_addr = config.socket_addr;
                 let timeout = options.timeout;
 
+                // ipv6 multicast requires an interface, 0 is the default interface of the system
+                let ipv6_if = if socket_addr.is_ipv6() { Some(0) } else { None };
+
                 let (stream, handle) =
-                    MdnsClientStream::new(socket_addr, MdnsQueryType::OneShot, None, None, None);
+                    MdnsClientStream::new(socket_addr, MdnsQueryType::OneShot, None, None, ipv6_if);
                 // TODO: need config for Signer...
                 let dns_conn = DnsMultiplexer::with_timeout(
                     stream,
@@ -239,6 +286,14 @@ where
             spawner: self.0.clone(),
         }
     }
+
+    fn spawn_bg<F>(&self, future: F) -> Result<(), F>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.0.clone().spawn_bg(future.map(Ok));
+        Ok(())
+    }
 }
 
 #[cfg(feature = "dns-over-tls")]
@@ -11,16 +11,19 @@ mod name_server;
 mod name_server_pool;
 mod name_server_state;
 mod name_server_stats;
+mod response_observer;
 
 pub use self::connection_provider::{ConnectionProvider, RuntimeProvider, Spawn};
 pub use self::connection_provider::{GenericConnection, GenericConnectionProvider};
 #[cfg(feature = "mdns")]
 #[cfg_attr(docsrs, doc(cfg(feature = "mdns")))]
-pub(crate) use self::name_server::mdns_nameserver;
+pub(crate) use self::name_server::mdns_nameservers;
 pub use self::name_server::NameServer;
 pub use self::name_server_pool::NameServerPool;
 use self::name_server_state::NameServerState;
 use self::name_server_stats::NameServerStats;
+pub use self::response_observer::ResponseObserver;
+pub(crate) use self::response_observer::ResponseObserverSlot;
 
 #[cfg(feature = "tokio-runtime")]
 #[cfg_attr(docsrs, doc(cfg(feature = "tokio-runtime")))]
@@ -7,25 +7,38 @@
 
 use std::cmp::Ordering;
 use std::fmt::{self, Debug, Formatter};
+#[cfg(feature = "mdns")]
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use futures_util::lock::Mutex;
+#[cfg(feature = "mdns")]
+use futures_util::stream::StreamExt;
 use futures_util::stream::{once, Stream};
 
+use proto::error::ProtoError;
 #[cfg(feature = "mdns")]
-use proto::multicast::MDNS_IPV4;
+use proto::multicast::{MDNS_IPV4, MDNS_IPV6};
+use proto::op::{Edns, PaddingPolicy, Query};
+use proto::rr::Name;
 use proto::xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer};
-
 #[cfg(feature = "mdns")]
-use crate::config::Protocol;
-use crate::config::{NameServerConfig, ResolverOpts};
-use crate::error::ResolveError;
-use crate::name_server::{ConnectionProvider, NameServerState, NameServerStats};
+use proto::Time;
+
+use crate::config::{NameServerConfig, Protocol, ResolverOpts};
+use crate::error::{ResolveError, ResolveErrorKind};
+use crate::name_server::{
+    ConnectionProvider, NameServerState, NameServerStats, ResponseObserverSlot,
+};
 #[cfg(feature = "tokio-runtime")]
 use crate::name_server::{TokioConnection, TokioConnectionProvider, TokioHandle};
 
+// > An EDNS buffer size of 1232 bytes will avoid fragmentation on nearly all current networks.
+// https://dnsflagday.net/2020/
+const SHRUNK_MAX_PAYLOAD_LEN: u16 = 1232;
+
 /// Specifies the details of a remote NameServer used for lookups
 #[derive(Clone)]
 pub struct NameServer<
@@ -37,6 +50,7 @@ pub struct NameServer<
     client: Arc<Mutex<Option<C>>>,
     state: Arc<NameServerState>,
     stats: Arc<NameServerStats>,
+    observer: Arc<ResponseObserverSlot>,
     conn_provider: P,
 }
 
@@ -68,6 +82,7 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> NameSe
             client: Arc::new(Mutex::new(None)),
             state: Arc::new(NameServerState::init(None)),
             stats: Arc::new(NameServerStats::default()),
+            observer: Arc::default(),
             conn_provider,
         }
     }
@@ -85,10 +100,17 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> NameSe
             client: Arc::new(Mutex::new(Some(client))),
             state: Arc::new(NameServerState::init(None)),
             stats: Arc::new(NameServerStats::default()),
+            observer: Arc::default(),
             conn_provider,
         }
     }
 
+    /// Shares the observer of responses, usually with all other name servers of the pool
+    pub(crate) fn with_response_observer(mut self, observer: Arc<ResponseObserverSlot>) -> Self {
+        self.observer = observer;
+        self
+    }
+
     #[cfg(test)]
     #[allow(dead_code)]
     pub(crate) fn is_connected(&self) -> bool {
@@ -134,11 +156,72 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> NameSe
         mut self,
         request: R,
     ) -> Result<DnsResponse, ResolveError> {
+        let mut request = request.into();
+
+        // hide the size of queries over encrypted transports
+        let padding = self.options.padding;
+        if self.config.protocol.is_encrypted() && padding != PaddingPolicy::None {
+            request.extensions_mut().get_or_insert_with(Edns::new);
+            request.pad(padding)?;
+        }
+
+        // don't advertise a larger payload than the name server was found to be reachable with
+        let max_payload = self.state.max_payload();
+        if let Some(edns) = request.extensions_mut() {
+            if edns.max_payload() > max_payload {
+                edns.set_max_payload(max_payload);
+            }
+        }
+
+        // DNS 0x20, the original name is restored in the response once the case has been verified
+        let randomized_names = if self.options.case_randomization {
+            request.queries_mut().first_mut().map(|query| {
+                let original = query.name().clone();
+                let sent = original.to_random_case();
+                query.set_name(sent.clone());
+                (original, sent)
+            })
+        } else {
+            None
+        };
+
+        // the query is only kept for an observer, so that there is no allocation without one
+        let observer = self
+            .observer
+            .get()
+            .and_then(|observer| Some((observer, request.queries().first()?.clone())));
+
         let mut client = self.connected_mut_client().await?;
-        let response = client.send(request).first_answer().await;
+        let shrunk_request = self.shrunk_request(&request);
+        let mut sent = Instant::now();
+        let mut response = client.send(request).first_answer().await;
+
+        // large responses might be fragmented, and the fragments dropped on the way
+        if let Some(shrunk_request) = shrunk_request {
+            if matches!(response, Err(ref e) if matches!(e.kind(), ResolveErrorKind::Timeout)) {
+                debug!(
+                    "timed out with a large UDP payload, retrying with {}: {:?}",
+                    SHRUNK_MAX_PAYLOAD_LEN, self.config
+                );
+                self.state.shrink_max_payload(SHRUNK_MAX_PAYLOAD_LEN);
+
+                sent = Instant::now();
+                response = client.send(shrunk_request).first_answer().await;
+            }
+        }
 
         match response {
-            Ok(response) => {
+            Ok(mut response) => {
+                let rtt = sent.elapsed();
+
+                if let Some((original, sent)) = randomized_names {
+                    restore_case(&mut response, &original, &sent)?;
+                }
+                response.set_server_addr(Some(self.config.socket_addr));
+                if let Some((observer, query)) = observer {
+                    observer.on_response(&query, &self.config, &response, rtt);
+                }
+
                 // First evaluate if the message succeeded.
                 let response =
                     ResolveError::from_response(response, self.config.trust_nx_responses)?;
@@ -150,17 +233,18 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> NameSe
                 self.state.establish(remote_edns);
 
                 // record the success
-                self.stats.next_success();
+                self.stats.next_success(rtt);
                 Ok(response)
             }
             Err(error) => {
                 debug!("name_server connection failure: {}", error);
 
                 // this transitions the state to failure
-                self.state.fail(Instant::now());
+                let now = Instant::now();
+                self.state.fail(now);
 
                 // record the failure
-                self.stats.next_failure();
+                self.stats.next_failure(now);
 
                 // These are connection failures, not lookup failures, that is handled in the resolver layer
                 Err(error)
@@ -168,9 +252,112 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> NameSe
         }
     }
 
+    /// A copy of the request advertising a smaller UDP payload, if it may need to be retried with one
+    ///
+    /// This is only the case for requests over UDP advertising a payload larger than `SHRUNK_MAX_PAYLOAD_LEN`, when
+    ///  `ResolverOpts::edns_udp_shrink` is set.
+    fn shrunk_request(&self, request: &DnsRequest) -> Option<DnsRequest> {
+        if !self.options.edns_udp_shrink || self.config.protocol != Protocol::Udp {
+            return None;
+        }
+
+        match request.extensions() {
+            Some(edns) if edns.max_payload() > SHRUNK_MAX_PAYLOAD_LEN => (),
+            _ => return None,
+        }
+
+        let mut shrunk_request = request.clone();
+        shrunk_request
+            .extensions_mut()
+            .as_mut()?
+            .set_max_payload(SHRUNK_MAX_PAYLOAD_LEN);
+        Some(shrunk_request)
+    }
+
     pub fn trust_nx_responses(&self) -> bool {
         self.config.trust_nx_responses
     }
+
+    /// The key by which the name servers are ordered with `ServerOrderingStrategy::QueryStatistics`
+    ///
+    /// Name servers which failed within the cooldown before `now` come last, otherwise the one with the lowest
+    ///  smoothed round trip time comes first; those which have not answered yet are tried before the others.
+    pub(crate) fn statistics_key(&self, now: Instant) -> (bool, Duration) {
+        (self.stats.is_cooling_down(now), self.stats.srtt())
+    }
+
+    /// Sends the request and collects all of the responses received within the `window`
+    ///
+    /// This is for mDNS, where any number of responders may answer a single query. The responses are not checked
+    ///  for errors, it's up to the caller to merge them.
+    #[cfg(feature = "mdns")]
+    pub(crate) async fn collect_responses(
+        mut self,
+        request: DnsRequest,
+        window: Duration,
+    ) -> Result<Vec<DnsResponse>, ResolveError> {
+        let mut client = self.connected_mut_client().await?;
+        let mut stream = client.send(request);
+        let deadline = Instant::now() + window;
+        let mut responses = Vec::new();
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let (next, rest) = match P::Time::timeout(remaining, stream.into_future()).await {
+                Ok(next) => next,
+                // the window has passed
+                Err(_) => break,
+            };
+            stream = rest;
+
+            match next {
+                Some(Ok(response)) => responses.push(response),
+                Some(Err(error)) if responses.is_empty() => return Err(error),
+                Some(Err(error)) => {
+                    debug!(
+                        "mdns stream failed after {} responses: {}",
+                        responses.len(),
+                        error
+                    );
+                    break;
+                }
+                None => break,
+            }
+        }
+
+        Ok(responses)
+    }
+}
+
+/// Verifies that the response echoes the name of the query with exactly the case it was sent with, and restores the
+///  original case of the query and of the answers for it
+fn restore_case(
+    response: &mut DnsResponse,
+    original: &Name,
+    sent: &Name,
+) -> Result<(), ResolveError> {
+    match response.queries().first() {
+        Some(query) if query.name().eq_case(sent) => (),
+        received => {
+            warn!(
+                "case of the query name does not match, sent: {} received: {:?}, dropped",
+                sent,
+                received.map(Query::name)
+            );
+            return Err(
+                ProtoError::from("case of the query name does not match the request").into(),
+            );
+        }
+    }
+
+    response.queries_mut()[0].set_name(original.clone());
+    for record in response.answers_mut() {
+        if record.name().eq_case(sent) {
+            record.set_name(original.clone());
+        }
+    }
+
+    Ok(())
 }
 
 impl<C, P> DnsHandle for NameServer<C, P>
@@ -235,22 +422,37 @@ impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> Partia
 
 impl<C: DnsHandle<Error = ResolveError>, P: ConnectionProvider<Conn = C>> Eq for NameServer<C, P> {}
 
-// TODO: once IPv6 is better understood, also make this a binary keep.
+/// The NameServers for one-shot mDNS queries, to the IPv4 and IPv6 multicast groups
 #[cfg(feature = "mdns")]
-pub(crate) fn mdns_nameserver<C, P>(
+pub(crate) fn mdns_nameservers<C, P>(
+    options: ResolverOpts,
+    conn_provider: P,
+) -> Vec<NameServer<C, P>>
+where
+    C: DnsHandle<Error = ResolveError>,
+    P: ConnectionProvider<Conn = C>,
+{
+    vec![
+        mdns_nameserver(*MDNS_IPV4, options, conn_provider.clone()),
+        mdns_nameserver(*MDNS_IPV6, options, conn_provider),
+    ]
+}
+
+#[cfg(feature = "mdns")]
+fn mdns_nameserver<C, P>(
+    socket_addr: SocketAddr,
     options: ResolverOpts,
     conn_provider: P,
-    trust_nx_responses: bool,
 ) -> NameServer<C, P>
 where
     C: DnsHandle<Error = ResolveError>,
     P: ConnectionProvider<Conn = C>,
 {
     let config = NameServerConfig {
-        socket_addr: *MDNS_IPV4,
+        socket_addr,
         protocol: Protocol::Mdns,
         tls_dns_name: None,
-        trust_nx_responses,
+        trust_nx_responses: false,
         #[cfg(feature = "dns-over-rustls")]
         tls_config: None,
         bind_addr: None,
@@ -272,7 +474,8 @@ mod tests {
     use proto::xfer::{DnsHandle, DnsRequestOptions, FirstAnswer};
 
     use super::*;
-    use crate::config::Protocol;
+    use crate::name_server::ResponseObserver;
+    use crate::proto::xfer::retry_dns_handle::RetryableError;
 
     #[test]
     fn test_name_server() {
@@ -344,4 +547,419 @@ mod tests {
             }))
             .is_err());
     }
+
+    /// Sends a query over UDP with `ResolverOpts::bind_addr`, returning the source address seen by the name server
+    #[cfg(target_os = "linux")]
+    async fn query_source(bind_addr: SocketAddr) -> SocketAddr {
+        let server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let config = NameServerConfig {
+            socket_addr: server.local_addr().unwrap(),
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+            trust_nx_responses: false,
+            #[cfg(feature = "dns-over-rustls")]
+            tls_config: None,
+            bind_addr: None,
+        };
+        let options = ResolverOpts {
+            bind_addr: Some(bind_addr),
+            ..ResolverOpts::default()
+        };
+
+        let mut name_server =
+            NameServer::<_, TokioConnectionProvider>::new(config, options, TokioHandle);
+        let name = Name::parse("www.example.com.", None).unwrap();
+        let lookup = name_server
+            .lookup(
+                Query::query(name, RecordType::A),
+                DnsRequestOptions::default(),
+            )
+            .first_answer();
+        let lookup = tokio::spawn(lookup);
+
+        let mut buf = [0u8; 512];
+        let (len, src) = server.recv_from(&mut buf).await.unwrap();
+        let mut response = proto::op::Message::from_vec(&buf[..len]).unwrap();
+        response.set_message_type(proto::op::MessageType::Response);
+        server
+            .send_to(&response.to_vec().unwrap(), src)
+            .await
+            .unwrap();
+
+        // the response has no answers, it only has to arrive
+        let _ = lookup.await.unwrap();
+        src
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_bind_addr() {
+        let io_loop = Runtime::new().unwrap();
+
+        // all of 127.0.0.0/8 is local on Linux
+        let bind_ip = IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2));
+        let src = io_loop.block_on(query_source(SocketAddr::new(bind_ip, 0)));
+        assert_eq!(src.ip(), bind_ip);
+        assert_ne!(src.port(), 0);
+
+        // of another family, it's not used
+        let src = io_loop.block_on(query_source("[::1]:0".parse().unwrap()));
+        assert_eq!(src.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+    }
+
+    /// Records the size of the requests, responding with an empty message
+    #[derive(Clone, Default)]
+    struct RecordingHandle {
+        sizes: Arc<std::sync::Mutex<Vec<usize>>>,
+    }
+
+    impl DnsHandle for RecordingHandle {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ResolveError>> + Send>>;
+        type Error = ResolveError;
+
+        fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(
+            &mut self,
+            request: R,
+        ) -> Self::Response {
+            let request = request.into();
+            self.sizes
+                .lock()
+                .unwrap()
+                .push(request.to_vec().unwrap().len());
+
+            let mut response = proto::op::Message::new();
+            response.set_id(request.id());
+            Box::pin(once(future::ok(response.into())))
+        }
+    }
+
+    #[derive(Clone)]
+    struct RecordingProvider;
+
+    impl ConnectionProvider for RecordingProvider {
+        type Conn = RecordingHandle;
+        type FutureConn = future::Ready<Result<RecordingHandle, ResolveError>>;
+        type Time = proto::TokioTime;
+
+        fn new_connection(&self, _: &NameServerConfig, _: &ResolverOpts) -> Self::FutureConn {
+            future::ok(RecordingHandle::default())
+        }
+    }
+
+    fn padded_size(protocol: Protocol) -> usize {
+        let config = NameServerConfig {
+            socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 853),
+            protocol,
+            tls_dns_name: Some("dns.example.com".to_string()),
+            trust_nx_responses: false,
+            #[cfg(feature = "dns-over-rustls")]
+            tls_config: None,
+            bind_addr: None,
+        };
+        let options = ResolverOpts {
+            padding: PaddingPolicy::Block(128),
+            ..ResolverOpts::default()
+        };
+
+        let handle = RecordingHandle::default();
+        let mut name_server =
+            NameServer::from_conn(config, options, handle.clone(), RecordingProvider);
+
+        let name = Name::parse("www.example.com.", None).unwrap();
+        let _ = futures_executor::block_on(
+            name_server
+                .lookup(
+                    Query::query(name, RecordType::A),
+                    DnsRequestOptions::default(),
+                )
+                .first_answer(),
+        );
+
+        let sizes = handle.sizes.lock().unwrap();
+        assert_eq!(sizes.len(), 1);
+        sizes[0]
+    }
+
+    #[test]
+    fn test_padding_not_encrypted() {
+        assert_ne!(padded_size(Protocol::Udp) % 128, 0);
+        assert_ne!(padded_size(Protocol::Tcp) % 128, 0);
+    }
+
+    #[test]
+    #[cfg(feature = "dns-over-tls")]
+    fn test_padding_tls() {
+        assert_eq!(padded_size(Protocol::Tls), 128);
+    }
+
+    /// Answers the query with an address, echoing the name of the query or lowercasing it
+    #[derive(Clone)]
+    struct EchoHandle {
+        lowercase: bool,
+    }
+
+    impl DnsHandle for EchoHandle {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ResolveError>> + Send>>;
+        type Error = ResolveError;
+
+        fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(
+            &mut self,
+            request: R,
+        ) -> Self::Response {
+            let request = request.into();
+            let mut query = request.queries()[0].clone();
+            if self.lowercase {
+                query.set_name(query.name().to_lowercase());
+            }
+
+            let mut response = proto::op::Message::new();
+            response.set_id(request.id());
+            response.add_answer(proto::rr::Record::from_rdata(
+                query.name().clone(),
+                300,
+                proto::rr::RData::A(Ipv4Addr::LOCALHOST),
+            ));
+            response.add_query(query);
+            Box::pin(once(future::ok(response.into())))
+        }
+    }
+
+    #[derive(Clone)]
+    struct EchoProvider {
+        lowercase: bool,
+    }
+
+    impl ConnectionProvider for EchoProvider {
+        type Conn = EchoHandle;
+        type FutureConn = future::Ready<Result<EchoHandle, ResolveError>>;
+        type Time = proto::TokioTime;
+
+        fn new_connection(&self, _: &NameServerConfig, _: &ResolverOpts) -> Self::FutureConn {
+            future::ok(EchoHandle {
+                lowercase: self.lowercase,
+            })
+        }
+    }
+
+    fn case_randomized_lookup(lowercase: bool) -> Result<DnsResponse, ResolveError> {
+        let config = NameServerConfig {
+            socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 53),
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+            trust_nx_responses: false,
+            #[cfg(feature = "dns-over-rustls")]
+            tls_config: None,
+            bind_addr: None,
+        };
+        let options = ResolverOpts {
+            case_randomization: true,
+            ..ResolverOpts::default()
+        };
+
+        let mut name_server =
+            NameServer::new_with_provider(config, options, EchoProvider { lowercase });
+
+        // long enough that the randomized name is never all lowercase
+        let name = Name::parse("www.case-randomization.example.com.", None).unwrap();
+        futures_executor::block_on(
+            name_server
+                .lookup(
+                    Query::query(name, RecordType::A),
+                    DnsRequestOptions::default(),
+                )
+                .first_answer(),
+        )
+    }
+
+    #[test]
+    fn test_case_randomization() {
+        let name = Name::parse("www.case-randomization.example.com.", None).unwrap();
+
+        let response = case_randomized_lookup(false).expect("case was echoed");
+        assert!(response.queries()[0].name().eq_case(&name));
+        assert!(response.answers()[0].name().eq_case(&name));
+
+        let error = case_randomized_lookup(true).expect_err("case was not echoed");
+        assert!(error.should_retry());
+    }
+
+    /// Records the queries and name servers of the observed responses
+    #[derive(Default)]
+    struct RecordingObserver {
+        observed: std::sync::Mutex<Vec<(Query, SocketAddr)>>,
+    }
+
+    impl ResponseObserver for RecordingObserver {
+        fn on_response(
+            &self,
+            query: &Query,
+            name_server: &NameServerConfig,
+            response: &DnsResponse,
+            _rtt: Duration,
+        ) {
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+            self.observed
+                .lock()
+                .unwrap()
+                .push((query.clone(), name_server.socket_addr));
+        }
+    }
+
+    #[test]
+    fn test_response_observer() {
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 53);
+        let config = NameServerConfig {
+            socket_addr,
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+            trust_nx_responses: false,
+            #[cfg(feature = "dns-over-rustls")]
+            tls_config: None,
+            bind_addr: None,
+        };
+
+        let slot = Arc::new(ResponseObserverSlot::default());
+        let mut name_server = NameServer::from_conn(
+            config,
+            ResolverOpts::default(),
+            RecordingHandle::default(),
+            RecordingProvider,
+        )
+        .with_response_observer(slot.clone());
+
+        let query = Query::query(
+            Name::parse("www.example.com.", None).unwrap(),
+            RecordType::A,
+        );
+        // the empty responses are errors, which are observed all the same
+        let lookup = |name_server: &mut NameServer<_, _>| {
+            let _ = futures_executor::block_on(
+                name_server
+                    .lookup(query.clone(), DnsRequestOptions::default())
+                    .first_answer(),
+            );
+        };
+
+        // nothing is observed without an observer
+        lookup(&mut name_server);
+
+        let observer = Arc::new(RecordingObserver::default());
+        slot.set(Some(observer.clone()));
+        lookup(&mut name_server);
+        assert_eq!(
+            *observer.observed.lock().unwrap(),
+            vec![(query.clone(), socket_addr)]
+        );
+
+        slot.set(None);
+        lookup(&mut name_server);
+        assert_eq!(observer.observed.lock().unwrap().len(), 1);
+    }
+
+    /// Times out on requests advertising a UDP payload larger than 1232, as if the fragmented responses were dropped
+    ///  on the way, and records the advertised payloads
+    #[derive(Clone, Default)]
+    struct FragmentDroppingHandle {
+        max_payloads: Arc<std::sync::Mutex<Vec<u16>>>,
+    }
+
+    impl DnsHandle for FragmentDroppingHandle {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ResolveError>> + Send>>;
+        type Error = ResolveError;
+
+        fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(
+            &mut self,
+            request: R,
+        ) -> Self::Response {
+            let request = request.into();
+            let max_payload = request.extensions().as_ref().map_or(512, Edns::max_payload);
+            self.max_payloads.lock().unwrap().push(max_payload);
+            if max_payload > 1232 {
+                return Box::pin(once(future::err(ResolveErrorKind::Timeout.into())));
+            }
+
+            let query = request.queries()[0].clone();
+            let mut response = proto::op::Message::new();
+            response.set_id(request.id());
+            response.add_answer(proto::rr::Record::from_rdata(
+                query.name().clone(),
+                300,
+                proto::rr::RData::A(Ipv4Addr::LOCALHOST),
+            ));
+            response.add_query(query);
+            Box::pin(once(future::ok(response.into())))
+        }
+    }
+
+    #[derive(Clone)]
+    struct FragmentDroppingProvider(FragmentDroppingHandle);
+
+    impl ConnectionProvider for FragmentDroppingProvider {
+        type Conn = FragmentDroppingHandle;
+        type FutureConn = future::Ready<Result<FragmentDroppingHandle, ResolveError>>;
+        type Time = proto::TokioTime;
+
+        fn new_connection(&self, _: &NameServerConfig, _: &ResolverOpts) -> Self::FutureConn {
+            future::ok(self.0.clone())
+        }
+    }
+
+    fn fragment_dropping_lookups(edns_udp_shrink: bool) -> (Vec<bool>, Vec<u16>) {
+        let config = NameServerConfig {
+            socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 53),
+            protocol: Protocol::Udp,
+            tls_dns_name: None,
+            trust_nx_responses: false,
+            #[cfg(feature = "dns-over-rustls")]
+            tls_config: None,
+            bind_addr: None,
+        };
+        let options = ResolverOpts {
+            edns_udp_shrink,
+            ..ResolverOpts::default()
+        };
+        let handle = FragmentDroppingHandle::default();
+        let mut name_server = NameServer::from_conn(
+            config,
+            options,
+            handle.clone(),
+            FragmentDroppingProvider(handle.clone()),
+        );
+
+        let mut request_options = DnsRequestOptions::default();
+        request_options.use_edns = true;
+        request_options.max_payload_len = 4096;
+
+        let query = Query::query(
+            Name::parse("www.example.com.", None).unwrap(),
+            RecordType::A,
+        );
+        let succeeded = (0..2)
+            .map(|_| {
+                futures_executor::block_on(
+                    name_server
+                        .lookup(query.clone(), request_options)
+                        .first_answer(),
+                )
+                .is_ok()
+            })
+            .collect();
+
+        let max_payloads = handle.max_payloads.lock().unwrap().clone();
+        (succeeded, max_payloads)
+    }
+
+    #[test]
+    fn test_edns_udp_shrink() {
+        // the timed out request is retried with the smaller payload, which is kept for the next request
+        assert_eq!(
+            fragment_dropping_lookups(true),
+            (vec![true, true], vec![4096, 1232, 1232])
+        );
+
+        assert_eq!(
+            fragment_dropping_lookups(false),
+            (vec![false, false], vec![4096, 4096])
+        );
+    }
 }
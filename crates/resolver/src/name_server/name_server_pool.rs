@@ -7,9 +7,10 @@
 
 use std::cmp::Ordering;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::sync::Arc;
 use std::task::{Context, Poll};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures_util::future::FutureExt;
 use futures_util::stream::{once, FuturesUnordered, Stream, StreamExt};
@@ -18,11 +19,11 @@ use smallvec::SmallVec;
 use proto::xfer::{DnsHandle, DnsRequest, DnsResponse, FirstAnswer};
 use proto::Time;
 
-use crate::config::{ResolverConfig, ResolverOpts};
+use crate::config::{ResolverConfig, ResolverOpts, ServerOrderingStrategy};
 use crate::error::{ResolveError, ResolveErrorKind};
 #[cfg(feature = "mdns")]
 use crate::name_server;
-use crate::name_server::{ConnectionProvider, NameServer};
+use crate::name_server::{ConnectionProvider, NameServer, ResponseObserverSlot};
 #[cfg(test)]
 #[cfg(feature = "tokio-runtime")]
 use crate::name_server::{TokioConnection, TokioConnectionProvider, TokioHandle};
@@ -39,8 +40,12 @@ pub struct NameServerPool<
     datagram_conns: Arc<[NameServer<C, P>]>, /* All NameServers must be the same type */
     stream_conns: Arc<[NameServer<C, P>]>,   /* All NameServers must be the same type */
     #[cfg(feature = "mdns")]
-    mdns_conns: NameServer<C, P>, /* All NameServers must be the same type */
+    mdns_conns: Arc<[NameServer<C, P>]>, /* All NameServers must be the same type */
     options: ResolverOpts,
+    /// The count of requests, for `ServerOrderingStrategy::RoundRobin`
+    rotation: Arc<AtomicUsize>,
+    /// The observer of responses, shared with the name servers
+    observer: Arc<ResponseObserverSlot>,
 }
 
 #[cfg(test)]
@@ -65,6 +70,8 @@ where
         options: &ResolverOpts,
         conn_provider: P,
     ) -> Self {
+        let observer = Arc::new(ResponseObserverSlot::default());
+
         let datagram_conns: Vec<NameServer<C, P>> = config
             .name_servers()
             .iter()
@@ -80,6 +87,7 @@ where
                 let ns_config = { ns_config.clone() };
 
                 NameServer::<C, P>::new_with_provider(ns_config, *options, conn_provider.clone())
+                    .with_response_observer(observer.clone())
             })
             .collect();
 
@@ -98,6 +106,7 @@ where
                 let ns_config = { ns_config.clone() };
 
                 NameServer::<C, P>::new_with_provider(ns_config, *options, conn_provider.clone())
+                    .with_response_observer(observer.clone())
             })
             .collect();
 
@@ -105,8 +114,10 @@ where
             datagram_conns: Arc::from(datagram_conns),
             stream_conns: Arc::from(stream_conns),
             #[cfg(feature = "mdns")]
-            mdns_conns: name_server::mdns_nameserver(*options, conn_provider.clone(), false),
+            mdns_conns: Arc::from(name_server::mdns_nameservers(*options, conn_provider)),
             options: *options,
+            rotation: Arc::new(AtomicUsize::new(0)),
+            observer,
         }
     }
 
@@ -117,10 +128,14 @@ where
         datagram_conns: Vec<NameServer<C, P>>,
         stream_conns: Vec<NameServer<C, P>>,
     ) -> Self {
+        let observer = Arc::new(ResponseObserverSlot::default());
+
         Self {
-            datagram_conns: Arc::from(datagram_conns),
-            stream_conns: Arc::from(stream_conns),
+            datagram_conns: share_observer(datagram_conns, &observer),
+            stream_conns: share_observer(stream_conns, &observer),
             options: *options,
+            rotation: Arc::new(AtomicUsize::new(0)),
+            observer,
         }
     }
 
@@ -130,13 +145,17 @@ where
         options: &ResolverOpts,
         datagram_conns: Vec<NameServer<C, P>>,
         stream_conns: Vec<NameServer<C, P>>,
-        mdns_conns: NameServer<C, P>,
+        mdns_conns: Vec<NameServer<C, P>>,
     ) -> Self {
+        let observer = Arc::new(ResponseObserverSlot::default());
+
         NameServerPool {
-            datagram_conns: Arc::from(datagram_conns),
-            stream_conns: Arc::from(stream_conns),
-            mdns_conns,
+            datagram_conns: share_observer(datagram_conns, &observer),
+            stream_conns: share_observer(stream_conns, &observer),
+            mdns_conns: Arc::from(mdns_conns),
             options: *options,
+            rotation: Arc::new(AtomicUsize::new(0)),
+            observer,
         }
     }
 
@@ -152,6 +171,8 @@ where
             datagram_conns,
             stream_conns,
             options: *options,
+            rotation: Arc::new(AtomicUsize::new(0)),
+            observer: Arc::default(),
         }
     }
 
@@ -161,28 +182,46 @@ where
         options: &ResolverOpts,
         datagram_conns: Arc<[NameServer<C, P>]>,
         stream_conns: Arc<[NameServer<C, P>]>,
-        mdns_conns: NameServer<C, P>,
+        mdns_conns: Arc<[NameServer<C, P>]>,
     ) -> Self {
         NameServerPool {
             datagram_conns,
             stream_conns,
             mdns_conns,
             options: *options,
-            conn_provider,
+            rotation: Arc::new(AtomicUsize::new(0)),
+            observer: Arc::default(),
         }
     }
 
+    /// The observer of the responses of all name servers of the pool
+    pub(crate) fn response_observer(&self) -> Arc<ResponseObserverSlot> {
+        Arc::clone(&self.observer)
+    }
+
     async fn try_send(
-        opts: ResolverOpts,
+        opts: &ResolverOpts,
         conns: Arc<[NameServer<C, P>]>,
         request: DnsRequest,
+        rotation: usize,
     ) -> Result<DnsResponse, ResolveError> {
         let mut conns: Vec<NameServer<C, P>> = conns.to_vec();
 
         // select the highest priority connection
-        //   reorder the connections based on current view...
-        //   this reorders the inner set
-        conns.sort_unstable();
+        match opts.server_ordering_strategy {
+            ServerOrderingStrategy::QueryStatistics => {
+                // the sort is stable, servers with equal statistics stay in the configured order
+                let now = Instant::now();
+                conns.sort_by_cached_key(|conn| conn.statistics_key(now));
+            }
+            ServerOrderingStrategy::UserProvidedOrder => {}
+            ServerOrderingStrategy::RoundRobin => {
+                if !conns.is_empty() {
+                    let first = rotation % conns.len();
+                    conns.rotate_left(first);
+                }
+            }
+        }
         let request_loop = request.clone();
 
         parallel_conn_loop(conns, request_loop, opts).await
@@ -200,6 +239,7 @@ where
     fn send<R: Into<DnsRequest>>(&mut self, request: R) -> Self::Response {
         let opts = self.options;
         let request = request.into();
+        let rotation = self.rotation.fetch_add(1, AtomicOrdering::Relaxed);
         let datagram_conns = Arc::clone(&self.datagram_conns);
         let stream_conns = Arc::clone(&self.stream_conns);
         // TODO: remove this clone, return the Message in the error?
@@ -207,7 +247,7 @@ where
 
         // if it's a .local. query, then we *only* query mDNS, these should never be sent on to upstream resolvers
         #[cfg(feature = "mdns")]
-        let mdns = mdns::maybe_local(&mut self.mdns_conns, request);
+        let mdns = mdns::maybe_local(Arc::clone(&self.mdns_conns), &opts, request);
 
         // TODO: limited to only when mDNS is enabled, but this should probably always be enforced?
         #[cfg(not(feature = "mdns"))]
@@ -226,7 +266,7 @@ where
             debug!("sending request: {:?}", request.queries());
 
             // First try the UDP connections
-            let udp_res = match Self::try_send(opts, datagram_conns, request).await {
+            let udp_res = match Self::try_send(&opts, datagram_conns, request, rotation).await {
                 Ok(response) if response.truncated() => {
                     debug!("truncated response received, retrying over TCP");
                     Ok(response)
@@ -245,7 +285,7 @@ where
 
             // Try query over TCP, as response to query over UDP was either truncated or was an
             // error.
-            let tcp_res = Self::try_send(opts, stream_conns, tcp_message).await;
+            let tcp_res = Self::try_send(&opts, stream_conns, tcp_message, rotation).await;
 
             let tcp_err = match tcp_res {
                 res @ Ok(..) => return res,
@@ -266,12 +306,27 @@ where
     }
 }
 
+/// Collects the name servers, sharing the observer of the pool with them
+fn share_observer<C, P>(
+    conns: Vec<NameServer<C, P>>,
+    observer: &Arc<ResponseObserverSlot>,
+) -> Arc<[NameServer<C, P>]>
+where
+    C: DnsHandle<Error = ResolveError> + 'static,
+    P: ConnectionProvider<Conn = C> + 'static,
+{
+    conns
+        .into_iter()
+        .map(|conn| conn.with_response_observer(Arc::clone(observer)))
+        .collect()
+}
+
 // TODO: we should be able to have a self-referential future here with Pin and not require cloned conns
 /// An async function that will loop over all the conns with a max parallel request count of ops.num_concurrent_req
 async fn parallel_conn_loop<C, P>(
     mut conns: Vec<NameServer<C, P>>,
     request: DnsRequest,
-    opts: ResolverOpts,
+    opts: &ResolverOpts,
 ) -> Result<DnsResponse, ResolveError>
 where
     C: DnsHandle<Error = ResolveError> + 'static,
@@ -328,6 +383,20 @@ where
 
             match e.kind() {
                 ResolveErrorKind::NoRecordsFound { trusted, .. } if *trusted => {
+                    // a slower name server might still have a positive answer
+                    if let Some(grace) = opts.negative_grace_period {
+                        let success = async move {
+                            while let Some(result) = requests.next().await {
+                                if let Ok(sent) = result {
+                                    return Some(sent);
+                                }
+                            }
+                            None
+                        };
+                        if let Ok(Some(sent)) = P::Time::timeout(grace, success).await {
+                            return Ok(sent);
+                        }
+                    }
                     return Err(e);
                 }
                 ResolveErrorKind::Proto(e) if e.is_busy() => {
@@ -346,29 +415,104 @@ where
 mod mdns {
     use super::*;
 
+    use proto::op::Message;
     use proto::rr::domain::usage;
+    use proto::rr::Record;
     use proto::DnsHandle;
 
-    /// Returns true
+    /// The longest time that responses to a one-shot query are collected
+    const RESPONSE_WINDOW: Duration = Duration::from_secs(1);
+
+    /// Returns a Local::ResolveStream if mDNS is enabled and the request is for a `.local.` name
     pub(crate) fn maybe_local<C, P>(
-        name_server: &mut NameServer<C, P>,
-        request: DnsRequest,
+        name_servers: Arc<[NameServer<C, P>]>,
+        options: &ResolverOpts,
+        mut request: DnsRequest,
     ) -> Local
     where
         C: DnsHandle<Error = ResolveError> + 'static,
         P: ConnectionProvider<Conn = C> + 'static,
         P: ConnectionProvider,
     {
-        if request
-            .queries()
-            .iter()
-            .any(|query| usage::LOCAL.name().zone_of(query.name()))
+        if options.use_mdns
+            && request
+                .queries()
+                .iter()
+                .any(|query| usage::LOCAL.name().zone_of(query.name()))
         {
-            Local::ResolveStream(name_server.send(request))
+            // the one-shot query is not sent from port 5353, ask for unicast responses, RFC 6762 section 5.4
+            for query in request.queries_mut() {
+                query.set_mdns_unicast_response(true);
+            }
+
+            let window = options.timeout.min(RESPONSE_WINDOW);
+            Local::ResolveStream(Box::pin(once(send(name_servers, request, window))))
         } else {
             Local::NotMdns(request)
         }
     }
+
+    /// Sends the request to all of the multicast groups, the answers of all responders are merged
+    async fn send<C, P>(
+        name_servers: Arc<[NameServer<C, P>]>,
+        request: DnsRequest,
+        window: Duration,
+    ) -> Result<DnsResponse, ResolveError>
+    where
+        C: DnsHandle<Error = ResolveError> + 'static,
+        P: ConnectionProvider<Conn = C> + 'static,
+    {
+        let results = futures_util::future::join_all(
+            name_servers
+                .iter()
+                .cloned()
+                .map(|name_server| name_server.collect_responses(request.clone(), window)),
+        )
+        .await;
+
+        let mut merged: Option<Message> = None;
+        let mut error = None;
+        for result in results {
+            let responses = match result {
+                Ok(responses) => responses,
+                Err(e) => {
+                    debug!("mdns query failed: {}", e);
+                    error = Some(e);
+                    continue;
+                }
+            };
+
+            for response in responses {
+                let response = response.into_inner();
+                match merged {
+                    Some(ref mut merged) => merge(merged, response),
+                    None => merged = Some(response),
+                }
+            }
+        }
+
+        match merged {
+            Some(merged) => Ok(merged.into()),
+            None => Err(error.unwrap_or_else(|| ResolveErrorKind::Timeout.into())),
+        }
+    }
+
+    /// Adds the records of the response which are not already in the merged message
+    fn merge(merged: &mut Message, mut response: Message) {
+        fn add(merged: &[Record], records: Vec<Record>) -> Vec<Record> {
+            records
+                .into_iter()
+                .filter(|record| !merged.contains(record))
+                .collect()
+        }
+
+        let answers = add(merged.answers(), response.take_answers());
+        merged.add_answers(answers);
+        let name_servers = add(merged.name_servers(), response.take_name_servers());
+        merged.add_name_servers(name_servers);
+        let additionals = add(merged.additionals(), response.take_additionals());
+        merged.add_additionals(additionals);
+    }
 }
 
 pub(crate) enum Local {
@@ -537,7 +681,10 @@ mod tests {
             Arc::from([]),
             Arc::clone(&name_servers),
             #[cfg(feature = "mdns")]
-            name_server::mdns_nameserver(opts, TokioConnectionProvider::new(TokioHandle)),
+            Arc::from(name_server::mdns_nameservers(
+                opts,
+                TokioConnectionProvider::new(TokioHandle),
+            )),
         );
 
         let name = Name::from_str("www.example.com.").unwrap();
@@ -590,4 +737,227 @@ mod tests {
             "if this is failing then the NameServers aren't being properly shared."
         );
     }
+
+    /// Answers every request with an empty message
+    #[derive(Clone)]
+    struct EmptyHandle;
+
+    impl DnsHandle for EmptyHandle {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ResolveError>> + Send>>;
+        type Error = ResolveError;
+
+        fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(
+            &mut self,
+            request: R,
+        ) -> Self::Response {
+            let mut response = proto::op::Message::new();
+            response.set_id(request.into().id());
+            Box::pin(once(futures_util::future::ok(response.into())))
+        }
+    }
+
+    /// Records the configuration of every connection
+    #[derive(Clone, Default)]
+    struct RecordingProvider {
+        connected: Arc<std::sync::Mutex<Vec<NameServerConfig>>>,
+    }
+
+    impl ConnectionProvider for RecordingProvider {
+        type Conn = EmptyHandle;
+        type FutureConn = futures_util::future::Ready<Result<EmptyHandle, ResolveError>>;
+        type Time = proto::TokioTime;
+
+        fn new_connection(&self, config: &NameServerConfig, _: &ResolverOpts) -> Self::FutureConn {
+            self.connected.lock().unwrap().push(config.clone());
+            futures_util::future::ok(EmptyHandle)
+        }
+    }
+
+    #[test]
+    fn test_bootstrap_addrs() {
+        let primary = IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1));
+        let bootstrap = [
+            IpAddr::V4(Ipv4Addr::new(192, 0, 2, 2)),
+            IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+        ];
+        let config = |ip| NameServerConfig {
+            socket_addr: SocketAddr::new(ip, 853),
+            protocol: Protocol::Tcp,
+            tls_dns_name: Some("dns.example".to_string()),
+            trust_nx_responses: false,
+            #[cfg(feature = "dns-over-rustls")]
+            tls_config: None,
+            bind_addr: None,
+        };
+
+        // the server is configured by its name, and each of its addresses
+        let name_servers = Some(primary)
+            .iter()
+            .chain(&bootstrap)
+            .map(|ip| config(*ip))
+            .collect::<Vec<_>>();
+        let resolver_config = ResolverConfig::from_parts(None, vec![], name_servers);
+
+        let opts = ResolverOpts {
+            num_concurrent_reqs: 3,
+            server_ordering_strategy: ServerOrderingStrategy::UserProvidedOrder,
+            ..ResolverOpts::default()
+        };
+        let provider = RecordingProvider::default();
+        let mut pool =
+            NameServerPool::from_config_with_provider(&resolver_config, &opts, provider.clone());
+
+        // the empty response is an error, so that all of the addresses are tried
+        let name = Name::from_str("www.example.com.").unwrap();
+        let _ = futures_executor::block_on(
+            pool.lookup(
+                Query::query(name, RecordType::A),
+                DnsRequestOptions::default(),
+            )
+            .first_answer(),
+        );
+
+        // only the configured addresses are connected to, dns.example is never looked up
+        let mut connected = provider
+            .connected
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|config| {
+                assert_eq!(config.tls_dns_name.as_deref(), Some("dns.example"));
+                config.socket_addr
+            })
+            .collect::<Vec<_>>();
+        connected.sort();
+
+        let mut expected = Some(primary)
+            .iter()
+            .chain(&bootstrap)
+            .map(|ip| SocketAddr::new(*ip, 853))
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        assert_eq!(connected, expected);
+    }
+
+    /// Answers with an A record from the server on port 1, after a delay, and with NXDOMAIN from all others
+    #[derive(Clone)]
+    struct DivergentHandle {
+        positive: bool,
+    }
+
+    impl DnsHandle for DivergentHandle {
+        type Response = Pin<Box<dyn Stream<Item = Result<DnsResponse, ResolveError>> + Send>>;
+        type Error = ResolveError;
+
+        fn send<R: Into<DnsRequest> + Unpin + Send + 'static>(
+            &mut self,
+            request: R,
+        ) -> Self::Response {
+            let request = request.into();
+            let mut response = proto::op::Message::new();
+            response
+                .set_id(request.id())
+                .set_message_type(proto::op::MessageType::Response)
+                .add_queries(request.queries().to_vec());
+
+            let positive = self.positive;
+            if positive {
+                let name = request.queries()[0].name().clone();
+                response.add_answer(proto::rr::Record::from_rdata(
+                    name,
+                    300,
+                    RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+                ));
+            } else {
+                response.set_response_code(proto::op::ResponseCode::NXDomain);
+            }
+
+            Box::pin(once(async move {
+                if positive {
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                Ok(response.into())
+            }))
+        }
+    }
+
+    #[derive(Clone)]
+    struct DivergentProvider;
+
+    impl ConnectionProvider for DivergentProvider {
+        type Conn = DivergentHandle;
+        type FutureConn = futures_util::future::Ready<Result<DivergentHandle, ResolveError>>;
+        type Time = proto::TokioTime;
+
+        fn new_connection(&self, config: &NameServerConfig, _: &ResolverOpts) -> Self::FutureConn {
+            futures_util::future::ok(DivergentHandle {
+                positive: config.socket_addr.port() == 1,
+            })
+        }
+    }
+
+    #[test]
+    fn test_negative_grace_period() {
+        let mut resolver_config = ResolverConfig::new();
+        for port in &[2, 1] {
+            resolver_config.add_name_server(NameServerConfig {
+                socket_addr: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 0, 2, 53)), *port),
+                protocol: Protocol::Udp,
+                tls_dns_name: None,
+                trust_nx_responses: true,
+                #[cfg(feature = "dns-over-rustls")]
+                tls_config: None,
+                bind_addr: None,
+            });
+        }
+
+        let lookup = |opts: ResolverOpts| {
+            let mut pool = NameServerPool::from_config_with_provider(
+                &resolver_config,
+                &opts,
+                DivergentProvider,
+            );
+            let name = Name::from_str("www.example.com.").unwrap();
+            Runtime::new().unwrap().block_on(
+                pool.lookup(
+                    Query::query(name, RecordType::A),
+                    DnsRequestOptions::default(),
+                )
+                .first_answer(),
+            )
+        };
+
+        let opts = ResolverOpts {
+            num_concurrent_reqs: usize::MAX,
+            server_ordering_strategy: ServerOrderingStrategy::UserProvidedOrder,
+            ..ResolverOpts::default()
+        };
+
+        // without a grace period the fast NXDOMAIN wins
+        match lookup(opts).unwrap_err().kind() {
+            ResolveErrorKind::NoRecordsFound { response_code, .. } => {
+                assert_eq!(*response_code, proto::op::ResponseCode::NXDomain)
+            }
+            kind => panic!("unexpected error: {:?}", kind),
+        }
+
+        // the slower positive answer arrives within the grace period
+        let response = lookup(ResolverOpts {
+            negative_grace_period: Some(Duration::from_secs(5)),
+            ..opts
+        })
+        .expect("positive answer is preferred");
+        assert_eq!(
+            *response.answers()[0].data().and_then(RData::as_a).unwrap(),
+            Ipv4Addr::new(192, 0, 2, 1)
+        );
+
+        // but not if it arrives after the grace period
+        assert!(lookup(ResolverOpts {
+            negative_grace_period: Some(Duration::from_millis(10)),
+            ..opts
+        })
+        .is_err());
+    }
 }
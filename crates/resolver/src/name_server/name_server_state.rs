@@ -6,7 +6,7 @@
 // copied, modified, or distributed except according to those terms.
 
 use std::cmp::Ordering;
-use std::sync::atomic::{self, AtomicU8};
+use std::sync::atomic::{self, AtomicU16, AtomicU8};
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -16,6 +16,7 @@ use proto::op::Edns;
 pub(crate) struct NameServerState {
     conn_state: AtomicU8,
     remote_edns: Mutex<Arc<Option<Edns>>>,
+    max_payload: AtomicU16,
 }
 
 /// State of a connection with a remote NameServer.
@@ -70,6 +71,7 @@ impl NameServerState {
         Self {
             conn_state: AtomicU8::new(NameServerStateInner::Init.into()),
             remote_edns: Mutex::new(Arc::new(None)),
+            max_payload: AtomicU16::new(u16::MAX),
         }
     }
 
@@ -107,6 +109,17 @@ impl NameServerState {
         self.store(NameServerStateInner::Failed);
     }
 
+    /// The largest UDP payload size to advertise to the remote, unlimited unless it was shrunk
+    pub(crate) fn max_payload(&self) -> u16 {
+        self.max_payload.load(atomic::Ordering::Acquire)
+    }
+
+    /// Limits the UDP payload size advertised to the remote from now on, the limit is never raised again
+    pub(crate) fn shrink_max_payload(&self, max_payload: u16) {
+        self.max_payload
+            .fetch_min(max_payload, atomic::Ordering::AcqRel);
+    }
+
     /// True if this is in the Failed state
     pub(crate) fn is_failed(&self) -> bool {
         NameServerStateInner::Failed == self.load()
@@ -6,13 +6,22 @@
 // copied, modified, or distributed except according to those terms.
 
 use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::sync::atomic::{self, AtomicU32, AtomicUsize};
+use std::time::{Duration, Instant};
 
-use std::sync::atomic::{self, AtomicUsize};
+use parking_lot::Mutex;
+
+/// How long a name server is demoted after a failure
+pub(crate) const FAILURE_COOLDOWN: Duration = Duration::from_secs(5);
 
 pub(crate) struct NameServerStats {
     successes: AtomicUsize,
     failures: AtomicUsize,
-    // TODO: incorporate latency
+    /// Smoothed round trip time in microseconds, 0 until the first success
+    srtt_micros: AtomicU32,
+    /// The time of the last failure, cleared by a success
+    last_failure: Mutex<Option<Instant>>,
 }
 
 impl Default for NameServerStats {
@@ -26,15 +35,50 @@ impl NameServerStats {
         Self {
             successes: AtomicUsize::new(successes),
             failures: AtomicUsize::new(failures),
+            srtt_micros: AtomicU32::new(0),
+            last_failure: Mutex::new(None),
         }
     }
 
-    pub(crate) fn next_success(&self) {
+    /// Records a response received after `rtt`
+    pub(crate) fn next_success(&self, rtt: Duration) {
         self.successes.fetch_add(1, atomic::Ordering::Release);
+
+        // the same weighting as the smoothed round trip time of TCP, RFC 6298
+        let rtt = u32::try_from(rtt.as_micros()).unwrap_or(u32::MAX).max(1);
+        self.srtt_micros
+            .fetch_update(
+                atomic::Ordering::AcqRel,
+                atomic::Ordering::Acquire,
+                |srtt| {
+                    if srtt == 0 {
+                        Some(rtt)
+                    } else {
+                        Some(srtt - srtt / 8 + rtt / 8)
+                    }
+                },
+            )
+            .ok();
+
+        *self.last_failure.lock() = None;
     }
 
-    pub(crate) fn next_failure(&self) {
+    /// Records a failure, i.e. a timeout or a broken connection, at `now`
+    pub(crate) fn next_failure(&self, now: Instant) {
         self.failures.fetch_add(1, atomic::Ordering::Release);
+
+        *self.last_failure.lock() = Some(now);
+    }
+
+    /// The smoothed round trip time, zero if the name server has not answered yet
+    pub(crate) fn srtt(&self) -> Duration {
+        Duration::from_micros(u64::from(self.srtt_micros.load(atomic::Ordering::Acquire)))
+    }
+
+    /// Returns true if the name server failed within the `FAILURE_COOLDOWN` before `now`
+    pub(crate) fn is_cooling_down(&self, now: Instant) -> bool {
+        let last_failure = *self.last_failure.lock();
+        matches!(last_failure, Some(failed) if now.saturating_duration_since(failed) < FAILURE_COOLDOWN)
     }
 
     fn noload_eq(
@@ -127,4 +171,32 @@ mod tests {
         assert_eq!(nil.cmp(&successes), Ordering::Greater);
         assert_eq!(successes.cmp(&failures), Ordering::Greater);
     }
+
+    #[test]
+    fn test_srtt() {
+        let stats = NameServerStats::default();
+        assert_eq!(stats.srtt(), Duration::from_micros(0));
+
+        stats.next_success(Duration::from_millis(80));
+        assert_eq!(stats.srtt(), Duration::from_millis(80));
+
+        stats.next_success(Duration::from_millis(0));
+        assert_eq!(stats.srtt(), Duration::from_millis(70));
+    }
+
+    #[test]
+    fn test_cooldown() {
+        let stats = NameServerStats::default();
+        let now = Instant::now();
+        assert!(!stats.is_cooling_down(now));
+
+        stats.next_failure(now);
+        assert!(stats.is_cooling_down(now));
+        assert!(stats.is_cooling_down(now + FAILURE_COOLDOWN - Duration::from_millis(1)));
+        assert!(!stats.is_cooling_down(now + FAILURE_COOLDOWN));
+
+        // a success ends the cooldown
+        stats.next_success(Duration::from_millis(10));
+        assert!(!stats.is_cooling_down(now));
+    }
 }
@@ -0,0 +1,51 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+
+use proto::op::Query;
+use proto::xfer::DnsResponse;
+
+use crate::config::NameServerConfig;
+
+/// Observes every response received from a name server, see [`crate::AsyncResolver::set_response_observer`]
+///
+/// Only responses from the network are observed, answers from the cache or the hosts file never reach a name server.
+pub trait ResponseObserver: Send + Sync {
+    /// Called for each response, before it is checked for errors, i.e. also for `NXDomain` or `ServFail`
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - the query sent to the name server
+    /// * `name_server` - the configuration of the name server which answered
+    /// * `response` - the response as received
+    /// * `rtt` - the time from sending the request to receiving the response
+    fn on_response(
+        &self,
+        query: &Query,
+        name_server: &NameServerConfig,
+        response: &DnsResponse,
+        rtt: Duration,
+    );
+}
+
+/// The observer shared by all name servers of a pool, which can be replaced at any time
+#[derive(Default)]
+pub(crate) struct ResponseObserverSlot(RwLock<Option<Arc<dyn ResponseObserver>>>);
+
+impl ResponseObserverSlot {
+    pub(crate) fn get(&self) -> Option<Arc<dyn ResponseObserver>> {
+        self.0.read().clone()
+    }
+
+    pub(crate) fn set(&self, observer: Option<Arc<dyn ResponseObserver>>) {
+        *self.0.write() = observer;
+    }
+}
@@ -6,22 +6,23 @@
 // copied, modified, or distributed except according to those terms.
 
 //! Structs for creating and using a Resolver
-use std::io;
+use std::io::{self, Read, Write};
 use std::net::IpAddr;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 use proto::rr::domain::TryParseIp;
 use proto::rr::IntoName;
-use proto::rr::RecordType;
+use proto::rr::{DNSClass, RecordType};
 use tokio::runtime::{self, Runtime};
 use trust_dns_proto::xfer::DnsRequestOptions;
 
 use crate::config::{ResolverConfig, ResolverOpts};
+use crate::dns_lru::{CacheEvent, CacheStats};
 use crate::error::*;
 use crate::lookup;
 use crate::lookup::Lookup;
 use crate::lookup_ip::LookupIp;
-use crate::name_server::{TokioConnection, TokioConnectionProvider, TokioHandle};
+use crate::name_server::{ResponseObserver, TokioConnection, TokioConnectionProvider, TokioHandle};
 use crate::AsyncResolver;
 
 /// The Resolver is used for performing DNS queries.
@@ -122,6 +123,46 @@ impl Resolver {
         Ok(())
     }
 
+    /// Returns the hit, miss, insert and eviction counters and the number of entries of the cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.async_resolver.cache_stats()
+    }
+
+    /// Sets a listener which is called for every insertion into and eviction from the cache, see
+    ///  [`AsyncResolver::set_cache_listener`]
+    pub fn set_cache_listener<F>(&self, listener: F)
+    where
+        F: Fn(CacheEvent<'_>) + Send + Sync + 'static,
+    {
+        self.async_resolver.set_cache_listener(listener)
+    }
+
+    /// Removes the listener set with [`Self::set_cache_listener`]
+    pub fn clear_cache_listener(&self) {
+        self.async_resolver.clear_cache_listener()
+    }
+
+    /// Writes the current entries of the cache to the writer, see [`AsyncResolver::dump_cache`]
+    pub fn dump_cache<W: Write>(&self, writer: W) -> ResolveResult<usize> {
+        self.async_resolver.dump_cache(writer)
+    }
+
+    /// Loads the entries written by [`Self::dump_cache`], see [`AsyncResolver::load_cache`]
+    pub fn load_cache<R: Read>(&self, reader: R) -> ResolveResult<usize> {
+        self.async_resolver.load_cache(reader)
+    }
+
+    /// Sets an observer which is called for every response received from a name server, see
+    ///  [`AsyncResolver::set_response_observer`]
+    pub fn set_response_observer(&self, observer: Arc<dyn ResponseObserver>) {
+        self.async_resolver.set_response_observer(observer)
+    }
+
+    /// Removes the observer set with [`Self::set_response_observer`]
+    pub fn clear_response_observer(&self) {
+        self.async_resolver.clear_response_observer()
+    }
+
     /// Generic lookup for any RecordType
     ///
     /// *WARNING* This interface may change in the future, please use [`Self::lookup_ip`] or another variant for more stable interfaces.
@@ -137,6 +178,28 @@ impl Resolver {
         self.runtime.lock()?.block_on(lookup)
     }
 
+    /// Generic lookup for any RecordType in any DNSClass, e.g. `CH` for the `version.bind.` of a name server
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the record to lookup, if name is not a valid domain name, an error will be returned
+    /// * `query_class` - class of the records to lookup
+    /// * `record_type` - type of record to lookup
+    pub fn lookup_with_class<N: IntoName>(
+        &self,
+        name: N,
+        query_class: DNSClass,
+        record_type: RecordType,
+    ) -> ResolveResult<Lookup> {
+        let lookup = self.async_resolver.lookup_with_class(
+            name,
+            query_class,
+            record_type,
+            DnsRequestOptions::default(),
+        );
+        self.runtime.lock()?.block_on(lookup)
+    }
+
     /// Performs a dual-stack DNS lookup for the IP for the given hostname.
     ///
     /// See the configuration and options parameters for controlling the way in which A(Ipv4) and AAAA(Ipv6) lookups will be performed. For the least expensive query a fully-qualified-domain-name, FQDN, which ends in a final `.`, e.g. `www.example.com.`, will only issue one query. Anything else will always incur the cost of querying the `ResolverConfig::domain` and `ResolverConfig::search`.
@@ -149,6 +212,23 @@ impl Resolver {
         self.runtime.lock()?.block_on(lookup)
     }
 
+    /// Looks up the addresses of a service, see [`AsyncResolver::lookup_service`]
+    ///
+    /// # Arguments
+    ///
+    /// * `service` - the symbolic name of the service, without the leading `_`, e.g. `xmpp-server`
+    /// * `proto` - the protocol of the service, without the leading `_`, e.g. `tcp`
+    /// * `name` - the domain of the service
+    pub fn lookup_service<N: IntoName>(
+        &self,
+        service: &str,
+        proto: &str,
+        name: N,
+    ) -> ResolveResult<lookup::ServiceLookup> {
+        let lookup = self.async_resolver.lookup_service(service, proto, name);
+        self.runtime.lock()?.block_on(lookup)
+    }
+
     lookup_fn!(reverse_lookup, lookup::ReverseLookup, IpAddr);
     lookup_fn!(ipv4_lookup, lookup::Ipv4Lookup);
     lookup_fn!(ipv6_lookup, lookup::Ipv6Lookup);
@@ -158,6 +238,8 @@ impl Resolver {
     lookup_fn!(srv_lookup, lookup::SrvLookup);
     lookup_fn!(tlsa_lookup, lookup::TlsaLookup);
     lookup_fn!(txt_lookup, lookup::TxtLookup);
+    lookup_fn!(uri_lookup, lookup::UriLookup);
+    lookup_fn!(https_lookup, lookup::SvcbLookup);
 }
 
 #[cfg(test)]
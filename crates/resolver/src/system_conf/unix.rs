@@ -100,6 +100,12 @@ fn into_resolver_config(
         ndots: parsed_config.ndots as usize,
         timeout: Duration::from_secs(u64::from(parsed_config.timeout)),
         attempts: parsed_config.attempts as usize,
+        // `options rotate` spreads the queries across the name servers, not the addresses of the answers
+        server_ordering_strategy: if parsed_config.rotate {
+            ServerOrderingStrategy::RoundRobin
+        } else {
+            ServerOrderingStrategy::default()
+        },
         ..ResolverOpts::default()
     };
 
@@ -186,6 +192,16 @@ mod tests {
         assert_eq!(ResolverOpts::default(), parsed.1);
     }
 
+    #[test]
+    fn test_rotate() {
+        let parsed = parse_resolv_conf("options rotate").expect("failed");
+        assert_eq!(
+            parsed.1.server_ordering_strategy,
+            ServerOrderingStrategy::RoundRobin
+        );
+        assert!(!parsed.1.rotate);
+    }
+
     #[test]
     fn test_read_resolv_conf() {
         read_resolv_conf(format!("{}/resolv.conf-simple", tests_dir())).expect("simple failed");
@@ -7,27 +7,78 @@
 
 //! System configuration loading for windows
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
 use std::str::FromStr;
 
 use ipconfig::computer::{get_domain, get_search_list, is_round_robin_enabled};
-use ipconfig::get_adapters;
+use ipconfig::{get_adapters, Adapter, IfType, OperStatus};
+use winreg::enums::{HKEY_LOCAL_MACHINE, KEY_READ};
+use winreg::RegKey;
 
 use proto::rr::Name;
 
 use crate::config::{NameServerConfig, Protocol, ResolverConfig, ResolverOpts};
 use crate::error::*;
 
-/// Returns the name servers of the computer (of all adapters)
-fn get_name_servers() -> ResolveResult<Vec<NameServerConfig>> {
-    let adapters = get_adapters()?;
-    let mut name_servers = vec![];
+const TCPIP_INTERFACES_KEY_PATH: &str =
+    "SYSTEM\\CurrentControlSet\\Services\\Tcpip\\Parameters\\Interfaces";
 
-    for dns_server in adapters
-        .iter()
-        .flat_map(|adapter| adapter.dns_servers().iter())
-    {
-        let socket_addr = SocketAddr::new(*dns_server, 53);
+/// Returns the adapters which are up, in the order in which the system queries their name servers
+///
+/// Windows prefers the adapters with the lowest interface metric, the loopback adapter has no name servers.
+fn get_active_adapters() -> ResolveResult<Vec<Adapter>> {
+    let mut adapters: Vec<Adapter> = get_adapters()?
+        .into_iter()
+        .filter(|adapter| adapter.oper_status() == OperStatus::IfOperStatusUp)
+        .filter(|adapter| adapter.if_type() != IfType::SoftwareLoopback)
+        .collect();
+
+    adapters.sort_by_key(|adapter| adapter.ipv4_metric().min(adapter.ipv6_metric()));
+    Ok(adapters)
+}
+
+/// Returns the address of the name server on the adapter, none for the placeholders of unconfigured IPv6 name servers
+fn name_server_addr(adapter: &Adapter, dns_server: IpAddr) -> Option<SocketAddr> {
+    match dns_server {
+        IpAddr::V4(ip) => Some(SocketAddr::new(IpAddr::V4(ip), 53)),
+        IpAddr::V6(ip) => {
+            // the deprecated site-local addresses are reported by Windows when no IPv6 name server is configured
+            let segments = ip.segments();
+            if segments[..7] == [0xfec0, 0, 0, 0xffff, 0, 0, 0] && (1..=3).contains(&segments[7]) {
+                return None;
+            }
+
+            // link-local addresses are only reachable through the adapter
+            let scope_id = if is_unicast_link_local(&ip) {
+                adapter.ipv6_if_index()
+            } else {
+                0
+            };
+            Some(SocketAddr::V6(SocketAddrV6::new(ip, 53, 0, scope_id)))
+        }
+    }
+}
+
+fn is_unicast_link_local(ip: &Ipv6Addr) -> bool {
+    ip.segments()[0] & 0xffc0 == 0xfe80
+}
+
+/// Returns the name servers of the computer (of all active adapters)
+fn get_name_servers(adapters: &[Adapter]) -> Vec<NameServerConfig> {
+    let mut socket_addrs: Vec<SocketAddr> = vec![];
+    for adapter in adapters {
+        for dns_server in adapter.dns_servers() {
+            match name_server_addr(adapter, *dns_server) {
+                Some(socket_addr) if !socket_addrs.contains(&socket_addr) => {
+                    socket_addrs.push(socket_addr)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    let mut name_servers = vec![];
+    for socket_addr in socket_addrs {
         name_servers.push(NameServerConfig {
             socket_addr,
             protocol: Protocol::Udp,
@@ -47,23 +98,68 @@ fn get_name_servers() -> ResolveResult<Vec<NameServerConfig>> {
             bind_addr: None,
         });
     }
-    Ok(name_servers)
+    name_servers
 }
 
-pub fn read_system_conf() -> ResolveResult<(ResolverConfig, ResolverOpts)> {
-    let name_servers = get_name_servers()?;
+/// Returns the connection-specific DNS suffix of the adapter, if any
+///
+/// A configured suffix takes precedence over the one assigned by DHCP.
+fn get_connection_suffix(adapter: &Adapter) -> Option<String> {
+    let hklm = RegKey::predef(HKEY_LOCAL_MACHINE);
+    let interface = hklm
+        .open_subkey_with_flags(
+            format!("{}\\{}", TCPIP_INTERFACES_KEY_PATH, adapter.adapter_name()),
+            KEY_READ,
+        )
+        .ok()?;
 
-    let search_list: Vec<Name> = get_search_list()?
+    ["Domain", "DhcpDomain"]
         .iter()
-        .map(|x| Name::from_str(x))
-        .collect::<Result<Vec<_>, _>>()?;
+        .filter_map(|value_name| interface.get_value::<String, _>(value_name).ok())
+        .map(|suffix| suffix.trim().to_string())
+        .find(|suffix| !suffix.is_empty())
+}
 
-    let domain = match get_domain()? {
-        Some(domain) => Name::from_str(&domain)?,
-        None => Name::root(),
+/// Returns the names to search, like the system does
+///
+/// A configured search list replaces all other suffixes, otherwise the primary DNS suffix of the computer is
+///  searched, followed by the connection-specific suffixes of the adapters.
+fn get_search_names(domain: Option<&Name>, adapters: &[Adapter]) -> ResolveResult<Vec<Name>> {
+    let search_list = get_search_list()?;
+    let suffixes: Vec<String> = if search_list.iter().any(|suffix| !suffix.trim().is_empty()) {
+        search_list
+    } else {
+        adapters.iter().filter_map(get_connection_suffix).collect()
     };
 
-    let config = ResolverConfig::from_parts(Some(domain), search_list, name_servers);
+    let mut search_names: Vec<Name> = domain.into_iter().cloned().collect();
+    for suffix in suffixes.iter().map(|suffix| suffix.trim()) {
+        if suffix.is_empty() {
+            continue;
+        }
+
+        let name = Name::from_str(suffix)?;
+        if !search_names.contains(&name) {
+            search_names.push(name);
+        }
+    }
+    Ok(search_names)
+}
+
+pub fn read_system_conf() -> ResolveResult<(ResolverConfig, ResolverOpts)> {
+    let adapters = get_active_adapters()?;
+    let name_servers = get_name_servers(&adapters);
+
+    let domain = get_domain()?
+        .map(|domain| Name::from_str(&domain))
+        .transpose()?;
+    let search_list = get_search_names(domain.as_ref(), &adapters)?;
+
+    let config = ResolverConfig::from_parts(
+        Some(domain.unwrap_or_else(Name::root)),
+        search_list,
+        name_servers,
+    );
 
     let rotate = is_round_robin_enabled()?;
 
@@ -73,3 +169,14 @@ pub fn read_system_conf() -> ResolveResult<(ResolverConfig, ResolverOpts)> {
     };
     Ok((config, opts))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_system_conf() {
+        let (config, _) = read_system_conf().expect("failed to read the system configuration");
+        assert!(!config.name_servers().is_empty());
+    }
+}
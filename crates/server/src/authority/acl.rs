@@ -0,0 +1,102 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Access control lists for the queries and transfers of a zone
+
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+
+use crate::client::rr::{LowerName, RecordType};
+
+/// The clients which may query and transfer a zone, see `Catalog::set_acl`
+///
+/// The lists are not exclusive of each other, a client which may transfer a zone does not need to be allowed to
+///  query it. Transfers are also subject to the zone allowing AXFR at all.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ZoneAcl {
+    /// The prefixes of the clients which may query the zone, all clients if empty
+    pub allow_query: Vec<IpNet>,
+    /// The prefixes of the clients which may transfer the zone with AXFR or IXFR
+    ///
+    /// All clients may transfer the zone if both this and `allow_transfer_keys` are empty.
+    pub allow_transfer: Vec<IpNet>,
+    /// The names of the TSIG keys which may transfer the zone, from any address
+    ///
+    /// A transfer request signed with one of these keys is allowed, in addition to the clients of
+    ///  `allow_transfer`. The keys must be known to the `Catalog`, see `Catalog::add_tsig_key`.
+    pub allow_transfer_keys: Vec<LowerName>,
+}
+
+impl ZoneAcl {
+    /// Returns true if the query is allowed from the client
+    ///
+    /// # Arguments
+    ///
+    /// * `query_type` - the type of the query, AXFR and IXFR are checked against the transfer ACL
+    /// * `client` - the address of the client
+    /// * `key` - the name of the TSIG key the request was verified with, if any
+    pub fn allows(&self, query_type: RecordType, client: IpAddr, key: Option<&LowerName>) -> bool {
+        let client = crate::server::unmap_ipv4(client);
+        let contains = |acl: &[IpNet]| acl.iter().any(|net| net.contains(&client));
+
+        match query_type {
+            RecordType::AXFR | RecordType::IXFR => {
+                (self.allow_transfer.is_empty() && self.allow_transfer_keys.is_empty())
+                    || contains(&self.allow_transfer)
+                    || matches!(key, Some(key) if self.allow_transfer_keys.contains(key))
+            }
+            _ => self.allow_query.is_empty() || contains(&self.allow_query),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::client::rr::Name;
+
+    #[test]
+    fn test_allows() {
+        let key = LowerName::from(Name::from_str("transfer.example.com.").unwrap());
+        let other_key = LowerName::from(Name::from_str("other.example.com.").unwrap());
+        let inside = IpAddr::from([192, 0, 2, 1]);
+        let mapped = IpAddr::from_str("::ffff:192.0.2.1").unwrap();
+        let outside = IpAddr::from([198, 51, 100, 1]);
+
+        let acl = ZoneAcl::default();
+        assert!(acl.allows(RecordType::A, outside, None));
+        assert!(acl.allows(RecordType::AXFR, outside, None));
+
+        let acl = ZoneAcl {
+            allow_query: vec!["192.0.2.0/24".parse().unwrap()],
+            allow_transfer: vec!["192.0.2.1/32".parse().unwrap()],
+            allow_transfer_keys: vec![key.clone()],
+        };
+        assert!(acl.allows(RecordType::A, inside, None));
+        assert!(acl.allows(RecordType::A, mapped, None));
+        assert!(!acl.allows(RecordType::A, outside, None));
+        assert!(!acl.allows(RecordType::A, outside, Some(&key)));
+
+        assert!(acl.allows(RecordType::AXFR, inside, None));
+        assert!(acl.allows(RecordType::IXFR, mapped, None));
+        assert!(!acl.allows(RecordType::AXFR, outside, None));
+        assert!(acl.allows(RecordType::AXFR, outside, Some(&key)));
+        assert!(!acl.allows(RecordType::IXFR, outside, Some(&other_key)));
+
+        // only keys
+        let acl = ZoneAcl {
+            allow_transfer_keys: vec![key.clone()],
+            ..ZoneAcl::default()
+        };
+        assert!(acl.allows(RecordType::A, outside, None));
+        assert!(!acl.allows(RecordType::AXFR, inside, None));
+        assert!(acl.allows(RecordType::AXFR, inside, Some(&key)));
+    }
+}
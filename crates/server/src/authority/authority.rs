@@ -7,6 +7,9 @@
 
 //! All authority related types
 
+#[cfg(feature = "dnssec")]
+use std::time::Duration;
+
 use cfg_if::cfg_if;
 
 #[cfg(feature = "dnssec")]
@@ -17,7 +20,10 @@ use crate::client::{
 };
 use crate::{
     authority::{LookupError, MessageRequest, UpdateResult, ZoneType},
-    client::rr::{LowerName, RecordSet, RecordType},
+    client::{
+        op::ResponseCode,
+        rr::{rdata::opt::ClientSubnet, LowerName, RecordSet, RecordType},
+    },
     proto::rr::RrsetRecords,
     server::RequestInfo,
 };
@@ -31,6 +37,7 @@ pub struct LookupOptions {
     is_dnssec: bool,
     #[cfg(feature = "dnssec")]
     supported_algorithms: SupportedAlgorithms,
+    client_subnet: Option<ClientSubnet>,
 }
 
 /// Lookup Options for the request to the authority
@@ -42,6 +49,7 @@ impl LookupOptions {
         Self {
             is_dnssec,
             supported_algorithms,
+            client_subnet: None,
         }
     }
 
@@ -76,6 +84,19 @@ impl LookupOptions {
         self.supported_algorithms
     }
 
+    /// Specify the EDNS Client Subnet of the request, [RFC 7871](https://tools.ietf.org/html/rfc7871)
+    pub fn set_client_subnet(self, val: Option<ClientSubnet>) -> Self {
+        Self {
+            client_subnet: val,
+            ..self
+        }
+    }
+
+    /// The EDNS Client Subnet of the request, if any, forwarders pass it on to the upstream resolvers
+    pub fn client_subnet(&self) -> Option<ClientSubnet> {
+        self.client_subnet
+    }
+
     /// Returns the subset of the rrset limited to the supported_algorithms
     pub fn rrset_with_supported_algorithms<'r>(
         &self,
@@ -109,6 +130,14 @@ pub trait Authority: Send + Sync {
     /// Perform a dynamic update of a zone
     async fn update(&self, update: &MessageRequest) -> UpdateResult<bool>;
 
+    /// Handle a NOTIFY, [RFC 1996](https://tools.ietf.org/html/rfc1996), from a primary of this zone
+    ///
+    /// Returns true if a refresh of the zone was scheduled. Only secondary zones act on a NOTIFY, the default
+    ///  implementation returns `NotImp`.
+    async fn notify(&self, _request_info: RequestInfo<'_>) -> UpdateResult<bool> {
+        Err(ResponseCode::NotImp)
+    }
+
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
     fn origin(&self) -> &LowerName;
 
@@ -199,4 +228,41 @@ pub trait DnssecAuthority: Authority {
 
     /// Sign the zone for DNSSEC
     async fn secure_zone(&self) -> DnsSecResult<()>;
+
+    /// Begins the pre-publish rollover of the zone signing key with the key tag `old_key_tag` to `new_signer`, see
+    ///  [RFC 6781 section 4.1.1.1](https://tools.ietf.org/html/rfc6781#section-4.1.1.1)
+    ///
+    /// The DNSKEY of `new_signer` is published immediately, while the zone is still signed with the old key, which
+    ///  must have the same algorithm. After `RolloverTiming::publish_delay` the zone is signed with `new_signer` instead
+    ///  of the old key, and after `RolloverTiming::retire_delay` the DNSKEY of the old key is removed. Other keys are
+    ///  not affected. The SOA serial is incremented at each of these stages, which take place when the zone is next signed.
+    ///
+    /// The stage of the rollover is only kept in memory, it is not persisted with the zone. It continues when the records
+    ///  of the zone are reloaded, e.g. by `FileAuthority::reload`, but is lost when the server is restarted, after
+    ///  which the zone is signed with the keys of its configuration again. The configuration should therefore be changed
+    ///  to `new_signer` once the rollover has completed. Authorities which don't support rollovers return an error,
+    ///  which is the default.
+    async fn begin_zsk_rollover(
+        &self,
+        _old_key_tag: u16,
+        _new_signer: SigSigner,
+        _timing: RolloverTiming,
+    ) -> DnsSecResult<()> {
+        Err("zone signing key rollovers are not supported by this authority".into())
+    }
+}
+
+/// The delays between the stages of a zone signing key rollover, see `DnssecAuthority::begin_zsk_rollover`
+#[cfg(feature = "dnssec")]
+#[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RolloverTiming {
+    /// How long the new key is published before it signs the zone
+    ///
+    /// This must be at least the TTL of the DNSKEY RRset, so that no resolver caches a DNSKEY RRset without the new key.
+    pub publish_delay: Duration,
+    /// How long the old key is published after it no longer signs the zone
+    ///
+    /// This must be at least the longest TTL in the zone, so that no resolver caches a signature by the old key.
+    pub retire_delay: Duration,
 }
@@ -32,6 +32,9 @@ pub trait AuthorityObject: Send + Sync {
     /// Perform a dynamic update of a zone
     async fn update(&self, update: &MessageRequest) -> UpdateResult<bool>;
 
+    /// Handle a NOTIFY from a primary of this zone, returns true if a refresh of the zone was scheduled
+    async fn notify(&self, request_info: RequestInfo<'_>) -> UpdateResult<bool>;
+
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
     fn origin(&self) -> &LowerName;
 
@@ -140,6 +143,11 @@ where
         Authority::update(self.as_ref(), update).await
     }
 
+    /// Handle a NOTIFY from a primary of this zone
+    async fn notify(&self, request_info: RequestInfo<'_>) -> UpdateResult<bool> {
+        Authority::notify(self.as_ref(), request_info).await
+    }
+
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
     fn origin(&self) -> &LowerName {
         Authority::origin(self.as_ref())
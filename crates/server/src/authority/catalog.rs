@@ -8,38 +8,278 @@
 // TODO, I've implemented this as a separate entity from the cache, but I wonder if the cache
 //  should be the only "front-end" for lookups, where if that misses, then we go to the catalog
 //  then, if requested, do a recursive lookup... i.e. the catalog would only point to files.
-use std::{borrow::Borrow, collections::HashMap, future::Future, io};
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    future::Future,
+    io, iter, mem,
+    net::SocketAddr,
+    ops::Deref,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock, Weak,
+    },
+    time::Duration,
+};
 
 use cfg_if::cfg_if;
 use log::{debug, error, info, trace, warn};
-use trust_dns_proto::rr::Record;
+use tokio::task::JoinHandle;
+use trust_dns_proto::rr::{
+    rdata::{HINFO, TXT},
+    DNSClass, RData, Record, RecordSet,
+};
+
+#[cfg(feature = "dnssec")]
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[cfg(feature = "dnssec")]
-use crate::client::rr::{
-    dnssec::{Algorithm, SupportedAlgorithms},
-    rdata::opt::{EdnsCode, EdnsOption},
+use crate::{
+    authority::tsig::{self, TsigResponseHandler},
+    client::rr::{
+        dnssec::{tsig::TSigner, Algorithm, SupportedAlgorithms},
+        rdata::DNSSECRData,
+    },
 };
 use crate::{
     authority::{
         AuthLookup, AuthorityObject, EmptyLookup, LookupError, LookupObject, LookupOptions,
-        MessageResponse, MessageResponseBuilder, ZoneType,
+        LookupRecords, MessageResponse, MessageResponseBuilder, ZoneAcl, ZoneType,
     },
     client::{
+        error::ClientError,
         op::{Edns, Header, LowerQuery, MessageType, OpCode, ResponseCode},
-        rr::{LowerName, RecordType},
+        rr::{
+            rdata::opt::{EdnsCode, EdnsOption},
+            LowerName, Name, RecordType,
+        },
+        serialize::binary::{BinEncodable, BinEncoder},
     },
-    server::{Request, RequestHandler, RequestInfo, ResponseHandler, ResponseInfo},
+    config::ChaosConfig,
+    server::{Protocol, Request, RequestHandler, RequestInfo, ResponseHandler, ResponseInfo},
+    store::secondary::{CatalogMember, CatalogZone, SecondaryAuthority},
 };
 
 /// Set of authorities, zones, available to this server.
 #[derive(Default)]
 pub struct Catalog {
     authorities: HashMap<LowerName, Box<dyn AuthorityObject>>,
+    #[cfg(feature = "dnssec")]
+    tsig_keys: HashMap<LowerName, TSigner>,
+    acls: HashMap<LowerName, ZoneAcl>,
+    minimal_any: bool,
+    axfr_message_size: usize,
+    chaos: ChaosConfig,
+    catalog_zones: Arc<RwLock<CatalogZones>>,
+    /// Set once a catalog zone is loaded, the lock of `catalog_zones` is not taken for queries before
+    has_catalog_zones: AtomicBool,
+}
+
+/// The default size to which the messages of a zone transfer are filled, see `Catalog::set_axfr_message_size`
+const DEFAULT_AXFR_MESSAGE_SIZE: usize = 16 * 1024;
+
+/// The largest size of the records of a message of a zone transfer, leaves room in a TCP message for the rest
+const MAX_AXFR_MESSAGE_SIZE: usize = u16::MAX as usize - 1024;
+
+/// The catalog zones of a `Catalog` and their member zones, shared with the tasks which refresh the catalog zones
+#[derive(Default)]
+struct CatalogZones {
+    zones: HashMap<LowerName, LoadedCatalogZone>,
+    /// The secondary zones of all members, which are served along with the authorities of the catalog
+    members: HashMap<LowerName, Arc<SecondaryAuthority>>,
+    /// The zones of the authorities of the catalog, which members never replace
+    served: HashSet<LowerName>,
+}
+
+/// How often a catalog zone is refreshed when its SOA has no refresh interval
+const DEFAULT_CATALOG_ZONE_REFRESH: Duration = Duration::from_secs(3600);
+
+/// A catalog zone, and the secondary zones provisioned for its members
+struct LoadedCatalogZone {
+    authority: Arc<SecondaryAuthority>,
+    members: HashMap<LowerName, LoadedMember>,
+    refresh: JoinHandle<()>,
+}
+
+impl Drop for LoadedCatalogZone {
+    fn drop(&mut self) {
+        self.refresh.abort();
+    }
+}
+
+/// A secondary zone provisioned for a member of a catalog zone, its refreshes stop when dropped
+struct LoadedMember {
+    member: CatalogMember,
+    refresh: JoinHandle<()>,
+}
+
+impl Drop for LoadedMember {
+    fn drop(&mut self) {
+        self.refresh.abort();
+    }
+}
+
+impl CatalogZones {
+    /// Synchronizes the member zones of the catalog zone `name` with its current members
+    ///
+    /// Returns the member zones which are provisioned, of the `previous` members those which are no longer listed
+    ///  are removed.
+    fn sync_members(
+        &mut self,
+        catalog_zone: &CatalogZone,
+        primaries: &[SocketAddr],
+        mut previous: HashMap<LowerName, LoadedMember>,
+    ) -> HashMap<LowerName, LoadedMember> {
+        let name = catalog_zone.name();
+        let mut members = HashMap::new();
+        for member in catalog_zone.members() {
+            let zone = LowerName::new(member.zone());
+
+            if let Some(mut loaded) = previous.remove(&zone) {
+                if loaded.member.unique_id() == member.unique_id() {
+                    loaded.member = member.clone();
+                    members.insert(zone, loaded);
+                    continue;
+                }
+
+                info!("member zone {} of catalog zone {} is reset", zone, name);
+                self.members.remove(&zone);
+            } else if self.served.contains(&zone) {
+                warn!(
+                    "member zone {} of catalog zone {} is served already, ignored",
+                    zone, name
+                );
+                continue;
+            } else if self.members.contains_key(&zone) {
+                let owner = self
+                    .zones
+                    .values_mut()
+                    .find(|other| other.members.contains_key(&zone));
+                match owner {
+                    Some(owner) if matches!(owner.members[&zone].member.coo(), Some(coo) if coo == name) =>
+                    {
+                        info!("member zone {} migrates to catalog zone {}", zone, name);
+                        owner.members.remove(&zone);
+                    }
+                    _ => {
+                        warn!(
+                            "member zone {} of catalog zone {} is served already, ignored",
+                            zone, name
+                        );
+                        continue;
+                    }
+                }
+            }
+
+            let loaded = self.provision_member(member, primaries);
+            members.insert(zone, loaded);
+        }
+
+        // the remaining members were removed from the catalog zone
+        for zone in previous.keys() {
+            info!(
+                "member zone {} was removed from catalog zone {}",
+                zone, name
+            );
+            self.members.remove(zone);
+        }
+
+        members
+    }
+
+    /// Adds a secondary zone for the member of a catalog zone, which is transferred in the background
+    fn provision_member(
+        &mut self,
+        member: &CatalogMember,
+        primaries: &[SocketAddr],
+    ) -> LoadedMember {
+        info!(
+            "adding member zone {} in group {}",
+            member.zone(),
+            member.group().unwrap_or("-")
+        );
+
+        let authority = Arc::new(SecondaryAuthority::new(
+            member.zone().clone(),
+            primaries.to_vec(),
+            false,
+        ));
+        self.members
+            .insert(LowerName::new(member.zone()), authority.clone());
+
+        let refresh = tokio::spawn(async move {
+            if let Err(e) = authority.refresh().await {
+                warn!("failed to transfer zone {}: {}", authority.origin(), e);
+            }
+            authority.refresh_on_notify().await
+        });
+
+        LoadedMember {
+            member: member.clone(),
+            refresh,
+        }
+    }
+}
+
+/// Refreshes the catalog zone on NOTIFY and at the refresh interval of its SOA, synchronizing its member zones
+///
+/// This ends once the catalog zone is no longer loaded.
+async fn refresh_catalog_zone(
+    catalog_zones: Weak<RwLock<CatalogZones>>,
+    authority: Arc<SecondaryAuthority>,
+) {
+    loop {
+        let interval = authority
+            .refresh_interval()
+            .await
+            .unwrap_or(DEFAULT_CATALOG_ZONE_REFRESH);
+        // either a NOTIFY or the end of the interval is a reason to check the serial
+        let _ = tokio::time::timeout(interval, authority.refresh_requested()).await;
+
+        let catalog_zone = match authority.refresh().await {
+            Ok(true) => {
+                CatalogZone::from_records(authority.origin().into(), &authority.records().await)
+            }
+            Ok(false) => continue,
+            Err(e) => {
+                warn!(
+                    "failed to refresh catalog zone {}: {}",
+                    authority.origin(),
+                    e
+                );
+                continue;
+            }
+        };
+        let catalog_zone = match catalog_zone {
+            Ok(catalog_zone) => catalog_zone,
+            Err(e) => {
+                warn!("invalid catalog zone {}: {}", authority.origin(), e);
+                continue;
+            }
+        };
+
+        let catalog_zones = match catalog_zones.upgrade() {
+            Some(catalog_zones) => catalog_zones,
+            None => return,
+        };
+        let mut catalog_zones = catalog_zones.write().expect("catalog zones lock poisoned");
+        let previous = match catalog_zones.zones.get_mut(authority.origin()) {
+            Some(loaded) if Arc::ptr_eq(&loaded.authority, &authority) => {
+                mem::take(&mut loaded.members)
+            }
+            _ => return,
+        };
+
+        let members = catalog_zones.sync_members(&catalog_zone, authority.primaries(), previous);
+        if let Some(loaded) = catalog_zones.zones.get_mut(authority.origin()) {
+            loaded.members = members;
+        }
+    }
 }
 
 #[allow(unused_mut, unused_variables)]
 async fn send_response<'a, R: ResponseHandler>(
-    response_edns: Option<Edns>,
+    mut response_edns: Option<Edns>,
     mut response: MessageResponse<
         '_,
         'a,
@@ -48,10 +288,10 @@ async fn send_response<'a, R: ResponseHandler>(
         impl Iterator<Item = &'a Record> + Send + 'a,
         impl Iterator<Item = &'a Record> + Send + 'a,
     >,
-    mut response_handle: R,
+    response_handle: &mut R,
 ) -> io::Result<ResponseInfo> {
     #[cfg(feature = "dnssec")]
-    if let Some(mut resp_edns) = response_edns {
+    if let Some(ref mut resp_edns) = response_edns {
         // set edns DAU and DHU
         // send along the algorithms which are supported by this authority
         let mut algorithms = SupportedAlgorithms::default();
@@ -59,13 +299,17 @@ async fn send_response<'a, R: ResponseHandler>(
         algorithms.set(Algorithm::ECDSAP256SHA256);
         algorithms.set(Algorithm::ECDSAP384SHA384);
         algorithms.set(Algorithm::ED25519);
+        #[cfg(feature = "openssl")]
+        algorithms.set(Algorithm::ED448);
 
         let dau = EdnsOption::DAU(algorithms);
         let dhu = EdnsOption::DHU(algorithms);
 
         resp_edns.options_mut().insert(dau);
         resp_edns.options_mut().insert(dhu);
+    }
 
+    if let Some(resp_edns) = response_edns {
         response.set_edns(resp_edns);
     }
 
@@ -133,13 +377,50 @@ impl RequestHandler for Catalog {
             response_edns = None;
         }
 
+        // a signed request is verified before anything else, and the response signed with the same key
+        #[cfg(feature = "dnssec")]
+        let (mut response_handle, tsig_key) = {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs());
+
+            let signer = tsig::verify_request(&self.tsig_keys, request, now);
+            let is_error = matches!(&signer, Some(signer) if signer.is_error());
+            let tsig_key = signer
+                .as_ref()
+                .map(|signer| LowerName::from(signer.key_name()));
+            let mut response_handle = TsigResponseHandler::new(response_handle, signer);
+
+            if is_error {
+                let response = MessageResponseBuilder::new(Some(request.raw_query()));
+
+                let result = response_handle
+                    .send_response(response.error_msg(request.header(), ResponseCode::NotAuth))
+                    .await;
+
+                return match result {
+                    Err(e) => {
+                        error!("request error: {}", e);
+                        ResponseInfo::serve_failed()
+                    }
+                    Ok(info) => info,
+                };
+            }
+
+            (response_handle, tsig_key)
+        };
+        #[cfg(not(feature = "dnssec"))]
+        let tsig_key: Option<LowerName> = None;
+
         let result = match request.message_type() {
             // TODO think about threading query lookups for multiple lookups, this could be a huge improvement
             //  especially for recursive lookups
             MessageType::Query => match request.op_code() {
                 OpCode::Query => {
                     debug!("query received: {}", request.id());
-                    let info = self.lookup(request, response_edns, response_handle).await;
+                    let info = self
+                        .lookup_with_key(request, tsig_key.as_ref(), response_edns, response_handle)
+                        .await;
 
                     Ok(info)
                 }
@@ -147,6 +428,10 @@ impl RequestHandler for Catalog {
                     debug!("update received: {}", request.id());
                     self.update(request, response_edns, response_handle).await
                 }
+                OpCode::Notify => {
+                    debug!("notify received: {}", request.id());
+                    self.notify(request, response_edns, response_handle).await
+                }
                 c => {
                     warn!("unimplemented op_code: {:?}", c);
                     let response = MessageResponseBuilder::new(Some(request.raw_query()));
@@ -181,9 +466,70 @@ impl Catalog {
     pub fn new() -> Self {
         Self {
             authorities: HashMap::new(),
+            #[cfg(feature = "dnssec")]
+            tsig_keys: HashMap::new(),
+            acls: HashMap::new(),
+            minimal_any: false,
+            axfr_message_size: DEFAULT_AXFR_MESSAGE_SIZE,
+            chaos: ChaosConfig::default(),
+            catalog_zones: Arc::default(),
+            has_catalog_zones: AtomicBool::new(false),
         }
     }
 
+    /// Answers ANY queries minimally, [RFC 8482](https://tools.ietf.org/html/rfc8482), instead of with all records
+    ///
+    /// This makes the server less useful for amplification attacks. The answer is a synthesized HINFO record, or the
+    ///  first RRset of the name and its signatures for DNSSEC requests. Defaults to `false`, all records are returned.
+    pub fn set_minimal_any(&mut self, minimal_any: bool) {
+        self.minimal_any = minimal_any;
+    }
+
+    /// The size to which the messages of a zone transfer over TCP are filled with records, before the next is started
+    ///
+    /// The zone is sent in as many messages as it takes, each encoded as it is sent, rather than in one message of
+    ///  the whole zone. A record larger than the size is sent in a message of its own. Defaults to 16 KiB, at most
+    ///  63 KiB.
+    pub fn set_axfr_message_size(&mut self, axfr_message_size: usize) {
+        self.axfr_message_size = axfr_message_size.min(MAX_AXFR_MESSAGE_SIZE);
+    }
+
+    /// Answers the CHAOS class TXT queries for the version and name of the server, see [`ChaosConfig`]
+    ///
+    /// Defaults to refusing them, so as not to disclose the version of the server.
+    pub fn set_chaos(&mut self, chaos: ChaosConfig) {
+        self.chaos = chaos;
+    }
+
+    /// Adds a key to authenticate requests with, [RFC 8945](https://tools.ietf.org/html/rfc8945) TSIG
+    ///
+    /// The TSIG of a signed request must be of a known key and valid, or the request is rejected with NOTAUTH,
+    ///  the response to a valid request is signed with the same key. A verified request is authorized to update
+    ///  any zone which allows updates.
+    ///
+    /// # Arguments
+    ///
+    /// * `signer` - the key, its name must be the name the clients use
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn add_tsig_key(&mut self, signer: TSigner) {
+        self.tsig_keys
+            .insert(LowerName::from(signer.signer_name()), signer);
+    }
+
+    /// Restricts the clients which may query and transfer a zone, by default all clients may
+    ///
+    /// Queries and transfers which the ACL does not allow are refused. The ACL of the zone which is
+    ///  found for the query name applies, it is kept when the zone is removed or replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - zone name, e.g. example.com.
+    /// * `acl` - the clients and TSIG keys which are allowed
+    pub fn set_acl(&mut self, name: LowerName, acl: ZoneAcl) {
+        self.acls.insert(name, acl);
+    }
+
     /// Insert or update a zone authority
     ///
     /// # Arguments
@@ -191,14 +537,93 @@ impl Catalog {
     /// * `name` - zone name, e.g. example.com.
     /// * `authority` - the zone data
     pub fn upsert(&mut self, name: LowerName, authority: Box<dyn AuthorityObject>) {
+        self.catalog_zones
+            .write()
+            .expect("catalog zones lock poisoned")
+            .served
+            .insert(name.clone());
         self.authorities.insert(name, authority);
     }
 
     /// Remove a zone from the catalog
     pub fn remove(&mut self, name: &LowerName) -> Option<Box<dyn AuthorityObject>> {
+        self.catalog_zones
+            .write()
+            .expect("catalog zones lock poisoned")
+            .served
+            .remove(name);
         self.authorities.remove(name)
     }
 
+    /// Loads a catalog zone, [RFC 9432](https://tools.ietf.org/html/rfc9432), and serves its member zones as secondaries
+    ///
+    /// The catalog zone is transferred from the primaries, it is not served itself. For each member zone a
+    ///  `SecondaryAuthority` is added, which is transferred from the same primaries in the background and refreshed on
+    ///  NOTIFY. The catalog zone is refreshed in the background as well, on NOTIFY and at the refresh interval of its
+    ///  SOA, and each change synchronizes the member zones: those which were removed from the catalog zone are removed,
+    ///  and those whose unique id changed are transferred anew. A zone which is served already, by an authority of the
+    ///  catalog or for another catalog zone, is left alone, unless the other catalog zone migrates it to this one with
+    ///  its `coo` property. Loading a catalog zone again replaces it.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - the name of the catalog zone
+    /// * `primaries` - the primaries of the catalog zone and its member zones
+    ///
+    /// # Return value
+    ///
+    /// The catalog zone with all its members, including any which are not served because of a conflict.
+    pub async fn load_catalog_zone(
+        &self,
+        name: Name,
+        primaries: Vec<SocketAddr>,
+    ) -> Result<CatalogZone, ClientError> {
+        let key = LowerName::new(&name);
+        let authority = Arc::new(SecondaryAuthority::new(
+            name.clone(),
+            primaries.clone(),
+            false,
+        ));
+        authority.refresh().await?;
+        let catalog_zone = CatalogZone::from_records(name, &authority.records().await)?;
+
+        let mut catalog_zones = self
+            .catalog_zones
+            .write()
+            .expect("catalog zones lock poisoned");
+        let previous = catalog_zones
+            .zones
+            .get_mut(&key)
+            .map(|loaded| mem::take(&mut loaded.members))
+            .unwrap_or_default();
+        let members = catalog_zones.sync_members(&catalog_zone, &primaries, previous);
+
+        let refresh = tokio::spawn(refresh_catalog_zone(
+            Arc::downgrade(&self.catalog_zones),
+            authority.clone(),
+        ));
+        catalog_zones.zones.insert(
+            key,
+            LoadedCatalogZone {
+                authority,
+                members,
+                refresh,
+            },
+        );
+        self.has_catalog_zones.store(true, Ordering::Release);
+        Ok(catalog_zone)
+    }
+
+    /// The secondary zone provisioned for a member of a loaded catalog zone, see `load_catalog_zone`
+    pub fn member_zone(&self, name: &LowerName) -> Option<Arc<SecondaryAuthority>> {
+        self.catalog_zones
+            .read()
+            .expect("catalog zones lock poisoned")
+            .members
+            .get(name)
+            .cloned()
+    }
+
     /// Update the zone given the Update request.
     ///
     /// [RFC 2136](https://tools.ietf.org/html/rfc2136), DNS Update, April 1997
@@ -252,7 +677,7 @@ impl Catalog {
         &self,
         update: &Request,
         response_edns: Option<Edns>,
-        response_handle: R,
+        mut response_handle: R,
     ) -> io::Result<ResponseInfo> {
         let request_info = update.request_info();
 
@@ -280,8 +705,7 @@ impl Catalog {
         // verify the zone type and number of zones in request, then find the zone to update
         let request_info = verify_request();
         let authority = request_info.as_ref().map_err(|e| *e).and_then(|info| {
-            self.find(info.query.name())
-                .map(|a| a.box_clone())
+            self.find_authority(info.query.name())
                 .ok_or(ResponseCode::Refused)
         });
 
@@ -317,7 +741,79 @@ impl Catalog {
         send_response(
             response_edns,
             response.build_no_records(response_header),
-            response_handle,
+            &mut response_handle,
+        )
+        .await
+    }
+
+    /// Handle a NOTIFY of a change to a secondary zone.
+    ///
+    /// [RFC 1996](https://tools.ietf.org/html/rfc1996), DNS NOTIFY, August 1996
+    ///
+    /// ```text
+    /// 3.7. A NOTIFY request has QDCOUNT>0, ANCOUNT>=0, AUCOUNT>=0,
+    ///   ADCOUNT>=0.  If ANCOUNT>0, then the answer section represents an
+    ///   unsecure hint at the new RRset for this <QNAME,QCLASS,QTYPE>.  A
+    ///   slave receiving such a hint is free to treat equivalence of this
+    ///   answer section with its local data as a "no further work needs to be
+    ///   done" indication.  If ANCOUNT=0, or ANCOUNT>0 and the answer section
+    ///   differs from the slave's local data, then the slave should query its
+    ///   known masters to retrieve the new data.
+    ///
+    /// 3.10. If a slave receives a NOTIFY request from a host that is not a
+    ///   known master for the zone containing the QNAME, it should ignore the
+    ///   request and produce an error message in its operations log.
+    /// ```
+    ///
+    /// The QNAME must be the origin of a secondary zone in the catalog, the `Authority` of that zone validates the
+    ///  source of the request and schedules the refresh of the zone. A request from an unknown primary is refused.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - a notify message
+    /// * `response_handle` - sink for the response message to be sent
+    pub async fn notify<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        response_edns: Option<Edns>,
+        mut response_handle: R,
+    ) -> io::Result<ResponseInfo> {
+        let request_info = request.request_info();
+        let qtype = request_info.query.query_type();
+
+        let response_code = if qtype != RecordType::SOA {
+            warn!("unsupported notify request type: {}", qtype);
+            ResponseCode::NotImp
+        } else if let Some(authority) = self.find_notified(request_info.query.name()) {
+            #[allow(deprecated)]
+            match authority.zone_type() {
+                ZoneType::Secondary | ZoneType::Slave => {
+                    match authority.notify(request_info).await {
+                        Ok(_) => ResponseCode::NoError,
+                        Err(response_code) => response_code,
+                    }
+                }
+                _ => {
+                    warn!(
+                        "notify for zone that is not secondary: {}",
+                        authority.origin()
+                    );
+                    ResponseCode::NotAuth
+                }
+            }
+        } else {
+            warn!("notify for unknown zone: {}", request_info.query.name());
+            ResponseCode::NotAuth
+        };
+
+        let response = MessageResponseBuilder::new(Some(request.raw_query()));
+        let mut response_header = Header::response_from_request(request.header());
+        response_header.set_response_code(response_code);
+
+        send_response(
+            response_edns,
+            response.build_no_records(response_header),
+            &mut response_handle,
         )
         .await
     }
@@ -333,6 +829,12 @@ impl Catalog {
     /// want to use the authority it contains, use `find` instead.
     pub fn contains(&self, name: &LowerName) -> bool {
         self.authorities.contains_key(name)
+            || self
+                .catalog_zones
+                .read()
+                .expect("catalog zones lock poisoned")
+                .members
+                .contains_key(name)
     }
 
     /// Given the requested query, lookup and return any matching results.
@@ -346,19 +848,59 @@ impl Catalog {
         request: &Request,
         response_edns: Option<Edns>,
         response_handle: R,
+    ) -> ResponseInfo {
+        self.lookup_with_key(request, None, response_edns, response_handle)
+            .await
+    }
+
+    /// Lookup for a request verified with the TSIG key `tsig_key`, if any, which the ACL of the zone may allow
+    async fn lookup_with_key<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        tsig_key: Option<&LowerName>,
+        response_edns: Option<Edns>,
+        mut response_handle: R,
     ) -> ResponseInfo {
         let request_info = request.request_info();
-        let authority = self.find(request_info.query.name());
+        if request_info.query.query_class() == DNSClass::CH {
+            return self
+                .chaos_lookup(request, response_edns, response_handle)
+                .await;
+        }
+
+        // a query the ACL of the zone does not allow is refused the same as one for an unknown zone
+        let authority = self
+            .find_authority(request_info.query.name())
+            .filter(|authority| {
+                let allowed = match self.acls.get(authority.origin()) {
+                    Some(acl) => acl.allows(
+                        request_info.query.query_type(),
+                        request.src().ip(),
+                        tsig_key,
+                    ),
+                    None => true,
+                };
+                if !allowed {
+                    warn!(
+                        "refusing query {} from {} by the ACL",
+                        request_info.query,
+                        request.src()
+                    );
+                }
+                allowed
+            });
 
         if let Some(authority) = authority {
             lookup(
                 request_info,
-                authority,
+                &*authority,
+                self.minimal_any,
+                self.axfr_message_size,
                 request,
                 response_edns
                     .as_ref()
                     .map(|arc| Borrow::<Edns>::borrow(arc).clone()),
-                response_handle.clone(),
+                response_handle,
             )
             .await
         } else {
@@ -368,7 +910,7 @@ impl Catalog {
             let result = send_response(
                 response_edns,
                 response.error_msg(request.header(), ResponseCode::Refused),
-                response_handle,
+                &mut response_handle,
             )
             .await;
 
@@ -382,29 +924,161 @@ impl Catalog {
         }
     }
 
-    /// Recursively searches the catalog for a matching authority
+    /// Answers a CHAOS class query, with the strings of the `ChaosConfig` or REFUSED
+    async fn chaos_lookup<R: ResponseHandler>(
+        &self,
+        request: &Request,
+        response_edns: Option<Edns>,
+        mut response_handle: R,
+    ) -> ResponseInfo {
+        let query = request.request_info().query;
+        let response = MessageResponseBuilder::new(Some(request.raw_query()));
+
+        let txt = match query.name().to_string().as_str() {
+            "version.bind." | "version.server." => self.chaos.version.as_ref(),
+            "hostname.bind." | "id.server." => self.chaos.hostname.as_ref(),
+            _ => None,
+        };
+
+        let result = match txt {
+            Some(txt) if self.chaos.enabled => {
+                let mut response_header = Header::response_from_request(request.header());
+                response_header.set_authoritative(true);
+
+                let mut answers = Vec::new();
+                if matches!(query.query_type(), RecordType::TXT | RecordType::ANY) {
+                    let mut record = Record::from_rdata(
+                        query.name().into(),
+                        0,
+                        RData::TXT(TXT::new(character_strings(txt))),
+                    );
+                    record.set_dns_class(DNSClass::CH);
+                    answers.push(record);
+                }
+
+                let response = response.build(
+                    response_header,
+                    answers.iter(),
+                    iter::empty(),
+                    iter::empty(),
+                    iter::empty(),
+                );
+                send_response(response_edns, response, &mut response_handle).await
+            }
+            _ => {
+                debug!("refusing CHAOS query: {}", query);
+                send_response(
+                    response_edns,
+                    response.error_msg(request.header(), ResponseCode::Refused),
+                    &mut response_handle,
+                )
+                .await
+            }
+        };
+
+        match result {
+            Err(e) => {
+                error!("failed to send response: {}", e);
+                ResponseInfo::serve_failed()
+            }
+            Ok(r) => r,
+        }
+    }
+
+    /// Searches the catalog for the authority of `name`
+    ///
+    /// When zones are nested, e.g. `example.com.` and `sub.example.com.`, the most specific zone
+    ///  wins, i.e. the authority whose origin is the longest suffix of `name`.
     pub fn find(&self, name: &LowerName) -> Option<&(dyn AuthorityObject + 'static)> {
         debug!("searching authorities for: {}", name);
-        self.authorities
-            .get(name)
-            .map(|authority| &**authority)
-            .or_else(|| {
-                if !name.is_root() {
-                    let name = name.base_name();
-                    self.find(&name)
-                } else {
-                    None
-                }
-            })
+
+        let mut name = name.clone();
+        loop {
+            if let Some(authority) = self.authorities.get(&name) {
+                return Some(&**authority);
+            }
+
+            if name.is_root() {
+                return None;
+            }
+
+            name = name.base_name();
+        }
+    }
+
+    /// Searches the authorities and the member zones of catalog zones for the zone of `name`, see `find`
+    fn find_authority(&self, name: &LowerName) -> Option<FoundAuthority<'_>> {
+        if !self.has_catalog_zones.load(Ordering::Acquire) {
+            return self.find(name).map(FoundAuthority::Catalog);
+        }
+
+        let catalog_zones = self
+            .catalog_zones
+            .read()
+            .expect("catalog zones lock poisoned");
+
+        let mut name = name.clone();
+        loop {
+            if let Some(authority) = self.authorities.get(&name) {
+                return Some(FoundAuthority::Catalog(&**authority));
+            }
+            if let Some(member) = catalog_zones.members.get(&name) {
+                return Some(FoundAuthority::Member(member.clone()));
+            }
+
+            if name.is_root() {
+                return None;
+            }
+
+            name = name.base_name();
+        }
+    }
+
+    /// The zone named `name` to which a NOTIFY is passed, of the authorities, the member zones or the catalog zones
+    fn find_notified(&self, name: &LowerName) -> Option<FoundAuthority<'_>> {
+        if let Some(authority) = self.authorities.get(name) {
+            return Some(FoundAuthority::Catalog(&**authority));
+        }
+
+        let catalog_zones = self
+            .catalog_zones
+            .read()
+            .expect("catalog zones lock poisoned");
+        let secondary = catalog_zones.members.get(name).or_else(|| {
+            catalog_zones
+                .zones
+                .get(name)
+                .map(|loaded| &loaded.authority)
+        })?;
+        Some(FoundAuthority::Member(secondary.clone()))
+    }
+}
+
+/// An authority found by `Catalog::find_authority`, borrowed from the catalog or shared with a catalog zone
+enum FoundAuthority<'a> {
+    Catalog(&'a (dyn AuthorityObject + 'static)),
+    Member(Arc<SecondaryAuthority>),
+}
+
+impl Deref for FoundAuthority<'_> {
+    type Target = dyn AuthorityObject;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            FoundAuthority::Catalog(authority) => *authority,
+            FoundAuthority::Member(member) => member,
+        }
     }
 }
 
 async fn lookup<'a, R: ResponseHandler + Unpin>(
     request_info: RequestInfo<'_>,
     authority: &dyn AuthorityObject,
+    minimal_any: bool,
+    axfr_message_size: usize,
     request: &Request,
     response_edns: Option<Edns>,
-    response_handle: R,
+    mut response_handle: R,
 ) -> ResponseInfo {
     let query = request_info.query;
     debug!(
@@ -415,6 +1089,7 @@ async fn lookup<'a, R: ResponseHandler + Unpin>(
 
     let (response_header, sections) = build_response(
         &*authority,
+        minimal_any,
         request_info,
         request.id(),
         request.header(),
@@ -423,15 +1098,33 @@ async fn lookup<'a, R: ResponseHandler + Unpin>(
     )
     .await;
 
-    let response = MessageResponseBuilder::new(Some(request.raw_query())).build(
-        response_header,
-        sections.answers.iter(),
-        sections.ns.iter(),
-        sections.soa.iter(),
-        sections.additionals.iter(),
-    );
-
-    let result = send_response(response_edns.clone(), response, response_handle.clone()).await;
+    let result = match query.query_type() {
+        RecordType::AXFR
+            if response_header.response_code() == ResponseCode::NoError
+                && matches!(request.protocol(), Protocol::Tcp | Protocol::Tls) =>
+        {
+            send_axfr_response(
+                request,
+                response_header,
+                sections.answers.iter(),
+                axfr_message_size,
+                response_edns,
+                response_handle,
+            )
+            .await
+        }
+        _ => {
+            let response = MessageResponseBuilder::new(Some(request.raw_query())).build(
+                response_header,
+                sections.answers.iter(),
+                sections.ns.iter(),
+                sections.soa.iter(),
+                sections.additionals.iter(),
+            );
+
+            send_response(response_edns, response, &mut response_handle).await
+        }
+    };
 
     match result {
         Err(e) => {
@@ -442,13 +1135,91 @@ async fn lookup<'a, R: ResponseHandler + Unpin>(
     }
 }
 
+/// Sends the records of a zone transfer in as many messages as it takes to fill each to `message_size`,
+///  [RFC 5936, section 2.2](https://tools.ietf.org/html/rfc5936#section-2.2)
+///
+/// Only the records of one message are encoded at a time. The question is only in the first message.
+///  All messages are sent with the one response handle, which waits until the connection is ready for each.
+async fn send_axfr_response<'a, R: ResponseHandler>(
+    request: &Request,
+    response_header: Header,
+    records: impl Iterator<Item = &'a Record> + Send + 'a,
+    message_size: usize,
+    response_edns: Option<Edns>,
+    mut response_handle: R,
+) -> io::Result<ResponseInfo> {
+    let mut query = Some(request.raw_query());
+    let mut message = Vec::new();
+    let mut message_len = 0;
+    let mut buffer = Vec::with_capacity(512);
+
+    for record in records {
+        buffer.clear();
+        record
+            .emit(&mut BinEncoder::new(&mut buffer))
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("error encoding message: {}", e),
+                )
+            })?;
+
+        if !message.is_empty() && message_len + buffer.len() > message_size {
+            let response = MessageResponseBuilder::new(query.take()).build(
+                response_header,
+                message.drain(..),
+                iter::empty(),
+                iter::empty(),
+                iter::empty(),
+            );
+            send_response(response_edns.clone(), response, &mut response_handle).await?;
+            message_len = 0;
+        }
+
+        message_len += buffer.len();
+        message.push(record);
+    }
+
+    // the last message, with the closing SOA
+    let response = MessageResponseBuilder::new(query).build(
+        response_header,
+        message,
+        iter::empty(),
+        iter::empty(),
+        iter::empty(),
+    );
+    send_response(response_edns, response, &mut response_handle).await
+}
+
 #[allow(unused_variables)]
+/// Splits the text into character-strings of at most 255 bytes, without splitting a character
+fn character_strings(txt: &str) -> Vec<String> {
+    let mut strings = Vec::new();
+    let mut rest = txt;
+    while rest.len() > 255 {
+        let mut end = 255;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (string, tail) = rest.split_at(end);
+        strings.push(string.to_string());
+        rest = tail;
+    }
+    strings.push(rest.to_string());
+    strings
+}
+
 fn lookup_options_for_edns(edns: Option<&Edns>) -> LookupOptions {
     let edns = match edns {
         Some(edns) => edns,
         None => return LookupOptions::default(),
     };
 
+    let client_subnet = match edns.option(EdnsCode::Subnet) {
+        Some(&EdnsOption::Subnet(client_subnet)) => Some(client_subnet),
+        _ => None,
+    };
+
     cfg_if! {
         if #[cfg(feature = "dnssec")] {
             let supported_algorithms = if let Some(&EdnsOption::DAU(algs)) = edns.option(EdnsCode::DAU)
@@ -460,14 +1231,16 @@ fn lookup_options_for_edns(edns: Option<&Edns>) -> LookupOptions {
             };
 
             LookupOptions::for_dnssec(edns.dnssec_ok(), supported_algorithms)
+                .set_client_subnet(client_subnet)
         } else {
-            LookupOptions::default()
+            LookupOptions::default().set_client_subnet(client_subnet)
         }
     }
 }
 
 async fn build_response(
     authority: &dyn AuthorityObject,
+    minimal_any: bool,
     request_info: RequestInfo<'_>,
     request_id: u16,
     request_header: &Header,
@@ -496,6 +1269,7 @@ async fn build_response(
             send_authoritative_response(
                 future,
                 authority,
+                minimal_any,
                 &mut response_header,
                 lookup_options,
                 request_id,
@@ -514,6 +1288,7 @@ async fn build_response(
 async fn send_authoritative_response(
     future: impl Future<Output = Result<Box<dyn LookupObject>, LookupError>>,
     authority: &dyn AuthorityObject,
+    minimal_any: bool,
     response_header: &mut Header,
     lookup_options: LookupOptions,
     request_id: u16,
@@ -550,6 +1325,16 @@ async fn send_authoritative_response(
         }
     };
 
+    let answers = match answers {
+        Some(answers) if minimal_any && query.query_type() == RecordType::ANY => {
+            Some(minimal_any_answers(&*answers, lookup_options).unwrap_or(answers))
+        }
+        answers => answers,
+    };
+
+    // an empty answer, e.g. of an RRset emptied by updates, is NODATA and gets the SOA like NameExists
+    let answers = answers.filter(|answers| !answers.is_empty());
+
     let (ns, soa) = if answers.is_some() {
         // SOA queries should return the NS records as well.
         if query.query_type().is_soa() {
@@ -584,7 +1369,10 @@ async fn send_authoritative_response(
         };
 
         match authority.soa_secure(lookup_options).await {
-            Ok(soa) => (nsecs, Some(soa)),
+            Ok(soa) => (
+                nsecs,
+                Some(negative_soa(&*soa, lookup_options).unwrap_or(soa)),
+            ),
             Err(e) => {
                 warn!("failed to lookup soa: {}", e);
                 (nsecs, None)
@@ -615,6 +1403,94 @@ async fn send_authoritative_response(
     }
 }
 
+/// The SOA for the authority section of a negative response, `None` if there is no SOA
+///
+/// The TTL of the SOA is the lesser of its own TTL and the SOA minimum, which is the TTL of the negative answer,
+///  [RFC 2308, section 3](https://tools.ietf.org/html/rfc2308#section-3).
+fn negative_soa(
+    soa: &dyn LookupObject,
+    lookup_options: LookupOptions,
+) -> Option<Box<dyn LookupObject>> {
+    let record = soa
+        .iter()
+        .find(|record| record.record_type() == RecordType::SOA)?;
+    let ttl = match record.data() {
+        Some(RData::SOA(soa)) => record.ttl().min(soa.minimum()),
+        _ => record.ttl(),
+    };
+
+    let mut rrset = RecordSet::with_ttl(record.name().clone(), RecordType::SOA, ttl);
+    for record in soa.iter() {
+        let mut record = record.clone();
+        record.set_ttl(ttl);
+
+        if record.record_type() == RecordType::SOA {
+            rrset.insert(record, 0);
+        } else if covers(&record, RecordType::SOA) {
+            rrset.insert_rrsig(record);
+        }
+    }
+
+    Some(Box::new(LookupRecords::new(
+        lookup_options,
+        Arc::new(rrset),
+    )))
+}
+
+/// The minimal answer to an ANY query, [RFC 8482](https://tools.ietf.org/html/rfc8482), `None` if there are no records
+///
+/// A synthesized record can't be signed, so DNSSEC requests are answered with the first RRset and its signatures,
+///  section 4.1, all others with an HINFO record with the CPU `RFC8482`, section 4.2.
+fn minimal_any_answers(
+    answers: &dyn LookupObject,
+    lookup_options: LookupOptions,
+) -> Option<Box<dyn LookupObject>> {
+    let first = answers
+        .iter()
+        .find(|record| record.record_type() != RecordType::RRSIG)?;
+
+    let rrset = if lookup_options.is_dnssec() {
+        let record_type = first.record_type();
+        let mut rrset = RecordSet::new(first.name(), record_type, 0);
+        for record in answers.iter() {
+            if record.record_type() == record_type {
+                rrset.insert(record.clone(), 0);
+            } else if covers(record, record_type) {
+                rrset.insert_rrsig(record.clone());
+            }
+        }
+        rrset
+    } else {
+        let ttl = answers.iter().map(Record::ttl).min().unwrap_or_default();
+        let mut rrset = RecordSet::with_ttl(first.name().clone(), RecordType::HINFO, ttl);
+        rrset.add_rdata(RData::HINFO(HINFO::new(
+            "RFC8482".to_string(),
+            String::new(),
+        )));
+        rrset
+    };
+
+    Some(Box::new(LookupRecords::new(
+        lookup_options,
+        Arc::new(rrset),
+    )))
+}
+
+/// Returns true if the record is an RRSIG of the RRset of the type
+#[allow(unused_variables)]
+fn covers(record: &Record, record_type: RecordType) -> bool {
+    cfg_if! {
+        if #[cfg(feature = "dnssec")] {
+            matches!(
+                record.data(),
+                Some(RData::DNSSEC(DNSSECRData::SIG(sig))) if sig.type_covered() == record_type
+            )
+        } else {
+            false
+        }
+    }
+}
+
 async fn send_forwarded_response(
     future: impl Future<Output = Result<Box<dyn LookupObject>, LookupError>>,
     request_header: &Header,
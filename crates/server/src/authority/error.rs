@@ -12,7 +12,7 @@ use thiserror::Error;
 
 use crate::client::op::ResponseCode;
 #[cfg(feature = "trust-dns-resolver")]
-use crate::resolver::error::ResolveError;
+use crate::resolver::error::{ResolveError, ResolveErrorKind};
 
 // TODO: should this implement Failure?
 #[allow(clippy::large_enum_variant)]
@@ -42,9 +42,20 @@ impl LookupError {
         Self::NameExists
     }
 
-    /// This is a non-existent domain name
+    /// This is a non-existent domain name, also when an upstream resolver answered NXDOMAIN
     pub fn is_nx_domain(&self) -> bool {
-        matches!(*self, LookupError::ResponseCode(ResponseCode::NXDomain))
+        match *self {
+            Self::ResponseCode(ResponseCode::NXDomain) => true,
+            #[cfg(feature = "trust-dns-resolver")]
+            Self::ResolveError(ref e) => matches!(
+                e.kind(),
+                ResolveErrorKind::NoRecordsFound {
+                    response_code: ResponseCode::NXDomain,
+                    ..
+                }
+            ),
+            _ => false,
+        }
     }
 
     /// This is a non-existent domain name
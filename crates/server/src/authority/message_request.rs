@@ -15,7 +15,7 @@ use crate::{
             message::{self, EmitAndCount},
             Edns, Header, Message, MessageType, OpCode, ResponseCode,
         },
-        rr::Record,
+        rr::{Record, RecordType},
         serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder},
     },
 };
@@ -30,6 +30,8 @@ pub struct MessageRequest {
     additionals: Vec<Record>,
     sig0: Vec<Record>,
     edns: Option<Edns>,
+    #[cfg(feature = "dnssec")]
+    signed_original: Option<Box<[u8]>>,
 }
 
 impl MessageRequest {
@@ -160,6 +162,20 @@ impl MessageRequest {
         &self.sig0
     }
 
+    /// The TSIG record, if this message is signed with one
+    pub fn tsig(&self) -> Option<&Record> {
+        self.sig0
+            .last()
+            .filter(|record| record.rr_type() == RecordType::TSIG)
+    }
+
+    /// The message as received, this is only kept for messages signed with a SIG(0) or TSIG, as the signature
+    ///  is over the original bytes of the message
+    #[cfg(feature = "dnssec")]
+    pub(crate) fn signed_original(&self) -> Option<&[u8]> {
+        self.signed_original.as_deref()
+    }
+
     /// # Return value
     ///
     /// the max payload value as it's defined in the EDNS section.
@@ -189,6 +205,8 @@ impl<'q> BinDecodable<'q> for MessageRequest {
     // TODO: generify this with Message?
     /// Reads a MessageRequest from the decoder
     fn read(decoder: &mut BinDecoder<'q>) -> ProtoResult<Self> {
+        #[cfg(feature = "dnssec")]
+        let message_start = decoder.index();
         let mut header = Header::read(decoder)?;
 
         let mut try_parse_rest = move || {
@@ -210,6 +228,14 @@ impl<'q> BinDecodable<'q> for MessageRequest {
                 header.merge_response_code(high_response_code);
             }
 
+            // the SIG(0) and TSIG are verified against the message as it was received
+            #[cfg(feature = "dnssec")]
+            let signed_original = if sig0.is_empty() {
+                None
+            } else {
+                Some(decoder.slice_from(message_start)?.into())
+            };
+
             Ok(Self {
                 header,
                 query,
@@ -218,6 +244,8 @@ impl<'q> BinDecodable<'q> for MessageRequest {
                 additionals,
                 sig0,
                 edns,
+                #[cfg(feature = "dnssec")]
+                signed_original,
             })
         };
 
@@ -19,10 +19,12 @@ use crate::{
         rr::Record,
         serialize::binary::BinEncoder,
     },
-    server::ResponseInfo,
+    server::{Request, ResponseInfo},
 };
 
 use super::message_request::WireQuery;
+#[cfg(feature = "dnssec")]
+use super::tsig::ResponseSigner;
 
 /// A EncodableMessage with borrowed data for Responses in the Server
 #[derive(Debug)]
@@ -41,6 +43,8 @@ where
     additionals: Additionals,
     sig0: Vec<Record>,
     edns: Option<Edns>,
+    #[cfg(feature = "dnssec")]
+    tsig: Option<ResponseSigner>,
 }
 
 enum EmptyOrQueries<'q> {
@@ -90,28 +94,102 @@ where
         &mut self.header
     }
 
+    /// Returns the maximum size of the response over UDP, the payload size of its EDNS or 512 without
+    ///
+    /// The `Catalog` sets the payload size of the response to that of the request.
+    pub fn max_payload(&self) -> u16 {
+        self.edns.as_ref().map_or(512, Edns::max_payload).max(512)
+    }
+
+    /// Returns a mutable reference to the EDNS of the response, if any
+    pub fn edns_mut(&mut self) -> Option<&mut Edns> {
+        self.edns.as_mut()
+    }
+
     /// Set the EDNS options for the Response
     pub fn set_edns(&mut self, edns: Edns) -> &mut Self {
         self.edns = Some(edns);
         self
     }
 
+    /// Set the signer of the TSIG, which is appended to the response when emitted
+    #[cfg(feature = "dnssec")]
+    pub(crate) fn set_tsig(&mut self, tsig: ResponseSigner) -> &mut Self {
+        self.tsig = Some(tsig);
+        self
+    }
+
+    /// Consumes self, returning a response with the TC flag set and all records removed
+    ///
+    /// The header and query are kept, so that the client can retry the query over TCP.
+    pub(crate) fn into_truncated(
+        self,
+    ) -> MessageResponse<
+        'q,
+        'a,
+        impl Iterator<Item = &'a Record> + Send + 'a,
+        impl Iterator<Item = &'a Record> + Send + 'a,
+        impl Iterator<Item = &'a Record> + Send + 'a,
+        impl Iterator<Item = &'a Record> + Send + 'a,
+    > {
+        let mut header = self.header;
+        header.set_truncated(true);
+
+        MessageResponse {
+            header,
+            query: self.query,
+            answers: Box::new(None.into_iter()),
+            name_servers: Box::new(None.into_iter()),
+            soa: Box::new(None.into_iter()),
+            additionals: Box::new(None.into_iter()),
+            sig0: Vec::new(),
+            edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: self.tsig,
+        }
+    }
+
     /// Consumes self, and emits to the encoder.
-    pub fn destructive_emit(mut self, encoder: &mut BinEncoder<'_>) -> ProtoResult<ResponseInfo> {
+    ///
+    /// If the response does not fit in the maximum size of the encoder, as many whole RRsets as fit are
+    ///  emitted, the answers first, and the TC flag is set. The returned info carries the counts of the
+    ///  records which were emitted.
+    pub fn destructive_emit(self, encoder: &mut BinEncoder<'_>) -> ProtoResult<ResponseInfo> {
         // soa records are part of the nameserver section
-        let mut name_servers = self.name_servers.chain(self.soa);
+        let name_servers = self.name_servers.chain(self.soa);
+
+        // the space for the TSIG is reserved, it is appended after the message is emitted
+        #[cfg(feature = "dnssec")]
+        let (start, max_size) = (encoder.offset(), encoder.max_size());
+        #[cfg(feature = "dnssec")]
+        if let Some(tsig) = &self.tsig {
+            let reserved = tsig.record_len()?.min(usize::from(u16::MAX)) as u16;
+            encoder.set_max_size(max_size.saturating_sub(reserved));
+        }
 
-        message::emit_message_parts(
+        let header = message::emit_message_parts(
             &self.header,
             &mut EmptyOrQueries::from(self.query),
-            &mut self.answers,
-            &mut name_servers,
-            &mut self.additionals,
+            &mut message::RRSets(self.answers),
+            &mut message::RRSets(name_servers),
+            &mut message::RRSets(self.additionals),
             self.edns.as_ref(),
             &self.sig0,
             encoder,
-        )
-        .map(Into::into)
+        );
+
+        #[cfg(feature = "dnssec")]
+        encoder.set_max_size(max_size);
+        let header = header?;
+
+        // the TSIG must be the last record, and covers all of the message before it
+        #[cfg(feature = "dnssec")]
+        let header = match &self.tsig {
+            Some(tsig) => tsig.sign(encoder, start, header)?,
+            None => header,
+        };
+
+        Ok(header.into())
     }
 }
 
@@ -183,6 +261,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: additionals.into_iter(),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 
@@ -207,6 +287,8 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: Box::new(None.into_iter()),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 
@@ -241,6 +323,141 @@ impl<'q> MessageResponseBuilder<'q> {
             additionals: Box::new(None.into_iter()),
             sig0: self.sig0.unwrap_or_default(),
             edns: self.edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
+        }
+    }
+}
+
+/// A builder of the response to a request, which holds the header and the records of the response
+///
+/// A custom `RequestHandler` can start the response with `from_request`, add its records and set
+///  the header bits, and then finish it with `build`, e.g.
+///
+/// ```ignore
+/// let mut response = ResponseBuilder::from_request(request);
+/// response
+///     .answers(&answers)
+///     .authoritative(true)
+///     .response_code(ResponseCode::NoError);
+/// response_handle.send_response(response.build()).await
+/// ```
+pub struct ResponseBuilder<'q> {
+    query: &'q WireQuery,
+    edns: Option<Edns>,
+    header: Header,
+    answers: Vec<&'q Record>,
+    name_servers: Vec<&'q Record>,
+    additionals: Vec<&'q Record>,
+}
+
+impl<'q> ResponseBuilder<'q> {
+    /// Constructs a new response builder for the request
+    ///
+    /// The response has the ID, op code and query of the request, and the QR flag set. If the
+    ///  request has EDNS, so does the response, with the payload size of the request.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - the request to which this is a response
+    pub fn from_request(request: &'q Request) -> Self {
+        let edns = request.edns().map(|req_edns| {
+            let mut resp_edns = Edns::new();
+            resp_edns.set_dnssec_ok(req_edns.dnssec_ok());
+            resp_edns.set_max_payload(req_edns.max_payload().max(512));
+            resp_edns
+        });
+
+        ResponseBuilder {
+            query: request.raw_query(),
+            edns,
+            header: Header::response_from_request(request.header()),
+            answers: Vec::new(),
+            name_servers: Vec::new(),
+            additionals: Vec::new(),
+        }
+    }
+
+    /// Replaces the EDNS of the response, `None` removes it
+    pub fn edns(&mut self, edns: Option<Edns>) -> &mut Self {
+        self.edns = edns;
+        self
+    }
+
+    /// Adds records to the answer section of the response
+    pub fn answers<I>(&mut self, records: I) -> &mut Self
+    where
+        I: IntoIterator<Item = &'q Record>,
+    {
+        self.answers.extend(records);
+        self
+    }
+
+    /// Adds records to the authority section of the response
+    pub fn name_servers<I>(&mut self, records: I) -> &mut Self
+    where
+        I: IntoIterator<Item = &'q Record>,
+    {
+        self.name_servers.extend(records);
+        self
+    }
+
+    /// Adds records to the additional section of the response
+    pub fn additionals<I>(&mut self, records: I) -> &mut Self
+    where
+        I: IntoIterator<Item = &'q Record>,
+    {
+        self.additionals.extend(records);
+        self
+    }
+
+    /// Sets the response code of the response
+    ///
+    /// The high bits of extended response codes are carried in the EDNS of the response.
+    pub fn response_code(&mut self, response_code: ResponseCode) -> &mut Self {
+        self.header.set_response_code(response_code);
+        self
+    }
+
+    /// Sets the AA flag of the response
+    pub fn authoritative(&mut self, authoritative: bool) -> &mut Self {
+        self.header.set_authoritative(authoritative);
+        self
+    }
+
+    /// Sets the RA flag of the response
+    pub fn recursion_available(&mut self, recursion_available: bool) -> &mut Self {
+        self.header.set_recursion_available(recursion_available);
+        self
+    }
+
+    /// Constructs the new MessageResponse from the header and the records set on the builder
+    pub fn build(
+        self,
+    ) -> MessageResponse<
+        'q,
+        'q,
+        impl Iterator<Item = &'q Record> + Send + 'q,
+        impl Iterator<Item = &'q Record> + Send + 'q,
+        impl Iterator<Item = &'q Record> + Send + 'q,
+        impl Iterator<Item = &'q Record> + Send + 'q,
+    > {
+        let mut edns = self.edns;
+        if let Some(edns) = edns.as_mut() {
+            edns.set_rcode_high(self.header.response_code().high());
+        }
+
+        MessageResponse {
+            header: self.header,
+            query: Some(self.query),
+            answers: self.answers.into_iter(),
+            name_servers: self.name_servers.into_iter(),
+            soa: None.into_iter(),
+            additionals: self.additionals.into_iter(),
+            sig0: Vec::new(),
+            edns,
+            #[cfg(feature = "dnssec")]
+            tsig: None,
         }
     }
 }
@@ -251,12 +468,29 @@ mod tests {
     use std::net::Ipv4Addr;
     use std::str::FromStr;
 
-    use crate::proto::op::{Header, Message};
-    use crate::proto::rr::{DNSClass, Name, RData, Record};
-    use crate::proto::serialize::binary::BinEncoder;
+    use crate::authority::MessageRequest;
+    use crate::proto::op::{Header, Message, MessageType, OpCode, Query};
+    use crate::proto::rr::{DNSClass, Name, RData, Record, RecordType};
+    use crate::proto::serialize::binary::{BinDecodable, BinEncoder};
+    use crate::server::Protocol;
 
     use super::*;
 
+    /// Returns `count` RRsets of `len` A records each
+    fn rrsets(count: usize, len: u8) -> Vec<Record> {
+        (0..count)
+            .flat_map(|i| {
+                (0..len).map(move |j| {
+                    Record::from_rdata(
+                        Name::from_str(&format!("www{}.example.com.", i)).unwrap(),
+                        3600,
+                        RData::A(Ipv4Addr::new(192, 0, 2, j)),
+                    )
+                })
+            })
+            .collect()
+    }
+
     #[test]
     fn test_truncation_ridiculous_number_answers() {
         let mut buf = Vec::with_capacity(512);
@@ -269,16 +503,19 @@ mod tests {
                 .set_data(Some(RData::A(Ipv4Addr::new(93, 184, 216, 34))))
                 .set_dns_class(DNSClass::NONE)
                 .clone();
+            let answers = rrsets(100, 1);
 
             let message = MessageResponse {
                 header: Header::new(),
                 query: None,
-                answers: iter::repeat(&answer),
+                answers: answers.iter(),
                 name_servers: iter::once(&answer),
                 soa: iter::once(&answer),
                 additionals: iter::once(&answer),
                 sig0: vec![],
                 edns: None,
+                #[cfg(feature = "dnssec")]
+                tsig: None,
             };
 
             message
@@ -306,15 +543,19 @@ mod tests {
                 .set_dns_class(DNSClass::NONE)
                 .clone();
 
+            let name_servers = rrsets(100, 1);
+
             let message = MessageResponse {
                 header: Header::new(),
                 query: None,
                 answers: iter::empty(),
-                name_servers: iter::repeat(&answer),
+                name_servers: name_servers.iter(),
                 soa: iter::repeat(&answer),
                 additionals: iter::repeat(&answer),
                 sig0: vec![],
                 edns: None,
+                #[cfg(feature = "dnssec")]
+                tsig: None,
             };
 
             message
@@ -327,4 +568,122 @@ mod tests {
         assert_eq!(response.answer_count(), 0);
         assert!(response.name_server_count() > 1);
     }
+
+    #[test]
+    fn test_response_builder() {
+        let name = Name::from_str("www.example.com.").unwrap();
+        let mut request = Message::new();
+        request
+            .set_id(0x1234)
+            .set_op_code(OpCode::Query)
+            .set_recursion_desired(true)
+            .add_query(Query::query(name.clone(), RecordType::A));
+        let mut edns = Edns::new();
+        edns.set_max_payload(1232);
+        request.set_edns(edns);
+
+        let request = MessageRequest::from_bytes(&request.to_vec().unwrap()).unwrap();
+        let request = Request::new(request, ([192, 0, 2, 1], 53).into(), Protocol::Udp);
+
+        let answers = rrsets(1, 2);
+        let name_servers = [Record::from_rdata(
+            Name::from_str("example.com.").unwrap(),
+            3600,
+            RData::NS(Name::from_str("ns.example.com.").unwrap()),
+        )];
+
+        let mut builder = ResponseBuilder::from_request(&request);
+        builder
+            .answers(&answers)
+            .name_servers(&name_servers)
+            .authoritative(true)
+            .response_code(ResponseCode::NoError);
+
+        let mut buf = Vec::with_capacity(512);
+        builder
+            .build()
+            .destructive_emit(&mut BinEncoder::new(&mut buf))
+            .expect("failed to encode");
+
+        let response = Message::from_vec(&buf).expect("failed to decode");
+        assert_eq!(response.id(), 0x1234);
+        assert_eq!(response.message_type(), MessageType::Response);
+        assert_eq!(response.op_code(), OpCode::Query);
+        assert!(response.authoritative());
+        assert!(response.recursion_desired());
+        assert!(!response.recursion_available());
+        assert!(!response.truncated());
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert_eq!(response.queries(), &[Query::query(name, RecordType::A)]);
+        assert_eq!(response.answers(), &answers[..]);
+        assert_eq!(response.name_servers(), &name_servers[..]);
+        assert!(response.additionals().is_empty());
+        assert_eq!(
+            response.extensions().as_ref().map(Edns::max_payload),
+            Some(1232)
+        );
+
+        // extended response codes are split with the EDNS
+        let mut builder = ResponseBuilder::from_request(&request);
+        builder
+            .recursion_available(true)
+            .response_code(ResponseCode::BADVERS);
+
+        let mut buf = Vec::with_capacity(512);
+        builder
+            .build()
+            .destructive_emit(&mut BinEncoder::new(&mut buf))
+            .expect("failed to encode");
+
+        let response = Message::from_vec(&buf).expect("failed to decode");
+        assert!(response.recursion_available());
+        assert!(!response.authoritative());
+        assert_eq!(response.response_code(), ResponseCode::BADVERS);
+        assert!(response.answers().is_empty());
+    }
+
+    #[test]
+    fn test_truncation_whole_rrsets() {
+        let answers = rrsets(8, 10);
+        let additionals = rrsets(1, 1);
+        let mut buf = Vec::with_capacity(512);
+        let info = {
+            let mut encoder = BinEncoder::new(&mut buf);
+            encoder.set_max_size(512);
+
+            let message = MessageResponse {
+                header: Header::new(),
+                query: None,
+                answers: answers.iter(),
+                name_servers: iter::empty(),
+                soa: iter::empty(),
+                additionals: additionals.iter(),
+                sig0: vec![],
+                edns: Some(Edns::new()),
+                #[cfg(feature = "dnssec")]
+                tsig: None,
+            };
+            assert_eq!(message.max_payload(), 512);
+
+            message
+                .destructive_emit(&mut encoder)
+                .expect("failed to encode")
+        };
+
+        assert!(buf.len() <= 512);
+        let response = Message::from_vec(&buf).expect("failed to decode");
+        assert!(response.header().truncated());
+        assert!(info.truncated());
+        assert_eq!(response.answer_count(), info.answer_count());
+
+        // only whole RRsets, and the records of the following sections are left out
+        assert!(response.answer_count() > 0);
+        assert_eq!(response.answer_count() % 10, 0);
+        assert!(response.answer_count() < 80);
+        assert_eq!(response.answers(), &answers[..response.answers().len()]);
+        assert!(response.additionals().is_empty());
+
+        // the EDNS always fits
+        assert!(response.extensions().is_some());
+    }
 }
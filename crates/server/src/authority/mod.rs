@@ -12,6 +12,7 @@ use crate::client::op::ResponseCode;
 /// Result of an Update operation
 pub type UpdateResult<T> = Result<T, ResponseCode>;
 
+mod acl;
 mod auth_lookup;
 #[allow(clippy::module_inception)]
 mod authority;
@@ -20,8 +21,11 @@ mod catalog;
 mod error;
 pub(crate) mod message_request;
 mod message_response;
+#[cfg(feature = "dnssec")]
+mod tsig;
 mod zone_type;
 
+pub use self::acl::ZoneAcl;
 pub use self::auth_lookup::{
     AnyRecords, AuthLookup, AuthLookupIter, LookupRecords, LookupRecordsIter,
 };
@@ -30,9 +34,9 @@ pub use self::authority_object::{AuthorityObject, EmptyLookup, LookupObject};
 pub use self::catalog::Catalog;
 pub use self::error::{LookupError, LookupResult};
 pub use self::message_request::{MessageRequest, Queries, UpdateRequest};
-pub use self::message_response::{MessageResponse, MessageResponseBuilder};
+pub use self::message_response::{MessageResponse, MessageResponseBuilder, ResponseBuilder};
 pub use self::zone_type::ZoneType;
 
 #[cfg(feature = "dnssec")]
 #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
-pub use self::authority::DnssecAuthority;
+pub use self::authority::{DnssecAuthority, RolloverTiming};
@@ -0,0 +1,274 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! TSIG, [RFC 8945](https://tools.ietf.org/html/rfc8945), verification of requests and signing of responses
+
+use std::{
+    collections::HashMap,
+    fmt, io,
+    sync::{Arc, Mutex},
+};
+
+use log::{debug, warn};
+
+use crate::{
+    authority::{MessageRequest, MessageResponse},
+    client::{
+        op::{Header, ResponseCode},
+        rr::{dnssec::tsig::TSigner, LowerName, Name, RData, Record},
+        serialize::binary::{BinEncodable, BinEncoder},
+    },
+    proto::{
+        error::ProtoResult,
+        rr::dnssec::rdata::{
+            tsig::{make_tsig_record, TSIG},
+            DNSSECRData,
+        },
+    },
+    server::{ResponseHandler, ResponseInfo},
+};
+
+/// Signs a response with the key of the request, or attaches the unsigned TSIG of an error response
+#[derive(Clone)]
+pub(crate) struct ResponseSigner {
+    key_name: Name,
+    /// None for error responses which must not be signed, i.e. BADKEY and BADSIG
+    signer: Option<TSigner>,
+    request_mac: Vec<u8>,
+    /// The MAC of the previous message of a multi-message response, e.g. AXFR, which the next one covers
+    previous_mac: Arc<Mutex<Option<Vec<u8>>>>,
+    pre_tsig: TSIG,
+}
+
+impl ResponseSigner {
+    /// True if the request was not verified, the response must be NOTAUTH with the TSIG error
+    pub(crate) fn is_error(&self) -> bool {
+        self.pre_tsig.error() != 0
+    }
+
+    /// The name of the key of the request
+    pub(crate) fn key_name(&self) -> &Name {
+        &self.key_name
+    }
+
+    /// The length of the TSIG record which `sign` appends at most, the space to leave for it in the response
+    pub(crate) fn record_len(&self) -> ProtoResult<usize> {
+        let mac_len = match &self.signer {
+            Some(signer) => signer.algorithm().output_len()?,
+            None => 0,
+        };
+
+        let mut buffer = Vec::new();
+        let mut encoder = BinEncoder::new(&mut buffer);
+        make_tsig_record(
+            self.key_name.clone(),
+            self.pre_tsig.clone().set_mac(vec![0; mac_len]),
+        )
+        .emit(&mut encoder)?;
+        Ok(encoder.len())
+    }
+
+    /// Appends the TSIG record to the response emitted with `header` in the encoder since `start`
+    ///
+    /// [RFC 8945](https://tools.ietf.org/html/rfc8945#section-5.3), the MAC of the response covers the MAC of the
+    ///  request, the response and the TSIG variables. The MAC of each following message of a multi-message
+    ///  response covers the MAC of the message before it, the message and only the timers of the TSIG,
+    ///  section 5.3.1. The header is emitted again, with the TSIG counted in the additional section.
+    pub(crate) fn sign(
+        &self,
+        encoder: &mut BinEncoder<'_>,
+        start: usize,
+        mut header: Header,
+    ) -> ProtoResult<Header> {
+        let tsig = match &self.signer {
+            Some(signer) => {
+                let mut previous_mac = self.previous_mac.lock().expect("tsig lock poisoned");
+
+                let mut tbs = Vec::with_capacity(encoder.offset() - start + 512);
+                {
+                    let mut tbs_encoder = BinEncoder::new(&mut tbs);
+                    let mac = previous_mac.as_ref().unwrap_or(&self.request_mac);
+                    tbs_encoder.emit_u16(mac.len() as u16)?;
+                    tbs_encoder.emit_vec(mac)?;
+                    tbs_encoder.emit_vec(encoder.slice_of(start, encoder.offset()))?;
+                    if previous_mac.is_none() {
+                        self.pre_tsig
+                            .emit_tsig_for_mac(&mut tbs_encoder, &self.key_name)?;
+                    } else {
+                        let time = self.pre_tsig.time();
+                        tbs_encoder.emit_u16((time >> 32) as u16)?;
+                        tbs_encoder.emit_u32(time as u32)?;
+                        tbs_encoder.emit_u16(self.pre_tsig.fudge())?;
+                    }
+                }
+
+                let mac = signer.sign(&tbs)?;
+                *previous_mac = Some(mac.clone());
+                self.pre_tsig.clone().set_mac(mac)
+            }
+            None => self.pre_tsig.clone(),
+        };
+
+        make_tsig_record(self.key_name.clone(), tsig).emit(encoder)?;
+
+        let end = encoder.offset();
+        header.set_additional_count(header.additional_count() + 1);
+        encoder.set_offset(start);
+        header.emit(encoder)?;
+        encoder.set_offset(end);
+
+        Ok(header)
+    }
+}
+
+impl fmt::Debug for ResponseSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseSigner")
+            .field("key_name", &self.key_name)
+            .field("signed", &self.signer.is_some())
+            .field("pre_tsig", &self.pre_tsig)
+            .finish()
+    }
+}
+
+/// Verifies the TSIG of the request, if any, against the known keys
+///
+/// [RFC 8945](https://tools.ietf.org/html/rfc8945#section-5.2), an unknown key is BADKEY and an invalid MAC is
+///  BADSIG, the responses to those are unsigned. A request signed outside of the fudge of `now` is BADTIME, the
+///  response to that is signed and carries the time of the server.
+///
+/// # Returns
+///
+/// None if the request is not signed, otherwise the `ResponseSigner` for the response, which `is_error` if the
+///  request was not verified.
+pub(crate) fn verify_request(
+    keys: &HashMap<LowerName, TSigner>,
+    request: &MessageRequest,
+    now: u64,
+) -> Option<ResponseSigner> {
+    let record = request.tsig()?;
+    let tsig = match record.data() {
+        Some(RData::DNSSEC(DNSSECRData::TSIG(tsig))) => tsig,
+        _ => return None,
+    };
+
+    let error_signer =
+        |error: ResponseCode, signer: Option<TSigner>, other: Vec<u8>| ResponseSigner {
+            key_name: record.name().clone(),
+            signer,
+            request_mac: tsig.mac().to_vec(),
+            previous_mac: Arc::new(Mutex::new(None)),
+            pre_tsig: TSIG::new(
+                tsig.algorithm().clone(),
+                tsig.time(),
+                tsig.fudge(),
+                Vec::new(),
+                tsig.oid(),
+                error.into(),
+                other,
+            ),
+        };
+
+    // 5.2.1.  Key Check and Error Handling
+    let signer = match keys
+        .get(&LowerName::from(record.name()))
+        .filter(|signer| signer.algorithm() == tsig.algorithm())
+    {
+        Some(signer) => signer,
+        None => {
+            warn!(
+                "unknown tsig key: {} algorithm: {}",
+                record.name(),
+                tsig.algorithm()
+            );
+            return Some(error_signer(ResponseCode::BADKEY, None, Vec::new()));
+        }
+    };
+
+    // 5.2.2.  MAC Check and Error Handling
+    let (request_mac, valid_time, time) = match request
+        .signed_original()
+        .ok_or_else(|| "missing original message".into())
+        .and_then(|original| signer.verify_message_byte(None, original, true))
+    {
+        Ok(verified) => verified,
+        Err(e) => {
+            warn!("invalid tsig from key: {}: {}", record.name(), e);
+            return Some(error_signer(ResponseCode::BADSIG, None, Vec::new()));
+        }
+    };
+
+    // 5.2.3.  Time Check and Error Handling
+    if !valid_time.contains(&now) {
+        warn!(
+            "tsig from key: {} outside of time window, signed: {} now: {}",
+            record.name(),
+            time,
+            now
+        );
+
+        // the time of the server is sent as a 48 bit value
+        let other = now.to_be_bytes()[2..].to_vec();
+        return Some(error_signer(
+            ResponseCode::BADTIME,
+            Some(signer.clone()),
+            other,
+        ));
+    }
+
+    debug!("verified tsig from key: {}", record.name());
+    Some(ResponseSigner {
+        key_name: record.name().clone(),
+        signer: Some(signer.clone()),
+        request_mac,
+        previous_mac: Arc::new(Mutex::new(None)),
+        pre_tsig: TSIG::new(
+            tsig.algorithm().clone(),
+            now,
+            tsig.fudge(),
+            Vec::new(),
+            tsig.oid(),
+            0,
+            Vec::new(),
+        ),
+    })
+}
+
+/// A `ResponseHandler` which signs the response with the TSIG key of the request
+#[derive(Clone)]
+pub(crate) struct TsigResponseHandler<R: ResponseHandler> {
+    inner: R,
+    signer: Option<ResponseSigner>,
+}
+
+impl<R: ResponseHandler> TsigResponseHandler<R> {
+    /// Wraps the handler, responses are signed if there is a `signer`
+    pub(crate) fn new(inner: R, signer: Option<ResponseSigner>) -> Self {
+        Self { inner, signer }
+    }
+}
+
+#[async_trait::async_trait]
+impl<R: ResponseHandler> ResponseHandler for TsigResponseHandler<R> {
+    async fn send_response<'a>(
+        &mut self,
+        mut response: MessageResponse<
+            '_,
+            'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+        >,
+    ) -> io::Result<ResponseInfo> {
+        if let Some(signer) = &self.signer {
+            response.set_tsig(signer.clone());
+        }
+
+        self.inner.send_response(response).await
+    }
+}
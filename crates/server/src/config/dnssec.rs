@@ -121,6 +121,7 @@ impl KeyConfig {
             "ECDSAP256SHA256" => Ok(Algorithm::ECDSAP256SHA256),
             "ECDSAP384SHA384" => Ok(Algorithm::ECDSAP384SHA384),
             "ED25519" => Ok(Algorithm::ED25519),
+            "ED448" => Ok(Algorithm::ED448),
             s => Err(format!("unrecognized string {}", s).into()),
         }
     }
@@ -253,7 +254,7 @@ impl TlsCertConfig {
 /// for custom keys can be found elsewhere.
 ///
 /// the currently supported set of supported_algorithms are
-/// ["RSASHA256", "RSASHA512", "ECDSAP256SHA256", "ECDSAP384SHA384", "ED25519"]
+/// ["RSASHA256", "RSASHA512", "ECDSAP256SHA256", "ECDSAP384SHA384", "ED25519", "ED448"]
 ///
 /// keys are listed in pairs of key_name and algorithm, the search path is the
 /// same directory has the zone $file:
@@ -17,6 +17,7 @@ use std::str::FromStr;
 use std::time::Duration;
 
 use cfg_if::cfg_if;
+use ipnet::IpNet;
 use log;
 use serde::{self, Deserialize};
 use toml;
@@ -24,8 +25,9 @@ use toml;
 use crate::client::rr::Name;
 use crate::proto::error::ProtoResult;
 
-use crate::authority::ZoneType;
+use crate::authority::{ZoneAcl, ZoneType};
 use crate::error::{ConfigError, ConfigResult};
+use crate::server::RrlConfig;
 use crate::store::StoreConfig;
 
 static DEFAULT_PATH: &str = "/var/named"; // TODO what about windows (do I care? ;)
@@ -54,8 +56,21 @@ pub struct Config {
     quic_listen_port: Option<u16>,
     /// Timeout associated to a request before it is closed.
     tcp_request_timeout: Option<u64>,
+    /// Idle timeout in seconds to answer the edns-tcp-keepalive option with, per RFC 7828, default is to not answer it
+    tcp_keepalive: Option<u64>,
+    /// Longest keepalive interval in seconds granted to DSO sessions, per RFC 8490, default is to not support DSO
+    dso_keepalive: Option<u64>,
+    /// Response Rate Limiting of UDP responses, default is to not limit them
+    rate_limit: Option<RateLimitConfig>,
     /// Level at which to log, default is INFO
     log_level: Option<String>,
+    /// Answer ANY queries minimally, per RFC 8482, default is false
+    minimal_any: Option<bool>,
+    /// Size in bytes to which the messages of a zone transfer are filled with records, default is 16 KiB
+    axfr_message_size: Option<usize>,
+    /// Answers to the CHAOS class queries for the version and name of the server, default is to refuse them
+    #[serde(default)]
+    chaos: ChaosConfig,
     /// Base configuration directory, i.e. root path for zones
     directory: Option<String>,
     /// List of configurations for zones
@@ -119,6 +134,36 @@ impl Config {
         )
     }
 
+    /// idle timeout of the edns-tcp-keepalive option, see [`crate::ServerFuture::set_tcp_keepalive`]
+    pub fn get_tcp_keepalive(&self) -> Option<Duration> {
+        self.tcp_keepalive.map(Duration::from_secs)
+    }
+
+    /// longest keepalive interval of DSO sessions, see [`crate::ServerFuture::set_dso_keepalive`]
+    pub fn get_dso_keepalive(&self) -> Option<Duration> {
+        self.dso_keepalive.map(Duration::from_secs)
+    }
+
+    /// response rate limiting of UDP responses, see [`crate::ServerFuture::set_rrl`]
+    pub fn get_rrl(&self) -> Option<RrlConfig> {
+        self.rate_limit.as_ref().map(RateLimitConfig::to_rrl_config)
+    }
+
+    /// answer ANY queries minimally, see [`crate::authority::Catalog::set_minimal_any`]
+    pub fn is_minimal_any(&self) -> bool {
+        self.minimal_any.unwrap_or(false)
+    }
+
+    /// size of the messages of a zone transfer, see [`crate::authority::Catalog::set_axfr_message_size`]
+    pub fn get_axfr_message_size(&self) -> Option<usize> {
+        self.axfr_message_size
+    }
+
+    /// answers to the CHAOS class queries, see [`crate::authority::Catalog::set_chaos`]
+    pub fn get_chaos(&self) -> &ChaosConfig {
+        &self.chaos
+    }
+
     /// specify the log level which should be used, ["Trace", "Debug", "Info", "Warn", "Error"]
     pub fn get_log_level(&self) -> log::Level {
         if let Some(ref level_str) = self.log_level {
@@ -167,6 +212,50 @@ impl FromStr for Config {
     }
 }
 
+/// Configuration of the answers to the CHAOS class TXT queries identifying the server
+///
+/// These are `version.bind.` and `version.server.` for the version of the server, and `hostname.bind.` and
+///  `id.server.` for its name, [RFC 4892](https://tools.ietf.org/html/rfc4892). As they disclose details of the
+///  server, they are refused unless `enabled`, and then only answered for the strings which are configured.
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Default)]
+pub struct ChaosConfig {
+    /// Answer the CHAOS class queries at all, default is false
+    #[serde(default)]
+    pub enabled: bool,
+    /// The version of the server, for `version.bind.` and `version.server.`
+    pub version: Option<String>,
+    /// The name of the server, for `hostname.bind.` and `id.server.`
+    pub hostname: Option<String>,
+}
+
+/// Configuration of Response Rate Limiting, see [`RrlConfig`] for the meaning and defaults of the fields
+#[derive(Deserialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct RateLimitConfig {
+    /// Number of responses of one type sent per second to a client prefix before limiting
+    pub responses_per_second: Option<u32>,
+    /// Period in seconds over which the rate is accounted
+    pub window: Option<u64>,
+    /// Every `slip`th limited response is sent truncated, `0` drops all limited responses
+    pub slip: Option<u32>,
+    /// Maximum number of prefix and response type buckets tracked at once
+    pub max_entries: Option<usize>,
+}
+
+impl RateLimitConfig {
+    /// The limits to apply, with the unset fields taken from the default of [`RrlConfig`]
+    pub fn to_rrl_config(&self) -> RrlConfig {
+        let default = RrlConfig::default();
+        RrlConfig {
+            responses_per_second: self
+                .responses_per_second
+                .unwrap_or(default.responses_per_second),
+            window: self.window.map_or(default.window, Duration::from_secs),
+            slip: self.slip.unwrap_or(default.slip),
+            max_entries: self.max_entries.unwrap_or(default.max_entries),
+        }
+    }
+}
+
 /// Configuration for a zone
 #[derive(Deserialize, PartialEq, Debug)]
 pub struct ZoneConfig {
@@ -188,6 +277,15 @@ pub struct ZoneConfig {
     /// Store configurations, TODO: allow chained Stores
     #[serde(default)]
     pub stores: Option<StoreConfig>,
+    /// Prefixes of the clients which may query the zone, all clients if empty
+    #[serde(default)]
+    pub allow_query: Vec<IpNet>,
+    /// Prefixes of the clients which may transfer the zone, see `allow_axfr`
+    #[serde(default)]
+    pub allow_transfer: Vec<IpNet>,
+    /// Names of the TSIG keys which may transfer the zone from any client
+    #[serde(default)]
+    pub allow_transfer_keys: Vec<String>,
 }
 
 impl ZoneConfig {
@@ -220,6 +318,9 @@ impl ZoneConfig {
             enable_dnssec,
             keys,
             stores: None,
+            allow_query: Vec::new(),
+            allow_transfer: Vec::new(),
+            allow_transfer_keys: Vec::new(),
         }
     }
 
@@ -253,6 +354,21 @@ impl ZoneConfig {
         self.allow_axfr.unwrap_or(false)
     }
 
+    /// the clients and TSIG keys which may query and transfer the zone
+    pub fn get_acl(&self) -> ProtoResult<ZoneAcl> {
+        let allow_transfer_keys = self
+            .allow_transfer_keys
+            .iter()
+            .map(|key| Name::parse(key, Some(&Name::new())).map(Into::into))
+            .collect::<ProtoResult<_>>()?;
+
+        Ok(ZoneAcl {
+            allow_query: self.allow_query.clone(),
+            allow_transfer: self.allow_transfer.clone(),
+            allow_transfer_keys,
+        })
+    }
+
     /// declare that this zone should be signed, see keys for configuration of the keys for signing
     pub fn is_dnssec_enabled(&self) -> bool {
         cfg_if! {
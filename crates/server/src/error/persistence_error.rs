@@ -24,6 +24,19 @@ pub enum ErrorKind {
     #[error("error recovering from journal: {}", _0)]
     Recovery(&'static str),
 
+    /// The journal was started from a different version of the zone than the one loaded
+    #[error(
+        "journal base serial: {} does not match zone serial: {}",
+        journal,
+        zone
+    )]
+    SerialMismatch {
+        /// The serial of the zone the journal was started from
+        journal: u32,
+        /// The serial of the loaded zone
+        zone: u32,
+    },
+
     /// The number of inserted records didn't match the expected amount
     #[error("wrong insert count: {} expect: {}", got, expect)]
     WrongInsertCount {
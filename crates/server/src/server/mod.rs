@@ -14,11 +14,15 @@ mod protocol;
 mod quic_handler;
 mod request_handler;
 mod response_handler;
+mod rrl;
 mod server_future;
 mod timeout_stream;
+mod views;
 
 pub use self::protocol::Protocol;
 pub use self::request_handler::{Request, RequestHandler, RequestInfo, ResponseInfo};
 pub use self::response_handler::{ResponseHandle, ResponseHandler};
+pub use self::rrl::RrlConfig;
 pub use self::server_future::ServerFuture;
 pub use self::timeout_stream::TimeoutStream;
+pub(crate) use self::views::{unmap_ipv4, Views};
@@ -5,7 +5,7 @@
 // http://opensource.org/licenses/MIT>, at your option. This file may not be
 // copied, modified, or distributed except according to those terms.
 
-use std::{io, net::SocketAddr};
+use std::{io, net::SocketAddr, time::Duration};
 
 use log::debug;
 use trust_dns_proto::rr::Record;
@@ -13,8 +13,8 @@ use trust_dns_proto::rr::Record;
 use crate::{
     authority::MessageResponse,
     client::serialize::binary::BinEncoder,
-    proto::{xfer::SerialMessage, BufDnsStreamHandle, DnsStreamHandle},
-    server::ResponseInfo,
+    proto::{rr::rdata::opt::EdnsOption, xfer::SerialMessage, BufDnsStreamHandle, DnsStreamHandle},
+    server::{Protocol, ResponseInfo},
 };
 
 /// A handler for send a response to a client
@@ -45,12 +45,35 @@ pub trait ResponseHandler: Clone + Send + Sync + Unpin + 'static {
 pub struct ResponseHandle {
     dst: SocketAddr,
     stream_handle: BufDnsStreamHandle,
+    protocol: Option<Protocol>,
+    tcp_keepalive: Option<u16>,
 }
 
 impl ResponseHandle {
     /// Returns a new `ResponseHandle` for sending a response message
     pub fn new(dst: SocketAddr, stream_handle: BufDnsStreamHandle) -> Self {
-        Self { dst, stream_handle }
+        Self {
+            dst,
+            stream_handle,
+            protocol: None,
+            tcp_keepalive: None,
+        }
+    }
+
+    /// Sets the protocol the response is sent over
+    ///
+    /// Responses over UDP are truncated to the payload size of their EDNS, see `MessageResponse::max_payload`.
+    pub fn with_protocol(mut self, protocol: Protocol) -> Self {
+        self.protocol = Some(protocol);
+        self
+    }
+
+    /// Adds the edns-tcp-keepalive option with the idle timeout to the response, if it has EDNS
+    pub(crate) fn with_tcp_keepalive(mut self, idle_timeout: Option<Duration>) -> Self {
+        // in units of 100 milliseconds
+        self.tcp_keepalive = idle_timeout
+            .map(|idle_timeout| (idle_timeout.as_millis() / 100).min(u128::from(u16::MAX)) as u16);
+        self
     }
 }
 
@@ -61,7 +84,7 @@ impl ResponseHandler for ResponseHandle {
     /// self is consumed as only one message should ever be sent in response to a Request
     async fn send_response<'a>(
         &mut self,
-        response: MessageResponse<
+        mut response: MessageResponse<
             '_,
             'a,
             impl Iterator<Item = &'a Record> + Send + 'a,
@@ -75,9 +98,17 @@ impl ResponseHandler for ResponseHandle {
             response.header().id(),
             response.header().response_code(),
         );
+        if let (Some(timeout), Some(edns)) = (self.tcp_keepalive, response.edns_mut()) {
+            edns.options_mut()
+                .insert(EdnsOption::TcpKeepalive(Some(timeout)));
+        }
+
         let mut buffer = Vec::with_capacity(512);
         let encode_result = {
             let mut encoder = BinEncoder::new(&mut buffer);
+            if let Some(Protocol::Udp) = self.protocol {
+                encoder.set_max_size(response.max_payload());
+            }
             response.destructive_emit(&mut encoder)
         };
 
@@ -88,6 +119,14 @@ impl ResponseHandler for ResponseHandle {
             )
         })?;
 
+        // the responses of a zone transfer come in a row, the connection applies backpressure to them
+        if !matches!(self.protocol, Some(Protocol::Udp)) {
+            self.stream_handle
+                .ready()
+                .await
+                .map_err(|e| io::Error::new(io::ErrorKind::BrokenPipe, e))?;
+        }
+
         self.stream_handle
             .send(SerialMessage::new(buffer, self.dst))
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "unknown"))?;
@@ -0,0 +1,410 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Response Rate Limiting (RRL) for UDP responses
+
+use std::{
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use log::debug;
+use lru_cache::LruCache;
+use trust_dns_proto::rr::Record;
+
+use crate::{
+    authority::MessageResponse,
+    proto::op::ResponseCode,
+    server::{ResponseHandler, ResponseInfo},
+};
+
+/// Configuration for Response Rate Limiting
+///
+/// RRL limits the rate of identical responses sent to a block of client addresses, which
+///  reduces the value of an authoritative server as an amplifier in reflection attacks. Clients
+///  are grouped by their /24 IPv4 or /56 IPv6 prefix, and each group has a token bucket per type
+///  of response (answers, NXDOMAIN and errors). Only UDP responses are limited, responses over
+///  TCP, TLS, HTTPS and QUIC are always sent, as those can not be used with a spoofed source
+///  address.
+///
+/// At most `max_entries` buckets are kept, when there are more prefixes the least recently seen
+///  is forgotten, and starts from a full bucket when it is seen again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RrlConfig {
+    /// Number of responses of one type sent per second to a client prefix before limiting
+    pub responses_per_second: u32,
+    /// Period over which the rate is accounted. A prefix that exceeds the rate accumulates a debt
+    ///  of up to `responses_per_second * window` responses, which it must pay back before it
+    ///  is answered again, and prefixes not seen for this period are forgotten.
+    pub window: Duration,
+    /// Every `slip`th limited response is sent truncated, instead of being dropped, so that
+    ///  legitimate clients can retry over TCP. `0` drops all limited responses, `1` truncates all.
+    pub slip: u32,
+    /// Maximum number of prefix and response type buckets tracked at once
+    pub max_entries: usize,
+}
+
+impl Default for RrlConfig {
+    fn default() -> Self {
+        Self {
+            responses_per_second: 5,
+            window: Duration::from_secs(15),
+            slip: 2,
+            max_entries: 100_000,
+        }
+    }
+}
+
+/// The type of response for which a rate is tracked
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum ResponseKind {
+    Answer,
+    NxDomain,
+    Error,
+}
+
+impl From<ResponseCode> for ResponseKind {
+    fn from(code: ResponseCode) -> Self {
+        match code {
+            ResponseCode::NoError => Self::Answer,
+            ResponseCode::NXDomain => Self::NxDomain,
+            _ => Self::Error,
+        }
+    }
+}
+
+/// What to do with a response
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RrlAction {
+    /// The response is within the rate, send it
+    Send,
+    /// Send the response truncated, without any records
+    Slip,
+    /// Don't send any response
+    Drop,
+}
+
+struct Bucket {
+    /// number of responses which may still be sent, negative when in debt
+    balance: f64,
+    /// number of limited responses since the last slip, for deciding when to slip
+    limited: u32,
+    last_seen: Instant,
+}
+
+/// A token bucket rate limiter, keyed by client prefix and response type
+pub(crate) struct ResponseRateLimiter {
+    config: RrlConfig,
+    buckets: Mutex<LruCache<(IpAddr, ResponseKind), Bucket>>,
+}
+
+impl ResponseRateLimiter {
+    pub(crate) fn new(config: RrlConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(LruCache::new(config.max_entries.max(1))),
+        }
+    }
+
+    /// Records a response of `response_code` to `src`, returning what should be done with it
+    pub(crate) fn check(&self, src: IpAddr, response_code: ResponseCode) -> RrlAction {
+        self.check_at(src, response_code, Instant::now())
+    }
+
+    fn check_at(&self, src: IpAddr, response_code: ResponseCode, now: Instant) -> RrlAction {
+        let rate = f64::from(self.config.responses_per_second);
+        let max_debt = rate * self.config.window.as_secs_f64();
+
+        let mut buckets = self.buckets.lock().expect("rrl lock poisoned");
+
+        // forget about any prefixes which haven't been seen in the last window, the least recently
+        //  seen come first
+        let window = self.config.window;
+        while let Some((_, bucket)) = buckets.iter().next() {
+            if now.saturating_duration_since(bucket.last_seen) < window {
+                break;
+            }
+            buckets.remove_lru();
+        }
+
+        let key = (prefix(src), ResponseKind::from(response_code));
+        if !buckets.contains_key(&key) {
+            // evicts the least recently seen bucket when full
+            buckets.insert(
+                key,
+                Bucket {
+                    balance: rate,
+                    limited: 0,
+                    last_seen: now,
+                },
+            );
+        }
+        let bucket = buckets.get_mut(&key).expect("bucket was just inserted");
+
+        // credit the time since the last response, up to one second of responses
+        let elapsed = now.saturating_duration_since(bucket.last_seen);
+        bucket.balance = (bucket.balance + elapsed.as_secs_f64() * rate).min(rate);
+        bucket.last_seen = now;
+
+        if bucket.balance >= 1.0 {
+            bucket.balance -= 1.0;
+            bucket.limited = 0;
+            return RrlAction::Send;
+        }
+
+        bucket.balance = (bucket.balance - 1.0).max(-max_debt);
+        bucket.limited = bucket.limited.saturating_add(1);
+
+        let slip = self.config.slip;
+        if slip != 0 && bucket.limited >= slip {
+            bucket.limited = 0;
+            RrlAction::Slip
+        } else {
+            RrlAction::Drop
+        }
+    }
+}
+
+/// The /24 or /56 prefix used to group clients
+fn prefix(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(ip) => IpAddr::V4(Ipv4Addr::from(u32::from(ip) & 0xFFFF_FF00)),
+        IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(
+            u128::from(ip) & 0xFFFF_FFFF_FFFF_FF00_0000_0000_0000_0000,
+        )),
+    }
+}
+
+/// A ResponseHandler which applies the rate limit before sending the response to `handler`
+#[derive(Clone)]
+pub(crate) struct RateLimitedResponseHandler<R: ResponseHandler> {
+    src_addr: SocketAddr,
+    limiter: Arc<ResponseRateLimiter>,
+    handler: R,
+}
+
+impl<R: ResponseHandler> RateLimitedResponseHandler<R> {
+    pub(crate) fn new(src_addr: SocketAddr, limiter: Arc<ResponseRateLimiter>, handler: R) -> Self {
+        Self {
+            src_addr,
+            limiter,
+            handler,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<R: ResponseHandler> ResponseHandler for RateLimitedResponseHandler<R> {
+    async fn send_response<'a>(
+        &mut self,
+        response: MessageResponse<
+            '_,
+            'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+            impl Iterator<Item = &'a Record> + Send + 'a,
+        >,
+    ) -> io::Result<ResponseInfo> {
+        let header = *response.header();
+
+        match self
+            .limiter
+            .check(self.src_addr.ip(), header.response_code())
+        {
+            RrlAction::Send => self.handler.send_response(response).await,
+            RrlAction::Slip => {
+                debug!(
+                    "rrl: truncating response:{} to: {}",
+                    header.id(),
+                    self.src_addr
+                );
+                self.handler.send_response(response.into_truncated()).await
+            }
+            RrlAction::Drop => {
+                debug!(
+                    "rrl: dropping response:{} to: {}",
+                    header.id(),
+                    self.src_addr
+                );
+                Ok(header.into())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(slip: u32) -> ResponseRateLimiter {
+        ResponseRateLimiter::new(RrlConfig {
+            responses_per_second: 2,
+            window: Duration::from_secs(5),
+            slip,
+            max_entries: 3,
+        })
+    }
+
+    #[test]
+    fn test_prefix() {
+        assert_eq!(
+            prefix("192.0.2.77".parse().unwrap()),
+            "192.0.2.0".parse::<IpAddr>().unwrap()
+        );
+        assert_eq!(
+            prefix("2001:db8:aa:bbcc::1".parse().unwrap()),
+            "2001:db8:aa:bb00::".parse::<IpAddr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rate_limited() {
+        let limiter = limiter(0);
+        let now = Instant::now();
+        let src: IpAddr = "192.0.2.1".parse().unwrap();
+
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, now),
+            RrlAction::Send
+        );
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, now),
+            RrlAction::Send
+        );
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, now),
+            RrlAction::Drop
+        );
+
+        // the same prefix is limited, but other response types and prefixes are not
+        let neighbor: IpAddr = "192.0.2.200".parse().unwrap();
+        assert_eq!(
+            limiter.check_at(neighbor, ResponseCode::NoError, now),
+            RrlAction::Drop
+        );
+        assert_eq!(
+            limiter.check_at(neighbor, ResponseCode::NXDomain, now),
+            RrlAction::Send
+        );
+        assert_eq!(
+            limiter.check_at("192.0.3.1".parse().unwrap(), ResponseCode::NoError, now),
+            RrlAction::Send
+        );
+
+        // two responses are in debt, so a single second is not enough to send again
+        let later = now + Duration::from_millis(1000);
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, later),
+            RrlAction::Drop
+        );
+        let later = later + Duration::from_millis(1500);
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, later),
+            RrlAction::Send
+        );
+    }
+
+    #[test]
+    fn test_debt_limited_to_window() {
+        let limiter = limiter(0);
+        let now = Instant::now();
+        let src: IpAddr = "2001:db8::1".parse().unwrap();
+
+        for _ in 0..1000 {
+            limiter.check_at(src, ResponseCode::ServFail, now);
+        }
+
+        // at most 10 responses of debt, which takes 5 seconds to pay back
+        let later = now + Duration::from_millis(4500);
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::ServFail, later),
+            RrlAction::Drop
+        );
+        let later = later + Duration::from_millis(1500);
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::ServFail, later),
+            RrlAction::Send
+        );
+    }
+
+    #[test]
+    fn test_slip() {
+        let limiter = limiter(2);
+        let now = Instant::now();
+        let src: IpAddr = "192.0.2.1".parse().unwrap();
+
+        limiter.check_at(src, ResponseCode::NoError, now);
+        limiter.check_at(src, ResponseCode::NoError, now);
+
+        let actions = (0..6)
+            .map(|_| limiter.check_at(src, ResponseCode::NoError, now))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                RrlAction::Drop,
+                RrlAction::Slip,
+                RrlAction::Drop,
+                RrlAction::Slip,
+                RrlAction::Drop,
+                RrlAction::Slip
+            ]
+        );
+
+        let limiter = self::limiter(1);
+        limiter.check_at(src, ResponseCode::NoError, now);
+        limiter.check_at(src, ResponseCode::NoError, now);
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, now),
+            RrlAction::Slip
+        );
+    }
+
+    #[test]
+    fn test_sweep() {
+        let limiter = limiter(0);
+        let now = Instant::now();
+
+        limiter.check_at("192.0.2.1".parse().unwrap(), ResponseCode::NoError, now);
+        limiter.check_at("192.0.3.1".parse().unwrap(), ResponseCode::NoError, now);
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 2);
+
+        let later = now + Duration::from_secs(6);
+        limiter.check_at("192.0.4.1".parse().unwrap(), ResponseCode::NoError, later);
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_max_entries() {
+        let limiter = limiter(0);
+        let now = Instant::now();
+        let src: IpAddr = "192.0.2.1".parse().unwrap();
+
+        limiter.check_at(src, ResponseCode::NoError, now);
+        limiter.check_at(src, ResponseCode::NoError, now);
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, now),
+            RrlAction::Drop
+        );
+
+        // many other prefixes never grow the table past its limit
+        for i in 0..=255 {
+            let other = IpAddr::V4(Ipv4Addr::new(198, 51, i, 1));
+            limiter.check_at(other, ResponseCode::NoError, now);
+            assert!(limiter.buckets.lock().unwrap().len() <= 3);
+        }
+
+        // and the least recently seen prefixes were forgotten
+        assert_eq!(
+            limiter.check_at(src, ResponseCode::NoError, now),
+            RrlAction::Send
+        );
+    }
+}
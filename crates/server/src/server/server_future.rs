@@ -12,6 +12,7 @@ use std::{
 };
 
 use futures_util::{future, StreamExt};
+use ipnet::IpNet;
 use log::{debug, info, warn};
 #[cfg(feature = "dns-over-rustls")]
 use rustls::{Certificate, PrivateKey};
@@ -21,26 +22,60 @@ use trust_dns_proto::rr::Record;
 #[cfg(all(feature = "dns-over-openssl", not(feature = "dns-over-rustls")))]
 use crate::proto::openssl::tls_server::*;
 use crate::{
-    authority::{MessageRequest, MessageResponseBuilder},
+    authority::{Catalog, MessageRequest, MessageResponseBuilder},
     client::op::LowerQuery,
     proto::{
-        error::ProtoError,
+        error::{ProtoError, ProtoErrorKind, ProtoResult},
         iocompat::AsyncIoTokioAsStd,
-        op::{Edns, Header, Query, ResponseCode},
+        op::{
+            dso::MIN_KEEPALIVE_INTERVAL, DsoMessage, DsoTlv, Edns, Header, Keepalive, MessageType,
+            Query, ResponseCode,
+        },
+        rr::rdata::opt::EdnsCode,
         serialize::binary::{BinDecodable, BinDecoder},
         tcp::TcpStream,
         udp::UdpStream,
         xfer::SerialMessage,
-        BufDnsStreamHandle,
+        BufDnsStreamHandle, DnsStreamHandle,
+    },
+    server::{
+        rrl::{RateLimitedResponseHandler, ResponseRateLimiter},
+        Protocol, Request, RequestHandler, ResponseHandle, ResponseHandler, ResponseInfo,
+        RrlConfig, TimeoutStream, Views,
     },
-    server::{Protocol, Request, RequestHandler, ResponseHandle, ResponseHandler, TimeoutStream},
 };
 
+/// The handler of the requests of the listeners, the handler of the server or the views around it
+enum ListenerHandler<T: RequestHandler> {
+    Handler(Arc<T>),
+    Views(Arc<Views<T>>),
+}
+
+#[async_trait::async_trait]
+impl<T: RequestHandler> RequestHandler for ListenerHandler<T> {
+    async fn handle_request<R>(&self, request: &Request, response_handle: R) -> ResponseInfo
+    where
+        R: ResponseHandler,
+    {
+        match self {
+            Self::Handler(handler) => handler.handle_request(request, response_handle).await,
+            Self::Views(views) => views.handle_request(request, response_handle).await,
+        }
+    }
+}
+
+/// The highest EDNS version supported, requests with a higher version are answered with BADVERS
+const EDNS_VERSION: u8 = 0;
+
 // TODO, would be nice to have a Slab for buffers here...
 /// A Futures based implementation of a DNS server
 pub struct ServerFuture<T: RequestHandler> {
     handler: Arc<T>,
+    views: Option<Arc<Views<T>>>,
     tasks: Vec<ServerTask>,
+    rrl: Option<Arc<ResponseRateLimiter>>,
+    tcp_keepalive: Option<Duration>,
+    dso_keepalive: Option<Duration>,
 }
 
 impl<T: RequestHandler> ServerFuture<T> {
@@ -48,10 +83,117 @@ impl<T: RequestHandler> ServerFuture<T> {
     pub fn new(handler: T) -> Self {
         Self {
             handler: Arc::new(handler),
+            views: None,
             tasks: vec![],
+            rrl: None,
+            tcp_keepalive: None,
+            dso_keepalive: None,
         }
     }
 
+    /// Creates a new ServerFuture with the specified Handler, applying Response Rate Limiting to
+    ///  all UDP responses.
+    ///
+    /// See [`RrlConfig`] for the details of the limits. Responses over TCP, TLS, HTTPS and QUIC
+    ///  are not limited.
+    pub fn with_rrl(handler: T, rrl: RrlConfig) -> Self {
+        let mut server = Self::new(handler);
+        server.set_rrl(Some(rrl));
+        server
+    }
+
+    /// Sets the Response Rate Limiting applied to the UDP responses, `None` to not limit them
+    ///
+    /// This applies to the UDP listeners registered after it is set. Responses over TCP, TLS,
+    ///  HTTPS and QUIC are not limited, see [`RrlConfig`].
+    pub fn set_rrl(&mut self, rrl: Option<RrlConfig>) {
+        self.rrl = rrl.map(|rrl| Arc::new(ResponseRateLimiter::new(rrl)));
+    }
+
+    /// Adds a split-horizon view, serving the zones of the catalog to the clients within the prefixes of the ACL
+    ///
+    /// A request is handled by the first view, in the order they were added, whose ACL contains the address of
+    ///  the client. Requests from clients which match no view are handled by the handler of the server, see
+    ///  `set_refuse_unmatched` to refuse them instead. Without any views, requests go straight to the handler of
+    ///  the server. The views apply to the listeners registered after the first one is added, further views may be
+    ///  added at any time.
+    ///
+    /// ```
+    /// use trust_dns_server::authority::Catalog;
+    /// use trust_dns_server::ServerFuture;
+    ///
+    /// let external = Catalog::new();
+    /// let internal = Catalog::new();
+    ///
+    /// let mut server = ServerFuture::new(external);
+    /// server.add_view(
+    ///     "internal",
+    ///     vec!["10.0.0.0/8".parse().unwrap(), "fd00::/8".parse().unwrap()],
+    ///     internal,
+    /// );
+    /// ```
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - name of the view, for logging
+    /// * `acl` - the prefixes of the addresses of the clients this view is served to
+    /// * `catalog` - the zones of the view
+    pub fn add_view(&mut self, name: impl Into<String>, acl: Vec<IpNet>, catalog: Catalog) {
+        self.views().add_view(name.into(), acl, catalog);
+    }
+
+    /// Refuse the requests of clients which match none of the views, instead of handling them with the
+    ///  handler of the server.
+    pub fn set_refuse_unmatched(&mut self, refuse_unmatched: bool) {
+        self.views().set_refuse_unmatched(refuse_unmatched);
+    }
+
+    /// Trusts the EDNS Client Subnet, [RFC 7871](https://tools.ietf.org/html/rfc7871), of requests from these
+    ///  prefixes for the selection of views
+    ///
+    /// For requests from a trusted source, e.g. a resolver forwarding on behalf of its clients, the address of the
+    ///  Client Subnet option is matched against the ACLs instead of the source address. The option is ignored for all
+    ///  other requests, by default it's never trusted.
+    pub fn set_client_subnet_sources(&mut self, sources: Vec<IpNet>) {
+        self.views().set_client_subnet_sources(sources);
+    }
+
+    /// The views around the handler of the server, which are created on first use
+    fn views(&mut self) -> &Views<T> {
+        let handler = &self.handler;
+        self.views
+            .get_or_insert_with(|| Arc::new(Views::new(Arc::clone(handler))))
+    }
+
+    /// The handler of the requests of a listener, the views if there are any
+    fn listener_handler(&self) -> Arc<ListenerHandler<T>> {
+        Arc::new(match self.views {
+            Some(ref views) => ListenerHandler::Views(Arc::clone(views)),
+            None => ListenerHandler::Handler(Arc::clone(&self.handler)),
+        })
+    }
+
+    /// Answers the queries over TCP and TLS which include the edns-tcp-keepalive option,
+    ///  [RFC 7828](https://tools.ietf.org/html/rfc7828), with this idle timeout.
+    ///
+    /// A connection is then kept open for at least the idle timeout, even if the timeout of its listener is shorter.
+    ///  The timeout is sent in units of 100 milliseconds. This applies to the listeners registered afterwards.
+    pub fn set_tcp_keepalive(&mut self, idle_timeout: Option<Duration>) {
+        self.tcp_keepalive = idle_timeout;
+    }
+
+    /// Establishes DSO sessions, [RFC 8490](https://tools.ietf.org/html/rfc8490), on TCP and TLS connections
+    ///  which request them with the Keepalive TLV, granting at most this keepalive interval.
+    ///
+    /// The keepalive interval requested by the client is clamped between 10 seconds and this maximum. The
+    ///  connection of a session is then closed after being idle for twice the maximum, regardless of the interval
+    ///  requested, or the timeout of its listener if that is longer. Defaults to `None`, DSO messages are not
+    ///  supported and are handled as any other request. This applies to the listeners registered afterwards.
+    pub fn set_dso_keepalive(&mut self, max_keepalive_interval: Option<Duration>) {
+        self.dso_keepalive =
+            max_keepalive_interval.map(|interval| interval.max(MIN_KEEPALIVE_INTERVAL));
+    }
+
     /// Register a UDP socket. Should be bound before calling this function.
     pub fn register_socket(&mut self, socket: net::UdpSocket) {
         debug!("registering udp: {:?}", socket);
@@ -61,7 +203,8 @@ impl<T: RequestHandler> ServerFuture<T> {
         let (mut buf_stream, stream_handle) =
             UdpStream::with_bound(socket, ([127, 255, 255, 254], 0).into());
         //let request_stream = RequestStream::new(buf_stream, stream_handle);
-        let handler = self.handler.clone();
+        let handler = self.listener_handler();
+        let rrl = self.rrl.clone();
 
         // this spawns a ForEach future which handles all the requests into a Handler.
         let task = tokio::spawn({
@@ -91,6 +234,27 @@ impl<T: RequestHandler> ServerFuture<T> {
                     let handler = handler.clone();
                     let stream_handle = stream_handle.with_remote_addr(src_addr);
 
+                    if let Some(rrl) = rrl.clone() {
+                        tokio::spawn(async move {
+                            let response_handler = RateLimitedResponseHandler::new(
+                                src_addr,
+                                rrl,
+                                ResponseHandle::new(src_addr, stream_handle)
+                                    .with_protocol(Protocol::Udp),
+                            );
+
+                            self::handle_request(
+                                message.bytes(),
+                                src_addr,
+                                Protocol::Udp,
+                                handler,
+                                response_handler,
+                            )
+                            .await;
+                        });
+                        continue;
+                    }
+
                     tokio::spawn(async move {
                         self::handle_raw_request(message, Protocol::Udp, handler, stream_handle)
                             .await;
@@ -126,7 +290,9 @@ impl<T: RequestHandler> ServerFuture<T> {
     pub fn register_listener(&mut self, listener: net::TcpListener, timeout: Duration) {
         debug!("register tcp: {:?}", listener);
 
-        let handler = self.handler.clone();
+        let handler = self.listener_handler();
+        let tcp_keepalive = self.tcp_keepalive;
+        let dso_keepalive = self.dso_keepalive;
 
         // for each incoming request...
         let task = tokio::spawn({
@@ -176,13 +342,18 @@ impl<T: RequestHandler> ServerFuture<T> {
                             };
 
                             // we don't spawn here to limit clients from getting too many resources
-                            self::handle_raw_request(
+                            if let Some(idle_timeout) = self::handle_stream_request(
                                 message,
                                 Protocol::Tcp,
                                 handler.clone(),
                                 stream_handle.clone(),
+                                tcp_keepalive,
+                                dso_keepalive,
                             )
-                            .await;
+                            .await
+                            {
+                                timeout_stream.set_timeout(idle_timeout.max(timeout));
+                            }
                         }
                     });
                 }
@@ -244,7 +415,9 @@ impl<T: RequestHandler> ServerFuture<T> {
 
         let ((cert, chain), key) = certificate_and_key;
 
-        let handler = self.handler.clone();
+        let handler = self.listener_handler();
+        let tcp_keepalive = self.tcp_keepalive;
+        let dso_keepalive = self.dso_keepalive;
         debug!("registered tcp: {:?}", listener);
 
         let tls_acceptor = Box::pin(tls_server::new_acceptor(cert, chain, key)?);
@@ -314,13 +487,18 @@ impl<T: RequestHandler> ServerFuture<T> {
                                 }
                             };
 
-                            self::handle_raw_request(
+                            if let Some(idle_timeout) = self::handle_stream_request(
                                 message,
                                 Protocol::Tls,
                                 handler.clone(),
                                 stream_handle.clone(),
+                                tcp_keepalive,
+                                dso_keepalive,
                             )
-                            .await;
+                            .await
+                            {
+                                timeout_stream.set_timeout(idle_timeout.max(timeout));
+                            }
                         }
                     });
                 }
@@ -387,7 +565,9 @@ impl<T: RequestHandler> ServerFuture<T> {
         use crate::proto::rustls::{tls_from_stream, tls_server};
         use tokio_rustls::TlsAcceptor;
 
-        let handler = self.handler.clone();
+        let handler = self.listener_handler();
+        let tcp_keepalive = self.tcp_keepalive;
+        let dso_keepalive = self.dso_keepalive;
 
         debug!("registered tcp: {:?}", listener);
 
@@ -457,13 +637,18 @@ impl<T: RequestHandler> ServerFuture<T> {
                                 }
                             };
 
-                            self::handle_raw_request(
+                            if let Some(idle_timeout) = self::handle_stream_request(
                                 message,
                                 Protocol::Tls,
                                 handler.clone(),
                                 stream_handle.clone(),
+                                tcp_keepalive,
+                                dso_keepalive,
                             )
-                            .await;
+                            .await
+                            {
+                                timeout_stream.set_timeout(idle_timeout.max(timeout));
+                            }
                         }
                     });
                 }
@@ -537,7 +722,7 @@ impl<T: RequestHandler> ServerFuture<T> {
         use crate::server::https_handler::h2_handler;
 
         let dns_hostname: Arc<str> = Arc::from(dns_hostname);
-        let handler = self.handler.clone();
+        let handler = self.listener_handler();
         debug!("registered https: {:?}", listener);
 
         let tls_acceptor = tls_server::new_acceptor(certificate_and_key.0, certificate_and_key.1)
@@ -631,7 +816,7 @@ impl<T: RequestHandler> ServerFuture<T> {
         use crate::server::quic_handler::quic_handler;
 
         let dns_hostname: Arc<str> = Arc::from(dns_hostname);
-        let handler = self.handler.clone();
+        let handler = self.listener_handler();
 
         debug!("registered quic: {:?}", socket);
         let mut server =
@@ -712,6 +897,113 @@ impl Drop for ServerTask {
     }
 }
 
+/// Handles a request received on a TCP or TLS connection, DSO messages are answered directly when DSO is enabled
+///
+/// Returns the idle timeout the connection should use from now on, if the request changed it
+async fn handle_stream_request<T: RequestHandler>(
+    message: SerialMessage,
+    protocol: Protocol,
+    request_handler: Arc<T>,
+    stream_handle: BufDnsStreamHandle,
+    tcp_keepalive: Option<Duration>,
+    dso_keepalive: Option<Duration>,
+) -> Option<Duration> {
+    if let Some(max_keepalive_interval) = dso_keepalive {
+        if DsoMessage::is_dso(message.bytes()) {
+            return handle_dso_request(&message, stream_handle, max_keepalive_interval);
+        }
+    }
+
+    let request = MessageRequest::from_bytes(message.bytes());
+    let keepalive = tcp_keepalive
+        .filter(|_| matches!(request, Ok(ref request) if requests_tcp_keepalive(request)));
+    let response_handler = ResponseHandle::new(message.addr(), stream_handle)
+        .with_protocol(protocol)
+        .with_tcp_keepalive(keepalive);
+
+    self::handle_decoded_request(
+        request,
+        message.addr(),
+        protocol,
+        request_handler,
+        response_handler,
+    )
+    .await;
+
+    keepalive
+}
+
+/// Answers the DSO requests, [RFC 8490](https://tools.ietf.org/html/rfc8490), of a TCP or TLS connection
+///
+/// Returns the idle timeout of the connection once a session is established with the Keepalive TLV. The client has
+///  to send a message within the granted keepalive interval, the connection is closed after twice the maximum.
+fn handle_dso_request(
+    message: &SerialMessage,
+    mut stream_handle: BufDnsStreamHandle,
+    max_keepalive_interval: Duration,
+) -> Option<Duration> {
+    let request = match DsoMessage::from_vec(message.bytes()) {
+        Ok(request) => request,
+        Err(e) => {
+            debug!(
+                "error decoding DSO request src: {} error: {}",
+                message.addr(),
+                e
+            );
+            return None;
+        }
+    };
+
+    // no requests are sent to clients, and unidirectional messages of clients are not supported
+    if request.header().message_type() != MessageType::Query || request.is_unidirectional() {
+        debug!("ignoring DSO message src: {}", message.addr());
+        return None;
+    }
+
+    let (response, idle_timeout) = match request.primary_tlv() {
+        Some(DsoTlv::Keepalive(keepalive)) => {
+            let keepalive_interval = keepalive
+                .keepalive_interval()
+                .unwrap_or(max_keepalive_interval)
+                .clamp(MIN_KEEPALIVE_INTERVAL, max_keepalive_interval);
+            let inactivity_timeout = keepalive
+                .inactivity_timeout()
+                .map(|timeout| timeout.min(max_keepalive_interval));
+            let keepalive = Keepalive::new(inactivity_timeout, Some(keepalive_interval));
+
+            let response = DsoMessage::new_response(
+                &request,
+                ResponseCode::NoError,
+                vec![DsoTlv::Keepalive(keepalive)],
+            );
+            (response, Some(max_keepalive_interval * 2))
+        }
+        _ => (
+            DsoMessage::new_response(&request, ResponseCode::DSOTYPENI, vec![]),
+            None,
+        ),
+    };
+
+    let sent = response
+        .to_vec()
+        .and_then(|buffer| stream_handle.send(SerialMessage::new(buffer, message.addr())));
+    if let Err(e) = sent {
+        debug!(
+            "error sending DSO response src: {} error: {}",
+            message.addr(),
+            e
+        );
+        return None;
+    }
+
+    idle_timeout
+}
+
+/// Returns true if the request includes the edns-tcp-keepalive option, [RFC 7828](https://tools.ietf.org/html/rfc7828)
+fn requests_tcp_keepalive(request: &MessageRequest) -> bool {
+    matches!(request.edns(), Some(edns) if edns.option(EdnsCode::Keepalive).is_some())
+}
+
 pub(crate) async fn handle_raw_request<T: RequestHandler>(
     message: SerialMessage,
     protocol: Protocol,
@@ -719,7 +1011,8 @@ pub(crate) async fn handle_raw_request<T: RequestHandler>(
     response_handler: BufDnsStreamHandle,
 ) {
     let src_addr = message.addr();
-    let response_handler = ResponseHandle::new(message.addr(), response_handler);
+    let response_handler =
+        ResponseHandle::new(message.addr(), response_handler).with_protocol(protocol);
 
     self::handle_request(
         message.bytes(),
@@ -799,6 +1092,24 @@ pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
 ) {
     let mut decoder = BinDecoder::new(message_bytes);
 
+    self::handle_decoded_request(
+        MessageRequest::read(&mut decoder),
+        src_addr,
+        protocol,
+        request_handler,
+        response_handler,
+    )
+    .await;
+}
+
+/// Handles the request, or the error of decoding it
+async fn handle_decoded_request<R: ResponseHandler, T: RequestHandler>(
+    request: ProtoResult<MessageRequest>,
+    src_addr: SocketAddr,
+    protocol: Protocol,
+    request_handler: Arc<T>,
+    response_handler: R,
+) {
     // method to handle the request
     let inner_handle_request = |message: MessageRequest, response_handler: R| async move {
         let id = message.id();
@@ -842,8 +1153,36 @@ pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
         request_handler.handle_request(&request, reporter).await;
     };
 
-    // Attempt to decode the message
-    match MessageRequest::read(&mut decoder) {
+    match request {
+        Ok(message) if matches!(message.edns(), Some(edns) if edns.version() > EDNS_VERSION) => {
+            debug!(
+                "request:{id} src:{proto}://{addr}#{port} unsupported edns version: {version}",
+                id = message.id(),
+                proto = protocol,
+                addr = src_addr.ip(),
+                port = src_addr.port(),
+                version = message.edns().map_or(0, Edns::version),
+            );
+
+            let mut reporter = ReportingResponseHandler {
+                request_header: *message.header(),
+                query: message.query().clone(),
+                protocol,
+                src_addr,
+                handler: response_handler,
+            };
+
+            // RFC 6891, the OPT record of the response has the highest version supported
+            let mut response = MessageResponseBuilder::new(Some(message.raw_query()));
+            response.edns(error_edns(ResponseCode::BADVERS));
+            let result = reporter
+                .send_response(response.error_msg(message.header(), ResponseCode::BADVERS))
+                .await;
+
+            if let Err(e) = result {
+                warn!("failed to return BADVERS to client: {}", e);
+            }
+        }
         Ok(message) => {
             inner_handle_request(message, response_handler).await;
         }
@@ -875,7 +1214,15 @@ pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
                 handler: response_handler,
             };
 
-            let response = MessageResponseBuilder::new(None);
+            // the client sent an OPT record, even if broken, so an OPT record is expected in the response
+            let mut response = MessageResponseBuilder::new(None);
+            if matches!(
+                error.kind(),
+                ProtoErrorKind::MalformedEdns(_) | ProtoErrorKind::EdnsNameNotRoot(_)
+            ) {
+                response.edns(error_edns(ResponseCode::FormErr));
+            }
+
             let result = reporter
                 .send_response(response.error_msg(&header, ResponseCode::FormErr))
                 .await;
@@ -888,6 +1235,14 @@ pub(crate) async fn handle_request<R: ResponseHandler, T: RequestHandler>(
     }
 }
 
+/// A bare OPT record for an error response, carrying the high bits of the response code
+fn error_edns(response_code: ResponseCode) -> Edns {
+    let mut edns = Edns::new();
+    edns.set_version(EDNS_VERSION)
+        .set_rcode_high(response_code.high());
+    edns
+}
+
 /// Checks if the IP address is safe for returning messages
 ///
 /// Examples of unsafe addresses are any with a port of `0`
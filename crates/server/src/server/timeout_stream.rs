@@ -34,6 +34,12 @@ impl<S> TimeoutStream<S> {
         }
     }
 
+    /// Changes the timeout between each request, restarting the timeout
+    pub fn set_timeout(&mut self, timeout_duration: Duration) {
+        self.timeout_duration = timeout_duration;
+        self.timeout = None;
+    }
+
     fn timeout(timeout_duration: Duration) -> Option<Pin<Box<Sleep>>> {
         if timeout_duration > Duration::from_millis(0) {
             Some(Box::pin(tokio::time::sleep(timeout_duration)))
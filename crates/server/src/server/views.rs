@@ -0,0 +1,165 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Split-horizon views, serving different sets of zones depending on the address of the client
+
+use std::net::IpAddr;
+use std::sync::{Arc, RwLock};
+
+use ipnet::IpNet;
+use log::{debug, error};
+
+use crate::{
+    authority::{Catalog, MessageResponseBuilder},
+    client::rr::rdata::opt::{EdnsCode, EdnsOption},
+    proto::op::ResponseCode,
+    server::{Request, RequestHandler, ResponseHandler, ResponseInfo},
+};
+
+/// A set of zones, served to the clients within the prefixes of the ACL
+struct View {
+    name: String,
+    acl: Vec<IpNet>,
+    catalog: Catalog,
+}
+
+#[derive(Default)]
+struct ViewsConfig {
+    views: Vec<Arc<View>>,
+    refuse_unmatched: bool,
+    client_subnet_sources: Vec<IpNet>,
+}
+
+/// Split-horizon views, the handler of `ServerFuture`, see `ServerFuture::add_view`
+///
+/// A request is handled by the first view, in the order they were added, whose ACL contains the
+///  address of the client. Requests from clients which match no view are handled by the default
+///  handler, or refused.
+pub(crate) struct Views<T: RequestHandler> {
+    default: Arc<T>,
+    config: RwLock<ViewsConfig>,
+}
+
+impl<T: RequestHandler> Views<T> {
+    /// Constructs the views around the handler of the server, without any views all requests go to it
+    pub(crate) fn new(default: Arc<T>) -> Self {
+        Self {
+            default,
+            config: RwLock::new(ViewsConfig::default()),
+        }
+    }
+
+    /// Adds a view, which is matched after all previously added views
+    pub(crate) fn add_view(&self, name: String, acl: Vec<IpNet>, catalog: Catalog) {
+        self.config
+            .write()
+            .expect("views lock poisoned")
+            .views
+            .push(Arc::new(View { name, acl, catalog }));
+    }
+
+    /// Refuse the requests of clients which match no view, instead of handling them with the default handler
+    pub(crate) fn set_refuse_unmatched(&self, refuse_unmatched: bool) {
+        self.config
+            .write()
+            .expect("views lock poisoned")
+            .refuse_unmatched = refuse_unmatched;
+    }
+
+    /// Match the Client Subnet of requests from these prefixes against the ACLs, instead of the source address
+    pub(crate) fn set_client_subnet_sources(&self, sources: Vec<IpNet>) {
+        self.config
+            .write()
+            .expect("views lock poisoned")
+            .client_subnet_sources = sources;
+    }
+}
+
+impl ViewsConfig {
+    fn find(&self, client: IpAddr) -> Option<&Arc<View>> {
+        self.views
+            .iter()
+            .find(|view| view.acl.iter().any(|net| net.contains(&client)))
+    }
+
+    /// The address of the client, from the Client Subnet if the source is trusted
+    fn client_addr(&self, request: &Request) -> IpAddr {
+        let src = unmap_ipv4(request.src().ip());
+        if !self
+            .client_subnet_sources
+            .iter()
+            .any(|net| net.contains(&src))
+        {
+            return src;
+        }
+
+        match request
+            .edns()
+            .and_then(|edns| edns.option(EdnsCode::Subnet))
+        {
+            // a source prefix of 0 asks for the subnet not to be used, RFC 7871 section 7.1.2
+            Some(EdnsOption::Subnet(subnet)) if subnet.source_prefix() > 0 => {
+                unmap_ipv4(subnet.addr())
+            }
+            _ => src,
+        }
+    }
+}
+
+/// IPv4 clients of dual-stack sockets have IPv4-mapped addresses, `::ffff:a.b.c.d`
+pub(crate) fn unmap_ipv4(addr: IpAddr) -> IpAddr {
+    match addr {
+        IpAddr::V6(ipv6) => match ipv6.segments() {
+            [0, 0, 0, 0, 0, 0xffff, ..] => ipv6.to_ipv4().map_or(addr, IpAddr::V4),
+            _ => addr,
+        },
+        IpAddr::V4(..) => addr,
+    }
+}
+
+#[async_trait::async_trait]
+impl<T: RequestHandler> RequestHandler for Views<T> {
+    async fn handle_request<R>(&self, request: &Request, mut response_handle: R) -> ResponseInfo
+    where
+        R: ResponseHandler,
+    {
+        let (client, view, refuse_unmatched) = {
+            let config = self.config.read().expect("views lock poisoned");
+            let client = config.client_addr(request);
+            (
+                client,
+                config.find(client).cloned(),
+                config.refuse_unmatched,
+            )
+        };
+
+        match view {
+            Some(view) => {
+                debug!("request from {} is served by view {}", client, view.name);
+                view.catalog.handle_request(request, response_handle).await
+            }
+            None if !refuse_unmatched => {
+                self.default.handle_request(request, response_handle).await
+            }
+            None => {
+                debug!("request from {} matches no view, refused", client);
+                let response = MessageResponseBuilder::new(Some(request.raw_query()));
+                let result = response_handle
+                    .send_response(response.error_msg(request.header(), ResponseCode::Refused))
+                    .await;
+
+                match result {
+                    Err(e) => {
+                        error!("request error: {}", e);
+                        ResponseInfo::serve_failed()
+                    }
+                    Ok(info) => info,
+                }
+            }
+        }
+    }
+}
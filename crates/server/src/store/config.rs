@@ -30,5 +30,5 @@ pub enum StoreConfig {
     /// Forwarder, aka Resolver
     #[cfg(feature = "trust-dns-resolver")]
     #[cfg_attr(docsrs, doc(cfg(feature = "trust-dns-resolver")))]
-    Forward(ForwardConfig),
+    Forward(Box<ForwardConfig>),
 }
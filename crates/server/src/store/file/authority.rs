@@ -9,66 +9,56 @@
 
 use std::{
     collections::BTreeMap,
-    fs::File,
-    io::{BufRead, BufReader},
+    fs,
     ops::{Deref, DerefMut},
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Weak,
+    },
+    time::{Duration, SystemTime},
 };
 
-use log::{debug, info};
+use log::{debug, info, warn};
+use tokio::{sync::Mutex, task::JoinHandle};
 
-#[cfg(feature = "dnssec")]
 use crate::{
-    authority::DnssecAuthority,
+    authority::{Authority, LookupError, LookupOptions, MessageRequest, UpdateResult, ZoneType},
     client::{
-        proto::rr::dnssec::rdata::key::KEY,
-        rr::dnssec::{DnsSecResult, SigSigner},
+        rr::{rdata::SOA, LowerName, Name, RData, RecordSet, RecordType, RrKey, SerialNumber},
+        serialize::txt::Parser,
     },
+    server::RequestInfo,
+    store::{file::FileConfig, in_memory::InMemoryAuthority},
 };
+#[cfg(feature = "dnssec")]
 use crate::{
-    authority::{Authority, LookupError, LookupOptions, MessageRequest, UpdateResult, ZoneType},
+    authority::{DnssecAuthority, RolloverTiming},
     client::{
-        rr::{LowerName, Name, RecordSet, RecordType, RrKey},
-        serialize::txt::{Lexer, Parser, Token},
+        proto::rr::dnssec::rdata::key::KEY,
+        rr::dnssec::{DnsSecResult, SigSigner},
     },
-    server::RequestInfo,
-    store::{file::FileConfig, in_memory::InMemoryAuthority},
 };
 
+/// The zone origin and records, along with every file read to produce them
+type ParsedZone = (Name, BTreeMap<RrKey, RecordSet>, Vec<PathBuf>);
+
 /// FileAuthority is responsible for storing the resource records for a particular zone.
 ///
 /// Authorities default to DNSClass IN. The ZoneType specifies if this should be treated as the
 /// start of authority for the zone, is a Secondary, or a cached zone.
-pub struct FileAuthority(InMemoryAuthority);
-
-/// Max traversal depth for $INCLUDE files
-const MAX_INCLUDE_LEVEL: u16 = 256;
-
-/// Inner state of zone file loader, tracks depth of $INCLUDE
-/// loads as well as visited previously files, so the loader
-/// is able to abort e.g. when cycle is detected
-///
-/// Note, that tracking max depth level explicitly covers also
-/// cycles in $INCLUDEs. The error description in this case would
-/// not be very helpful to detect the root cause of the problem
-/// though. The way to improve diagnose experience would be to
-/// traverse $INCLUDE files in topologically sorted order which
-/// requires quite some re-arrangements in the code and in the
-/// way loader is currently implemented.
-struct FileReaderState {
-    level: u16,
-}
-
-impl FileReaderState {
-    fn new() -> Self {
-        Self { level: 0 }
-    }
-
-    fn next_level(&self) -> Self {
-        Self {
-            level: self.level + 1,
-        }
-    }
+pub struct FileAuthority {
+    in_memory: InMemoryAuthority,
+    /// The zone file this was loaded from, None if created from records
+    zone_path: Option<PathBuf>,
+    /// The zone file and the files it includes, as of the last time it was read
+    ///
+    /// Held for the duration of a reload, so that concurrent reloads don't interleave
+    zone_files: Mutex<Vec<PathBuf>>,
+    /// The serial of the zone file, as of the last time it was read
+    ///
+    /// The zone being served may have a greater serial, as signing the zone increments it
+    file_serial: AtomicU32,
 }
 
 impl FileAuthority {
@@ -93,89 +83,35 @@ impl FileAuthority {
         zone_type: ZoneType,
         allow_axfr: bool,
     ) -> Result<Self, String> {
-        InMemoryAuthority::new(origin, records, zone_type, allow_axfr).map(Self)
+        let file_serial = Self::soa_serial(&origin, &records).unwrap_or_default();
+        InMemoryAuthority::new(origin, records, zone_type, allow_axfr).map(|in_memory| Self {
+            in_memory,
+            zone_path: None,
+            zone_files: Mutex::new(Vec::new()),
+            file_serial: AtomicU32::new(file_serial),
+        })
     }
 
-    /// Read given file line by line and recursively invokes reader for
-    /// $INCLUDE directives
-    ///
-    /// TODO: it looks hacky as far we effectively duplicate parser's functionallity
-    /// (at least partially) and performing lexing twice.
-    /// Better solution requires us to change lexer to deal
-    /// with Lines-like iterator instead of String buf (or capability to combine a few
-    /// lexer instances into a single lexer).
-    ///
-    /// TODO: $INCLUDE could specify domain name -- to support on-flight swap for Origin
-    /// value we definitely need to rethink and rework loader/parser/lexer
-    fn read_file(
-        zone_path: PathBuf,
-        buf: &mut String,
-        state: FileReaderState,
-    ) -> Result<(), String> {
-        let file = File::open(&zone_path)
-            .map_err(|e| format!("failed to read {}: {:?}", zone_path.display(), e))?;
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let content = line.unwrap();
-            let mut lexer = Lexer::new(&content);
-
-            match (lexer.next_token(), lexer.next_token(), lexer.next_token()) {
-                (
-                    Ok(Some(Token::Include)),
-                    Ok(Some(Token::CharData(include_path))),
-                    Ok(Some(Token::CharData(_domain))),
-                ) => {
-                    return Err(format!(
-                        "Domain name for $INCLUDE is not supported at {}, trying to include {}",
-                        zone_path.display(),
-                        include_path
-                    ));
-                }
-                (Ok(Some(Token::Include)), Ok(Some(Token::CharData(include_path))), _) => {
-                    // RFC1035 (section 5) does not specify how filename for $INCLUDE
-                    // should be resolved into file path. The underlying code implements the
-                    // following:
-                    // * if the path is absolute (relies on Path::is_absolute), it uses normalized path
-                    // * otherwise, it joins the path with parent root of the current file
-                    //
-                    // TODO: Inlining files specified using non-relative path might potentially introduce
-                    // security issue in some cases (e.g. when working with zone files from untrusted sources)
-                    // and should probably be configurable by user.
-                    let include_path = Path::new(&include_path);
-                    let include_zone_path = if include_path.is_absolute() {
-                        include_path.to_path_buf()
-                    } else {
-                        let parent_dir =
-                            zone_path.parent().expect("file has to have parent folder");
-                        parent_dir.join(include_path)
-                    };
-
-                    if state.level >= MAX_INCLUDE_LEVEL {
-                        return Err(format!("Max depth level for nested $INCLUDE is reached at {}, trying to include {}", zone_path.display(), include_zone_path.display()));
-                    }
-
-                    let mut include_buf = String::new();
-
-                    info!(
-                        "including file {} into {}",
-                        include_zone_path.display(),
-                        zone_path.display()
-                    );
-
-                    Self::read_file(include_zone_path, &mut include_buf, state.next_level())?;
-                    buf.push_str(&include_buf);
-                }
-                _ => {
-                    buf.push_str(&content);
-                }
-            }
+    /// The serial of the SOA record of the zone in the records
+    fn soa_serial(origin: &Name, records: &BTreeMap<RrKey, RecordSet>) -> Option<u32> {
+        records
+            .get(&RrKey::new(origin.into(), RecordType::SOA))
+            .and_then(|soa| soa.records_without_rrsigs().next())
+            .and_then(|soa| soa.data())
+            .and_then(RData::as_soa)
+            .map(SOA::serial)
+    }
 
-            buf.push('\n');
-        }
-        Ok(())
+    /// Read and parse the zone file, including any $INCLUDE files, which are returned with the zone file
+    fn read_zone(origin: Name, zone_path: &Path) -> Result<ParsedZone, String> {
+        Parser::new()
+            .parse_file_with_includes(zone_path, Some(origin), None)
+            .map_err(|e| format!("failed to parse {}: {:?}", zone_path.display(), e))
     }
 
     /// Read the Authority for the origin from the specified configuration
+    ///
+    /// Watching the zone file, if `config.watch` is set, is left to the caller, see `watch`.
     pub fn try_from_config(
         origin: Name,
         zone_type: ZoneType,
@@ -188,17 +124,7 @@ impl FileAuthority {
 
         info!("loading zone file: {:?}", zone_path);
 
-        let mut buf = String::new();
-
-        // TODO: this should really use something to read line by line or some other method to
-        //  keep the usage down. and be a custom lexer...
-        Self::read_file(zone_path, &mut buf, FileReaderState::new())
-            .map_err(|e| format!("failed to read {}: {:?}", &config.zone_file_path, e))?;
-
-        let lexer = Lexer::new(&buf);
-        let (origin, records) = Parser::new()
-            .parse(lexer, Some(origin), None)
-            .map_err(|e| format!("failed to parse {}: {:?}", config.zone_file_path, e))?;
+        let (origin, records, zone_files) = Self::read_zone(origin, &zone_path)?;
 
         info!(
             "zone file loaded: {} with {} records",
@@ -207,12 +133,129 @@ impl FileAuthority {
         );
         debug!("zone: {:#?}", records);
 
-        Self::new(origin, records, zone_type, allow_axfr)
+        let mut authority = Self::new(origin, records, zone_type, allow_axfr)?;
+        authority.zone_path = Some(zone_path);
+        authority.zone_files = Mutex::new(zone_files);
+        #[cfg(feature = "dnssec")]
+        authority.set_nsec_white_lies(config.nsec_white_lies);
+        Ok(authority)
+    }
+
+    /// Reloads the zone from its zone file, e.g. on SIGHUP
+    ///
+    /// The records of the zone are only replaced if the file parses cleanly, and the serial of its
+    ///  SOA record is greater than the serial of the file when it was last read. Otherwise the
+    ///  current records continue to be served. A signed zone is signed again, see
+    ///  `InMemoryAuthority::replace_records`.
+    ///
+    /// # Return value
+    ///
+    /// True if the records were replaced, false if the serial did not increase.
+    pub async fn reload(&self) -> Result<bool, String> {
+        let zone_path = self
+            .zone_path
+            .as_ref()
+            .ok_or_else(|| format!("zone {} was not loaded from a file", self.origin()))?;
+        let mut zone_files = self.zone_files.lock().await;
+
+        let reloaded = Self::read_zone(self.origin().into(), zone_path).and_then(
+            |(origin, records, files)| {
+                InMemoryAuthority::new(origin, records, self.zone_type(), self.is_axfr_allowed())
+                    .map(|reloaded| (reloaded, files))
+            },
+        );
+        let reloaded = match reloaded {
+            Ok((reloaded, files)) => {
+                *zone_files = files;
+                reloaded
+            }
+            Err(e) => {
+                warn!(
+                    "failed to reload zone {}, continuing with the loaded zone: {}",
+                    self.origin(),
+                    e
+                );
+                return Err(e);
+            }
+        };
+
+        let serial = self.file_serial.load(Ordering::Acquire);
+        let new_serial = reloaded.serial().await;
+        let is_newer = SerialNumber::from(new_serial) > SerialNumber::from(serial);
+        if !is_newer {
+            info!(
+                "not reloading zone {}, the serial {} of {} is not greater than {}",
+                self.origin(),
+                new_serial,
+                zone_path.display(),
+                serial
+            );
+            return Ok(false);
+        }
+
+        self.in_memory.replace_records(reloaded).await;
+        self.file_serial.store(new_serial, Ordering::Release);
+        info!(
+            "zone {} reloaded from {}, serial {} -> {}",
+            self.origin(),
+            zone_path.display(),
+            serial,
+            new_serial
+        );
+        Ok(true)
+    }
+
+    /// Polls the zone file, and the files it includes, for modifications every `interval`, reloading the zone
+    ///  when any of them has changed
+    ///
+    /// This must be called from within a tokio runtime. The spawned task ends once all other
+    ///  references to the authority have been dropped.
+    pub fn watch(authority: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        fn modified(zone_files: &[PathBuf]) -> Vec<(PathBuf, Option<SystemTime>)> {
+            zone_files
+                .iter()
+                .map(|path| {
+                    let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+                    (path.clone(), modified)
+                })
+                .collect()
+        }
+
+        let weak: Weak<Self> = Arc::downgrade(authority);
+        // while a reload is in progress, the files are compared once it is done
+        let mut last_modified = authority
+            .zone_files
+            .try_lock()
+            .map(|zone_files| modified(&zone_files))
+            .unwrap_or_default();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                let authority = match weak.upgrade() {
+                    Some(authority) => authority,
+                    None => return,
+                };
+
+                // a file which is missing, e.g. while it is being replaced, is picked up once it is back
+                let modified = modified(&authority.zone_files.lock().await);
+                if modified.iter().any(|(_, modified)| modified.is_none())
+                    || modified == last_modified
+                {
+                    continue;
+                }
+                last_modified = modified;
+
+                // failures are logged by reload, and the loaded zone continues to be served
+                authority.reload().await.ok();
+            }
+        })
     }
 
     /// Unwrap the InMemoryAuthority
     pub fn unwrap(self) -> InMemoryAuthority {
-        self.0
+        self.in_memory
     }
 }
 
@@ -220,13 +263,13 @@ impl Deref for FileAuthority {
     type Target = InMemoryAuthority;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.in_memory
     }
 }
 
 impl DerefMut for FileAuthority {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.in_memory
     }
 }
 
@@ -236,12 +279,12 @@ impl Authority for FileAuthority {
 
     /// What type is this zone
     fn zone_type(&self) -> ZoneType {
-        self.0.zone_type()
+        self.in_memory.zone_type()
     }
 
     /// Return true if AXFR is allowed
     fn is_axfr_allowed(&self) -> bool {
-        self.0.is_axfr_allowed()
+        self.in_memory.is_axfr_allowed()
     }
 
     /// Perform a dynamic update of a zone
@@ -252,7 +295,7 @@ impl Authority for FileAuthority {
 
     /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
     fn origin(&self) -> &LowerName {
-        self.0.origin()
+        self.in_memory.origin()
     }
 
     /// Looks up all Resource Records matching the giving `Name` and `RecordType`.
@@ -275,7 +318,7 @@ impl Authority for FileAuthority {
         rtype: RecordType,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
-        self.0.lookup(name, rtype, lookup_options).await
+        self.in_memory.lookup(name, rtype, lookup_options).await
     }
 
     /// Using the specified query, perform a lookup against this zone.
@@ -294,12 +337,12 @@ impl Authority for FileAuthority {
         request_info: RequestInfo<'_>,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
-        self.0.search(request_info, lookup_options).await
+        self.in_memory.search(request_info, lookup_options).await
     }
 
     /// Get the NS, NameServer, record for the zone
     async fn ns(&self, lookup_options: LookupOptions) -> Result<Self::Lookup, LookupError> {
-        self.0.ns(lookup_options).await
+        self.in_memory.ns(lookup_options).await
     }
 
     /// Return the NSEC records based on the given name
@@ -314,7 +357,7 @@ impl Authority for FileAuthority {
         name: &LowerName,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
-        self.0.get_nsec_records(name, lookup_options).await
+        self.in_memory.get_nsec_records(name, lookup_options).await
     }
 
     /// Returns the SOA of the authority.
@@ -322,12 +365,12 @@ impl Authority for FileAuthority {
     /// *Note*: This will only return the SOA, if this is fulfilling a request, a standard lookup
     ///  should be used, see `soa_secure()`, which will optionally return RRSIGs.
     async fn soa(&self) -> Result<Self::Lookup, LookupError> {
-        self.0.soa().await
+        self.in_memory.soa().await
     }
 
     /// Returns the SOA record for the zone
     async fn soa_secure(&self, lookup_options: LookupOptions) -> Result<Self::Lookup, LookupError> {
-        self.0.soa_secure(lookup_options).await
+        self.in_memory.soa_secure(lookup_options).await
     }
 }
 
@@ -337,17 +380,29 @@ impl Authority for FileAuthority {
 impl DnssecAuthority for FileAuthority {
     /// Add a (Sig0) key that is authorized to perform updates against this authority
     async fn add_update_auth_key(&self, name: Name, key: KEY) -> DnsSecResult<()> {
-        self.0.add_update_auth_key(name, key).await
+        self.in_memory.add_update_auth_key(name, key).await
     }
 
     /// Add Signer
     async fn add_zone_signing_key(&self, signer: SigSigner) -> DnsSecResult<()> {
-        self.0.add_zone_signing_key(signer).await
+        self.in_memory.add_zone_signing_key(signer).await
     }
 
     /// Sign the zone for DNSSEC
     async fn secure_zone(&self) -> DnsSecResult<()> {
-        DnssecAuthority::secure_zone(&self.0).await
+        DnssecAuthority::secure_zone(&self.in_memory).await
+    }
+
+    /// Begin the rollover of the zone signing key
+    async fn begin_zsk_rollover(
+        &self,
+        old_key_tag: u16,
+        new_signer: SigSigner,
+        timing: RolloverTiming,
+    ) -> DnsSecResult<()> {
+        self.in_memory
+            .begin_zsk_rollover(old_key_tag, new_signer, timing)
+            .await
     }
 }
 
@@ -368,10 +423,14 @@ mod tests {
         let config = FileConfig {
             zone_file_path: "../../tests/test-data/named_test_configs/dnssec/example.com.zone"
                 .to_string(),
+            watch: false,
+            nsec_white_lies: false,
         };
         #[cfg(not(feature = "dnssec"))]
         let config = FileConfig {
             zone_file_path: "../../tests/test-data/named_test_configs/example.com.zone".to_string(),
+            watch: false,
+            nsec_white_lies: false,
         };
         let authority = FileAuthority::try_from_config(
             Name::from_str("example.com.").unwrap(),
@@ -418,4 +477,278 @@ mod tests {
             _ => panic!("wrong rdata type returned"),
         }
     }
+
+    fn write_zone(path: &Path, serial: u32, www: &str) {
+        fs::write(
+            path,
+            format!(
+                "@ IN SOA ns.example.com. root.example.com. {} 8h 120m 7d 24h\n\
+                 @ IN NS ns\n\
+                 www IN A {}\n",
+                serial, www
+            ),
+        )
+        .unwrap();
+    }
+
+    fn load_zone(path: &Path) -> FileAuthority {
+        FileAuthority::try_from_config(
+            Name::from_str("example.com.").unwrap(),
+            ZoneType::Primary,
+            false,
+            None,
+            &FileConfig {
+                zone_file_path: path.to_str().unwrap().to_string(),
+                watch: true,
+                nsec_white_lies: false,
+            },
+        )
+        .expect("failed to load file")
+    }
+
+    async fn lookup_www(authority: &FileAuthority) -> Ipv4Addr {
+        let lookup = Authority::lookup(
+            authority,
+            &LowerName::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+            LookupOptions::default(),
+        )
+        .await
+        .expect("lookup failed");
+
+        match lookup
+            .into_iter()
+            .next()
+            .expect("A record not found in authority")
+            .data()
+        {
+            Some(RData::A(ip)) => *ip,
+            _ => panic!("wrong rdata type returned"),
+        }
+    }
+
+    #[test]
+    fn test_reload() {
+        let path =
+            std::env::temp_dir().join(format!("trust-dns-reload-{}.zone", std::process::id()));
+        write_zone(&path, 1, "127.0.0.1");
+        let authority = load_zone(&path);
+
+        // a newer serial replaces the records
+        write_zone(&path, 2, "127.0.0.2");
+        assert!(block_on(authority.reload()).unwrap());
+        assert_eq!(
+            block_on(lookup_www(&authority)),
+            Ipv4Addr::new(127, 0, 0, 2)
+        );
+        assert_eq!(block_on(authority.serial()), 2);
+
+        // the same, or an older, serial is ignored
+        write_zone(&path, 2, "127.0.0.3");
+        assert!(!block_on(authority.reload()).unwrap());
+        write_zone(&path, 1, "127.0.0.3");
+        assert!(!block_on(authority.reload()).unwrap());
+        assert_eq!(
+            block_on(lookup_www(&authority)),
+            Ipv4Addr::new(127, 0, 0, 2)
+        );
+
+        // a broken zone file keeps the loaded zone
+        fs::write(&path, "@ IN SOA ns.example.com. ( 3 \n").unwrap();
+        assert!(block_on(authority.reload()).is_err());
+        assert_eq!(
+            block_on(lookup_www(&authority)),
+            Ipv4Addr::new(127, 0, 0, 2)
+        );
+
+        // as does a missing one
+        fs::remove_file(&path).unwrap();
+        assert!(block_on(authority.reload()).is_err());
+        assert_eq!(
+            block_on(lookup_www(&authority)),
+            Ipv4Addr::new(127, 0, 0, 2)
+        );
+    }
+
+    #[cfg(feature = "dnssec-ring")]
+    #[test]
+    fn test_reload_signed() {
+        use crate::client::rr::dnssec::{Algorithm, KeyFormat, KeyPair};
+
+        let path =
+            std::env::temp_dir().join(format!("trust-dns-signed-{}.zone", std::process::id()));
+        write_zone(&path, 1, "127.0.0.1");
+        let authority = load_zone(&path);
+
+        let pkcs8 = KeyPair::generate_pkcs8(Algorithm::ED25519).unwrap();
+        let key = KeyFormat::Pkcs8
+            .decode_key(&pkcs8, None, Algorithm::ED25519)
+            .unwrap();
+        let dnskey = key.to_dnskey(Algorithm::ED25519).unwrap();
+        let signer = SigSigner::dnssec(
+            dnskey.clone(),
+            key,
+            Name::from_str("example.com.").unwrap(),
+            Duration::from_secs(86400),
+        );
+        block_on(authority.add_zone_signing_key(signer)).unwrap();
+        block_on(authority.secure_zone()).unwrap();
+
+        // signing incremented the serial being served, the file's serial is compared
+        assert_eq!(block_on(authority.serial()), 2);
+        write_zone(&path, 2, "127.0.0.2");
+        assert!(block_on(authority.reload()).unwrap());
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            block_on(lookup_www(&authority)),
+            Ipv4Addr::new(127, 0, 0, 2)
+        );
+        assert_eq!(block_on(authority.serial()), 3);
+
+        // the key is published again and the records are signed
+        let dnssec = LookupOptions::for_dnssec(true, Default::default());
+        let dnskeys = block_on(Authority::lookup(
+            &authority,
+            &LowerName::from_str("example.com.").unwrap(),
+            RecordType::DNSKEY,
+            dnssec,
+        ))
+        .unwrap();
+        assert_eq!(
+            dnskeys
+                .iter()
+                .filter_map(|r| r.data())
+                .filter_map(|d| d.as_dnssec())
+                .filter_map(|d| d.as_dnskey())
+                .collect::<Vec<_>>(),
+            vec![&dnskey]
+        );
+
+        let www = block_on(Authority::lookup(
+            &authority,
+            &LowerName::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+            dnssec,
+        ))
+        .unwrap();
+        assert!(www.iter().any(|r| r.rr_type() == RecordType::RRSIG));
+    }
+
+    #[cfg(feature = "dnssec-ring")]
+    #[test]
+    fn test_nsec_white_lies() {
+        use crate::authority::LookupError;
+        use crate::client::rr::dnssec::{Algorithm, KeyFormat, KeyPair};
+        use crate::proto::op::ResponseCode;
+
+        let path =
+            std::env::temp_dir().join(format!("trust-dns-white-lies-{}.zone", std::process::id()));
+        write_zone(&path, 1, "127.0.0.1");
+        let authority = FileAuthority::try_from_config(
+            Name::from_str("example.com.").unwrap(),
+            ZoneType::Primary,
+            false,
+            None,
+            &FileConfig {
+                zone_file_path: path.to_str().unwrap().to_string(),
+                watch: false,
+                nsec_white_lies: true,
+            },
+        )
+        .expect("failed to load file");
+        fs::remove_file(&path).unwrap();
+
+        let pkcs8 = KeyPair::generate_pkcs8(Algorithm::ED25519).unwrap();
+        let key = KeyFormat::Pkcs8
+            .decode_key(&pkcs8, None, Algorithm::ED25519)
+            .unwrap();
+        let signer = SigSigner::dnssec(
+            key.to_dnskey(Algorithm::ED25519).unwrap(),
+            key,
+            Name::from_str("example.com.").unwrap(),
+            Duration::from_secs(86400),
+        );
+        block_on(authority.add_zone_signing_key(signer)).unwrap();
+        block_on(authority.secure_zone()).unwrap();
+
+        // the name which doesn't exist is denied as if it had no records
+        let lookup = |lookup_options| {
+            block_on(Authority::lookup(
+                &authority,
+                &LowerName::from_str("nx.example.com.").unwrap(),
+                RecordType::A,
+                lookup_options,
+            ))
+            .err()
+        };
+        assert!(matches!(
+            lookup(LookupOptions::for_dnssec(true, Default::default())),
+            Some(LookupError::NameExists)
+        ));
+        assert!(matches!(
+            lookup(LookupOptions::default()),
+            Some(LookupError::ResponseCode(ResponseCode::NXDomain))
+        ));
+    }
+
+    #[test]
+    fn test_reload_without_file() {
+        let path = std::env::temp_dir().join(format!("trust-dns-new-{}.zone", std::process::id()));
+        write_zone(&path, 1, "127.0.0.1");
+        let (origin, records, _) =
+            FileAuthority::read_zone(Name::from_str("example.com.").unwrap(), &path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let authority = FileAuthority::new(origin, records, ZoneType::Primary, false).unwrap();
+        assert!(block_on(authority.reload()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_watch() {
+        let path =
+            std::env::temp_dir().join(format!("trust-dns-watch-{}.zone", std::process::id()));
+        write_zone(&path, 1, "127.0.0.1");
+        let authority = Arc::new(load_zone(&path));
+        let watch = FileAuthority::watch(&authority, Duration::from_millis(10));
+
+        write_zone(&path, 2, "127.0.0.2");
+        for _ in 0..500 {
+            if authority.serial().await == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(lookup_www(&authority).await, Ipv4Addr::new(127, 0, 0, 2));
+        fs::remove_file(&path).unwrap();
+
+        // the watch ends with the authority
+        drop(authority);
+        tokio::time::timeout(Duration::from_secs(5), watch)
+            .await
+            .expect("watch did not end")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_watch_include() {
+        let dir =
+            std::env::temp_dir().join(format!("trust-dns-watch-include-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("example.com.zone");
+        fs::write(&path, "$INCLUDE soa.db\n").unwrap();
+        write_zone(&dir.join("soa.db"), 1, "127.0.0.1");
+        let authority = Arc::new(load_zone(&path));
+        let _watch = FileAuthority::watch(&authority, Duration::from_millis(10));
+
+        // only the included file changes
+        write_zone(&dir.join("soa.db"), 2, "127.0.0.2");
+        for _ in 0..500 {
+            if authority.serial().await == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(lookup_www(&authority).await, Ipv4Addr::new(127, 0, 0, 2));
+        fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -12,4 +12,10 @@ use serde::Deserialize;
 pub struct FileConfig {
     /// path to the zone file
     pub zone_file_path: String,
+    /// reload the zone whenever the zone file changes, see `FileAuthority::watch`
+    #[serde(default)]
+    pub watch: bool,
+    /// deny records with NSEC "white lies" when the zone is signed, see `InMemoryAuthority::set_nsec_white_lies`
+    #[serde(default)]
+    pub nsec_white_lies: bool,
 }
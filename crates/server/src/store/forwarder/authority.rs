@@ -19,7 +19,10 @@ use crate::{
         rr::{LowerName, Name, Record, RecordType},
     },
     resolver::{
-        config::ResolverConfig, lookup::Lookup as ResolverLookup, TokioAsyncResolver, TokioHandle,
+        config::{ResolverConfig, ResolverOpts},
+        lookup::Lookup as ResolverLookup,
+        system_conf::read_system_conf,
+        TokioAsyncResolver, TokioHandle,
     },
     server::RequestInfo,
     store::forwarder::ForwardConfig,
@@ -27,10 +30,18 @@ use crate::{
 
 /// An authority that will forward resolutions to upstream resolvers.
 ///
-/// This uses the trust-dns-resolver for resolving requests.
+/// This uses the trust-dns-resolver for resolving requests. The DO bit of a request is passed on to the upstream
+///  resolvers, as is the EDNS Client Subnet option if `ForwardConfig::forward_client_subnet` is set.
+///
+/// The cache of the resolver is keyed by the query only. It is shared by the clients with and without the DO bit, so
+///  the DNSSEC records are always requested, see `ForwardLookup`. The answers for a Client Subnet are specific to that
+///  subnet, the requests which pass it on bypass the cache.
 pub struct ForwardAuthority {
     origin: LowerName,
     resolver: TokioAsyncResolver,
+    uncached_resolver: TokioAsyncResolver,
+    request_options: DnsRequestOptions,
+    forward_client_subnet: bool,
 }
 
 impl ForwardAuthority {
@@ -38,13 +49,10 @@ impl ForwardAuthority {
     #[allow(clippy::new_without_default)]
     #[doc(hidden)]
     pub async fn new(runtime: TokioHandle) -> Result<Self, String> {
-        let resolver = TokioAsyncResolver::from_system_conf(runtime)
-            .map_err(|e| format!("error constructing new Resolver: {}", e))?;
+        let (config, options) =
+            read_system_conf().map_err(|e| format!("error constructing new Resolver: {}", e))?;
 
-        Ok(Self {
-            origin: Name::root().into(),
-            resolver,
-        })
+        Self::from_parts(Name::root(), config, options, false, runtime)
     }
 
     /// Read the Authority for the origin from the specified configuration
@@ -56,6 +64,7 @@ impl ForwardAuthority {
         info!("loading forwarder config: {}", origin);
 
         let name_servers = config.name_servers.clone();
+        let forward_client_subnet = config.forward_client_subnet;
         let mut options = config.options.unwrap_or_default();
 
         // See RFC 1034, Section 4.3.2:
@@ -78,16 +87,47 @@ impl ForwardAuthority {
         }
 
         let config = ResolverConfig::from_parts(None, vec![], name_servers);
+        let forwarder = Self::from_parts(
+            origin.clone(),
+            config,
+            options,
+            forward_client_subnet,
+            TokioHandle,
+        )?;
 
-        let resolver = TokioAsyncResolver::new(config, options, TokioHandle)
+        info!("forward resolver configured: {}: ", origin);
+
+        Ok(forwarder)
+    }
+
+    fn from_parts(
+        origin: Name,
+        config: ResolverConfig,
+        mut options: ResolverOpts,
+        forward_client_subnet: bool,
+        runtime: TokioHandle,
+    ) -> Result<Self, String> {
+        // the RRSIGs are returned to the clients which set the DO bit, see `ForwardLookup`
+        options.retain_dnssec_records = true;
+
+        let mut request_options = DnsRequestOptions::default();
+        request_options.use_edns = options.edns0;
+        request_options.client_subnet = options.client_subnet;
+
+        let resolver = TokioAsyncResolver::new(config.clone(), options, runtime)
             .map_err(|e| format!("error constructing new Resolver: {}", e))?;
 
-        info!("forward resolver configured: {}: ", origin);
+        // nothing is kept in a cache of no entries
+        options.cache_size = 0;
+        let uncached_resolver = TokioAsyncResolver::new(config, options, runtime)
+            .map_err(|e| format!("error constructing new Resolver: {}", e))?;
 
-        // TODO: this might be infallible?
         Ok(Self {
             origin: origin.into(),
             resolver,
+            uncached_resolver,
+            request_options,
+            forward_client_subnet,
         })
     }
 }
@@ -124,19 +164,38 @@ impl Authority for ForwardAuthority {
         &self,
         name: &LowerName,
         rtype: RecordType,
-        _lookup_options: LookupOptions,
+        lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
         // TODO: make this an error?
         debug_assert!(self.origin.zone_of(name));
 
+        let mut request_options = self.request_options;
+        let client_subnet = lookup_options
+            .client_subnet()
+            .filter(|_| self.forward_client_subnet);
+        let resolver = match client_subnet {
+            Some(client_subnet) => {
+                request_options.client_subnet = Some(client_subnet);
+                request_options.dnssec_ok = lookup_options.is_dnssec();
+                &self.uncached_resolver
+            }
+            None => {
+                request_options.dnssec_ok = true;
+                &self.resolver
+            }
+        };
+
         debug!("forwarding lookup: {} {}", name, rtype);
         let name: LowerName = name.clone();
-        let resolve = self
-            .resolver
-            .lookup(name, rtype, DnsRequestOptions::default())
-            .await;
-
-        resolve.map(ForwardLookup).map_err(LookupError::from)
+        let resolve = resolver.lookup(name, rtype, request_options).await;
+
+        resolve
+            .map(|lookup| ForwardLookup {
+                lookup,
+                rtype,
+                is_dnssec: lookup_options.is_dnssec(),
+            })
+            .map_err(LookupError::from)
     }
 
     async fn search(
@@ -164,15 +223,26 @@ impl Authority for ForwardAuthority {
     }
 }
 
-pub struct ForwardLookup(ResolverLookup);
+/// The records of a forwarded lookup
+///
+/// The cache of the resolver is shared by all clients, the DNSSEC records which were not queried for are only
+///  returned to the clients which set the DO bit.
+pub struct ForwardLookup {
+    lookup: ResolverLookup,
+    rtype: RecordType,
+    is_dnssec: bool,
+}
 
 impl LookupObject for ForwardLookup {
     fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.iter().next().is_none()
     }
 
     fn iter<'a>(&'a self) -> Box<dyn Iterator<Item = &'a Record> + Send + 'a> {
-        Box::new(self.0.record_iter())
+        let (rtype, is_dnssec) = (self.rtype, self.is_dnssec);
+        Box::new(self.lookup.record_iter().filter(move |record| {
+            is_dnssec || record.rr_type() == rtype || !record.rr_type().is_dnssec()
+        }))
     }
 
     fn take_additionals(&mut self) -> Option<Box<dyn LookupObject>> {
@@ -16,4 +16,10 @@ pub struct ForwardConfig {
     pub name_servers: NameServerConfigGroup,
     /// Resolver options
     pub options: Option<ResolverOpts>,
+    /// Pass the EDNS Client Subnet option of requests on to the upstream resolvers, default is false
+    ///
+    /// The answers for a Client Subnet are specific to that subnet, so these requests bypass the cache. When false
+    ///  the option of requests is ignored, and they are answered from the cache like any other.
+    #[serde(default)]
+    pub forward_client_subnet: bool,
 }
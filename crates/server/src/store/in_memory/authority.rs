@@ -21,13 +21,11 @@ use log::{debug, error, warn};
 use tokio::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 #[cfg(feature = "dnssec")]
-use crate::{
-    authority::DnssecAuthority,
-    client::rr::{
-        dnssec::{DnsSecResult, SigSigner, SupportedAlgorithms},
-        rdata::{key::KEY, DNSSECRData},
-    },
-};
+use std::time::Duration;
+
+#[cfg(feature = "dnssec")]
+use time::OffsetDateTime;
+
 use crate::{
     authority::{
         AnyRecords, AuthLookup, Authority, LookupError, LookupOptions, LookupRecords, LookupResult,
@@ -42,6 +40,14 @@ use crate::{
     },
     server::RequestInfo,
 };
+#[cfg(feature = "dnssec")]
+use crate::{
+    authority::{DnssecAuthority, RolloverTiming},
+    client::rr::{
+        dnssec::{DnsSecResult, SigSigner, SupportedAlgorithms},
+        rdata::{key::KEY, DNSSECRData, DNSKEY},
+    },
+};
 #[cfg(all(feature = "dnssec", feature = "testing"))]
 use std::ops::Deref;
 
@@ -168,6 +174,24 @@ impl InMemoryAuthority {
         &mut self.inner.get_mut().records
     }
 
+    /// Replaces all records of the zone with those of `other`, e.g. after the zone was reloaded from its source
+    ///
+    /// The keys for updating and signing the zone are kept. If the zone is signed, the DNSKEYs of its keys are
+    ///  published in the new records, which are then signed with an incremented serial.
+    pub async fn replace_records(&self, mut other: Self) {
+        let records = std::mem::take(&mut other.inner.get_mut().records);
+
+        let mut inner = self.inner.write().await;
+        inner.records = records;
+
+        #[cfg(feature = "dnssec")]
+        {
+            if let Err(e) = inner.resecure_zone(self.origin(), self.class) {
+                warn!("failed to sign replaced zone {}: {}", self.origin(), e);
+            }
+        }
+    }
+
     /// Returns the minimum ttl (as used in the SOA record)
     pub async fn minimum_ttl(&self) -> u32 {
         self.inner.read().await.minimum_ttl(self.origin())
@@ -298,6 +322,50 @@ impl InMemoryAuthority {
         inner.get_mut().secure_zone_mut(origin, self.class)
     }
 
+    /// Sets how far in the past the inception of generated RRSIGs is, defaults to none
+    ///
+    /// Signatures are valid from `now - offset` until `now + sig_duration` of the signing key.
+    ///  Backdating the inception allows validators with a slow clock to accept fresh signatures.
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn set_rrsig_inception_offset(&mut self, offset: Duration) {
+        self.inner.get_mut().inception_offset = offset;
+    }
+
+    /// Denies the records of DNSSEC queries with "white lies", [RFC 4470](https://tools.ietf.org/html/rfc4470),
+    ///  instead of the NSEC records chaining the names of the zone, defaults to false
+    ///
+    /// An NSEC record is generated and signed for each such query, preventing the enumeration of the zone. Its owner
+    ///  is the query name, and its next name the immediately following name, `\000.<query name>`, so that it covers
+    ///  no other name. Its type bitmap lists the types at the name, or only RRSIG and NSEC if there are none. As the
+    ///  record claims the query name to exist, names which don't exist are answered with NOERROR and no records,
+    ///  instead of NXDOMAIN, like with compact denial of existence.
+    #[cfg(feature = "dnssec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
+    pub fn set_nsec_white_lies(&mut self, white_lies: bool) {
+        self.inner.get_mut().nsec_white_lies = white_lies;
+    }
+
+    /// Signs any changed records and regenerates the NSEC records, if there are zone signing keys
+    ///
+    /// Once a zone signing key has been added, this is performed automatically on lookups with
+    ///  DNSSEC requested (the DO bit). Signatures are cached on the records, and are only
+    ///  regenerated when the records change, or when half the signature duration has passed.
+    #[cfg(feature = "dnssec")]
+    async fn sign_if_needed(&self) {
+        let now = OffsetDateTime::now_utc();
+
+        // check with the read lock first, this is the common case
+        if !self.inner.read().await.needs_signing(now) {
+            return;
+        }
+
+        let mut inner = self.inner.write().await;
+        if let Err(e) = inner.sign_if_needed(self.origin(), self.class, now) {
+            warn!("failed to sign zone {}: {}", self.origin(), e);
+        }
+    }
+
     /// (Re)generates the nsec records, increments the serial number and signs the zone
     #[cfg(not(feature = "dnssec"))]
     #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
@@ -316,6 +384,52 @@ struct InnerInMemory {
     //   for this, in some form, perhaps alternate root zones...
     #[cfg(feature = "dnssec")]
     secure_keys: Vec<SigSigner>,
+    /// Set when records have changed since the zone was last signed
+    #[cfg(feature = "dnssec")]
+    needs_signing: bool,
+    /// After this time all signatures in the zone are regenerated
+    #[cfg(feature = "dnssec")]
+    resign_at: Option<OffsetDateTime>,
+    /// How far to backdate the inception of RRSIGs
+    #[cfg(feature = "dnssec")]
+    inception_offset: Duration,
+    /// The zone signing key rollover in progress, if any, which is not persisted
+    #[cfg(feature = "dnssec")]
+    rollover: Option<Rollover>,
+    /// Deny records with NSEC records generated for each query, see `InMemoryAuthority::set_nsec_white_lies`
+    #[cfg(feature = "dnssec")]
+    nsec_white_lies: bool,
+}
+
+/// The stages of a zone signing key rollover, see `DnssecAuthority::begin_zsk_rollover`
+#[cfg(feature = "dnssec")]
+enum Rollover {
+    /// The DNSKEY of the new key is published, it signs the zone instead of the key with `old_key_tag` from `until`
+    PrePublished {
+        old_key_tag: u16,
+        signer: Box<SigSigner>,
+        until: OffsetDateTime,
+        retire_delay: Duration,
+    },
+    /// The new key signs the zone, the DNSKEY of the old key is removed at `until`
+    Active {
+        retired: DNSKEY,
+        until: OffsetDateTime,
+    },
+}
+
+#[cfg(feature = "dnssec")]
+impl Rollover {
+    fn until(&self) -> OffsetDateTime {
+        match self {
+            Self::PrePublished { until, .. } | Self::Active { until, .. } => *until,
+        }
+    }
+}
+
+#[cfg(feature = "dnssec")]
+fn has_key_tag(key: &SigSigner, key_tag: u16) -> bool {
+    matches!(key.calculate_key_tag(), Ok(tag) if tag == key_tag)
 }
 
 impl InnerInMemory {
@@ -639,6 +753,13 @@ impl InnerInMemory {
         let mut records_clone = RecordSet::clone(&*records);
         if records_clone.insert(record, serial) {
             *records = Arc::new(records_clone);
+
+            // the rrsigs were cleared by the insert
+            #[cfg(feature = "dnssec")]
+            {
+                self.needs_signing = true;
+            }
+
             true
         } else {
             false
@@ -649,6 +770,8 @@ impl InnerInMemory {
     #[cfg(feature = "dnssec")]
     #[cfg_attr(docsrs, doc(cfg(feature = "dnssec")))]
     fn secure_zone_mut(&mut self, origin: &LowerName, dns_class: DNSClass) -> DnsSecResult<()> {
+        self.advance_rollover(origin, dns_class, OffsetDateTime::now_utc())?;
+
         // TODO: only call nsec_zone after adds/deletes
         // needs to be called before incrementing the soa serial, to make sure IXFR works properly
         self.nsec_zone(origin, dns_class);
@@ -661,6 +784,301 @@ impl InnerInMemory {
         self.sign_zone(origin, dns_class)
     }
 
+    /// Publishes the DNSKEYs of the signing keys, and of a rollover in progress, and signs the zone
+    ///
+    /// This restores the signed state of the zone after its records were replaced.
+    #[cfg(feature = "dnssec")]
+    fn resecure_zone(&mut self, origin: &LowerName, dns_class: DNSClass) -> DnsSecResult<()> {
+        if self.secure_keys.is_empty() {
+            return Ok(());
+        }
+
+        let mut dnskeys = self
+            .secure_keys
+            .iter()
+            .map(|key| key.key().to_dnskey(key.algorithm()))
+            .collect::<DnsSecResult<Vec<_>>>()?;
+        match self.rollover {
+            Some(Rollover::PrePublished { ref signer, .. }) => {
+                dnskeys.push(signer.key().to_dnskey(signer.algorithm())?)
+            }
+            Some(Rollover::Active { ref retired, .. }) => dnskeys.push(retired.clone()),
+            None => (),
+        }
+
+        let zone_ttl = self.minimum_ttl(origin);
+        let serial = self.serial(origin);
+        for dnskey in dnskeys {
+            let dnskey = Record::from_rdata(
+                origin.clone().into(),
+                zone_ttl,
+                RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
+            );
+            self.upsert(dnskey, serial, dns_class);
+        }
+
+        self.secure_zone_mut(origin, dns_class)
+    }
+
+    /// Returns true if there are records to sign, or the signatures should be refreshed
+    #[cfg(feature = "dnssec")]
+    fn needs_signing(&self, now: OffsetDateTime) -> bool {
+        !self.secure_keys.is_empty()
+            && (self.needs_signing
+                || !matches!(self.resign_at, Some(resign_at) if now < resign_at)
+                || matches!(&self.rollover, Some(rollover) if now >= rollover.until()))
+    }
+
+    /// Publishes the DNSKEY of `signer`, which replaces the key with `old_key_tag` after the `publish_delay`
+    #[cfg(feature = "dnssec")]
+    fn begin_zsk_rollover(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        old_key_tag: u16,
+        signer: SigSigner,
+        timing: RolloverTiming,
+        now: OffsetDateTime,
+    ) -> DnsSecResult<()> {
+        if self.rollover.is_some() {
+            return Err("a zone signing key rollover is already in progress".into());
+        }
+
+        let mut old_keys = self
+            .secure_keys
+            .iter()
+            .filter(|key| has_key_tag(key, old_key_tag));
+        let old_key = match (old_keys.next(), old_keys.next()) {
+            (Some(old_key), None) => old_key,
+            (None, _) => {
+                return Err(format!("no zone signing key with key tag: {}", old_key_tag).into())
+            }
+            (Some(_), Some(_)) => {
+                return Err(
+                    format!("several zone signing keys with key tag: {}", old_key_tag).into(),
+                )
+            }
+        };
+        if old_key.algorithm() != signer.algorithm() {
+            return Err(format!(
+                "the zone signing key with key tag: {} has algorithm: {}, not: {}",
+                old_key_tag,
+                old_key.algorithm(),
+                signer.algorithm()
+            )
+            .into());
+        }
+
+        let zone_ttl = self.minimum_ttl(origin);
+        let dnskey = signer.key().to_dnskey(signer.algorithm())?;
+        let dnskey = Record::from_rdata(
+            origin.clone().into(),
+            zone_ttl,
+            RData::DNSSEC(DNSSECRData::DNSKEY(dnskey)),
+        );
+
+        // the DNSKEY RRset is resigned with the old keys
+        let serial = self.serial(origin);
+        self.upsert(dnskey, serial, dns_class);
+        self.increment_soa_serial(origin, dns_class);
+
+        self.rollover = Some(Rollover::PrePublished {
+            old_key_tag,
+            signer: Box::new(signer),
+            until: now + timing.publish_delay,
+            retire_delay: timing.retire_delay,
+        });
+        Ok(())
+    }
+
+    /// Moves the rollover in progress to its next stage, if it is due
+    ///
+    /// Every stage lasts at least until the zone is signed once, even if its delay is zero.
+    #[cfg(feature = "dnssec")]
+    fn advance_rollover(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        now: OffsetDateTime,
+    ) -> DnsSecResult<()> {
+        match self.rollover.take() {
+            Some(Rollover::PrePublished {
+                old_key_tag,
+                signer,
+                until,
+                retire_delay,
+            }) if now >= until => {
+                debug!(
+                    "zone signing key rollover of {}: signing with the new key",
+                    origin
+                );
+
+                // the old key no longer signs the zone, but stays published
+                let position = self
+                    .secure_keys
+                    .iter()
+                    .position(|key| has_key_tag(key, old_key_tag))
+                    .ok_or_else(|| format!("no zone signing key with key tag: {}", old_key_tag))?;
+                let retired = self.secure_keys.remove(position);
+                let retired = retired.key().to_dnskey(retired.algorithm())?;
+                self.secure_keys.push(*signer);
+
+                // all signatures are replaced by those of the new key
+                self.increment_soa_serial(origin, dns_class);
+                self.resign_at = None;
+
+                self.rollover = Some(Rollover::Active {
+                    retired,
+                    until: now + retire_delay,
+                });
+            }
+            Some(Rollover::Active { retired, until }) if now >= until => {
+                debug!(
+                    "zone signing key rollover of {}: removing the old key",
+                    origin
+                );
+
+                let serial = self.serial(origin);
+                let rr_key = RrKey::new(origin.clone(), RecordType::DNSKEY);
+                if let Some(rrset) = self.records.get_mut(&rr_key) {
+                    let rrset = Arc::make_mut(rrset);
+                    let dnskey = Record::from_rdata(
+                        origin.clone().into(),
+                        0,
+                        RData::DNSSEC(DNSSECRData::DNSKEY(retired)),
+                    );
+                    rrset.remove(&dnskey, serial);
+                    rrset.clear_rrsigs();
+                }
+
+                self.needs_signing = true;
+                self.increment_soa_serial(origin, dns_class);
+            }
+            rollover => self.rollover = rollover,
+        }
+
+        Ok(())
+    }
+
+    /// Signs all unsigned record sets, regenerating the NSEC records if anything changed
+    ///
+    /// All record sets are resigned once half of the shortest signature duration has passed.
+    #[cfg(feature = "dnssec")]
+    fn sign_if_needed(
+        &mut self,
+        origin: &LowerName,
+        dns_class: DNSClass,
+        now: OffsetDateTime,
+    ) -> DnsSecResult<()> {
+        if !self.needs_signing(now) {
+            return Ok(());
+        }
+
+        self.advance_rollover(origin, dns_class, now)?;
+
+        let resign_all = !matches!(self.resign_at, Some(resign_at) if now < resign_at);
+        debug!("online signing zone: {} all: {}", origin, resign_all);
+
+        if self.needs_signing {
+            // names may have been added, the NSEC records are regenerated and need signing
+            self.nsec_zone(origin, dns_class);
+        }
+
+        let minimum_ttl = self.minimum_ttl(origin);
+        let secure_keys = &self.secure_keys;
+        let inception_offset = self.inception_offset;
+
+        for rr_set_orig in self.records.values_mut() {
+            if !resign_all && !rr_set_orig.rrsigs().is_empty() {
+                continue;
+            }
+
+            // because the rrset is an Arc, it must be cloned before mutated
+            let rr_set = Arc::make_mut(rr_set_orig);
+            Self::sign_rrset(
+                rr_set,
+                secure_keys,
+                minimum_ttl,
+                dns_class,
+                inception_offset,
+            )?;
+        }
+
+        self.needs_signing = false;
+        if resign_all {
+            self.resign_at = Some(self.next_resign(now));
+        }
+
+        Ok(())
+    }
+
+    /// The time at which the signatures created at `now` should be refreshed
+    #[cfg(feature = "dnssec")]
+    fn next_resign(&self, now: OffsetDateTime) -> OffsetDateTime {
+        let shortest = self
+            .secure_keys
+            .iter()
+            .map(SigSigner::sig_duration)
+            .min()
+            .unwrap_or_default();
+
+        now + shortest / 2
+    }
+
+    /// True if records are denied with white lies in answer to the lookup
+    #[cfg(feature = "dnssec")]
+    fn tells_white_lies(&self, lookup_options: LookupOptions) -> bool {
+        self.nsec_white_lies && lookup_options.is_dnssec() && !self.secure_keys.is_empty()
+    }
+
+    #[cfg(not(feature = "dnssec"))]
+    fn tells_white_lies(&self, _lookup_options: LookupOptions) -> bool {
+        false
+    }
+
+    /// Generates the signed NSEC record with the name as owner, denying all other types at it, and no other name
+    #[cfg(feature = "dnssec")]
+    fn white_lie_nsec(
+        &self,
+        name: &LowerName,
+        origin: &LowerName,
+        dns_class: DNSClass,
+    ) -> DnsSecResult<RecordSet> {
+        use crate::client::rr::rdata::NSEC;
+
+        // this range covers all the records for any of the RecordTypes at a given label.
+        let start_range_key = RrKey::new(name.clone(), RecordType::Unknown(u16::min_value()));
+        let end_range_key = RrKey::new(name.clone(), RecordType::Unknown(u16::max_value()));
+
+        let mut type_bit_maps: Vec<RecordType> = self
+            .records
+            .range(&start_range_key..&end_range_key)
+            .map(|(key, _)| key.record_type)
+            .filter(|record_type| *record_type != RecordType::NSEC)
+            .collect();
+        type_bit_maps.extend_from_slice(&[RecordType::RRSIG, RecordType::NSEC]);
+
+        // the immediate successor of the name in the canonical order
+        let name = Name::from(name);
+        let next_domain_name = Name::from_labels(Some(&[0u8][..]))?.append_name(&name)?;
+
+        let ttl = self.minimum_ttl(origin);
+        let mut rr_set = RecordSet::with_ttl(name, RecordType::NSEC, ttl);
+        rr_set.add_rdata(RData::DNSSEC(DNSSECRData::NSEC(NSEC::new(
+            next_domain_name,
+            type_bit_maps,
+        ))));
+
+        Self::sign_rrset(
+            &mut rr_set,
+            &self.secure_keys,
+            ttl,
+            dns_class,
+            self.inception_offset,
+        )?;
+        Ok(rr_set)
+    }
+
     /// Dummy implementation for when DNSSEC is disabled.
     #[cfg(feature = "dnssec")]
     fn nsec_zone(&mut self, origin: &LowerName, dns_class: DNSClass) {
@@ -737,18 +1155,20 @@ impl InnerInMemory {
     /// * `secure_keys` - Set of keys to use to sign the RecordSet, see `self.signers()`
     /// * `zone_ttl` - the zone TTL, see `self.minimum_ttl()`
     /// * `zone_class` - DNSClass of the zone, see `self.zone_class()`
+    /// * `inception_offset` - how far to backdate the signature inception
     #[cfg(feature = "dnssec")]
     fn sign_rrset(
         rr_set: &mut RecordSet,
         secure_keys: &[SigSigner],
         zone_ttl: u32,
         zone_class: DNSClass,
+        inception_offset: Duration,
     ) -> DnsSecResult<()> {
         use crate::client::rr::dnssec::tbs;
         use crate::client::rr::rdata::SIG;
-        use time::OffsetDateTime;
 
-        let inception = OffsetDateTime::now_utc();
+        let now = OffsetDateTime::now_utc();
+        let inception = now - inception_offset;
 
         rr_set.clear_rrsigs();
 
@@ -762,7 +1182,7 @@ impl InnerInMemory {
                 signer.algorithm(),
             );
 
-            let expiration = inception + signer.sig_duration();
+            let expiration = now + signer.sig_duration();
 
             let tbs = tbs::rrset_tbs(
                 rr_set.name(),
@@ -836,6 +1256,7 @@ impl InnerInMemory {
 
         let minimum_ttl = self.minimum_ttl(origin);
         let secure_keys = &self.secure_keys;
+        let inception_offset = self.inception_offset;
         let records = &mut self.records;
 
         // TODO: should this be an error?
@@ -850,9 +1271,18 @@ impl InnerInMemory {
         for rr_set_orig in records.values_mut() {
             // because the rrset is an Arc, it must be cloned before mutated
             let rr_set = Arc::make_mut(rr_set_orig);
-            Self::sign_rrset(rr_set, secure_keys, minimum_ttl, dns_class)?;
+            Self::sign_rrset(
+                rr_set,
+                secure_keys,
+                minimum_ttl,
+                dns_class,
+                inception_offset,
+            )?;
         }
 
+        self.needs_signing = false;
+        self.resign_at = Some(self.next_resign(OffsetDateTime::now_utc()));
+
         Ok(())
     }
 }
@@ -1011,6 +1441,11 @@ impl Authority for InMemoryAuthority {
         query_type: RecordType,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
+        #[cfg(feature = "dnssec")]
+        if lookup_options.is_dnssec() {
+            self.sign_if_needed().await;
+        }
+
         let inner = self.inner.read().await;
 
         // Collect the records from each rr_set
@@ -1109,6 +1544,7 @@ impl Authority for InMemoryAuthority {
                                             inner.secure_keys(),
                                             inner.minimum_ttl(self.origin()),
                                             self.class(),
+                                            inner.inception_offset,
                                         )
                                         // rather than failing the request, we'll just warn
                                         .map_err(|e| warn!("failed to sign ANAME record: {}", e))
@@ -1152,7 +1588,9 @@ impl Authority for InMemoryAuthority {
                     .records
                     .keys()
                     .any(|key| key.name() == name || name.zone_of(key.name()))
+                    || (inner.tells_white_lies(lookup_options) && self.origin().zone_of(name))
                 {
+                    // with white lies the names are never denied, only the types at them
                     return Err(LookupError::NameExists);
                 } else {
                     let code = if self.origin().zone_of(name) {
@@ -1239,7 +1677,21 @@ impl Authority for InMemoryAuthority {
         name: &LowerName,
         lookup_options: LookupOptions,
     ) -> Result<Self::Lookup, LookupError> {
+        if lookup_options.is_dnssec() {
+            self.sign_if_needed().await;
+        }
+
         let inner = self.inner.read().await;
+        if inner.tells_white_lies(lookup_options) {
+            return match inner.white_lie_nsec(name, self.origin(), self.class) {
+                Ok(rr_set) => Ok(LookupRecords::new(lookup_options, Arc::new(rr_set)).into()),
+                Err(e) => {
+                    warn!("failed to generate the NSEC record for {}: {}", name, e);
+                    Err(LookupError::from(ResponseCode::ServFail))
+                }
+            };
+        }
+
         fn is_nsec_rrset(rr_set: &RecordSet) -> bool {
             rr_set.record_type() == RecordType::NSEC
         }
@@ -1354,4 +1806,23 @@ impl DnssecAuthority for InMemoryAuthority {
 
         inner.secure_zone_mut(self.origin(), self.class)
     }
+
+    /// Begin the rollover of the zone signing key
+    async fn begin_zsk_rollover(
+        &self,
+        old_key_tag: u16,
+        new_signer: SigSigner,
+        timing: RolloverTiming,
+    ) -> DnsSecResult<()> {
+        let mut inner = self.inner.write().await;
+
+        inner.begin_zsk_rollover(
+            self.origin(),
+            self.class,
+            old_key_tag,
+            new_signer,
+            timing,
+            OffsetDateTime::now_utc(),
+        )
+    }
 }
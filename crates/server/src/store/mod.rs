@@ -11,6 +11,7 @@ mod config;
 pub mod file;
 pub mod forwarder;
 pub mod in_memory;
+pub mod secondary;
 #[cfg(feature = "sqlite")]
 #[cfg_attr(docsrs, doc(cfg(feature = "sqlite")))]
 pub mod sqlite;
@@ -0,0 +1,357 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! All authority related types
+
+use std::{collections::BTreeMap, convert::TryFrom, net::SocketAddr, sync::Arc, time::Duration};
+
+use futures_util::stream::TryStreamExt;
+use log::{debug, info, warn};
+use tokio::{net::TcpStream as TokioTcpStream, sync::Notify};
+
+#[cfg(feature = "dnssec")]
+use crate::client::rr::rdata::DNSSECRData;
+use crate::{
+    authority::{Authority, LookupError, LookupOptions, MessageRequest, UpdateResult, ZoneType},
+    client::{
+        client::{AsyncClient, ClientHandle, ZoneDiff},
+        error::{ClientError, ClientErrorKind},
+        op::ResponseCode,
+        rr::{
+            rdata::SOA, LowerName, Name, RData, Record, RecordSet, RecordType, RrKey, SerialNumber,
+        },
+        tcp::TcpClientStream,
+    },
+    proto::iocompat::AsyncIoTokioAsStd,
+    server::RequestInfo,
+    store::in_memory::InMemoryAuthority,
+};
+
+/// SecondaryAuthority serves a zone which is transferred from its primaries.
+///
+/// The zone is empty until the first `refresh`. A NOTIFY, [RFC 1996](https://tools.ietf.org/html/rfc1996), from
+///  one of the primaries schedules a refresh, which is performed by `refresh_on_notify`. A refresh checks the
+///  serial of the SOA at the primary and, if it is newer, transfers the changes to the zone with IXFR (or the
+///  entire zone with AXFR if there is no local copy yet, or the primary does not support IXFR).
+pub struct SecondaryAuthority {
+    in_memory: InMemoryAuthority,
+    primaries: Vec<SocketAddr>,
+    refresh: Notify,
+}
+
+impl SecondaryAuthority {
+    /// Creates a new, empty, secondary Authority.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The zone `Name` being transferred.
+    /// * `primaries` - The addresses of the primaries of the zone, these are the only sources from which a NOTIFY
+    ///                 is accepted, and are tried in order on refresh.
+    /// * `allow_axfr` - If true, then this zone may itself be transferred.
+    pub fn new(origin: Name, primaries: Vec<SocketAddr>, allow_axfr: bool) -> Self {
+        Self {
+            in_memory: InMemoryAuthority::empty(origin, ZoneType::Secondary, allow_axfr),
+            primaries,
+            refresh: Notify::new(),
+        }
+    }
+
+    /// The addresses of the primaries of this zone
+    pub fn primaries(&self) -> &[SocketAddr] {
+        &self.primaries
+    }
+
+    /// The records of the zone, as last transferred, without the signatures
+    pub async fn records(&self) -> Vec<Record> {
+        self.in_memory
+            .records()
+            .await
+            .values()
+            .flat_map(|rrset| rrset.records_without_rrsigs())
+            .cloned()
+            .collect()
+    }
+
+    /// Completes once a refresh has been requested by a NOTIFY, a request made while no one was waiting is kept.
+    pub async fn refresh_requested(&self) {
+        self.refresh.notified().await
+    }
+
+    /// Refreshes the zone each time a NOTIFY is received from one of the primaries, this never completes.
+    pub async fn refresh_on_notify(&self) {
+        loop {
+            self.refresh_requested().await;
+
+            if let Err(e) = self.refresh().await {
+                warn!("failed to refresh zone {}: {}", self.origin(), e);
+            }
+        }
+    }
+
+    /// Checks the SOA serial at the primaries and transfers the zone if it is newer than the local copy.
+    ///
+    /// The primaries are tried in order until one succeeds. Returns true if the zone was updated.
+    pub async fn refresh(&self) -> Result<bool, ClientError> {
+        let mut error = None;
+
+        for primary in &self.primaries {
+            match self.refresh_from(*primary).await {
+                Ok(updated) => return Ok(updated),
+                Err(e) => {
+                    debug!(
+                        "refresh of {} from {} failed: {}",
+                        self.origin(),
+                        primary,
+                        e
+                    );
+                    error = Some(e);
+                }
+            }
+        }
+
+        Err(error.unwrap_or_else(|| ClientErrorKind::Message("no primaries configured").into()))
+    }
+
+    async fn refresh_from(&self, primary: SocketAddr) -> Result<bool, ClientError> {
+        match self.transfer_from(primary, true).await {
+            // not every primary supports IXFR
+            Err(e) if self.current_serial().await.is_some() => {
+                debug!(
+                    "incremental refresh of {} from {} failed, trying AXFR: {}",
+                    self.origin(),
+                    primary,
+                    e
+                );
+                self.transfer_from(primary, false).await
+            }
+            result => result,
+        }
+    }
+
+    async fn transfer_from(
+        &self,
+        primary: SocketAddr,
+        incremental: bool,
+    ) -> Result<bool, ClientError> {
+        let (stream, sender) = TcpClientStream::<AsyncIoTokioAsStd<TokioTcpStream>>::new(primary);
+        let (mut client, bg) = AsyncClient::new(stream, sender, None).await?;
+        let bg = tokio::spawn(bg);
+
+        let result = self.transfer(&mut client, incremental).await;
+        bg.abort();
+        result
+    }
+
+    async fn transfer(
+        &self,
+        client: &mut AsyncClient,
+        incremental: bool,
+    ) -> Result<bool, ClientError> {
+        let origin = Name::from(self.origin());
+        let response = client
+            .query(origin.clone(), self.in_memory.class(), RecordType::SOA)
+            .await?;
+        let serial = response
+            .answers()
+            .iter()
+            .find_map(|r| r.data().and_then(RData::as_soa))
+            .map(SOA::serial)
+            .ok_or(ClientErrorKind::Message("primary did not return an SOA"))?;
+
+        let current_serial = self.current_serial().await;
+        if let Some(current_serial) = current_serial {
+            if SerialNumber::from(serial) <= SerialNumber::from(current_serial) {
+                debug!("zone {} is up to date: {}", self.origin(), current_serial);
+                return Ok(false);
+            }
+        }
+
+        let diff = match current_serial {
+            Some(current_serial) if incremental => {
+                client.incremental_transfer(origin, current_serial).await?
+            }
+            _ => {
+                let mut answers = client
+                    .zone_transfer(origin, None)
+                    .try_fold(Vec::new(), |mut answers, mut response| {
+                        answers.extend(response.take_answers());
+                        futures_util::future::ok(answers)
+                    })
+                    .await?;
+
+                // the transfer is terminated by a copy of the leading SOA
+                answers.pop();
+                ZoneDiff::Full(answers)
+            }
+        };
+
+        let mut records = self.in_memory.records_mut().await;
+        let transferred = match diff {
+            ZoneDiff::Full(transferred) => transferred,
+            ZoneDiff::Incremental(diffs) => {
+                let mut current = records
+                    .values()
+                    .flat_map(|rrset| rrset.records_without_rrsigs().chain(rrset.rrsigs()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                for (deleted, added) in diffs {
+                    current.retain(|record| !deleted.iter().any(|d| is_same_record(d, record)));
+                    current.extend(added);
+                }
+
+                current
+            }
+        };
+
+        *records = to_record_sets(transferred, serial);
+        info!("zone {} transferred at serial {}", self.origin(), serial);
+        Ok(true)
+    }
+
+    /// The refresh interval of the SOA of the zone, `None` until the zone was transferred
+    pub async fn refresh_interval(&self) -> Option<Duration> {
+        let soa = Authority::soa(&self.in_memory).await.ok()?;
+        let refresh = soa
+            .iter()
+            .find_map(|r| r.data().and_then(RData::as_soa))
+            .map(SOA::refresh)?;
+        Some(Duration::from_secs(u64::try_from(refresh).ok()?))
+    }
+
+    async fn current_serial(&self) -> Option<u32> {
+        let soa = Authority::soa(&self.in_memory).await.ok()?;
+        let serial = soa
+            .iter()
+            .find_map(|r| r.data().and_then(RData::as_soa))
+            .map(SOA::serial);
+        serial
+    }
+}
+
+/// Records are the same if they only differ in TTL
+fn is_same_record(left: &Record, right: &Record) -> bool {
+    left.name() == right.name()
+        && left.rr_type() == right.rr_type()
+        && left.dns_class() == right.dns_class()
+        && left.data() == right.data()
+}
+
+fn to_record_sets(records: Vec<Record>, serial: u32) -> BTreeMap<RrKey, Arc<RecordSet>> {
+    let mut record_sets = BTreeMap::<RrKey, RecordSet>::new();
+    #[cfg(feature = "dnssec")]
+    let mut rrsigs = Vec::new();
+
+    for record in records {
+        if record.rr_type() == RecordType::RRSIG {
+            #[cfg(feature = "dnssec")]
+            rrsigs.push(record);
+            continue;
+        }
+
+        let key = RrKey::new(LowerName::new(record.name()), record.rr_type());
+        record_sets
+            .entry(key)
+            .or_insert_with(|| RecordSet::new(record.name(), record.rr_type(), serial))
+            .insert(record, serial);
+    }
+
+    // signatures are kept with the RecordSet they cover
+    #[cfg(feature = "dnssec")]
+    for rrsig in rrsigs {
+        let type_covered = match rrsig.data() {
+            Some(RData::DNSSEC(DNSSECRData::SIG(sig))) => sig.type_covered(),
+            _ => continue,
+        };
+
+        let key = RrKey::new(LowerName::new(rrsig.name()), type_covered);
+        if let Some(record_set) = record_sets.get_mut(&key) {
+            record_set.insert_rrsig(rrsig);
+        }
+    }
+
+    record_sets
+        .into_iter()
+        .map(|(key, record_set)| (key, Arc::new(record_set)))
+        .collect()
+}
+
+#[async_trait::async_trait]
+impl Authority for SecondaryAuthority {
+    type Lookup = <InMemoryAuthority as Authority>::Lookup;
+
+    /// What type is this zone
+    fn zone_type(&self) -> ZoneType {
+        self.in_memory.zone_type()
+    }
+
+    /// Return true if AXFR is allowed
+    fn is_axfr_allowed(&self) -> bool {
+        self.in_memory.is_axfr_allowed()
+    }
+
+    /// Updates are not supported on secondaries, they must be sent to the primary
+    async fn update(&self, _update: &MessageRequest) -> UpdateResult<bool> {
+        Err(ResponseCode::NotImp)
+    }
+
+    /// Schedules a refresh of the zone, if the NOTIFY is from one of the primaries
+    ///
+    /// Any SOA hint in the answer section is ignored, the serial is always checked at the primary.
+    async fn notify(&self, request_info: RequestInfo<'_>) -> UpdateResult<bool> {
+        let src = request_info.src.ip();
+        if !self.primaries.iter().any(|primary| primary.ip() == src) {
+            warn!(
+                "refusing notify for {} from unknown primary: {}",
+                self.origin(),
+                src
+            );
+            return Err(ResponseCode::Refused);
+        }
+
+        info!(
+            "notify for {} from {}, refresh scheduled",
+            self.origin(),
+            src
+        );
+        self.refresh.notify_one();
+        Ok(true)
+    }
+
+    /// Get the origin of this zone, i.e. example.com is the origin for www.example.com
+    fn origin(&self) -> &LowerName {
+        self.in_memory.origin()
+    }
+
+    /// Looks up all Resource Records matching the giving `Name` and `RecordType`.
+    async fn lookup(
+        &self,
+        name: &LowerName,
+        rtype: RecordType,
+        lookup_options: LookupOptions,
+    ) -> Result<Self::Lookup, LookupError> {
+        self.in_memory.lookup(name, rtype, lookup_options).await
+    }
+
+    /// Using the specified query, perform a lookup against this zone.
+    async fn search(
+        &self,
+        request_info: RequestInfo<'_>,
+        lookup_options: LookupOptions,
+    ) -> Result<Self::Lookup, LookupError> {
+        self.in_memory.search(request_info, lookup_options).await
+    }
+
+    /// Return the NSEC records based on the given name
+    async fn get_nsec_records(
+        &self,
+        name: &LowerName,
+        lookup_options: LookupOptions,
+    ) -> Result<Self::Lookup, LookupError> {
+        self.in_memory.get_nsec_records(name, lookup_options).await
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright 2015-2022 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Catalog zones, [RFC 9432](https://tools.ietf.org/html/rfc9432), listing the member zones to serve
+
+use std::collections::{BTreeMap, HashSet};
+
+use log::warn;
+
+use crate::client::{
+    error::{ClientError, ClientErrorKind},
+    rr::{LowerName, Name, RData, Record},
+};
+
+/// The only schema version of catalog zones which is supported
+const CATALOG_ZONE_VERSION: &[u8] = b"2";
+
+/// A member zone of a catalog zone
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogMember {
+    zone: Name,
+    unique_id: String,
+    coo: Option<Name>,
+    group: Option<String>,
+}
+
+impl CatalogMember {
+    /// The name of the member zone
+    pub fn zone(&self) -> &Name {
+        &self.zone
+    }
+
+    /// The label identifying the member in the catalog zone, a change of it resets the member zone
+    pub fn unique_id(&self) -> &str {
+        &self.unique_id
+    }
+
+    /// The change of ownership property, the catalog zone to which the member zone is migrating
+    pub fn coo(&self) -> Option<&Name> {
+        self.coo.as_ref()
+    }
+
+    /// The group property, which the producer uses to signal how the member zone should be configured
+    pub fn group(&self) -> Option<&str> {
+        self.group.as_deref()
+    }
+}
+
+/// The member zones listed by a catalog zone, [RFC 9432](https://tools.ietf.org/html/rfc9432)
+///
+/// ```text
+/// version.$CATZ            0 IN TXT "2"
+/// <unique-N>.zones.$CATZ   0 IN PTR example.com.
+/// coo.<unique-N>.zones.$CATZ   0 IN PTR $NEWCATZ
+/// group.<unique-N>.zones.$CATZ 0 IN TXT "group-name"
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogZone {
+    name: Name,
+    members: Vec<CatalogMember>,
+}
+
+/// The records of a member found in the catalog zone, before they are checked
+#[derive(Default)]
+struct MemberRecords {
+    zones: Vec<Name>,
+    coos: Vec<Name>,
+    groups: Vec<String>,
+}
+
+impl CatalogZone {
+    /// Reads the member zones from the records of the catalog zone
+    ///
+    /// Members with more than one zone are ignored, as are the properties which are not single valued. If a zone is
+    ///  listed more than once, only the first member is kept.
+    ///
+    /// # Errors
+    ///
+    /// If the schema version of the catalog zone is missing or not supported.
+    pub fn from_records<'r>(
+        name: Name,
+        records: impl IntoIterator<Item = &'r Record>,
+    ) -> Result<Self, ClientError> {
+        let version = Name::from_ascii("version")?.append_domain(&name)?;
+        let zones = Name::from_ascii("zones")?.append_domain(&name)?;
+
+        let mut versions = Vec::new();
+        let mut members = BTreeMap::<LowerName, MemberRecords>::new();
+        for record in records {
+            let owner = record.name();
+            if *owner == version {
+                if let Some(RData::TXT(txt)) = record.data() {
+                    versions.extend(txt.iter().map(|data| data.to_vec()));
+                }
+                continue;
+            }
+            if !zones.zone_of(owner) {
+                continue;
+            }
+
+            match (owner.num_labels() - zones.num_labels(), record.data()) {
+                // <unique-N>.zones.$CATZ
+                (1, Some(RData::PTR(zone))) => members
+                    .entry(LowerName::new(owner))
+                    .or_default()
+                    .zones
+                    .push(zone.clone()),
+                // <property>.<unique-N>.zones.$CATZ
+                (2, Some(data)) => {
+                    let member = members
+                        .entry(LowerName::new(&owner.base_name()))
+                        .or_default();
+                    let property = owner.iter().next().map(<[u8]>::to_ascii_lowercase);
+                    match (property.as_deref(), data) {
+                        (Some(b"coo"), RData::PTR(coo)) => member.coos.push(coo.clone()),
+                        (Some(b"group"), RData::TXT(txt)) => member.groups.extend(
+                            txt.iter()
+                                .map(|data| String::from_utf8_lossy(data).into_owned()),
+                        ),
+                        _ => (),
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        if versions != [CATALOG_ZONE_VERSION] {
+            return Err(ClientErrorKind::Msg(format!(
+                "catalog zone {} has an unsupported version: {:?}",
+                name,
+                versions
+                    .iter()
+                    .map(|version| String::from_utf8_lossy(version))
+                    .collect::<Vec<_>>()
+            ))
+            .into());
+        }
+
+        let mut seen = HashSet::new();
+        let members = members
+            .into_iter()
+            .filter_map(|(node, records)| {
+                let node = Name::from(node);
+                let mut unique_id = Name::from_labels(node.iter().take(1)).ok()?;
+                unique_id.set_fqdn(false);
+                let unique_id = unique_id.to_ascii();
+
+                let zone = match records.zones.as_slice() {
+                    [zone] => zone.clone(),
+                    // properties without a member
+                    [] => return None,
+                    _ => {
+                        warn!("ignoring catalog zone member {} with several zones", node);
+                        return None;
+                    }
+                };
+                if !seen.insert(LowerName::new(&zone)) {
+                    warn!(
+                        "ignoring catalog zone member {}, {} is listed already",
+                        node, zone
+                    );
+                    return None;
+                }
+
+                Some(CatalogMember {
+                    zone,
+                    unique_id,
+                    coo: single(&node, "coo", records.coos),
+                    group: single(&node, "group", records.groups),
+                })
+            })
+            .collect();
+
+        Ok(Self { name, members })
+    }
+
+    /// The name of the catalog zone
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// The member zones of the catalog
+    pub fn members(&self) -> &[CatalogMember] {
+        &self.members
+    }
+}
+
+/// A property must have a single value, otherwise it's ignored
+fn single<T>(node: &Name, property: &str, mut values: Vec<T>) -> Option<T> {
+    if values.len() > 1 {
+        warn!(
+            "ignoring the {} property of catalog zone member {} with several values",
+            property, node
+        );
+        return None;
+    }
+
+    values.pop()
+}
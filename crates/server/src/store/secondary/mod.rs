@@ -0,0 +1,14 @@
+// Copyright 2015-2021 Benjamin Fry <benjaminfry@me.com>
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or
+// http://apache.org/licenses/LICENSE-2.0> or the MIT license <LICENSE-MIT or
+// http://opensource.org/licenses/MIT>, at your option. This file may not be
+// copied, modified, or distributed except according to those terms.
+
+//! Secondary zones, transferred from their primaries and refreshed on NOTIFY, and catalog zones listing them
+
+mod authority;
+mod catalog_zone;
+
+pub use self::authority::SecondaryAuthority;
+pub use self::catalog_zone::{CatalogMember, CatalogZone};
@@ -32,7 +32,7 @@ use crate::{
 };
 #[cfg(feature = "dnssec")]
 use crate::{
-    authority::{DnssecAuthority, UpdateRequest},
+    authority::{DnssecAuthority, RolloverTiming, UpdateRequest},
     client::rr::dnssec::{DnsSecResult, SigSigner},
     proto::rr::dnssec::rdata::key::KEY,
 };
@@ -46,6 +46,7 @@ pub struct SqliteAuthority {
     in_memory: InMemoryAuthority,
     journal: Mutex<Option<Journal>>,
     allow_update: bool,
+    allow_update_keys: Vec<LowerName>,
     is_dnssec_enabled: bool,
 }
 
@@ -67,6 +68,7 @@ impl SqliteAuthority {
             in_memory,
             journal: Mutex::new(None),
             allow_update,
+            allow_update_keys: Vec::new(),
             is_dnssec_enabled,
         }
     }
@@ -83,6 +85,12 @@ impl SqliteAuthority {
         use crate::store::file::{FileAuthority, FileConfig};
 
         let zone_name: Name = origin;
+        let allow_update_keys = config
+            .allow_update_keys
+            .iter()
+            .map(|key| Name::parse(key, Some(&Name::new())).map(LowerName::from))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("invalid name of an update key: {}", e))?;
 
         let root_zone_dir = root_dir.map(PathBuf::from).unwrap_or_else(PathBuf::new);
 
@@ -91,29 +99,13 @@ impl SqliteAuthority {
         let zone_path: PathBuf = root_zone_dir.join(&config.zone_file_path);
 
         // load the zone
-        if journal_path.exists() {
-            info!("recovering zone from journal: {:?}", journal_path);
-            let journal = Journal::from_file(&journal_path)
-                .map_err(|e| format!("error opening journal: {:?}: {}", journal_path, e))?;
-
-            let in_memory = InMemoryAuthority::empty(zone_name.clone(), zone_type, allow_axfr);
-            let mut authority = Self::new(in_memory, config.allow_update, enable_dnssec);
-
-            authority
-                .recover_with_journal(&journal)
-                .await
-                .map_err(|e| format!("error recovering from journal: {}", e))?;
-
-            authority.set_journal(journal).await;
-            info!("recovered zone: {}", zone_name);
-
-            Ok(authority)
-        } else if zone_path.exists() {
-            // TODO: deprecate this portion of loading, instantiate the journal through a separate tool
+        if zone_path.exists() {
             info!("loading zone file: {:?}", zone_path);
 
             let file_config = FileConfig {
                 zone_file_path: config.zone_file_path.clone(),
+                watch: false,
+                nsec_white_lies: false,
             };
 
             let in_memory = FileAuthority::try_from_config(
@@ -125,22 +117,37 @@ impl SqliteAuthority {
             )?
             .unwrap();
 
+            // replay any updates since the zone file was loaded, or start a new journal
             let mut authority = Self::new(in_memory, config.allow_update, enable_dnssec);
+            authority.set_allow_update_keys(allow_update_keys);
+            #[cfg(feature = "dnssec")]
+            authority.set_nsec_white_lies(config.nsec_white_lies);
+            let authority = authority
+                .with_journal(&journal_path)
+                .await
+                .map_err(|e| format!("error loading journal {:?}: {}", journal_path, e))?;
 
-            // if dynamic update is enabled, enable the journal
-            info!("creating new journal: {:?}", journal_path);
+            info!("zone file loaded: {}", zone_name);
+            Ok(authority)
+        } else if journal_path.exists() {
+            info!("recovering zone from journal: {:?}", journal_path);
             let journal = Journal::from_file(&journal_path)
-                .map_err(|e| format!("error creating journal {:?}: {}", journal_path, e))?;
+                .map_err(|e| format!("error opening journal: {:?}: {}", journal_path, e))?;
 
-            authority.set_journal(journal).await;
+            let in_memory = InMemoryAuthority::empty(zone_name.clone(), zone_type, allow_axfr);
+            let mut authority = Self::new(in_memory, config.allow_update, enable_dnssec);
+            authority.set_allow_update_keys(allow_update_keys);
+            #[cfg(feature = "dnssec")]
+            authority.set_nsec_white_lies(config.nsec_white_lies);
 
-            // preserve to the new journal, i.e. we just loaded the zone from disk, start the journal
             authority
-                .persist_to_journal()
+                .recover_with_journal(&journal)
                 .await
-                .map_err(|e| format!("error persisting to journal {:?}: {}", journal_path, e))?;
+                .map_err(|e| format!("error recovering from journal: {}", e))?;
+
+            authority.set_journal(journal).await;
+            info!("recovered zone: {}", zone_name);
 
-            info!("zone file loaded: {}", zone_name);
             Ok(authority)
         } else {
             Err(format!(
@@ -150,6 +157,47 @@ impl SqliteAuthority {
         }
     }
 
+    /// Attaches the journal at the given path to the zone loaded from its zone file.
+    ///
+    /// A new journal is started from the loaded zone if it is empty, otherwise the updates recorded
+    ///  in it are replayed on top of the zone. The journal must have been started from the same
+    ///  version of the zone, i.e. the SOA serial of the zone must match the base serial of the
+    ///  journal, if the zone file has changed since, the journal needs to be removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `journal_path` - the path of the journal, created if it does not exist.
+    pub async fn with_journal(mut self, journal_path: &Path) -> PersistenceResult<Self> {
+        let journal = Journal::from_file(journal_path)?;
+
+        match journal.base_serial()? {
+            None => {
+                info!("creating new journal: {:?}", journal_path);
+                self.set_journal(journal).await;
+
+                // preserve to the new journal, i.e. we just loaded the zone from disk, start the journal
+                self.persist_to_journal().await?;
+            }
+            Some(base_serial) => {
+                let serial = self.in_memory.serial().await;
+                if base_serial != serial {
+                    return Err(PersistenceErrorKind::SerialMismatch {
+                        journal: base_serial,
+                        zone: serial,
+                    }
+                    .into());
+                }
+
+                info!("replaying journal: {:?}", journal_path);
+                self.in_memory.clear();
+                self.recover_with_journal(&journal).await?;
+                self.set_journal(journal).await;
+            }
+        }
+
+        Ok(self)
+    }
+
     /// Recovers the zone from a Journal, returns an error on failure to recover the zone.
     ///
     /// # Arguments
@@ -219,6 +267,14 @@ impl SqliteAuthority {
         self.allow_update = allow_update;
     }
 
+    /// Sets the names of the TSIG keys which may update the zone, defaults to none
+    ///
+    /// An update signed with TSIG is refused unless its key is one of these. The keys must be known to the
+    ///  `Catalog`, which verifies the TSIG, see `Catalog::add_tsig_key`.
+    pub fn set_allow_update_keys(&mut self, allow_update_keys: Vec<LowerName>) {
+        self.allow_update_keys = allow_update_keys;
+    }
+
     /// Get serial
     #[cfg(any(test, feature = "testing"))]
     #[cfg_attr(docsrs, doc(cfg(feature = "testing")))]
@@ -453,7 +509,7 @@ impl SqliteAuthority {
         use log::debug;
 
         use crate::client::rr::rdata::DNSSECRData;
-        use crate::proto::rr::dnssec::Verifier;
+        use crate::proto::rr::dnssec::{tbs, Verifier};
 
         // 3.3.3 - Pseudocode for Permission Checking
         //
@@ -473,53 +529,85 @@ impl SqliteAuthority {
             return Err(ResponseCode::Refused);
         }
 
-        // verify sig0, currently the only authorization that is accepted.
+        // a TSIG is verified by the Catalog, requests with an unknown key or an invalid TSIG never get here
+        if let Some(tsig) = update_message.tsig() {
+            let key = LowerName::from(tsig.name());
+            if !self.allow_update_keys.contains(&key) {
+                warn!(
+                    "update of {} refused for tsig key: {}",
+                    self.origin(),
+                    tsig.name()
+                );
+                return Err(ResponseCode::Refused);
+            }
+
+            info!("update authorized by tsig key: {}", tsig.name());
+            return Ok(());
+        }
+
+        // verify sig0
         let sig0s: &[Record] = update_message.sig0();
         debug!("authorizing with: {:?}", sig0s);
         if !sig0s.is_empty() {
-            let mut found_key = false;
-            for sig in sig0s.iter().filter_map(|sig0| {
-                sig0.data()
-                    .and_then(RData::as_dnssec)
-                    .and_then(DNSSECRData::as_sig)
-            }) {
-                let name = LowerName::from(sig.signer_name());
-                let keys = self
-                    .lookup(&name, RecordType::KEY, LookupOptions::default())
-                    .await;
-
-                let keys = match keys {
-                    Ok(keys) => keys,
-                    Err(_) => continue, // error trying to lookup a key by that name, try the next one.
-                };
-
-                debug!("found keys {:?}", keys);
-                // TODO: check key usage flags and restrictions
-                found_key = keys
-                    .iter()
-                    .filter_map(|rr_set| {
-                        rr_set
-                            .data()
-                            .and_then(RData::as_dnssec)
-                            .and_then(DNSSECRData::as_key)
-                    })
-                    .any(|key| {
-                        key.verify_message(update_message, sig.sig(), sig)
-                            .map(|_| {
-                                info!("verified sig: {:?} with key: {:?}", sig, key);
-                                true
-                            })
-                            .unwrap_or_else(|_| {
-                                debug!("did not verify sig: {:?} with key: {:?}", sig, key);
-                                false
-                            })
-                    });
-
-                if found_key {
-                    break; // stop searching for matching keys, we found one
+            // the signature is over the message as it was received, the SIG(0) must be the last record
+            let (tbs, sig) = match update_message
+                .signed_original()
+                .ok_or_else(|| "missing original message".into())
+                .and_then(tbs::signed_message_tbs)
+            {
+                Ok(signed) => signed,
+                Err(e) => {
+                    warn!("invalid sig0 on update: id {}: {}", update_message.id(), e);
+                    return Err(ResponseCode::Refused);
                 }
+            };
+
+            let now = time::OffsetDateTime::now_utc().unix_timestamp() as u32;
+            if !sig.is_valid_at(now) {
+                warn!(
+                    "sig0 outside of its validity period, inception: {} expiration: {} now: {}",
+                    sig.sig_inception(),
+                    sig.sig_expiration(),
+                    now
+                );
+                return Err(ResponseCode::Refused);
             }
 
+            let name = LowerName::from(sig.signer_name());
+            let keys = self
+                .lookup(&name, RecordType::KEY, LookupOptions::default())
+                .await;
+
+            let keys = match keys {
+                Ok(keys) => keys,
+                Err(_) => {
+                    warn!("no key found for sig0 signer: {}", name);
+                    return Err(ResponseCode::Refused);
+                }
+            };
+
+            debug!("found keys {:?}", keys);
+            // TODO: check key usage flags and restrictions
+            let found_key = keys
+                .iter()
+                .filter_map(|rr_set| {
+                    rr_set
+                        .data()
+                        .and_then(RData::as_dnssec)
+                        .and_then(DNSSECRData::as_key)
+                })
+                .any(|key| {
+                    key.verify(tbs.as_ref(), sig.sig())
+                        .map(|_| {
+                            info!("verified sig: {:?} with key: {:?}", sig, key);
+                            true
+                        })
+                        .unwrap_or_else(|_| {
+                            debug!("did not verify sig: {:?} with key: {:?}", sig, key);
+                            false
+                        })
+                });
+
             if found_key {
                 return Ok(());
             }
@@ -756,7 +844,7 @@ impl SqliteAuthority {
                                 .filter(|k| {
                                     !((k.record_type == RecordType::SOA
                                         || k.record_type == RecordType::NS)
-                                        && k.name != *origin)
+                                        && k.name == *origin)
                                 })
                                 .filter(|k| k.name == rr_name)
                                 .cloned()
@@ -826,6 +914,24 @@ impl SqliteAuthority {
                 //  dnssec, then we need to do it here...
                 self.in_memory.increment_soa_serial().await;
             }
+
+            // record the new serial as well, so that it survives a recovery
+            if let Some(ref journal) = *self.journal.lock().await {
+                let serial = self.in_memory.serial().await;
+                let soa = self
+                    .in_memory
+                    .records()
+                    .await
+                    .get(&RrKey::new(self.origin().clone(), RecordType::SOA))
+                    .and_then(|rrset| rrset.records_without_rrsigs().next().cloned());
+
+                if let Some(soa) = soa {
+                    if let Err(error) = journal.insert_record(serial, &soa) {
+                        error!("could not persist updated SOA: {}", error);
+                        return Err(ResponseCode::ServFail);
+                    }
+                }
+            }
         }
 
         Ok(updated)
@@ -1007,6 +1113,18 @@ impl DnssecAuthority for SqliteAuthority {
     async fn secure_zone(&self) -> DnsSecResult<()> {
         self.in_memory.secure_zone().await
     }
+
+    /// Begin the rollover of the zone signing key
+    async fn begin_zsk_rollover(
+        &self,
+        old_key_tag: u16,
+        new_signer: SigSigner,
+        timing: RolloverTiming,
+    ) -> DnsSecResult<()> {
+        self.in_memory
+            .begin_zsk_rollover(old_key_tag, new_signer, timing)
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -17,4 +17,10 @@ pub struct SqliteConfig {
     /// Are updates allowed to this zone
     #[serde(default)]
     pub allow_update: bool,
+    /// Names of the TSIG keys which may update this zone, updates signed with other keys are refused
+    #[serde(default)]
+    pub allow_update_keys: Vec<String>,
+    /// Deny records with NSEC "white lies" when the zone is signed, see `InMemoryAuthority::set_nsec_white_lies`
+    #[serde(default)]
+    pub nsec_white_lies: bool,
 }
@@ -17,7 +17,7 @@ use rusqlite::{self, Connection};
 use time;
 
 use crate::error::{PersistenceErrorKind, PersistenceResult};
-use crate::proto::rr::Record;
+use crate::proto::rr::{Record, RecordType};
 use crate::proto::serialize::binary::{BinDecodable, BinDecoder, BinEncodable, BinEncoder};
 
 /// The current Journal version of the application
@@ -127,6 +127,35 @@ impl Journal {
         Ok(())
     }
 
+    /// Returns the SOA serial of the zone the journal was last started from, None if the journal
+    ///  is empty.
+    ///
+    /// This is the serial recorded against the last AXFR marker, i.e. the most recent full dump of
+    ///  the zone, subsequent updates are replayed on top of it.
+    pub fn base_serial(&self) -> PersistenceResult<Option<u32>> {
+        assert!(
+            self.version == CURRENT_VERSION,
+            "schema version mismatch, schema_up() resolves this"
+        );
+
+        let conn = self.conn.lock().expect("conn poisoned");
+        let mut stmt = conn.prepare("SELECT soa_serial, record FROM records ORDER BY _rowid_")?;
+        let mut rows = stmt.query([])?;
+
+        let mut base_serial = None;
+        while let Some(row) = rows.next()? {
+            let soa_serial: i64 = row.get(0)?;
+            let record_bytes: Vec<u8> = row.get(1)?;
+            let record = Record::read(&mut BinDecoder::new(&record_bytes))?;
+
+            if record.rr_type() == RecordType::AXFR {
+                base_serial = Some(soa_serial as u32);
+            }
+        }
+
+        Ok(base_serial)
+    }
+
     /// Selects a record from the given row_id.
     ///
     /// This allows for the entire set of records to be iterated through, by starting at 0, and
@@ -4,6 +4,7 @@ use std::{
     future::Future,
     net::{Ipv4Addr, Ipv6Addr},
     str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use futures_executor::block_on;
@@ -29,7 +30,12 @@ fn update_authority<A: Authority<Lookup = AuthLookup>>(
     key: &SigSigner,
     authority: &mut A,
 ) -> UpdateResult<bool> {
-    message.finalize(key, 1).expect("failed to sign message");
+    // the signature must be valid at the time of the update
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32;
+    message.finalize(key, now).expect("failed to sign message");
     let message = message.to_bytes().unwrap();
     let request = MessageRequest::from_bytes(&message).unwrap();
 
@@ -775,6 +781,36 @@ pub fn test_delete_all<A: Authority<Lookup = AuthLookup>>(mut authority: A, keys
     }
 }
 
+pub fn test_delete_all_at_origin<A: Authority<Lookup = AuthLookup>>(
+    mut authority: A,
+    keys: &[SigSigner],
+) {
+    let origin = Name::from_str("example.com.").unwrap();
+    let search = |authority: &A, record_type| {
+        let query = Query::query(origin.clone(), record_type).into();
+        let request_info = RequestInfo::new(
+            "127.0.0.1:53".parse().unwrap(),
+            Protocol::Udp,
+            TEST_HEADER,
+            &query,
+        );
+
+        block_on(authority.search(request_info, LookupOptions::default()))
+    };
+
+    for key in keys {
+        let message =
+            update_message::delete_all(origin.clone(), origin.clone(), DNSClass::IN, true);
+        update_authority(message, key, &mut authority).expect("delete_all failed");
+
+        // RFC 2136 - 3.4.2.3, the SOA and NS records of the zone are kept
+        assert!(search(&authority, RecordType::SOA).is_ok());
+        assert!(search(&authority, RecordType::NS).is_ok());
+        assert!(search(&authority, RecordType::MX).is_err());
+        assert!(search(&authority, RecordType::AAAA).is_err());
+    }
+}
+
 pub fn add_auth<A: DnssecAuthority>(authority: &mut A) -> Vec<SigSigner> {
     use trust_dns_client::rr::rdata::key::KeyUsage;
     use trust_dns_server::config::dnssec::*;
@@ -904,6 +940,7 @@ macro_rules! dynamic_update {
                     test_delete_by_rdata_multi,
                     test_delete_rrset,
                     test_delete_all,
+                    test_delete_all_at_origin,
                 );
             }
         }
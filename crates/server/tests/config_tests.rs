@@ -20,6 +20,7 @@ use std::time::Duration;
 
 use trust_dns_server::authority::ZoneType;
 use trust_dns_server::config::*;
+use trust_dns_server::server::RrlConfig;
 
 #[test]
 fn test_read_config() {
@@ -139,6 +140,50 @@ fn test_parse_toml() {
 
     let config: Config = "tcp_request_timeout = 25".parse().unwrap();
     assert_eq!(config.get_tcp_request_timeout(), Duration::from_secs(25));
+    assert_eq!(config.get_tcp_keepalive(), None);
+
+    let config: Config = "tcp_keepalive = 30".parse().unwrap();
+    assert_eq!(config.get_tcp_keepalive(), Some(Duration::from_secs(30)));
+    assert_eq!(config.get_dso_keepalive(), None);
+
+    let config: Config = "dso_keepalive = 600".parse().unwrap();
+    assert_eq!(config.get_dso_keepalive(), Some(Duration::from_secs(600)));
+    assert_eq!(config.get_rrl(), None);
+
+    let config: Config = "rate_limit = { responses_per_second = 10, slip = 0 }"
+        .parse()
+        .unwrap();
+    assert_eq!(
+        config.get_rrl(),
+        Some(RrlConfig {
+            responses_per_second: 10,
+            slip: 0,
+            ..RrlConfig::default()
+        })
+    );
+
+    let config: Config = "minimal_any = true".parse().unwrap();
+    assert!(config.is_minimal_any());
+    assert_eq!(config.get_axfr_message_size(), None);
+
+    let config: Config = "axfr_message_size = 4096".parse().unwrap();
+    assert_eq!(config.get_axfr_message_size(), Some(4096));
+
+    let config: Config = "listen_port = 2053".parse().unwrap();
+    assert_eq!(config.get_chaos(), &ChaosConfig::default());
+    assert!(!config.get_chaos().enabled);
+
+    let config: Config = "[chaos]\nenabled = true\nversion = \"1.0\"\nhostname = \"ns1\""
+        .parse()
+        .unwrap();
+    assert_eq!(
+        config.get_chaos(),
+        &ChaosConfig {
+            enabled: true,
+            version: Some("1.0".to_string()),
+            hostname: Some("ns1".to_string()),
+        }
+    );
 
     let config: Config = "log_level = \"Debug\"".parse().unwrap();
     assert_eq!(config.get_log_level(), log::Level::Debug);
@@ -147,6 +192,66 @@ fn test_parse_toml() {
     assert_eq!(config.get_directory(), Path::new("/dev/null"));
 }
 
+#[test]
+fn test_parse_zone_acl() {
+    use trust_dns_client::rr::Name;
+    use trust_dns_server::authority::ZoneAcl;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+file = \"example.com.zone\"
+allow_query = [\"192.0.2.0/24\", \"2001:db8::/32\"]
+allow_transfer = [\"192.0.2.53/32\"]
+allow_transfer_keys = [\"transfer.example.com\"]
+
+[[zones]]
+zone = \"example.net\"
+zone_type = \"Primary\"
+file = \"example.net.zone\"
+"
+    .parse()
+    .unwrap();
+
+    assert_eq!(
+        config.get_zones()[0].get_acl().unwrap(),
+        ZoneAcl {
+            allow_query: vec![
+                "192.0.2.0/24".parse().unwrap(),
+                "2001:db8::/32".parse().unwrap()
+            ],
+            allow_transfer: vec!["192.0.2.53/32".parse().unwrap()],
+            allow_transfer_keys: vec![Name::from_ascii("transfer.example.com.").unwrap().into()],
+        }
+    );
+    assert_eq!(config.get_zones()[1].get_acl().unwrap(), ZoneAcl::default());
+}
+
+#[test]
+fn test_parse_nsec_white_lies() {
+    use trust_dns_server::store::file::FileConfig;
+    use trust_dns_server::store::StoreConfig;
+
+    let config: Config = "
+[[zones]]
+zone = \"example.com\"
+zone_type = \"Primary\"
+stores = { type = \"file\", zone_file_path = \"example.com.zone\", nsec_white_lies = true }
+"
+    .parse()
+    .unwrap();
+
+    assert_eq!(
+        config.get_zones()[0].stores,
+        Some(StoreConfig::File(FileConfig {
+            zone_file_path: "example.com.zone".to_string(),
+            watch: false,
+            nsec_white_lies: true,
+        }))
+    );
+}
+
 #[cfg(feature = "dnssec")]
 #[test]
 fn test_parse_zone_keys() {
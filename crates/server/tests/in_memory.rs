@@ -147,3 +147,409 @@ fn test_cname_loop() {
         Some(&RData::CNAME(Name::from_str("baz.example.com.").unwrap()))
     );
 }
+
+#[cfg(feature = "dnssec-ring")]
+#[test]
+fn test_online_signing() {
+    use std::time::Duration;
+
+    use trust_dns_client::rr::dnssec::{Algorithm, SupportedAlgorithms, Verifier};
+    use trust_dns_client::rr::rdata::SOA;
+    use trust_dns_client::rr::{DNSClass, LowerName};
+    use trust_dns_server::authority::LookupOptions;
+    use trust_dns_server::config::dnssec::KeyConfig;
+
+    let runtime = Runtime::new().expect("failed to create Tokio Runtime");
+    let origin = Name::from_str("example.com.").unwrap();
+    let www = Name::from_str("www.example.com.").unwrap();
+    let mut auth = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, false);
+
+    auth.upsert_mut(
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.example.com.").unwrap(),
+                Name::from_str("root.example.com.").unwrap(),
+                1,
+                3600,
+                600,
+                86400,
+                300,
+            )),
+        ),
+        1,
+    );
+    auth.upsert_mut(
+        Record::from_rdata(www.clone(), 300, RData::A([192, 0, 2, 1].into())),
+        1,
+    );
+
+    let key_config = KeyConfig {
+        key_path: "../../tests/test-data/named_test_configs/dnssec/ed25519.pk8".to_string(),
+        password: None,
+        algorithm: Algorithm::ED25519.to_string(),
+        signer_name: Some(origin.to_string()),
+        is_zone_signing_key: Some(true),
+        is_zone_update_auth: Some(false),
+    };
+    let signer = key_config
+        .try_into_signer(origin.clone())
+        .expect("failed to read key_config");
+    let dnskey = signer.to_dnskey().expect("failed to create DNSKEY");
+
+    // the zone is never explicitly signed, signatures are generated on lookup
+    auth.add_zone_signing_key_mut(signer)
+        .expect("failed to add signer to zone");
+    auth.set_rrsig_inception_offset(Duration::from_secs(3600));
+
+    let dnssec = LookupOptions::for_dnssec(true, SupportedAlgorithms::new());
+    let verify = |records: Vec<Record>, record_type: RecordType| {
+        let (records, rrsigs): (Vec<_>, Vec<_>) = records
+            .into_iter()
+            .partition(|r| r.record_type() == record_type);
+        let rrsig = rrsigs
+            .iter()
+            .find_map(|r| r.data().and_then(RData::as_dnssec))
+            .and_then(|r| r.as_sig())
+            .expect("no RRSIG returned");
+
+        assert_eq!(rrsig.type_covered(), record_type);
+        assert!(rrsig.sig_expiration() - rrsig.sig_inception() >= 3600);
+        dnskey
+            .verify_rrsig(records[0].name(), DNSClass::IN, rrsig, &records)
+            .expect("RRSIG failed to verify");
+        records
+    };
+
+    let lookup = |name: &Name, record_type: RecordType, lookup_options: LookupOptions| {
+        runtime
+            .block_on(auth.lookup(&LowerName::from(name), record_type, lookup_options))
+            .unwrap()
+            .iter()
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    // without DO, no RRSIGs are returned
+    let records = lookup(&www, RecordType::A, LookupOptions::default());
+    assert!(records.iter().all(|r| r.record_type() == RecordType::A));
+
+    let records = verify(lookup(&www, RecordType::A, dnssec), RecordType::A);
+    assert_eq!(records.len(), 1);
+    verify(
+        lookup(&origin, RecordType::DNSKEY, dnssec),
+        RecordType::DNSKEY,
+    );
+
+    // signatures are cached with the records
+    let rrsets = runtime.block_on(auth.records());
+    assert!(rrsets.values().all(|rrset| !rrset.rrsigs().is_empty()));
+
+    // changing the records invalidates the signature, it's regenerated on the next lookup
+    runtime.block_on(auth.upsert(
+        Record::from_rdata(www.clone(), 300, RData::A([192, 0, 2, 2].into())),
+        1,
+    ));
+    let records = verify(lookup(&www, RecordType::A, dnssec), RecordType::A);
+    assert_eq!(records.len(), 2);
+
+    // negative answers are covered by signed NSEC records
+    let nsec = runtime
+        .block_on(auth.get_nsec_records(
+            &LowerName::from(Name::from_str("nx.example.com.").unwrap()),
+            dnssec,
+        ))
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let nsec = verify(nsec, RecordType::NSEC);
+    assert_eq!(nsec.len(), 1);
+    assert_eq!(nsec[0].name(), &origin);
+}
+
+#[cfg(feature = "dnssec-ring")]
+#[test]
+fn test_zsk_rollover() {
+    use std::time::Duration;
+
+    use trust_dns_client::rr::dnssec::{
+        Algorithm, KeyFormat, KeyPair, SigSigner, SupportedAlgorithms, Verifier,
+    };
+    use trust_dns_client::rr::rdata::{DNSKEY, SOA};
+    use trust_dns_client::rr::{DNSClass, LowerName, RrKey};
+    use trust_dns_server::authority::{DnssecAuthority, LookupOptions, RolloverTiming};
+
+    let runtime = Runtime::new().expect("failed to create Tokio Runtime");
+    let origin = Name::from_str("example.com.").unwrap();
+    let www = Name::from_str("www.example.com.").unwrap();
+
+    let generate = || KeyPair::generate_pkcs8(Algorithm::ED25519).expect("failed to generate key");
+    let signer = |pkcs8: &[u8]| {
+        let key = KeyFormat::Pkcs8
+            .decode_key(pkcs8, None, Algorithm::ED25519)
+            .expect("failed to decode key");
+        let dnskey = key.to_dnskey(Algorithm::ED25519).unwrap();
+        SigSigner::dnssec(dnskey, key, origin.clone(), Duration::from_secs(86400))
+    };
+    let zone = |signers: Vec<SigSigner>| {
+        let mut auth = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, false);
+        auth.upsert_mut(
+            Record::from_rdata(
+                origin.clone(),
+                3600,
+                RData::SOA(SOA::new(
+                    Name::from_str("ns.example.com.").unwrap(),
+                    Name::from_str("root.example.com.").unwrap(),
+                    1,
+                    3600,
+                    600,
+                    86400,
+                    300,
+                )),
+            ),
+            1,
+        );
+        auth.upsert_mut(
+            Record::from_rdata(www.clone(), 300, RData::A([192, 0, 2, 1].into())),
+            1,
+        );
+        for signer in signers {
+            auth.add_zone_signing_key_mut(signer)
+                .expect("failed to add signer to zone");
+        }
+        auth
+    };
+
+    // looks up the records and the key tags of their RRSIGs, the zone is signed if needed
+    let dnssec = LookupOptions::for_dnssec(true, SupportedAlgorithms::new());
+    let lookup = |auth: &InMemoryAuthority, name: &Name, record_type: RecordType| {
+        let (records, rrsigs): (Vec<_>, Vec<_>) = runtime
+            .block_on(auth.lookup(&LowerName::from(name), record_type, dnssec))
+            .unwrap()
+            .iter()
+            .cloned()
+            .partition(|r| r.record_type() == record_type);
+        let mut key_tags = rrsigs
+            .iter()
+            .filter_map(|r| r.data().and_then(RData::as_dnssec))
+            .filter_map(|r| r.as_sig())
+            .map(|sig| sig.key_tag())
+            .collect::<Vec<_>>();
+        key_tags.sort_unstable();
+        (records, key_tags)
+    };
+    // the key tags of the stored RRSIGs, without signing the zone
+    let signatures = |auth: &InMemoryAuthority, name: &Name, record_type: RecordType| {
+        let records = runtime.block_on(auth.records());
+        let rrset = records
+            .get(&RrKey::new(LowerName::from(name), record_type))
+            .expect("rrset not found");
+        let mut key_tags = rrset
+            .rrsigs()
+            .iter()
+            .filter_map(|r| r.data().and_then(RData::as_dnssec))
+            .filter_map(|r| r.as_sig())
+            .map(|sig| sig.key_tag())
+            .collect::<Vec<_>>();
+        key_tags.sort_unstable();
+        key_tags
+    };
+    let dnskeys = |records: &[Record]| {
+        let mut key_tags = records
+            .iter()
+            .filter_map(|r| r.data().and_then(RData::as_dnssec))
+            .filter_map(|r| r.as_dnskey())
+            .map(|dnskey| dnskey.calculate_key_tag().unwrap())
+            .collect::<Vec<_>>();
+        key_tags.sort_unstable();
+        key_tags
+    };
+
+    let sorted = |mut key_tags: Vec<u16>| {
+        key_tags.sort_unstable();
+        key_tags
+    };
+
+    let (old, new, other) = (generate(), generate(), generate());
+    let old_key: DNSKEY = signer(&old).to_dnskey().unwrap();
+    let old_tag = signer(&old).calculate_key_tag().unwrap();
+    let new_tag = signer(&new).calculate_key_tag().unwrap();
+    let other_tag = signer(&other).calculate_key_tag().unwrap();
+    let both_tags = sorted(vec![old_tag, new_tag]);
+
+    // pre-publish: the DNSKEY RRset has both keys, but is signed with the old key only
+    let auth = zone(vec![signer(&old)]);
+    let timing = RolloverTiming {
+        publish_delay: Duration::from_secs(3600),
+        retire_delay: Duration::from_secs(3600),
+    };
+    assert!(runtime
+        .block_on(auth.begin_zsk_rollover(new_tag, signer(&new), timing))
+        .is_err());
+    runtime
+        .block_on(auth.begin_zsk_rollover(old_tag, signer(&new), timing))
+        .expect("failed to begin rollover");
+    assert!(runtime
+        .block_on(auth.begin_zsk_rollover(old_tag, signer(&generate()), timing))
+        .is_err());
+    assert_eq!(runtime.block_on(auth.serial()), 2);
+
+    let (records, key_tags) = lookup(&auth, &origin, RecordType::DNSKEY);
+    assert_eq!(dnskeys(&records), both_tags);
+    assert_eq!(key_tags, vec![old_tag]);
+    let rrsigs = runtime
+        .block_on(auth.lookup(&LowerName::from(&origin), RecordType::DNSKEY, dnssec))
+        .unwrap()
+        .iter()
+        .filter_map(|r| r.data().and_then(RData::as_dnssec))
+        .filter_map(|r| r.as_sig())
+        .cloned()
+        .collect::<Vec<_>>();
+    old_key
+        .verify_rrsig(&origin, DNSClass::IN, &rrsigs[0], &records)
+        .expect("RRSIG failed to verify");
+    assert_eq!(lookup(&auth, &www, RecordType::A).1, vec![old_tag]);
+
+    // without delays, every signing of the zone moves the rollover to the next stage, the other key of the
+    //  same algorithm is not rolled over
+    let auth = zone(vec![signer(&old), signer(&other)]);
+    let timing = RolloverTiming {
+        publish_delay: Duration::from_secs(0),
+        retire_delay: Duration::from_secs(0),
+    };
+    runtime
+        .block_on(auth.begin_zsk_rollover(old_tag, signer(&new), timing))
+        .expect("failed to begin rollover");
+
+    // active: all keys are published, the zone is signed with the new and the other key
+    let signing_tags = sorted(vec![new_tag, other_tag]);
+    let (records, key_tags) = lookup(&auth, &origin, RecordType::DNSKEY);
+    assert_eq!(dnskeys(&records), sorted(vec![old_tag, new_tag, other_tag]));
+    assert_eq!(key_tags, signing_tags);
+    assert_eq!(signatures(&auth, &www, RecordType::A), signing_tags);
+    assert_eq!(runtime.block_on(auth.serial()), 3);
+
+    // retired: only the new and the other key are published
+    let (records, key_tags) = lookup(&auth, &origin, RecordType::DNSKEY);
+    assert_eq!(dnskeys(&records), signing_tags);
+    assert_eq!(key_tags, signing_tags);
+    assert_eq!(signatures(&auth, &www, RecordType::A), signing_tags);
+    assert_eq!(runtime.block_on(auth.serial()), 4);
+
+    // and the rollover is complete
+    lookup(&auth, &www, RecordType::A);
+    assert_eq!(runtime.block_on(auth.serial()), 4);
+}
+
+#[cfg(feature = "dnssec-ring")]
+#[test]
+fn test_nsec_white_lies() {
+    use trust_dns_client::op::ResponseCode;
+    use trust_dns_client::rr::dnssec::{Algorithm, SupportedAlgorithms, Verifier};
+    use trust_dns_client::rr::rdata::SOA;
+    use trust_dns_client::rr::{DNSClass, LowerName};
+    use trust_dns_server::authority::{LookupError, LookupOptions};
+    use trust_dns_server::config::dnssec::KeyConfig;
+
+    let runtime = Runtime::new().expect("failed to create Tokio Runtime");
+    let origin = Name::from_str("example.com.").unwrap();
+    let www = Name::from_str("www.example.com.").unwrap();
+    let nx = Name::from_str("nx.example.com.").unwrap();
+    let mut auth = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, false);
+
+    auth.upsert_mut(
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.example.com.").unwrap(),
+                Name::from_str("root.example.com.").unwrap(),
+                1,
+                3600,
+                600,
+                86400,
+                300,
+            )),
+        ),
+        1,
+    );
+    auth.upsert_mut(
+        Record::from_rdata(www.clone(), 300, RData::A([192, 0, 2, 1].into())),
+        1,
+    );
+
+    let key_config = KeyConfig {
+        key_path: "../../tests/test-data/named_test_configs/dnssec/ed25519.pk8".to_string(),
+        password: None,
+        algorithm: Algorithm::ED25519.to_string(),
+        signer_name: Some(origin.to_string()),
+        is_zone_signing_key: Some(true),
+        is_zone_update_auth: Some(false),
+    };
+    let signer = key_config
+        .try_into_signer(origin.clone())
+        .expect("failed to read key_config");
+    let dnskey = signer.to_dnskey().expect("failed to create DNSKEY");
+    auth.add_zone_signing_key_mut(signer)
+        .expect("failed to add signer to zone");
+    auth.set_nsec_white_lies(true);
+
+    let dnssec = LookupOptions::for_dnssec(true, SupportedAlgorithms::new());
+
+    // the NSEC record has the name as owner and covers no other name
+    let nsec = |name: &Name| {
+        let (nsecs, rrsigs): (Vec<_>, Vec<_>) = runtime
+            .block_on(auth.get_nsec_records(&LowerName::from(name), dnssec))
+            .unwrap()
+            .iter()
+            .cloned()
+            .partition(|r| r.record_type() == RecordType::NSEC);
+        assert_eq!(nsecs.len(), 1);
+        assert_eq!(nsecs[0].name(), name);
+
+        let rrsig = rrsigs
+            .iter()
+            .find_map(|r| r.data().and_then(RData::as_dnssec))
+            .and_then(|r| r.as_sig())
+            .expect("no RRSIG returned");
+        dnskey
+            .verify_rrsig(name, DNSClass::IN, rrsig, &nsecs)
+            .expect("RRSIG failed to verify");
+
+        let nsec = nsecs[0]
+            .data()
+            .and_then(RData::as_dnssec)
+            .and_then(|r| r.as_nsec())
+            .expect("not an NSEC record")
+            .clone();
+        assert_eq!(
+            nsec.next_domain_name(),
+            &Name::from_labels(Some(&[0u8][..]))
+                .unwrap()
+                .append_name(name)
+                .unwrap()
+        );
+        nsec.type_bit_maps().to_vec()
+    };
+
+    // names which don't exist are denied as if they had no records
+    let lookup = |name: &Name, lookup_options: LookupOptions| {
+        runtime
+            .block_on(auth.lookup(&LowerName::from(name), RecordType::A, lookup_options))
+            .err()
+    };
+    assert!(matches!(lookup(&nx, dnssec), Some(LookupError::NameExists)));
+    assert!(matches!(
+        lookup(&nx, LookupOptions::default()),
+        Some(LookupError::ResponseCode(ResponseCode::NXDomain))
+    ));
+    assert_eq!(nsec(&nx), vec![RecordType::RRSIG, RecordType::NSEC]);
+
+    // the types of the name are not denied
+    assert_eq!(
+        nsec(&www),
+        vec![RecordType::A, RecordType::RRSIG, RecordType::NSEC]
+    );
+}
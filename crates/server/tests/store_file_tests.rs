@@ -11,6 +11,8 @@ mod authority_battery;
 fn file(master_file_path: &str, _module: &str, _test_name: &str) -> FileAuthority {
     let config = FileConfig {
         zone_file_path: master_file_path.to_string(),
+        watch: false,
+        nsec_white_lies: false,
     };
 
     FileAuthority::try_from_config(
@@ -32,6 +34,8 @@ fn test_all_lines_are_loaded() {
     let config = FileConfig {
         zone_file_path: "../../tests/test-data/named_test_configs/default/nonewline.zone"
             .to_string(),
+        watch: false,
+        nsec_white_lies: false,
     };
 
     let mut authority = FileAuthority::try_from_config(
@@ -29,6 +29,8 @@ fn sqlite(master_file_path: &str, module: &str, test_name: &str) -> SqliteAuthor
         zone_file_path: master_file_path.to_string(),
         journal_file_path: journal_path.to_str().unwrap().to_string(),
         allow_update: true,
+        allow_update_keys: vec![],
+        nsec_white_lies: false,
     };
 
     block_on(SqliteAuthority::try_from_config(
@@ -57,6 +59,8 @@ fn sqlite_update(master_file_path: &str, module: &str, test_name: &str) -> Sqlit
         zone_file_path: master_file_path.to_string(),
         journal_file_path: journal_path.to_str().unwrap().to_string(),
         allow_update: true,
+        allow_update_keys: vec![],
+        nsec_white_lies: false,
     };
 
     block_on(SqliteAuthority::try_from_config(
@@ -5,6 +5,7 @@ use futures_executor::block_on;
 
 use trust_dns_client::proto::rr::rdata::tlsa::*;
 use trust_dns_client::rr::*;
+use trust_dns_client::serialize::binary::{BinDecodable, BinEncodable};
 use trust_dns_client::serialize::txt::*;
 use trust_dns_server::authority::{Authority, LookupOptions, ZoneType};
 use trust_dns_server::store::in_memory::InMemoryAuthority;
@@ -510,3 +511,357 @@ fn test_named_root() {
     assert!(records.contains_key(&key));
     assert_eq!(records[&key].dns_class(), DNSClass::IN)
 }
+
+#[test]
+fn test_generic_rdata() {
+    let lexer = Lexer::new(
+        r###"
+unknown                  3600         TYPE65534    \# 4 DEADBEEF
+generic                  3600         A            \# 4 0A000001
+"###,
+    );
+
+    let records = Parser::new().parse(
+        lexer,
+        Some(Name::from_str("example.com.").unwrap()),
+        Some(DNSClass::IN),
+    );
+
+    if records.is_err() {
+        panic!("failed to parse: {:?}", records.err())
+    }
+
+    let (_, records) = records.unwrap();
+
+    let key = RrKey::new(
+        LowerName::from(Name::from_str("unknown.example.com.").unwrap()),
+        RecordType::Unknown(65534),
+    );
+    let record = records[&key].records_without_rrsigs().next().unwrap();
+    assert_eq!(
+        record.data(),
+        Some(&RData::Unknown {
+            code: 65534,
+            rdata: rdata::NULL::with(vec![0xDE, 0xAD, 0xBE, 0xEF]),
+        })
+    );
+    assert_eq!(
+        record.to_string(),
+        "unknown.example.com. 3600 IN TYPE65534 \\# 4 DEADBEEF"
+    );
+
+    let key = RrKey::new(
+        LowerName::from(Name::from_str("generic.example.com.").unwrap()),
+        RecordType::A,
+    );
+    let record = records[&key].records_without_rrsigs().next().unwrap();
+    assert_eq!(record.data(), Some(&RData::A(Ipv4Addr::new(10, 0, 0, 1))));
+}
+
+#[test]
+fn test_sshfp() {
+    let lexer = Lexer::new(
+        r###"
+host                     3600         SSHFP        2 1 123456789ABCDEF67890123456789ABCDEF67890
+split                    3600         SSHFP        ( 1 2 b049f950d1397b8fee6a61e4d14a9acd
+                                                         c4721e084eff5460bbed80cfaa2ce2cb )
+"###,
+    );
+
+    let records = Parser::new().parse(
+        lexer,
+        Some(Name::from_str("example.com.").unwrap()),
+        Some(DNSClass::IN),
+    );
+
+    if records.is_err() {
+        panic!("failed to parse: {:?}", records.err())
+    }
+
+    let (_, records) = records.unwrap();
+
+    let key = RrKey::new(
+        LowerName::from(Name::from_str("host.example.com.").unwrap()),
+        RecordType::SSHFP,
+    );
+    let record = records[&key].records_without_rrsigs().next().unwrap();
+    if let Some(RData::SSHFP(ref sshfp)) = record.data() {
+        assert_eq!(sshfp.algorithm(), rdata::sshfp::Algorithm::DSA);
+        assert_eq!(
+            sshfp.fingerprint_type(),
+            rdata::sshfp::FingerprintType::SHA1
+        );
+        assert_eq!(
+            sshfp.fingerprint(),
+            &[
+                0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf6, 0x78, 0x90, 0x12, 0x34, 0x56, 0x78,
+                0x9a, 0xbc, 0xde, 0xf6, 0x78, 0x90
+            ]
+        );
+    } else {
+        panic!("Not an SSHFP record!!!") // valid panic, test code
+    }
+    assert_eq!(
+        record.to_string(),
+        "host.example.com. 3600 IN SSHFP 2 1 123456789abcdef67890123456789abcdef67890"
+    );
+
+    // the binary form round trips
+    let bytes = record.to_bytes().unwrap();
+    assert_eq!(&Record::from_bytes(&bytes).unwrap(), record);
+
+    let key = RrKey::new(
+        LowerName::from(Name::from_str("split.example.com.").unwrap()),
+        RecordType::SSHFP,
+    );
+    let record = records[&key].records_without_rrsigs().next().unwrap();
+    assert_eq!(
+        record.to_string(),
+        "split.example.com. 3600 IN SSHFP 1 2 b049f950d1397b8fee6a61e4d14a9acdc4721e084eff5460bbed80cfaa2ce2cb"
+    );
+}
+
+#[test]
+fn test_uri() {
+    let lexer = Lexer::new(
+        r###"
+_ftp._tcp                3600         URI          10 1 "ftp://ftp.example.com/public"
+"###,
+    );
+
+    let (_, records) = Parser::new()
+        .parse(
+            lexer,
+            Some(Name::from_str("example.com.").unwrap()),
+            Some(DNSClass::IN),
+        )
+        .expect("failed to parse");
+
+    let key = RrKey::new(
+        LowerName::from(Name::from_str("_ftp._tcp.example.com.").unwrap()),
+        RecordType::URI,
+    );
+    let record = records[&key].records_without_rrsigs().next().unwrap();
+    if let Some(RData::URI(ref uri)) = record.data() {
+        assert_eq!(uri.priority(), 10);
+        assert_eq!(uri.weight(), 1);
+        assert_eq!(uri.target(), "ftp://ftp.example.com/public");
+    } else {
+        panic!("Not a URI record!!!") // valid panic, test code
+    }
+    assert_eq!(
+        record.to_string(),
+        "_ftp._tcp.example.com. 3600 IN URI 10 1 \"ftp://ftp.example.com/public\""
+    );
+
+    // the binary form round trips
+    let bytes = record.to_bytes().unwrap();
+    assert_eq!(&Record::from_bytes(&bytes).unwrap(), record);
+}
+
+#[test]
+fn test_include() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("trust-dns-include-{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(
+        dir.join("example.com.zone"),
+        r###"
+$TTL 3600
+@   IN  SOA ns.example.com. hostmaster.example.com. ( 1 3600 600 86400 3600 )
+www     A   127.0.0.1
+$INCLUDE sub/sub.db sub.example.com. ; records of the sub domain
+after   A   127.0.0.2
+"###,
+    )
+    .unwrap();
+    fs::write(
+        dir.join("sub").join("sub.db"),
+        r###"
+www     A   127.0.0.3
+$ORIGIN other.example.com.
+www     A   127.0.0.4
+"###,
+    )
+    .unwrap();
+
+    let (origin, records, files) = Parser::new()
+        .parse_file_with_includes(
+            &dir.join("example.com.zone"),
+            Some(Name::from_str("example.com.").unwrap()),
+            None,
+        )
+        .expect("failed to parse");
+    assert_eq!(origin, Name::from_str("example.com.").unwrap());
+    assert_eq!(
+        files,
+        vec![
+            fs::canonicalize(dir.join("example.com.zone")).unwrap(),
+            fs::canonicalize(dir.join("sub").join("sub.db")).unwrap(),
+        ]
+    );
+
+    let a = |name: &str| {
+        let key = RrKey::new(
+            LowerName::from(Name::from_str(name).unwrap()),
+            RecordType::A,
+        );
+        records
+            .get(&key)
+            .and_then(|set| set.records_without_rrsigs().next())
+            .and_then(|record| record.data())
+            .cloned()
+    };
+
+    assert_eq!(
+        a("www.example.com."),
+        Some(RData::A(Ipv4Addr::new(127, 0, 0, 1)))
+    );
+    // the included file is relative to its own origin
+    assert_eq!(
+        a("www.sub.example.com."),
+        Some(RData::A(Ipv4Addr::new(127, 0, 0, 3)))
+    );
+    assert_eq!(
+        a("www.other.example.com."),
+        Some(RData::A(Ipv4Addr::new(127, 0, 0, 4)))
+    );
+    // and never changes the origin of the including file
+    assert_eq!(
+        a("after.example.com."),
+        Some(RData::A(Ipv4Addr::new(127, 0, 0, 2)))
+    );
+    assert_eq!(a("after.other.example.com."), None);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_include_cycle() {
+    use std::fs;
+
+    let dir = std::env::temp_dir().join(format!("trust-dns-include-cycle-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("a.zone"), "$INCLUDE b.zone\n").unwrap();
+    fs::write(dir.join("b.zone"), "$INCLUDE a.zone\n").unwrap();
+
+    let result = Parser::new().parse_file(
+        &dir.join("a.zone"),
+        Some(Name::from_str("example.com.").unwrap()),
+        Some(DNSClass::IN),
+    );
+    fs::remove_dir_all(&dir).unwrap();
+
+    let error = result.expect_err("cycle was not detected").to_string();
+    assert!(error.contains("$INCLUDE cycle"), "{}", error);
+}
+
+#[test]
+fn test_include_without_file() {
+    let lexer = Lexer::new("$INCLUDE /etc/passwd\n");
+
+    let error = Parser::new()
+        .parse(lexer, Some(Name::from_str("example.com.").unwrap()), None)
+        .expect_err("$INCLUDE was not rejected")
+        .to_string();
+    assert!(error.contains("$INCLUDE is only supported"), "{}", error);
+}
+
+#[test]
+fn test_generate() {
+    let lexer = Lexer::new(
+        r###"
+$TTL 3600
+$ORIGIN 0.168.192.in-addr.arpa.
+$GENERATE 1-254 $ PTR host-$.example.com.
+$GENERATE 1-10/3 ${10,3,d}.padded 60 IN A 10.0.0.$
+$GENERATE 10-12 hex-${0,2,x} CNAME host-${-9}.example.com.
+"###,
+    );
+
+    let (_, records) = Parser::new()
+        .parse(lexer, None, Some(DNSClass::IN))
+        .expect("failed to parse");
+
+    let record = |name: &str, record_type: RecordType| {
+        let key = RrKey::new(LowerName::from(Name::from_str(name).unwrap()), record_type);
+        records
+            .get(&key)
+            .and_then(|set| set.records_without_rrsigs().next())
+            .cloned()
+    };
+
+    // a /24 of PTRs
+    assert_eq!(
+        records
+            .keys()
+            .filter(|key| key.record_type == RecordType::PTR)
+            .count(),
+        254
+    );
+    let ptr = record("128.0.168.192.in-addr.arpa.", RecordType::PTR).unwrap();
+    assert_eq!(
+        ptr.data(),
+        Some(&RData::PTR(
+            Name::from_str("host-128.example.com.").unwrap()
+        ))
+    );
+    assert_eq!(ptr.ttl(), 3600);
+    assert!(record("0.0.168.192.in-addr.arpa.", RecordType::PTR).is_none());
+    assert!(record("255.0.168.192.in-addr.arpa.", RecordType::PTR).is_none());
+
+    // offset, width and step
+    for (name, address) in &[
+        (
+            "011.padded.0.168.192.in-addr.arpa.",
+            Ipv4Addr::new(10, 0, 0, 1),
+        ),
+        (
+            "014.padded.0.168.192.in-addr.arpa.",
+            Ipv4Addr::new(10, 0, 0, 4),
+        ),
+        (
+            "020.padded.0.168.192.in-addr.arpa.",
+            Ipv4Addr::new(10, 0, 0, 10),
+        ),
+    ] {
+        let a = record(name, RecordType::A).expect(name);
+        assert_eq!(a.data(), Some(&RData::A(*address)));
+        assert_eq!(a.ttl(), 60);
+    }
+    assert!(record("012.padded.0.168.192.in-addr.arpa.", RecordType::A).is_none());
+
+    // radix and negative offsets
+    let cname = record("hex-0b.0.168.192.in-addr.arpa.", RecordType::CNAME).unwrap();
+    assert_eq!(
+        cname.data(),
+        Some(&RData::CNAME(
+            Name::from_str("host-2.example.com.").unwrap()
+        ))
+    );
+}
+
+#[test]
+fn test_generate_errors() {
+    for generate in &[
+        "$GENERATE 254-1 $ PTR host-$.example.com.",
+        "$GENERATE 1-254/0 $ PTR host-$.example.com.",
+        "$GENERATE 1-254 $ PTR host-${-2}.example.com.",
+        "$GENERATE 1-254 $ PTR host-${0,3,z}.example.com.",
+        "$GENERATE 1-254 $ PTR host-${0,256,d}.example.com.",
+        "$GENERATE 1-254 $ PTR host-${0,18446744073709551615,d}.example.com.",
+        "$GENERATE 1-254 $ PTR host-${9223372036854775807}.example.com.",
+        "$GENERATE 1-254 $",
+        "$GENERATE 0-65536 $ PTR host-$.example.com.",
+        "$GENERATE 1-4294967295/65536 $ PTR host-$.example.com.",
+    ] {
+        let lexer = Lexer::new(generate);
+        let result = Parser::new().parse(
+            lexer,
+            Some(Name::from_str("0.168.192.in-addr.arpa.").unwrap()),
+            Some(DNSClass::IN),
+        );
+        assert!(result.is_err(), "{}", generate);
+    }
+}
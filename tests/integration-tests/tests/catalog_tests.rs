@@ -1,4 +1,4 @@
-use std::{net::*, str::FromStr, sync::Arc};
+use std::{net::*, str::FromStr, sync::Arc, time::Duration};
 
 use trust_dns_client::{
     op::*,
@@ -6,10 +6,16 @@ use trust_dns_client::{
     serialize::binary::{BinDecodable, BinEncodable},
 };
 
+use trust_dns_resolver::config::NameServerConfigGroup;
 use trust_dns_server::{
-    authority::{Authority, Catalog, MessageRequest, ZoneType},
-    server::{Protocol, Request},
-    store::in_memory::InMemoryAuthority,
+    authority::{Authority, Catalog, MessageRequest, ZoneAcl, ZoneType},
+    config::ChaosConfig,
+    server::{Protocol, Request, RequestHandler, ServerFuture},
+    store::{
+        forwarder::{ForwardAuthority, ForwardConfig},
+        in_memory::InMemoryAuthority,
+        secondary::{CatalogZone, SecondaryAuthority},
+    },
 };
 
 use trust_dns_integration::{authority::create_example, *};
@@ -191,6 +197,275 @@ async fn test_catalog_lookup() {
     );
 }
 
+async fn lookup_any(catalog: &Catalog, name: Name, dnssec_ok: bool) -> Message {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(name, RecordType::ANY));
+    if dnssec_ok {
+        let mut edns = Edns::new();
+        edns.set_dnssec_ok(true);
+        question.set_edns(edns);
+    }
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+    let question_req = Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp);
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(
+            &question_req,
+            question_req.edns().cloned(),
+            response_handler.clone(),
+        )
+        .await;
+    response_handler.into_message().await
+}
+
+#[tokio::test]
+async fn test_minimal_any() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(Arc::new(example)));
+
+    // by default all of the records are returned
+    let result = lookup_any(&catalog, origin.clone().into(), false).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result
+        .answers()
+        .iter()
+        .any(|record| record.rr_type() == RecordType::A));
+    assert!(result
+        .answers()
+        .iter()
+        .any(|record| record.rr_type() == RecordType::AAAA));
+
+    catalog.set_minimal_any(true);
+
+    let result = lookup_any(&catalog, origin.clone().into(), false).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.header().authoritative());
+    assert_eq!(result.answers().len(), 1);
+
+    let hinfo = &result.answers()[0];
+    assert_eq!(hinfo.name(), &Name::from(origin.clone()));
+    assert_eq!(
+        hinfo.data(),
+        Some(&RData::HINFO(HINFO::new(
+            "RFC8482".to_string(),
+            String::new()
+        )))
+    );
+
+    // nothing is synthesized for names without records
+    let result = lookup_any(&catalog, Name::from_str("nx.example.com.").unwrap(), false).await;
+    assert!(result.answers().is_empty());
+}
+
+#[cfg(feature = "dnssec")]
+#[tokio::test]
+async fn test_minimal_any_dnssec() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(Arc::new(example)));
+    catalog.set_minimal_any(true);
+
+    // a synthesized record can't be signed, so a single RRset of the zone is returned
+    let result = lookup_any(&catalog, origin.into(), true).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+
+    let record_type = result.answers()[0].rr_type();
+    assert_ne!(record_type, RecordType::HINFO);
+    assert!(result
+        .answers()
+        .iter()
+        .all(|record| record.rr_type() == record_type || record.rr_type() == RecordType::RRSIG));
+}
+
+async fn lookup_chaos(catalog: &Catalog, name: &str, record_type: RecordType) -> Message {
+    let mut query = Query::query(Name::from_str(name).unwrap(), record_type);
+    query.set_query_class(DNSClass::CH);
+    let mut question: Message = Message::new();
+    question.add_query(query);
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+    let question_req = Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp);
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    response_handler.into_message().await
+}
+
+#[tokio::test]
+async fn test_chaos() {
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(
+        Name::from_str("bind.").unwrap().into(),
+        Box::new(Arc::new(create_example())),
+    );
+
+    // refused by default, even with a zone for the name
+    let result = lookup_chaos(&catalog, "version.bind.", RecordType::TXT).await;
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+
+    catalog.set_chaos(ChaosConfig {
+        enabled: true,
+        version: Some("trust-dns 1.0".to_string()),
+        hostname: None,
+    });
+
+    for &name in &["version.bind.", "VERSION.BIND.", "version.server."] {
+        let result = lookup_chaos(&catalog, name, RecordType::TXT).await;
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+        assert!(result.header().authoritative());
+        assert_eq!(result.answers().len(), 1);
+
+        let txt = &result.answers()[0];
+        assert_eq!(txt.dns_class(), DNSClass::CH);
+        assert_eq!(
+            txt.data(),
+            Some(&RData::TXT(TXT::new(vec!["trust-dns 1.0".to_string()])))
+        );
+    }
+
+    // only TXT records exist
+    let result = lookup_chaos(&catalog, "version.bind.", RecordType::A).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.answers().is_empty());
+
+    // the hostname is not configured, and other names are not answered
+    let result = lookup_chaos(&catalog, "hostname.bind.", RecordType::TXT).await;
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    let result = lookup_chaos(&catalog, "authors.bind.", RecordType::TXT).await;
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+
+    // strings longer than a character-string are split over several
+    let long = "é".repeat(200);
+    catalog.set_chaos(ChaosConfig {
+        enabled: true,
+        version: None,
+        hostname: Some(long.clone()),
+    });
+    let result = lookup_chaos(&catalog, "id.server.", RecordType::TXT).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    match result.answers()[0].data() {
+        Some(RData::TXT(txt)) => {
+            assert_eq!(txt.txt_data().len(), 2);
+            assert!(txt.txt_data().iter().all(|s| s.len() <= 255));
+            assert_eq!(txt.txt_data().concat(), long.as_bytes());
+        }
+        data => panic!("unexpected data: {:?}", data),
+    }
+}
+
+#[tokio::test]
+async fn test_catalog_lookup_most_specific_zone() {
+    let host = Name::from_str("host.sub.example.com.").unwrap();
+
+    // the parent zone also has a (stale) record for the name in the child zone
+    let mut example = create_example();
+    example.upsert_mut(
+        Record::from_rdata(host.clone(), 86400, RData::A(Ipv4Addr::new(192, 0, 2, 1))),
+        0,
+    );
+
+    let sub_origin = Name::from_str("sub.example.com.").unwrap();
+    let mut sub = InMemoryAuthority::empty(sub_origin.clone(), ZoneType::Primary, false);
+    sub.upsert_mut(
+        Record::from_rdata(
+            sub_origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.sub.example.com.").unwrap(),
+                Name::from_str("root.sub.example.com.").unwrap(),
+                1,
+                7200,
+                3600,
+                1209600,
+                3600,
+            )),
+        ),
+        0,
+    );
+    sub.upsert_mut(
+        Record::from_rdata(host.clone(), 86400, RData::A(Ipv4Addr::new(192, 0, 2, 2))),
+        0,
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(example.origin().clone(), Box::new(Arc::new(example)));
+    catalog.upsert(LowerName::from(&sub_origin), Box::new(Arc::new(sub)));
+
+    assert_eq!(
+        catalog
+            .find(&LowerName::from(&host))
+            .map(|a| Name::from(a.origin())),
+        Some(sub_origin.clone())
+    );
+
+    let lookup = |name: Name| {
+        let mut question: Message = Message::new();
+        let mut query: Query = Query::new();
+        query.set_name(name);
+        question.add_query(query);
+
+        let question_bytes = question.to_bytes().unwrap();
+        let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+        Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp)
+    };
+
+    // the more specific zone answers for names within it
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&lookup(host), None, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(
+        result.answers().first().unwrap().data().unwrap(),
+        &RData::A(Ipv4Addr::new(192, 0, 2, 2))
+    );
+
+    // including the negative answers
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(
+            &lookup(Name::from_str("nx.sub.example.com.").unwrap()),
+            None,
+            response_handler.clone(),
+        )
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert_eq!(result.name_servers().first().unwrap().name(), &sub_origin);
+
+    // and the parent for everything else
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(
+            &lookup(Name::from_str("www.example.com.").unwrap()),
+            None,
+            response_handler.clone(),
+        )
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(
+        result.answers().first().unwrap().data().unwrap(),
+        &RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+}
+
 #[tokio::test]
 async fn test_catalog_lookup_soa() {
     let example = create_example();
@@ -308,6 +583,65 @@ async fn test_catalog_nx_soa() {
     );
 }
 
+#[tokio::test]
+#[allow(clippy::unreadable_literal)]
+async fn test_catalog_nodata_soa() {
+    let mut example = create_example();
+    let origin = example.origin().clone();
+    let www = Name::parse("www.example.com.", None).unwrap();
+
+    // the negative TTL is the SOA minimum when it is lower than the TTL of the SOA
+    let soa = SOA::new(
+        Name::parse("sns.dns.icann.org.", None).unwrap(),
+        Name::parse("noc.dns.icann.org.", None).unwrap(),
+        2015082404,
+        7200,
+        3600,
+        1209600,
+        3600,
+    );
+    assert!(example.upsert_mut(
+        Record::from_rdata(origin.clone().into(), 86400, RData::SOA(soa.clone())),
+        0,
+    ));
+
+    // an RRset emptied by an update doesn't exist anymore
+    example.records_get_mut().insert(
+        RrKey::new(www.clone().into(), RecordType::AAAA),
+        Arc::new(RecordSet::new(&www, RecordType::AAAA, 0)),
+    );
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin, Box::new(Arc::new(example)));
+
+    for record_type in &[RecordType::MX, RecordType::AAAA] {
+        let mut question: Message = Message::new();
+        question.add_query(Query::query(www.clone(), *record_type));
+
+        // temp request
+        let question_bytes = question.to_bytes().unwrap();
+        let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+        let question_req = Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp);
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .lookup(&question_req, None, response_handler.clone())
+            .await;
+        let result = response_handler.into_message().await;
+
+        assert_eq!(result.response_code(), ResponseCode::NoError);
+        assert!(result.header().authoritative());
+        assert!(result.answers().is_empty());
+
+        let ns: &[Record] = result.name_servers();
+
+        assert_eq!(ns.len(), 1);
+        assert_eq!(ns[0].rr_type(), RecordType::SOA);
+        assert_eq!(ns[0].ttl(), 3600);
+        assert_eq!(ns[0].data().unwrap(), &RData::SOA(soa.clone()));
+    }
+}
+
 #[tokio::test]
 async fn test_non_authoritive_nx_refused() {
     let example = create_example();
@@ -518,6 +852,230 @@ async fn test_axfr_refused() {
     assert!(result.additionals().is_empty());
 }
 
+async fn lookup_from(
+    catalog: &Catalog,
+    name: Name,
+    record_type: RecordType,
+    src: SocketAddr,
+) -> Message {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(name, record_type));
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+    let question_req = Request::new(question_req, src, Protocol::Tcp);
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    response_handler.into_message().await
+}
+
+#[tokio::test]
+async fn test_axfr_acl() {
+    let mut test = create_test();
+    test.set_allow_axfr(true);
+
+    let origin = test.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(Arc::new(test)));
+    catalog.set_acl(
+        origin.clone(),
+        ZoneAcl {
+            allow_query: vec![
+                "127.0.0.0/8".parse().unwrap(),
+                "192.0.2.0/24".parse().unwrap(),
+            ],
+            allow_transfer: vec!["127.0.0.1/32".parse().unwrap()],
+            allow_transfer_keys: vec![],
+        },
+    );
+
+    let allowed = ([127, 0, 0, 1], 5553).into();
+    let denied = ([192, 0, 2, 1], 5553).into();
+    let www = Name::parse("www.test.com.", None).unwrap();
+
+    let result = lookup_from(&catalog, origin.clone().into(), RecordType::AXFR, allowed).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(!result.answers().is_empty());
+
+    let result = lookup_from(&catalog, origin.clone().into(), RecordType::AXFR, denied).await;
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+
+    // the transfer ACL does not restrict queries
+    let result = lookup_from(&catalog, www.clone(), RecordType::A, denied).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(!result.answers().is_empty());
+
+    let result = lookup_from(
+        &catalog,
+        www,
+        RecordType::A,
+        ([198, 51, 100, 1], 5553).into(),
+    )
+    .await;
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+    assert!(result.answers().is_empty());
+}
+
+async fn forward_query(catalog: &Catalog, name: &str, recursion_desired: bool) -> Message {
+    forward_query_with_edns(catalog, name, recursion_desired, Edns::new()).await
+}
+
+async fn forward_query_with_edns(
+    catalog: &Catalog,
+    name: &str,
+    recursion_desired: bool,
+    edns: Edns,
+) -> Message {
+    let mut question: Message = Message::new();
+    question.add_query(Query::query(
+        Name::parse(name, None).unwrap(),
+        RecordType::A,
+    ));
+    question.set_recursion_desired(recursion_desired);
+    question.set_edns(edns);
+
+    let question_bytes = question.to_bytes().unwrap();
+    let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+    let question_req = Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Udp);
+
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .lookup(&question_req, None, response_handler.clone())
+        .await;
+    response_handler.into_message().await
+}
+
+#[tokio::test]
+async fn test_forward_unknown_zone() {
+    // the upstream server is authoritative for example.com
+    let example = create_example();
+    let mut upstream: Catalog = Catalog::new();
+    upstream.upsert(example.origin().clone(), Box::new(Arc::new(example)));
+
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let upstream_addr = socket.local_addr().unwrap();
+    let mut server = ServerFuture::new(upstream);
+    server.register_socket(socket);
+
+    let config = ForwardConfig {
+        name_servers: NameServerConfigGroup::from_ips_clear(
+            &[upstream_addr.ip()],
+            upstream_addr.port(),
+            true,
+        ),
+        options: None,
+        forward_client_subnet: false,
+    };
+    let forwarder = ForwardAuthority::try_from_config(Name::root(), ZoneType::Forward, &config)
+        .await
+        .unwrap();
+
+    // the local server is authoritative for test.com, and forwards everything else
+    let test = create_test();
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(test.origin().clone(), Box::new(Arc::new(test)));
+    catalog.upsert(Name::root().into(), Box::new(Arc::new(forwarder)));
+
+    let result = forward_query(&catalog, "www.example.com.", true).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.recursion_available());
+    assert!(!result.authoritative());
+    assert_eq!(
+        result.answers().first().unwrap().data().unwrap(),
+        &RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+
+    let result = forward_query(&catalog, "www.test.com.", true).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.authoritative());
+    assert_eq!(
+        result.answers().first().unwrap().data().unwrap(),
+        &RData::A(Ipv4Addr::new(94, 184, 216, 34))
+    );
+
+    let result = forward_query(&catalog, "nowhere.example.com.", true).await;
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    assert!(result.answers().is_empty());
+
+    // without RD the query is not forwarded
+    let result = forward_query(&catalog, "www.example.com.", false).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert!(result.answers().is_empty());
+}
+
+#[tokio::test]
+async fn test_forward_client_subnet_bypasses_cache() {
+    let example = Arc::new(create_example());
+    let mut upstream: Catalog = Catalog::new();
+    upstream.upsert(example.origin().clone(), Box::new(example.clone()));
+
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let upstream_addr = socket.local_addr().unwrap();
+    let mut server = ServerFuture::new(upstream);
+    server.register_socket(socket);
+
+    let forwarder = |forward_client_subnet| async move {
+        let config = ForwardConfig {
+            name_servers: NameServerConfigGroup::from_ips_clear(
+                &[upstream_addr.ip()],
+                upstream_addr.port(),
+                true,
+            ),
+            options: None,
+            forward_client_subnet,
+        };
+        let forwarder = ForwardAuthority::try_from_config(Name::root(), ZoneType::Forward, &config)
+            .await
+            .unwrap();
+        let mut catalog: Catalog = Catalog::new();
+        catalog.upsert(Name::root().into(), Box::new(Arc::new(forwarder)));
+        catalog
+    };
+    let catalog = forwarder(true).await;
+    let ignoring = forwarder(false).await;
+
+    // the NXDOMAIN is cached
+    let result = forward_query(&catalog, "new.example.com.", true).await;
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+    let result = forward_query(&ignoring, "new.example.com.", true).await;
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+
+    example
+        .upsert(
+            Record::from_rdata(
+                Name::parse("new.example.com.", None).unwrap(),
+                86400,
+                RData::A(Ipv4Addr::new(192, 0, 2, 1)),
+            ),
+            0,
+        )
+        .await;
+
+    // a request with a Client Subnet is not answered from the cache, nor is its answer cached
+    let mut edns = Edns::new();
+    edns.options_mut().insert(opt::EdnsOption::Subnet(
+        opt::ClientSubnet::from_str("192.0.2.0/24").unwrap(),
+    ));
+    let result = forward_query_with_edns(&catalog, "new.example.com.", true, edns.clone()).await;
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(
+        result.answers().first().unwrap().data().unwrap(),
+        &RData::A(Ipv4Addr::new(192, 0, 2, 1))
+    );
+
+    let result = forward_query(&catalog, "new.example.com.", true).await;
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+
+    // unless it is configured to, the forwarder ignores the Client Subnet and answers from the cache
+    let result = forward_query_with_edns(&ignoring, "new.example.com.", true, edns).await;
+    assert_eq!(result.response_code(), ResponseCode::NXDomain);
+}
+
 // TODO: add this test
 // #[test]
 // fn test_truncated_returns_records() {
@@ -628,3 +1186,567 @@ async fn test_multiple_cname_additionals() {
         &RData::A(Ipv4Addr::new(93, 184, 216, 34))
     );
 }
+
+fn notify_request(origin: Name, src: SocketAddr) -> Request {
+    let mut query: Query = Query::new();
+    query.set_name(origin);
+    query.set_query_type(RecordType::SOA);
+
+    let mut notify: Message = Message::new();
+    notify.set_op_code(OpCode::Notify);
+    notify.set_authoritative(true);
+    notify.add_query(query);
+
+    let notify_bytes = notify.to_bytes().unwrap();
+    let notify_req = MessageRequest::from_bytes(&notify_bytes).unwrap();
+    Request::new(notify_req, src, Protocol::Udp)
+}
+
+#[tokio::test]
+async fn test_notify_secondary() {
+    let primary: SocketAddr = ([127, 0, 0, 2], 53).into();
+    let origin = Name::parse("example.com.", None).unwrap();
+    let secondary = Arc::new(SecondaryAuthority::new(
+        origin.clone(),
+        vec![primary],
+        false,
+    ));
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), Box::new(secondary.clone()));
+
+    let request = notify_request(origin, ([127, 0, 0, 2], 5553).into());
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&request, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+    assert_eq!(result.message_type(), MessageType::Response);
+    assert_eq!(result.op_code(), OpCode::Notify);
+
+    tokio::time::timeout(Duration::from_secs(5), secondary.refresh_requested())
+        .await
+        .expect("refresh was not requested");
+}
+
+#[tokio::test]
+async fn test_notify_secondary_refused() {
+    let primary: SocketAddr = ([127, 0, 0, 2], 53).into();
+    let origin = Name::parse("example.com.", None).unwrap();
+    let secondary = SecondaryAuthority::new(origin.clone(), vec![primary], false);
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), Box::new(Arc::new(secondary)));
+
+    let request = notify_request(origin, ([127, 0, 0, 3], 5553).into());
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&request, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+}
+
+#[tokio::test]
+async fn test_notify_primary_not_auth() {
+    let example = create_example();
+    let origin = example.origin().clone();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(Arc::new(example)));
+
+    let request = notify_request(origin.into(), ([127, 0, 0, 2], 5553).into());
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&request, response_handler.clone())
+        .await;
+    let result = response_handler.into_message().await;
+
+    assert_eq!(result.response_code(), ResponseCode::NotAuth);
+}
+
+#[tokio::test]
+async fn test_secondary_refresh() {
+    let example = create_example();
+    let origin = example.origin().clone();
+    let primary = InMemoryAuthority::new(
+        origin.clone().into(),
+        example
+            .records()
+            .await
+            .into_iter()
+            .map(|(key, rrset)| (key, RecordSet::clone(&rrset)))
+            .collect(),
+        ZoneType::Primary,
+        true,
+    )
+    .unwrap();
+
+    let mut catalog: Catalog = Catalog::new();
+    catalog.upsert(origin.clone(), Box::new(Arc::new(primary)));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let primary_addr = listener.local_addr().unwrap();
+    let mut server = ServerFuture::new(catalog);
+    server.register_listener(listener, Duration::from_secs(5));
+
+    let secondary = SecondaryAuthority::new(origin.clone().into(), vec![primary_addr], false);
+    assert!(secondary.refresh().await.expect("refresh failed"));
+    assert!(!secondary.refresh().await.expect("refresh failed"));
+
+    let lookup = secondary
+        .lookup(&origin, RecordType::A, Default::default())
+        .await
+        .unwrap();
+    assert_eq!(
+        lookup.iter().next().unwrap().data().unwrap(),
+        &RData::A(Ipv4Addr::new(93, 184, 216, 34))
+    );
+}
+
+fn catalog_zone_soa(origin: &Name, serial: u32) -> Record {
+    Record::from_rdata(
+        origin.clone(),
+        0,
+        RData::SOA(SOA::new(
+            Name::from_str("invalid.").unwrap(),
+            Name::from_str("invalid.").unwrap(),
+            serial,
+            3600,
+            600,
+            86400,
+            0,
+        )),
+    )
+}
+
+fn catalog_zone_records(origin: &Name, members: &[(&str, RData)]) -> Vec<Record> {
+    let mut records = vec![
+        catalog_zone_soa(origin, 1),
+        Record::from_rdata(
+            Name::from_str("version")
+                .unwrap()
+                .append_domain(origin)
+                .unwrap(),
+            0,
+            RData::TXT(TXT::new(vec!["2".to_string()])),
+        ),
+    ];
+    records.extend(members.iter().map(|(owner, rdata)| {
+        Record::from_rdata(
+            Name::from_str(owner)
+                .unwrap()
+                .append_domain(origin)
+                .unwrap(),
+            0,
+            rdata.clone(),
+        )
+    }));
+    records
+}
+
+fn ptr(name: &str) -> RData {
+    RData::PTR(Name::from_str(name).unwrap())
+}
+
+fn txt(text: &str) -> RData {
+    RData::TXT(TXT::new(vec![text.to_string()]))
+}
+
+#[test]
+fn test_catalog_zone_members() {
+    let origin = Name::from_str("catalog.invalid.").unwrap();
+    let records = catalog_zone_records(
+        &origin,
+        &[
+            ("m1.zones", ptr("example.com.")),
+            ("coo.m1.zones", ptr("other.catalog.invalid.")),
+            ("group.m1.zones", txt("blue")),
+            // several zones, the member is ignored
+            ("m2.zones", ptr("a.example.net.")),
+            ("m2.zones", ptr("b.example.net.")),
+            // a zone listed twice, only the first member is kept
+            ("m3.zones", ptr("test.com.")),
+            ("m4.zones", ptr("test.com.")),
+            // several groups, the property is ignored
+            ("m5.zones", ptr("example.org.")),
+            ("group.m5.zones", txt("blue")),
+            ("group.m5.zones", txt("green")),
+        ],
+    );
+
+    let catalog_zone = CatalogZone::from_records(origin.clone(), &records).unwrap();
+    let members = catalog_zone.members();
+    assert_eq!(
+        members
+            .iter()
+            .map(|member| (member.unique_id(), member.zone().to_string()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("m1", "example.com.".to_string()),
+            ("m3", "test.com.".to_string()),
+            ("m5", "example.org.".to_string()),
+        ]
+    );
+    assert_eq!(
+        members[0].coo(),
+        Some(&Name::from_str("other.catalog.invalid.").unwrap())
+    );
+    assert_eq!(members[0].group(), Some("blue"));
+    assert_eq!(members[2].group(), None);
+
+    // only version 2 is supported
+    let mut records = catalog_zone_records(&origin, &[("m1.zones", ptr("example.com."))]);
+    records[1].set_data(Some(txt("1")));
+    assert!(CatalogZone::from_records(origin.clone(), &records).is_err());
+    records.remove(1);
+    assert!(CatalogZone::from_records(origin, &records).is_err());
+}
+
+#[tokio::test]
+async fn test_load_catalog_zone() {
+    let example = create_example();
+    let example_origin = example.origin().clone();
+    let example = InMemoryAuthority::new(
+        example_origin.clone().into(),
+        example
+            .records()
+            .await
+            .into_iter()
+            .map(|(key, rrset)| (key, RecordSet::clone(&rrset)))
+            .collect(),
+        ZoneType::Primary,
+        true,
+    )
+    .unwrap();
+
+    let origin = Name::from_str("catalog.invalid.").unwrap();
+    let mut catalog_zone = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, true);
+    for record in catalog_zone_records(
+        &origin,
+        &[
+            ("m1.zones", ptr("example.com.")),
+            ("m2.zones", ptr("test.com.")),
+        ],
+    ) {
+        catalog_zone.upsert_mut(record, 1);
+    }
+    let catalog_zone = Arc::new(catalog_zone);
+
+    let mut primary = Catalog::new();
+    primary.upsert(example_origin.clone(), Box::new(Arc::new(example)));
+    primary.upsert(origin.clone().into(), Box::new(catalog_zone.clone()));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let primary_addr = listener.local_addr().unwrap();
+    let mut server = ServerFuture::new(primary);
+    server.register_listener(listener, Duration::from_secs(5));
+
+    let catalog = Catalog::new();
+    let loaded = catalog
+        .load_catalog_zone(origin.clone(), vec![primary_addr])
+        .await
+        .expect("load failed");
+    assert_eq!(loaded.members().len(), 2);
+    assert!(catalog.contains(&example_origin));
+    assert!(catalog.contains(&Name::from_str("test.com.").unwrap().into()));
+    // the catalog zone itself is not served
+    assert!(!catalog.contains(&origin.clone().into()));
+
+    // the member zones are transferred in the background
+    let member = catalog.member_zone(&example_origin).unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while member
+            .lookup(&example_origin, RecordType::A, Default::default())
+            .await
+            .is_err()
+        {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("member zone was not transferred");
+
+    // removing a member from the catalog zone removes the zone
+    catalog_zone.records_mut().await.remove(&RrKey::new(
+        Name::from_str("m2.zones.catalog.invalid.").unwrap().into(),
+        RecordType::PTR,
+    ));
+    catalog_zone.upsert(catalog_zone_soa(&origin, 2), 2).await;
+
+    // the catalog zone is refreshed on NOTIFY from its primary
+    let mut notify = Message::new();
+    notify
+        .set_op_code(OpCode::Notify)
+        .add_query(Query::query(origin.clone(), RecordType::SOA));
+    let notify = MessageRequest::from_bytes(&notify.to_bytes().unwrap()).unwrap();
+    let notify = Request::new(notify, ([127, 0, 0, 1], 53).into(), Protocol::Udp);
+    let response_handler = TestResponseHandler::new();
+    catalog
+        .handle_request(&notify, response_handler.clone())
+        .await;
+    assert_eq!(
+        response_handler.into_message().await.response_code(),
+        ResponseCode::NoError
+    );
+
+    let test_origin = LowerName::from(Name::from_str("test.com.").unwrap());
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while catalog.contains(&test_origin) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("member zone was not removed");
+    assert!(catalog.contains(&example_origin));
+    assert!(catalog.member_zone(&test_origin).is_none());
+
+    // loading the catalog zone again synchronizes it as well
+    let loaded = catalog
+        .load_catalog_zone(origin, vec![primary_addr])
+        .await
+        .expect("load failed");
+    assert_eq!(loaded.members().len(), 1);
+    assert!(catalog.contains(&example_origin));
+    assert!(!catalog.contains(&test_origin));
+}
+
+#[cfg(feature = "dnssec")]
+mod tsig {
+    use futures::TryStreamExt;
+    use trust_dns_client::serialize::binary::BinEncoder;
+    use trust_dns_client::{
+        client::{AsyncClient, ClientHandle},
+        rr::{dnssec::tsig::TSigner, rdata::tsig::TsigAlgorithm},
+    };
+    use trust_dns_proto::op::MessageFinalizer;
+    use trust_dns_server::{
+        authority::MessageResponse,
+        server::{ResponseHandler, ResponseInfo},
+    };
+
+    use super::*;
+
+    fn signer(key: &[u8]) -> TSigner {
+        TSigner::new(
+            key.to_vec(),
+            TsigAlgorithm::HmacSha256,
+            Name::from_str("tsig-key.example.com.").unwrap(),
+            300,
+        )
+        .unwrap()
+    }
+
+    fn catalog() -> Catalog {
+        let mut example = create_example();
+        example.set_allow_axfr(true);
+        let origin = example.origin().clone();
+
+        let mut catalog = Catalog::new();
+        catalog.upsert(origin, Box::new(Arc::new(example)));
+        catalog.add_tsig_key(signer(b"some_key"));
+        catalog
+    }
+
+    fn now() -> u32 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as u32
+    }
+
+    /// Sends an AXFR signed at `time` to the catalog, returns the response
+    async fn signed_axfr(catalog: &Catalog, signer: &TSigner, time: u32) -> Message {
+        let mut question = Message::new();
+        question.set_id(10).add_query(Query::query(
+            Name::from_str("example.com.").unwrap(),
+            RecordType::AXFR,
+        ));
+        let (tsig, _) = signer.finalize_message(&question, time).unwrap();
+        question.add_tsig(tsig.into_iter().next().unwrap());
+
+        let question_bytes = question.to_bytes().unwrap();
+        let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+        let question_req = Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Tcp);
+
+        let response_handler = TestResponseHandler::new();
+        catalog
+            .handle_request(&question_req, response_handler.clone())
+            .await;
+        response_handler.into_message().await
+    }
+
+    /// Collects every message of a multi-message response
+    #[derive(Clone, Default)]
+    struct CollectingResponseHandler(Arc<std::sync::Mutex<Vec<Vec<u8>>>>);
+
+    #[async_trait::async_trait]
+    impl ResponseHandler for CollectingResponseHandler {
+        async fn send_response<'a>(
+            &mut self,
+            response: MessageResponse<
+                '_,
+                'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+                impl Iterator<Item = &'a Record> + Send + 'a,
+            >,
+        ) -> std::io::Result<ResponseInfo> {
+            let mut buf = Vec::new();
+            let info = response
+                .destructive_emit(&mut BinEncoder::new(&mut buf))
+                .expect("could not encode");
+            self.0.lock().unwrap().push(buf);
+            Ok(info)
+        }
+    }
+
+    fn response_tsig(response: &Message) -> &trust_dns_client::rr::rdata::tsig::TSIG {
+        response
+            .signature()
+            .last()
+            .and_then(Record::data)
+            .and_then(RData::as_dnssec)
+            .and_then(DNSSECRData::as_tsig)
+            .expect("response should have a TSIG")
+    }
+
+    #[tokio::test]
+    async fn test_tsig_axfr() {
+        let catalog = Arc::new(std::sync::Mutex::new(catalog()));
+        let (stream, sender) = TestClientStream::new(catalog);
+        let (mut client, bg) =
+            AsyncClient::new(stream, sender, Some(Arc::new(signer(b"some_key").into())))
+                .await
+                .expect("client failed to connect");
+        tokio::spawn(bg);
+
+        // the client fails the transfer if the response is not signed with the key
+        let response = client
+            .zone_transfer(Name::from_str("example.com.").unwrap(), None)
+            .try_next()
+            .await
+            .expect("transfer failed")
+            .expect("no response");
+
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(!response.answers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tsig_axfr_chained() {
+        // one record per message, the MAC of each message after the first covers the one before it
+        let mut catalog = catalog();
+        catalog.set_axfr_message_size(1);
+
+        let mut question = Message::new();
+        question.set_id(10).add_query(Query::query(
+            Name::from_str("example.com.").unwrap(),
+            RecordType::AXFR,
+        ));
+        let (tsig, verifier) = signer(b"some_key")
+            .finalize_message(&question, now())
+            .unwrap();
+        let mut verifier = verifier.expect("should have a verifier");
+        question.add_tsig(tsig.into_iter().next().unwrap());
+
+        let question_bytes = question.to_bytes().unwrap();
+        let question_req = MessageRequest::from_bytes(&question_bytes).unwrap();
+        let question_req = Request::new(question_req, ([127, 0, 0, 1], 5553).into(), Protocol::Tcp);
+
+        let response_handler = CollectingResponseHandler::default();
+        catalog
+            .handle_request(&question_req, response_handler.clone())
+            .await;
+
+        let messages = response_handler.0.lock().unwrap().clone();
+        assert!(messages.len() > 2);
+        for message in messages {
+            let response = verifier(&message).expect("response should verify");
+            assert_eq!(response.response_code(), ResponseCode::NoError);
+            assert_eq!(response.answers().len(), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tsig_axfr_acl() {
+        let mut catalog = catalog();
+        catalog.set_acl(
+            Name::from_str("example.com.").unwrap().into(),
+            ZoneAcl {
+                allow_query: vec![],
+                allow_transfer: vec!["192.0.2.0/24".parse().unwrap()],
+                allow_transfer_keys: vec![Name::from_str("tsig-key.example.com.").unwrap().into()],
+            },
+        );
+
+        // the key allows the transfer from outside of allow_transfer
+        let response = signed_axfr(&catalog, &signer(b"some_key"), now()).await;
+        assert_eq!(response.response_code(), ResponseCode::NoError);
+        assert!(!response.answers().is_empty());
+
+        let response = lookup_from(
+            &catalog,
+            Name::from_str("example.com.").unwrap(),
+            RecordType::AXFR,
+            ([127, 0, 0, 1], 5553).into(),
+        )
+        .await;
+        assert_eq!(response.response_code(), ResponseCode::Refused);
+        assert!(response.answers().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tsig_bad_key() {
+        let catalog = catalog();
+        let other = TSigner::new(
+            b"some_key".to_vec(),
+            TsigAlgorithm::HmacSha256,
+            Name::from_str("other-key.example.com.").unwrap(),
+            300,
+        )
+        .unwrap();
+
+        let response = signed_axfr(&catalog, &other, now()).await;
+        let tsig = response_tsig(&response);
+
+        assert_eq!(response.response_code(), ResponseCode::NotAuth);
+        assert!(response.answers().is_empty());
+        assert_eq!(tsig.error(), u16::from(ResponseCode::BADKEY));
+        assert!(tsig.mac().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tsig_bad_sig() {
+        let catalog = catalog();
+
+        let response = signed_axfr(&catalog, &signer(b"other_key"), now()).await;
+        let tsig = response_tsig(&response);
+
+        assert_eq!(response.response_code(), ResponseCode::NotAuth);
+        assert!(response.answers().is_empty());
+        assert_eq!(tsig.error(), u16::from(ResponseCode::BADSIG));
+        assert!(tsig.mac().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_tsig_bad_time() {
+        let catalog = catalog();
+        let time = now() - 3600;
+
+        let response = signed_axfr(&catalog, &signer(b"some_key"), time).await;
+        let tsig = response_tsig(&response);
+
+        assert_eq!(response.response_code(), ResponseCode::NotAuth);
+        assert!(response.answers().is_empty());
+        assert_eq!(tsig.error(), u16::from(ResponseCode::BADTIME));
+        assert_eq!(tsig.time(), time as u64);
+        assert_eq!(tsig.other().len(), 6);
+        // BADTIME is signed
+        assert!(!tsig.mac().is_empty());
+    }
+}
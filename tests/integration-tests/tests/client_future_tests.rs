@@ -1,7 +1,10 @@
 use std::{
     net::*,
     str::FromStr,
-    sync::{Arc, Mutex as StdMutex},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex as StdMutex,
+    },
 };
 
 use futures::{Future, FutureExt, TryFutureExt};
@@ -106,6 +109,99 @@ fn test_query_tcp_ipv4() {
     io_loop.block_on(test_query(&mut client));
 }
 
+/// Answers the queries on each accepted connection in reverse order, in batches of `batch` queries
+///
+/// Returns the address of the server and the count of accepted connections
+fn pipelining_tcp_server(batch: usize) -> (SocketAddr, Arc<AtomicUsize>) {
+    use std::io::{Read, Write};
+    use trust_dns_client::serialize::binary::{BinDecodable, BinEncodable};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let connections = Arc::new(AtomicUsize::new(0));
+    let accepted = Arc::clone(&connections);
+
+    std::thread::spawn(move || {
+        for socket in listener.incoming() {
+            let mut socket = socket.expect("accept failed");
+            accepted.fetch_add(1, Ordering::SeqCst);
+
+            std::thread::spawn(move || loop {
+                let mut responses = Vec::with_capacity(batch);
+                for _ in 0..batch {
+                    let mut len = [0_u8; 2];
+                    if socket.read_exact(&mut len).is_err() {
+                        return;
+                    }
+                    let mut bytes = vec![0_u8; u16::from_be_bytes(len) as usize];
+                    socket.read_exact(&mut bytes).expect("read failed");
+
+                    // the address of each answer is the label of the query, e.g. 7.example.com. is 127.0.0.7
+                    let mut response = Message::from_bytes(&bytes).expect("bad query");
+                    let name = response.queries()[0].name().clone();
+                    let label = name.iter().next().expect("no label");
+                    let ip =
+                        0x7F00_0000 + std::str::from_utf8(label).unwrap().parse::<u32>().unwrap();
+                    response.set_message_type(MessageType::Response).add_answer(
+                        trust_dns_client::rr::Record::from_rdata(
+                            name,
+                            300,
+                            RData::A(Ipv4Addr::from(ip)),
+                        ),
+                    );
+                    responses.push(response.to_bytes().unwrap());
+                }
+
+                for bytes in responses.iter().rev() {
+                    socket
+                        .write_all(&(bytes.len() as u16).to_be_bytes())
+                        .expect("write failed");
+                    socket.write_all(bytes).expect("write failed");
+                }
+            });
+        }
+    });
+
+    (addr, connections)
+}
+
+#[test]
+fn test_pipelined_tcp_queries() {
+    const QUERIES: u32 = 1000;
+
+    let io_loop = Runtime::new().unwrap();
+    let (addr, connections) = pipelining_tcp_server(10);
+
+    let (stream, sender) = TcpClientStream::<AsyncIoTokioAsStd<TokioTcpStream>>::new(addr);
+    let client = AsyncClient::new(stream, sender, None);
+    let (client, bg) = io_loop.block_on(client).expect("client failed to connect");
+    trust_dns_proto::spawn_bg(&io_loop, bg);
+
+    // all of the queries are outstanding at once, responses are matched to the queries by ID
+    let queries = (0..QUERIES).map(|i| {
+        let mut client = client.clone();
+        let name = Name::from_ascii(&format!("{}.example.com.", i)).unwrap();
+        async move {
+            let response = client
+                .query(name, DNSClass::IN, RecordType::A)
+                .await
+                .expect("query failed");
+            (i, response)
+        }
+    });
+
+    let responses = io_loop.block_on(futures::future::join_all(queries));
+    for (i, response) in responses {
+        assert_eq!(
+            response.answers()[0].data(),
+            Some(&RData::A(Ipv4Addr::from(0x7F00_0000 + i)))
+        );
+    }
+
+    // all of the queries were sent over the one connection
+    assert_eq!(connections.load(Ordering::SeqCst), 1);
+}
+
 #[test]
 #[ignore]
 fn test_query_tcp_ipv6() {
@@ -258,6 +354,139 @@ fn test_query_edns(client: &mut AsyncClient) -> impl Future<Output = ()> {
         .map(|r: Result<_, _>| r.expect("query failed"))
 }
 
+#[test]
+fn test_exchange_multiple_queries() {
+    use trust_dns_client::rr::Record;
+
+    // answers every question of a message, A with 127.0.0.1 and AAAA with ::1
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let mut buf = [0_u8; 512];
+        let (len, src) = socket.recv_from(&mut buf).expect("recv failed");
+
+        let mut response = Message::from_vec(&buf[..len]).expect("bad query");
+        let answers = response
+            .queries()
+            .iter()
+            .map(|query| {
+                let rdata = match query.query_type() {
+                    RecordType::A => RData::A(Ipv4Addr::LOCALHOST),
+                    RecordType::AAAA => RData::AAAA(Ipv6Addr::LOCALHOST),
+                    query_type => panic!("unexpected query type: {}", query_type),
+                };
+                Record::from_rdata(query.name().clone(), 300, rdata)
+            })
+            .collect::<Vec<_>>();
+        response
+            .set_message_type(MessageType::Response)
+            .insert_answers(answers);
+
+        socket
+            .send_to(&response.to_vec().unwrap(), src)
+            .expect("send failed");
+    });
+
+    let io_loop = Runtime::new().unwrap();
+    let stream = UdpClientStream::<TokioUdpSocket>::new(addr);
+    let client = AsyncClient::connect(stream);
+    let (mut client, bg) = io_loop.block_on(client).expect("client failed to connect");
+    trust_dns_proto::spawn_bg(&io_loop, bg);
+
+    let name = Name::from_str("www.example.com.").unwrap();
+    let mut message = Message::new();
+    message
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .add_query(Query::query(name.clone(), RecordType::A))
+        .add_query(Query::query(name.clone(), RecordType::AAAA));
+
+    let response = io_loop
+        .block_on(client.exchange(message))
+        .expect("exchange failed");
+    assert_eq!(response.queries().len(), 2);
+    assert_eq!(response.answers().len(), 2);
+
+    // each answer correlates to one of the questions
+    for (query, answer) in response.queries().iter().zip(response.answers()) {
+        assert_eq!(answer.name(), &name);
+        assert_eq!(answer.record_type(), query.query_type());
+    }
+}
+
+#[test]
+fn test_send_bytes() {
+    use trust_dns_client::rr::Record;
+    use trust_dns_client::serialize::binary::{BinEncodable, BinEncoder};
+
+    fn emit_uncompressed(message: &Message) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = BinEncoder::new(&mut bytes);
+        encoder.set_canonical_names(true);
+        message.emit(&mut encoder).unwrap();
+        bytes
+    }
+
+    // answers with names that are not compressed, which is kept as is in the returned bytes
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+    let (sent, responses) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0_u8; 512];
+        let (len, src) = socket.recv_from(&mut buf).expect("recv failed");
+        let request = buf[..len].to_vec();
+
+        let mut response = Message::from_vec(&buf[..len]).expect("bad query");
+        let name = response.queries()[0].name().clone();
+        response
+            .set_message_type(MessageType::Response)
+            .add_answer(Record::from_rdata(name, 300, RData::A(Ipv4Addr::LOCALHOST)));
+
+        let bytes = emit_uncompressed(&response);
+
+        socket.send_to(&bytes, src).expect("send failed");
+        sent.send((request, bytes)).unwrap();
+    });
+
+    let io_loop = Runtime::new().unwrap();
+    let stream = UdpClientStream::<TokioUdpSocket>::new(addr);
+    let client = AsyncClient::connect(stream);
+    let (mut client, bg) = io_loop.block_on(client).expect("client failed to connect");
+    trust_dns_proto::spawn_bg(&io_loop, bg);
+
+    let mut message = Message::new();
+    message
+        .set_id(0xBEEF)
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ))
+        .add_query(Query::query(
+            Name::from_str("mail.example.com.").unwrap(),
+            RecordType::A,
+        ));
+    let wire = emit_uncompressed(&message);
+
+    let bytes = io_loop
+        .block_on(client.send_bytes(&wire))
+        .expect("send failed");
+
+    // the request is received as is, only with the id of the transport
+    let (request, mut expected) = responses.recv().unwrap();
+    assert_eq!(request[2..], wire[2..]);
+    assert_ne!(request[2..], message.to_vec().unwrap()[2..]);
+
+    // the bytes are those sent by the server, with the id of the request
+    assert_ne!(
+        expected,
+        Message::from_vec(&expected).unwrap().to_vec().unwrap()
+    );
+    expected[..2].copy_from_slice(&0xBEEF_u16.to_be_bytes());
+    assert_eq!(bytes, expected);
+}
+
 #[test]
 fn test_notify() {
     let io_loop = Runtime::new().unwrap();
@@ -286,16 +515,9 @@ fn test_notify() {
 // update tests
 //
 
-/// create a client with a sig0 section
+/// create a catalog with the KEY of the returned signer at trusted.example.com
 #[cfg(all(feature = "dnssec", feature = "sqlite"))]
-#[allow(clippy::type_complexity)]
-async fn create_sig0_ready_client() -> (
-    (
-        AsyncClient,
-        DnsExchangeBackground<DnsMultiplexer<TestClientStream, Signer>, TokioTime>,
-    ),
-    Name,
-) {
+fn create_sig0_ready_catalog() -> (Catalog, SigSigner) {
     use openssl::rsa::Rsa;
     use trust_dns_client::rr::dnssec::{Algorithm, KeyPair};
     use trust_dns_client::rr::rdata::DNSSECRData;
@@ -303,7 +525,6 @@ async fn create_sig0_ready_client() -> (
 
     let authority = create_example();
     let mut authority = SqliteAuthority::new(authority, true, false);
-    let origin = authority.origin().clone();
 
     let trusted_name = Name::from_str("trusted.example.com").unwrap();
 
@@ -326,13 +547,29 @@ async fn create_sig0_ready_client() -> (
     let mut catalog = Catalog::new();
     catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
 
+    (catalog, signer)
+}
+
+/// create a client with a sig0 section
+#[cfg(all(feature = "dnssec", feature = "sqlite"))]
+#[allow(clippy::type_complexity)]
+async fn create_sig0_ready_client() -> (
+    (
+        AsyncClient,
+        DnsExchangeBackground<DnsMultiplexer<TestClientStream, Signer>, TokioTime>,
+    ),
+    Name,
+) {
+    let (catalog, signer) = create_sig0_ready_catalog();
+    let origin = Name::from_str("example.com.").unwrap();
+
     let signer = Arc::new(signer.into());
     let (stream, sender) = TestClientStream::new(Arc::new(StdMutex::new(catalog)));
     let client = AsyncClient::new(stream, sender, Some(signer))
         .await
         .expect("failed to get new AsyncClient");
 
-    (client, origin.into())
+    (client, origin)
 }
 
 #[cfg(all(feature = "dnssec", feature = "sqlite"))]
@@ -379,6 +616,111 @@ fn test_create() {
     assert_eq!(result.response_code(), ResponseCode::YXRRSet);
 }
 
+#[cfg(all(feature = "dnssec", feature = "sqlite"))]
+#[test]
+fn test_create_with_expired_sig0() {
+    use trust_dns_client::op::update_message;
+    use trust_dns_proto::xfer::{DnsRequest, DnsRequestOptions};
+
+    let io_loop = Runtime::new().unwrap();
+    let (catalog, signer) = create_sig0_ready_catalog();
+
+    // the update is signed by the test, not by the client
+    let (stream, sender) = TestClientStream::new(Arc::new(StdMutex::new(catalog)));
+    let client = AsyncClient::new(stream, sender, None);
+    let (mut client, bg) = io_loop.block_on(client).expect("client failed to connect");
+    trust_dns_proto::spawn_bg(&io_loop, bg);
+
+    let mut record = Record::with(
+        Name::from_str("new.example.com").unwrap(),
+        RecordType::A,
+        Duration::minutes(5).whole_seconds() as u32,
+    );
+    record.set_data(Some(RData::A(Ipv4Addr::new(100, 10, 100, 10))));
+
+    // a valid signature from long ago is not accepted
+    let mut update =
+        update_message::create(record.into(), Name::from_str("example.com.").unwrap(), true);
+    update.finalize(&signer, 1000).expect("failed to sign");
+
+    let result = io_loop
+        .block_on(
+            client
+                .send(DnsRequest::new(update, DnsRequestOptions::default()))
+                .first_answer(),
+        )
+        .expect("update failed");
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+}
+
+#[cfg(all(feature = "dnssec", feature = "sqlite"))]
+#[test]
+fn test_create_with_tsig() {
+    use trust_dns_client::rr::dnssec::tsig::TSigner;
+    use trust_dns_client::rr::rdata::tsig::TsigAlgorithm;
+    use trust_dns_server::store::sqlite::SqliteAuthority;
+
+    let io_loop = Runtime::new().unwrap();
+
+    let mut authority = SqliteAuthority::new(create_example(), true, false);
+    authority.set_allow_update_keys(vec![Name::from_str("tsig-key.example.com.")
+        .unwrap()
+        .into()]);
+    let origin: Name = authority.origin().clone().into();
+
+    let tsigner = |name: &str| {
+        TSigner::new(
+            b"some_key".to_vec(),
+            TsigAlgorithm::HmacSha256,
+            Name::from_str(name).unwrap(),
+            300,
+        )
+        .unwrap()
+    };
+    let signer = tsigner("tsig-key.example.com.");
+    let other = tsigner("other-key.example.com.");
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(authority.origin().clone(), Box::new(Arc::new(authority)));
+    catalog.add_tsig_key(signer.clone());
+    catalog.add_tsig_key(other.clone());
+    let catalog = Arc::new(StdMutex::new(catalog));
+
+    let mut record = Record::with(
+        Name::from_str("new.example.com").unwrap(),
+        RecordType::A,
+        Duration::minutes(5).whole_seconds() as u32,
+    );
+    record.set_data(Some(RData::A(Ipv4Addr::new(100, 10, 100, 10))));
+
+    // a key known to the catalog, but not allowed to update the zone, is refused
+    let (stream, sender) = TestClientStream::new(Arc::clone(&catalog));
+    let client = AsyncClient::new(stream, sender, Some(Arc::new(other.into())));
+    let (mut client, bg) = io_loop.block_on(client).expect("client failed to connect");
+    trust_dns_proto::spawn_bg(&io_loop, bg);
+
+    let result = io_loop
+        .block_on(client.create(record.clone(), origin.clone()))
+        .expect("create failed");
+    assert_eq!(result.response_code(), ResponseCode::Refused);
+
+    let (stream, sender) = TestClientStream::new(catalog);
+    let client = AsyncClient::new(stream, sender, Some(Arc::new(signer.into())));
+    let (mut client, bg) = io_loop.block_on(client).expect("client failed to connect");
+    trust_dns_proto::spawn_bg(&io_loop, bg);
+
+    // the response is verified by the signer of the client
+    let result = io_loop
+        .block_on(client.create(record.clone(), origin))
+        .expect("create failed");
+    assert_eq!(result.response_code(), ResponseCode::NoError);
+
+    let result = io_loop
+        .block_on(client.query(record.name().clone(), record.dns_class(), record.rr_type()))
+        .expect("query failed");
+    assert_eq!(result.answers(), &[record]);
+}
+
 #[cfg(all(feature = "dnssec", feature = "sqlite"))]
 #[test]
 fn test_create_multi() {
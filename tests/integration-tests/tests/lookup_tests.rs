@@ -91,6 +91,7 @@ fn test_lookup_hosts() {
         Default::default(),
         Some(Arc::new(hosts)),
         None,
+        None,
     );
     let lookup = io_loop.block_on(lookup).unwrap();
 
@@ -134,6 +135,7 @@ fn test_lookup_ipv4_like() {
         Default::default(),
         Some(Arc::new(Hosts::default())),
         Some(RData::A(Ipv4Addr::new(1, 2, 3, 4))),
+        None,
     );
     let lookup = io_loop.block_on(lookup).unwrap();
 
@@ -164,6 +166,7 @@ fn test_lookup_ipv4_like_fall_through() {
         Default::default(),
         Some(Arc::new(Hosts::default())),
         Some(RData::A(Ipv4Addr::new(198, 51, 100, 35))),
+        None,
     );
     let lookup = io_loop.block_on(lookup).unwrap();
 
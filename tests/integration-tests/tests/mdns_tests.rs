@@ -19,7 +19,7 @@ use trust_dns_client::multicast::{MdnsClientStream, MdnsStream};
 use trust_dns_client::op::Message;
 use trust_dns_client::rr::{DNSClass, Name, RecordType};
 use trust_dns_client::serialize::binary::BinDecodable;
-use trust_dns_proto::xfer::SerialMessage;
+use trust_dns_proto::xfer::{DnsStreamHandle, SerialMessage};
 
 const MDNS_PORT: u16 = 5363;
 
@@ -10,6 +10,8 @@ use std::task::Poll;
 use futures::executor::block_on;
 use futures::{future, Future};
 
+#[cfg(feature = "mdns")]
+use tokio::runtime::Runtime;
 use trust_dns_client::op::Query;
 use trust_dns_client::rr::{Name, RecordType};
 use trust_dns_integration::mock_client::*;
@@ -81,6 +83,23 @@ fn mock_nameserver_on_send_nx<O: OnSend + Unpin>(
     options: ResolverOpts,
     on_send: O,
     trust_nx_responses: bool,
+) -> MockedNameServer<O> {
+    mock_nameserver_at(
+        SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 0),
+        messages,
+        options,
+        on_send,
+        trust_nx_responses,
+    )
+}
+
+#[cfg(test)]
+fn mock_nameserver_at<O: OnSend + Unpin>(
+    socket_addr: SocketAddr,
+    messages: Vec<Result<DnsResponse, ResolveError>>,
+    options: ResolverOpts,
+    on_send: O,
+    trust_nx_responses: bool,
 ) -> MockedNameServer<O> {
     let conn_provider = MockConnProvider {
         on_send: on_send.clone(),
@@ -89,7 +108,7 @@ fn mock_nameserver_on_send_nx<O: OnSend + Unpin>(
 
     NameServer::from_conn(
         NameServerConfig {
-            socket_addr: SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 0),
+            socket_addr,
             protocol: Protocol::Udp,
             tls_dns_name: None,
             trust_nx_responses,
@@ -107,7 +126,7 @@ fn mock_nameserver_on_send_nx<O: OnSend + Unpin>(
 fn mock_nameserver_pool(
     udp: Vec<MockedNameServer<DefaultOnSend>>,
     tcp: Vec<MockedNameServer<DefaultOnSend>>,
-    _mdns: Option<MockedNameServer<DefaultOnSend>>,
+    _mdns: Vec<MockedNameServer<DefaultOnSend>>,
     options: ResolverOpts,
 ) -> MockedNameServerPool<DefaultOnSend> {
     mock_nameserver_pool_on_send::<DefaultOnSend>(udp, tcp, _mdns, options)
@@ -118,18 +137,14 @@ fn mock_nameserver_pool(
 fn mock_nameserver_pool_on_send<O: OnSend + Unpin>(
     udp: Vec<MockedNameServer<O>>,
     tcp: Vec<MockedNameServer<O>>,
-    _mdns: Option<MockedNameServer<O>>,
+    _mdns: Vec<MockedNameServer<O>>,
     options: ResolverOpts,
 ) -> MockedNameServerPool<O> {
     #[cfg(not(feature = "mdns"))]
     return NameServerPool::from_nameservers(&options, udp, tcp);
 
     #[cfg(feature = "mdns")]
-    return NameServerPool::from_nameservers(
-        &options, udp,
-        tcp,
-        //_mdns.unwrap_or_else(move || mock_nameserver_on_send(vec![], options, on_send)),
-    );
+    return NameServerPool::from_nameservers(&options, udp, tcp, _mdns);
 }
 
 #[test]
@@ -147,7 +162,7 @@ fn test_datagram() {
     let mut pool = mock_nameserver_pool(
         vec![udp_nameserver],
         vec![tcp_nameserver],
-        None,
+        vec![],
         Default::default(),
     );
 
@@ -179,7 +194,7 @@ fn test_datagram_stream_upgrades_on_truncation() {
     let mut pool = mock_nameserver_pool(
         vec![udp_nameserver],
         vec![tcp_nameserver],
-        None,
+        vec![],
         Default::default(),
     );
 
@@ -218,7 +233,7 @@ fn test_datagram_stream_upgrade_on_truncation_despite_udp() {
     let mut pool = mock_nameserver_pool(
         vec![udp_nameserver],
         vec![tcp_nameserver],
-        None,
+        vec![],
         Default::default(),
     );
 
@@ -247,7 +262,8 @@ fn test_datagram_fails_to_stream() {
 
     let mut options = ResolverOpts::default();
     options.try_tcp_on_error = true;
-    let mut pool = mock_nameserver_pool(vec![udp_nameserver], vec![tcp_nameserver], None, options);
+    let mut pool =
+        mock_nameserver_pool(vec![udp_nameserver], vec![tcp_nameserver], vec![], options);
 
     let request = message(query, vec![], vec![], vec![]);
     let future = pool.send(request).first_answer();
@@ -279,7 +295,7 @@ fn test_tcp_fallback_only_on_truncated() {
     let mut pool = mock_nameserver_pool(
         vec![udp_nameserver],
         vec![tcp_nameserver],
-        None,
+        vec![],
         Default::default(),
     );
 
@@ -297,6 +313,13 @@ fn test_tcp_fallback_only_on_truncated() {
     }
 }
 
+#[cfg(feature = "mdns")]
+fn mdns_options() -> ResolverOpts {
+    let mut options = ResolverOpts::default();
+    options.use_mdns = true;
+    options
+}
+
 #[test]
 #[cfg(feature = "mdns")]
 fn test_local_mdns() {
@@ -318,16 +341,79 @@ fn test_local_mdns() {
     let mut pool = mock_nameserver_pool(
         vec![udp_nameserver],
         vec![tcp_nameserver],
-        Some(mdns_nameserver),
-        Default::default(),
+        vec![mdns_nameserver],
+        mdns_options(),
     );
 
-    // lookup on UDP succeeds, any other would fail
+    // lookup on mDNS succeeds, any other would fail
     let request = message(query, vec![], vec![], vec![]);
-    let future = pool.send(request);
+    let future = pool.send(request).first_answer();
 
-    let response = block_on(future).unwrap();
-    assert_eq!(response.answers()[0], mdns_record);
+    let io_loop = Runtime::new().unwrap();
+    let response = io_loop.block_on(future).unwrap();
+    assert_eq!(response.answers(), &[mdns_record]);
+}
+
+#[test]
+#[cfg(feature = "mdns")]
+fn test_local_mdns_all_responders() {
+    let query = Query::query(Name::from_str("www.example.local.").unwrap(), RecordType::A);
+
+    let ipv4_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 2));
+    let ipv6_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 3));
+
+    // both responders return the first record, it's only in the merged response once
+    let ipv4_message = message(query.clone(), vec![ipv4_record.clone()], vec![], vec![]);
+    let ipv6_message = message(
+        query.clone(),
+        vec![ipv4_record.clone(), ipv6_record.clone()],
+        vec![],
+        vec![],
+    );
+
+    let ipv4_nameserver = mock_nameserver(vec![Ok(ipv4_message.into())], Default::default());
+    let ipv6_nameserver = mock_nameserver(vec![Ok(ipv6_message.into())], Default::default());
+
+    let mut pool = mock_nameserver_pool(
+        vec![],
+        vec![],
+        vec![ipv4_nameserver, ipv6_nameserver],
+        mdns_options(),
+    );
+
+    let request = message(query, vec![], vec![], vec![]);
+    let future = pool.send(request).first_answer();
+
+    let io_loop = Runtime::new().unwrap();
+    let response = io_loop.block_on(future).unwrap();
+    assert_eq!(response.answers(), &[ipv4_record, ipv6_record]);
+}
+
+#[test]
+#[cfg(feature = "mdns")]
+fn test_local_mdns_disabled() {
+    let query = Query::query(Name::from_str("www.example.local.").unwrap(), RecordType::A);
+
+    let udp_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 1));
+    let mdns_record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 2));
+
+    let udp_message = message(query.clone(), vec![udp_record.clone()], vec![], vec![]);
+    let mdns_message = message(query.clone(), vec![mdns_record], vec![], vec![]);
+
+    let udp_nameserver = mock_nameserver(vec![Ok(udp_message.into())], Default::default());
+    let mdns_nameserver = mock_nameserver(vec![Ok(mdns_message.into())], Default::default());
+
+    let mut pool = mock_nameserver_pool(
+        vec![udp_nameserver],
+        vec![],
+        vec![mdns_nameserver],
+        Default::default(),
+    );
+
+    // without use_mdns the local name is sent to the name servers
+    let request = message(query, vec![], vec![], vec![]);
+    let response = block_on(pool.send(request).first_answer()).unwrap();
+    assert_eq!(response.answers(), &[udp_record]);
 }
 
 #[test]
@@ -356,7 +442,7 @@ fn test_trust_nx_responses_fails() {
     let mut pool = mock_nameserver_pool(
         vec![fail_nameserver, succeed_nameserver],
         vec![],
-        None,
+        vec![],
         ResolverOpts::default(),
     );
 
@@ -419,7 +505,7 @@ fn test_distrust_nx_responses() {
     let mut pool = mock_nameserver_pool(
         vec![error_nameserver, fallback_nameserver],
         vec![],
-        None,
+        vec![],
         ResolverOpts::default(),
     );
     for response_code in RETRYABLE_ERRORS.iter() {
@@ -458,7 +544,7 @@ fn test_return_error_from_highest_priority_nameserver() {
             mock_nameserver(vec![Err(response)], ResolverOpts::default())
         })
         .collect();
-    let mut pool = mock_nameserver_pool(name_servers, vec![], None, ResolverOpts::default());
+    let mut pool = mock_nameserver_pool(name_servers, vec![], vec![], ResolverOpts::default());
 
     let request = message(query, vec![], vec![], vec![]);
     let future = pool.send(request).first_answer();
@@ -477,6 +563,125 @@ fn test_return_error_from_highest_priority_nameserver() {
     }
 }
 
+#[test]
+fn test_lookup_reports_answering_nameserver() {
+    use trust_dns_proto::op::ResponseCode;
+    use trust_dns_resolver::caching_client::CachingClient;
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let failing_addr = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 1).into(), 53);
+    let answering_addr = SocketAddr::new(Ipv4Addr::new(127, 0, 0, 2).into(), 53);
+
+    let mut servfail = message(query.clone(), vec![], vec![], vec![]);
+    servfail.set_response_code(ResponseCode::ServFail);
+    let servfail = ResolveError::from_response(servfail.into(), false)
+        .expect_err("SERVFAIL should result in resolve error");
+    let record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 1));
+    let answer = message(query.clone(), vec![record], vec![], vec![]);
+
+    let failing = mock_nameserver_at(
+        failing_addr,
+        vec![Err(servfail)],
+        ResolverOpts::default(),
+        DefaultOnSend,
+        false,
+    );
+    let answering = mock_nameserver_at(
+        answering_addr,
+        vec![Ok(answer.into())],
+        ResolverOpts::default(),
+        DefaultOnSend,
+        false,
+    );
+    let pool = mock_nameserver_pool(
+        vec![failing, answering],
+        vec![],
+        vec![],
+        ResolverOpts::default(),
+    );
+    let mut client = CachingClient::new(10, pool, false);
+
+    let lookup = block_on(client.lookup(query.clone(), Default::default())).unwrap();
+    assert_eq!(lookup.server_addr(), Some(answering_addr));
+
+    // the second lookup is answered from the cache
+    let lookup = block_on(client.lookup(query, Default::default())).unwrap();
+    assert_eq!(lookup.server_addr(), None);
+}
+
+#[derive(Clone, Default)]
+struct OnSendCounter {
+    count: Arc<AtomicIsize>,
+}
+
+impl OnSend for OnSendCounter {
+    fn on_send<E>(
+        &mut self,
+        response: Result<DnsResponse, E>,
+    ) -> Pin<Box<dyn Future<Output = Result<DnsResponse, E>> + Send>>
+    where
+        E: From<ProtoError> + Send + 'static,
+    {
+        self.count.fetch_add(1, Ordering::Relaxed);
+        Box::pin(future::ready(response))
+    }
+}
+
+/// Sends three queries to a pool of a dead and a healthy name server, returns the count of attempts
+fn attempts_with_dead_server(strategy: ServerOrderingStrategy) -> isize {
+    use trust_dns_resolver::error::ResolveErrorKind;
+
+    let query = Query::query(Name::from_str("www.example.com.").unwrap(), RecordType::A);
+    let record = v4_record(query.name().clone(), Ipv4Addr::new(127, 0, 0, 1));
+    let answer = message(query.clone(), vec![record.clone()], vec![], vec![]);
+
+    let mut options = ResolverOpts::default();
+    options.num_concurrent_reqs = 1;
+    options.server_ordering_strategy = strategy;
+
+    let on_send = OnSendCounter::default();
+
+    // every connection to the dead server times out
+    let dead = mock_nameserver_on_send(
+        vec![Err(ResolveErrorKind::Timeout.into())],
+        options,
+        on_send.clone(),
+    );
+    let healthy =
+        mock_nameserver_on_send(vec![Ok(answer.clone().into()); 3], options, on_send.clone());
+
+    let mut pool = mock_nameserver_pool_on_send(vec![dead, healthy], vec![], vec![], options);
+
+    for _ in 0..3 {
+        let request = message(query.clone(), vec![], vec![], vec![]);
+        let response = block_on(pool.send(request).first_answer()).unwrap();
+        assert_eq!(response.answers()[0], record);
+    }
+
+    on_send.count.load(Ordering::Relaxed)
+}
+
+#[test]
+fn test_skip_failed_server_until_cooldown() {
+    // the dead server is demoted after the first timeout, the cooldown lasts longer than this test
+    assert_eq!(
+        attempts_with_dead_server(ServerOrderingStrategy::QueryStatistics),
+        4
+    );
+
+    // the dead server is tried first by every query
+    assert_eq!(
+        attempts_with_dead_server(ServerOrderingStrategy::UserProvidedOrder),
+        6
+    );
+
+    // every other query starts with the healthy server
+    assert_eq!(
+        attempts_with_dead_server(ServerOrderingStrategy::RoundRobin),
+        5
+    );
+}
+
 // === Concurrent requests ===
 
 #[derive(Clone)]
@@ -553,7 +758,7 @@ fn test_concurrent_requests_2_conns() {
     let mut pool = mock_nameserver_pool_on_send(
         vec![udp2_nameserver, udp1_nameserver],
         vec![],
-        None,
+        vec![],
         options,
     );
 
@@ -593,7 +798,7 @@ fn test_concurrent_requests_more_than_conns() {
     let mut pool = mock_nameserver_pool_on_send(
         vec![udp2_nameserver, udp1_nameserver],
         vec![],
-        None,
+        vec![],
         options,
     );
 
@@ -632,7 +837,7 @@ fn test_concurrent_requests_1_conn() {
     let mut pool = mock_nameserver_pool_on_send(
         vec![udp2_nameserver, udp1_nameserver],
         vec![],
-        None,
+        vec![],
         options,
     );
 
@@ -671,7 +876,7 @@ fn test_concurrent_requests_0_conn() {
     let mut pool = mock_nameserver_pool_on_send(
         vec![udp2_nameserver, udp1_nameserver],
         vec![],
-        None,
+        vec![],
         options,
     );
 
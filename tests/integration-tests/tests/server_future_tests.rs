@@ -3,7 +3,7 @@ use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use futures::{future, Future, FutureExt};
 use tokio::net::TcpListener;
@@ -14,11 +14,16 @@ use trust_dns_client::client::*;
 use trust_dns_client::op::*;
 use trust_dns_client::rr::*;
 use trust_dns_client::tcp::TcpClientConnection;
+use trust_dns_client::tcp::TcpClientStream;
 use trust_dns_client::udp::UdpClientConnection;
 use trust_dns_proto::error::ProtoError;
-use trust_dns_proto::xfer::DnsRequestSender;
+use trust_dns_proto::iocompat::AsyncIoTokioAsStd;
+use trust_dns_proto::op::{Keepalive, NoopMessageFinalizer};
+use trust_dns_proto::rr::rdata::opt::{EdnsCode, EdnsOption};
+use trust_dns_proto::xfer::{DnsMultiplexer, DnsRequestSender};
 
 use trust_dns_server::authority::{Authority, Catalog};
+use trust_dns_server::server::RrlConfig;
 use trust_dns_server::ServerFuture;
 
 use trust_dns_integration::authority::create_example;
@@ -84,6 +89,191 @@ fn test_server_www_tcp() {
     server_thread.join().unwrap();
 }
 
+async fn connect_tcp(addr: SocketAddr, keepalive: Option<Keepalive>) -> AsyncClient {
+    let (stream, sender) = TcpClientStream::<AsyncIoTokioAsStd<tokio::net::TcpStream>>::new(addr);
+    let mut multiplexer = DnsMultiplexer::new(stream, sender, NoopMessageFinalizer::new());
+    if let Some(keepalive) = keepalive {
+        multiplexer = multiplexer.with_dso_keepalive(keepalive);
+    }
+
+    let (client, bg) = AsyncClient::connect(multiplexer)
+        .await
+        .expect("client failed to connect");
+    tokio::spawn(bg);
+    client
+}
+
+async fn query_www(client: &mut AsyncClient) -> bool {
+    client
+        .query(
+            Name::from_str("www.example.com.").unwrap(),
+            DNSClass::IN,
+            RecordType::A,
+        )
+        .await
+        .is_ok()
+}
+
+#[tokio::test]
+async fn test_server_dso_keepalive() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let tcp_listener = TcpListener::bind(&addr).await.unwrap();
+    let addr = tcp_listener.local_addr().unwrap();
+
+    let mut server = ServerFuture::new(new_catalog());
+    server.set_dso_keepalive(Some(Duration::from_secs(10)));
+    server.register_listener(tcp_listener, Duration::from_millis(500));
+
+    // idle connections are closed by the server
+    let mut client = connect_tcp(addr, None).await;
+    assert!(query_www(&mut client).await);
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(!query_www(&mut client).await);
+
+    // unless a DSO session is established
+    let keepalive = Keepalive::new(None, Some(Duration::from_secs(3600)));
+    let mut client = connect_tcp(addr, Some(keepalive)).await;
+    assert!(query_www(&mut client).await);
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(query_www(&mut client).await);
+}
+
+#[tokio::test]
+async fn test_server_dso_disabled() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let tcp_listener = TcpListener::bind(&addr).await.unwrap();
+    let addr = tcp_listener.local_addr().unwrap();
+
+    let mut server = ServerFuture::new(new_catalog());
+    server.register_listener(tcp_listener, Duration::from_millis(500));
+
+    // no session is established, so the idle connection is closed
+    let keepalive = Keepalive::new(None, Some(Duration::from_secs(10)));
+    let mut client = connect_tcp(addr, Some(keepalive)).await;
+    assert!(query_www(&mut client).await);
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    assert!(!query_www(&mut client).await);
+}
+
+#[tokio::test]
+async fn test_server_axfr_in_multiple_messages() {
+    use futures::TryStreamExt;
+    use trust_dns_client::rr::rdata::SOA;
+    use trust_dns_server::authority::ZoneType;
+    use trust_dns_server::store::in_memory::InMemoryAuthority;
+
+    const HOSTS: u32 = 5000;
+
+    let origin = Name::from_str("example.com.").unwrap();
+    let mut authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, true);
+    let soa = SOA::new(
+        Name::from_str("sns.dns.icann.org.").unwrap(),
+        Name::from_str("noc.dns.icann.org.").unwrap(),
+        2015082403,
+        7200,
+        3600,
+        1209600,
+        3600,
+    );
+    authority.upsert_mut(Record::from_rdata(origin.clone(), 3600, RData::SOA(soa)), 0);
+    for i in 0..HOSTS {
+        let name = Name::from_str(&format!("host-{}", i))
+            .unwrap()
+            .append_domain(&origin)
+            .unwrap();
+        authority.upsert_mut(
+            Record::from_rdata(name, 86400, RData::A(Ipv4Addr::from(0x0a00_0000 + i))),
+            0,
+        );
+    }
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.clone().into(), Box::new(Arc::new(authority)));
+
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let tcp_listener = TcpListener::bind(&addr).await.unwrap();
+    let addr = tcp_listener.local_addr().unwrap();
+
+    let mut server = ServerFuture::new(catalog);
+    server.register_listener(tcp_listener, Duration::from_secs(5));
+
+    let mut client = connect_tcp(addr, None).await;
+    let messages: Vec<DnsResponse> = client
+        .zone_transfer(origin, None)
+        .try_collect()
+        .await
+        .expect("zone transfer failed");
+
+    // the zone arrives in messages of at most the default size
+    assert!(messages.len() > 1, "{} messages", messages.len());
+    assert!(messages[1..].iter().all(|m| m.queries().is_empty()));
+
+    let records = messages
+        .iter()
+        .flat_map(|m| m.answers())
+        .collect::<Vec<_>>();
+    assert_eq!(records.len(), HOSTS as usize + 2);
+    assert_eq!(records.first().unwrap().rr_type(), RecordType::SOA);
+    assert_eq!(records.last().unwrap().rr_type(), RecordType::SOA);
+    assert_eq!(
+        records
+            .iter()
+            .filter(|r| r.rr_type() == RecordType::A)
+            .count(),
+        HOSTS as usize
+    );
+}
+
+#[tokio::test]
+async fn test_server_tcp_keepalive() {
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let tcp_listener = TcpListener::bind(&addr).await.unwrap();
+    let addr = tcp_listener.local_addr().unwrap();
+
+    let mut server = ServerFuture::new(new_catalog());
+    server.set_tcp_keepalive(Some(Duration::from_millis(500)));
+    server.register_listener(tcp_listener, Duration::from_secs(5));
+
+    let (stream, sender) = TcpClientStream::<AsyncIoTokioAsStd<tokio::net::TcpStream>>::new(addr);
+    let multiplexer = DnsMultiplexer::new(stream, sender, NoopMessageFinalizer::new())
+        .with_tcp_keepalive(Duration::from_secs(10));
+    let (mut client, bg) = AsyncClient::connect(multiplexer)
+        .await
+        .expect("client failed to connect");
+    let bg = tokio::spawn(bg);
+
+    let response = client
+        .query(
+            Name::from_str("www.example.com.").unwrap(),
+            DNSClass::IN,
+            RecordType::A,
+        )
+        .await
+        .expect("query failed");
+    let keepalive = response
+        .extensions()
+        .as_ref()
+        .and_then(|edns| edns.option(EdnsCode::Keepalive));
+    assert_eq!(keepalive, Some(&EdnsOption::TcpKeepalive(Some(5))));
+
+    // the client closes the idle connection at the timeout of the server, instead of its own
+    let idle = Instant::now();
+    tokio::time::timeout(Duration::from_secs(5), bg)
+        .await
+        .expect("idle connection was not closed")
+        .expect("background task failed")
+        .expect("connection failed");
+    let idle = idle.elapsed();
+    assert!(
+        idle >= Duration::from_millis(400),
+        "closed after {:?}",
+        idle
+    );
+    assert!(idle < Duration::from_secs(2), "closed after {:?}", idle);
+
+    assert!(!query_www(&mut client).await);
+}
+
 #[test]
 fn test_server_unknown_type() {
     let runtime = Runtime::new().expect("failed to create Tokio Runtime");
@@ -131,6 +321,51 @@ fn test_server_unknown_type() {
     server_thread.join().unwrap();
 }
 
+#[test]
+fn test_server_rrl_udp() {
+    let runtime = Runtime::new().expect("failed to create Tokio Runtime");
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = runtime.block_on(UdpSocket::bind(&addr)).unwrap();
+
+    let ipaddr = udp_socket.local_addr().unwrap();
+    println!("udp_socket on port: {}", ipaddr);
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+
+    // a single response per second, every limited response is truncated
+    let rrl = RrlConfig {
+        responses_per_second: 1,
+        window: Duration::from_secs(15),
+        slip: 1,
+        ..RrlConfig::default()
+    };
+
+    let server_thread = thread::Builder::new()
+        .name("test_server:udp:server".to_string())
+        .spawn(move || server_thread_udp_rrl(runtime, udp_socket, server_continue2, rrl))
+        .unwrap();
+
+    let conn = UdpClientConnection::new(ipaddr).unwrap();
+    let client = SyncClient::new(conn);
+    let name = Name::from_str("www.example.com.").unwrap();
+
+    let response = client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("first query failed");
+    assert!(!response.truncated());
+    assert_eq!(response.answers().len(), 1);
+
+    let response = client
+        .query(&name, DNSClass::IN, RecordType::A)
+        .expect("second query failed");
+    assert!(response.truncated());
+    assert!(response.answers().is_empty());
+    assert_eq!(response.queries().first().unwrap().name(), &name);
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+}
+
 #[test]
 fn test_server_form_error_on_multiple_queries() {
     let runtime = Runtime::new().expect("failed to create Tokio Runtime");
@@ -178,6 +413,106 @@ fn test_server_form_error_on_multiple_queries() {
     server_thread.join().unwrap();
 }
 
+#[test]
+fn test_server_badvers_on_unsupported_edns_version() {
+    let runtime = Runtime::new().expect("failed to create Tokio Runtime");
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = runtime.block_on(UdpSocket::bind(&addr)).unwrap();
+
+    let ipaddr = udp_socket.local_addr().unwrap();
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+
+    let server_thread = thread::Builder::new()
+        .name("test_server:udp:server".to_string())
+        .spawn(move || server_thread_udp(runtime, udp_socket, server_continue2))
+        .unwrap();
+
+    let conn = UdpClientConnection::new(ipaddr).unwrap();
+    let client = SyncClient::new(conn);
+
+    let mut edns = Edns::new();
+    edns.set_version(1);
+    let mut message = Message::new();
+    message
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ))
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_edns(edns);
+
+    let mut client_result = client.send(message);
+    let response = client_result
+        .pop()
+        .expect("there should be one response")
+        .expect("should have been a successful network request");
+
+    assert_eq!(response.response_code(), ResponseCode::BADVERS);
+    assert!(response.answers().is_empty());
+    let edns = response.extensions().as_ref().expect("no OPT in response");
+    assert_eq!(edns.version(), 0);
+    assert_eq!(edns.rcode_high(), ResponseCode::BADVERS.high());
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+}
+
+#[test]
+fn test_server_form_error_on_malformed_edns() {
+    let runtime = Runtime::new().expect("failed to create Tokio Runtime");
+    let addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::new(127, 0, 0, 1), 0));
+    let udp_socket = runtime.block_on(UdpSocket::bind(&addr)).unwrap();
+
+    let ipaddr = udp_socket.local_addr().unwrap();
+    let server_continue = Arc::new(AtomicBool::new(true));
+    let server_continue2 = server_continue.clone();
+
+    let server_thread = thread::Builder::new()
+        .name("test_server:udp:server".to_string())
+        .spawn(move || server_thread_udp(runtime, udp_socket, server_continue2))
+        .unwrap();
+
+    let mut edns = Edns::new();
+    edns.options_mut()
+        .insert(EdnsOption::Unknown(65001, vec![1, 2]));
+    let mut message = Message::new();
+    message
+        .set_id(4321)
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ))
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_edns(edns);
+
+    // the OPT record is last, the length of its only option is made to exceed the record
+    let mut bytes = message.to_vec().unwrap();
+    let option_length = bytes.len() - 4;
+    bytes[option_length..option_length + 2].copy_from_slice(&5_u16.to_be_bytes());
+
+    let socket = std::net::UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    socket.send_to(&bytes, ipaddr).unwrap();
+
+    let mut buf = [0_u8; 512];
+    let (len, _) = socket.recv_from(&mut buf).expect("no response");
+    let response = Message::from_vec(&buf[..len]).unwrap();
+
+    assert_eq!(response.id(), 4321);
+    assert_eq!(response.response_code(), ResponseCode::FormErr);
+    let edns = response.extensions().as_ref().expect("no OPT in response");
+    assert_eq!(edns.version(), 0);
+    assert!(edns.options().as_ref().is_empty());
+
+    server_continue.store(false, Ordering::Relaxed);
+    server_thread.join().unwrap();
+}
+
 #[cfg(feature = "dns-over-rustls")]
 #[allow(unused)]
 fn read_file(path: &str) -> Vec<u8> {
@@ -342,6 +677,26 @@ fn server_thread_udp(io_loop: Runtime, udp_socket: UdpSocket, server_continue: A
     drop(io_loop);
 }
 
+fn server_thread_udp_rrl(
+    io_loop: Runtime,
+    udp_socket: UdpSocket,
+    server_continue: Arc<AtomicBool>,
+    rrl: RrlConfig,
+) {
+    let catalog = new_catalog();
+
+    let mut server = ServerFuture::with_rrl(catalog, rrl);
+
+    let _guard = io_loop.enter();
+    server.register_socket(udp_socket);
+
+    while server_continue.load(Ordering::Relaxed) {
+        io_loop.block_on(future::lazy(|_| tokio::time::sleep(Duration::from_millis(10))).flatten());
+    }
+
+    drop(io_loop);
+}
+
 fn server_thread_tcp(
     io_loop: Runtime,
     tcp_listener: TcpListener,
@@ -1135,6 +1135,74 @@ async fn test_recovery() {
     }));
 }
 
+#[tokio::test]
+async fn test_with_journal() {
+    let journal_path =
+        std::env::temp_dir().join(format!("trust-dns-journal-{}.jrnl", std::process::id()));
+    let _ = std::fs::remove_file(&journal_path);
+
+    // a new journal is started from the zone
+    let authority = create_example()
+        .with_journal(&journal_path)
+        .await
+        .expect("journal not started");
+    let base_serial = authority.serial().await;
+
+    let new_name = Name::from_str("new.example.com").unwrap();
+    let new_record = Record::new()
+        .set_name(new_name.clone())
+        .set_record_type(RecordType::A)
+        .set_data(Some(RData::A(Ipv4Addr::new(10, 11, 12, 13))))
+        .clone();
+    let delete_record = Record::new()
+        .set_name(Name::from_str("www.example.com").unwrap())
+        .set_record_type(RecordType::A)
+        .set_data(Some(RData::A(Ipv4Addr::new(93, 184, 216, 34))))
+        .set_dns_class(DNSClass::NONE)
+        .clone();
+    authority
+        .update_records(&[new_record.clone()], true)
+        .await
+        .unwrap();
+    authority
+        .update_records(&[delete_record], true)
+        .await
+        .unwrap();
+    assert_eq!(authority.serial().await, base_serial + 2);
+    drop(authority);
+
+    // "restart", the updates are replayed on top of the zone
+    let recovered = create_example()
+        .with_journal(&journal_path)
+        .await
+        .expect("journal not replayed");
+    assert_eq!(recovered.serial().await, base_serial + 2);
+
+    let new_rrset: Vec<Record> = recovered
+        .lookup(&new_name.into(), RecordType::A, LookupOptions::default())
+        .await
+        .unwrap()
+        .iter()
+        .cloned()
+        .collect();
+    assert_eq!(new_rrset, vec![new_record.clone()]);
+
+    let www_name = LowerName::from(Name::from_str("www.example.com").unwrap());
+    let www_rrset = recovered
+        .lookup(&www_name, RecordType::A, LookupOptions::default())
+        .await
+        .unwrap();
+    assert!(www_rrset.was_empty());
+    drop(recovered);
+
+    // the zone changed since the journal was started
+    let changed = create_example();
+    changed.update_records(&[new_record], true).await.unwrap();
+    assert!(changed.with_journal(&journal_path).await.is_err());
+
+    std::fs::remove_file(&journal_path).unwrap();
+}
+
 #[tokio::test]
 async fn test_axfr() {
     let mut authority = create_example();
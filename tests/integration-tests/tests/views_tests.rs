@@ -0,0 +1,175 @@
+use std::net::*;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use ipnet::IpNet;
+use tokio::net::UdpSocket as TokioUdpSocket;
+
+use trust_dns_client::{
+    client::AsyncClient,
+    op::*,
+    rr::{
+        rdata::opt::{ClientSubnet, EdnsOption},
+        rdata::SOA,
+        *,
+    },
+    udp::UdpClientStream,
+};
+use trust_dns_proto::xfer::{DnsHandle, DnsRequest, DnsRequestOptions, FirstAnswer};
+
+use trust_dns_server::{
+    authority::{Catalog, ZoneType},
+    store::in_memory::InMemoryAuthority,
+    ServerFuture,
+};
+
+/// A catalog with example.com., where www.example.com. has the address
+fn catalog(address: Ipv4Addr) -> Catalog {
+    let origin = Name::from_str("example.com.").unwrap();
+    let mut authority = InMemoryAuthority::empty(origin.clone(), ZoneType::Primary, false);
+
+    authority.upsert_mut(
+        Record::from_rdata(
+            origin.clone(),
+            3600,
+            RData::SOA(SOA::new(
+                Name::from_str("ns.example.com.").unwrap(),
+                Name::from_str("hostmaster.example.com.").unwrap(),
+                1,
+                7200,
+                3600,
+                1209600,
+                3600,
+            )),
+        ),
+        0,
+    );
+    authority.upsert_mut(
+        Record::from_rdata(
+            Name::from_str("www.example.com.").unwrap(),
+            3600,
+            RData::A(address),
+        ),
+        0,
+    );
+
+    let mut catalog = Catalog::new();
+    catalog.upsert(origin.into(), Box::new(Arc::new(authority)));
+    catalog
+}
+
+const INTERNAL: Ipv4Addr = Ipv4Addr::new(10, 0, 0, 1);
+const EXTERNAL: Ipv4Addr = Ipv4Addr::new(192, 0, 2, 1);
+
+/// A server with the internal view, for the clients of the ACL, and the external catalog as the default
+async fn server(acl: &str) -> (ServerFuture<Catalog>, SocketAddr) {
+    let socket = TokioUdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    let mut server = ServerFuture::new(catalog(EXTERNAL));
+    server.add_view(
+        "internal",
+        vec![IpNet::from_str(acl).unwrap()],
+        catalog(INTERNAL),
+    );
+    server.register_socket(socket);
+    (server, addr)
+}
+
+async fn lookup(addr: SocketAddr, client_subnet: Option<ClientSubnet>) -> Message {
+    let (mut client, bg) = AsyncClient::connect(UdpClientStream::<TokioUdpSocket>::new(addr))
+        .await
+        .expect("client failed to connect");
+    tokio::spawn(bg);
+
+    let mut message = Message::new();
+    message
+        .add_query(Query::query(
+            Name::from_str("www.example.com.").unwrap(),
+            RecordType::A,
+        ))
+        .set_recursion_desired(true);
+    if let Some(client_subnet) = client_subnet {
+        let mut edns = Edns::new();
+        edns.options_mut().insert(EdnsOption::Subnet(client_subnet));
+        message.set_edns(edns);
+    }
+
+    client
+        .send(DnsRequest::new(message, DnsRequestOptions::default()))
+        .first_answer()
+        .await
+        .expect("query failed")
+        .into_inner()
+}
+
+fn answer(response: &Message) -> Option<Ipv4Addr> {
+    response
+        .answers()
+        .first()
+        .and_then(|record| match record.data() {
+            Some(RData::A(address)) => Some(*address),
+            _ => None,
+        })
+}
+
+#[tokio::test]
+async fn test_views() {
+    let (_server, addr) = server("127.0.0.0/8").await;
+    assert_eq!(answer(&lookup(addr, None).await), Some(INTERNAL));
+
+    let (_server, addr) = server("10.0.0.0/8").await;
+    assert_eq!(answer(&lookup(addr, None).await), Some(EXTERNAL));
+}
+
+#[tokio::test]
+async fn test_without_views() {
+    let socket = TokioUdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let addr = socket.local_addr().unwrap();
+    let mut server = ServerFuture::new(catalog(EXTERNAL));
+    server.register_socket(socket);
+    assert_eq!(answer(&lookup(addr, None).await), Some(EXTERNAL));
+
+    // the listener registered before the first view goes straight to the catalog
+    server.add_view(
+        "internal",
+        vec![IpNet::from_str("127.0.0.0/8").unwrap()],
+        catalog(INTERNAL),
+    );
+    assert_eq!(answer(&lookup(addr, None).await), Some(EXTERNAL));
+}
+
+#[tokio::test]
+async fn test_views_refused() {
+    let (mut server, addr) = server("10.0.0.0/8").await;
+    server.set_refuse_unmatched(true);
+
+    let response = lookup(addr, None).await;
+    assert_eq!(response.response_code(), ResponseCode::Refused);
+    assert!(response.answers().is_empty());
+
+    server.add_view(
+        "local",
+        vec![IpNet::from_str("127.0.0.0/8").unwrap()],
+        catalog(INTERNAL),
+    );
+    let response = lookup(addr, None).await;
+    assert_eq!(response.response_code(), ResponseCode::NoError);
+    assert_eq!(answer(&response), Some(INTERNAL));
+}
+
+#[tokio::test]
+async fn test_views_client_subnet() {
+    let (mut server, addr) = server("10.0.0.0/8").await;
+    let subnet = ClientSubnet::new(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 0)), 24, 0);
+
+    // not trusted
+    assert_eq!(answer(&lookup(addr, Some(subnet)).await), Some(EXTERNAL));
+
+    server.set_client_subnet_sources(vec![IpNet::from_str("127.0.0.0/8").unwrap()]);
+    assert_eq!(answer(&lookup(addr, Some(subnet)).await), Some(INTERNAL));
+
+    // a source prefix of 0 opts out of the subnet
+    let opt_out = ClientSubnet::new(IpAddr::V4(Ipv4Addr::new(10, 1, 2, 0)), 0, 0);
+    assert_eq!(answer(&lookup(addr, Some(opt_out)).await), Some(EXTERNAL));
+}